@@ -0,0 +1,231 @@
+use crate::history::{get_metadata, set_metadata, NewHistoryEntry, CALC_RESULT};
+use crate::item_access::require_revealed_item;
+use crate::paste::paste_plain;
+use crate::vault;
+use serde_json::Value;
+use std::iter::Peekable;
+use std::str::Chars;
+use tauri::{command, AppHandle, Runtime};
+
+/// Longer texts are not taken for an expression.
+const MAX_EXPRESSION_LENGTH: usize = 256;
+
+/// Nesting deeper than this is rejected before it can exhaust the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Results are rounded to this many decimals, `0.1 + 0.2` gives `0.3`.
+const DECIMALS: usize = 12;
+
+/// Recursive descent over `+ - * / % ^`, parentheses and unary signs.
+/// Precedence from low to high: sums, products, signs, powers.
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    depth: usize,
+    operators: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().copied()
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+
+        while let Some(ch @ ('+' | '-' | '−')) = self.peek() {
+            self.chars.next();
+            self.operators += 1;
+
+            let rhs = self.product()?;
+            value = if ch == '+' { value + rhs } else { value - rhs };
+        }
+
+        Some(value)
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+
+        while let Some(ch @ ('*' | '×' | '/' | '÷' | '%')) = self.peek() {
+            self.chars.next();
+            self.operators += 1;
+
+            let rhs = self.unary()?;
+            value = match ch {
+                '%' if rhs == 0.0 => return None,
+                '%' => value % rhs,
+                '/' | '÷' if rhs == 0.0 => return None,
+                '/' | '÷' => value / rhs,
+                _ => value * rhs,
+            };
+        }
+
+        Some(value)
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        match self.peek()? {
+            '-' | '−' => {
+                self.chars.next();
+                self.nested(|parser| parser.unary().map(|value| -value))
+            }
+            '+' => {
+                self.chars.next();
+                self.nested(Self::unary)
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Option<f64> {
+        let base = self.atom()?;
+
+        if self.peek() == Some('^') {
+            self.chars.next();
+            self.operators += 1;
+
+            // Right associative, 2^3^2 is 2^9
+            let exponent = self.nested(Self::unary)?;
+
+            return Some(base.powf(exponent));
+        }
+
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<f64> {
+        match self.peek()? {
+            '(' => {
+                self.chars.next();
+                let value = self.nested(Self::sum)?;
+
+                if self.peek()? != ')' {
+                    return None;
+                }
+                self.chars.next();
+
+                Some(value)
+            }
+            ch if ch.is_ascii_digit() || ch == '.' => self.number(),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        let mut number = String::new();
+
+        while let Some(ch) = self.chars.next_if(|ch| ch.is_ascii_digit() || *ch == '.') {
+            number.push(ch);
+        }
+
+        number.parse().ok()
+    }
+
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Option<f64>) -> Option<f64> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+}
+
+/// Dates and phone numbers such as `2024-01-31` or `1/2/2024` parse as
+/// arithmetic, a repeated `-` or `/` without spaces or other operators is
+/// taken for one of them.
+fn looks_like_identifier(text: &str) -> bool {
+    let formula = text
+        .chars()
+        .any(|ch| ch.is_whitespace() || "()+*×÷%^".contains(ch));
+
+    if formula {
+        return false;
+    }
+
+    text.matches('-').count() >= 2 || text.matches('/').count() >= 2
+}
+
+/// Evaluate an arithmetic expression such as `(12.5 + 3) * 4`. Plain numbers
+/// and anything else than numbers and operators give `None`.
+pub fn evaluate(text: &str) -> Option<f64> {
+    let text = text.trim().trim_end_matches('=').trim_end();
+
+    if text.is_empty() || text.len() > MAX_EXPRESSION_LENGTH || looks_like_identifier(text) {
+        return None;
+    }
+
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        depth: 0,
+        operators: 0,
+    };
+
+    let value = parser.sum()?;
+
+    if parser.peek().is_some() || parser.operators == 0 || !value.is_finite() {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// A result as it is pasted, without a trailing `.0` or rounding noise.
+pub fn format_result(value: f64) -> String {
+    if value.abs() >= 1e15 {
+        return format!("{:e}", value);
+    }
+
+    let formatted = format!("{:.*}", DECIMALS, value);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+
+    match formatted {
+        "-0" => "0".to_string(),
+        formatted => formatted.to_string(),
+    }
+}
+
+/// Save the result of a copied expression with the item. Items are not
+/// evaluated in vault mode, the metadata is stored in plain text.
+pub fn record_result(entry: &NewHistoryEntry) {
+    if vault::is_enabled() {
+        return;
+    }
+
+    let field = |name: &str| entry.row.get(name).and_then(Value::as_str);
+
+    let (Some("text"), Some(id), Some(text)) = (field("type"), field("id"), field("value")) else {
+        return;
+    };
+
+    if let Some(value) = evaluate(text) {
+        if let Err(error) = set_metadata(id, CALC_RESULT, &format_result(value)) {
+            log::warn!("Failed to save the expression result: {}", error);
+        }
+    }
+}
+
+// 粘贴算式条目的计算结果
+#[command]
+pub async fn paste_result<R: Runtime>(app_handle: AppHandle<R>, id: String) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+
+    // Items copied before results were recorded are evaluated now
+    let result = match get_metadata(&id)?.remove(CALC_RESULT) {
+        Some(result) => result,
+        None if item.kind == "text" => evaluate(&item.value)
+            .map(format_result)
+            .ok_or("The item is not an arithmetic expression")?,
+        None => return Err("The item is not an arithmetic expression".to_string()),
+    };
+
+    paste_plain(app_handle, result).await
+}
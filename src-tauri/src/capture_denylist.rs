@@ -0,0 +1,131 @@
+use crate::clipboard_files::read_file_paths;
+use crate::clipboard_text::peek_text;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::command;
+
+/// Characters of a copied text the patterns are matched against, the rest
+/// of a huge copy is not read.
+const MAX_SCAN_CHARS: usize = 4 * 1024 * 1024;
+
+struct DenyPattern {
+    source: String,
+    regex: Regex,
+    /// Captures this pattern blocked since it was added.
+    blocked: u64,
+}
+
+static PATTERNS: Mutex<Vec<DenyPattern>> = Mutex::new(Vec::new());
+/// Blocked captures since launch, including by patterns removed since.
+static BLOCKED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternAudit {
+    pub pattern: String,
+    pub blocked: u64,
+}
+
+/// How many captures the denylist blocked, the content itself is never
+/// logged.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DenylistAudit {
+    pub blocked: u64,
+    pub patterns: Vec<PatternAudit>,
+}
+
+/// Text of the current copy to match, plain text or the copied paths.
+fn copied_text() -> Option<String> {
+    match peek_text(MAX_SCAN_CHARS) {
+        Ok(Some(text)) => return Some(text),
+        Ok(None) => {}
+        Err(error) => log::warn!("Failed to read the copy for the denylist: {}", error),
+    }
+
+    let paths = read_file_paths().ok()?;
+
+    (!paths.is_empty()).then(|| paths.join("\n"))
+}
+
+/// Whether the current copy matches a denylist pattern, checked in the
+/// watcher before the webview reads and stores anything.
+pub fn is_denied() -> bool {
+    if PATTERNS.lock().unwrap().is_empty() {
+        return false;
+    }
+
+    let Some(text) = copied_text() else {
+        return false;
+    };
+
+    let mut patterns = PATTERNS.lock().unwrap();
+
+    let Some(pattern) = patterns
+        .iter_mut()
+        .find(|pattern| pattern.regex.is_match(&text))
+    else {
+        return false;
+    };
+
+    pattern.blocked += 1;
+    BLOCKED.fetch_add(1, Ordering::Relaxed);
+    log::info!("Capture blocked by denylist pattern {:?}", pattern.source);
+
+    true
+}
+
+// 设置不记录的内容规则（正则表达式，不区分大小写），匹配的内容不会被保存
+#[command]
+pub async fn set_capture_denylist(patterns: Vec<String>) -> Result<(), String> {
+    let mut current = PATTERNS.lock().unwrap();
+    let mut compiled = Vec::new();
+
+    for source in patterns.iter().map(|source| source.trim()) {
+        if source.is_empty() {
+            continue;
+        }
+
+        let regex = RegexBuilder::new(source)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid pattern {:?}: {}", source, e))?;
+
+        // Patterns that stay keep their count
+        let blocked = current
+            .iter()
+            .find(|pattern| pattern.source == source)
+            .map_or(0, |pattern| pattern.blocked);
+
+        compiled.push(DenyPattern {
+            source: source.to_string(),
+            regex,
+            blocked,
+        });
+    }
+
+    *current = compiled;
+
+    Ok(())
+}
+
+// 获取被内容规则拦截的次数
+#[command]
+pub async fn get_capture_denylist_audit() -> DenylistAudit {
+    let patterns = PATTERNS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|pattern| PatternAudit {
+            pattern: pattern.source.clone(),
+            blocked: pattern.blocked,
+        })
+        .collect();
+
+    DenylistAudit {
+        blocked: BLOCKED.load(Ordering::Relaxed),
+        patterns,
+    }
+}
@@ -0,0 +1,190 @@
+use crate::context::foreground_context;
+use crate::history::{get_metadata, require_item, HistoryItem, SOURCE_URL};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Manager, Runtime};
+
+/// What a matching rule does with the captured item.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RuleAction {
+    /// Append the item to a note, `path` may contain `{date}`, `{year}`,
+    /// `{month}` and `{day}` and start with `~`.
+    #[serde(rename_all = "camelCase")]
+    AppendToFile { path: String },
+}
+
+/// Rule applied to newly captured items, empty conditions match anything.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureRule {
+    /// Process name of the app copied from, case insensitive.
+    pub app: Option<String>,
+    /// Item type: text, html, rtf, image or files.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// Text the item has to contain, case insensitive.
+    pub contains: Option<String>,
+    pub action: RuleAction,
+}
+
+static RULES: Mutex<Vec<CaptureRule>> = Mutex::new(Vec::new());
+
+/// Serializes appends so two captures never interleave in one note.
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+impl CaptureRule {
+    fn matches(&self, item: &HistoryItem, app_name: Option<&str>) -> bool {
+        let app_matches = match (&self.app, app_name) {
+            (Some(app), Some(name)) => {
+                let name = name.trim_end_matches(".exe");
+                app.trim_end_matches(".exe").eq_ignore_ascii_case(name)
+            }
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+
+        let kind_matches = self.kind.as_ref().is_none_or(|kind| *kind == item.kind);
+
+        let text_matches = self.contains.as_ref().is_none_or(|needle| {
+            item.plain_text()
+                .to_lowercase()
+                .contains(&needle.to_lowercase())
+        });
+
+        app_matches && kind_matches && text_matches
+    }
+}
+
+/// Expand the date placeholders of a path template for a `YYYY-MM-DD` date.
+fn expand_path<R: Runtime>(app_handle: &AppHandle<R>, template: &str, date: &str) -> PathBuf {
+    let path = template
+        .replace("{date}", date)
+        .replace("{year}", date.get(..4).unwrap_or_default())
+        .replace("{month}", date.get(5..7).unwrap_or_default())
+        .replace("{day}", date.get(8..10).unwrap_or_default());
+
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        Some(rest) => match app_handle.path().home_dir() {
+            Ok(home) => home.join(rest),
+            Err(_) => PathBuf::from(path),
+        },
+        None => PathBuf::from(path),
+    }
+}
+
+/// A Markdown list entry for an item, continuation lines are indented so
+/// multi-line text stays inside the list item.
+fn journal_entry(item: &HistoryItem) -> String {
+    let time = item.create_time.get(11..16).unwrap_or_default();
+
+    let body = match item.kind.as_str() {
+        "image" => format!("![](<{}>)", item.value),
+        "files" => {
+            let files: Vec<String> = serde_json::from_str(&item.value).unwrap_or_default();
+
+            files
+                .iter()
+                .map(|file| format!("\n  - {}", file))
+                .collect()
+        }
+        _ => item.plain_text().trim().replace('\n', "\n  "),
+    };
+
+    let mut entry = format!("- {} {}", time, body);
+
+    if let Some(url) = get_metadata(&item.id).ok().and_then(|metadata| {
+        metadata.get(SOURCE_URL).cloned()
+    }) {
+        entry.push_str(&format!("\n  <{}>", url));
+    }
+
+    entry.push('\n');
+    entry
+}
+
+/// Append to a note by writing the whole new content next to it and
+/// renaming it over the old one, so a crash never leaves half a note.
+fn append_atomically(path: &Path, entry: &str) -> Result<(), String> {
+    let _guard = APPEND_LOCK.lock().unwrap();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let mut content = match std::fs::read(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(error) => return Err(error.to_string()),
+    };
+
+    if !content.is_empty() && !content.ends_with(b"\n") {
+        content.push(b'\n');
+    }
+    content.extend_from_slice(entry.as_bytes());
+
+    let file_name = path
+        .file_name()
+        .ok_or("Journal path has no file name")?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    let mut file = std::fs::File::create(&tmp_path).map_err(|e| e.to_string())?;
+    file.write_all(&content)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| e.to_string())?;
+    drop(file);
+
+    std::fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        e.to_string()
+    })
+}
+
+fn run_action<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    action: &RuleAction,
+    item: &HistoryItem,
+) -> Result<(), String> {
+    match action {
+        RuleAction::AppendToFile { path } => {
+            let path = expand_path(app_handle, path, item.create_date());
+
+            append_atomically(&path, &journal_entry(item))
+        }
+    }
+}
+
+// 设置采集规则
+#[command]
+pub async fn set_capture_rules(rules: Vec<CaptureRule>) {
+    *RULES.lock().unwrap() = rules;
+}
+
+// 对新采集的条目执行匹配的规则，返回执行的规则数量
+#[command]
+pub async fn apply_capture_rules<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<usize, String> {
+    let rules = RULES.lock().unwrap().clone();
+    if rules.is_empty() {
+        return Ok(0);
+    }
+
+    let item = require_item(&id)?;
+    let app_name = foreground_context().app_name;
+
+    let mut applied = 0;
+    for rule in rules
+        .iter()
+        .filter(|rule| rule.matches(&item, app_name.as_deref()))
+    {
+        run_action(&app_handle, &rule.action, &item)?;
+        applied += 1;
+    }
+
+    Ok(applied)
+}
@@ -0,0 +1,471 @@
+use serde::Serialize;
+use std::path::Path;
+use tauri::command;
+
+/// What a copied path points to.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FileKind {
+    File,
+    Directory,
+    /// The path no longer exists, e.g. it was copied and then deleted.
+    Missing,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardFile {
+    pub path: String,
+    /// Size in bytes, directories count all the files inside them.
+    pub size: u64,
+    pub kind: FileKind,
+    /// Lowercased extension without the dot.
+    pub extension: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadFilesResult {
+    pub files: Vec<ClipboardFile>,
+    /// Total size of all the files.
+    pub size: u64,
+}
+
+/// Offset of `fWide` in a DROPFILES header.
+const DROPFILES_WIDE_OFFSET: usize = 16;
+/// Size of a DROPFILES header.
+const DROPFILES_SIZE: usize = 20;
+
+/// Parse a CF_HDROP payload, a DROPFILES header followed by a double null
+/// terminated list of paths. The payload comes from another process, so
+/// offsets are checked instead of trusted.
+pub fn parse_drop_files(bytes: &[u8]) -> Vec<String> {
+    if bytes.len() < DROPFILES_SIZE {
+        return Vec::new();
+    }
+
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    };
+
+    let offset = read_u32(0) as usize;
+    let wide = read_u32(DROPFILES_WIDE_OFFSET) != 0;
+    let Some(list) = bytes.get(offset..) else {
+        return Vec::new();
+    };
+
+    if wide {
+        let units: Vec<u16> = list
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        units
+            .split(|&unit| unit == 0)
+            .take_while(|path| !path.is_empty())
+            .map(String::from_utf16_lossy)
+            .collect()
+    } else {
+        list.split(|&byte| byte == 0)
+            .take_while(|path| !path.is_empty())
+            .map(|path| String::from_utf8_lossy(path).into_owned())
+            .collect()
+    }
+}
+
+/// Decode `%XX` escapes of a URI path.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escaped = match bytes[index] {
+            b'%' => bytes
+                .get(index + 1..index + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse a `text/uri-list` payload, keeping only local `file://` URIs.
+/// GNOME's `x-special/gnome-copied-files` uses the same format after a
+/// leading `copy` or `cut` line, which is skipped as it is not a URI.
+pub fn parse_uri_list(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("file://"))
+        // Drop the host part, local files use an empty or `localhost` host
+        .filter_map(|rest| rest.find('/').map(|index| &rest[index..]))
+        .map(percent_decode)
+        .collect()
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => {
+                entry.metadata().map(|metadata| metadata.len()).unwrap_or(0)
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+fn describe(path: String) -> ClipboardFile {
+    let path_ref = Path::new(&path);
+    let extension = path_ref
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    let (kind, size) = match std::fs::metadata(path_ref) {
+        Ok(metadata) if metadata.is_dir() => (FileKind::Directory, dir_size(path_ref)),
+        Ok(metadata) => (FileKind::File, metadata.len()),
+        Err(_) => (FileKind::Missing, 0),
+    };
+
+    ClipboardFile {
+        path,
+        size,
+        kind,
+        extension,
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::{parse_drop_files, DROPFILES_SIZE};
+    use crate::clipboard_text::set_clipboard_bytes;
+
+    const CF_HDROP: u32 = 15;
+    const DROPEFFECT_COPY: u32 = 1;
+
+    extern "system" {
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn EmptyClipboard() -> i32;
+        fn RegisterClipboardFormatW(lpszFormat: *const u16) -> u32;
+        fn GetClipboardData(uFormat: u32) -> *mut std::ffi::c_void;
+        fn IsClipboardFormatAvailable(format: u32) -> i32;
+        fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
+        fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+    }
+
+    /// Build a wide DROPFILES payload, the inverse of `parse_drop_files`.
+    fn drop_files_bytes(paths: &[String]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(DROPFILES_SIZE);
+        bytes.extend_from_slice(&(DROPFILES_SIZE as u32).to_le_bytes());
+        // pt and fNC, only meaningful for drag and drop
+        bytes.extend_from_slice(&[0; 12]);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+
+        for path in paths {
+            for unit in path.encode_utf16().chain(std::iter::once(0)) {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&[0, 0]);
+
+        bytes
+    }
+
+    /// Explorer reads this to tell a copy from a cut.
+    fn register_drop_effect_format() -> u32 {
+        let name: Vec<u16> = "Preferred DropEffect\0".encode_utf16().collect();
+        unsafe { RegisterClipboardFormatW(name.as_ptr()) }
+    }
+
+    pub fn write_paths(paths: &[String]) -> Result<(), String> {
+        write_paths_with(paths, &[])
+    }
+
+    /// Write the file list together with extra formats, e.g. a request.
+    pub fn write_paths_with(paths: &[String], extra: &[(u32, Vec<u8>)]) -> Result<(), String> {
+        let drop_files = drop_files_bytes(paths);
+
+        unsafe {
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            EmptyClipboard();
+
+            let result = set_clipboard_bytes(CF_HDROP, &drop_files)
+                .and_then(|_| {
+                    set_clipboard_bytes(
+                        register_drop_effect_format(),
+                        &DROPEFFECT_COPY.to_le_bytes(),
+                    )
+                })
+                .and_then(|_| {
+                    extra
+                        .iter()
+                        .try_for_each(|(format, bytes)| set_clipboard_bytes(*format, bytes))
+                });
+
+            CloseClipboard();
+            result
+        }
+    }
+
+    pub fn read_paths() -> Result<Vec<String>, String> {
+        unsafe {
+            if IsClipboardFormatAvailable(CF_HDROP) == 0 {
+                return Ok(Vec::new());
+            }
+
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            let mut paths = Vec::new();
+            let handle = GetClipboardData(CF_HDROP);
+            if !handle.is_null() {
+                let data = GlobalLock(handle);
+                if !data.is_null() {
+                    let bytes = std::slice::from_raw_parts(data as *const u8, GlobalSize(handle));
+                    paths = parse_drop_files(bytes);
+                    GlobalUnlock(handle);
+                }
+            }
+
+            CloseClipboard();
+            Ok(paths)
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSAutoreleasePool, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    /// Finder still publishes copied files under the legacy property list
+    /// type next to the file URLs, one string path per file.
+    const FILENAMES_TYPE: &str = "NSFilenamesPboardType";
+
+    unsafe fn to_string(value: id) -> String {
+        let bytes = value.UTF8String();
+        if bytes.is_null() {
+            return String::new();
+        }
+
+        std::ffi::CStr::from_ptr(bytes).to_string_lossy().into_owned()
+    }
+
+    /// Write the files as file URLs, which Finder pastes as copies.
+    pub fn write_paths(paths: &[String]) -> Result<(), String> {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let urls: Vec<id> = paths
+                .iter()
+                .map(|path| {
+                    let path = NSString::alloc(nil).init_str(path).autorelease();
+                    msg_send![class!(NSURL), fileURLWithPath: path]
+                })
+                .collect();
+
+            let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+            let _: i64 = msg_send![pasteboard, clearContents];
+            let written: bool =
+                msg_send![pasteboard, writeObjects: NSArray::arrayWithObjects(nil, &urls)];
+
+            pool.drain();
+
+            if written {
+                Ok(())
+            } else {
+                Err("Failed to write files to the pasteboard".to_string())
+            }
+        }
+    }
+
+    pub fn read_paths() -> Result<Vec<String>, String> {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+            let pasteboard_type = NSString::alloc(nil).init_str(FILENAMES_TYPE).autorelease();
+            let list: id = msg_send![pasteboard, propertyListForType: pasteboard_type];
+
+            let mut paths = Vec::new();
+            if list != nil {
+                for index in 0..list.count() {
+                    let path = to_string(list.objectAtIndex(index));
+                    if !path.is_empty() {
+                        paths.push(path);
+                    }
+                }
+            }
+
+            pool.drain();
+            Ok(paths)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::parse_uri_list;
+    use crate::clipboard_image::{available_targets, read_target};
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    /// File list targets in order of preference.
+    const FILE_TARGETS: &[&str] = &["text/uri-list", "x-special/gnome-copied-files"];
+
+    /// Percent-encode a path for a `file://` URI.
+    fn file_uri(path: &str) -> String {
+        let mut uri = String::from("file://");
+
+        for byte in path.bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                    uri.push(byte as char)
+                }
+                _ => uri.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+
+        uri
+    }
+
+    /// Owning a selection means answering every paste request, which
+    /// wl-copy and xclip do from a background process after we exit.
+    pub fn write_paths(paths: &[String]) -> Result<(), String> {
+        let uri_list: String = paths
+            .iter()
+            .map(|path| format!("{}\r\n", file_uri(path)))
+            .collect();
+
+        let mut command = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            let mut command = Command::new("wl-copy");
+            command.args(["--type", "text/uri-list"]);
+            command
+        } else {
+            let mut command = Command::new("xclip");
+            command.args(["-selection", "clipboard", "-target", "text/uri-list"]);
+            command
+        };
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        child
+            .stdin
+            .take()
+            .ok_or("Failed to open clipboard helper input")?
+            .write_all(uri_list.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err("Failed to write files to the clipboard".to_string())
+        }
+    }
+
+    pub fn read_paths() -> Result<Vec<String>, String> {
+        let targets = available_targets();
+
+        let paths = FILE_TARGETS
+            .iter()
+            .filter(|target| targets.iter().any(|available| available == *target))
+            .find_map(|target| read_target(target))
+            .map(|bytes| parse_uri_list(&bytes))
+            .unwrap_or_default();
+
+        Ok(paths)
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::{read_paths, write_paths};
+
+#[cfg(target_os = "windows")]
+pub use win::write_paths_with;
+
+#[cfg(target_os = "macos")]
+use mac::{read_paths, write_paths};
+
+#[cfg(target_os = "linux")]
+use linux::{read_paths, write_paths};
+
+/// The copied paths as they are, without looking at the files.
+pub fn read_file_paths() -> Result<Vec<String>, String> {
+    read_paths()
+}
+
+/// Read the copied file list, `None` when the clipboard holds no files.
+pub fn read_files() -> Result<Option<ReadFilesResult>, String> {
+    Ok(describe_files(read_paths()?))
+}
+
+/// Describe a file list the way a copied one is read, `None` when empty.
+pub fn describe_files(paths: Vec<String>) -> Option<ReadFilesResult> {
+    if paths.is_empty() {
+        return None;
+    }
+
+    let files: Vec<ClipboardFile> = paths.into_iter().map(describe).collect();
+    let size = files.iter().map(|file| file.size).sum();
+
+    Some(ReadFilesResult { files, size })
+}
+
+// 读取剪贴板中复制的文件列表
+#[command]
+pub async fn read_clipboard_files() -> Result<Option<ReadFilesResult>, String> {
+    read_files()
+}
+
+// 将文件列表写入剪贴板，可以直接粘贴到资源管理器或访达
+#[command]
+pub async fn write_clipboard_files(paths: Vec<String>) -> Result<(), String> {
+    // Files copied long ago may have been moved or deleted since
+    let paths: Vec<String> = paths
+        .into_iter()
+        .filter(|path| Path::new(path).exists())
+        .collect();
+
+    if paths.is_empty() {
+        return Err("None of the files exist anymore".to_string());
+    }
+
+    write_paths(&paths)
+}
@@ -0,0 +1,351 @@
+use serde::Serialize;
+use tauri::command;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadHtmlResult {
+    /// Sanitized HTML of the copied fragment.
+    pub html: String,
+    /// Plain text of the fragment.
+    pub text: String,
+    /// Page the fragment was copied from, if the source reported it.
+    pub source_url: Option<String>,
+}
+
+/// The fragment of a CF_HTML document and the header fields around it.
+pub struct CfHtml {
+    pub fragment: String,
+    pub source_url: Option<String>,
+}
+
+const START_FRAGMENT_MARKER: &[u8] = b"<!--StartFragment-->";
+const END_FRAGMENT_MARKER: &[u8] = b"<!--EndFragment-->";
+
+/// Elements dropped together with their content when extracting text.
+const INVISIBLE_ELEMENTS: &[&str] = &["head", "script", "style", "template", "title"];
+
+/// Elements that start a new line in the extracted text.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "figcaption",
+    "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav",
+    "ol", "p", "pre", "section", "table", "tr", "ul",
+];
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Parse a CF_HTML document. The header offsets are byte offsets into the
+/// payload, but some apps write them wrong or as -1, so every offset is
+/// checked and the fragment comments are used as a fallback.
+pub fn parse_cf_html(bytes: &[u8]) -> Option<CfHtml> {
+    let len = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+    let bytes = &bytes[..len];
+
+    // The header is plain ASCII and ends where the markup starts
+    let header_len = bytes.iter().position(|&byte| byte == b'<')?;
+    let header = String::from_utf8_lossy(&bytes[..header_len]);
+
+    let offset = |key: &str| {
+        header
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .filter(|&value| value >= 0)
+            .map(|value| value as usize)
+    };
+    let slice = |start: Option<usize>, end: Option<usize>| match (start, end) {
+        (Some(start), Some(end)) if header_len <= start && start <= end => bytes.get(start..end),
+        _ => None,
+    };
+
+    let fragment = slice(offset("StartFragment"), offset("EndFragment"))
+        .or_else(|| {
+            let start = find(bytes, START_FRAGMENT_MARKER)? + START_FRAGMENT_MARKER.len();
+            let end = start + find(&bytes[start..], END_FRAGMENT_MARKER)?;
+
+            Some(&bytes[start..end])
+        })
+        .or_else(|| slice(offset("StartHTML"), offset("EndHTML")))
+        .unwrap_or(&bytes[header_len..]);
+
+    let source_url = header
+        .lines()
+        .find_map(|line| line.strip_prefix("SourceURL:"))
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+
+    Some(CfHtml {
+        fragment: String::from_utf8_lossy(fragment).into_owned(),
+        source_url,
+    })
+}
+
+/// Strip scripts, event handlers and `javascript:` links from HTML that
+/// another app put on the clipboard, keeping the inline formatting.
+pub fn sanitize_html(html: &str) -> String {
+    ammonia::Builder::default()
+        .add_generic_attributes(["style", "class"])
+        .clean(html)
+        .to_string()
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Plain text of an HTML fragment: tags are dropped, block elements become
+/// line breaks and whitespace is collapsed the way a browser renders it.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut hidden_until: Option<String> = None;
+    let mut pending_space = false;
+    let mut rest = html;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch == '<' {
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            let closing = tag.starts_with('/');
+            let name = tag
+                .trim_start_matches('/')
+                .split(|ch: char| ch.is_whitespace() || ch == '/')
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+
+            if let Some(hidden) = &hidden_until {
+                if closing && *hidden == name {
+                    hidden_until = None;
+                }
+                continue;
+            }
+
+            if !closing && INVISIBLE_ELEMENTS.contains(&name.as_str()) {
+                hidden_until = Some(name);
+            } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                pending_space = false;
+            } else if name == "td" || name == "th" {
+                if closing {
+                    text.push('\t');
+                }
+                pending_space = false;
+            }
+
+            continue;
+        }
+
+        rest = &rest[ch.len_utf8()..];
+
+        if hidden_until.is_some() {
+            continue;
+        }
+
+        if ch.is_whitespace() {
+            pending_space = !text.is_empty() && !text.ends_with(['\n', '\t']);
+            continue;
+        }
+
+        if pending_space {
+            text.push(' ');
+            pending_space = false;
+        }
+
+        let mut decoded = ch;
+        if ch == '&' {
+            // Entity names are short, a far away `;` belongs to the text
+            if let Some(end) = rest.find(';').filter(|&end| end <= 10) {
+                if let Some(entity) = decode_entity(&rest[..end]) {
+                    decoded = entity;
+                    rest = &rest[end + 1..];
+                }
+            }
+        }
+
+        text.push(decoded);
+    }
+
+    text.trim().to_string()
+}
+
+/// Build a CF_HTML document around a fragment. All offsets are byte offsets
+/// into the UTF-8 payload and padded to 10 digits so the header length is
+/// known before the offsets are filled in.
+#[cfg(target_os = "windows")]
+pub fn build_cf_html(fragment: &str) -> String {
+    const HEADER: &str = "Version:0.9\r\nStartHTML:{start_html}\r\nEndHTML:{end_html}\r\nStartFragment:{start_fragment}\r\nEndFragment:{end_fragment}\r\n";
+    const PREFIX: &str = "<html><body>\r\n<!--StartFragment-->";
+    const SUFFIX: &str = "<!--EndFragment-->\r\n</body></html>";
+
+    // Each placeholder is replaced by exactly 10 digits
+    let header_len = HEADER.len() - "{start_html}{end_html}{start_fragment}{end_fragment}".len() + 40;
+
+    let start_html = header_len;
+    let start_fragment = start_html + PREFIX.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + SUFFIX.len();
+
+    let header = HEADER
+        .replace("{start_html}", &format!("{:010}", start_html))
+        .replace("{end_html}", &format!("{:010}", end_html))
+        .replace("{start_fragment}", &format!("{:010}", start_fragment))
+        .replace("{end_fragment}", &format!("{:010}", end_fragment));
+
+    format!("{}{}{}{}", header, PREFIX, fragment, SUFFIX)
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::{build_cf_html, parse_cf_html, CfHtml};
+    use crate::clipboard_text::{set_clipboard_bytes, set_unicode_text};
+
+    extern "system" {
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn EmptyClipboard() -> i32;
+        fn GetClipboardData(uFormat: u32) -> *mut std::ffi::c_void;
+        fn IsClipboardFormatAvailable(format: u32) -> i32;
+        fn RegisterClipboardFormatW(lpszFormat: *const u16) -> u32;
+        fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
+        fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+    }
+
+    pub fn register_html_format() -> u32 {
+        let name: Vec<u16> = "HTML Format\0".encode_utf16().collect();
+        unsafe { RegisterClipboardFormatW(name.as_ptr()) }
+    }
+
+    pub fn read_html() -> Result<Option<CfHtml>, String> {
+        let format = register_html_format();
+
+        unsafe {
+            if IsClipboardFormatAvailable(format) == 0 {
+                return Ok(None);
+            }
+
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            let mut html = None;
+            let handle = GetClipboardData(format);
+            if !handle.is_null() {
+                let data = GlobalLock(handle);
+                if !data.is_null() {
+                    let bytes = std::slice::from_raw_parts(data as *const u8, GlobalSize(handle));
+                    html = parse_cf_html(bytes);
+                    GlobalUnlock(handle);
+                }
+            }
+
+            CloseClipboard();
+            Ok(html)
+        }
+    }
+
+    /// Write an HTML fragment with a plain text alternative.
+    pub fn write_html(html: &str, alt_text: &str) -> Result<(), String> {
+        let document = build_cf_html(html);
+
+        unsafe {
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            EmptyClipboard();
+
+            let result = set_clipboard_bytes(register_html_format(), document.as_bytes())
+                .and_then(|_| set_unicode_text(alt_text));
+
+            CloseClipboard();
+            result
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod other {
+    use super::CfHtml;
+    use arboard::Clipboard;
+
+    /// Other platforms carry the bare HTML without a header.
+    pub fn read_html() -> Result<Option<CfHtml>, String> {
+        let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+        match clipboard.get().html() {
+            Ok(fragment) => Ok(Some(CfHtml {
+                fragment,
+                source_url: None,
+            })),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Write an HTML fragment with a plain text alternative.
+    pub fn write_html(html: &str, alt_text: &str) -> Result<(), String> {
+        let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+        clipboard
+            .set_html(html, Some(alt_text))
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub use win::{read_html, write_html};
+
+#[cfg(not(target_os = "windows"))]
+pub use other::{read_html, write_html};
+
+// 读取剪贴板中的 html，返回清理后的 html 和纯文本
+#[command]
+pub async fn read_clipboard_html() -> Result<Option<ReadHtmlResult>, String> {
+    let Some(CfHtml {
+        fragment,
+        source_url,
+    }) = read_html()?
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(ReadHtmlResult {
+        html: sanitize_html(&fragment),
+        text: html_to_text(&fragment),
+        source_url,
+    }))
+}
+
+// 将 html 写入剪贴板，未提供纯文本时从 html 中提取
+#[command]
+pub async fn write_clipboard_html(html: String, text: Option<String>) -> Result<(), String> {
+    let text = text.unwrap_or_else(|| html_to_text(&html));
+
+    write_html(&html, &text)
+}
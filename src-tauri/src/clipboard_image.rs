@@ -1,18 +1,664 @@
+use crate::clipboard_files::{read_files, FileKind};
+use crate::clipboard_watcher::mark_own_write;
+use crate::cold_storage::warm_image;
+use crate::image_decode::{check_dimensions, decode_limited, run_isolated};
+use crate::image_storage::{encode_png_for_storage, storage_policy};
+use crate::vault;
+use image::{ImageFormat, RgbaImage};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Longest edge of the thumbnails shown in the history list.
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// Directory shared with tauri-plugin-clipboard-x where history images live.
+pub fn get_images_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("tauri-plugin-clipboard-x").join("images")
+}
+
+/// Downscaled WebP copies of history images, named after the full image.
+pub fn get_thumbnails_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    get_images_dir(app_handle).join("thumbnails")
+}
+
+/// Resolve a stored image value, which may be a bare file name.
+pub fn resolve_image_path<R: Runtime>(app_handle: &AppHandle<R>, value: &str) -> PathBuf {
+    let path = PathBuf::from(value);
+
+    if path.is_absolute() {
+        path
+    } else {
+        get_images_dir(app_handle).join(path)
+    }
+}
+
+/// Size of a BITMAPINFOHEADER, the smallest header a DIB can start with.
+const BITMAPINFOHEADER_SIZE: usize = 40;
+
+/// Wrap a CF_DIB/CF_DIBV5 payload into a complete BMP file. Header fields
+/// come from another process and may be garbage, so every offset is checked.
+pub fn dib_to_bmp(bytes: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    if bytes.len() < BITMAPINFOHEADER_SIZE || bytes.len() >= u32::MAX as usize {
+        return Ok(None);
+    }
+
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    };
+
+    let header_size = read_u32(0);
+    let width = (read_u32(4) as i32).unsigned_abs();
+    let height = (read_u32(8) as i32).unsigned_abs();
+    let bit_count = u16::from_le_bytes([bytes[14], bytes[15]]) as u32;
+    let clr_used = read_u32(32);
+
+    check_dimensions(width, height)?;
+
+    // Calculate color table size
+    let colors_used = if clr_used > 0 {
+        clr_used
+    } else if bit_count <= 8 {
+        1u32 << bit_count
+    } else {
+        0
+    };
+    let color_table_size = colors_used.saturating_mul(4); // RGBQUAD = 4 bytes each
+
+    let pixel_data_offset = header_size.saturating_add(color_table_size);
+    if bytes.len() as u64 <= pixel_data_offset as u64 {
+        return Ok(None);
+    }
+
+    // Build a complete BMP file: file header + DIB data
+    let bmp_file_header_size: u32 = 14;
+    let file_size = bmp_file_header_size.saturating_add(bytes.len() as u32);
+    let pixel_offset = bmp_file_header_size.saturating_add(pixel_data_offset);
+
+    let mut bmp_data = Vec::with_capacity(file_size as usize);
+    // BMP file header
+    bmp_data.extend_from_slice(&[0x42, 0x4D]); // 'BM'
+    bmp_data.extend_from_slice(&file_size.to_le_bytes());
+    bmp_data.extend_from_slice(&[0, 0, 0, 0]); // reserved
+    bmp_data.extend_from_slice(&pixel_offset.to_le_bytes());
+    // DIB data (header + color table + pixels)
+    bmp_data.extend_from_slice(bytes);
+
+    Ok(Some(bmp_data))
+}
+
+/// Compression values of a DIB header.
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+/// Masks of BI_RGB 32-bit data, the top byte is alpha in practice.
+const DEFAULT_MASKS: [u32; 4] = [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000];
+
+/// Where the pixels of a 32-bit DIB are and how to read them.
+struct Dib32 {
+    width: u32,
+    height: u32,
+    top_down: bool,
+    /// Red, green, blue and alpha masks, a zero alpha mask means opaque.
+    masks: [u32; 4],
+    pixel_offset: usize,
+}
+
+fn parse_dib32(bytes: &[u8]) -> Option<Dib32> {
+    let read_u32 = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let header_size = read_u32(0)? as usize;
+    if header_size < BITMAPINFOHEADER_SIZE {
+        return None;
+    }
+
+    let bit_count = u16::from_le_bytes([*bytes.get(14)?, *bytes.get(15)?]);
+    let compression = read_u32(16)?;
+    if bit_count != 32 || (compression != BI_RGB && compression != BI_BITFIELDS) {
+        return None;
+    }
+
+    let raw_height = read_u32(8)? as i32;
+
+    let masks = if compression == BI_RGB {
+        DEFAULT_MASKS
+    } else {
+        // BITMAPV3 and later headers carry an alpha mask after the colors,
+        // a plain BITMAPINFOHEADER is followed by three color masks only
+        let alpha = if header_size >= 56 { read_u32(52)? } else { 0 };
+        [read_u32(40)?, read_u32(44)?, read_u32(48)?, alpha]
+    };
+
+    let masks_size = if compression == BI_BITFIELDS && header_size == BITMAPINFOHEADER_SIZE {
+        12
+    } else {
+        0
+    };
+    let colors_size = read_u32(32)? as usize * 4;
+
+    Some(Dib32 {
+        width: (read_u32(4)? as i32).unsigned_abs(),
+        height: raw_height.unsigned_abs(),
+        top_down: raw_height < 0,
+        masks,
+        pixel_offset: header_size
+            .checked_add(masks_size)?
+            .checked_add(colors_size)?,
+    })
+}
+
+/// Scale the bits of a channel selected by `mask` to 0-255.
+fn channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    let max = mask >> mask.trailing_zeros();
+
+    if max == 0xFF {
+        value as u8
+    } else {
+        (value as u64 * 255 / max as u64) as u8
+    }
+}
+
+/// Build an RGBA image from a 32-bit CF_DIB/CF_DIBV5 payload, keeping the
+/// alpha channel the BMP decoder ignores for BI_RGB data. Other bit depths
+/// return `None` and go through [`dib_to_bmp`].
+pub fn dib_to_rgba(bytes: &[u8]) -> Result<Option<RgbaImage>, String> {
+    let Some(dib) = parse_dib32(bytes) else {
+        return Ok(None);
+    };
+
+    if dib.width == 0 || dib.height == 0 {
+        return Ok(None);
+    }
+
+    check_dimensions(dib.width, dib.height)?;
+
+    let stride = dib.width as usize * 4;
+    let pixels_size = stride * dib.height as usize;
+    let Some(pixels) = bytes.get(dib.pixel_offset..dib.pixel_offset + pixels_size) else {
+        return Ok(None);
+    };
+
+    let [red, green, blue, alpha] = dib.masks;
+    let mut image = RgbaImage::new(dib.width, dib.height);
+
+    for (index, row) in pixels.chunks_exact(stride).enumerate() {
+        // Bottom-up unless the height was negative
+        let y = if dib.top_down {
+            index
+        } else {
+            dib.height as usize - 1 - index
+        };
+
+        for (x, pixel) in row.chunks_exact(4).enumerate() {
+            let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+
+            image.put_pixel(
+                x as u32,
+                y as u32,
+                image::Rgba([
+                    channel(value, red),
+                    channel(value, green),
+                    channel(value, blue),
+                    channel(value, alpha),
+                ]),
+            );
+        }
+    }
+
+    // Many apps leave the top byte of BI_RGB data zeroed, a fully
+    // transparent image is never what was copied
+    if alpha == 0 || image.pixels().all(|pixel| pixel[3] == 0) {
+        for pixel in image.pixels_mut() {
+            pixel[3] = 0xFF;
+        }
+    }
+
+    Ok(Some(image))
+}
+
+/// A CF_DIB/CF_DIBV5 payload as the capture path reads it.
+pub enum DibImage {
+    /// 32-bit data decoded by hand, alpha included.
+    Rgba(RgbaImage),
+    /// Other bit depths, wrapped into a BMP file for the decoder.
+    Bmp(Vec<u8>),
+}
+
+/// Read a DIB payload, `None` when it holds nothing that can be decoded.
+pub fn read_dib(bytes: &[u8]) -> Result<Option<DibImage>, String> {
+    if let Some(image) = dib_to_rgba(bytes)? {
+        return Ok(Some(DibImage::Rgba(image)));
+    }
+
+    Ok(dib_to_bmp(bytes)?.map(DibImage::Bmp))
+}
+
+fn hash_bytes(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash of the decoded pixels, the same image names the same file whether
+/// it arrived as PNG, DIB or TIFF.
+fn hash_pixels(image: &RgbaImage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image.dimensions().hash(&mut hasher);
+    image.as_raw().hash(&mut hasher);
+    hasher.finish()
+}
+
+fn get_png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 24 {
+        return None;
+    }
+    if &data[0..8] != &[137, 80, 78, 71, 13, 10, 26, 10] {
+        return None;
+    }
+    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
+    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+    Some((width, height))
+}
+
+/// Frames shorter than this are slowed down to [`GIF_DEFAULT_DELAY`] by
+/// browsers, the reported duration follows them.
+const GIF_MIN_DELAY: u32 = 2;
+const GIF_DEFAULT_DELAY: u32 = 10;
+
+struct GifInfo {
+    width: u32,
+    height: u32,
+    frames: u32,
+    /// Total playback time of one loop in milliseconds.
+    duration: u64,
+}
+
+/// Walk the blocks of a GIF to count its frames and add up their delays
+/// without decoding any pixels. The data comes from another process, so
+/// every length is checked.
+fn parse_gif(data: &[u8]) -> Option<GifInfo> {
+    if data.len() < 13 || !(data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")) {
+        return None;
+    }
+
+    let width = u16::from_le_bytes([data[6], data[7]]) as u32;
+    let height = u16::from_le_bytes([data[8], data[9]]) as u32;
+
+    // Color tables hold 2^(n+1) RGB entries
+    let color_table_size = |packed: u8| -> usize {
+        if packed & 0x80 != 0 {
+            3 << ((packed & 0x07) + 1)
+        } else {
+            0
+        }
+    };
+
+    // Data sub-blocks end with a zero length byte
+    let skip_sub_blocks = |mut offset: usize| -> Option<usize> {
+        loop {
+            let size = *data.get(offset)? as usize;
+            offset += 1 + size;
+
+            if size == 0 {
+                return Some(offset);
+            }
+        }
+    };
+
+    let mut offset = 13 + color_table_size(data[10]);
+    let mut frames = 0;
+    let mut duration = 0;
+    let mut delay = None;
+
+    loop {
+        match *data.get(offset)? {
+            // Extension, only the graphic control extension matters
+            0x21 => {
+                let label = *data.get(offset + 1)?;
+
+                if label == 0xF9 {
+                    let bytes = data.get(offset + 4..offset + 6)?;
+                    delay = Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32);
+                }
+
+                offset = skip_sub_blocks(offset + 2)?;
+            }
+            // Image descriptor followed by the LZW data of a frame
+            0x2C => {
+                let packed = *data.get(offset + 9)?;
+                offset = skip_sub_blocks(offset + 10 + color_table_size(packed) + 1)?;
+
+                let centiseconds = match delay.take() {
+                    Some(delay) if delay >= GIF_MIN_DELAY => delay,
+                    _ => GIF_DEFAULT_DELAY,
+                };
+
+                frames += 1;
+                duration += centiseconds as u64 * 10;
+            }
+            // Trailer
+            0x3B => break,
+            _ => return None,
+        }
+    }
+
+    Some(GifInfo {
+        width,
+        height,
+        frames,
+        duration,
+    })
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadImageResult {
+    pub path: String,
+    pub size: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Downscaled WebP for the history list, absent for images that are
+    /// already small enough.
+    pub thumbnail_path: Option<String>,
+    /// Frame count of an animated GIF kept as is, absent for still images.
+    pub frames: Option<u32>,
+    /// Length of one loop of the animation in milliseconds.
+    pub duration: Option<u64>,
+}
+
+/// Write a WebP thumbnail next to the saved image when it is larger than
+/// [`THUMBNAIL_SIZE`], returns its path. Animations get their first frame.
+fn save_thumbnail<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    hash: u64,
+    bytes: &[u8],
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+) -> Result<Option<PathBuf>, String> {
+    if width <= THUMBNAIL_SIZE && height <= THUMBNAIL_SIZE {
+        return Ok(None);
+    }
+
+    let dir = get_thumbnails_dir(app_handle);
+    let path = dir.join(format!("{}.webp", hash));
+
+    if path.exists() {
+        return Ok(Some(path));
+    }
+
+    let bytes = bytes.to_vec();
+    let webp_bytes = run_isolated(move || {
+        let img = decode_limited(&bytes, format)?;
+        let thumbnail = img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+
+        let mut webp_bytes = Vec::new();
+        thumbnail
+            .write_to(&mut Cursor::new(&mut webp_bytes), ImageFormat::WebP)
+            .map_err(|e| format!("Failed to encode WebP: {}", e))?;
+
+        Ok(webp_bytes)
+    })?;
+
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    vault::write_file(&path, &webp_bytes)?;
+
+    Ok(Some(path))
+}
+
+/// Save PNG data read from the clipboard as is, named after its pixels.
+fn save_png_bytes<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    png_bytes: &[u8],
+) -> Result<Option<ReadImageResult>, String> {
+    if png_bytes.is_empty() {
+        return Ok(None);
+    }
+
+    let bytes = png_bytes.to_vec();
+    let hash = run_isolated(move || {
+        decode_limited(&bytes, ImageFormat::Png).map(|img| hash_pixels(&img.to_rgba8()))
+    })
+    // Data that doesn't decode is still saved, it can only match itself
+    .unwrap_or_else(|_| hash_bytes(png_bytes));
+
+    save_encoded_png(app_handle, png_bytes, hash)
+}
+
+/// Save encoded PNG data under the hash of its pixels, an image already
+/// saved from another format is reused. The file is re-encoded when another
+/// storage format is set.
+fn save_encoded_png<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    png_bytes: &[u8],
+    hash: u64,
+) -> Result<Option<ReadImageResult>, String> {
+    let (width, height) = get_png_dimensions(png_bytes).unwrap_or((0, 0));
+    if width == 0 || height == 0 {
+        return Ok(None);
+    }
+
+    let policy = storage_policy();
+    let filename = format!("{}.{}", hash, policy.format.extension());
+    let dir = get_images_dir(app_handle);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(&filename);
+
+    // Only write if not already present (hash-based dedup on disk)
+    if !path.exists() {
+        vault::write_file(&path, &encode_png_for_storage(png_bytes, policy)?)?;
+    }
+
+    let file_size = std::fs::metadata(&path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    // The full image is already saved, a failed thumbnail only costs speed
+    let thumbnail_path =
+        save_thumbnail(app_handle, hash, png_bytes, ImageFormat::Png, width, height)
+            .unwrap_or_else(|error| {
+                log::warn!("Failed to save image thumbnail: {}", error);
+                None
+            });
+
+    Ok(Some(ReadImageResult {
+        path: path.to_string_lossy().to_string(),
+        size: file_size,
+        width,
+        height,
+        thumbnail_path: thumbnail_path.map(|path| path.to_string_lossy().to_string()),
+        frames: None,
+        duration: None,
+    }))
+}
+
+/// Save an animated GIF untouched instead of flattening it to PNG. Still
+/// GIFs return `None` and go through the usual PNG path.
+fn save_gif_bytes<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    gif_bytes: &[u8],
+) -> Result<Option<ReadImageResult>, String> {
+    let Some(info) = parse_gif(gif_bytes) else {
+        return Ok(None);
+    };
+
+    if info.frames < 2 || info.width == 0 || info.height == 0 {
+        return Ok(None);
+    }
+
+    check_dimensions(info.width, info.height)?;
+
+    let hash = hash_bytes(gif_bytes);
+    let dir = get_images_dir(app_handle);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.gif", hash));
+
+    if !path.exists() {
+        vault::write_file(&path, gif_bytes)?;
+    }
+
+    let thumbnail_path = save_thumbnail(
+        app_handle,
+        hash,
+        gif_bytes,
+        ImageFormat::Gif,
+        info.width,
+        info.height,
+    )
+    .unwrap_or_else(|error| {
+        log::warn!("Failed to save image thumbnail: {}", error);
+        None
+    });
+
+    Ok(Some(ReadImageResult {
+        path: path.to_string_lossy().to_string(),
+        size: gif_bytes.len() as u64,
+        width: info.width,
+        height: info.height,
+        thumbnail_path: thumbnail_path.map(|path| path.to_string_lossy().to_string()),
+        frames: Some(info.frames),
+        duration: Some(info.duration),
+    }))
+}
+
+/// Apps such as browsers back a copied image with a temporary file, when
+/// that file is a GIF it holds the animation the bitmap formats lost.
+fn read_gif_file<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Option<ReadImageResult>, String> {
+    let Some(result) = read_files()? else {
+        return Ok(None);
+    };
+
+    let [file] = result.files.as_slice() else {
+        return Ok(None);
+    };
+
+    if file.extension.as_deref() != Some("gif") || file.kind != FileKind::File {
+        return Ok(None);
+    }
+
+    let bytes = std::fs::read(&file.path).map_err(|e| e.to_string())?;
+
+    save_gif_bytes(app_handle, &bytes)
+}
+
+/// Convert a non-PNG clipboard image to PNG and save it.
+fn convert_to_png<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    data: Vec<u8>,
+    format: ImageFormat,
+) -> Result<Option<ReadImageResult>, String> {
+    // Decoding and encoding run isolated, a malformed bitmap from a buggy
+    // app must not take the backend down with it
+    let encoded = run_isolated(move || {
+        let img = decode_limited(&data, format)
+            .map_err(|e| format!("Failed to decode {:?}: {}", format, e))?;
+
+        if img.width() == 0 || img.height() == 0 {
+            return Ok(None);
+        }
+
+        let mut png_bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut png_bytes), ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+
+        Ok(Some((png_bytes, hash_pixels(&img.to_rgba8()))))
+    })?;
+
+    match encoded {
+        Some((png_bytes, hash)) => save_encoded_png(app_handle, &png_bytes, hash),
+        None => Ok(None),
+    }
+}
+
+/// Encode a decoded image to PNG and save it.
+fn save_rgba_image<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    image: RgbaImage,
+) -> Result<Option<ReadImageResult>, String> {
+    let hash = hash_pixels(&image);
+    let png_bytes = run_isolated(move || {
+        let mut png_bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png_bytes), ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+
+        Ok(png_bytes)
+    })?;
+
+    save_encoded_png(app_handle, &png_bytes, hash)
+}
+
+/// Decode an image to write, keeping PNG data as is and encoding anything
+/// else to PNG, so both the PNG and bitmap clipboard formats can be offered.
+fn prepare_image(bytes: Vec<u8>) -> Result<(Vec<u8>, RgbaImage), String> {
+    run_isolated(move || {
+        let format = image::guess_format(&bytes).map_err(|e| e.to_string())?;
+        let img = decode_limited(&bytes, format)?;
+
+        let png_bytes = if format == ImageFormat::Png {
+            bytes
+        } else {
+            let mut png_bytes = Vec::new();
+            img.write_to(&mut Cursor::new(&mut png_bytes), ImageFormat::Png)
+                .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+            png_bytes
+        };
+
+        Ok((png_bytes, img.to_rgba8()))
+    })
+}
+
 #[cfg(target_os = "windows")]
 mod win {
-    use serde::Serialize;
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    use std::io::Cursor;
-    use std::path::PathBuf;
-    use tauri::{AppHandle, Manager, Runtime};
+    use super::{
+        convert_to_png, dib_to_rgba, read_dib, save_gif_bytes, save_png_bytes, save_rgba_image,
+        DibImage, ReadImageResult,
+    };
+    use crate::clipboard_text::set_clipboard_bytes;
+    use crate::image_decode::check_dimensions;
+    use image::{ImageFormat, RgbaImage};
+    use tauri::{AppHandle, Runtime};
 
     const CF_DIB: u32 = 8;
     const CF_DIBV5: u32 = 17;
+    const CF_BITMAP: u32 = 2;
+
+    const BI_RGB: u32 = 0;
+    const DIB_RGB_COLORS: u32 = 0;
+
+    #[repr(C)]
+    #[allow(non_snake_case, dead_code)]
+    struct Bitmap {
+        bmType: i32,
+        bmWidth: i32,
+        bmHeight: i32,
+        bmWidthBytes: i32,
+        bmPlanes: u16,
+        bmBitsPixel: u16,
+        bmBits: *mut std::ffi::c_void,
+    }
 
     #[repr(C)]
-    #[allow(non_snake_case)]
-    struct BITMAPINFOHEADER {
+    #[allow(non_snake_case, dead_code)]
+    struct BitmapInfoHeader {
         biSize: u32,
         biWidth: i32,
         biHeight: i32,
@@ -26,6 +672,33 @@ mod win {
         biClrImportant: u32,
     }
 
+    #[link(name = "gdi32")]
+    extern "system" {
+        fn GetObjectW(h: *mut std::ffi::c_void, c: i32, pv: *mut std::ffi::c_void) -> i32;
+        fn CreateBitmap(
+            nWidth: i32,
+            nHeight: i32,
+            nPlanes: u32,
+            nBitCount: u32,
+            lpBits: *const std::ffi::c_void,
+        ) -> *mut std::ffi::c_void;
+        fn DeleteObject(ho: *mut std::ffi::c_void) -> i32;
+        fn GetDIBits(
+            hdc: *mut std::ffi::c_void,
+            hbm: *mut std::ffi::c_void,
+            start: u32,
+            cLines: u32,
+            lpvBits: *mut std::ffi::c_void,
+            lpbmi: *mut BitmapInfoHeader,
+            usage: u32,
+        ) -> i32;
+    }
+
+    extern "system" {
+        fn GetDC(hWnd: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn ReleaseDC(hWnd: *mut std::ffi::c_void, hDC: *mut std::ffi::c_void) -> i32;
+    }
+
     extern "system" {
         fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
         fn CloseClipboard() -> i32;
@@ -35,6 +708,8 @@ mod win {
         fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
         fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
         fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+        fn EmptyClipboard() -> i32;
+        fn SetClipboardData(uFormat: u32, hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
     }
 
     fn register_png_format() -> u32 {
@@ -42,35 +717,11 @@ mod win {
         unsafe { RegisterClipboardFormatW(name.as_ptr()) }
     }
 
-    fn get_images_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
-        let app_data = app_handle.path().app_data_dir().unwrap();
-        app_data.join("tauri-plugin-clipboard-x").join("images")
-    }
-
-    fn hash_bytes(data: &[u8]) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        data.hash(&mut hasher);
-        hasher.finish()
-    }
-
-    fn get_png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
-        if data.len() < 24 {
-            return None;
-        }
-        if &data[0..8] != &[137, 80, 78, 71, 13, 10, 26, 10] {
-            return None;
-        }
-        let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
-        let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
-        Some((width, height))
-    }
-
-    #[derive(Serialize, Clone)]
-    pub struct ReadImageResult {
-        pub path: String,
-        pub size: u64,
-        pub width: u32,
-        pub height: u32,
+    /// Offered next to the bitmap formats by browsers and chat apps, the
+    /// only one that keeps a GIF animated.
+    fn register_gif_format() -> u32 {
+        let name: Vec<u16> = "GIF\0".encode_utf16().collect();
+        unsafe { RegisterClipboardFormatW(name.as_ptr()) }
     }
 
     /// Check if the Windows clipboard has any image format available.
@@ -80,6 +731,7 @@ mod win {
             IsClipboardFormatAvailable(png_format) != 0
                 || IsClipboardFormatAvailable(CF_DIBV5) != 0
                 || IsClipboardFormatAvailable(CF_DIB) != 0
+                || IsClipboardFormatAvailable(CF_BITMAP) != 0
         }
     }
 
@@ -124,9 +776,162 @@ mod win {
             }
         }
 
+        // Some apps only offer a device-dependent HBITMAP
+        if IsClipboardFormatAvailable(CF_BITMAP) != 0 {
+            if let Some(result) = try_read_bitmap(app_handle)? {
+                return Ok(Some(result));
+            }
+        }
+
         Ok(None)
     }
 
+    /// Read the registered GIF format and keep it when it is animated.
+    pub fn read_gif<R: Runtime>(
+        app_handle: &AppHandle<R>,
+    ) -> Result<Option<ReadImageResult>, String> {
+        unsafe {
+            let gif_format = register_gif_format();
+            if IsClipboardFormatAvailable(gif_format) == 0 {
+                return Ok(None);
+            }
+
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            let bytes = read_global(gif_format);
+            CloseClipboard();
+
+            match bytes {
+                Some(bytes) => save_gif_bytes(app_handle, &bytes),
+                None => Ok(None),
+            }
+        }
+    }
+
+    /// Copy the bytes of a clipboard format, the clipboard must be open.
+    unsafe fn read_global(format: u32) -> Option<Vec<u8>> {
+        let handle = GetClipboardData(format);
+        if handle.is_null() {
+            return None;
+        }
+
+        let data = GlobalLock(handle);
+        if data.is_null() {
+            return None;
+        }
+
+        let size = GlobalSize(handle);
+        let bytes = std::slice::from_raw_parts(data as *const u8, size).to_vec();
+        GlobalUnlock(handle);
+
+        Some(bytes)
+    }
+
+    /// BITMAPV5HEADER describing a top-down 32-bit BGRA bitmap with alpha.
+    fn dibv5_bytes(image: &RgbaImage) -> Vec<u8> {
+        const HEADER_SIZE: u32 = 124;
+        const BI_BITFIELDS: u32 = 3;
+        const LCS_SRGB: u32 = 0x7352_4742; // 'sRGB'
+        const LCS_GM_IMAGES: u32 = 4;
+
+        let (width, height) = image.dimensions();
+        let pixels_size = width * height * 4;
+
+        let mut bytes = Vec::with_capacity((HEADER_SIZE + pixels_size) as usize);
+        bytes.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+        bytes.extend_from_slice(&(width as i32).to_le_bytes());
+        // A negative height marks the rows as top-down
+        bytes.extend_from_slice(&(-(height as i32)).to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // planes
+        bytes.extend_from_slice(&32u16.to_le_bytes()); // bit count
+        bytes.extend_from_slice(&BI_BITFIELDS.to_le_bytes());
+        bytes.extend_from_slice(&pixels_size.to_le_bytes());
+        bytes.extend_from_slice(&[0; 16]); // resolution and palette
+        bytes.extend_from_slice(&0x00FF_0000u32.to_le_bytes()); // red mask
+        bytes.extend_from_slice(&0x0000_FF00u32.to_le_bytes()); // green mask
+        bytes.extend_from_slice(&0x0000_00FFu32.to_le_bytes()); // blue mask
+        bytes.extend_from_slice(&0xFF00_0000u32.to_le_bytes()); // alpha mask
+        bytes.extend_from_slice(&LCS_SRGB.to_le_bytes());
+        bytes.extend_from_slice(&[0; 48]); // endpoints and gamma
+        bytes.extend_from_slice(&LCS_GM_IMAGES.to_le_bytes());
+        bytes.extend_from_slice(&[0; 12]); // profile data, size and reserved
+
+        for pixel in image.pixels() {
+            let [r, g, b, a] = pixel.0;
+            bytes.extend_from_slice(&[b, g, r, a]);
+        }
+
+        bytes
+    }
+
+    /// Offer a 32-bit CF_BITMAP for legacy apps like Paint, which read the
+    /// bitmap Windows synthesizes from CF_DIBV5 with black where the image
+    /// is transparent. The pixels are flattened onto white. The clipboard
+    /// must be open.
+    unsafe fn set_clipboard_bitmap(image: &RgbaImage) -> Result<(), String> {
+        let mut bits = Vec::with_capacity(image.as_raw().len());
+
+        for pixel in image.pixels() {
+            let [r, g, b, a] = pixel.0;
+            let blend =
+                |channel: u8| ((channel as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+
+            bits.extend_from_slice(&[blend(b), blend(g), blend(r), 255]);
+        }
+
+        // Rows of a 32-bit bitmap are always word aligned, top-down
+        let bitmap = CreateBitmap(
+            image.width() as i32,
+            image.height() as i32,
+            1,
+            32,
+            bits.as_ptr() as *const std::ffi::c_void,
+        );
+        if bitmap.is_null() {
+            return Err("Failed to create bitmap".to_string());
+        }
+
+        // The clipboard owns the bitmap once it was set
+        if SetClipboardData(CF_BITMAP, bitmap).is_null() {
+            DeleteObject(bitmap);
+            return Err("Failed to set clipboard data".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Place an image on the clipboard as registered PNG, which keeps alpha
+    /// for apps that understand it, CF_DIBV5 for everything else and
+    /// CF_BITMAP for legacy apps. An animated GIF is offered as is as well.
+    pub fn write_image(
+        png_bytes: &[u8],
+        image: &RgbaImage,
+        gif_bytes: Option<&[u8]>,
+    ) -> Result<(), String> {
+        let dib = dibv5_bytes(image);
+
+        unsafe {
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            EmptyClipboard();
+
+            let result = set_clipboard_bytes(register_png_format(), png_bytes)
+                .and_then(|_| set_clipboard_bytes(CF_DIBV5, &dib))
+                .and_then(|_| set_clipboard_bitmap(image))
+                .and_then(|_| match gif_bytes {
+                    Some(gif_bytes) => set_clipboard_bytes(register_gif_format(), gif_bytes),
+                    None => Ok(()),
+                });
+
+            CloseClipboard();
+            result
+        }
+    }
+
     /// Read a raw clipboard format that is already PNG data.
     unsafe fn try_read_raw_format<R: Runtime>(
         app_handle: &AppHandle<R>,
@@ -160,151 +965,648 @@ mod win {
         result
     }
 
-    /// Read a DIB/DIBV5 format, convert to PNG, and save.
-    unsafe fn try_read_dib<R: Runtime>(
+    /// Copy the pixels of a CF_BITMAP handle into a 32-bit top-down DIB with
+    /// GetDIBits, then convert it like any other DIB.
+    unsafe fn try_read_bitmap<R: Runtime>(
         app_handle: &AppHandle<R>,
-        format: u32,
     ) -> Result<Option<ReadImageResult>, String> {
-        let handle = GetClipboardData(format);
+        let handle = GetClipboardData(CF_BITMAP);
         if handle.is_null() {
             return Ok(None);
         }
 
-        let data = GlobalLock(handle);
-        if data.is_null() {
+        let mut bitmap: Bitmap = std::mem::zeroed();
+        let size = std::mem::size_of::<Bitmap>() as i32;
+        if GetObjectW(handle, size, &mut bitmap as *mut _ as *mut _) == 0 {
             return Ok(None);
         }
 
-        let size = GlobalSize(handle);
-        if size < std::mem::size_of::<BITMAPINFOHEADER>() {
-            GlobalUnlock(handle);
+        let width = bitmap.bmWidth.unsigned_abs();
+        let height = bitmap.bmHeight.unsigned_abs();
+        if width == 0 || height == 0 {
             return Ok(None);
         }
 
-        let bytes = std::slice::from_raw_parts(data as *const u8, size);
-        let header = &*(data as *const BITMAPINFOHEADER);
-
-        let bit_count = header.biBitCount as u32;
+        check_dimensions(width, height)?;
 
-        // Calculate color table size
-        let colors_used = if header.biClrUsed > 0 {
-            header.biClrUsed
-        } else if bit_count <= 8 {
-            1u32 << bit_count
-        } else {
-            0
+        let mut header = BitmapInfoHeader {
+            biSize: std::mem::size_of::<BitmapInfoHeader>() as u32,
+            biWidth: width as i32,
+            // A negative height asks for the rows top-down
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            biSizeImage: 0,
+            biXPelsPerMeter: 0,
+            biYPelsPerMeter: 0,
+            biClrUsed: 0,
+            biClrImportant: 0,
         };
-        let color_table_size = colors_used * 4; // RGBQUAD = 4 bytes each
 
-        let header_size = header.biSize;
-        let pixel_data_offset = header_size + color_table_size;
-        if size as u32 <= pixel_data_offset {
-            GlobalUnlock(handle);
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+
+        let hdc = GetDC(std::ptr::null_mut());
+        if hdc.is_null() {
             return Ok(None);
         }
 
-        // Build a complete BMP file: file header + DIB data
-        let bmp_file_header_size: u32 = 14;
-        let file_size = bmp_file_header_size + size as u32;
-        let pixel_offset = bmp_file_header_size + pixel_data_offset;
+        let lines = GetDIBits(
+            hdc,
+            handle,
+            0,
+            height,
+            pixels.as_mut_ptr() as *mut _,
+            &mut header,
+            DIB_RGB_COLORS,
+        );
+        ReleaseDC(std::ptr::null_mut(), hdc);
+
+        if lines == 0 {
+            return Ok(None);
+        }
 
-        let mut bmp_data = Vec::with_capacity(file_size as usize);
-        // BMP file header
-        bmp_data.extend_from_slice(&[0x42, 0x4D]); // 'BM'
-        bmp_data.extend_from_slice(&file_size.to_le_bytes());
-        bmp_data.extend_from_slice(&[0, 0, 0, 0]); // reserved
-        bmp_data.extend_from_slice(&pixel_offset.to_le_bytes());
-        // DIB data (header + color table + pixels)
-        bmp_data.extend_from_slice(bytes);
+        let header_bytes = std::slice::from_raw_parts(
+            &header as *const _ as *const u8,
+            std::mem::size_of::<BitmapInfoHeader>(),
+        );
 
-        GlobalUnlock(handle);
+        let mut dib = Vec::with_capacity(header_bytes.len() + pixels.len());
+        dib.extend_from_slice(header_bytes);
+        dib.extend_from_slice(&pixels);
 
-        // Convert BMP to PNG using the image crate
-        convert_bmp_to_png(app_handle, &bmp_data)
+        match dib_to_rgba(&dib)? {
+            Some(image) => save_rgba_image(app_handle, image),
+            None => Ok(None),
+        }
     }
 
-    fn save_png_bytes<R: Runtime>(
+    /// Read a DIB/DIBV5 format, convert to PNG, and save. 32-bit data is
+    /// decoded by hand so its alpha channel survives.
+    unsafe fn try_read_dib<R: Runtime>(
         app_handle: &AppHandle<R>,
-        png_bytes: &[u8],
+        format: u32,
     ) -> Result<Option<ReadImageResult>, String> {
-        if png_bytes.is_empty() {
+        let handle = GetClipboardData(format);
+        if handle.is_null() {
             return Ok(None);
         }
 
-        let (width, height) = get_png_dimensions(png_bytes).unwrap_or((0, 0));
-        if width == 0 || height == 0 {
+        let data = GlobalLock(handle);
+        if data.is_null() {
             return Ok(None);
         }
 
-        let hash = hash_bytes(png_bytes);
-        let filename = format!("{}.png", hash);
-        let dir = get_images_dir(app_handle);
-        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-        let path = dir.join(&filename);
+        let size = GlobalSize(handle);
+        let bytes = std::slice::from_raw_parts(data as *const u8, size);
+        let dib = read_dib(bytes);
+
+        GlobalUnlock(handle);
+
+        match dib? {
+            Some(DibImage::Rgba(image)) => save_rgba_image(app_handle, image),
+            // Convert BMP to PNG using the image crate
+            Some(DibImage::Bmp(bmp_data)) => {
+                convert_to_png(app_handle, bmp_data, ImageFormat::Bmp)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use super::{
+        convert_to_png, get_png_dimensions, save_gif_bytes, save_png_bytes, ReadImageResult,
+    };
+    use crate::image_decode::check_dimensions;
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSAutoreleasePool, NSString};
+    use image::ImageFormat;
+    use objc::{class, msg_send, sel, sel_impl};
+    use tauri::{AppHandle, Runtime};
 
-        // Only write if not already present (hash-based dedup on disk)
-        if !path.exists() {
-            std::fs::write(&path, png_bytes).map_err(|e| e.to_string())?;
+    /// Pasteboard types tried in order, PNG needs no conversion.
+    const PNG_TYPE: &str = "public.png";
+    const TIFF_TYPE: &str = "public.tiff";
+    const GIF_TYPE: &str = "com.compuserve.gif";
+
+    unsafe fn general_pasteboard() -> id {
+        msg_send![class!(NSPasteboard), generalPasteboard]
+    }
+
+    unsafe fn image_classes() -> id {
+        NSArray::arrayWithObject(nil, class!(NSImage) as *const _ as id)
+    }
+
+    unsafe fn data_bytes(data: id) -> Option<Vec<u8>> {
+        if data == nil {
+            return None;
         }
 
-        let file_size = std::fs::metadata(&path)
-            .map(|m| m.len())
-            .unwrap_or(0);
+        let length: usize = msg_send![data, length];
+        let bytes: *const u8 = msg_send![data, bytes];
+        if length == 0 || bytes.is_null() {
+            return None;
+        }
 
-        Ok(Some(ReadImageResult {
-            path: path.to_string_lossy().to_string(),
-            size: file_size,
-            width,
-            height,
-        }))
+        Some(std::slice::from_raw_parts(bytes, length).to_vec())
+    }
+
+    unsafe fn data_for_type(pasteboard: id, pasteboard_type: &str) -> Option<Vec<u8>> {
+        let pasteboard_type = NSString::alloc(nil).init_str(pasteboard_type).autorelease();
+
+        data_bytes(msg_send![pasteboard, dataForType: pasteboard_type])
+    }
+
+    /// Anything else NSImage can read (PDF, icns, file promises of images),
+    /// flattened to TIFF by AppKit.
+    unsafe fn ns_image_tiff(pasteboard: id) -> Option<Vec<u8>> {
+        let objects: id = msg_send![pasteboard, readObjectsForClasses: image_classes() options: nil];
+        if objects == nil || objects.count() == 0 {
+            return None;
+        }
+
+        let image = objects.objectAtIndex(0);
+
+        data_bytes(msg_send![image, TIFFRepresentation])
     }
 
-    fn convert_bmp_to_png<R: Runtime>(
+    /// Check if the pasteboard holds anything readable as an image.
+    pub fn has_image() -> bool {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let available: bool =
+                msg_send![general_pasteboard(), canReadObjectForClasses: image_classes() options: nil];
+            pool.drain();
+
+            available
+        }
+    }
+
+    /// Read a GIF from the pasteboard and keep it when it is animated.
+    pub fn read_gif<R: Runtime>(
         app_handle: &AppHandle<R>,
-        bmp_data: &[u8],
     ) -> Result<Option<ReadImageResult>, String> {
-        let img = image::load_from_memory_with_format(bmp_data, image::ImageFormat::Bmp)
-            .map_err(|e| format!("Failed to decode BMP: {}", e))?;
+        let data = unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let data = data_for_type(general_pasteboard(), GIF_TYPE);
+            pool.drain();
 
-        let width = img.width();
-        let height = img.height();
+            data
+        };
 
-        if width == 0 || height == 0 {
-            return Ok(None);
+        match data {
+            Some(data) => save_gif_bytes(app_handle, &data),
+            None => Ok(None),
         }
+    }
 
-        let mut png_bytes = Vec::new();
-        img.write_to(&mut Cursor::new(&mut png_bytes), image::ImageFormat::Png)
-            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    /// Read an image from the pasteboard and save it as PNG.
+    pub fn read_image<R: Runtime>(
+        app_handle: &AppHandle<R>,
+    ) -> Result<Option<ReadImageResult>, String> {
+        let (data, format) = unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let pasteboard = general_pasteboard();
+
+            let found = match data_for_type(pasteboard, PNG_TYPE) {
+                Some(data) => Some((data, ImageFormat::Png)),
+                None => data_for_type(pasteboard, TIFF_TYPE)
+                    .or_else(|| ns_image_tiff(pasteboard))
+                    .map(|data| (data, ImageFormat::Tiff)),
+            };
+
+            pool.drain();
 
-        save_png_bytes(app_handle, &png_bytes)
+            match found {
+                Some(found) => found,
+                None => return Ok(None),
+            }
+        };
+
+        match format {
+            ImageFormat::Png => {
+                let (width, height) = get_png_dimensions(&data).unwrap_or((0, 0));
+                check_dimensions(width, height)?;
+
+                save_png_bytes(app_handle, &data)
+            }
+            _ => convert_to_png(app_handle, data, format),
+        }
     }
+}
 
-    #[tauri::command]
-    pub async fn has_clipboard_image_win() -> bool {
-        has_image()
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{
+        convert_to_png, get_png_dimensions, save_gif_bytes, save_png_bytes, ReadImageResult,
+    };
+    use crate::image_decode::check_dimensions;
+    use image::ImageFormat;
+    use std::process::Command;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use tauri::{AppHandle, Runtime};
+    use x11::xlib::{
+        self, Atom, Display, Window, XCheckTypedWindowEvent, XCloseDisplay, XConvertSelection,
+        XCreateSimpleWindow, XDefaultRootWindow, XDestroyWindow, XEvent, XFree,
+        XGetWindowProperty, XInternAtom, XOpenDisplay, XSelectInput,
+    };
+
+    /// Image targets in order of preference, PNG needs no conversion.
+    const IMAGE_TARGETS: &[(&str, ImageFormat)] =
+        &[("image/png", ImageFormat::Png), ("image/bmp", ImageFormat::Bmp)];
+
+    /// How long the selection owner gets to answer a request.
+    const SELECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+    const GIF_TARGET: &str = "image/gif";
+
+    fn is_wayland() -> bool {
+        std::env::var_os("WAYLAND_DISPLAY").is_some()
     }
 
-    #[tauri::command]
-    pub async fn read_clipboard_image_win<R: Runtime>(
-        app_handle: AppHandle<R>,
+    /// wl-clipboard is the only way to read the Wayland clipboard without
+    /// owning a focused surface, `None` means it is not installed.
+    mod wayland {
+        use super::Command;
+
+        pub fn list_types() -> Option<Vec<String>> {
+            let output = Command::new("wl-paste").arg("--list-types").output().ok()?;
+
+            Some(
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .collect(),
+            )
+        }
+
+        pub fn read(mime_type: &str) -> Option<Vec<u8>> {
+            let output = Command::new("wl-paste")
+                .args(["--no-newline", "--type", mime_type])
+                .output()
+                .ok()?;
+
+            if output.status.success() && !output.stdout.is_empty() {
+                Some(output.stdout)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// A hidden window that requests conversions of the CLIPBOARD selection.
+    struct SelectionReader {
+        display: *mut Display,
+        window: Window,
+        clipboard: Atom,
+        property: Atom,
+        incr: Atom,
+    }
+
+    impl SelectionReader {
+        unsafe fn open() -> Option<Self> {
+            let display = XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+
+            let window =
+                XCreateSimpleWindow(display, XDefaultRootWindow(display), 0, 0, 1, 1, 0, 0, 0);
+            // INCR transfers are driven by property change notifications
+            XSelectInput(display, window, xlib::PropertyChangeMask);
+
+            Some(Self {
+                display,
+                window,
+                clipboard: Self::atom(display, "CLIPBOARD"),
+                property: Self::atom(display, "ECOPASTE_SELECTION"),
+                incr: Self::atom(display, "INCR"),
+            })
+        }
+
+        unsafe fn atom(display: *mut Display, name: &str) -> Atom {
+            let name = std::ffi::CString::new(name).unwrap();
+            XInternAtom(display, name.as_ptr(), xlib::False)
+        }
+
+        unsafe fn atom_name(&self, atom: Atom) -> Option<String> {
+            let name = xlib::XGetAtomName(self.display, atom);
+            if name.is_null() {
+                return None;
+            }
+
+            let value = std::ffi::CStr::from_ptr(name).to_string_lossy().into_owned();
+            XFree(name as *mut _);
+
+            Some(value)
+        }
+
+        unsafe fn wait_for_event(&self, event_type: i32, event: &mut XEvent) -> bool {
+            let deadline = Instant::now() + SELECTION_TIMEOUT;
+
+            while Instant::now() < deadline {
+                if XCheckTypedWindowEvent(self.display, self.window, event_type, event) != 0 {
+                    return true;
+                }
+
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            false
+        }
+
+        /// Read and delete the transfer property, returning its type, format
+        /// and content.
+        unsafe fn take_property(&self) -> Option<(Atom, i32, Vec<u8>)> {
+            let mut actual_type: Atom = 0;
+            let mut actual_format: i32 = 0;
+            let mut nitems: u64 = 0;
+            let mut bytes_after: u64 = 0;
+            let mut data: *mut u8 = std::ptr::null_mut();
+
+            let result = XGetWindowProperty(
+                self.display,
+                self.window,
+                self.property,
+                0,
+                i64::MAX / 4,
+                xlib::True,
+                xlib::AnyPropertyType as _,
+                &mut actual_type,
+                &mut actual_format,
+                &mut nitems,
+                &mut bytes_after,
+                &mut data,
+            );
+
+            if result != xlib::Success as i32 {
+                return None;
+            }
+
+            // Xlib hands 32-bit items out as longs
+            let item_size = match actual_format {
+                32 => std::mem::size_of::<std::ffi::c_ulong>(),
+                16 => 2,
+                _ => 1,
+            };
+
+            let bytes = if data.is_null() {
+                Vec::new()
+            } else {
+                let bytes = std::slice::from_raw_parts(data, nitems as usize * item_size).to_vec();
+                XFree(data as *mut _);
+                bytes
+            };
+
+            Some((actual_type, actual_format, bytes))
+        }
+
+        unsafe fn convert(&self, target: &str) -> Option<(i32, Vec<u8>)> {
+            let target = Self::atom(self.display, target);
+            XConvertSelection(
+                self.display,
+                self.clipboard,
+                target,
+                self.property,
+                self.window,
+                xlib::CurrentTime,
+            );
+
+            let mut event: XEvent = std::mem::zeroed();
+            if !self.wait_for_event(xlib::SelectionNotify, &mut event) {
+                return None;
+            }
+
+            if event.selection.property == 0 {
+                return None;
+            }
+
+            let (actual_type, format, bytes) = self.take_property()?;
+            if actual_type != self.incr {
+                return Some((format, bytes));
+            }
+
+            // Large selections arrive in chunks, an empty chunk ends the transfer
+            let mut data = Vec::new();
+            loop {
+                if !self.wait_for_event(xlib::PropertyNotify, &mut event) {
+                    return None;
+                }
+
+                if event.property.atom != self.property
+                    || event.property.state != xlib::PropertyNewValue
+                {
+                    continue;
+                }
+
+                let (_, _, chunk) = self.take_property()?;
+                if chunk.is_empty() {
+                    return Some((8, data));
+                }
+
+                data.extend_from_slice(&chunk);
+            }
+        }
+
+        unsafe fn targets(&self) -> Vec<String> {
+            let Some((32, bytes)) = self.convert("TARGETS") else {
+                return Vec::new();
+            };
+
+            bytes
+                .chunks_exact(std::mem::size_of::<std::ffi::c_ulong>())
+                .filter_map(|chunk| {
+                    let atom = std::ffi::c_ulong::from_ne_bytes(chunk.try_into().ok()?);
+                    self.atom_name(atom)
+                })
+                .collect()
+        }
+    }
+
+    impl Drop for SelectionReader {
+        fn drop(&mut self) {
+            unsafe {
+                XDestroyWindow(self.display, self.window);
+                XCloseDisplay(self.display);
+            }
+        }
+    }
+
+    /// Targets the current clipboard owner offers.
+    pub fn available_targets() -> Vec<String> {
+        if is_wayland() {
+            if let Some(types) = wayland::list_types() {
+                return types;
+            }
+        }
+
+        // X11, or XWayland when wl-clipboard is missing
+        unsafe { SelectionReader::open().map(|reader| reader.targets()).unwrap_or_default() }
+    }
+
+    /// Read the clipboard converted to a single target.
+    pub fn read_target(target: &str) -> Option<Vec<u8>> {
+        if is_wayland() && wayland::list_types().is_some() {
+            return wayland::read(target);
+        }
+
+        unsafe { SelectionReader::open()?.convert(target).map(|(_, bytes)| bytes) }
+    }
+
+    /// Check if the clipboard offers an image target.
+    pub fn has_image() -> bool {
+        let targets = available_targets();
+
+        IMAGE_TARGETS
+            .iter()
+            .any(|(target, _)| targets.iter().any(|available| available == target))
+    }
+
+    /// Read a GIF from the clipboard and keep it when it is animated.
+    pub fn read_gif<R: Runtime>(
+        app_handle: &AppHandle<R>,
+    ) -> Result<Option<ReadImageResult>, String> {
+        let targets = available_targets();
+
+        if !targets.iter().any(|target| target == GIF_TARGET) {
+            return Ok(None);
+        }
+
+        match read_target(GIF_TARGET) {
+            Some(data) => save_gif_bytes(app_handle, &data),
+            None => Ok(None),
+        }
+    }
+
+    /// Read an image from the clipboard and save it as PNG.
+    pub fn read_image<R: Runtime>(
+        app_handle: &AppHandle<R>,
     ) -> Result<Option<ReadImageResult>, String> {
-        read_image(&app_handle)
+        let targets = available_targets();
+
+        for (target, format) in IMAGE_TARGETS {
+            if !targets.iter().any(|available| available == target) {
+                continue;
+            }
+
+            let Some(data) = read_target(target) else {
+                continue;
+            };
+
+            return match format {
+                ImageFormat::Png => {
+                    let (width, height) = get_png_dimensions(&data).unwrap_or((0, 0));
+                    check_dimensions(width, height)?;
+
+                    save_png_bytes(app_handle, &data)
+                }
+                _ => convert_to_png(app_handle, data, *format),
+            };
+        }
+
+        Ok(None)
     }
 }
 
 #[cfg(target_os = "windows")]
-pub use win::{has_clipboard_image_win, read_clipboard_image_win};
+use win::{has_image, read_gif, read_image, write_image};
+
+#[cfg(target_os = "macos")]
+use mac::{has_image, read_gif, read_image};
+
+#[cfg(target_os = "linux")]
+use linux::{has_image, read_gif, read_image};
 
-// Stubs for non-Windows platforms
+#[cfg(target_os = "linux")]
+pub use linux::{available_targets, read_target};
+
+/// Only a still frame can be written through arboard.
 #[cfg(not(target_os = "windows"))]
+fn write_image(
+    _png_bytes: &[u8],
+    image: &RgbaImage,
+    _gif_bytes: Option<&[u8]>,
+) -> Result<(), String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+
+    clipboard
+        .set_image(arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        })
+        .map_err(|e| e.to_string())
+}
+
+// 原生读取剪贴板图片，补充剪贴板插件识别不到的格式
 #[tauri::command]
 pub async fn has_clipboard_image_win() -> bool {
-    false
+    has_image()
 }
 
-#[cfg(not(target_os = "windows"))]
 #[tauri::command]
-pub async fn read_clipboard_image_win() -> Result<Option<()>, String> {
-    Ok(None)
+pub async fn read_clipboard_image_win<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<Option<ReadImageResult>, String> {
+    read_image(&app_handle)
+}
+
+// 读取剪贴板中的动图，保留原始 GIF 而不是转换为单帧 PNG
+#[tauri::command]
+pub async fn read_clipboard_gif<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<Option<ReadImageResult>, String> {
+    if let Some(result) = read_gif(&app_handle)? {
+        return Ok(Some(result));
+    }
+
+    // A file list alone is a copy from the file manager, not an image
+    if !has_image() {
+        return Ok(None);
+    }
+
+    read_gif_file(&app_handle)
+}
+
+// 把图片写入剪贴板，图片可以是文件路径或原始数据
+#[tauri::command]
+pub async fn write_clipboard_image<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: Option<String>,
+    bytes: Option<Vec<u8>>,
+) -> Result<(), String> {
+    let bytes = match (path, bytes) {
+        (_, Some(bytes)) => bytes,
+        (Some(path), None) => {
+            vault::read_file(&warm_image(&app_handle, &path)?)?
+        }
+        (None, None) => return Err("Either a path or image bytes are required".to_string()),
+    };
+
+    let is_gif = image::guess_format(&bytes).is_ok_and(|format| format == ImageFormat::Gif);
+    let gif_bytes = is_gif.then(|| bytes.clone());
+
+    let (png_bytes, image) = prepare_image(bytes)?;
+
+    write_image(&png_bytes, &image, gif_bytes.as_deref())
+}
+
+// 把历史记录中的图片复制回剪贴板，同时提供 PNG 和位图格式，旧版应用（例如画图）也能粘贴
+#[tauri::command]
+pub async fn copy_image_entry<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+) -> Result<(), String> {
+    // Images in cold storage are moved back first
+    let bytes = vault::read_file(&warm_image(&app_handle, &path)?)?;
+    let (png_bytes, image) = prepare_image(bytes)?;
+
+    mark_own_write();
+
+    write_image(&png_bytes, &image, None)
 }
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+/// Name of the clipboard format other apps set to send EcoPaste a request,
+/// see `docs/clipboard-protocol.md`.
+#[cfg(not(target_os = "linux"))]
+pub const REQUEST_FORMAT: &str = "ecopaste-paste";
+#[cfg(target_os = "linux")]
+pub const REQUEST_FORMAT: &str = "application/x-ecopaste-paste";
+
+/// The only protocol version so far.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Requests are a few fields of JSON, anything bigger is not one.
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+const MAX_TAGS: usize = 32;
+const MAX_TAG_CHARS: usize = 64;
+
+/// What the app that copied wants EcoPaste to do with the copy.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum PasteRequest {
+    /// Store the copy with tags, a note and optionally as a favorite.
+    Store {
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        note: Option<String>,
+        #[serde(default)]
+        favorite: bool,
+    },
+    /// Do not store the copy at all, e.g. for secrets.
+    Ignore,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    #[serde(flatten)]
+    request: PasteRequest,
+}
+
+/// Trimmed and truncated tags without blanks or duplicates, at most
+/// `MAX_TAGS` of them.
+pub fn clean_tags(tags: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag: String = tag.trim().chars().take(MAX_TAG_CHARS).collect();
+
+        if !tag.is_empty() && !cleaned.contains(&tag) && cleaned.len() < MAX_TAGS {
+            cleaned.push(tag);
+        }
+    }
+
+    cleaned
+}
+
+/// Parse a request payload: UTF-8 JSON, optionally NUL terminated.
+pub fn parse_request(bytes: &[u8]) -> Result<PasteRequest, String> {
+    if bytes.len() > MAX_REQUEST_BYTES {
+        return Err(format!("Request of {} bytes is too big", bytes.len()));
+    }
+
+    let len = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+    let text = std::str::from_utf8(&bytes[..len]).map_err(|e| e.to_string())?;
+
+    let envelope: Envelope = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if envelope.version != PROTOCOL_VERSION {
+        return Err(format!("Unsupported request version {}", envelope.version));
+    }
+
+    Ok(match envelope.request {
+        PasteRequest::Store {
+            tags,
+            note,
+            favorite,
+        } => {
+            PasteRequest::Store {
+                tags: clean_tags(tags),
+                note: note.filter(|note| !note.trim().is_empty()),
+                favorite,
+            }
+        }
+        PasteRequest::Ignore => PasteRequest::Ignore,
+    })
+}
+
+/// Serialize a request the way other apps are expected to, for setting it
+/// on the clipboard ourselves.
+pub fn encode_request(request: &PasteRequest) -> Vec<u8> {
+    let envelope = Envelope {
+        version: PROTOCOL_VERSION,
+        request: request.clone(),
+    };
+
+    serde_json::to_vec(&envelope).unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::REQUEST_FORMAT;
+
+    extern "system" {
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn GetClipboardData(uFormat: u32) -> *mut std::ffi::c_void;
+        fn IsClipboardFormatAvailable(format: u32) -> i32;
+        fn RegisterClipboardFormatW(lpszFormat: *const u16) -> u32;
+        fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
+        fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+    }
+
+    pub fn request_format() -> u32 {
+        let name: Vec<u16> = REQUEST_FORMAT.encode_utf16().chain([0]).collect();
+
+        unsafe { RegisterClipboardFormatW(name.as_ptr()) }
+    }
+
+    pub fn read_request_bytes() -> Option<Vec<u8>> {
+        unsafe {
+            let format = request_format();
+            if format == 0 || IsClipboardFormatAvailable(format) == 0 {
+                return None;
+            }
+
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return None;
+            }
+
+            let mut bytes = None;
+            let handle = GetClipboardData(format);
+            if !handle.is_null() {
+                let data = GlobalLock(handle);
+                if !data.is_null() {
+                    bytes = Some(
+                        std::slice::from_raw_parts(data as *const u8, GlobalSize(handle)).to_vec(),
+                    );
+                    GlobalUnlock(handle);
+                }
+            }
+
+            CloseClipboard();
+            bytes
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use super::REQUEST_FORMAT;
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSAutoreleasePool, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    pub fn read_request_bytes() -> Option<Vec<u8>> {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+            let pasteboard_type = NSString::alloc(nil).init_str(REQUEST_FORMAT).autorelease();
+            let data: id = msg_send![pasteboard, dataForType: pasteboard_type];
+
+            let mut bytes = None;
+            if data != nil {
+                let length: usize = msg_send![data, length];
+                let pointer: *const u8 = msg_send![data, bytes];
+                if length > 0 && !pointer.is_null() {
+                    bytes = Some(std::slice::from_raw_parts(pointer, length).to_vec());
+                }
+            }
+
+            pool.drain();
+            bytes
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::REQUEST_FORMAT;
+    use crate::clipboard_image::read_target;
+
+    pub fn read_request_bytes() -> Option<Vec<u8>> {
+        read_target(REQUEST_FORMAT)
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::read_request_bytes;
+
+#[cfg(target_os = "windows")]
+pub use win::request_format;
+
+#[cfg(target_os = "macos")]
+use mac::read_request_bytes;
+
+#[cfg(target_os = "linux")]
+use linux::read_request_bytes;
+
+/// The request on the clipboard, if the copying app set one. Malformed
+/// requests are logged and treated as absent, the copy is stored as usual.
+pub fn read_request() -> Option<PasteRequest> {
+    let bytes = read_request_bytes()?;
+
+    match parse_request(&bytes) {
+        Ok(request) => Some(request),
+        Err(error) => {
+            log::warn!("Ignoring malformed {} request: {}", REQUEST_FORMAT, error);
+            None
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::clipboard_files::write_clipboard_files;
+use crate::clipboard_html::write_html;
+use crate::clipboard_image::write_clipboard_image;
+use crate::clipboard_rtf::write_rtf;
+use crate::clipboard_simulate::{write_payload, ClipboardPayload};
+use crate::clipboard_text::write_text;
+use crate::clipboard_watcher::mark_own_write;
+use crate::history::{get_formats, item_at, EntryFormat, HistoryItem};
+use crate::item_access::require_access;
+use serde::Serialize;
+use tauri::{command, AppHandle, Runtime};
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredClipboard {
+    /// The item whose clipboard state was restored.
+    pub id: String,
+    pub create_time: String,
+    /// Whether the raw formats of the original copy were written, instead of
+    /// the item re-encoded by its type.
+    pub raw: bool,
+}
+
+/// Bytes of a stored format as the clipboard holds them, textual formats
+/// are kept decoded.
+fn format_bytes(format: &EntryFormat) -> Option<Vec<u8>> {
+    if let Some(data) = &format.data {
+        return Some(data.clone());
+    }
+
+    let text = format.text.as_ref()?;
+
+    if format.format == "CF_UNICODETEXT" {
+        let units = text.encode_utf16().chain(std::iter::once(0));
+
+        Some(units.flat_map(u16::to_le_bytes).collect())
+    } else {
+        Some(text.as_bytes().to_vec())
+    }
+}
+
+/// Write every format captured with the item, exactly as it was copied.
+fn write_raw(formats: &[EntryFormat]) -> Result<(), String> {
+    let mut payload = ClipboardPayload::default();
+
+    for format in formats {
+        if let Some(bytes) = format_bytes(format) {
+            payload.formats.insert(format.format.clone(), bytes);
+        }
+    }
+
+    write_payload(&payload)
+}
+
+/// Rebuild the clipboard from the item itself, with the alternatives the
+/// backend writes for its type.
+async fn write_item<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    item: &HistoryItem,
+) -> Result<(), String> {
+    match item.kind.as_str() {
+        "text" => write_text(&item.value),
+        "html" => write_html(&item.value, &item.search),
+        "rtf" => write_rtf(&item.value, &item.search),
+        "image" => write_clipboard_image(app_handle.clone(), Some(item.value.clone()), None).await,
+        "files" => {
+            let paths: Vec<String> =
+                serde_json::from_str(&item.value).map_err(|e| e.to_string())?;
+
+            write_clipboard_files(paths).await
+        }
+        kind => Err(format!("Unsupported item type for restore: {}", kind)),
+    }
+}
+
+// 把系统剪贴板恢复为某一时刻的状态（该时刻或之前最后一次复制的内容），有原始格式时原样写回
+#[command]
+pub async fn restore_clipboard_state<R: Runtime>(
+    app_handle: AppHandle<R>,
+    timestamp: String,
+) -> Result<RestoredClipboard, String> {
+    let item =
+        item_at(&timestamp)?.ok_or_else(|| format!("Nothing was copied before {}", timestamp))?;
+
+    require_access(&app_handle, &item.id).await?;
+
+    let formats = get_formats(&item.id)?;
+
+    // Raw formats can only be written natively on Windows, elsewhere the
+    // item is re-encoded
+    let raw = cfg!(target_os = "windows") && !formats.is_empty();
+
+    mark_own_write();
+
+    if raw {
+        write_raw(&formats)?;
+    } else {
+        write_item(&app_handle, &item).await?;
+    }
+
+    Ok(RestoredClipboard {
+        id: item.id,
+        create_time: item.create_time,
+        raw,
+    })
+}
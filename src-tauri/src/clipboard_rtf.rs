@@ -0,0 +1,431 @@
+use serde::Serialize;
+use tauri::command;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadRtfResult {
+    /// The RTF document as put on the clipboard.
+    pub rtf: String,
+    /// Plain text extracted from the document.
+    pub text: String,
+}
+
+/// Destinations whose content is not part of the document text.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "colortbl",
+    "datastore",
+    "fldinst",
+    "fonttbl",
+    "footer",
+    "footerl",
+    "footerr",
+    "generator",
+    "header",
+    "headerl",
+    "headerr",
+    "info",
+    "latentstyles",
+    "listoverridetable",
+    "listtable",
+    "object",
+    "pict",
+    "rsidtbl",
+    "stylesheet",
+    "themedata",
+    "xmlnstbl",
+];
+
+/// Collects the text of an RTF document while walking its groups.
+struct PlainTextWriter {
+    text: String,
+    /// Raw `\'hh` bytes, decoded together so multi-byte sequences survive.
+    bytes: Vec<u8>,
+    /// High half of a surrogate pair written as two `\u` words.
+    high_surrogate: Option<u32>,
+}
+
+impl PlainTextWriter {
+    fn flush_bytes(&mut self) {
+        if self.bytes.is_empty() {
+            return;
+        }
+
+        // The code page is rarely worth honoring, UTF-8 or Latin-1 covers
+        // what `\u` does not, as Word writes non-ASCII text as `\u` anyway
+        match std::str::from_utf8(&self.bytes) {
+            Ok(text) => self.text.push_str(text),
+            Err(_) => self.text.extend(self.bytes.iter().map(|&byte| byte as char)),
+        }
+
+        self.bytes.clear();
+    }
+
+    fn push(&mut self, ch: char) {
+        self.flush_bytes();
+        self.text.push(ch);
+    }
+
+    fn push_unicode(&mut self, value: i32) {
+        // Values above 32767 are written as negative numbers
+        let code = (if value < 0 { value + 65536 } else { value }) as u32;
+
+        match (self.high_surrogate.take(), code) {
+            (_, 0xD800..=0xDBFF) => self.high_surrogate = Some(code),
+            (Some(high), 0xDC00..=0xDFFF) => {
+                let combined = 0x10000 + ((high - 0xD800) << 10) + (code - 0xDC00);
+                if let Some(ch) = char::from_u32(combined) {
+                    self.push(ch);
+                }
+            }
+            (_, code) => {
+                if let Some(ch) = char::from_u32(code) {
+                    self.push(ch);
+                }
+            }
+        }
+    }
+}
+
+/// Extract the plain text of an RTF document. Formatting is dropped,
+/// paragraphs become line breaks and `\u` escapes are decoded.
+pub fn rtf_to_plain_text(rtf: &str) -> String {
+    let chars: Vec<char> = rtf.chars().collect();
+    let mut writer = PlainTextWriter {
+        text: String::new(),
+        bytes: Vec::new(),
+        high_surrogate: None,
+    };
+
+    // (skipping, unicode fallback length) of the enclosing groups
+    let mut groups: Vec<(bool, usize)> = Vec::new();
+    let mut skipping = false;
+    let mut fallback_len = 1;
+    // Fallback characters still to drop after a `\u` word
+    let mut fallback_left = 0;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        index += 1;
+
+        match ch {
+            '{' => {
+                groups.push((skipping, fallback_len));
+                fallback_left = 0;
+            }
+            '}' => {
+                if let Some((outer_skipping, outer_fallback_len)) = groups.pop() {
+                    skipping = outer_skipping;
+                    fallback_len = outer_fallback_len;
+                }
+                fallback_left = 0;
+            }
+            '\r' | '\n' => {}
+            '\\' => {
+                let Some(&next) = chars.get(index) else {
+                    break;
+                };
+
+                if !next.is_ascii_alphabetic() {
+                    index += 1;
+
+                    let byte = if next == '\'' {
+                        let hex: String = chars.iter().skip(index).take(2).collect();
+                        index += 2;
+                        u8::from_str_radix(&hex, 16).ok()
+                    } else {
+                        None
+                    };
+
+                    if fallback_left > 0 {
+                        fallback_left -= 1;
+                        continue;
+                    }
+
+                    match next {
+                        '*' => skipping = true,
+                        _ if skipping => {}
+                        '\'' => writer.bytes.extend(byte),
+                        '\\' | '{' | '}' => writer.push(next),
+                        '~' => writer.push('\u{a0}'),
+                        '\r' | '\n' => writer.push('\n'),
+                        _ => {}
+                    }
+
+                    continue;
+                }
+
+                let start = index;
+                while index < chars.len() && chars[index].is_ascii_alphabetic() {
+                    index += 1;
+                }
+                let word: String = chars[start..index].iter().collect();
+
+                let param_start = index;
+                if chars.get(index) == Some(&'-') {
+                    index += 1;
+                }
+                while index < chars.len() && chars[index].is_ascii_digit() {
+                    index += 1;
+                }
+                let param: Option<i32> = chars[param_start..index]
+                    .iter()
+                    .collect::<String>()
+                    .parse()
+                    .ok();
+
+                // A single space delimits the control word and is not text
+                if chars.get(index) == Some(&' ') {
+                    index += 1;
+                }
+
+                if fallback_left > 0 {
+                    fallback_left -= 1;
+                    continue;
+                }
+
+                match word.as_str() {
+                    "uc" => fallback_len = param.unwrap_or(1).max(0) as usize,
+                    "u" => {
+                        if let (Some(value), false) = (param, skipping) {
+                            writer.push_unicode(value);
+                        }
+                        fallback_left = fallback_len;
+                    }
+                    _ if skipping => {}
+                    "par" | "line" | "row" => writer.push('\n'),
+                    "tab" | "cell" => writer.push('\t'),
+                    "emdash" => writer.push('—'),
+                    "endash" => writer.push('–'),
+                    "bullet" => writer.push('•'),
+                    "lquote" => writer.push('‘'),
+                    "rquote" => writer.push('’'),
+                    "ldblquote" => writer.push('“'),
+                    "rdblquote" => writer.push('”'),
+                    word if SKIPPED_DESTINATIONS.contains(&word) => skipping = true,
+                    _ => {}
+                }
+            }
+            _ => {
+                if fallback_left > 0 {
+                    fallback_left -= 1;
+                } else if !skipping {
+                    writer.push(ch);
+                }
+            }
+        }
+    }
+
+    writer.flush_bytes();
+    writer.text.trim_end().to_string()
+}
+
+/// Clipboard owners usually null terminate the document.
+fn bytes_to_rtf(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+
+    String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use crate::clipboard_text::{set_clipboard_bytes, set_unicode_text};
+
+    extern "system" {
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn EmptyClipboard() -> i32;
+        fn GetClipboardData(uFormat: u32) -> *mut std::ffi::c_void;
+        fn IsClipboardFormatAvailable(format: u32) -> i32;
+        fn RegisterClipboardFormatW(lpszFormat: *const u16) -> u32;
+        fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
+        fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+    }
+
+    pub fn register_rtf_format() -> u32 {
+        let name: Vec<u16> = "Rich Text Format\0".encode_utf16().collect();
+        unsafe { RegisterClipboardFormatW(name.as_ptr()) }
+    }
+
+    pub fn read_rtf_bytes() -> Result<Option<Vec<u8>>, String> {
+        let format = register_rtf_format();
+
+        unsafe {
+            if IsClipboardFormatAvailable(format) == 0 {
+                return Ok(None);
+            }
+
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            let mut bytes = None;
+            let handle = GetClipboardData(format);
+            if !handle.is_null() {
+                let data = GlobalLock(handle);
+                if !data.is_null() {
+                    bytes = Some(
+                        std::slice::from_raw_parts(data as *const u8, GlobalSize(handle)).to_vec(),
+                    );
+                    GlobalUnlock(handle);
+                }
+            }
+
+            CloseClipboard();
+            Ok(bytes)
+        }
+    }
+
+    /// Write an RTF document with a plain text alternative.
+    pub fn write_rtf(rtf: &str, alt_text: &str) -> Result<(), String> {
+        let mut document = rtf.as_bytes().to_vec();
+        document.push(0);
+
+        unsafe {
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            EmptyClipboard();
+
+            let result = set_clipboard_bytes(register_rtf_format(), &document)
+                .and_then(|_| set_unicode_text(alt_text));
+
+            CloseClipboard();
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSAutoreleasePool, NSData, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    const RTF_TYPE: &str = "public.rtf";
+    const STRING_TYPE: &str = "public.utf8-plain-text";
+
+    pub fn read_rtf_bytes() -> Result<Option<Vec<u8>>, String> {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+            let pasteboard_type = NSString::alloc(nil).init_str(RTF_TYPE).autorelease();
+            let data: id = msg_send![pasteboard, dataForType: pasteboard_type];
+
+            let mut bytes = None;
+            if data != nil {
+                let length: usize = msg_send![data, length];
+                let pointer: *const u8 = msg_send![data, bytes];
+                if length > 0 && !pointer.is_null() {
+                    bytes = Some(std::slice::from_raw_parts(pointer, length).to_vec());
+                }
+            }
+
+            pool.drain();
+            Ok(bytes)
+        }
+    }
+
+    /// Write an RTF document with a plain text alternative.
+    pub fn write_rtf(rtf: &str, alt_text: &str) -> Result<(), String> {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+            let _: i64 = msg_send![pasteboard, clearContents];
+
+            let data = NSData::dataWithBytes_length_(
+                nil,
+                rtf.as_ptr() as *const std::ffi::c_void,
+                rtf.len() as u64,
+            );
+            let rtf_type = NSString::alloc(nil).init_str(RTF_TYPE).autorelease();
+            let text = NSString::alloc(nil).init_str(alt_text).autorelease();
+            let string_type = NSString::alloc(nil).init_str(STRING_TYPE).autorelease();
+
+            let rtf_written: bool = msg_send![pasteboard, setData: data forType: rtf_type];
+            let text_written: bool = msg_send![pasteboard, setString: text forType: string_type];
+
+            pool.drain();
+
+            if rtf_written && text_written {
+                Ok(())
+            } else {
+                Err("Failed to write RTF to the pasteboard".to_string())
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use crate::clipboard_image::{available_targets, read_target};
+    use crate::clipboard_text::write_text;
+
+    /// RTF targets in order of preference.
+    const RTF_TARGETS: &[&str] = &["text/rtf", "application/rtf", "text/richtext"];
+
+    pub fn read_rtf_bytes() -> Result<Option<Vec<u8>>, String> {
+        let targets = available_targets();
+
+        Ok(RTF_TARGETS
+            .iter()
+            .filter(|target| targets.iter().any(|available| available == *target))
+            .find_map(|target| read_target(target)))
+    }
+
+    // Serving several targets at once needs a selection owner of our own,
+    // keep the plain text until there is one
+    pub fn write_rtf(_rtf: &str, alt_text: &str) -> Result<(), String> {
+        write_text(alt_text)
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub use win::write_rtf;
+#[cfg(target_os = "windows")]
+use win::read_rtf_bytes;
+
+#[cfg(target_os = "macos")]
+pub use mac::write_rtf;
+#[cfg(target_os = "macos")]
+use mac::read_rtf_bytes;
+
+#[cfg(target_os = "linux")]
+pub use linux::write_rtf;
+#[cfg(target_os = "linux")]
+use linux::read_rtf_bytes;
+
+/// Read the RTF document on the clipboard with its plain text.
+pub fn read_rtf() -> Result<Option<ReadRtfResult>, String> {
+    let Some(bytes) = read_rtf_bytes()? else {
+        return Ok(None);
+    };
+
+    let rtf = bytes_to_rtf(&bytes);
+    if !rtf.trim_start().starts_with("{\\rtf") {
+        return Ok(None);
+    }
+
+    Ok(Some(ReadRtfResult {
+        text: rtf_to_plain_text(&rtf),
+        rtf,
+    }))
+}
+
+// 读取剪贴板中的富文本（RTF），同时返回纯文本
+#[command]
+pub async fn read_clipboard_rtf() -> Result<Option<ReadRtfResult>, String> {
+    read_rtf()
+}
+
+// 将富文本（RTF）写入剪贴板，未提供纯文本时从 RTF 中提取
+#[command]
+pub async fn write_clipboard_rtf(rtf: String, text: Option<String>) -> Result<(), String> {
+    let text = text.unwrap_or_else(|| rtf_to_plain_text(&rtf));
+
+    write_rtf(&rtf, &text)
+}
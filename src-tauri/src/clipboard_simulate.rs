@@ -0,0 +1,384 @@
+use crate::clipboard_files::{parse_drop_files, parse_uri_list};
+use crate::clipboard_html::{html_to_text, parse_cf_html};
+use crate::clipboard_image::{read_dib, DibImage};
+use crate::clipboard_request::{parse_request, PasteRequest};
+use crate::context::parse_source_url;
+use crate::image_decode::decode;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::command;
+
+/// Raw clipboard formats of one copy, keyed by their Windows format name, e.g.
+/// `CF_UNICODETEXT`, `CF_DIB` or `HTML Format`.
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ClipboardPayload {
+    pub formats: BTreeMap<String, Vec<u8>>,
+}
+
+/// What the capture parsers made of a payload.
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseReport {
+    pub name: String,
+    pub formats: Vec<String>,
+    pub text_chars: Option<usize>,
+    pub source_url: Option<String>,
+    pub image_size: Option<(u32, u32)>,
+    pub file_count: Option<usize>,
+    pub html_text_chars: Option<usize>,
+    pub request: Option<PasteRequest>,
+    pub errors: Vec<String>,
+}
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join("clipboard")
+}
+
+fn ensure_debug() -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        Ok(())
+    } else {
+        Err("Clipboard simulation is only available in development builds".to_string())
+    }
+}
+
+/// Each fixture is a directory holding one `<format name>.bin` per format.
+fn load_fixture(dir: &PathBuf) -> Result<ClipboardPayload, String> {
+    let mut payload = ClipboardPayload::default();
+
+    for entry in std::fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|extension| extension == "bin") {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+
+            payload.formats.insert(name, bytes);
+        }
+    }
+
+    Ok(payload)
+}
+
+/// Run the same parsers the capture path uses, without touching the clipboard.
+fn parse_payload(name: String, payload: &ClipboardPayload) -> ParseReport {
+    let mut report = ParseReport {
+        name,
+        formats: payload.formats.keys().cloned().collect(),
+        ..Default::default()
+    };
+
+    if let Some(bytes) = payload.formats.get("CF_UNICODETEXT") {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+
+        report.text_chars = Some(String::from_utf16_lossy(&units).chars().count());
+    }
+
+    if let Some(bytes) = payload.formats.get("HTML Format") {
+        report.source_url = parse_source_url(bytes);
+
+        match parse_cf_html(bytes) {
+            Some(html) => {
+                report.html_text_chars = Some(html_to_text(&html.fragment).chars().count())
+            }
+            None => report.errors.push("Malformed CF_HTML header".to_string()),
+        }
+    }
+
+    if let Some(bytes) = payload.formats.get("CF_HDROP") {
+        report.file_count = Some(parse_drop_files(bytes).len());
+    } else if let Some(bytes) = payload.formats.get("text/uri-list") {
+        report.file_count = Some(parse_uri_list(bytes).len());
+    }
+
+    if let Some(bytes) = payload.formats.get("ecopaste-paste") {
+        match parse_request(bytes) {
+            Ok(request) => report.request = Some(request),
+            Err(error) => report.errors.push(error),
+        }
+    }
+
+    let image = if let Some(bytes) = payload.formats.get("PNG") {
+        Some(decode(bytes.clone(), ImageFormat::Png))
+    } else if let Some(bytes) = payload
+        .formats
+        .get("CF_DIBV5")
+        .or_else(|| payload.formats.get("CF_DIB"))
+    {
+        // Same reader as the capture path, 32-bit data keeps its alpha
+        match read_dib(bytes) {
+            Ok(Some(DibImage::Rgba(image))) => Some(Ok(DynamicImage::ImageRgba8(image))),
+            Ok(Some(DibImage::Bmp(bmp))) => Some(decode(bmp, ImageFormat::Bmp)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    } else {
+        None
+    };
+
+    match image {
+        Some(Ok(image)) => report.image_size = Some((image.width(), image.height())),
+        Some(Err(error)) => report.errors.push(error),
+        None => {}
+    }
+
+    report
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::ClipboardPayload;
+    use crate::clipboard_text::set_clipboard_bytes;
+
+    extern "system" {
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn EmptyClipboard() -> i32;
+        fn RegisterClipboardFormatW(lpszFormat: *const u16) -> u32;
+    }
+
+    fn format_id(name: &str) -> u32 {
+        match name {
+            "CF_TEXT" => 1,
+            "CF_BITMAP" => 2,
+            "CF_DIB" => 8,
+            "CF_UNICODETEXT" => 13,
+            "CF_HDROP" => 15,
+            "CF_DIBV5" => 17,
+            _ => {
+                let name: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+                unsafe { RegisterClipboardFormatW(name.as_ptr()) }
+            }
+        }
+    }
+
+    /// Replace the clipboard with the raw payload, so the watcher picks it up
+    /// like a copy from the original application.
+    pub fn write_payload(payload: &ClipboardPayload) -> Result<(), String> {
+        unsafe {
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            EmptyClipboard();
+
+            let result = payload
+                .formats
+                .iter()
+                .try_for_each(|(name, bytes)| set_clipboard_bytes(format_id(name), bytes));
+
+            CloseClipboard();
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub use win::write_payload;
+
+#[cfg(not(target_os = "windows"))]
+pub fn write_payload(_payload: &ClipboardPayload) -> Result<(), String> {
+    Err("Writing raw clipboard formats is only supported on Windows".to_string())
+}
+
+// 把原始的剪贴板格式写入系统剪贴板，用于复现其它应用的复制内容
+#[command]
+pub async fn simulate_clipboard(payload: ClipboardPayload) -> Result<(), String> {
+    ensure_debug()?;
+
+    write_payload(&payload)
+}
+
+// 把指定的样本写入系统剪贴板
+#[command]
+pub async fn simulate_clipboard_fixture(name: String) -> Result<(), String> {
+    ensure_debug()?;
+
+    let dir = fixtures_dir().join(&name);
+    if !dir.starts_with(fixtures_dir()) || !dir.is_dir() {
+        return Err(format!("Clipboard fixture {} not found", name));
+    }
+
+    write_payload(&load_fixture(&dir)?)
+}
+
+// 用解析器逐个检查剪贴板样本
+#[command]
+pub async fn replay_clipboard_fixtures() -> Result<Vec<ParseReport>, String> {
+    ensure_debug()?;
+
+    let mut reports = Vec::new();
+
+    for entry in std::fs::read_dir(fixtures_dir()).map_err(|e| e.to_string())?.flatten() {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let payload = load_fixture(&dir)?;
+
+        reports.push(parse_payload(name, &payload));
+    }
+
+    reports.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> ClipboardPayload {
+        load_fixture(&fixtures_dir().join(name)).unwrap()
+    }
+
+    fn report(name: &str) -> ParseReport {
+        parse_payload(name.to_string(), &fixture(name))
+    }
+
+    fn dib(name: &str, format: &str) -> Result<Option<DibImage>, String> {
+        read_dib(&fixture(name).formats[format])
+    }
+
+    fn html(name: &str) -> String {
+        let html = parse_cf_html(&fixture(name).formats["HTML Format"]).unwrap();
+
+        html_to_text(&html.fragment)
+    }
+
+    #[test]
+    fn every_fixture_has_formats() {
+        for entry in std::fs::read_dir(fixtures_dir()).unwrap().flatten() {
+            if entry.path().is_dir() {
+                assert!(
+                    !load_fixture(&entry.path()).unwrap().formats.is_empty(),
+                    "{} has no formats",
+                    entry.file_name().to_string_lossy()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chrome_selection() {
+        let report = report("chrome-selection");
+
+        assert_eq!(report.text_chars, Some(44));
+        assert_eq!(
+            report.source_url.as_deref(),
+            Some("https://github.com/EcoPasteHub/EcoPaste")
+        );
+        assert_eq!(
+            html("chrome-selection"),
+            "EcoPaste is an open source clipboard manager"
+        );
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn excel_range() {
+        let report = report("excel-range");
+
+        assert_eq!(report.text_chars, Some(21));
+        assert_eq!(report.source_url, None);
+        assert!(html("excel-range").contains("北京\t1,024"));
+        assert!(matches!(
+            dib("excel-range", "CF_DIB"),
+            Ok(Some(DibImage::Bmp(_)))
+        ));
+        assert_eq!(report.image_size, Some((4, 2)));
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn explorer_files() {
+        let files = parse_drop_files(&fixture("explorer-files").formats["CF_HDROP"]);
+
+        assert_eq!(
+            files,
+            [
+                r"C:\Users\Public\Documents\report.docx",
+                r"C:\Users\Public\Pictures\photo 1.jpg",
+                r"C:\Users\Public\Music",
+            ]
+        );
+        assert_eq!(report("explorer-files").file_count, Some(3));
+    }
+
+    #[test]
+    fn firefox_image() {
+        let report = report("firefox-image");
+
+        assert_eq!(report.source_url.as_deref(), Some("https://example.com/"));
+        assert_eq!(report.image_size, Some((2, 2)));
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn photoshop_layer() {
+        match dib("photoshop-layer", "CF_DIBV5") {
+            Ok(Some(DibImage::Rgba(image))) => assert_eq!(image.dimensions(), (3, 3)),
+            _ => panic!("32-bit DIBV5 was not read as RGBA"),
+        }
+
+        assert_eq!(report("photoshop-layer").image_size, Some((3, 3)));
+    }
+
+    #[test]
+    fn tagged_snippet() {
+        let report = report("tagged-snippet");
+
+        assert_eq!(report.text_chars, Some(43));
+        assert_eq!(
+            report.request,
+            Some(PasteRequest::Store {
+                tags: vec!["sql".to_string(), "work".to_string()],
+                note: Some("Open orders query".to_string()),
+                favorite: true,
+            })
+        );
+    }
+
+    #[test]
+    fn wechat_screenshot() {
+        let report = report("wechat-screenshot");
+
+        assert_eq!(report.image_size, Some((5, 4)));
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn truncated_dib_is_rejected() {
+        let report = report("truncated-dib");
+
+        assert_eq!(report.image_size, None);
+        assert!(!report.errors.is_empty());
+    }
+
+    #[test]
+    fn oversized_dib_is_rejected() {
+        assert!(dib("oversized-dib", "CF_DIB").is_err());
+
+        let report = report("oversized-dib");
+        assert_eq!(report.image_size, None);
+        assert!(!report.errors.is_empty());
+    }
+
+    #[test]
+    fn wechat_bogus_palette_is_rejected() {
+        assert!(matches!(dib("wechat-bogus-palette", "CF_DIB"), Ok(None)));
+
+        let report = report("wechat-bogus-palette");
+        assert_eq!(report.image_size, None);
+        assert!(report.errors.is_empty());
+    }
+}
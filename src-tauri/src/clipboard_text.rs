@@ -0,0 +1,431 @@
+use crate::core::cache;
+use crate::sensitive::{classify, SensitiveKind};
+use crate::vault;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Component, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Texts larger than this (in UTF-8 bytes) are not inlined into history.
+pub const DEFAULT_MAX_INLINE_BYTES: usize = 1024 * 1024;
+
+/// Number of characters kept inline as a preview of an overflowed text.
+const PREVIEW_CHARS: usize = 2048;
+
+static MAX_INLINE_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_INLINE_BYTES);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadTextResult {
+    /// The full text, or only a preview when `blob_path` is set.
+    pub value: String,
+    /// Character count of the full text.
+    pub count: usize,
+    /// Blob file holding the full text when it exceeded the inline limit.
+    pub blob_path: Option<String>,
+    /// What kind of secret the text looks like, overflowed texts are never
+    /// classified.
+    pub sensitive: Option<SensitiveKind>,
+}
+
+pub fn max_inline_bytes() -> usize {
+    MAX_INLINE_BYTES.load(Ordering::Relaxed)
+}
+
+pub fn get_blobs_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("blobs")
+}
+
+/// Where an overflowed text goes until it is complete. In vault mode it is
+/// kept in memory and only written encrypted, a plain temporary file would
+/// leave it on disk.
+enum Sink {
+    File {
+        tmp_path: PathBuf,
+        writer: BufWriter<File>,
+    },
+    Memory(Vec<u8>),
+}
+
+/// Streams text chunks into a blob file while keeping a short inline preview.
+struct OverflowWriter {
+    dir: PathBuf,
+    sink: Sink,
+    hasher: Sha256,
+    preview: String,
+    count: usize,
+}
+
+impl OverflowWriter {
+    fn new(dir: PathBuf) -> Result<Self, String> {
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let sink = if vault::is_enabled() {
+            Sink::Memory(Vec::new())
+        } else {
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            let tmp_path = dir.join(format!("{}.part", nanos));
+            let file = File::create(&tmp_path).map_err(|e| e.to_string())?;
+
+            Sink::File {
+                tmp_path,
+                writer: BufWriter::new(file),
+            }
+        };
+
+        Ok(Self {
+            dir,
+            sink,
+            hasher: Sha256::new(),
+            preview: String::new(),
+            count: 0,
+        })
+    }
+
+    fn push(&mut self, chunk: &str) -> Result<(), String> {
+        for ch in chunk.chars() {
+            if self.count < PREVIEW_CHARS {
+                self.preview.push(ch);
+            }
+            self.count += 1;
+        }
+
+        self.hasher.update(chunk.as_bytes());
+
+        match &mut self.sink {
+            Sink::File { writer, .. } => writer
+                .write_all(chunk.as_bytes())
+                .map_err(|e| e.to_string()),
+            Sink::Memory(bytes) => {
+                bytes.extend_from_slice(chunk.as_bytes());
+
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self) -> Result<ReadTextResult, String> {
+        // Keyed in vault mode, the name must not confirm guessed content
+        let name = vault::content_hash(&self.hasher.finalize());
+        let path = self.dir.join(format!("{}.txt", name));
+
+        // Identical content was already stored, keep the existing blob
+        match self.sink {
+            Sink::File {
+                tmp_path,
+                mut writer,
+            } => {
+                writer.flush().map_err(|e| e.to_string())?;
+                drop(writer);
+
+                if path.exists() {
+                    let _ = std::fs::remove_file(&tmp_path);
+                } else {
+                    std::fs::rename(&tmp_path, &path).map_err(|e| e.to_string())?;
+                }
+            }
+            Sink::Memory(bytes) => {
+                if !path.exists() {
+                    vault::write_file(&path, &bytes)?;
+                }
+            }
+        }
+
+        Ok(ReadTextResult {
+            value: self.preview,
+            count: self.count,
+            blob_path: Some(path.to_string_lossy().to_string()),
+            sensitive: None,
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::{classify, get_blobs_dir, max_inline_bytes, OverflowWriter, ReadTextResult};
+    use tauri::{AppHandle, Runtime};
+
+    const CF_UNICODETEXT: u32 = 13;
+    const GMEM_MOVEABLE: u32 = 0x0002;
+
+    /// UTF-16 code units converted per chunk when streaming to a blob.
+    const CHUNK_UNITS: usize = 64 * 1024;
+
+    extern "system" {
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn EmptyClipboard() -> i32;
+        fn GetClipboardData(uFormat: u32) -> *mut std::ffi::c_void;
+        fn SetClipboardData(uFormat: u32, hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn IsClipboardFormatAvailable(format: u32) -> i32;
+        fn GlobalAlloc(uFlags: u32, dwBytes: usize) -> *mut std::ffi::c_void;
+        fn GlobalFree(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
+        fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+    }
+
+    /// Read CF_UNICODETEXT, streaming it to a blob file when it is larger
+    /// than the inline limit instead of materializing one huge string.
+    pub fn read_text<R: Runtime>(
+        app_handle: &AppHandle<R>,
+    ) -> Result<Option<ReadTextResult>, String> {
+        unsafe {
+            if IsClipboardFormatAvailable(CF_UNICODETEXT) == 0 {
+                return Ok(None);
+            }
+
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            let result = read_text_inner(app_handle);
+            CloseClipboard();
+            result
+        }
+    }
+
+    unsafe fn read_text_inner<R: Runtime>(
+        app_handle: &AppHandle<R>,
+    ) -> Result<Option<ReadTextResult>, String> {
+        let handle = GetClipboardData(CF_UNICODETEXT);
+        if handle.is_null() {
+            return Ok(None);
+        }
+
+        let data = GlobalLock(handle);
+        if data.is_null() {
+            return Ok(None);
+        }
+
+        let units = std::slice::from_raw_parts(data as *const u16, GlobalSize(handle) / 2);
+        let len = units.iter().position(|&unit| unit == 0).unwrap_or(units.len());
+        let units = &units[..len];
+
+        // A UTF-16 unit never expands to more than 3 UTF-8 bytes
+        let result = if len * 3 <= max_inline_bytes() {
+            let text = String::from_utf16_lossy(units);
+            Ok(Some(ReadTextResult {
+                count: text.chars().count(),
+                sensitive: classify(&text),
+                value: text,
+                blob_path: None,
+            }))
+        } else {
+            stream_to_blob(app_handle, units).map(Some)
+        };
+
+        GlobalUnlock(handle);
+        result
+    }
+
+    /// Read at most `max_units` of CF_UNICODETEXT into memory, nothing is
+    /// written to disk.
+    pub fn peek_text(max_units: usize) -> Result<Option<String>, String> {
+        unsafe {
+            if IsClipboardFormatAvailable(CF_UNICODETEXT) == 0 {
+                return Ok(None);
+            }
+
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            let mut text = None;
+            let handle = GetClipboardData(CF_UNICODETEXT);
+            if !handle.is_null() {
+                let data = GlobalLock(handle);
+                if !data.is_null() {
+                    let units =
+                        std::slice::from_raw_parts(data as *const u16, GlobalSize(handle) / 2);
+                    let len = units.iter().position(|&unit| unit == 0).unwrap_or(units.len());
+
+                    text = Some(String::from_utf16_lossy(&units[..len.min(max_units)]));
+                    GlobalUnlock(handle);
+                }
+            }
+
+            CloseClipboard();
+            Ok(text)
+        }
+    }
+
+    fn stream_to_blob<R: Runtime>(
+        app_handle: &AppHandle<R>,
+        units: &[u16],
+    ) -> Result<ReadTextResult, String> {
+        let mut writer = OverflowWriter::new(get_blobs_dir(app_handle))?;
+        let mut start = 0;
+
+        while start < units.len() {
+            let mut end = (start + CHUNK_UNITS).min(units.len());
+
+            // Never split a surrogate pair across two chunks
+            if end < units.len() && (0xD800..0xDC00).contains(&units[end - 1]) {
+                end -= 1;
+            }
+
+            writer.push(&String::from_utf16_lossy(&units[start..end]))?;
+            start = end;
+        }
+
+        writer.finish()
+    }
+
+    pub fn write_text(text: &str) -> Result<(), String> {
+        unsafe {
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return Err("Failed to open clipboard".to_string());
+            }
+
+            EmptyClipboard();
+            let result = set_unicode_text(text);
+            CloseClipboard();
+            result
+        }
+    }
+
+    /// Place UTF-16 text on the already opened clipboard.
+    pub unsafe fn set_unicode_text(text: &str) -> Result<(), String> {
+        let bytes: Vec<u8> = text
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+
+        set_clipboard_bytes(CF_UNICODETEXT, &bytes)
+    }
+
+    /// Place raw bytes on the already opened clipboard under `format`.
+    pub unsafe fn set_clipboard_bytes(format: u32, bytes: &[u8]) -> Result<(), String> {
+        let handle = GlobalAlloc(GMEM_MOVEABLE, bytes.len());
+        if handle.is_null() {
+            return Err("Failed to allocate clipboard memory".to_string());
+        }
+
+        let data = GlobalLock(handle);
+        if data.is_null() {
+            GlobalFree(handle);
+            return Err("Failed to lock clipboard memory".to_string());
+        }
+
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), data as *mut u8, bytes.len());
+        GlobalUnlock(handle);
+
+        if SetClipboardData(format, handle).is_null() {
+            GlobalFree(handle);
+            return Err("Failed to set clipboard data".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod other {
+    use super::{classify, get_blobs_dir, max_inline_bytes, OverflowWriter, ReadTextResult};
+    use arboard::Clipboard;
+    use tauri::{AppHandle, Runtime};
+
+    /// Applies the inline limit to an already materialized string.
+    fn inline_or_overflow<R: Runtime>(
+        app_handle: &AppHandle<R>,
+        text: String,
+    ) -> Result<ReadTextResult, String> {
+        if text.len() <= max_inline_bytes() {
+            return Ok(ReadTextResult {
+                count: text.chars().count(),
+                sensitive: classify(&text),
+                value: text,
+                blob_path: None,
+            });
+        }
+
+        let mut writer = OverflowWriter::new(get_blobs_dir(app_handle))?;
+        writer.push(&text)?;
+        writer.finish()
+    }
+
+    pub fn read_text<R: Runtime>(
+        app_handle: &AppHandle<R>,
+    ) -> Result<Option<ReadTextResult>, String> {
+        let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+        match clipboard.get_text() {
+            Ok(text) => inline_or_overflow(app_handle, text).map(Some),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// arboard can only read the whole text, it is cut afterwards.
+    pub fn peek_text(max_units: usize) -> Result<Option<String>, String> {
+        let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+        match clipboard.get_text() {
+            Ok(text) => Ok(Some(text.chars().take(max_units).collect())),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn write_text(text: &str) -> Result<(), String> {
+        let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+        clipboard.set_text(text).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub use win::{peek_text, read_text, set_clipboard_bytes, set_unicode_text, write_text};
+
+#[cfg(not(target_os = "windows"))]
+pub use other::{peek_text, read_text, write_text};
+
+#[tauri::command]
+pub async fn read_clipboard_text<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<Option<ReadTextResult>, String> {
+    read_text(&app_handle)
+}
+
+/// Explicitly load the full content of an overflowed text.
+#[tauri::command]
+pub async fn load_full_text<R: Runtime>(
+    app_handle: AppHandle<R>,
+    blob_path: String,
+) -> Result<String, String> {
+    let path = PathBuf::from(blob_path);
+
+    // Only blobs written by the capture path may be loaded, `..` would
+    // still pass the prefix check
+    if !path.starts_with(get_blobs_dir(&app_handle))
+        || path
+            .components()
+            .any(|component| component == Component::ParentDir)
+    {
+        return Err("Blob path is outside of the blobs directory".to_string());
+    }
+
+    let key = path.to_string_lossy().to_string();
+    if let Some(text) = cache::get_string(cache::PREVIEWS, &key) {
+        return Ok(text);
+    }
+
+    let text = String::from_utf8(vault::read_file(&path)?).map_err(|e| e.to_string())?;
+    cache::put_string(cache::PREVIEWS, key, &text);
+
+    Ok(text)
+}
+
+#[tauri::command]
+pub async fn set_max_text_capture_size(max_bytes: usize) {
+    MAX_INLINE_BYTES.store(max_bytes.max(PREVIEW_CHARS * 4), Ordering::Relaxed);
+}
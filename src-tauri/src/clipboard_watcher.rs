@@ -0,0 +1,645 @@
+use crate::capture_denylist::is_denied;
+use crate::clipboard_request::{read_request, PasteRequest, REQUEST_FORMAT};
+use crate::context::{source_app_snapshot, SourceApp};
+use crate::excluded_apps::{is_excluded_source, source_app};
+use crate::screenshot::{detect_screenshot, ScreenshotInfo};
+use crate::vm_clipboard::{self, VmSource};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+/// Emitted on every clipboard change with the formats now available.
+pub const CHANGED_EVENT: &str = "clipboard://changed";
+
+/// Formats password managers add to copies that must never be recorded, the
+/// Windows markers and the nspasteboard.org concealed type on macOS.
+const SENSITIVE_FORMATS: &[&str] = &[
+    "ExcludeClipboardContentFromMonitorProcessing",
+    "Clipboard Viewer Ignore",
+    "org.nspasteboard.ConcealedType",
+];
+
+/// Holds a DWORD, zero keeps the copy out of the Windows clipboard history.
+#[cfg(target_os = "windows")]
+const CAN_INCLUDE_FORMAT: &str = "CanIncludeInClipboardHistory";
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static WATCHING: AtomicBool = AtomicBool::new(true);
+/// Set while the system Do Not Disturb mode pauses capture.
+static FOCUS_PAUSED: AtomicBool = AtomicBool::new(false);
+/// Set while the screen is locked or another user's session is active.
+static SESSION_PAUSED: AtomicBool = AtomicBool::new(false);
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+static LAST_CHANGE: Mutex<Option<Instant>> = Mutex::new(None);
+static OWN_WRITE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// A change this soon after [`mark_own_write`] is attributed to EcoPaste.
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Best-effort explanation of a clipboard change.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeCause {
+    /// Process owning the clipboard, the foreground app where the platform
+    /// does not tell the owner.
+    pub source_app: Option<String>,
+    /// EcoPaste wrote the clipboard itself, e.g. to paste an item.
+    pub own_write: bool,
+    /// The clipboard was emptied, no format is left.
+    pub cleared: bool,
+    /// Milliseconds since the previous change, absent for the first one.
+    pub elapsed_ms: Option<u64>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardChange {
+    /// Increases by one per change seen by this process.
+    pub sequence: u64,
+    /// Format names, `CF_*` and registered names on Windows, UTIs on macOS
+    /// and MIME types on Linux.
+    pub formats: Vec<String>,
+    /// Request the copying app attached in the `ecopaste-paste` format.
+    pub request: Option<PasteRequest>,
+    pub cause: ChangeCause,
+    /// Virtual machine the copy was bridged from.
+    pub vm: Option<VmSource>,
+    /// App the copy was made in, absent for own writes and bridged copies.
+    pub source: Option<SourceApp>,
+    /// Set when the copy is an image taken by an OS screenshot tool.
+    pub screenshot: Option<ScreenshotInfo>,
+}
+
+/// Whether the copying app marked the content as sensitive.
+fn is_sensitive(formats: &[String]) -> bool {
+    if formats
+        .iter()
+        .any(|format| SENSITIVE_FORMATS.contains(&format.as_str()))
+    {
+        return true;
+    }
+
+    // An unreadable value counts as a refusal, better to miss a copy than
+    // to record a password
+    #[cfg(target_os = "windows")]
+    if formats.iter().any(|format| format == CAN_INCLUDE_FORMAT) {
+        return win::can_include_in_history() != Some(true);
+    }
+
+    false
+}
+
+/// Whether EcoPaste itself wrote the current content. Windows tells the
+/// clipboard owner, elsewhere the write has to be marked.
+fn is_own_write(source_app: Option<&str>) -> bool {
+    let marked = OWN_WRITE
+        .lock()
+        .unwrap()
+        .take()
+        .is_some_and(|at| at.elapsed() < OWN_WRITE_WINDOW);
+
+    if marked {
+        return true;
+    }
+
+    #[cfg(target_os = "windows")]
+    if let Some(source_app) = source_app {
+        return std::env::current_exe().is_ok_and(|exe| {
+            exe.file_name()
+                .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(source_app))
+        });
+    }
+
+    let _ = source_app;
+
+    false
+}
+
+fn change_cause(formats: &[String]) -> ChangeCause {
+    let now = Instant::now();
+    let elapsed_ms = LAST_CHANGE
+        .lock()
+        .unwrap()
+        .replace(now)
+        .map(|previous| now.duration_since(previous).as_millis() as u64);
+
+    let source_app = source_app();
+
+    ChangeCause {
+        own_write: is_own_write(source_app.as_deref()),
+        source_app,
+        cleared: formats.is_empty(),
+        elapsed_ms,
+    }
+}
+
+/// Mark the next clipboard change as EcoPaste's own write.
+pub fn mark_own_write() {
+    *OWN_WRITE.lock().unwrap() = Some(Instant::now());
+}
+
+fn emit_change<R: Runtime>(app_handle: &AppHandle<R>, formats: Vec<String>) {
+    let sequence = SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1;
+    let cause = change_cause(&formats);
+
+    if !WATCHING.load(Ordering::Relaxed)
+        || FOCUS_PAUSED.load(Ordering::Relaxed)
+        || SESSION_PAUSED.load(Ordering::Relaxed)
+    {
+        return;
+    }
+
+    // Marked as sensitive or copied from an app the user excluded, such as
+    // a password manager
+    if is_sensitive(&formats) || is_excluded_source() {
+        return;
+    }
+
+    // Content the user never wants stored, e.g. classified markers
+    if is_denied() {
+        return;
+    }
+
+    let request = formats
+        .iter()
+        .any(|format| format == REQUEST_FORMAT)
+        .then(read_request)
+        .flatten();
+
+    // The copying app asked for this copy not to be recorded
+    if request == Some(PasteRequest::Ignore) {
+        return;
+    }
+
+    let vm = vm_clipboard::detect();
+
+    if vm.as_ref().is_some_and(vm_clipboard::is_excluded) {
+        return;
+    }
+
+    // The host app of a bridged copy is the virtual machine window
+    let source = if cause.own_write || cause.cleared || vm.is_some() {
+        None
+    } else {
+        source_app_snapshot(app_handle)
+    };
+
+    let screenshot = if cause.own_write || vm.is_some() {
+        None
+    } else {
+        detect_screenshot(&formats, cause.source_app.as_deref())
+    };
+
+    let _ = app_handle.emit(
+        CHANGED_EVENT,
+        ClipboardChange {
+            sequence,
+            formats,
+            request,
+            cause,
+            vm,
+            source,
+            screenshot,
+        },
+    );
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::{emit_change, CAN_INCLUDE_FORMAT};
+    use std::mem;
+    use std::ptr;
+    use std::sync::atomic::{AtomicIsize, Ordering};
+    use tauri::{AppHandle, Runtime};
+
+    /// Parent of message-only windows.
+    const HWND_MESSAGE: isize = -3;
+    const WM_CLIPBOARDUPDATE: u32 = 0x031D;
+    /// WM_APP, asks the watcher thread to register its listener again.
+    const WM_REREGISTER: u32 = 0x8000;
+
+    /// The watcher's window, zero while it is not running.
+    static WINDOW: AtomicIsize = AtomicIsize::new(0);
+
+    #[repr(C)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[repr(C)]
+    struct Msg {
+        hwnd: *mut std::ffi::c_void,
+        message: u32,
+        w_param: usize,
+        l_param: isize,
+        time: u32,
+        pt: Point,
+    }
+
+    extern "system" {
+        fn CreateWindowExW(
+            dwExStyle: u32,
+            lpClassName: *const u16,
+            lpWindowName: *const u16,
+            dwStyle: u32,
+            x: i32,
+            y: i32,
+            nWidth: i32,
+            nHeight: i32,
+            hWndParent: isize,
+            hMenu: *mut std::ffi::c_void,
+            hInstance: *mut std::ffi::c_void,
+            lpParam: *mut std::ffi::c_void,
+        ) -> *mut std::ffi::c_void;
+        fn DestroyWindow(hWnd: *mut std::ffi::c_void) -> i32;
+        fn AddClipboardFormatListener(hwnd: *mut std::ffi::c_void) -> i32;
+        fn RemoveClipboardFormatListener(hwnd: *mut std::ffi::c_void) -> i32;
+        fn PostMessageW(
+            hWnd: *mut std::ffi::c_void,
+            Msg: u32,
+            wParam: usize,
+            lParam: isize,
+        ) -> i32;
+        fn GetMessageW(
+            lpMsg: *mut Msg,
+            hWnd: *mut std::ffi::c_void,
+            wMsgFilterMin: u32,
+            wMsgFilterMax: u32,
+        ) -> i32;
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn EnumClipboardFormats(format: u32) -> u32;
+        fn GetClipboardFormatNameW(format: u32, lpszFormatName: *mut u16, cchMaxCount: i32) -> i32;
+        fn RegisterClipboardFormatW(lpszFormat: *const u16) -> u32;
+        fn GetClipboardData(uFormat: u32) -> *mut std::ffi::c_void;
+        fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
+        fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+    }
+
+    /// Names of the predefined formats, matching the fixture file names.
+    fn standard_format_name(format: u32) -> Option<&'static str> {
+        Some(match format {
+            1 => "CF_TEXT",
+            2 => "CF_BITMAP",
+            7 => "CF_OEMTEXT",
+            8 => "CF_DIB",
+            13 => "CF_UNICODETEXT",
+            14 => "CF_ENHMETAFILE",
+            15 => "CF_HDROP",
+            16 => "CF_LOCALE",
+            17 => "CF_DIBV5",
+            _ => return None,
+        })
+    }
+
+    fn format_name(format: u32) -> String {
+        if let Some(name) = standard_format_name(format) {
+            return name.to_string();
+        }
+
+        let mut buffer = [0u16; 256];
+        let len = unsafe { GetClipboardFormatNameW(format, buffer.as_mut_ptr(), buffer.len() as i32) };
+        if len > 0 {
+            String::from_utf16_lossy(&buffer[..len as usize])
+        } else {
+            format!("#{}", format)
+        }
+    }
+
+    pub fn available_formats() -> Vec<String> {
+        let mut formats = Vec::new();
+
+        unsafe {
+            // The owner may still be writing, it is fine to report nothing
+            if OpenClipboard(ptr::null_mut()) == 0 {
+                return formats;
+            }
+
+            let mut format = EnumClipboardFormats(0);
+            while format != 0 {
+                formats.push(format_name(format));
+                format = EnumClipboardFormats(format);
+            }
+
+            CloseClipboard();
+        }
+
+        formats
+    }
+
+    /// Value of the CanIncludeInClipboardHistory DWORD, `None` when it is
+    /// missing or the clipboard is busy.
+    pub fn can_include_in_history() -> Option<bool> {
+        let name: Vec<u16> = format!("{}\0", CAN_INCLUDE_FORMAT).encode_utf16().collect();
+
+        unsafe {
+            let format = RegisterClipboardFormatW(name.as_ptr());
+            if OpenClipboard(ptr::null_mut()) == 0 {
+                return None;
+            }
+
+            let mut value = None;
+            let handle = GetClipboardData(format);
+            if !handle.is_null() && GlobalSize(handle) >= mem::size_of::<u32>() {
+                let data = GlobalLock(handle);
+                if !data.is_null() {
+                    value = Some(ptr::read_unaligned(data as *const u32) != 0);
+                    GlobalUnlock(handle);
+                }
+            }
+
+            CloseClipboard();
+            value
+        }
+    }
+
+    /// WM_CLIPBOARDUPDATE is posted, so a message-only window of a system
+    /// class is enough, no window procedure of our own is needed.
+    pub fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+        let class: Vec<u16> = "STATIC\0".encode_utf16().collect();
+
+        unsafe {
+            let hwnd = CreateWindowExW(
+                0,
+                class.as_ptr(),
+                ptr::null(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+
+            if hwnd.is_null() || AddClipboardFormatListener(hwnd) == 0 {
+                log::error!("Failed to start the clipboard watcher");
+                return;
+            }
+
+            WINDOW.store(hwnd as isize, Ordering::SeqCst);
+
+            let mut message: Msg = mem::zeroed();
+            while GetMessageW(&mut message, hwnd, 0, 0) > 0 {
+                match message.message {
+                    WM_CLIPBOARDUPDATE => emit_change(&app_handle, available_formats()),
+                    WM_REREGISTER => {
+                        RemoveClipboardFormatListener(hwnd);
+
+                        if AddClipboardFormatListener(hwnd) == 0 {
+                            log::error!("Failed to register the clipboard listener again");
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            WINDOW.store(0, Ordering::SeqCst);
+            RemoveClipboardFormatListener(hwnd);
+            DestroyWindow(hwnd);
+        }
+    }
+
+    /// The listener must be registered from the thread owning the window.
+    pub fn reregister() {
+        let hwnd = WINDOW.load(Ordering::SeqCst);
+
+        if hwnd != 0 {
+            unsafe { PostMessageW(hwnd as *mut std::ffi::c_void, WM_REREGISTER, 0, 0) };
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use super::emit_change;
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSAutoreleasePool, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::thread;
+    use std::time::Duration;
+    use tauri::{AppHandle, Runtime};
+
+    /// NSPasteboard has no change notification, its changeCount is cheap to
+    /// read and polling it is what every clipboard manager on macOS does.
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+    unsafe fn general_pasteboard() -> id {
+        msg_send![class!(NSPasteboard), generalPasteboard]
+    }
+
+    fn change_count() -> i64 {
+        unsafe { msg_send![general_pasteboard(), changeCount] }
+    }
+
+    pub fn available_formats() -> Vec<String> {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let types: id = msg_send![general_pasteboard(), types];
+
+            let mut formats = Vec::new();
+            if types != nil {
+                for index in 0..types.count() {
+                    let name = types.objectAtIndex(index).UTF8String();
+                    if !name.is_null() {
+                        formats.push(std::ffi::CStr::from_ptr(name).to_string_lossy().into_owned());
+                    }
+                }
+            }
+
+            pool.drain();
+            formats
+        }
+    }
+
+    pub fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+        let mut last = change_count();
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let current = change_count();
+            if current != last {
+                last = current;
+                emit_change(&app_handle, available_formats());
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::emit_change;
+    use crate::clipboard_image::available_targets;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use tauri::{AppHandle, Runtime};
+    use x11::xfixes::{
+        XFixesQueryExtension, XFixesSelectSelectionInput, XFixesSetSelectionOwnerNotifyMask,
+    };
+    use x11::xlib::{
+        self, XCloseDisplay, XCreateSimpleWindow, XDefaultRootWindow, XEvent, XInternAtom,
+        XNextEvent, XOpenDisplay,
+    };
+
+    /// XFixesSelectionNotify relative to the extension's event base.
+    const XFIXES_SELECTION_NOTIFY: i32 = 0;
+
+    pub fn available_formats() -> Vec<String> {
+        available_targets()
+    }
+
+    /// wl-paste runs the given command on every change, `None` means it is
+    /// not installed.
+    fn watch_wayland<R: Runtime>(app_handle: &AppHandle<R>) -> Option<()> {
+        let mut child = Command::new("wl-paste")
+            .args(["--watch", "echo"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        let stdout = child.stdout.take()?;
+
+        // wl-paste fires once for the current content right away
+        for _ in BufReader::new(stdout).lines().skip(1) {
+            emit_change(app_handle, available_formats());
+        }
+
+        let _ = child.wait();
+        Some(())
+    }
+
+    /// XFixes reports selection owner changes, which is every copy.
+    unsafe fn watch_x11<R: Runtime>(app_handle: &AppHandle<R>) {
+        let display = XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            log::error!("Failed to open the X display for the clipboard watcher");
+            return;
+        }
+
+        let mut event_base = 0;
+        let mut error_base = 0;
+        if XFixesQueryExtension(display, &mut event_base, &mut error_base) == 0 {
+            log::error!("XFixes is not available, clipboard changes will not be watched");
+            XCloseDisplay(display);
+            return;
+        }
+
+        let root = XDefaultRootWindow(display);
+        let window = XCreateSimpleWindow(display, root, 0, 0, 1, 1, 0, 0, 0);
+        let clipboard = XInternAtom(display, b"CLIPBOARD\0".as_ptr() as _, xlib::False);
+
+        XFixesSelectSelectionInput(
+            display,
+            window,
+            clipboard,
+            XFixesSetSelectionOwnerNotifyMask as _,
+        );
+
+        let mut event: XEvent = std::mem::zeroed();
+        loop {
+            XNextEvent(display, &mut event);
+
+            if event.get_type() == event_base + XFIXES_SELECTION_NOTIFY {
+                emit_change(app_handle, available_formats());
+            }
+        }
+    }
+
+    pub fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() && watch_wayland(&app_handle).is_some() {
+            return;
+        }
+
+        // X11, or XWayland when wl-clipboard is missing
+        unsafe { watch_x11(&app_handle) };
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::{available_formats, watch};
+
+#[cfg(target_os = "macos")]
+use mac::{available_formats, watch};
+
+#[cfg(target_os = "linux")]
+use linux::{available_formats, watch};
+
+/// Start watching the clipboard on a dedicated thread, unless it is already
+/// running. The watchers only return when the platform gives up on them.
+pub fn start<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let spawned = thread::Builder::new()
+        .name("clipboard-watcher".to_string())
+        .spawn(move || {
+            watch(app_handle);
+
+            STARTED.store(false, Ordering::SeqCst);
+            log::warn!("The clipboard watcher stopped");
+        });
+
+    if let Err(error) = spawned {
+        STARTED.store(false, Ordering::SeqCst);
+        log::error!("Failed to spawn the clipboard watcher: {}", error);
+    }
+}
+
+/// Pause capture on behalf of the system Do Not Disturb mode, independent of
+/// the user's own toggle.
+pub fn set_focus_paused(paused: bool) {
+    FOCUS_PAUSED.store(paused, Ordering::Relaxed);
+}
+
+/// Pause capture while the session is locked or switched away. Changes seen
+/// before the pause say nothing about the ones after it.
+pub fn set_session_paused(paused: bool) {
+    SESSION_PAUSED.store(paused, Ordering::Relaxed);
+
+    if !paused {
+        LAST_CHANGE.lock().unwrap().take();
+        OWN_WRITE.lock().unwrap().take();
+    }
+}
+
+/// Make sure the watcher still runs and is registered after a resume from
+/// sleep. Elapsed times measured across the sleep mean nothing.
+pub fn revalidate<R: Runtime>(app_handle: &AppHandle<R>) {
+    LAST_CHANGE.lock().unwrap().take();
+
+    #[cfg(target_os = "windows")]
+    win::reregister();
+
+    start(app_handle);
+}
+
+// 开启或暂停剪贴板监听
+#[command]
+pub async fn set_clipboard_watching(enabled: bool) {
+    WATCHING.store(enabled, Ordering::Relaxed);
+}
+
+// 标记下一次剪贴板变化是 EcoPaste 自己写入的
+#[command]
+pub async fn mark_clipboard_write() {
+    mark_own_write();
+}
+
+// 获取剪贴板中当前可用的格式
+#[command]
+pub async fn get_clipboard_formats() -> Vec<String> {
+    available_formats()
+}
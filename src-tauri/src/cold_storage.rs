@@ -0,0 +1,261 @@
+use crate::clipboard_image::{get_thumbnails_dir, resolve_image_path};
+use crate::history::{
+    get_metadata, local_time, older_than, set_metadata, store, with_connection, HistoryRow,
+    ACCESS_COUNT, COLD_STORAGE, LAST_ACCESS,
+};
+use crate::image_decode::decode_file;
+use crate::vault;
+use image::ImageFormat;
+use rusqlite::{params, OptionalExtension};
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Runtime};
+
+/// Value of [`COLD_STORAGE`] for images recompressed in place, moved images
+/// keep the path they were moved to.
+pub const RECOMPRESSED: &str = "recompressed";
+
+/// When images go cold and what happens to them, applied by the retention
+/// task. Favorites never go cold.
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ColdStoragePolicy {
+    /// Days since the image was copied and last accessed.
+    pub after_days: u32,
+    /// Images accessed more often than this stay untouched.
+    #[serde(default)]
+    pub max_access_count: u32,
+    /// Directory cold images are moved to, e.g. on an external drive. Images
+    /// are recompressed in place when unset.
+    pub location: Option<String>,
+}
+
+/// Image items that went cold under the policy and were not handled yet.
+fn cold_images(policy: &ColdStoragePolicy) -> Result<Vec<(String, String)>, String> {
+    with_connection(|connection| {
+        let cutoff: String = connection.query_row(
+            "SELECT datetime('now', 'localtime', ?1)",
+            params![format!("-{} days", policy.after_days)],
+            |row| row.get(0),
+        )?;
+
+        let mut statement = connection.prepare(&format!(
+            "SELECT id, value FROM history
+            WHERE type = 'image' AND favorite = 0 AND {copied}
+            AND NOT EXISTS (
+                SELECT 1 FROM history_metadata WHERE item_id = history.id AND key = '{cold}'
+            )
+            AND coalesce((
+                SELECT value FROM history_metadata WHERE item_id = history.id AND key = '{last}'
+            ), '') < ?1
+            AND coalesce((
+                SELECT CAST(value AS INTEGER) FROM history_metadata
+                WHERE item_id = history.id AND key = '{count}'
+            ), 0) <= ?2",
+            copied = older_than(policy.after_days as u64 * 24 * 60 * 60),
+            cold = COLD_STORAGE,
+            last = LAST_ACCESS,
+            count = ACCESS_COUNT,
+        ))?;
+
+        let rows = statement.query_map(params![cutoff, policy.max_access_count], |row| {
+            Ok((
+                row.get(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            ))
+        })?;
+
+        rows.collect()
+    })
+}
+
+/// Rename, or copy and delete when the destination is on another drive.
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    std::fs::copy(from, to).map_err(|e| e.to_string())?;
+    std::fs::remove_file(from).map_err(|e| e.to_string())
+}
+
+/// Re-encode a PNG as lossless WebP, which is a good deal smaller than
+/// even the best PNG compression `optimize_storage` applies. The item is
+/// pointed at the new file, thumbnails are found by name and keep working.
+fn recompress<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    id: &str,
+    value: &str,
+) -> Result<bool, String> {
+    let path = resolve_image_path(app_handle, value);
+
+    // Animated GIFs would lose their frames, marked so they are not looked
+    // at again
+    if ImageFormat::from_path(&path).ok() != Some(ImageFormat::Png) {
+        set_metadata(id, COLD_STORAGE, RECOMPRESSED)?;
+
+        return Ok(false);
+    }
+
+    let original = std::fs::metadata(&path).map_err(|e| e.to_string())?.len();
+    let image = decode_file(&path)?.to_rgba8();
+
+    let mut encoded = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut encoded), ImageFormat::WebP)
+        .map_err(|e| format!("Failed to encode WebP: {}", e))?;
+
+    if encoded.len() as u64 >= original {
+        set_metadata(id, COLD_STORAGE, RECOMPRESSED)?;
+
+        return Ok(false);
+    }
+
+    let webp_value = Path::new(value)
+        .with_extension("webp")
+        .to_string_lossy()
+        .to_string();
+
+    vault::write_file(&resolve_image_path(app_handle, &webp_value), &encoded)?;
+    store().update_row(
+        id,
+        &HistoryRow::from([("value".to_string(), Value::from(webp_value))]),
+    )?;
+    set_metadata(id, COLD_STORAGE, RECOMPRESSED)?;
+
+    let _ = std::fs::remove_file(path);
+
+    Ok(true)
+}
+
+/// Move the image to the cold location. The list shows thumbnails, so only
+/// images that have one are moved, the others are recompressed instead.
+fn move_cold<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    id: &str,
+    value: &str,
+    location: &Path,
+) -> Result<bool, String> {
+    let path = resolve_image_path(app_handle, value);
+
+    let (Some(name), Some(stem)) = (path.file_name(), path.file_stem()) else {
+        return Ok(false);
+    };
+
+    let thumbnail = get_thumbnails_dir(app_handle).join(Path::new(stem).with_extension("webp"));
+
+    if !thumbnail.exists() {
+        return recompress(app_handle, id, value);
+    }
+
+    let dest = location.join(name);
+
+    move_file(&path, &dest)?;
+    set_metadata(id, COLD_STORAGE, &dest.to_string_lossy())?;
+
+    Ok(true)
+}
+
+/// Recompress or move the images that went cold, returns how many were
+/// changed.
+pub fn apply_cold_policy<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    policy: &ColdStoragePolicy,
+) -> Result<usize, String> {
+    let location = policy
+        .location
+        .as_deref()
+        .filter(|location| !location.is_empty())
+        .map(PathBuf::from);
+
+    // An unplugged drive is skipped rather than recreated on the system disk
+    if location.as_ref().is_some_and(|location| !location.is_dir()) {
+        return Ok(0);
+    }
+
+    let mut changed = 0;
+
+    for (id, value) in cold_images(policy)? {
+        let result = match &location {
+            Some(location) => move_cold(app_handle, &id, &value, location),
+            None => recompress(app_handle, &id, &value),
+        };
+
+        match result {
+            Ok(true) => changed += 1,
+            Ok(false) => {}
+            Err(error) => log::warn!("Failed to move image {} to cold storage: {}", id, error),
+        }
+    }
+
+    Ok(changed)
+}
+
+fn image_item_id(value: &str) -> Result<Option<String>, String> {
+    with_connection(|connection| {
+        connection
+            .query_row(
+                "SELECT id FROM history WHERE type = 'image' AND value = ?1 LIMIT 1",
+                params![value],
+                |row| row.get(0),
+            )
+            .optional()
+    })
+}
+
+fn record_access(id: &str) -> Result<(), String> {
+    let count = get_metadata(id)?
+        .get(ACCESS_COUNT)
+        .and_then(|count| count.parse::<u64>().ok())
+        .unwrap_or_default();
+
+    set_metadata(id, ACCESS_COUNT, &(count + 1).to_string())?;
+    set_metadata(id, LAST_ACCESS, &local_time())
+}
+
+/// Path of a stored image about to be read, counting the access. An image
+/// in the cold location is moved back first.
+pub fn warm_image<R: Runtime>(app_handle: &AppHandle<R>, value: &str) -> Result<PathBuf, String> {
+    let path = resolve_image_path(app_handle, value);
+
+    let Some(id) = image_item_id(value)? else {
+        return Ok(path);
+    };
+
+    record_access(&id)?;
+
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let cold = get_metadata(&id)?
+        .remove(COLD_STORAGE)
+        .filter(|cold| cold != RECOMPRESSED);
+
+    let Some(cold) = cold else {
+        return Ok(path);
+    };
+
+    move_file(Path::new(&cold), &path)
+        .map_err(|e| format!("The image is in cold storage at {}: {}", cold, e))?;
+    store().delete_metadata(&id, COLD_STORAGE)?;
+
+    Ok(path)
+}
+
+// 获取图片的本地路径，图片已移到冷存储位置时先移回
+#[command]
+pub async fn fetch_image<R: Runtime>(
+    app_handle: AppHandle<R>,
+    value: String,
+) -> Result<String, String> {
+    let path = warm_image(&app_handle, &value)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,548 @@
+use crate::excluded_apps::{source_app, source_pid};
+use crate::process_tree::exe_path;
+use crate::transform::page_title_from_window;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Manager, Runtime};
+use tauri_plugin_eco_paste::{get_process_icon, icon_png};
+
+/// Executables of browsers whose address bar can be read for the active URL.
+pub const BROWSERS: &[&str] = &[
+    "chrome",
+    "msedge",
+    "firefox",
+    "brave",
+    "opera",
+    "vivaldi",
+    "safari",
+    "google chrome",
+    "microsoft edge",
+    "brave browser",
+    "arc",
+];
+
+/// Snapshot of the foreground application at capture time.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureContext {
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    pub url: Option<String>,
+}
+
+/// App a clipboard change came from, taken as soon as the change is seen
+/// since the webview reads the clipboard only after a debounce.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceApp {
+    pub name: String,
+    /// Full path of the executable.
+    pub path: Option<String>,
+    pub window_title: Option<String>,
+    /// Cached PNG of the app icon.
+    pub icon: Option<String>,
+    /// Page the copy was made on, for copies made in a browser.
+    pub url: Option<String>,
+    /// Title of that page, the window title without the browser name.
+    pub page_title: Option<String>,
+}
+
+pub fn is_browser(app_name: &str) -> bool {
+    let name = app_name.to_lowercase();
+    let name = name.trim_end_matches(".exe");
+
+    BROWSERS.contains(&name)
+}
+
+/// Address bars usually hide the scheme, normalize it back.
+#[cfg(not(target_os = "linux"))]
+fn normalize_url(value: &str) -> Option<String> {
+    let value = value.trim();
+
+    if value.is_empty() || value.contains(char::is_whitespace) || !value.contains('.') {
+        return None;
+    }
+
+    if value.contains("://") {
+        Some(value.to_string())
+    } else {
+        Some(format!("https://{}", value))
+    }
+}
+
+/// Read the `SourceURL:` line of a CF_HTML header.
+pub fn parse_source_url(bytes: &[u8]) -> Option<String> {
+    // The header always precedes the fragment, a few hundred bytes suffice
+    let header = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+
+    header
+        .lines()
+        .take_while(|line| !line.starts_with('<'))
+        .find_map(|line| line.strip_prefix("SourceURL:"))
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::{is_browser, normalize_url, CaptureContext};
+    use crate::process_tree::exe_path;
+    use uiautomation::controls::ControlType;
+    use uiautomation::patterns::UIValuePattern;
+    use uiautomation::types::Handle;
+    use uiautomation::UIAutomation;
+
+    extern "system" {
+        fn GetForegroundWindow() -> *mut std::ffi::c_void;
+        fn GetWindowTextLengthW(hWnd: *mut std::ffi::c_void) -> i32;
+        fn GetWindowTextW(hWnd: *mut std::ffi::c_void, lpString: *mut u16, nMaxCount: i32) -> i32;
+        fn GetWindowThreadProcessId(hWnd: *mut std::ffi::c_void, lpdwProcessId: *mut u32) -> u32;
+        fn OpenClipboard(hWndNewOwner: *mut std::ffi::c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn GetClipboardData(uFormat: u32) -> *mut std::ffi::c_void;
+        fn RegisterClipboardFormatW(lpszFormat: *const u16) -> u32;
+        fn GlobalLock(hMem: *mut std::ffi::c_void) -> *mut std::ffi::c_void;
+        fn GlobalUnlock(hMem: *mut std::ffi::c_void) -> i32;
+        fn GlobalSize(hMem: *mut std::ffi::c_void) -> usize;
+        fn GetClipboardOwner() -> *mut std::ffi::c_void;
+    }
+
+    /// Browsers put the page URL into the CF_HTML header, which is the most
+    /// reliable source for the tab the text was actually copied from.
+    pub fn clipboard_source_url() -> Option<String> {
+        let name: Vec<u16> = "HTML Format\0".encode_utf16().collect();
+
+        unsafe {
+            let format = RegisterClipboardFormatW(name.as_ptr());
+            if OpenClipboard(std::ptr::null_mut()) == 0 {
+                return None;
+            }
+
+            let mut url = None;
+            let handle = GetClipboardData(format);
+            if !handle.is_null() {
+                let data = GlobalLock(handle);
+                if !data.is_null() {
+                    let bytes = std::slice::from_raw_parts(data as *const u8, GlobalSize(handle));
+                    url = super::parse_source_url(bytes);
+                    GlobalUnlock(handle);
+                }
+            }
+
+            CloseClipboard();
+            url
+        }
+    }
+
+    /// Id of the process owning a window.
+    fn window_pid(hwnd: *mut std::ffi::c_void) -> Option<u32> {
+        let mut pid: u32 = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+
+        (pid != 0).then_some(pid)
+    }
+
+    /// Lowercased executable file name of the process owning a window.
+    pub fn window_exe_name(hwnd: *mut std::ffi::c_void) -> Option<String> {
+        let path = exe_path(window_pid(hwnd)?)?;
+        path.rsplit('\\').next().map(|name| name.to_lowercase())
+    }
+
+    /// Executable file name of the process owning the clipboard.
+    pub fn clipboard_owner_exe() -> Option<String> {
+        let owner = unsafe { GetClipboardOwner() };
+        if owner.is_null() {
+            return None;
+        }
+
+        window_exe_name(owner)
+    }
+
+    /// Id of the process owning the clipboard.
+    pub fn clipboard_owner_pid() -> Option<u32> {
+        let owner = unsafe { GetClipboardOwner() };
+        if owner.is_null() {
+            return None;
+        }
+
+        window_pid(owner)
+    }
+
+    /// Id of the process owning the foreground window.
+    pub fn foreground_pid() -> Option<u32> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.is_null() {
+            return None;
+        }
+
+        window_pid(hwnd)
+    }
+
+    /// Executable file name of the foreground app, without the window title
+    /// and URL lookups of a full context.
+    pub fn foreground_app_name() -> Option<String> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.is_null() {
+            return None;
+        }
+
+        window_exe_name(hwnd)
+    }
+
+    fn window_title(hwnd: *mut std::ffi::c_void) -> Option<String> {
+        unsafe {
+            let length = GetWindowTextLengthW(hwnd);
+            if length == 0 {
+                return None;
+            }
+
+            let mut buffer: Vec<u16> = vec![0; (length + 1) as usize];
+            GetWindowTextW(hwnd, buffer.as_mut_ptr(), length + 1);
+
+            Some(String::from_utf16_lossy(&buffer[..length as usize]))
+        }
+    }
+
+    pub fn foreground_window_title() -> Option<String> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.is_null() {
+            return None;
+        }
+
+        window_title(hwnd)
+    }
+
+    /// Read the address bar through UI Automation, the first edit control of
+    /// a browser window is its address bar in all Chromium and Gecko builds.
+    fn browser_url(hwnd: *mut std::ffi::c_void) -> Option<String> {
+        let automation = UIAutomation::new().ok()?;
+        let window = automation.element_from_handle(Handle::from(hwnd as isize)).ok()?;
+
+        let address_bar = automation
+            .create_matcher()
+            .from(window)
+            .control_type(ControlType::Edit)
+            .timeout(200)
+            .find_first()
+            .ok()?;
+
+        let pattern = address_bar.get_pattern::<UIValuePattern>().ok()?;
+
+        normalize_url(&pattern.get_value().ok()?)
+    }
+
+    pub fn foreground_context() -> CaptureContext {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.is_null() {
+            return CaptureContext::default();
+        }
+
+        let app_name = window_exe_name(hwnd);
+        let url = match &app_name {
+            Some(name) if is_browser(name) => browser_url(hwnd),
+            _ => None,
+        };
+
+        CaptureContext {
+            app_name,
+            window_title: window_title(hwnd),
+            url,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    use super::{is_browser, normalize_url, CaptureContext};
+    use std::process::Command;
+
+    fn run_script(script: &str) -> Option<String> {
+        let output = Command::new("osascript").args(["-e", script]).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if value.is_empty() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// One fixed script per browser. The app name comes from the process
+    /// list and is never spliced into a script, and `tell application` needs
+    /// a literal name to resolve the browser's terminology.
+    fn browser_url(app_name: &str) -> Option<String> {
+        let script = match app_name {
+            "Safari" => r#"tell application "Safari" to get URL of front document"#,
+            "Google Chrome" => {
+                r#"tell application "Google Chrome" to get URL of active tab of front window"#
+            }
+            "Microsoft Edge" => {
+                r#"tell application "Microsoft Edge" to get URL of active tab of front window"#
+            }
+            "Brave Browser" => {
+                r#"tell application "Brave Browser" to get URL of active tab of front window"#
+            }
+            "Vivaldi" => r#"tell application "Vivaldi" to get URL of active tab of front window"#,
+            "Opera" => r#"tell application "Opera" to get URL of active tab of front window"#,
+            "Arc" => r#"tell application "Arc" to get URL of active tab of front window"#,
+            _ => return None,
+        };
+
+        normalize_url(&run_script(script)?)
+    }
+
+    pub fn foreground_app_name() -> Option<String> {
+        run_script(
+            r#"tell application "System Events" to get name of first application process whose frontmost is true"#,
+        )
+    }
+
+    pub fn foreground_pid() -> Option<u32> {
+        run_script(
+            r#"tell application "System Events" to get unix id of first application process whose frontmost is true"#,
+        )?
+        .parse()
+        .ok()
+    }
+
+    pub fn foreground_window_title() -> Option<String> {
+        run_script(
+            r#"tell application "System Events" to get name of front window of (first application process whose frontmost is true)"#,
+        )
+    }
+
+    pub fn foreground_context() -> CaptureContext {
+        let app_name = foreground_app_name();
+        let window_title = foreground_window_title();
+        let url = match &app_name {
+            Some(name) if is_browser(name) => browser_url(name),
+            _ => None,
+        };
+
+        CaptureContext {
+            app_name,
+            window_title,
+            url,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::CaptureContext;
+    use x11::xlib::{
+        self, Atom, XCloseDisplay, XDefaultRootWindow, XFree, XGetWindowProperty, XInternAtom,
+        XOpenDisplay,
+    };
+
+    unsafe fn get_property(
+        display: *mut xlib::Display,
+        window: u64,
+        name: &[u8],
+    ) -> Option<(*mut u8, u64)> {
+        let mut actual_type: Atom = 0;
+        let mut actual_format: i32 = 0;
+        let mut nitems: u64 = 0;
+        let mut bytes_after: u64 = 0;
+        let mut prop: *mut u8 = std::ptr::null_mut();
+        let atom = XInternAtom(display, name.as_ptr() as _, xlib::False);
+
+        let result = XGetWindowProperty(
+            display,
+            window,
+            atom,
+            0,
+            1024,
+            xlib::False,
+            xlib::AnyPropertyType as _,
+            &mut actual_type,
+            &mut actual_format,
+            &mut nitems,
+            &mut bytes_after,
+            &mut prop,
+        );
+
+        if result == xlib::Success as i32 && !prop.is_null() && nitems > 0 {
+            Some((prop, nitems))
+        } else {
+            None
+        }
+    }
+
+    pub fn foreground_context() -> CaptureContext {
+        unsafe {
+            let display = XOpenDisplay(std::ptr::null_mut());
+            if display.is_null() {
+                return CaptureContext::default();
+            }
+
+            let root = XDefaultRootWindow(display);
+            let mut context = CaptureContext::default();
+
+            if let Some((prop, _)) = get_property(display, root, b"_NET_ACTIVE_WINDOW\0") {
+                let window = *(prop as *const u64);
+                XFree(prop as *mut _);
+
+                if let Some((prop, _)) = get_property(display, window, b"_NET_WM_NAME\0") {
+                    context.window_title = Some(
+                        std::ffi::CStr::from_ptr(prop as *const std::ffi::c_char)
+                            .to_string_lossy()
+                            .into_owned(),
+                    );
+                    XFree(prop as *mut _);
+                }
+
+                if let Some((prop, nitems)) = get_property(display, window, b"WM_CLASS\0") {
+                    let bytes = std::slice::from_raw_parts(prop, nitems as usize);
+                    context.app_name = bytes
+                        .split(|&b| b == 0)
+                        .nth(1)
+                        .map(|class| String::from_utf8_lossy(class).into_owned());
+                    XFree(prop as *mut _);
+                }
+            }
+
+            XCloseDisplay(display);
+            context
+        }
+    }
+
+    pub fn foreground_app_name() -> Option<String> {
+        foreground_context().app_name
+    }
+
+    pub fn foreground_window_title() -> Option<String> {
+        foreground_context().window_title
+    }
+
+    /// Id of the process owning the active window, from the `_NET_WM_PID`
+    /// the window manager sets.
+    pub fn foreground_pid() -> Option<u32> {
+        unsafe {
+            let display = XOpenDisplay(std::ptr::null_mut());
+            if display.is_null() {
+                return None;
+            }
+
+            let root = XDefaultRootWindow(display);
+            let mut pid = None;
+
+            if let Some((prop, _)) = get_property(display, root, b"_NET_ACTIVE_WINDOW\0") {
+                let window = *(prop as *const u64);
+                XFree(prop as *mut _);
+
+                if let Some((prop, _)) = get_property(display, window, b"_NET_WM_PID\0") {
+                    pid = Some(*(prop as *const u64) as u32);
+                    XFree(prop as *mut _);
+                }
+            }
+
+            XCloseDisplay(display);
+            pid
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub use win::{
+    clipboard_owner_exe, clipboard_owner_pid, clipboard_source_url, foreground_app_name,
+    foreground_context, foreground_pid, foreground_window_title, window_exe_name,
+};
+
+#[cfg(target_os = "macos")]
+pub use mac::{foreground_app_name, foreground_context, foreground_pid, foreground_window_title};
+
+#[cfg(target_os = "linux")]
+pub use linux::{foreground_app_name, foreground_context, foreground_pid, foreground_window_title};
+
+/// Where the icons of source apps are cached, one PNG per app.
+pub fn get_app_icons_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("app-icons")
+}
+
+/// App names may contain spaces or dots, e.g. `Google Chrome`.
+fn icon_file_name(app_name: &str) -> String {
+    let stem: String = app_name
+        .to_lowercase()
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("{}.png", stem)
+}
+
+/// Extracted once per app, icons rarely change and extraction is slow on
+/// Windows.
+fn cached_icon<R: Runtime>(app_handle: &AppHandle<R>, app_name: &str, pid: u32) -> Option<PathBuf> {
+    let path = get_app_icons_dir(app_handle).join(icon_file_name(app_name));
+    if path.exists() {
+        return Some(path);
+    }
+
+    let png = icon_png(get_process_icon(pid)?)?;
+
+    fs::create_dir_all(path.parent()?).ok()?;
+    fs::write(&path, png).ok()?;
+
+    Some(path)
+}
+
+/// Snapshot of the app a clipboard change came from. The window title is
+/// only kept when the foreground window belongs to that app, the clipboard
+/// owner on Windows may be a background process.
+pub fn source_app_snapshot<R: Runtime>(app_handle: &AppHandle<R>) -> Option<SourceApp> {
+    let name = source_app()?;
+    let pid = source_pid();
+
+    let in_foreground = pid.is_some_and(|pid| foreground_pid() == Some(pid));
+
+    let window_title = in_foreground.then(foreground_window_title).flatten();
+    let url = (in_foreground && is_browser(&name))
+        .then(browser_url)
+        .flatten();
+    let page_title = url
+        .as_ref()
+        .and(window_title.as_deref())
+        .map(page_title_from_window)
+        .filter(|title| !title.is_empty());
+
+    Some(SourceApp {
+        path: pid.and_then(exe_path),
+        icon: pid
+            .and_then(|pid| cached_icon(app_handle, &name, pid))
+            .map(|path| path.to_string_lossy().to_string()),
+        name,
+        window_title,
+        url,
+        page_title,
+    })
+}
+
+/// Page a copy in a browser was made on. Browsers on Windows put it into
+/// the CF_HTML header, which names the tab the text was actually copied
+/// from, the address bar is read otherwise.
+fn browser_url() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    if let Some(url) = clipboard_source_url() {
+        return Some(url);
+    }
+
+    foreground_context().url
+}
+
+// 获取当前前台窗口的上下文
+#[command]
+pub async fn get_capture_context() -> CaptureContext {
+    foreground_context()
+}
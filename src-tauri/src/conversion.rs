@@ -0,0 +1,551 @@
+use crate::calculator::format_result;
+use crate::history::{get_metadata, set_metadata, NewHistoryEntry, CONVERSIONS};
+use crate::item_access::require_revealed_item;
+use crate::paste::paste_plain;
+use crate::vault;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Manager, Runtime};
+
+/// Longer texts are not taken for a quantity.
+const MAX_QUANTITY_LENGTH: usize = 64;
+
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/USD";
+const RATES_TIMEOUT: Duration = Duration::from_secs(10);
+/// Cached rates older than this are refreshed in the background.
+const RATES_MAX_AGE: Duration = Duration::from_secs(12 * 60 * 60);
+/// A failed refresh is not retried before this, e.g. while offline.
+const RATES_RETRY: Duration = Duration::from_secs(10 * 60);
+
+/// Units converted to when nothing was configured.
+const DEFAULT_TARGETS: &[&str] = &[
+    "cm", "in", "m", "ft", "km", "mi", "g", "oz", "kg", "lb", "ml", "l", "gal", "°C", "°F", "km/h",
+    "mph", "USD", "EUR", "CNY",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Volume,
+    Temperature,
+    Speed,
+    Currency,
+}
+
+/// How a unit is written in results and passed to `paste_converted`, its
+/// size in meters, kilograms, liters or meters per second (unused for
+/// temperatures) and its spellings after a number, in lowercase.
+type Unit = (&'static str, f64, &'static [&'static str]);
+
+const LENGTHS: &[Unit] = &[
+    ("mm", 0.001, &["mm", "millimeter", "millimeters"]),
+    ("cm", 0.01, &["cm", "centimeter", "centimeters"]),
+    ("m", 1.0, &["m", "meter", "meters", "metre", "metres"]),
+    ("km", 1000.0, &["km", "kilometer", "kilometers"]),
+    ("in", 0.0254, &["in", "inch", "inches", "\""]),
+    ("ft", 0.3048, &["ft", "foot", "feet", "'"]),
+    ("yd", 0.9144, &["yd", "yard", "yards"]),
+    ("mi", 1609.344, &["mi", "mile", "miles"]),
+];
+
+const MASSES: &[Unit] = &[
+    ("mg", 0.000001, &["mg", "milligram", "milligrams"]),
+    ("g", 0.001, &["g", "gram", "grams"]),
+    ("kg", 1.0, &["kg", "kilo", "kilos", "kilogram", "kilograms"]),
+    ("oz", 0.028349523125, &["oz", "ounce", "ounces"]),
+    ("lb", 0.45359237, &["lb", "lbs", "pound", "pounds"]),
+];
+
+const VOLUMES: &[Unit] = &[
+    ("ml", 0.001, &["ml", "milliliter", "milliliters"]),
+    ("l", 1.0, &["l", "liter", "liters", "litre", "litres"]),
+    ("fl oz", 0.0295735295625, &["fl oz", "floz"]),
+    ("cup", 0.2365882365, &["cup", "cups"]),
+    ("gal", 3.785411784, &["gal", "gallon", "gallons"]),
+];
+
+const TEMPERATURES: &[Unit] = &[
+    ("°C", 0.0, &["°c", "℃", "celsius"]),
+    ("°F", 0.0, &["°f", "℉", "fahrenheit"]),
+    ("K", 0.0, &["kelvin"]),
+];
+
+const SPEEDS: &[Unit] = &[
+    ("m/s", 1.0, &["m/s"]),
+    ("km/h", 1.0 / 3.6, &["km/h", "kmh", "kph"]),
+    ("mph", 0.44704, &["mph"]),
+    ("kn", 1852.0 / 3600.0, &["kn", "knot", "knots"]),
+];
+
+const UNITS: &[(Dimension, &[Unit])] = &[
+    (Dimension::Length, LENGTHS),
+    (Dimension::Mass, MASSES),
+    (Dimension::Volume, VOLUMES),
+    (Dimension::Temperature, TEMPERATURES),
+    (Dimension::Speed, SPEEDS),
+];
+
+/// Rough rates per US dollar, used until real ones were fetched once.
+/// Only these currencies are recognized.
+const FALLBACK_RATES: &[(&str, f64)] = &[
+    ("AUD", 1.52),
+    ("BRL", 5.4),
+    ("CAD", 1.37),
+    ("CHF", 0.86),
+    ("CNY", 7.2),
+    ("EUR", 0.92),
+    ("GBP", 0.78),
+    ("HKD", 7.8),
+    ("INR", 84.0),
+    ("JPY", 150.0),
+    ("KRW", 1380.0),
+    ("MXN", 19.0),
+    ("RUB", 95.0),
+    ("SGD", 1.33),
+    ("TWD", 32.0),
+    ("USD", 1.0),
+];
+
+fn units() -> impl Iterator<Item = (Dimension, &'static Unit)> {
+    UNITS
+        .iter()
+        .flat_map(|(dimension, units)| units.iter().map(move |unit| (*dimension, unit)))
+}
+
+/// Currency symbols, longer ones first so `US$` wins over `$`.
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("US$", "USD"),
+    ("HK$", "HKD"),
+    ("NT$", "TWD"),
+    ("CN¥", "CNY"),
+    ("C$", "CAD"),
+    ("A$", "AUD"),
+    ("S$", "SGD"),
+    ("R$", "BRL"),
+    ("$", "USD"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("元", "CNY"),
+    ("₹", "INR"),
+    ("₩", "KRW"),
+    ("₽", "RUB"),
+];
+
+static TARGETS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+/// Live rates are only fetched once the user turned them on, nothing is
+/// sent over the network by default.
+static FETCH_RATES: AtomicBool = AtomicBool::new(false);
+static RATES: Mutex<Option<RateCache>> = Mutex::new(None);
+static REFRESHING: AtomicBool = AtomicBool::new(false);
+/// Unix time of the last fetch attempt.
+static LAST_FETCH: AtomicU64 = AtomicU64::new(0);
+
+/// Fetched rates, kept across restarts.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RateCache {
+    fetched_at: u64,
+    rates: BTreeMap<String, f64>,
+}
+
+/// A number with a unit, `5 mi`, `$30` or `72°F`.
+struct Quantity {
+    value: f64,
+    dimension: Dimension,
+    code: &'static str,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn rates_path<R: Runtime>(app_handle: &AppHandle<R>) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+
+    Ok(app_data.join("currency_rates.json"))
+}
+
+fn fetch_rates() -> Result<BTreeMap<String, f64>, String> {
+    #[derive(Deserialize)]
+    struct Response {
+        rates: BTreeMap<String, f64>,
+    }
+
+    let body = Client::builder()
+        .timeout(RATES_TIMEOUT)
+        .build()
+        .and_then(|client| client.get(RATES_URL).send())
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|e| e.to_string())?;
+
+    let response: Response = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+
+    Ok(response.rates)
+}
+
+/// Fetch the rates on a background thread, one fetch at a time.
+fn refresh_rates<R: Runtime>(app_handle: &AppHandle<R>) {
+    let now = now_secs();
+
+    if !FETCH_RATES.load(Ordering::Relaxed)
+        || now.saturating_sub(LAST_FETCH.load(Ordering::Relaxed)) < RATES_RETRY.as_secs()
+    {
+        return;
+    }
+
+    let path = match rates_path(app_handle) {
+        Ok(path) => path,
+        Err(error) => {
+            log::warn!("Failed to locate the currency rates: {}", error);
+            return;
+        }
+    };
+
+    if REFRESHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    LAST_FETCH.store(now, Ordering::Relaxed);
+
+    std::thread::spawn(move || {
+        match fetch_rates() {
+            Ok(rates) => {
+                let cache = RateCache {
+                    fetched_at: now_secs(),
+                    rates,
+                };
+
+                if let Ok(bytes) = serde_json::to_vec(&cache) {
+                    let _ = std::fs::write(path, bytes);
+                }
+
+                *RATES.lock().unwrap() = Some(cache);
+            }
+            Err(error) => log::warn!("Failed to fetch currency rates: {}", error),
+        }
+
+        REFRESHING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Rates per US dollar, the fallback ones for currencies never fetched.
+/// Stale rates are refreshed in the background for the next conversion.
+fn currency_rates<R: Runtime>(app_handle: &AppHandle<R>) -> BTreeMap<String, f64> {
+    let mut rates: BTreeMap<String, f64> = FALLBACK_RATES
+        .iter()
+        .map(|(code, rate)| (code.to_string(), *rate))
+        .collect();
+
+    let mut cache = RATES.lock().unwrap();
+
+    if cache.is_none() {
+        *cache = rates_path(app_handle)
+            .ok()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    }
+
+    let stale = cache.as_ref().map_or(true, |cache| {
+        now_secs().saturating_sub(cache.fetched_at) >= RATES_MAX_AGE.as_secs()
+    });
+
+    if let Some(cache) = cache.as_ref() {
+        for (code, rate) in &cache.rates {
+            if let Some(known) = rates.get_mut(code) {
+                *known = *rate;
+            }
+        }
+    }
+
+    drop(cache);
+
+    if stale {
+        refresh_rates(app_handle);
+    }
+
+    rates
+}
+
+/// A known currency code, matched case-insensitively.
+fn find_currency(code: &str) -> Option<&'static str> {
+    FALLBACK_RATES
+        .iter()
+        .find(|(currency, _)| currency.eq_ignore_ascii_case(code))
+        .map(|(currency, _)| *currency)
+}
+
+/// The canonical code of a unit or currency, matched case-insensitively.
+fn find_code(code: &str) -> Option<(&'static str, Dimension)> {
+    let code = code.trim();
+
+    if let Some(currency) = find_currency(code) {
+        return Some((currency, Dimension::Currency));
+    }
+
+    let code = code.to_lowercase();
+
+    units()
+        .find(|(_, (unit, _, _))| unit.to_lowercase() == code)
+        .map(|(dimension, (unit, _, _))| (*unit, dimension))
+}
+
+/// The unit written after a number: a currency symbol or code, or a unit
+/// alias.
+fn find_suffix(suffix: &str) -> Option<(&'static str, Dimension)> {
+    let currency = CURRENCY_SYMBOLS
+        .iter()
+        .find(|(symbol, _)| *symbol == suffix)
+        .map(|(_, code)| *code)
+        .or_else(|| find_currency(suffix));
+
+    if let Some(code) = currency {
+        return Some((code, Dimension::Currency));
+    }
+
+    let suffix = suffix.to_lowercase();
+
+    units()
+        .find(|(_, (_, _, aliases))| aliases.iter().any(|alias| *alias == suffix))
+        .map(|(dimension, (unit, _, _))| (*unit, dimension))
+}
+
+/// Split a leading number such as `-1,234.5` from the rest of the text.
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let end = text
+        .char_indices()
+        .find(|(index, ch)| {
+            !(ch.is_ascii_digit() || *ch == '.' || *ch == ',' || (*index == 0 && *ch == '-'))
+        })
+        .map_or(text.len(), |(index, _)| index);
+
+    let number: String = text[..end].chars().filter(|ch| *ch != ',').collect();
+
+    if !number.chars().any(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+
+    let value: f64 = number.parse().ok()?;
+
+    Some((value, text[end..].trim()))
+}
+
+/// Parse a copied value with a unit, the whole text has to be the
+/// quantity.
+fn parse_quantity(text: &str) -> Option<Quantity> {
+    let text = text.trim();
+
+    if text.is_empty() || text.len() > MAX_QUANTITY_LENGTH {
+        return None;
+    }
+
+    // A currency in front, `$30` or `EUR 30`
+    let prefix = CURRENCY_SYMBOLS
+        .iter()
+        .find_map(|(symbol, code)| Some((text.strip_prefix(symbol)?, *code)))
+        .or_else(|| {
+            let (code, rest) = text.split_once(char::is_whitespace)?;
+            Some((rest, find_currency(code)?))
+        });
+
+    if let Some((rest, code)) = prefix {
+        let (value, rest) = split_number(rest.trim_start())?;
+
+        return rest.is_empty().then_some(Quantity {
+            value,
+            dimension: Dimension::Currency,
+            code,
+        });
+    }
+
+    let (value, suffix) = split_number(text)?;
+    let (code, dimension) = find_suffix(suffix)?;
+
+    Some(Quantity {
+        value,
+        dimension,
+        code,
+    })
+}
+
+fn to_kelvin(value: f64, code: &str) -> f64 {
+    match code {
+        "°C" => value + 273.15,
+        "°F" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => value,
+    }
+}
+
+fn from_kelvin(value: f64, code: &str) -> f64 {
+    match code {
+        "°C" => value - 273.15,
+        "°F" => (value - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => value,
+    }
+}
+
+fn factor(code: &str) -> Option<f64> {
+    units()
+        .find(|(_, (unit, _, _))| *unit == code)
+        .map(|(_, (_, factor, _))| *factor)
+}
+
+fn convert(quantity: &Quantity, target: &str, rates: &BTreeMap<String, f64>) -> Option<f64> {
+    match quantity.dimension {
+        Dimension::Temperature => {
+            let kelvin = to_kelvin(quantity.value, quantity.code);
+
+            Some(from_kelvin(kelvin, target))
+        }
+        Dimension::Currency => {
+            Some(quantity.value / rates.get(quantity.code)? * rates.get(target)?)
+        }
+        _ => Some(quantity.value * factor(quantity.code)? / factor(target)?),
+    }
+}
+
+/// Two decimals, or four significant digits for small values.
+fn round(value: f64) -> f64 {
+    let scale = if value == 0.0 || value.abs() >= 1.0 {
+        100.0
+    } else {
+        10f64.powi(3 - value.abs().log10().floor() as i32)
+    };
+
+    (value * scale).round() / scale
+}
+
+/// A converted value as it is pasted, `8.05 km`, `22.22°C` or `27.60 EUR`.
+fn format_converted(value: f64, code: &str, dimension: Dimension) -> String {
+    match dimension {
+        Dimension::Currency => format!("{:.2} {}", value, code),
+        Dimension::Temperature if code != "K" => format!("{}{}", format_result(round(value)), code),
+        _ => format!("{} {}", format_result(round(value)), code),
+    }
+}
+
+/// The target units of the quantity's dimension, by code.
+fn conversions<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    quantity: &Quantity,
+) -> BTreeMap<String, String> {
+    let targets = TARGETS.lock().unwrap().clone();
+    let targets: Vec<&str> = match &targets {
+        Some(targets) => targets.iter().map(String::as_str).collect(),
+        None => DEFAULT_TARGETS.to_vec(),
+    };
+
+    let rates = match quantity.dimension {
+        Dimension::Currency => currency_rates(app_handle),
+        _ => BTreeMap::new(),
+    };
+
+    targets
+        .into_iter()
+        .filter_map(find_code)
+        .filter(|(code, dimension)| *dimension == quantity.dimension && *code != quantity.code)
+        .filter_map(|(code, dimension)| {
+            let value = convert(quantity, code, &rates)?;
+
+            Some((code.to_string(), format_converted(value, code, dimension)))
+        })
+        .collect()
+}
+
+/// Save the conversions of a copied value with a unit with the item, as a
+/// JSON object by unit code. Skipped in vault mode like expression results.
+pub fn record_conversions<R: Runtime>(app_handle: &AppHandle<R>, entry: &NewHistoryEntry) {
+    if vault::is_enabled() {
+        return;
+    }
+
+    let field = |name: &str| entry.row.get(name).and_then(Value::as_str);
+
+    let (Some("text"), Some(id), Some(text)) = (field("type"), field("id"), field("value")) else {
+        return;
+    };
+
+    let Some(quantity) = parse_quantity(text) else {
+        return;
+    };
+
+    let conversions = conversions(app_handle, &quantity);
+
+    if conversions.is_empty() {
+        return;
+    }
+
+    let saved = serde_json::to_string(&conversions)
+        .map_err(|e| e.to_string())
+        .and_then(|json| set_metadata(id, CONVERSIONS, &json));
+
+    if let Err(error) = saved {
+        log::warn!("Failed to save the unit conversions: {}", error);
+    }
+}
+
+// 设置换算的目标单位，例如 km、°F、EUR，为空时使用默认的单位
+#[command]
+pub async fn set_conversion_targets(targets: Option<Vec<String>>) {
+    *TARGETS.lock().unwrap() = targets;
+}
+
+// 设置是否从网络获取实时汇率，关闭时使用内置的汇率
+#[command]
+pub async fn set_currency_rates_fetch(enabled: bool) {
+    FETCH_RATES.store(enabled, Ordering::Relaxed);
+}
+
+// 粘贴带单位条目换算到指定单位的结果
+#[command]
+pub async fn paste_converted<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    unit: String,
+) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+
+    let (code, dimension) = find_code(&unit).ok_or_else(|| format!("Unknown unit {}", unit))?;
+
+    let recorded = get_metadata(&id)?
+        .remove(CONVERSIONS)
+        .and_then(|json| serde_json::from_str::<BTreeMap<String, String>>(&json).ok())
+        .and_then(|mut conversions| conversions.remove(code));
+
+    // Units that were not a target when the item was copied are converted now
+    let converted = match recorded {
+        Some(converted) => converted,
+        None => {
+            let quantity = Some(&item)
+                .filter(|item| item.kind == "text")
+                .and_then(|item| parse_quantity(&item.value))
+                .ok_or("The item is not a value with a unit")?;
+
+            if quantity.dimension != dimension {
+                return Err(format!("The item can't be converted to {}", code));
+            }
+
+            let rates = currency_rates(&app_handle);
+            let value = convert(&quantity, code, &rates)
+                .ok_or_else(|| format!("The item can't be converted to {}", code))?;
+
+            format_converted(value, code, dimension)
+        }
+    };
+
+    paste_plain(app_handle, converted).await
+}
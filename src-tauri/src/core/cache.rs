@@ -0,0 +1,270 @@
+use serde::Serialize;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::command;
+
+// 默认的缓存内存预算：64 MB
+const DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
+
+static BUDGET: AtomicUsize = AtomicUsize::new(DEFAULT_BUDGET);
+static CACHES: Mutex<Option<CacheManager>> = Mutex::new(None);
+
+// 各个缓存的名称
+pub const PREVIEWS: &str = "previews";
+pub const THUMBNAILS: &str = "thumbnails";
+pub const IMAGES: &str = "images";
+pub const SEARCH: &str = "search";
+
+type Value = Arc<dyn Any + Send + Sync>;
+
+struct Entry {
+    value: Value,
+    size: usize,
+    last_used: u64,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Values not cached because they alone exceed the budget.
+    pub rejections: u64,
+}
+
+#[derive(Default)]
+struct NamedCache {
+    entries: HashMap<String, Entry>,
+    stats: CacheStats,
+}
+
+// 所有缓存共享同一个内存预算，超出时按最近最少使用淘汰
+#[derive(Default)]
+struct CacheManager {
+    caches: BTreeMap<&'static str, NamedCache>,
+    // 按最后使用时间排序的条目，淘汰时从最旧的开始
+    order: BTreeMap<u64, (&'static str, String)>,
+    total_bytes: usize,
+    tick: u64,
+}
+
+impl CacheManager {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get(&mut self, name: &'static str, key: &str) -> Option<Value> {
+        let tick = self.next_tick();
+        let cache = self.caches.entry(name).or_default();
+
+        match cache.entries.get_mut(key) {
+            Some(entry) => {
+                if let Some(position) = self.order.remove(&entry.last_used) {
+                    self.order.insert(tick, position);
+                }
+
+                entry.last_used = tick;
+                cache.stats.hits += 1;
+                Some(entry.value.clone())
+            }
+            None => {
+                cache.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, name: &'static str, key: &str) -> Option<Entry> {
+        let cache = self.caches.get_mut(name)?;
+        let entry = cache.entries.remove(key)?;
+
+        self.order.remove(&entry.last_used);
+        cache.stats.entries -= 1;
+        cache.stats.bytes -= entry.size;
+        self.total_bytes -= entry.size;
+
+        Some(entry)
+    }
+
+    fn put(&mut self, name: &'static str, key: String, value: Value, size: usize) {
+        let budget = BUDGET.load(Ordering::Relaxed);
+
+        self.remove(name, &key);
+
+        // Caching it would flush everything else and still not fit
+        if size > budget {
+            self.caches.entry(name).or_default().stats.rejections += 1;
+            return;
+        }
+
+        let tick = self.next_tick();
+        let cache = self.caches.entry(name).or_default();
+
+        cache.entries.insert(
+            key.clone(),
+            Entry {
+                value,
+                size,
+                last_used: tick,
+            },
+        );
+        cache.stats.entries += 1;
+        cache.stats.bytes += size;
+        self.total_bytes += size;
+        self.order.insert(tick, (name, key));
+
+        self.evict(budget);
+    }
+
+    // 淘汰最久未使用的条目，直到总占用不超过预算
+    fn evict(&mut self, budget: usize) {
+        while self.total_bytes > budget {
+            let Some((_, (name, key))) = self.order.pop_first() else {
+                break;
+            };
+
+            if self.remove(name, &key).is_some() {
+                self.caches.get_mut(name).unwrap().stats.evictions += 1;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        for cache in self.caches.values_mut() {
+            cache.entries.clear();
+            cache.stats.entries = 0;
+            cache.stats.bytes = 0;
+        }
+
+        self.order.clear();
+        self.total_bytes = 0;
+    }
+}
+
+fn with_manager<T>(f: impl FnOnce(&mut CacheManager) -> T) -> T {
+    let mut caches = CACHES.lock().unwrap();
+
+    f(caches.get_or_insert_with(CacheManager::default))
+}
+
+/// Cached value of `key`, `None` when missing or stored as another type.
+pub fn get<T: Any + Send + Sync>(name: &'static str, key: &str) -> Option<Arc<T>> {
+    with_manager(|manager| manager.get(name, key))?
+        .downcast()
+        .ok()
+}
+
+/// Cache a value accounted as `size` bytes. Values bigger than the whole
+/// budget are not cached.
+pub fn put<T: Any + Send + Sync>(name: &'static str, key: String, value: T, size: usize) -> Arc<T> {
+    let value = Arc::new(value);
+
+    with_manager(|manager| manager.put(name, key, value.clone(), size));
+
+    value
+}
+
+pub fn get_string(name: &'static str, key: &str) -> Option<String> {
+    get::<String>(name, key).map(|value| value.as_str().to_string())
+}
+
+pub fn put_string(name: &'static str, key: String, value: &str) {
+    put(name, key, value.to_string(), value.len());
+}
+
+/// Drop every cached value, e.g. plaintext held while the vault was
+/// unlocked.
+pub fn clear() {
+    with_manager(CacheManager::clear);
+}
+
+// 当前进程的常驻内存
+#[cfg(target_os = "windows")]
+fn resident_bytes() -> Option<u64> {
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    struct PROCESS_MEMORY_COUNTERS {
+        cb: u32,
+        PageFaultCount: u32,
+        PeakWorkingSetSize: usize,
+        WorkingSetSize: usize,
+        QuotaPeakPagedPoolUsage: usize,
+        QuotaPagedPoolUsage: usize,
+        QuotaPeakNonPagedPoolUsage: usize,
+        QuotaNonPagedPoolUsage: usize,
+        PagefileUsage: usize,
+        PeakPagefileUsage: usize,
+    }
+
+    extern "system" {
+        fn GetCurrentProcess() -> *mut std::ffi::c_void;
+        fn K32GetProcessMemoryInfo(
+            Process: *mut std::ffi::c_void,
+            ppsmemCounters: *mut PROCESS_MEMORY_COUNTERS,
+            cb: u32,
+        ) -> i32;
+    }
+
+    unsafe {
+        let mut counters: PROCESS_MEMORY_COUNTERS = std::mem::zeroed();
+        let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+        counters.cb = size;
+
+        if K32GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) == 0 {
+            return None;
+        }
+
+        Some(counters.WorkingSetSize as u64)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn resident_bytes() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+
+    Some(pages * 4096)
+}
+
+#[cfg(target_os = "macos")]
+fn resident_bytes() -> Option<u64> {
+    None
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeMetrics {
+    pub resident_bytes: Option<u64>,
+    pub cache_budget: usize,
+    pub cache_bytes: usize,
+    pub caches: BTreeMap<&'static str, CacheStats>,
+}
+
+// 设置缓存的内存预算（字节）
+#[command]
+pub async fn set_memory_budget(bytes: usize) {
+    BUDGET.store(bytes, Ordering::Relaxed);
+
+    with_manager(|manager| manager.evict(bytes));
+}
+
+// 获取运行时的内存和缓存统计
+#[command]
+pub async fn get_runtime_metrics() -> RuntimeMetrics {
+    with_manager(|manager| RuntimeMetrics {
+        resident_bytes: resident_bytes(),
+        cache_budget: BUDGET.load(Ordering::Relaxed),
+        cache_bytes: manager.total_bytes,
+        caches: manager
+            .caches
+            .iter()
+            .map(|(name, cache)| (*name, cache.stats.clone()))
+            .collect(),
+    })
+}
@@ -1,2 +1,4 @@
+pub mod cache;
 pub mod prevent_default;
 pub mod setup;
+pub mod startup;
@@ -0,0 +1,65 @@
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
+use tauri::command;
+
+static STARTED_AT: OnceLock<Instant> = OnceLock::new();
+static TIMINGS: Mutex<Vec<StartupTiming>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupTiming {
+    pub name: &'static str,
+    // 相对于进程启动的开始时间
+    pub start_ms: u64,
+    pub duration_ms: u64,
+    // 是否在后台延迟初始化
+    pub deferred: bool,
+}
+
+fn elapsed_ms(instant: Instant) -> u64 {
+    instant.elapsed().as_millis() as u64
+}
+
+// 记录启动的起点，越早调用越准确
+pub fn begin() {
+    let _ = STARTED_AT.set(Instant::now());
+}
+
+fn record(name: &'static str, start: Instant, deferred: bool) {
+    let started_at = *STARTED_AT.get_or_init(Instant::now);
+
+    TIMINGS.lock().unwrap().push(StartupTiming {
+        name,
+        start_ms: start.saturating_duration_since(started_at).as_millis() as u64,
+        duration_ms: elapsed_ms(start),
+        deferred,
+    });
+}
+
+// 同步初始化并记录耗时，只用于窗口、剪贴板监听和快捷键等关键子系统
+pub fn measure<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+
+    record(name, start, false);
+
+    result
+}
+
+// 在后台线程中延迟初始化耗时的子系统
+pub fn defer(name: &'static str, f: impl FnOnce() + Send + 'static) {
+    thread::spawn(move || {
+        let start = Instant::now();
+        f();
+
+        record(name, start, true);
+    });
+}
+
+// 获取启动各阶段的耗时
+#[command]
+pub async fn get_startup_timings() -> Vec<StartupTiming> {
+    TIMINGS.lock().unwrap().clone()
+}
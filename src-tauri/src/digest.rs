@@ -0,0 +1,278 @@
+use crate::history::{
+    frecent_items, get_metadata, period_stats, store, with_connection, PeriodStats, REDACTED_TEXT,
+    SENSITIVE, SOURCE_APP,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+/// How often the scheduler checks whether a digest is due.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+const TOP_APPS: usize = 5;
+const NOTABLE_ITEMS: usize = 5;
+
+/// Longest preview of a notable item.
+const MAX_PREVIEW_CHARS: usize = 80;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum DigestPeriod {
+    Daily,
+    Weekly,
+}
+
+impl DigestPeriod {
+    /// SQLite date modifier going one period back.
+    fn modifier(self) -> &'static str {
+        match self {
+            DigestPeriod::Daily => "-1 day",
+            DigestPeriod::Weekly => "-7 days",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestPolicy {
+    pub period: DigestPeriod,
+    /// Show the digest as a system notification.
+    pub notify: bool,
+    /// Also write every digest as a markdown file into this directory.
+    pub directory: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotableItem {
+    pub id: String,
+    pub kind: String,
+    pub preview: String,
+    pub source_app: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Digest {
+    pub period: DigestPeriod,
+    /// Start and end of the period, in the `createTime` format.
+    pub since: String,
+    pub until: String,
+    pub stats: PeriodStats,
+    pub notable: Vec<NotableItem>,
+}
+
+/// When the last digest was delivered, kept across restarts.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DigestState {
+    sent_at: Option<String>,
+}
+
+static POLICY: Mutex<Option<DigestPolicy>> = Mutex::new(None);
+
+fn state_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("digest.json")
+}
+
+fn load_state<R: Runtime>(app_handle: &AppHandle<R>) -> DigestState {
+    std::fs::read(state_path(app_handle))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn save_state<R: Runtime>(app_handle: &AppHandle<R>, state: &DigestState) -> Result<(), String> {
+    let bytes = serde_json::to_vec(state).map_err(|e| e.to_string())?;
+
+    std::fs::write(state_path(app_handle), bytes).map_err(|e| e.to_string())
+}
+
+/// Local time in the `createTime` format, shifted by a SQLite modifier.
+fn local_time_at(modifier: &str) -> Result<String, String> {
+    with_connection(|connection| {
+        connection.query_row(
+            "SELECT datetime('now', 'localtime', ?1)",
+            [modifier],
+            |row| row.get(0),
+        )
+    })
+}
+
+fn preview(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    match text.char_indices().nth(MAX_PREVIEW_CHARS) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text,
+    }
+}
+
+/// Items the store can't read, e.g. while the vault is locked, are left
+/// out. Notifications and files are seen by anyone, sensitive items never
+/// show a preview.
+fn notable_item(id: &str) -> Option<NotableItem> {
+    let item = store().get_item(id).ok()??;
+    let mut metadata = get_metadata(id).ok()?;
+
+    let preview = match item.kind.as_str() {
+        _ if metadata.get(SENSITIVE).is_some_and(|value| value == "1") => REDACTED_TEXT.to_string(),
+        "image" => String::new(),
+        "files" => serde_json::from_str::<Vec<String>>(&item.value)
+            .map(|files| preview(&files.join(", ")))
+            .unwrap_or_default(),
+        _ => preview(item.plain_text()),
+    };
+
+    Some(NotableItem {
+        source_app: metadata.remove(SOURCE_APP),
+        id: item.id,
+        kind: item.kind,
+        preview,
+    })
+}
+
+pub fn build_digest(period: DigestPeriod) -> Result<Digest, String> {
+    let until = local_time_at("+0 seconds")?;
+    let since = local_time_at(period.modifier())?;
+
+    let stats = period_stats(&since, &until, TOP_APPS)?;
+    let notable = frecent_items(&since, &until, NOTABLE_ITEMS)?
+        .iter()
+        .filter_map(|id| notable_item(id))
+        .collect();
+
+    Ok(Digest {
+        period,
+        since,
+        until,
+        stats,
+        notable,
+    })
+}
+
+/// One line for the notification.
+fn summary(digest: &Digest) -> String {
+    let mut summary = format!("{} items copied", digest.stats.total);
+
+    if let Some(top) = digest.stats.top_apps.first() {
+        summary.push_str(&format!(", most from {}", top.app));
+    }
+
+    if digest.stats.favorites > 0 {
+        summary.push_str(&format!(", {} favorited", digest.stats.favorites));
+    }
+
+    summary
+}
+
+fn render_markdown(digest: &Digest) -> String {
+    let mut markdown = format!(
+        "# Clipboard digest\n\n{} – {}\n\n## Summary\n\n- {} items copied\n- {} favorited\n",
+        digest.since, digest.until, digest.stats.total, digest.stats.favorites
+    );
+
+    for count in &digest.stats.by_type {
+        markdown.push_str(&format!("- {}: {}\n", count.kind, count.count));
+    }
+
+    if !digest.stats.top_apps.is_empty() {
+        markdown.push_str("\n## Top apps\n\n");
+
+        for (index, app) in digest.stats.top_apps.iter().enumerate() {
+            markdown.push_str(&format!("{}. {} ({})\n", index + 1, app.app, app.count));
+        }
+    }
+
+    if !digest.notable.is_empty() {
+        markdown.push_str("\n## Notable items\n\n");
+
+        for item in &digest.notable {
+            markdown.push_str(&format!("- **{}**", item.kind));
+            if !item.preview.is_empty() {
+                markdown.push_str(&format!(" {}", item.preview));
+            }
+            if let Some(app) = &item.source_app {
+                markdown.push_str(&format!(" · {}", app));
+            }
+            markdown.push('\n');
+        }
+    }
+
+    markdown
+}
+
+fn deliver<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    policy: &DigestPolicy,
+    digest: &Digest,
+) -> Result<(), String> {
+    if let Some(directory) = &policy.directory {
+        let date = digest.until.get(..10).unwrap_or(&digest.until);
+        let path = PathBuf::from(directory).join(format!("EcoPaste digest {}.md", date));
+
+        std::fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+        std::fs::write(path, render_markdown(digest)).map_err(|e| e.to_string())?;
+    }
+
+    if policy.notify {
+        app_handle
+            .notification()
+            .builder()
+            .title("Clipboard digest")
+            .body(summary(digest))
+            .show()
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Deliver a digest when a period passed since the last one. The first
+/// check only starts the period, there is no digest right after enabling.
+fn run_due<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), String> {
+    let Some(policy) = POLICY.lock().unwrap().clone() else {
+        return Ok(());
+    };
+
+    let now = local_time_at("+0 seconds")?;
+    let mut state = load_state(app_handle);
+
+    match &state.sent_at {
+        Some(sent_at) if *sent_at > local_time_at(policy.period.modifier())? => return Ok(()),
+        Some(_) => deliver(app_handle, &policy, &build_digest(policy.period)?)?,
+        None => {}
+    }
+
+    state.sent_at = Some(now);
+    save_state(app_handle, &state)
+}
+
+pub fn start_scheduler<R: Runtime>(app_handle: AppHandle<R>) {
+    thread::spawn(move || loop {
+        if let Err(error) = run_due(&app_handle) {
+            log::warn!("Failed to deliver the clipboard digest: {}", error);
+        }
+
+        thread::sleep(SCHEDULE_CHECK_INTERVAL);
+    });
+}
+
+// 设置定期的剪贴板摘要，按天或按周以通知或文件的形式发送，传入 null 关闭
+#[command]
+pub async fn set_digest_policy(policy: Option<DigestPolicy>) {
+    *POLICY.lock().unwrap() = policy;
+}
+
+// 生成最近一天或一周的剪贴板摘要，用于预览
+#[command]
+pub async fn get_digest(period: DigestPeriod) -> Result<Digest, String> {
+    tauri::async_runtime::spawn_blocking(move || build_digest(period))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,74 @@
+use crate::clipboard_files::{describe_files, ReadFilesResult};
+use crate::history::CAPTURE_SOURCE;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::{
+    command, AppHandle, DragDropEvent, Emitter, EventTarget, Manager, Runtime, Window, WindowEvent,
+};
+use tauri_plugin_eco_window::MAIN_WINDOW_LABEL;
+
+/// Emitted with content dropped onto EcoPaste, which the webview stores like
+/// a clipboard change.
+pub const DROPPED_EVENT: &str = "clipboard://dropped";
+
+/// `capture_source` of items added by a drop.
+pub const MANUAL_SOURCE: &str = "manual";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedContent {
+    /// Described like a copied file list.
+    pub files: Option<ReadFilesResult>,
+    pub text: Option<String>,
+    /// Saved as item metadata once the item is stored.
+    pub metadata: BTreeMap<String, String>,
+}
+
+fn emit_dropped<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    files: Option<ReadFilesResult>,
+    text: Option<String>,
+) {
+    let content = DroppedContent {
+        files,
+        text,
+        metadata: BTreeMap::from([(CAPTURE_SOURCE.to_string(), MANUAL_SOURCE.to_string())]),
+    };
+
+    let _ = app_handle.emit_to(
+        EventTarget::labeled(MAIN_WINDOW_LABEL),
+        DROPPED_EVENT,
+        content,
+    );
+}
+
+/// Files dropped onto the main window. The tray icon API has no drop events,
+/// so the window is the only native drop target.
+pub fn handle_window_event<R: Runtime>(window: &Window<R>, event: &WindowEvent) {
+    if window.label() != MAIN_WINDOW_LABEL {
+        return;
+    }
+
+    let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event else {
+        return;
+    };
+
+    let paths = paths
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+
+    if let Some(files) = describe_files(paths) {
+        emit_dropped(window.app_handle(), Some(files), None);
+    }
+}
+
+// 添加拖放到窗口上的文本，不经过系统剪贴板
+#[command]
+pub async fn add_dropped_text<R: Runtime>(app_handle: AppHandle<R>, text: String) {
+    if text.trim().is_empty() {
+        return;
+    }
+
+    emit_dropped(&app_handle, None, Some(text));
+}
@@ -0,0 +1,70 @@
+use crate::context::{foreground_app_name, foreground_pid};
+use crate::process_tree::lineage;
+use std::sync::Mutex;
+use tauri::command;
+
+/// Process names copies are never recorded from, set by the webview from the
+/// user's preferences.
+static EXCLUDED_APPS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Names compare without case and without the `.exe` suffix, like capture
+/// rule apps.
+fn normalize(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .trim_end_matches(".exe")
+        .to_string()
+}
+
+/// App the copy came from. On Windows the clipboard owner is the window that
+/// wrote it, which is not always the foreground one, e.g. with auto-type or
+/// a copy from a tray menu.
+pub fn source_app() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    if let Some(owner) = crate::context::clipboard_owner_exe() {
+        return Some(owner);
+    }
+
+    foreground_app_name()
+}
+
+/// Process the copy came from, resolved the same way as [`source_app`].
+pub fn source_pid() -> Option<u32> {
+    #[cfg(target_os = "windows")]
+    if let Some(owner) = crate::context::clipboard_owner_pid() {
+        return Some(owner);
+    }
+
+    foreground_pid()
+}
+
+/// Whether the current clipboard content was copied from an excluded app or
+/// from a process it started, such as the auto-type helper of a password
+/// manager.
+pub fn is_excluded_source() -> bool {
+    let apps = EXCLUDED_APPS.lock().unwrap().clone();
+    if apps.is_empty() {
+        return false;
+    }
+
+    if source_app().is_some_and(|app| apps.contains(&normalize(&app))) {
+        return true;
+    }
+
+    // Walked at capture time, helpers come and go with their parent
+    source_pid().is_some_and(|pid| {
+        lineage(pid)
+            .iter()
+            .any(|name| apps.contains(&normalize(name)))
+    })
+}
+
+// 设置不记录复制内容的应用，例如密码管理器
+#[command]
+pub async fn set_excluded_apps(apps: Vec<String>) {
+    *EXCLUDED_APPS.lock().unwrap() = apps
+        .iter()
+        .map(|app| normalize(app))
+        .filter(|app| !app.is_empty())
+        .collect();
+}
@@ -0,0 +1,212 @@
+use crate::clipboard_watcher::set_focus_paused;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+/// Emitted when the system Do Not Disturb / Focus state changes.
+pub const CHANGED_EVENT: &str = "focus-mode-changed";
+
+/// None of the platforms notify about the state without private APIs, it is
+/// polled instead.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static PAUSE: Mutex<FocusPause> = Mutex::new(FocusPause::Off);
+
+/// What EcoPaste holds back while Do Not Disturb is on.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum FocusPause {
+    Off,
+    /// Copies are recorded silently.
+    Notifications,
+    /// Copies are not recorded at all.
+    Capture,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusModeState {
+    /// Whether the system Do Not Disturb / Focus mode is on.
+    pub active: bool,
+    pub pause: FocusPause,
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    /// QUNS_ACCEPTS_NOTIFICATIONS, every other state means the user is busy,
+    /// presenting or in quiet hours / Focus Assist.
+    const QUNS_ACCEPTS_NOTIFICATIONS: i32 = 5;
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHQueryUserNotificationState(pquns: *mut i32) -> i32;
+    }
+
+    pub fn is_active() -> bool {
+        let mut state = QUNS_ACCEPTS_NOTIFICATIONS;
+        let result = unsafe { SHQueryUserNotificationState(&mut state) };
+
+        result == 0 && state != QUNS_ACCEPTS_NOTIFICATIONS
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    use std::process::Command;
+
+    /// Focus modes (macOS 12 and later) keep their active assertions here.
+    fn focus_assertions_active() -> Option<bool> {
+        let home = std::env::var_os("HOME")?;
+        let path = std::path::Path::new(&home).join("Library/DoNotDisturb/DB/Assertions.json");
+        let content = std::fs::read_to_string(path).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+        let active = json
+            .get("data")?
+            .as_array()?
+            .iter()
+            .filter_map(|entry| entry.get("storeAssertionRecords")?.as_array())
+            .any(|records| !records.is_empty());
+
+        Some(active)
+    }
+
+    /// Do Not Disturb of macOS 11 and earlier.
+    fn legacy_do_not_disturb() -> bool {
+        Command::new("defaults")
+            .args([
+                "-currentHost",
+                "read",
+                "com.apple.notificationcenterui",
+                "doNotDisturb",
+            ])
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1")
+    }
+
+    pub fn is_active() -> bool {
+        focus_assertions_active().unwrap_or_else(legacy_do_not_disturb)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::process::Command;
+
+    fn output(program: &str, args: &[&str]) -> Option<String> {
+        let output = Command::new(program).args(args).output().ok()?;
+
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// GNOME turns banners off for Do Not Disturb.
+    fn gnome_do_not_disturb() -> Option<bool> {
+        let banners = output(
+            "gsettings",
+            &["get", "org.gnome.desktop.notifications", "show-banners"],
+        )?;
+
+        Some(banners == "false")
+    }
+
+    /// KDE Plasma and other notification servers expose `Inhibited`.
+    fn notifications_inhibited() -> Option<bool> {
+        let inhibited = output(
+            "busctl",
+            &[
+                "--user",
+                "get-property",
+                "org.freedesktop.Notifications",
+                "/org/freedesktop/Notifications",
+                "org.freedesktop.Notifications",
+                "Inhibited",
+            ],
+        )?;
+
+        Some(inhibited == "b true")
+    }
+
+    pub fn is_active() -> bool {
+        gnome_do_not_disturb()
+            .filter(|active| *active)
+            .or_else(notifications_inhibited)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::is_active;
+
+#[cfg(target_os = "macos")]
+use mac::is_active;
+
+#[cfg(target_os = "linux")]
+use linux::is_active;
+
+fn state() -> FocusModeState {
+    FocusModeState {
+        active: ACTIVE.load(Ordering::Relaxed),
+        pause: *PAUSE.lock().unwrap(),
+    }
+}
+
+/// Pause or resume capture for the current state and option.
+fn apply() {
+    let state = state();
+
+    set_focus_paused(state.active && state.pause == FocusPause::Capture);
+}
+
+fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        // Nothing to follow while the option is off
+        let active = *PAUSE.lock().unwrap() != FocusPause::Off && is_active();
+
+        if ACTIVE.swap(active, Ordering::Relaxed) != active {
+            apply();
+
+            let _ = app_handle.emit(CHANGED_EVENT, state());
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Start following the system Do Not Disturb state, only once.
+pub fn start<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let spawned = thread::Builder::new()
+        .name("focus-mode".to_string())
+        .spawn(move || watch(app_handle));
+
+    if let Err(error) = spawned {
+        STARTED.store(false, Ordering::SeqCst);
+        log::error!("Failed to spawn the focus mode watcher: {}", error);
+    }
+}
+
+// 设置勿扰模式开启时暂停的内容
+#[command]
+pub async fn set_focus_pause(pause: FocusPause) {
+    *PAUSE.lock().unwrap() = pause;
+
+    apply();
+}
+
+// 获取系统勿扰模式的状态
+#[command]
+pub async fn get_focus_mode() -> FocusModeState {
+    state()
+}
@@ -0,0 +1,412 @@
+use super::collation::{self, fold_text};
+use super::encryption::{open_row, seal_history, seal_metadata};
+use super::entries::delete_formats;
+use super::redaction::{is_redacting, redact, REDACTED_TEXT};
+use super::search::{snippet_of, split_terms, Highlight};
+use super::store::{history_column_list, history_row};
+use super::{database_path, with_connection, HistoryRow, BLOB_PATH, SENSITIVE};
+use crate::item_access::within_grace_period;
+use crate::vault;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OpenFlags};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+const DEFAULT_LIMIT: usize = 50;
+
+/// Largest number of matches returned by a single archive search.
+const MAX_LIMIT: usize = 200;
+
+/// Schema of a month archive, the `history` table mirrors the one created
+/// by the history migration.
+const ARCHIVE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS archive.history (
+        id TEXT PRIMARY KEY,
+        type TEXT,
+        \"group\" TEXT,
+        value TEXT,
+        search TEXT,
+        count INTEGER,
+        width INTEGER,
+        height INTEGER,
+        favorite INTEGER DEFAULT 0,
+        createTime TEXT,
+        note TEXT,
+        subtype TEXT
+    );
+    CREATE INDEX IF NOT EXISTS archive.history_create_time ON history (createTime);
+    CREATE TABLE IF NOT EXISTS archive.history_metadata (
+        item_id TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (item_id, key)
+    );";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveMatch {
+    pub item: HistoryRow,
+    /// Month of the archive the item was found in, `YYYY-MM`.
+    pub month: String,
+    pub snippet: String,
+    pub highlights: Vec<Highlight>,
+}
+
+/// Next to the database and named after it, so the development database
+/// keeps its own archives.
+fn archives_dir() -> Result<PathBuf, String> {
+    Ok(database_path()?.with_extension("archives"))
+}
+
+fn is_month(month: &str) -> bool {
+    let bytes = month.as_bytes();
+
+    bytes.len() == 7
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            4 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+/// Month archives with their month, newest first.
+fn archive_files() -> Result<Vec<(String, PathBuf)>, String> {
+    let Ok(entries) = std::fs::read_dir(archives_dir()?) else {
+        return Ok(Vec::new());
+    };
+
+    let mut files: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let month = path.file_name()?.to_str()?.strip_suffix(".db")?.to_string();
+
+            is_month(&month).then_some((month, path))
+        })
+        .collect();
+
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+
+    Ok(files)
+}
+
+/// Archives are never written after the month was moved, except by a later
+/// archival run, queries open them read-only.
+fn open_archive(path: &Path) -> Result<Connection, String> {
+    let connection = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| e.to_string())?;
+
+    collation::register(&connection).map_err(|e| e.to_string())?;
+
+    Ok(connection)
+}
+
+/// Move the non-favorite items of one month into the attached archive.
+fn move_month(connection: &Connection, month: &str, cutoff: &str) -> rusqlite::Result<usize> {
+    let transaction = connection.unchecked_transaction()?;
+    let filter = "favorite = 0 AND createTime < ?1 AND substr(createTime, 1, 7) = ?2";
+    let columns = history_column_list();
+
+    transaction.execute_batch(ARCHIVE_SCHEMA)?;
+
+    let ids: Vec<String> = {
+        let mut statement =
+            transaction.prepare(&format!("SELECT id FROM main.history WHERE {}", filter))?;
+        let rows = statement.query_map(params![cutoff, month], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    transaction.execute(
+        &format!(
+            "INSERT OR REPLACE INTO archive.history ({columns})
+            SELECT {columns} FROM main.history WHERE {filter}"
+        ),
+        params![cutoff, month],
+    )?;
+    transaction.execute(
+        &format!(
+            "INSERT OR REPLACE INTO archive.history_metadata (item_id, key, value)
+            SELECT item_id, key, value FROM main.history_metadata
+            WHERE item_id IN (SELECT id FROM main.history WHERE {filter})"
+        ),
+        params![cutoff, month],
+    )?;
+
+    for id in &ids {
+        transaction.execute(
+            "DELETE FROM main.history_metadata WHERE item_id = ?1",
+            params![id],
+        )?;
+        delete_formats(&transaction, id)?;
+    }
+
+    transaction.execute(
+        &format!("DELETE FROM main.history WHERE {}", filter),
+        params![cutoff, month],
+    )?;
+
+    transaction.commit()?;
+
+    Ok(ids.len())
+}
+
+/// Move items created before the start of the month `months` ago into one
+/// archive database per month, favorites stay in the history. Returns the
+/// number of archived items.
+pub fn archive_older_than(months: u32) -> Result<usize, String> {
+    let dir = archives_dir()?;
+
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    with_connection(|connection| {
+        let cutoff: String = connection.query_row(
+            "SELECT date('now', 'localtime', 'start of month', ?1)",
+            params![format!("-{} months", months)],
+            |row| row.get(0),
+        )?;
+
+        let due: Vec<String> = {
+            let mut statement = connection.prepare(
+                "SELECT DISTINCT substr(createTime, 1, 7) FROM history
+                WHERE favorite = 0 AND createTime < ?1",
+            )?;
+            let rows = statement.query_map(params![cutoff], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut archived = 0;
+
+        for month in due.iter().filter(|month| is_month(month)) {
+            let path = dir.join(format!("{}.db", month));
+
+            // ATTACH is not allowed inside a transaction
+            connection.execute(
+                "ATTACH DATABASE ?1 AS archive",
+                params![path.to_string_lossy()],
+            )?;
+            let moved = move_month(connection, month, &cutoff);
+            connection.execute("DETACH DATABASE archive", [])?;
+
+            archived += moved?;
+        }
+
+        // Deleted rows only shrink the file once it is rebuilt
+        if archived > 0 {
+            connection.execute_batch("VACUUM")?;
+        }
+
+        Ok(archived)
+    })
+}
+
+/// Encrypt or decrypt the archived months along with the history, each
+/// archive is rebuilt afterwards so no page keeps the old content.
+pub(super) fn seal_archives(seal: bool) -> Result<(), String> {
+    for (_, path) in archive_files()? {
+        let connection = Connection::open(&path).map_err(|e| e.to_string())?;
+
+        let sealed = || -> rusqlite::Result<()> {
+            let transaction = connection.unchecked_transaction()?;
+
+            seal_history(&transaction, seal)?;
+            seal_metadata(&transaction, seal)?;
+
+            transaction.commit()?;
+
+            connection.execute_batch("VACUUM")
+        };
+
+        sealed().map_err(|e| format!("Failed to convert the archive {:?}: {}", path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Image values of archived items, their files are still in use.
+pub fn archived_image_values() -> Result<Vec<String>, String> {
+    let mut values = Vec::new();
+
+    for (_, path) in archive_files()? {
+        let connection = open_archive(&path)?;
+
+        let mut statement = connection
+            .prepare("SELECT value FROM history WHERE type = 'image'")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, Option<String>>(0))
+            .map_err(|e| e.to_string())?;
+
+        for value in rows.flatten().flatten() {
+            values.push(value);
+        }
+    }
+
+    Ok(values)
+}
+
+/// Overflow blobs of archived items, their files are still in use.
+pub fn archived_blob_paths() -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+
+    for (_, path) in archive_files()? {
+        let connection = open_archive(&path)?;
+
+        let mut statement = connection
+            .prepare("SELECT value FROM history_metadata WHERE key = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map(params![BLOB_PATH], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+
+        paths.extend(rows.flatten());
+    }
+
+    Ok(paths)
+}
+
+/// Whether every term is in the search text or the note of a row.
+fn matches_terms(row: &HistoryRow, terms: &[String]) -> bool {
+    let text = ["search", "note"]
+        .iter()
+        .filter_map(|column| row.get(*column).and_then(Value::as_str))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = fold_text(&text);
+
+    terms.iter().all(|term| text.contains(term.as_str()))
+}
+
+/// Archives have no full-text index, every term is matched by a scan of
+/// the search text and the note. Encrypted archives are scanned once
+/// decrypted, nothing matches while the vault is locked.
+fn search_archive(
+    path: &Path,
+    terms: &[String],
+    limit: usize,
+) -> Result<Vec<(HistoryRow, bool)>, String> {
+    let sealed = vault::is_enabled();
+
+    if sealed && !vault::is_unlocked() {
+        return Ok(Vec::new());
+    }
+
+    let connection = open_archive(path)?;
+
+    let conditions = if sealed {
+        vec!["1"]
+    } else {
+        vec![
+            "instr(fold(coalesce(search, '') || char(10) || coalesce(note, '')), ?) > 0";
+            terms.len()
+        ]
+    };
+    let values: Vec<SqlValue> = terms
+        .iter()
+        .filter(|_| !sealed)
+        .map(|term| SqlValue::Text(term.clone()))
+        .collect();
+    // Encrypted rows are limited once matched
+    let limit_clause = if sealed {
+        String::new()
+    } else {
+        format!("LIMIT {}", limit)
+    };
+
+    let mut statement = connection
+        .prepare(&format!(
+            "SELECT {}, EXISTS (
+                SELECT 1 FROM history_metadata
+                WHERE item_id = history.id AND key = '{}' AND value = '1'
+            )
+            FROM history WHERE {} ORDER BY createTime DESC {}",
+            history_column_list(),
+            SENSITIVE,
+            conditions.join(" AND "),
+            limit_clause
+        ))
+        .map_err(|e| e.to_string())?;
+    let sensitive_index = statement.column_count() - 1;
+
+    let rows = statement
+        .query_map(params_from_iter(values.iter()), |row| {
+            Ok((history_row(row)?, row.get::<_, bool>(sensitive_index)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let rows: Vec<(HistoryRow, bool)> = rows
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+
+    if !sealed {
+        return Ok(rows);
+    }
+
+    let mut matches = Vec::new();
+
+    for (mut item, sensitive) in rows {
+        if matches.len() == limit {
+            break;
+        }
+
+        open_row(&mut item)?;
+
+        if matches_terms(&item, terms) {
+            matches.push((item, sensitive));
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Search the month archives newest first until `limit` matches are found.
+pub fn search_archived(query: &str, limit: usize) -> Result<Vec<ArchiveMatch>, String> {
+    let terms = split_terms(query);
+    let mut matches = Vec::new();
+
+    if terms.is_empty() {
+        return Ok(matches);
+    }
+
+    for (month, path) in archive_files()? {
+        let remaining = limit - matches.len();
+
+        if remaining == 0 {
+            break;
+        }
+
+        for (mut item, sensitive) in search_archive(&path, &terms, remaining)? {
+            let (mut snippet, mut highlights) = snippet_of(&item, &terms);
+
+            // Neither the excerpt nor its highlights may give the content away
+            if sensitive && (is_redacting() || !within_grace_period()) {
+                redact(&mut item);
+                snippet = REDACTED_TEXT.to_string();
+                highlights.clear();
+            }
+
+            matches.push(ArchiveMatch {
+                item,
+                month: month.clone(),
+                snippet,
+                highlights,
+            });
+        }
+    }
+
+    Ok(matches)
+}
+
+// 搜索已归档的历史记录，从最近的月份开始逐个查找
+#[command]
+pub async fn search_archives(
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ArchiveMatch>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    search_archived(&query, limit)
+}
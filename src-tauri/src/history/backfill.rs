@@ -0,0 +1,450 @@
+use super::search::fts_content;
+use super::{get_metadata, phash::phash, with_connection, OCR_TEXT};
+use crate::clipboard_image::resolve_image_path;
+use crate::ocr::{recognize_text, save_recognized_text};
+use crate::vault;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+/// Rows handed to the workers per checkpoint.
+const BATCH_SIZE: i64 = 256;
+
+const PROGRESS_EVENT: &str = "backfill-progress";
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// What a pass computes, the checkpoint keeps the set its cursor covers.
+const FEATURE_FTS: i64 = 1;
+const FEATURE_PHASH: i64 = 1 << 1;
+const FEATURE_OCR: i64 = 1 << 2;
+
+/// Backfills from before features were tracked indexed text and hashes.
+const LEGACY_FEATURES: i64 = FEATURE_FTS | FEATURE_PHASH;
+
+/// Optional work done next to the full-text index.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BackfillFeatures {
+    pub phash: bool,
+    pub ocr: bool,
+}
+
+impl BackfillFeatures {
+    fn bits(&self) -> i64 {
+        let mut bits = FEATURE_FTS;
+
+        if self.phash {
+            bits |= FEATURE_PHASH;
+        }
+
+        if self.ocr {
+            bits |= FEATURE_OCR;
+        }
+
+        bits
+    }
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackfillProgress {
+    pub processed: u64,
+    pub total: u64,
+    pub running: bool,
+}
+
+struct PendingRow {
+    rowid: i64,
+    id: String,
+    kind: String,
+    value: String,
+    search: String,
+    note: String,
+}
+
+struct IndexedRow {
+    id: String,
+    content: String,
+    phash: Option<u64>,
+    ocr: Option<String>,
+}
+
+struct Checkpoint {
+    cursor: i64,
+    processed: u64,
+    active: bool,
+    features: i64,
+}
+
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    let fts: Option<String> = connection
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'history_fts'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    // Indexes from before diacritics were folded are built again from scratch
+    let rebuild = fts.is_some_and(|sql| !sql.contains("remove_diacritics"));
+    if rebuild {
+        connection.execute_batch("DROP TABLE history_fts;")?;
+    }
+
+    connection.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5 (
+            item_id UNINDEXED,
+            content,
+            tokenize = 'trigram remove_diacritics 1'
+        );
+        CREATE TABLE IF NOT EXISTS history_phash (
+            item_id TEXT PRIMARY KEY,
+            hash INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS backfill_checkpoint (
+            id INTEGER PRIMARY KEY CHECK (id = 0),
+            cursor INTEGER NOT NULL,
+            processed INTEGER NOT NULL,
+            active INTEGER NOT NULL
+        );",
+    )?;
+
+    let has_features: bool = connection.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('backfill_checkpoint') WHERE name = 'features'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if !has_features {
+        connection.execute(
+            &format!(
+                "ALTER TABLE backfill_checkpoint ADD COLUMN features INTEGER NOT NULL DEFAULT {}",
+                LEGACY_FEATURES
+            ),
+            [],
+        )?;
+    }
+
+    if rebuild {
+        connection.execute(
+            "INSERT INTO backfill_checkpoint (id, cursor, processed, active) VALUES (0, 0, 0, 1)
+             ON CONFLICT (id) DO UPDATE SET cursor = 0, processed = 0, active = 1",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn read_checkpoint(connection: &Connection) -> rusqlite::Result<Checkpoint> {
+    let checkpoint = connection
+        .query_row(
+            "SELECT cursor, processed, active, features FROM backfill_checkpoint WHERE id = 0",
+            [],
+            |row| {
+                Ok(Checkpoint {
+                    cursor: row.get(0)?,
+                    processed: row.get(1)?,
+                    active: row.get(2)?,
+                    features: row.get(3)?,
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(checkpoint.unwrap_or(Checkpoint {
+        cursor: 0,
+        processed: 0,
+        active: false,
+        features: FEATURE_FTS,
+    }))
+}
+
+fn write_checkpoint(connection: &Connection, checkpoint: &Checkpoint) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO backfill_checkpoint (id, cursor, processed, active, features)
+         VALUES (0, ?1, ?2, ?3, ?4)
+         ON CONFLICT (id) DO UPDATE SET
+            cursor = excluded.cursor,
+            processed = excluded.processed,
+            active = excluded.active,
+            features = excluded.features",
+        params![
+            checkpoint.cursor,
+            checkpoint.processed,
+            checkpoint.active,
+            checkpoint.features
+        ],
+    )?;
+
+    Ok(())
+}
+
+fn progress() -> Result<BackfillProgress, String> {
+    with_connection(|connection| {
+        let checkpoint = read_checkpoint(connection)?;
+        let remaining: u64 = connection.query_row(
+            "SELECT COUNT(*) FROM history WHERE rowid > ?1",
+            params![checkpoint.cursor],
+            |row| row.get(0),
+        )?;
+
+        Ok(BackfillProgress {
+            processed: checkpoint.processed,
+            total: checkpoint.processed + remaining,
+            running: RUNNING.load(Ordering::Relaxed),
+        })
+    })
+}
+
+fn next_batch(cursor: i64) -> Result<Vec<PendingRow>, String> {
+    with_connection(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT rowid, id, type, value, search, note FROM history
+             WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+        )?;
+
+        let rows = statement.query_map(params![cursor, BATCH_SIZE], |row| {
+            Ok(PendingRow {
+                rowid: row.get(0)?,
+                id: row.get(1)?,
+                kind: row.get(2)?,
+                value: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                search: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                note: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            })
+        })?;
+
+        rows.collect()
+    })
+}
+
+/// Text of an image not recognized yet. The recognizers read the file
+/// themselves, so nothing is recognized while the vault is enabled.
+fn recognize_pending(id: &str, path: &PathBuf) -> Option<String> {
+    if vault::is_enabled() {
+        return None;
+    }
+
+    let recognized = get_metadata(id).is_ok_and(|metadata| metadata.contains_key(OCR_TEXT));
+    if recognized {
+        return None;
+    }
+
+    recognize_text(path).ok().filter(|text| !text.is_empty())
+}
+
+/// Hashing and recognizing decode every image, so batches are spread
+/// across all cores.
+fn index_batch(rows: Vec<(PendingRow, Option<PathBuf>)>, features: i64) -> Vec<IndexedRow> {
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = rows.len().div_ceil(workers).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = rows
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(row, image_path)| IndexedRow {
+                            id: row.id.clone(),
+                            content: fts_content(&row.search, &row.note),
+                            phash: image_path
+                                .as_deref()
+                                .filter(|_| features & FEATURE_PHASH != 0)
+                                .and_then(|path| phash(path).ok()),
+                            ocr: image_path
+                                .as_ref()
+                                .filter(|_| features & FEATURE_OCR != 0)
+                                .and_then(|path| recognize_pending(&row.id, path)),
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    })
+}
+
+fn store_batch(indexed: &[IndexedRow], cursor: i64) -> Result<(), String> {
+    with_connection(|connection| {
+        let transaction = connection.unchecked_transaction()?;
+
+        for row in indexed {
+            transaction.execute(
+                "DELETE FROM history_fts WHERE item_id = ?1",
+                params![row.id],
+            )?;
+
+            if !row.content.trim().is_empty() {
+                transaction.execute(
+                    "INSERT INTO history_fts (item_id, content) VALUES (?1, ?2)",
+                    params![row.id, row.content],
+                )?;
+            }
+
+            if let Some(hash) = row.phash {
+                transaction.execute(
+                    "INSERT OR REPLACE INTO history_phash (item_id, hash) VALUES (?1, ?2)",
+                    params![row.id, hash as i64],
+                )?;
+            }
+        }
+
+        // The checkpoint commits together with the batch it covers
+        let checkpoint = read_checkpoint(&transaction)?;
+        write_checkpoint(
+            &transaction,
+            &Checkpoint {
+                cursor,
+                processed: checkpoint.processed + indexed.len() as u64,
+                active: true,
+                ..checkpoint
+            },
+        )?;
+
+        transaction.commit()
+    })
+}
+
+fn run<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), String> {
+    loop {
+        let checkpoint = with_connection(read_checkpoint)?;
+        let batch = next_batch(checkpoint.cursor)?;
+
+        let Some(cursor) = batch.last().map(|row| row.rowid) else {
+            break;
+        };
+
+        let rows = batch
+            .into_iter()
+            .map(|row| {
+                let image_path =
+                    (row.kind == "image").then(|| resolve_image_path(app_handle, &row.value));
+
+                (row, image_path)
+            })
+            .collect();
+
+        let indexed = index_batch(rows, checkpoint.features);
+
+        // Saved before the checkpoint moves past them, the search column
+        // reaches the index through its triggers
+        for row in &indexed {
+            if let Some(text) = &row.ocr {
+                save_recognized_text(&row.id, text)?;
+            }
+        }
+
+        store_batch(&indexed, cursor)?;
+
+        let _ = app_handle.emit(PROGRESS_EVENT, progress()?);
+    }
+
+    with_connection(|connection| {
+        let checkpoint = read_checkpoint(connection)?;
+
+        write_checkpoint(
+            connection,
+            &Checkpoint {
+                active: false,
+                ..checkpoint
+            },
+        )
+    })
+}
+
+/// Run the backfill on the calling thread unless one is already running.
+pub fn backfill<R: Runtime>(app_handle: &AppHandle<R>) {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    if let Err(error) = run(app_handle) {
+        log::warn!("History backfill stopped: {}", error);
+    }
+
+    RUNNING.store(false, Ordering::SeqCst);
+
+    if let Ok(progress) = progress() {
+        let _ = app_handle.emit(PROGRESS_EVENT, progress);
+    }
+}
+
+/// Continue a backfill that was interrupted by quitting the app.
+pub fn resume_backfill<R: Runtime>(app_handle: AppHandle<R>) {
+    let active = with_connection(read_checkpoint).is_ok_and(|checkpoint| checkpoint.active);
+
+    if active {
+        thread::spawn(move || backfill(&app_handle));
+    }
+}
+
+// 开始为历史记录建立全文索引，并按需计算图片指纹、识别图片文字，已完成的部分不会重复处理
+#[command]
+pub async fn start_backfill<R: Runtime>(
+    app_handle: AppHandle<R>,
+    features: Option<BackfillFeatures>,
+) -> Result<(), String> {
+    let features = features.unwrap_or_default().bits();
+
+    with_connection(|connection| {
+        let checkpoint = read_checkpoint(connection)?;
+
+        // The cursor only covers the features it was advanced for, any
+        // other set starts over from the first row
+        let checkpoint = if checkpoint.features == features {
+            checkpoint
+        } else {
+            Checkpoint {
+                cursor: 0,
+                processed: 0,
+                active: true,
+                features,
+            }
+        };
+
+        write_checkpoint(
+            connection,
+            &Checkpoint {
+                active: true,
+                ..checkpoint
+            },
+        )
+    })?;
+
+    thread::spawn(move || backfill(&app_handle));
+
+    Ok(())
+}
+
+// 获取索引的进度
+#[command]
+pub async fn get_backfill_progress() -> Result<BackfillProgress, String> {
+    progress()
+}
+
+// 全文搜索，返回匹配的条目 id
+#[command]
+pub async fn search_fulltext(query: String) -> Result<Vec<String>, String> {
+    let query = format!("\"{}\"", query.trim().replace('"', "\"\""));
+
+    with_connection(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT item_id FROM history_fts WHERE history_fts MATCH ?1 ORDER BY rank",
+        )?;
+
+        let rows = statement.query_map(params![query], |row| row.get(0))?;
+
+        rows.collect()
+    })
+}
@@ -0,0 +1,124 @@
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::sync::Mutex;
+use tauri::command;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// SQL collation ordering text by the rules of the configured locale, e.g.
+/// `ORDER BY name COLLATE locale`.
+pub const LOCALE_COLLATION: &str = "locale";
+
+/// BCP 47 tag set by the webview, the root collation is used until then.
+static LOCALE: Mutex<Option<String>> = Mutex::new(None);
+
+thread_local! {
+    /// Collators are not `Send`, every thread that queries keeps its own for
+    /// the locale it was built for.
+    static COLLATOR: RefCell<Option<(String, Option<Collator>)>> = const { RefCell::new(None) };
+}
+
+/// Letters that don't decompose into a base letter and marks.
+fn fold_special(ch: char) -> Option<&'static str> {
+    let folded = match ch {
+        'ß' | 'ẞ' => "ss",
+        'æ' | 'Æ' => "ae",
+        'œ' | 'Œ' => "oe",
+        'ø' | 'Ø' => "o",
+        'đ' | 'Đ' => "d",
+        'ł' | 'Ł' => "l",
+        'ı' => "i",
+        _ => return None,
+    };
+
+    Some(folded)
+}
+
+/// Case and diacritic insensitive form of a text, `Über` and `uber` fold the
+/// same. Compatibility forms fold too, such as full-width letters and the
+/// `ﬁ` ligature.
+pub fn fold_text(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+
+    for ch in text.nfkd().filter(|ch| !is_combining_mark(*ch)) {
+        match fold_special(ch) {
+            Some(special) => folded.push_str(special),
+            None => folded.extend(ch.to_lowercase()),
+        }
+    }
+
+    folded
+}
+
+/// [`fold_text`] of a single character that keeps it a single character, so
+/// positions in a folded text line up with the original.
+pub fn fold_char(ch: char) -> char {
+    let base = std::iter::once(ch)
+        .nfd()
+        .find(|part| !is_combining_mark(*part))
+        .unwrap_or(ch);
+
+    match fold_special(base) {
+        Some(special) if special.chars().count() == 1 => special.chars().next().unwrap_or(base),
+        _ => base.to_lowercase().next().unwrap_or(base),
+    }
+}
+
+fn collator_for(locale: &str) -> Option<Collator> {
+    let locale: Locale = if locale.is_empty() {
+        Locale::UND
+    } else {
+        locale.parse().ok()?
+    };
+
+    Collator::try_new(&locale.into(), CollatorOptions::new()).ok()
+}
+
+/// Compare by the rules of the configured locale, by code point when it has
+/// no collation data.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let locale = LOCALE.lock().unwrap().clone().unwrap_or_default();
+
+    COLLATOR.with(|cell| {
+        let mut cached = cell.borrow_mut();
+
+        if cached
+            .as_ref()
+            .map_or(true, |(current, _)| *current != locale)
+        {
+            *cached = Some((locale.clone(), collator_for(&locale)));
+        }
+
+        match cached.as_ref().and_then(|(_, collator)| collator.as_ref()) {
+            Some(collator) => collator.compare(a, b),
+            None => a.cmp(b),
+        }
+    })
+}
+
+/// Make `fold()` and the locale collation available to queries on a newly
+/// opened connection.
+pub(super) fn register(connection: &Connection) -> rusqlite::Result<()> {
+    connection.create_scalar_function(
+        "fold",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |context| {
+            let text = context.get::<Option<String>>(0)?;
+
+            Ok(text.map(|text| fold_text(&text)))
+        },
+    )?;
+
+    connection.create_collation(LOCALE_COLLATION, compare)
+}
+
+// 设置排序所用的语言，例如 de、zh-CN，空值使用通用规则
+#[command]
+pub async fn set_collation_locale(locale: Option<String>) {
+    *LOCALE.lock().unwrap() = locale.map(|locale| locale.replace('_', "-"));
+}
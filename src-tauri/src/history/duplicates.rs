@@ -0,0 +1,467 @@
+use super::phash::{phash, save_phash};
+use super::redaction::redacted_among;
+use super::{
+    commit_operation, notify_changed, store, with_connection, HistoryRow, Operation, TAGS,
+};
+use crate::clipboard_image::resolve_image_path;
+use crate::vault;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Runtime};
+
+/// Images whose perceptual hashes differ in at most this many bits are
+/// considered the same picture.
+const SIMILAR_IMAGE_BITS: u32 = 6;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateMode {
+    /// Same type and value, images by file content.
+    Exact,
+    /// Text compared ignoring case and whitespace.
+    Normalized,
+    /// Images that look alike, from their perceptual hashes.
+    SimilarImages,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    /// Newest first, the first one is the suggested item to keep.
+    pub ids: Vec<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Freed by keeping only one item of the group.
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    /// Most reclaimable first.
+    pub groups: Vec<DuplicateGroup>,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarImage {
+    pub id: String,
+    /// Bits the perceptual hashes differ in, 0 for the same picture.
+    pub distance: u32,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeReport {
+    pub removed: usize,
+    pub reclaimed_bytes: u64,
+}
+
+struct ScannedItem {
+    id: String,
+    kind: String,
+    value: String,
+    search: String,
+    bytes: u64,
+    image_path: Option<PathBuf>,
+}
+
+/// Every item, newest first. Sensitive items that are hidden right now are
+/// left out, grouping them with other items would tell their content.
+fn scan<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Vec<ScannedItem>, String> {
+    let mut rows: Vec<(String, String, String, String)> = with_connection(|connection| {
+        let mut statement =
+            connection.prepare("SELECT id, type, value, search FROM history ORDER BY seq DESC")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            ))
+        })?;
+
+        rows.collect()
+    })?;
+
+    let redacted = redacted_among(rows.iter().map(|(id, ..)| id.as_str()))?;
+    rows.retain(|(id, ..)| !redacted.contains(id));
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, kind, value, search)| {
+            let image_path = (kind == "image").then(|| resolve_image_path(app_handle, &value));
+            let bytes = match &image_path {
+                Some(path) => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+                None => value.len() as u64,
+            };
+
+            ScannedItem {
+                id,
+                kind,
+                value,
+                search,
+                bytes,
+                image_path,
+            }
+        })
+        .collect())
+}
+
+/// Lowercased with runs of whitespace collapsed.
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    let bytes = vault::read_file(path).ok()?;
+
+    Some(format!("{:x}", Sha256::digest(bytes)))
+}
+
+/// Group key of an item, `None` when it cannot be compared in this mode.
+fn duplicate_key(item: &ScannedItem, mode: DuplicateMode) -> Option<String> {
+    if let Some(path) = &item.image_path {
+        return hash_file(path).map(|hash| format!("image:{}", hash));
+    }
+
+    match mode {
+        DuplicateMode::Normalized if item.kind != "files" => {
+            let text = normalize_text(&item.search);
+
+            (!text.is_empty()).then(|| format!("text:{}", text))
+        }
+        _ => Some(format!("{}:{}", item.kind, item.value)),
+    }
+}
+
+/// Perceptual hashes of image items, computing and saving the ones the
+/// backfill has not indexed yet.
+fn image_hashes(items: &[&ScannedItem]) -> Result<Vec<Option<u64>>, String> {
+    let stored: HashMap<String, u64> = with_connection(|connection| {
+        let mut statement = connection.prepare("SELECT item_id, hash FROM history_phash")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+
+        rows.collect()
+    })?;
+
+    let mut hashes = Vec::with_capacity(items.len());
+    for item in items {
+        if let Some(&hash) = stored.get(&item.id) {
+            hashes.push(Some(hash));
+            continue;
+        }
+
+        let hash = item.image_path.as_deref().and_then(|path| phash(path).ok());
+        if let Some(hash) = hash {
+            save_phash(&item.id, hash)?;
+        }
+
+        hashes.push(hash);
+    }
+
+    Ok(hashes)
+}
+
+/// Representative of a union-find set, halving paths on the way.
+fn root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+
+    index
+}
+
+/// Clusters of images linked by small hash distances.
+fn similar_image_groups(items: &[ScannedItem]) -> Result<Vec<Vec<&ScannedItem>>, String> {
+    let images: Vec<&ScannedItem> = items
+        .iter()
+        .filter(|item| item.image_path.is_some())
+        .collect();
+    let hashes = image_hashes(&images)?;
+
+    let mut parents: Vec<usize> = (0..images.len()).collect();
+
+    for a in 0..images.len() {
+        let Some(hash_a) = hashes[a] else {
+            continue;
+        };
+
+        for b in a + 1..images.len() {
+            let Some(hash_b) = hashes[b] else {
+                continue;
+            };
+
+            if (hash_a ^ hash_b).count_ones() <= SIMILAR_IMAGE_BITS {
+                let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+                parents[root_b] = root_a;
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<&ScannedItem>> = BTreeMap::new();
+    for index in 0..images.len() {
+        let cluster = root(&mut parents, index);
+        clusters.entry(cluster).or_default().push(images[index]);
+    }
+
+    Ok(clusters.into_values().collect())
+}
+
+/// Image items looking like the image at `path`, closest first. A bare
+/// file name is looked up among the history images.
+pub fn similar_images<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    path: &str,
+    threshold: u32,
+) -> Result<Vec<SimilarImage>, String> {
+    let target = phash(&resolve_image_path(app_handle, path))?;
+
+    let items = scan(app_handle)?;
+    let images: Vec<&ScannedItem> = items
+        .iter()
+        .filter(|item| item.image_path.is_some())
+        .collect();
+    let hashes = image_hashes(&images)?;
+
+    let mut similar: Vec<SimilarImage> = images
+        .iter()
+        .zip(hashes)
+        .filter_map(|(item, hash)| {
+            let distance = (hash? ^ target).count_ones();
+
+            (distance <= threshold).then(|| SimilarImage {
+                id: item.id.clone(),
+                distance,
+            })
+        })
+        .collect();
+
+    // Stable, equally close images stay newest first
+    similar.sort_by_key(|image| image.distance);
+
+    Ok(similar)
+}
+
+/// Scan the whole history for items stored more than once.
+pub fn find_duplicate_groups<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    mode: DuplicateMode,
+) -> Result<DuplicateReport, String> {
+    let items = scan(app_handle)?;
+
+    let clusters: Vec<Vec<&ScannedItem>> = match mode {
+        DuplicateMode::SimilarImages => similar_image_groups(&items)?,
+        _ => {
+            let mut keyed: BTreeMap<String, Vec<&ScannedItem>> = BTreeMap::new();
+            for item in &items {
+                if let Some(key) = duplicate_key(item, mode) {
+                    keyed.entry(key).or_default().push(item);
+                }
+            }
+
+            keyed.into_values().collect()
+        }
+    };
+
+    let mut groups: Vec<DuplicateGroup> = clusters
+        .into_iter()
+        .filter(|cluster| cluster.len() > 1)
+        .map(|cluster| {
+            // Items are scanned newest first, the first one is kept
+            let reclaimable_bytes = cluster[1..].iter().map(|item| item.bytes).sum();
+
+            DuplicateGroup {
+                kind: cluster[0].kind.clone(),
+                ids: cluster.iter().map(|item| item.id.clone()).collect(),
+                reclaimable_bytes,
+            }
+        })
+        .collect();
+
+    groups.sort_by(|a, b| b.reclaimable_bytes.cmp(&a.reclaimable_bytes));
+
+    Ok(DuplicateReport {
+        reclaimable_bytes: groups.iter().map(|group| group.reclaimable_bytes).sum(),
+        groups,
+    })
+}
+
+fn text_of<'a>(row: &'a HistoryRow, column: &str) -> &'a str {
+    row.get(column).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// Merge a group into `keep`: it takes over the favorite flag, a note,
+/// tags, metadata and the latest copy time, then the others are deleted
+/// through the operation log so the merge can be undone.
+pub fn merge_duplicate_group<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    group: &[String],
+    keep: &str,
+) -> Result<MergeReport, String> {
+    if !group.iter().any(|id| id == keep) {
+        return Err(format!("History item {} is not in the group", keep));
+    }
+
+    let store = store();
+    let kept = store
+        .get_row(keep)?
+        .ok_or_else(|| format!("History item {} not found", keep))?;
+    let mut kept_metadata = store.get_metadata(keep)?;
+
+    let mut others = Vec::new();
+    for id in group.iter().filter(|id| *id != keep) {
+        if let Some(row) = store.get_row(id)? {
+            let metadata = store.get_metadata(id)?;
+            others.push((row, metadata));
+        }
+    }
+
+    let mut after = HistoryRow::new();
+
+    let favorite = |row: &HistoryRow| row.get("favorite").and_then(Value::as_i64).unwrap_or(0) != 0;
+    if !favorite(&kept) && others.iter().any(|(row, _)| favorite(row)) {
+        after.insert("favorite".to_string(), Value::from(1));
+    }
+
+    if text_of(&kept, "note").is_empty() {
+        if let Some((row, _)) = others
+            .iter()
+            .find(|(row, _)| !text_of(row, "note").is_empty())
+        {
+            after.insert("note".to_string(), Value::from(text_of(row, "note")));
+        }
+    }
+
+    let latest = others
+        .iter()
+        .map(|(row, _)| text_of(row, "createTime"))
+        .max()
+        .unwrap_or_default();
+    if latest > text_of(&kept, "createTime") {
+        after.insert("createTime".to_string(), Value::from(latest));
+    }
+
+    if !after.is_empty() {
+        let before = after
+            .keys()
+            .map(|column| {
+                (
+                    column.clone(),
+                    kept.get(column).cloned().unwrap_or(Value::Null),
+                )
+            })
+            .collect();
+
+        commit_operation(Operation::Edit {
+            id: keep.to_string(),
+            before,
+            after,
+        })?;
+    }
+
+    for (_, metadata) in &others {
+        for (key, value) in metadata {
+            let current = kept_metadata.get(key).cloned();
+
+            let merged = match (key.as_str(), &current) {
+                (TAGS, Some(tags)) => {
+                    let mut merged: Vec<&str> = tags.split(',').collect();
+                    for tag in value.split(',') {
+                        if !merged.contains(&tag) {
+                            merged.push(tag);
+                        }
+                    }
+
+                    merged.join(",")
+                }
+                (_, Some(_)) => continue,
+                (_, None) => value.clone(),
+            };
+
+            if current.as_deref() == Some(merged.as_str()) {
+                continue;
+            }
+
+            commit_operation(Operation::Tag {
+                id: keep.to_string(),
+                key: key.clone(),
+                before: current,
+                after: Some(merged.clone()),
+            })?;
+            kept_metadata.insert(key.clone(), merged);
+        }
+    }
+
+    let kept_image = (text_of(&kept, "type") == "image")
+        .then(|| resolve_image_path(app_handle, text_of(&kept, "value")));
+
+    let mut report = MergeReport::default();
+    for (row, metadata) in others {
+        let image_path = (text_of(&row, "type") == "image")
+            .then(|| resolve_image_path(app_handle, text_of(&row, "value")));
+        let bytes = match &image_path {
+            Some(path) => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            None => text_of(&row, "value").len() as u64,
+        };
+
+        commit_operation(Operation::Delete { row, metadata })?;
+
+        // Exact duplicates may share the kept item's file
+        if let Some(path) = image_path.filter(|path| Some(path) != kept_image.as_ref()) {
+            let _ = std::fs::remove_file(path);
+        }
+
+        report.removed += 1;
+        report.reclaimed_bytes += bytes;
+    }
+
+    notify_changed(app_handle);
+
+    Ok(report)
+}
+
+// 查找重复的条目，在后台扫描整个历史记录
+#[command]
+pub async fn find_duplicates<R: Runtime>(
+    app_handle: AppHandle<R>,
+    mode: DuplicateMode,
+) -> Result<DuplicateReport, String> {
+    tauri::async_runtime::spawn_blocking(move || find_duplicate_groups(&app_handle, mode))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 查找与指定图片相似的图片条目，threshold 为感知哈希最多相差的位数
+#[command]
+pub async fn find_similar_images<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+    threshold: Option<u32>,
+) -> Result<Vec<SimilarImage>, String> {
+    let threshold = threshold.unwrap_or(SIMILAR_IMAGE_BITS).min(64);
+
+    tauri::async_runtime::spawn_blocking(move || similar_images(&app_handle, &path, threshold))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 合并一组重复的条目，只保留 keep
+#[command]
+pub async fn merge_duplicates<R: Runtime>(
+    app_handle: AppHandle<R>,
+    group: Vec<String>,
+    keep: String,
+) -> Result<MergeReport, String> {
+    merge_duplicate_group(&app_handle, &group, &keep)
+}
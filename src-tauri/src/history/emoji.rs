@@ -0,0 +1,243 @@
+use super::encryption::open_rows;
+use super::store::{history_column_list, history_row};
+use super::{with_connection, HistoryRow};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::command;
+
+/// Subtype of text items holding nothing but emoji.
+pub const EMOJI_SUBTYPE: &str = "emoji";
+
+/// Longest emoji-only text in characters, enough for a few ZWJ sequences.
+const MAX_EMOJI_LENGTH: usize = 32;
+
+/// Recent items scanned for [`recent_emoji`], copies of the same emoji are
+/// grouped.
+const RECENT_SCAN: usize = 500;
+
+const ZWJ: char = '\u{200D}';
+const KEYCAP: char = '\u{20E3}';
+
+/// Pictographs and symbols shown as emoji.
+fn is_pictograph(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x00A9
+            | 0x00AE
+            | 0x203C
+            | 0x2049
+            | 0x2122
+            | 0x2139
+            | 0x2194..=0x2199
+            | 0x21A9..=0x21AA
+            | 0x231A..=0x231B
+            | 0x2328
+            | 0x23CF
+            | 0x23E9..=0x23F3
+            | 0x23F8..=0x23FA
+            | 0x24C2
+            | 0x25AA..=0x25AB
+            | 0x25B6
+            | 0x25C0
+            | 0x25FB..=0x25FE
+            | 0x2600..=0x27BF
+            | 0x2934..=0x2935
+            | 0x2B05..=0x2B07
+            | 0x2B1B..=0x2B1C
+            | 0x2B50
+            | 0x2B55
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+            | 0x1F000..=0x1FAFF
+    )
+}
+
+/// Characters that only modify the emoji before them: variation selectors,
+/// skin tones and the tags of subdivision flags.
+fn is_modifier(ch: char) -> bool {
+    matches!(ch as u32, 0xFE0E..=0xFE0F | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F)
+}
+
+/// Whether a text is made of emoji only, e.g. `👍🏽`, `🇯🇵` or `1️⃣`.
+/// Surrounding whitespace is ignored.
+pub fn is_emoji(text: &str) -> bool {
+    let chars: Vec<char> = text.trim().chars().collect();
+
+    if chars.is_empty() || chars.len() > MAX_EMOJI_LENGTH {
+        return false;
+    }
+
+    let mut has_pictograph = false;
+
+    for (index, &ch) in chars.iter().enumerate() {
+        let valid = if is_pictograph(ch) {
+            has_pictograph = true;
+            true
+        } else if matches!(ch, '0'..='9' | '#' | '*') {
+            // Keycaps, a digit followed by an optional variation selector
+            chars[index + 1..]
+                .iter()
+                .find(|next| !is_modifier(**next))
+                .is_some_and(|next| *next == KEYCAP)
+        } else {
+            ch == ZWJ || ch == KEYCAP || is_modifier(ch)
+        };
+
+        if !valid {
+            return false;
+        }
+    }
+
+    has_pictograph || chars.contains(&KEYCAP)
+}
+
+/// Code points of an emoji as hex joined by `-`, e.g. `1F44D-1F3FD`.
+pub fn emoji_codepoints(emoji: &str) -> String {
+    emoji
+        .chars()
+        .map(|ch| format!("{:X}", ch as u32))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The emoji written as code points, `1F44D-1F3FD`, `U+1F44D U+1F3FD` or
+/// the emoji itself.
+pub fn parse_emoji(codepoint: &str) -> Result<String, String> {
+    let codepoint = codepoint.trim();
+
+    if is_emoji(codepoint) {
+        return Ok(codepoint.to_string());
+    }
+
+    let emoji = codepoint
+        .split(|ch: char| ch == '-' || ch == '_' || ch == ',' || ch.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let hex = part
+                .trim_start_matches("U+")
+                .trim_start_matches("u+")
+                .trim_start_matches("0x");
+
+            u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("Invalid code point {}", part))
+        })
+        .collect::<Result<String, String>>()?;
+
+    if !is_emoji(&emoji) {
+        return Err(format!("{} is not an emoji", codepoint));
+    }
+
+    Ok(emoji)
+}
+
+/// Tag a text item holding only emoji, the webview leaves the subtype of
+/// such items empty.
+pub(super) fn tag_emoji(row: &mut HistoryRow) {
+    let is_text = row.get("type").and_then(Value::as_str) == Some("text");
+    let untagged = row.get("subtype").map_or(true, Value::is_null);
+    let value = row.get("value").and_then(Value::as_str);
+
+    if is_text && untagged && value.is_some_and(is_emoji) {
+        row.insert("subtype".to_string(), Value::from(EMOJI_SUBTYPE));
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmojiUsage {
+    pub emoji: String,
+    /// Code points as taken by `paste_emoji`.
+    pub codepoint: String,
+    /// Times it was copied or pasted.
+    pub count: usize,
+}
+
+/// Emoji copied lately, the most recent first with how often each was
+/// used.
+pub fn recent_emoji_usage(limit: usize) -> Result<Vec<EmojiUsage>, String> {
+    let mut rows = with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM history WHERE type = 'text' AND subtype = ?1 ORDER BY seq DESC LIMIT ?2",
+            history_column_list()
+        ))?;
+        let rows = statement.query_map(params![EMOJI_SUBTYPE, RECENT_SCAN], history_row)?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    open_rows(&mut rows)?;
+
+    let mut usages: Vec<EmojiUsage> = Vec::new();
+
+    for row in rows.iter().filter(|row| !row.contains_key("locked")) {
+        let Some(emoji) = row.get("value").and_then(Value::as_str).map(str::trim) else {
+            continue;
+        };
+        let count = row.get("count").and_then(Value::as_u64).unwrap_or(1).max(1) as usize;
+
+        match usages.iter_mut().find(|usage| usage.emoji == emoji) {
+            Some(usage) => usage.count += count,
+            None if usages.len() < limit => usages.push(EmojiUsage {
+                emoji: emoji.to_string(),
+                codepoint: emoji_codepoints(emoji),
+                count,
+            }),
+            None => {}
+        }
+    }
+
+    Ok(usages)
+}
+
+/// Tag the emoji-only items copied before emoji were told apart, once. The
+/// partial index marks that it ran and serves [`recent_emoji_usage`].
+/// Encrypted items stay untagged.
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    let indexed: bool = connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = 'history_emoji')",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if indexed {
+        return Ok(());
+    }
+
+    let ids = {
+        let mut statement = connection.prepare(
+            "SELECT id, value FROM history
+            WHERE type = 'text' AND subtype IS NULL AND length(trim(value)) <= ?1",
+        )?;
+        let rows = statement.query_map(params![MAX_EMOJI_LENGTH], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|(_, value)| value.as_deref().is_some_and(is_emoji))
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    };
+
+    for id in ids {
+        connection.execute(
+            "UPDATE history SET subtype = ?1 WHERE id = ?2",
+            params![EMOJI_SUBTYPE, id],
+        )?;
+    }
+
+    connection.execute_batch(
+        "CREATE INDEX IF NOT EXISTS history_emoji ON history (seq) WHERE subtype = 'emoji'",
+    )
+}
+
+// 获取最近使用的表情，最近的在前，相同的表情合并计数
+#[command]
+pub async fn recent_emoji(limit: Option<usize>) -> Result<Vec<EmojiUsage>, String> {
+    recent_emoji_usage(limit.unwrap_or(24))
+}
@@ -0,0 +1,491 @@
+use super::archive::seal_archives;
+use super::collation::fold_text;
+use super::redaction::{redact, CONTENT_COLUMNS};
+use super::{with_connection, HistoryItem, HistoryRow, HistoryStore, SqliteStore, SEALED_METADATA};
+use crate::vault::{
+    is_enabled, is_sealed, is_sealed_text, is_unlocked, keyed_hash, open_bytes, open_text,
+    plain_hash, seal_bytes, seal_text,
+};
+use rusqlite::{params, params_from_iter, Connection};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Columns encrypted in vault mode. The value of an image item is the file
+/// name, which maintenance looks up by equality, the file itself is
+/// encrypted instead.
+fn sealed_columns(kind: &str) -> &'static [&'static str] {
+    match kind {
+        "image" => &CONTENT_COLUMNS[1..],
+        _ => CONTENT_COLUMNS,
+    }
+}
+
+fn crypto_error(error: String) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(error.into())
+}
+
+/// Encrypt the content columns of a row, or of the columns being updated.
+/// Rows stay as they are while the vault is disabled.
+pub(super) fn seal_row(row: &mut HistoryRow, kind: &str) -> Result<(), String> {
+    if !is_enabled() {
+        return Ok(());
+    }
+
+    for &column in sealed_columns(kind) {
+        if let Some(Value::String(text)) = row.get(column) {
+            if !is_sealed_text(text) {
+                let sealed = seal_text(text)?;
+                row.insert(column.to_string(), Value::from(sealed));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn open_row(row: &mut HistoryRow) -> Result<(), String> {
+    for &column in CONTENT_COLUMNS {
+        if let Some(Value::String(text)) = row.get(column) {
+            if is_sealed_text(text) {
+                let opened = open_text(text)?;
+                row.insert(column.to_string(), Value::from(opened));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_sealed_row(row: &HistoryRow) -> bool {
+    CONTENT_COLUMNS
+        .iter()
+        .filter_map(|column| row.get(*column).and_then(Value::as_str))
+        .any(is_sealed_text)
+}
+
+/// Decrypt rows for the webview. While the vault is locked encrypted rows
+/// are redacted, the list still shows that they exist.
+pub(super) fn open_rows(rows: &mut [HistoryRow]) -> Result<(), String> {
+    if !is_enabled() {
+        return Ok(());
+    }
+
+    for row in rows.iter_mut().filter(|row| is_sealed_row(row)) {
+        if is_unlocked() {
+            open_row(row)?;
+        } else {
+            redact(row);
+            row.insert("locked".to_string(), Value::Bool(true));
+        }
+    }
+
+    Ok(())
+}
+
+/// Encrypt a clipboard format written with an entry.
+pub(super) fn seal_format_text(text: Option<&str>) -> rusqlite::Result<Option<String>> {
+    match text {
+        Some(text) if is_enabled() => seal_text(text).map(Some).map_err(crypto_error),
+        text => Ok(text.map(str::to_string)),
+    }
+}
+
+pub(super) fn seal_format_data(data: &[u8]) -> rusqlite::Result<Vec<u8>> {
+    if is_enabled() {
+        seal_bytes(data).map_err(crypto_error)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
+pub(super) fn open_format(
+    text: Option<String>,
+    data: Option<Vec<u8>>,
+) -> rusqlite::Result<(Option<String>, Option<Vec<u8>>)> {
+    let text = text
+        .map(|text| open_text(&text))
+        .transpose()
+        .map_err(crypto_error)?;
+    let data = data.map(open_bytes).transpose().map_err(crypto_error)?;
+
+    Ok((text, data))
+}
+
+/// Encrypt a metadata value in vault mode when its key is one of
+/// [`SEALED_METADATA`].
+pub(super) fn seal_metadata_value(key: &str, value: &str) -> Result<String, String> {
+    if is_enabled() && SEALED_METADATA.contains(&key) && !is_sealed_text(value) {
+        seal_text(value)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+fn seal_item(item: &mut HistoryItem) -> Result<(), String> {
+    if item.kind != "image" && !is_sealed_text(&item.value) {
+        item.value = seal_text(&item.value)?;
+    }
+
+    if !is_sealed_text(&item.search) {
+        item.search = seal_text(&item.search)?;
+    }
+
+    Ok(())
+}
+
+fn open_item(mut item: HistoryItem) -> Result<HistoryItem, String> {
+    item.value = open_text(&item.value)?;
+    item.search = open_text(&item.search)?;
+
+    Ok(item)
+}
+
+/// Type of a stored item, needed to tell which columns of an update to
+/// encrypt.
+fn kind_of(id: &str) -> Result<String, String> {
+    Ok(SqliteStore
+        .get_item(id)?
+        .map(|item| item.kind)
+        .unwrap_or_default())
+}
+
+/// The SQLite store with the content of every row encrypted at rest and
+/// decrypted on read, active while the vault is enabled. Reads fail while
+/// it is locked.
+pub struct EncryptedStore;
+
+impl HistoryStore for EncryptedStore {
+    fn get_item(&self, id: &str) -> Result<Option<HistoryItem>, String> {
+        SqliteStore.get_item(id)?.map(open_item).transpose()
+    }
+
+    fn list_items(&self, limit: usize) -> Result<Vec<HistoryItem>, String> {
+        SqliteStore
+            .list_items(limit)?
+            .into_iter()
+            .map(open_item)
+            .collect()
+    }
+
+    fn list_items_on(&self, date: &str) -> Result<Vec<HistoryItem>, String> {
+        SqliteStore
+            .list_items_on(date)?
+            .into_iter()
+            .map(open_item)
+            .collect()
+    }
+
+    fn list_favorites(&self) -> Result<Vec<HistoryItem>, String> {
+        SqliteStore
+            .list_favorites()?
+            .into_iter()
+            .map(open_item)
+            .collect()
+    }
+
+    fn insert_item(&self, item: &HistoryItem) -> Result<(), String> {
+        let count = item.plain_text().chars().count();
+
+        let mut sealed = item.clone();
+        seal_item(&mut sealed)?;
+
+        SqliteStore.insert_item(&sealed)?;
+
+        // The count was taken from the encrypted text
+        SqliteStore.update_row(
+            &item.id,
+            &HistoryRow::from([("count".to_string(), Value::from(count))]),
+        )
+    }
+
+    fn delete_item(&self, id: &str) -> Result<bool, String> {
+        SqliteStore.delete_item(id)
+    }
+
+    fn get_row(&self, id: &str) -> Result<Option<HistoryRow>, String> {
+        let Some(mut row) = SqliteStore.get_row(id)? else {
+            return Ok(None);
+        };
+
+        open_row(&mut row)?;
+
+        Ok(Some(row))
+    }
+
+    fn put_row(&self, row: &HistoryRow) -> Result<(), String> {
+        let kind = row
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let mut row = row.clone();
+        seal_row(&mut row, &kind)?;
+
+        SqliteStore.put_row(&row)
+    }
+
+    fn update_row(&self, id: &str, fields: &HistoryRow) -> Result<(), String> {
+        let mut fields = fields.clone();
+        seal_row(&mut fields, &kind_of(id)?)?;
+
+        SqliteStore.update_row(id, &fields)
+    }
+
+    fn set_metadata(&self, item_id: &str, key: &str, value: &str) -> Result<(), String> {
+        SqliteStore.set_metadata(item_id, key, &seal_metadata_value(key, value)?)
+    }
+
+    fn delete_metadata(&self, item_id: &str, key: &str) -> Result<(), String> {
+        SqliteStore.delete_metadata(item_id, key)
+    }
+
+    /// Encrypted values are left out while the vault is locked.
+    fn get_metadata(&self, item_id: &str) -> Result<BTreeMap<String, String>, String> {
+        let mut metadata = SqliteStore.get_metadata(item_id)?;
+
+        metadata.retain(|_, value| !is_sealed_text(value) || is_unlocked());
+
+        for value in metadata.values_mut() {
+            *value = open_text(value)?;
+        }
+
+        Ok(metadata)
+    }
+
+    /// SQL matches the plain values, the encrypted ones are matched once
+    /// decrypted.
+    fn search_metadata(&self, query: &str) -> Result<Vec<String>, String> {
+        let mut ids = SqliteStore.search_metadata(query)?;
+
+        if !is_unlocked() {
+            return Ok(ids);
+        }
+
+        let query = fold_text(query.trim());
+
+        for (item_id, value) in sealed_metadata()? {
+            if !ids.contains(&item_id) && fold_text(&open_text(&value)?).contains(&query) {
+                ids.push(item_id);
+            }
+        }
+
+        Ok(ids)
+    }
+
+    fn metadata_values(&self, key: &str) -> Result<Vec<String>, String> {
+        if !SEALED_METADATA.contains(&key) {
+            return SqliteStore.metadata_values(key);
+        }
+
+        if !is_unlocked() {
+            return Ok(Vec::new());
+        }
+
+        let mut values = SqliteStore
+            .metadata_values(key)?
+            .iter()
+            .map(|value| open_text(value))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        values.sort();
+        values.dedup();
+
+        Ok(values)
+    }
+}
+
+/// Item ids and values of the encrypted metadata.
+fn sealed_metadata() -> Result<Vec<(String, String)>, String> {
+    let placeholders = vec!["?"; SEALED_METADATA.len()].join(", ");
+
+    with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT item_id, value FROM history_metadata WHERE key IN ({})",
+            placeholders
+        ))?;
+        let rows = statement.query_map(params_from_iter(SEALED_METADATA.iter()), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        rows.collect()
+    })
+}
+
+/// Encrypt or decrypt a text, leaving text already in the wanted state.
+fn convert_text(text: &str, seal: bool) -> rusqlite::Result<Option<String>> {
+    match (seal, is_sealed_text(text)) {
+        (true, false) => seal_text(text).map(Some).map_err(crypto_error),
+        (false, true) => open_text(text).map(Some).map_err(crypto_error),
+        _ => Ok(None),
+    }
+}
+
+pub(super) fn seal_history(connection: &Connection, seal: bool) -> rusqlite::Result<()> {
+    let rows: Vec<(String, String, Vec<Option<String>>)> = {
+        let mut statement = connection
+            .prepare("SELECT id, coalesce(type, ''), value, search, note FROM history")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                vec![row.get(2)?, row.get(3)?, row.get(4)?],
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (id, kind, values) in rows {
+        for (column, value) in CONTENT_COLUMNS.iter().zip(values) {
+            // Decrypting covers every column, whatever was sealed before
+            if seal && !sealed_columns(&kind).contains(column) {
+                continue;
+            }
+
+            let Some(value) = value else {
+                continue;
+            };
+
+            if let Some(converted) = convert_text(&value, seal)? {
+                connection.execute(
+                    &format!("UPDATE history SET \"{}\" = ?1 WHERE id = ?2", column),
+                    params![converted, id],
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Encrypt or decrypt the values of the [`SEALED_METADATA`] keys.
+pub(super) fn seal_metadata(connection: &Connection, seal: bool) -> rusqlite::Result<()> {
+    let placeholders = vec!["?"; SEALED_METADATA.len()].join(", ");
+    let values: Vec<(String, String, String)> = {
+        let mut statement = connection.prepare(&format!(
+            "SELECT item_id, key, value FROM history_metadata WHERE key IN ({})",
+            placeholders
+        ))?;
+        let rows = statement.query_map(params_from_iter(SEALED_METADATA.iter()), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (item_id, key, value) in values {
+        if let Some(converted) = convert_text(&value, seal)? {
+            connection.execute(
+                "UPDATE history_metadata SET value = ?1 WHERE item_id = ?2 AND key = ?3",
+                params![converted, item_id, key],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn seal_formats(connection: &Connection, seal: bool) -> rusqlite::Result<()> {
+    let texts: Vec<(String, String, String)> = {
+        let mut statement = connection
+            .prepare("SELECT item_id, format, text FROM history_formats WHERE text IS NOT NULL")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (item_id, format, text) in texts {
+        if let Some(converted) = convert_text(&text, seal)? {
+            connection.execute(
+                "UPDATE history_formats SET text = ?1 WHERE item_id = ?2 AND format = ?3",
+                params![converted, item_id, format],
+            )?;
+        }
+    }
+
+    let blobs: Vec<(String, Vec<u8>)> = {
+        let mut statement = connection.prepare("SELECT hash, data FROM history_blobs")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (hash, data) in blobs {
+        // The hash is keyed in vault mode, see `vault::keyed_hash`
+        let (converted, rehashed) = match (seal, is_sealed(&data)) {
+            (true, false) => (seal_bytes(&data).map_err(crypto_error)?, keyed_hash(&data)),
+            (false, true) => {
+                let plain = open_bytes(data).map_err(crypto_error)?;
+                let rehashed = plain_hash(&plain);
+
+                (plain, rehashed)
+            }
+            _ => continue,
+        };
+
+        connection.execute(
+            "UPDATE history_blobs SET data = ?1 WHERE hash = ?2",
+            params![converted, hash],
+        )?;
+
+        if rehashed == hash {
+            continue;
+        }
+
+        // Equal content may already be stored under the new hash
+        connection.execute(
+            "INSERT OR IGNORE INTO history_blobs (hash, data, size)
+            SELECT ?1, data, size FROM history_blobs WHERE hash = ?2",
+            params![rehashed, hash],
+        )?;
+        connection.execute(
+            "UPDATE history_formats SET blob_hash = ?1 WHERE blob_hash = ?2",
+            params![rehashed, hash],
+        )?;
+        connection.execute("DELETE FROM history_blobs WHERE hash = ?1", params![hash])?;
+    }
+
+    Ok(())
+}
+
+/// The log keeps whole rows for undo and sync, every operation is
+/// encrypted as one text.
+fn seal_oplog(connection: &Connection, seal: bool) -> rusqlite::Result<()> {
+    let operations: Vec<(i64, String)> = {
+        let mut statement = connection.prepare("SELECT seq, operation FROM history_oplog")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (seq, operation) in operations {
+        if let Some(converted) = convert_text(&operation, seal)? {
+            connection.execute(
+                "UPDATE history_oplog SET operation = ?1 WHERE seq = ?2",
+                params![converted, seq],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Encrypt or decrypt everything stored in the database in one
+/// transaction, then the month archives. The files are rebuilt afterwards
+/// so no page keeps the old content. Metadata that only classifies items
+/// stays plain, see [`SEALED_METADATA`].
+pub fn seal_database(seal: bool) -> Result<(), String> {
+    with_connection(|connection| {
+        let transaction = connection.unchecked_transaction()?;
+
+        seal_history(&transaction, seal)?;
+        seal_metadata(&transaction, seal)?;
+        seal_formats(&transaction, seal)?;
+        seal_oplog(&transaction, seal)?;
+
+        transaction.commit()?;
+
+        // The triggers reindexed the rows, the old index pages go with the
+        // rebuild
+
+        connection.execute_batch("VACUUM")
+    })?;
+
+    seal_archives(seal)
+}
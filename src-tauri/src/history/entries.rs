@@ -0,0 +1,809 @@
+use super::archive::archived_blob_paths;
+use super::collation::fold_text;
+use super::emoji::tag_emoji;
+use super::encryption::{
+    open_format, open_row, open_rows, seal_format_data, seal_format_text, seal_metadata_value,
+    seal_row,
+};
+use super::phash::hash_saved_image;
+use super::redaction::redact_rows;
+use super::store::{history_column_list, history_row, known_columns, to_sql};
+use super::tags::TAG_CONDITION;
+use super::timestamps::{format_times, TimeFormat};
+use super::{
+    commit_operation, record_operation, store, with_connection, HistoryRow, Operation, BLOB_PATH,
+};
+use crate::clipboard_image::{get_thumbnails_dir, resolve_image_path};
+use crate::clipboard_text::get_blobs_dir;
+use crate::item_access::require_access;
+use crate::shell_integration::remove_embedded_files;
+use crate::vault;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use tauri::{command, AppHandle, Runtime};
+
+/// Largest page returned by a single query.
+const MAX_PAGE_SIZE: usize = 200;
+
+/// One clipboard format of an entry, textual formats keep `text` and binary
+/// ones keep `data`, stored once per distinct content in `history_blobs`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryFormat {
+    pub format: String,
+    pub text: Option<String>,
+    pub data: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewHistoryEntry {
+    /// The `history` row, as the webview used to insert it.
+    #[serde(flatten)]
+    pub row: HistoryRow,
+    #[serde(default)]
+    pub formats: Vec<EntryFormat>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+    /// A group id, `all`, `favorite` or `screenshot`.
+    pub group: Option<String>,
+    /// Only items carrying this tag.
+    pub tag: Option<String>,
+    /// Matched against the search text and the note.
+    pub search: Option<String>,
+    /// 1-based.
+    pub page: usize,
+    pub size: usize,
+    #[serde(default)]
+    pub time_format: TimeFormat,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    /// Newest first.
+    pub items: Vec<HistoryRow>,
+    /// Rows matching the query across all pages.
+    pub total: usize,
+    pub has_more: bool,
+}
+
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    // The backend owns the schema, the webview reads and writes through
+    // commands only
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS history (
+            id TEXT PRIMARY KEY,
+            type TEXT,
+            \"group\" TEXT,
+            value TEXT,
+            search TEXT,
+            count INTEGER,
+            width INTEGER,
+            height INTEGER,
+            favorite INTEGER DEFAULT 0,
+            createTime TEXT,
+            note TEXT,
+            subtype TEXT
+        );
+        CREATE INDEX IF NOT EXISTS history_create_time ON history (createTime);
+        CREATE TABLE IF NOT EXISTS history_formats (
+            item_id TEXT NOT NULL,
+            format TEXT NOT NULL,
+            text TEXT,
+            blob_hash TEXT,
+            PRIMARY KEY (item_id, format)
+        );
+        CREATE INDEX IF NOT EXISTS history_formats_blob ON history_formats (blob_hash);
+        CREATE TABLE IF NOT EXISTS history_blobs (
+            hash TEXT PRIMARY KEY,
+            data BLOB NOT NULL,
+            size INTEGER NOT NULL
+        );",
+    )
+}
+
+pub(super) fn write_format(
+    connection: &Connection,
+    item_id: &str,
+    format: &EntryFormat,
+) -> rusqlite::Result<()> {
+    let blob_hash = match &format.data {
+        Some(data) => {
+            // Hashed before encryption, equal content is still stored once
+            let hash = vault::content_hash(data);
+
+            connection.execute(
+                "INSERT OR IGNORE INTO history_blobs (hash, data, size) VALUES (?1, ?2, ?3)",
+                params![hash, seal_format_data(data)?, data.len() as i64],
+            )?;
+
+            Some(hash)
+        }
+        None => None,
+    };
+
+    connection.execute(
+        "INSERT OR REPLACE INTO history_formats (item_id, format, text, blob_hash)
+        VALUES (?1, ?2, ?3, ?4)",
+        params![
+            item_id,
+            format.format,
+            seal_format_text(format.text.as_deref())?,
+            blob_hash
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Drop the formats of an item and the blobs no other item refers to.
+pub(super) fn delete_formats(connection: &Connection, item_id: &str) -> rusqlite::Result<()> {
+    connection.execute(
+        "DELETE FROM history_formats WHERE item_id = ?1",
+        params![item_id],
+    )?;
+    connection.execute(
+        "DELETE FROM history_blobs WHERE NOT EXISTS (
+            SELECT 1 FROM history_formats WHERE blob_hash = history_blobs.hash
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Insert an entry with its formats in one transaction and log it.
+pub fn insert_entry(entry: &NewHistoryEntry) -> Result<(), String> {
+    insert_entry_with_metadata(entry, &BTreeMap::new())
+}
+
+/// Like [`insert_entry`], saving metadata in the same transaction so an undo
+/// removes both.
+pub fn insert_entry_with_metadata(
+    entry: &NewHistoryEntry,
+    metadata: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let id = entry
+        .row
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or("History entry without an id")?;
+
+    let kind = entry
+        .row
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    // The log keeps the plain row, it is encrypted as a whole
+    let mut row = entry.row.clone();
+    tag_emoji(&mut row);
+    seal_row(&mut row, kind)?;
+
+    let fields = known_columns(&row);
+    let columns = fields
+        .iter()
+        .map(|(column, _)| format!("\"{}\"", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let placeholders = vec!["?"; fields.len()].join(", ");
+
+    let sealed_metadata = metadata
+        .iter()
+        .map(|(key, value)| Ok((key, seal_metadata_value(key, value)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    with_connection(|connection| {
+        let transaction = connection.unchecked_transaction()?;
+
+        transaction.execute(
+            &format!(
+                "INSERT INTO history ({}) VALUES ({})",
+                columns, placeholders
+            ),
+            params_from_iter(fields.iter().map(|(_, value)| to_sql(value))),
+        )?;
+
+        for format in &entry.formats {
+            write_format(&transaction, id, format)?;
+        }
+
+        for (key, value) in &sealed_metadata {
+            transaction.execute(
+                "INSERT OR REPLACE INTO history_metadata (item_id, key, value) VALUES (?1, ?2, ?3)",
+                params![id, key, value],
+            )?;
+        }
+
+        transaction.commit()
+    })?;
+
+    record_operation(Operation::Insert {
+        row: entry.row.clone(),
+        metadata: metadata.clone(),
+    })
+    .map(|_| ())
+}
+
+pub fn query_entries(query: &HistoryQuery) -> Result<HistoryPage, String> {
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    match query.group.as_deref() {
+        None | Some("all") => {}
+        Some("favorite") => conditions.push("favorite = 1"),
+        Some("screenshot") => conditions.push("subtype = 'screenshot'"),
+        Some(group) => {
+            conditions.push("\"group\" = ?");
+            values.push(SqlValue::Text(group.to_string()));
+        }
+    }
+
+    if let Some(tag) = query.tag.as_ref().filter(|tag| !tag.is_empty()) {
+        conditions.push(TAG_CONDITION);
+        values.push(SqlValue::Text(tag.clone()));
+    }
+
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|search| !search.is_empty());
+
+    // Encrypted text can't be matched by SQL, it is searched once decrypted
+    let sealed_search = search.filter(|_| vault::is_enabled());
+
+    if let Some(search) = search.filter(|_| sealed_search.is_none()) {
+        let pattern = format!("%{}%", fold_text(search));
+
+        conditions.push("(fold(search) LIKE ? OR fold(note) LIKE ?)");
+        values.push(SqlValue::Text(pattern.clone()));
+        values.push(SqlValue::Text(pattern));
+    }
+
+    let filter = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let size = query.size.clamp(1, MAX_PAGE_SIZE);
+    let offset = query.page.max(1).saturating_sub(1) * size;
+
+    if let Some(search) = sealed_search {
+        let mut page = search_sealed(&filter, &values, search, size, offset)?;
+        format_times(&mut page.items, query.time_format)?;
+
+        return Ok(page);
+    }
+
+    let mut page = with_connection(|connection| {
+        let total: i64 = connection.query_row(
+            &format!("SELECT COUNT(*) FROM history {}", filter),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM history {} ORDER BY seq DESC LIMIT {} OFFSET {}",
+            history_column_list(),
+            filter,
+            size,
+            offset
+        ))?;
+        let items = statement
+            .query_map(params_from_iter(values.iter()), history_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(HistoryPage {
+            has_more: offset + items.len() < total as usize,
+            total: total as usize,
+            items,
+        })
+    })?;
+
+    open_rows(&mut page.items)?;
+    redact_rows(&mut page.items)?;
+    format_times(&mut page.items, query.time_format)?;
+
+    Ok(page)
+}
+
+/// Page through the rows matching `search` after decrypting every row the
+/// other conditions select. Nothing matches while the vault is locked.
+fn search_sealed(
+    filter: &str,
+    values: &[SqlValue],
+    search: &str,
+    size: usize,
+    offset: usize,
+) -> Result<HistoryPage, String> {
+    let mut rows = with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM history {} ORDER BY seq DESC",
+            history_column_list(),
+            filter
+        ))?;
+        let rows = statement.query_map(params_from_iter(values.iter()), history_row)?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    open_rows(&mut rows)?;
+
+    let search = fold_text(search);
+    let matches: Vec<HistoryRow> = rows
+        .into_iter()
+        .filter(|row| !row.contains_key("locked"))
+        .filter(|row| {
+            ["search", "note"].iter().any(|column| {
+                row.get(*column)
+                    .and_then(Value::as_str)
+                    .is_some_and(|text| fold_text(text).contains(&search))
+            })
+        })
+        .collect();
+
+    let total = matches.len();
+    let mut items: Vec<HistoryRow> = matches.into_iter().skip(offset).take(size).collect();
+
+    redact_rows(&mut items)?;
+
+    Ok(HistoryPage {
+        has_more: offset + items.len() < total,
+        total,
+        items,
+    })
+}
+
+pub fn get_formats(item_id: &str) -> Result<Vec<EntryFormat>, String> {
+    with_connection(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT formats.format, formats.text, blobs.data
+            FROM history_formats formats
+            LEFT JOIN history_blobs blobs ON blobs.hash = formats.blob_hash
+            WHERE formats.item_id = ?1",
+        )?;
+
+        let rows = statement.query_map(params![item_id], |row| {
+            let (text, data) = open_format(row.get(1)?, row.get(2)?)?;
+
+            Ok(EntryFormat {
+                format: row.get(0)?,
+                text,
+                data,
+            })
+        })?;
+
+        rows.collect()
+    })
+}
+
+/// Remove the full text of an overflowed item once no item, archived ones
+/// included, points at it anymore. Equal texts share one file.
+fn remove_unused_blob<R: Runtime>(app_handle: &AppHandle<R>, path: &str) -> Result<(), String> {
+    let file = Path::new(path);
+
+    // The metadata is writable from the webview, only blobs are deleted
+    if !file.starts_with(get_blobs_dir(app_handle))
+        || file
+            .components()
+            .any(|component| component == Component::ParentDir)
+    {
+        return Ok(());
+    }
+
+    let used = with_connection(|connection| {
+        connection.query_row(
+            "SELECT EXISTS(SELECT 1 FROM history_metadata WHERE key = ?1 AND value = ?2)",
+            params![BLOB_PATH, path],
+            |row| row.get::<_, bool>(0),
+        )
+    })?;
+
+    if !used
+        && !archived_blob_paths()?
+            .iter()
+            .any(|archived| archived == path)
+    {
+        let _ = std::fs::remove_file(file);
+    }
+
+    Ok(())
+}
+
+/// Delete an entry through the operation log so it can be undone, returns
+/// whether it existed. The image file of an image entry and the embedded
+/// copies of a files entry are removed as well.
+pub fn delete_entry<R: Runtime>(app_handle: &AppHandle<R>, id: &str) -> Result<bool, String> {
+    let store = store();
+    let Some(row) = store.get_row(id)? else {
+        return Ok(false);
+    };
+    let metadata = store.get_metadata(id)?;
+
+    let blob_path = metadata.get(BLOB_PATH).cloned();
+
+    commit_operation(Operation::Delete {
+        row: row.clone(),
+        metadata,
+    })?;
+
+    if let Some(path) = blob_path {
+        remove_unused_blob(app_handle, &path)?;
+    }
+
+    if row.get("type").and_then(Value::as_str) == Some("image") {
+        if let Some(value) = row.get("value").and_then(Value::as_str) {
+            let path = resolve_image_path(app_handle, value);
+
+            if let Some(stem) = path.file_stem() {
+                let thumbnail = get_thumbnails_dir(app_handle)
+                    .join(stem)
+                    .with_extension("webp");
+                let _ = std::fs::remove_file(thumbnail);
+            }
+
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    if row.get("type").and_then(Value::as_str) == Some("files") {
+        // A sealed value can't be read while the vault is locked, its copies
+        // are left to the user then
+        let mut row = row;
+        if open_row(&mut row).is_ok() {
+            if let Some(paths) = row
+                .get("value")
+                .and_then(Value::as_str)
+                .and_then(|value| serde_json::from_str::<Vec<String>>(value).ok())
+            {
+                remove_embedded_files(app_handle, &paths);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Set or flip the favorite flag, returns the new value.
+pub fn update_favorite(id: &str, favorite: Option<bool>) -> Result<bool, String> {
+    let row = store()
+        .get_row(id)?
+        .ok_or_else(|| format!("History item {} not found", id))?;
+
+    let current = row
+        .get("favorite")
+        .and_then(Value::as_i64)
+        .is_some_and(|favorite| favorite != 0);
+    let next = favorite.unwrap_or(!current);
+
+    if next != current {
+        commit_operation(Operation::Edit {
+            id: id.to_string(),
+            before: HistoryRow::from([("favorite".to_string(), Value::from(current))]),
+            after: HistoryRow::from([("favorite".to_string(), Value::from(next))]),
+        })?;
+    }
+
+    Ok(next)
+}
+
+/// Columns the webview edits directly, the content is edited through
+/// [`edit_entry_content`](super::edit_entry_content).
+const EDITABLE_COLUMNS: &[&str] = &["createTime", "favorite", "note"];
+
+/// Edit the given columns through the operation log, so an encrypted store
+/// encrypts the note and the edit can be undone.
+pub fn update_entry(id: &str, fields: &HistoryRow) -> Result<(), String> {
+    if let Some(column) = fields
+        .keys()
+        .find(|column| !EDITABLE_COLUMNS.contains(&column.as_str()))
+    {
+        return Err(format!("Column {} cannot be edited", column));
+    }
+
+    let row = store()
+        .get_row(id)?
+        .ok_or_else(|| format!("History item {} not found", id))?;
+
+    let before: HistoryRow = fields
+        .keys()
+        .map(|column| {
+            let value = row.get(column).cloned().unwrap_or(Value::Null);
+
+            (column.clone(), value)
+        })
+        .collect();
+
+    if &before == fields {
+        return Ok(());
+    }
+
+    commit_operation(Operation::Edit {
+        id: id.to_string(),
+        before,
+        after: fields.clone(),
+    })
+    .map(|_| ())
+}
+
+/// What a new capture is compared with to find the item it repeats.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedContent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// As it would be stored: the file name of an image, the JSON list of
+    /// a files item.
+    pub value: String,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub count: Option<i64>,
+    /// Blob file of a text over the capture size limit, `value` only holds
+    /// its preview.
+    pub blob_path: Option<String>,
+}
+
+/// The newest item a capture repeats. Images match by file name or by the
+/// same dimensions and size, long texts by their blob file and everything
+/// else by value, decrypted when the vault is enabled.
+pub fn find_duplicate(content: &CapturedContent) -> Result<Option<String>, String> {
+    if let Some(blob_path) = &content.blob_path {
+        // The data directory may have moved since, compare the file name
+        let name = Path::new(blob_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| blob_path.clone());
+
+        return with_connection(|connection| {
+            connection
+                .query_row(
+                    "SELECT history.id FROM history
+                    JOIN history_metadata ON history_metadata.item_id = history.id
+                    WHERE history.type = ?1 AND history_metadata.key = ?2
+                    AND history_metadata.value LIKE ?3
+                    ORDER BY history.seq DESC LIMIT 1",
+                    params![content.kind, BLOB_PATH, format!("%{}", name)],
+                    |row| row.get(0),
+                )
+                .optional()
+        });
+    }
+
+    if content.kind == "image" {
+        return with_connection(|connection| {
+            connection
+                .query_row(
+                    "SELECT id FROM history WHERE type = 'image'
+                    AND (value = ?1 OR (width = ?2 AND height = ?3 AND count = ?4))
+                    ORDER BY seq DESC LIMIT 1",
+                    params![content.value, content.width, content.height, content.count],
+                    |row| row.get(0),
+                )
+                .optional()
+        });
+    }
+
+    if !vault::is_enabled() {
+        return with_connection(|connection| {
+            connection
+                .query_row(
+                    "SELECT id FROM history WHERE type = ?1 AND value = ?2
+                    ORDER BY seq DESC LIMIT 1",
+                    params![content.kind, content.value],
+                    |row| row.get(0),
+                )
+                .optional()
+        });
+    }
+
+    // Sealing uses a fresh nonce, equal texts only compare equal decrypted
+    if !vault::is_unlocked() {
+        return Ok(None);
+    }
+
+    let rows: Vec<(String, Option<String>)> = with_connection(|connection| {
+        let mut statement = connection
+            .prepare("SELECT id, value FROM history WHERE type = ?1 ORDER BY seq DESC")?;
+        let rows =
+            statement.query_map(params![content.kind], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        rows.collect()
+    })?;
+
+    for (id, value) in rows {
+        let Some(value) = value else {
+            continue;
+        };
+
+        let value = if vault::is_sealed_text(&value) {
+            vault::open_text(&value)?
+        } else {
+            value
+        };
+
+        if value == content.value {
+            return Ok(Some(id));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Delete the items copied within the inclusive `createTime` bounds, all
+/// of them without bounds. Returns how many were deleted.
+pub fn delete_entries_between<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    since: Option<&str>,
+    until: Option<&str>,
+    include_favorites: bool,
+) -> Result<usize, String> {
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    if !include_favorites {
+        conditions.push("favorite = 0");
+    }
+
+    if let Some(since) = since {
+        conditions.push("createTime >= ?");
+        values.push(SqlValue::Text(since.to_string()));
+    }
+
+    if let Some(until) = until {
+        conditions.push("createTime <= ?");
+        values.push(SqlValue::Text(until.to_string()));
+    }
+
+    let filter = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let ids: Vec<String> = with_connection(|connection| {
+        let mut statement = connection.prepare(&format!("SELECT id FROM history {}", filter))?;
+        let rows = statement.query_map(params_from_iter(values.iter()), |row| row.get(0))?;
+
+        rows.collect()
+    })?;
+
+    let mut deleted = 0;
+
+    for id in ids {
+        if delete_entry(app_handle, &id)? {
+            deleted += 1;
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Every favorite row, newest first, prepared for the webview like a
+/// queried page.
+pub fn favorite_entries() -> Result<Vec<HistoryRow>, String> {
+    let mut rows = with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM history WHERE favorite = 1 ORDER BY seq DESC",
+            history_column_list()
+        ))?;
+        let rows = statement.query_map([], history_row)?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    open_rows(&mut rows)?;
+    redact_rows(&mut rows)?;
+
+    Ok(rows)
+}
+
+// 插入历史记录及其各个剪贴板格式
+#[command]
+pub async fn insert_history_entry<R: Runtime>(
+    app_handle: AppHandle<R>,
+    entry: NewHistoryEntry,
+) -> Result<(), String> {
+    insert_entry(&entry)?;
+
+    tauri_plugin_eco_window::record_capture(&app_handle);
+
+    crate::calculator::record_result(&entry);
+
+    crate::conversion::record_conversions(&app_handle, &entry);
+
+    crate::transform::record_timestamp(&entry);
+
+    crate::network::record_network_info(&entry);
+
+    hash_saved_image(&app_handle, &entry);
+
+    // 像密码、银行卡号或密钥的文本标记为敏感内容，不推送到其它设备，
+    // 其余条目等采集处理器运行完再推送，处理器也可能将其标记为敏感
+    if !crate::sensitive::record_sensitive(&entry)? {
+        crate::processors::share_after_processors(entry);
+    }
+
+    Ok(())
+}
+
+// 分页查询历史记录
+#[command]
+pub async fn query_history(query: HistoryQuery) -> Result<HistoryPage, String> {
+    query_entries(&query)
+}
+
+// 获取历史记录保存的剪贴板格式，敏感条目需要先通过系统身份验证
+#[command]
+pub async fn get_history_formats<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<Vec<EntryFormat>, String> {
+    require_access(&app_handle, &id).await?;
+
+    get_formats(&id)
+}
+
+// 删除历史记录
+#[command]
+pub async fn delete_history_entry<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<bool, String> {
+    delete_entry(&app_handle, &id)
+}
+
+// 收藏或取消收藏，不传 favorite 时切换当前状态
+#[command]
+pub async fn toggle_history_favorite(id: String, favorite: Option<bool>) -> Result<bool, String> {
+    update_favorite(&id, favorite)
+}
+
+// 收藏或取消收藏，收藏的条目不会被保留策略清理
+#[command]
+pub async fn set_favorite(id: String, favorite: bool) -> Result<(), String> {
+    update_favorite(&id, Some(favorite)).map(|_| ())
+}
+
+// 修改历史记录的复制时间、收藏或备注
+#[command]
+pub async fn update_history_entry(id: String, fields: HistoryRow) -> Result<(), String> {
+    update_entry(&id, &fields)
+}
+
+// 查找新复制的内容与哪条历史记录重复
+#[command]
+pub async fn find_history_duplicate(content: CapturedContent) -> Result<Option<String>, String> {
+    find_duplicate(&content)
+}
+
+// 删除指定时间范围内复制的历史记录，返回删除的数量
+#[command]
+pub async fn delete_history_range<R: Runtime>(
+    app_handle: AppHandle<R>,
+    since: Option<String>,
+    until: Option<String>,
+    include_favorites: bool,
+) -> Result<usize, String> {
+    delete_entries_between(
+        &app_handle,
+        since.as_deref(),
+        until.as_deref(),
+        include_favorites,
+    )
+}
+
+// 获取所有收藏的条目，最新的在前
+#[command]
+pub async fn list_favorites() -> Result<Vec<HistoryRow>, String> {
+    favorite_entries()
+}
@@ -0,0 +1,66 @@
+use super::{store, with_connection};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// A row of the `history` table written by the webview.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryItem {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(default)]
+    pub search: String,
+    #[serde(default)]
+    pub subtype: Option<String>,
+    #[serde(default)]
+    pub create_time: String,
+}
+
+impl HistoryItem {
+    /// Plain text representation of the item, used by text transforms.
+    pub fn plain_text(&self) -> &str {
+        match self.kind.as_str() {
+            "text" => &self.value,
+            _ => &self.search,
+        }
+    }
+
+    pub fn is_url(&self) -> bool {
+        self.kind == "text" && self.subtype.as_deref() == Some("url")
+    }
+
+    /// Date part of `createTime` (`YYYY-MM-DD HH:mm:ss`).
+    pub fn create_date(&self) -> &str {
+        self.create_time.get(..10).unwrap_or(&self.create_time)
+    }
+}
+
+pub fn get_item(id: &str) -> Result<Option<HistoryItem>, String> {
+    store().get_item(id)
+}
+
+/// Like [`get_item`] but treats a missing item as an error.
+pub fn require_item(id: &str) -> Result<HistoryItem, String> {
+    get_item(id)?.ok_or_else(|| format!("History item {} not found", id))
+}
+
+/// The last item captured at or before `time` (`YYYY-MM-DD HH:mm:ss`).
+pub fn item_at(time: &str) -> Result<Option<HistoryItem>, String> {
+    let id: Option<String> = with_connection(|connection| {
+        connection
+            .query_row(
+                "SELECT id FROM history WHERE createTime <= ?1 ORDER BY createTime DESC LIMIT 1",
+                params![time],
+                |row| row.get(0),
+            )
+            .optional()
+    })?;
+
+    match id {
+        Some(id) => get_item(&id),
+        None => Ok(None),
+    }
+}
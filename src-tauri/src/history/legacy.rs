@@ -0,0 +1,337 @@
+use super::{database_path, notify_changed, with_connection, HISTORY_COLUMNS};
+use crate::clipboard_image::get_images_dir;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+const PROGRESS_EVENT: &str = "legacy-migration-progress";
+
+/// Directory under the data directory older releases saved images to.
+const LEGACY_IMAGES_DIR_NAME: &str = "images";
+
+/// Image rows relocated per transaction.
+const BATCH_SIZE: usize = 64;
+
+/// Group of a row when the legacy table has no `group` column.
+const GROUP_FROM_TYPE: &str =
+    "CASE type WHEN 'image' THEN 'image' WHEN 'files' THEN 'files' ELSE 'text' END";
+
+static PROGRESS: Mutex<Option<LegacyMigrationProgress>> = Mutex::new(None);
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LegacyMigrationStage {
+    /// The `history` table was rebuilt into the current schema.
+    Schema,
+    /// Images are being moved out of the old data directory.
+    Images,
+    Done,
+    Failed,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyMigrationProgress {
+    pub stage: LegacyMigrationStage,
+    pub processed: u64,
+    pub total: u64,
+    /// Copy of the database taken before the first change.
+    pub backup: Option<String>,
+    pub error: Option<String>,
+}
+
+/// An image row whose value is still an absolute path into the data
+/// directory, from before images moved to the clipboard plugin's directory.
+struct LegacyImage {
+    id: String,
+    path: PathBuf,
+}
+
+fn update_progress(f: impl FnOnce(&mut LegacyMigrationProgress)) -> LegacyMigrationProgress {
+    let mut progress = PROGRESS.lock().unwrap();
+    let progress = progress.get_or_insert(LegacyMigrationProgress {
+        stage: LegacyMigrationStage::Schema,
+        processed: 0,
+        total: 0,
+        backup: None,
+        error: None,
+    });
+
+    f(progress);
+
+    progress.clone()
+}
+
+fn report<R: Runtime>(app_handle: &AppHandle<R>, f: impl FnOnce(&mut LegacyMigrationProgress)) {
+    let _ = app_handle.emit(PROGRESS_EVENT, update_progress(f));
+}
+
+/// Copy the database next to itself before changing it, `VACUUM INTO`
+/// writes a consistent snapshot even with the webview connected.
+fn backup(connection: &Connection, database: &Path) -> Result<String, String> {
+    if let Some(backup) = PROGRESS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|progress| progress.backup.clone())
+    {
+        return Ok(backup);
+    }
+
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let file_name = database
+        .file_name()
+        .ok_or("History database path has no file name")?
+        .to_string_lossy();
+    let path = database
+        .with_file_name(format!("{}.legacy-{}.bak", file_name, secs))
+        .to_string_lossy()
+        .into_owned();
+
+    connection
+        .execute("VACUUM INTO ?1", [&path])
+        .map_err(|e| e.to_string())?;
+
+    update_progress(|progress| progress.backup = Some(path.clone()));
+
+    Ok(path)
+}
+
+/// Columns of the `history` table and their declared types, empty when the
+/// table does not exist yet.
+fn history_columns(connection: &Connection) -> rusqlite::Result<Vec<(String, String)>> {
+    let mut statement = connection.prepare("PRAGMA table_info(history)")?;
+    let rows = statement.query_map([], |row| Ok((row.get(1)?, row.get(2)?)))?;
+
+    rows.collect()
+}
+
+/// Older releases used integer ids and lacked some of today's columns.
+fn needs_rebuild(columns: &[(String, String)]) -> bool {
+    let id_is_text = columns
+        .iter()
+        .any(|(name, kind)| name == "id" && kind.eq_ignore_ascii_case("TEXT"));
+    let complete = HISTORY_COLUMNS
+        .iter()
+        .all(|column| columns.iter().any(|(name, _)| name == column));
+
+    !id_is_text || !complete
+}
+
+/// Copy the rows of a legacy `history` table into one with the current
+/// schema, missing columns get the values the webview would have written.
+fn rebuild(connection: &Connection, columns: &[(String, String)]) -> rusqlite::Result<()> {
+    let present: HashSet<&str> = columns.iter().map(|(name, _)| name.as_str()).collect();
+
+    let select = HISTORY_COLUMNS
+        .iter()
+        .map(|&column| match column {
+            "id" => "CAST(id AS TEXT)".to_string(),
+            column if present.contains(column) => format!("\"{}\"", column),
+            "group" => GROUP_FROM_TYPE.to_string(),
+            "favorite" => "0".to_string(),
+            _ => "NULL".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let insert = HISTORY_COLUMNS
+        .iter()
+        .map(|column| format!("\"{}\"", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let transaction = connection.unchecked_transaction()?;
+
+    transaction.execute_batch(
+        "DROP INDEX IF EXISTS history_create_time;
+        ALTER TABLE history RENAME TO history_legacy;",
+    )?;
+    super::entries::migrate(&transaction)?;
+    transaction.execute(
+        &format!(
+            "INSERT INTO history ({}) SELECT {} FROM history_legacy",
+            insert, select
+        ),
+        [],
+    )?;
+    transaction.execute_batch("DROP TABLE history_legacy")?;
+
+    transaction.commit()
+}
+
+/// Bring a legacy `history` table to the current schema before anything
+/// else touches it, called when the shared connection is opened.
+pub(super) fn upgrade_schema(connection: &Connection, database: &Path) -> Result<(), String> {
+    let columns = history_columns(connection).map_err(|e| e.to_string())?;
+
+    if columns.is_empty() || !needs_rebuild(&columns) {
+        return Ok(());
+    }
+
+    log::info!("Upgrading the legacy history table");
+
+    backup(connection, database)?;
+    rebuild(connection, &columns).map_err(|e| e.to_string())?;
+
+    update_progress(|progress| progress.stage = LegacyMigrationStage::Schema);
+
+    Ok(())
+}
+
+fn legacy_images(images_dir: &Path) -> Result<Vec<LegacyImage>, String> {
+    let rows = with_connection(|connection| {
+        let mut statement = connection
+            .prepare("SELECT CAST(id AS TEXT), value FROM history WHERE type = 'image'")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, value)| {
+            let path = PathBuf::from(value?);
+
+            let in_legacy_dir = path
+                .parent()
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == LEGACY_IMAGES_DIR_NAME);
+
+            (path.is_absolute() && in_legacy_dir && !path.starts_with(images_dir) && path.is_file())
+                .then_some(LegacyImage { id, path })
+        })
+        .collect())
+}
+
+/// Copy a legacy image into the images directory and return the file name
+/// the row should store. A different file of the same name is kept, the
+/// copy is prefixed with the item id instead.
+fn copy_image(image: &LegacyImage, images_dir: &Path) -> Result<String, String> {
+    let file_name = image
+        .path
+        .file_name()
+        .ok_or("Legacy image path has no file name")?
+        .to_string_lossy()
+        .into_owned();
+
+    let mut name = file_name.clone();
+    let mut target = images_dir.join(&name);
+
+    if target.exists() {
+        let same_size = match (std::fs::metadata(&target), std::fs::metadata(&image.path)) {
+            (Ok(existing), Ok(legacy)) => existing.len() == legacy.len(),
+            _ => false,
+        };
+
+        if same_size {
+            return Ok(name);
+        }
+
+        name = format!("{}-{}", image.id, file_name);
+        target = images_dir.join(&name);
+    }
+
+    std::fs::copy(&image.path, &target).map_err(|e| e.to_string())?;
+
+    Ok(name)
+}
+
+fn relocate_images<R: Runtime>(app_handle: &AppHandle<R>) -> Result<bool, String> {
+    let images_dir = get_images_dir(app_handle);
+    let images = legacy_images(&images_dir)?;
+
+    if images.is_empty() {
+        return Ok(false);
+    }
+
+    let total = images.len() as u64;
+    log::info!("Moving {} legacy history images", total);
+
+    let database = database_path()?;
+    with_connection(|connection| Ok(backup(connection, &database)))??;
+
+    std::fs::create_dir_all(&images_dir).map_err(|e| e.to_string())?;
+
+    report(app_handle, |progress| {
+        progress.stage = LegacyMigrationStage::Images;
+        progress.processed = 0;
+        progress.total = total;
+    });
+
+    let mut processed = 0;
+    for batch in images.chunks(BATCH_SIZE) {
+        let mut moved = Vec::new();
+
+        for image in batch {
+            match copy_image(image, &images_dir) {
+                Ok(name) => moved.push((image, name)),
+                Err(error) => {
+                    log::warn!("Failed to move legacy image {:?}: {}", image.path, error)
+                }
+            }
+        }
+
+        with_connection(|connection| {
+            let transaction = connection.unchecked_transaction()?;
+
+            for (image, name) in &moved {
+                transaction.execute(
+                    "UPDATE history SET value = ?1 WHERE id = ?2",
+                    params![name, image.id],
+                )?;
+            }
+
+            transaction.commit()
+        })?;
+
+        // The rows point at the copies now, the originals can go
+        for (image, _) in &moved {
+            let _ = std::fs::remove_file(&image.path);
+        }
+
+        processed += batch.len() as u64;
+        report(app_handle, |progress| progress.processed = processed);
+    }
+
+    Ok(true)
+}
+
+/// Finish upgrading data from older releases: move images left in the old
+/// data directory and tell the webview once everything is done.
+pub fn upgrade_legacy_data<R: Runtime>(app_handle: &AppHandle<R>) {
+    let upgraded_schema = PROGRESS.lock().unwrap().is_some();
+
+    match relocate_images(app_handle) {
+        Ok(relocated) if relocated || upgraded_schema => {
+            report(app_handle, |progress| {
+                progress.stage = LegacyMigrationStage::Done
+            });
+
+            notify_changed(app_handle);
+        }
+        Ok(_) => {}
+        Err(error) => {
+            log::error!("Failed to upgrade legacy history data: {}", error);
+
+            report(app_handle, |progress| {
+                progress.stage = LegacyMigrationStage::Failed;
+                progress.error = Some(error);
+            });
+        }
+    }
+}
+
+// 获取旧版本数据的升级进度，没有需要升级的数据时为空
+#[command]
+pub async fn get_legacy_migration() -> Option<LegacyMigrationProgress> {
+    PROGRESS.lock().unwrap().clone()
+}
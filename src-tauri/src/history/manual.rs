@@ -0,0 +1,216 @@
+use super::entries::{delete_formats, write_format};
+use super::{
+    commit_operation, insert_entry_with_metadata, local_time, new_item_id, notify_changed, store,
+    with_connection, EntryFormat, HistoryRow, NewHistoryEntry, Operation, TAGS,
+};
+use crate::clipboard_html::{html_to_text, sanitize_html};
+use crate::clipboard_request::clean_tags;
+use crate::clipboard_rtf::rtf_to_plain_text;
+use crate::clipboard_text::max_inline_bytes;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tauri::{command, AppHandle, Runtime};
+
+/// Types that can be written by hand, everything else comes from captures.
+const EDITABLE_TYPES: &[&str] = &["text", "html", "rtf"];
+
+/// Format the plain text alternative of rich items is kept under.
+const PLAIN_TEXT_FORMAT: &str = "text";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewItem {
+    /// `text`, `html` or `rtf`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// The `history` columns derived from an item's content.
+struct Content {
+    value: String,
+    search: String,
+    formats: Vec<EntryFormat>,
+}
+
+impl Content {
+    fn columns(&self) -> HistoryRow {
+        HistoryRow::from([
+            ("value".to_string(), Value::from(self.value.as_str())),
+            ("search".to_string(), Value::from(self.search.as_str())),
+            (
+                "count".to_string(),
+                Value::from(self.search.chars().count()),
+            ),
+        ])
+    }
+}
+
+/// Check content the way a capture of the same type would be stored and
+/// derive its search text. The content itself goes to the blob store.
+fn prepare(kind: &str, content: &str) -> Result<Content, String> {
+    if !EDITABLE_TYPES.contains(&kind) {
+        return Err(format!("Items of type {} cannot be written by hand", kind));
+    }
+
+    if content.trim().is_empty() {
+        return Err("Item content is empty".to_string());
+    }
+
+    if content.len() > max_inline_bytes() {
+        return Err(format!(
+            "Item content of {} bytes is over the {} bytes limit",
+            content.len(),
+            max_inline_bytes()
+        ));
+    }
+
+    let (value, search) = match kind {
+        "html" => (sanitize_html(content), html_to_text(content)),
+        "rtf" => {
+            if !content.trim_start().starts_with("{\\rtf") {
+                return Err("Item content is not an RTF document".to_string());
+            }
+
+            (content.to_string(), rtf_to_plain_text(content))
+        }
+        _ => (content.to_string(), content.to_string()),
+    };
+
+    let mut formats = vec![EntryFormat {
+        format: kind.to_string(),
+        text: None,
+        data: Some(value.as_bytes().to_vec()),
+    }];
+
+    if kind != PLAIN_TEXT_FORMAT {
+        formats.push(EntryFormat {
+            format: PLAIN_TEXT_FORMAT.to_string(),
+            text: Some(search.clone()),
+            data: None,
+        });
+    }
+
+    Ok(Content {
+        value,
+        search,
+        formats,
+    })
+}
+
+/// Add an item written by hand, returns its row.
+pub fn create_entry(item: &NewItem) -> Result<HistoryRow, String> {
+    let content = prepare(&item.kind, &item.content)?;
+    let id = new_item_id();
+
+    let mut row = content.columns();
+    row.extend([
+        ("id".to_string(), Value::from(id.as_str())),
+        ("type".to_string(), Value::from(item.kind.as_str())),
+        ("group".to_string(), Value::from("text")),
+        ("favorite".to_string(), Value::from(0)),
+        ("createTime".to_string(), Value::from(local_time())),
+    ]);
+
+    let tags = clean_tags(item.tags.clone());
+    let mut metadata = BTreeMap::new();
+    if !tags.is_empty() {
+        metadata.insert(TAGS.to_string(), tags.join(","));
+    }
+
+    insert_entry_with_metadata(
+        &NewHistoryEntry {
+            row: row.clone(),
+            formats: content.formats,
+        },
+        &metadata,
+    )?;
+
+    Ok(row)
+}
+
+/// Replace the content of a textual item through the operation log. The
+/// full text index follows through its triggers.
+pub fn edit_entry_content(id: &str, content: &str) -> Result<HistoryRow, String> {
+    let row = store()
+        .get_row(id)?
+        .ok_or_else(|| format!("History item {} not found", id))?;
+    let kind = row
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let content = prepare(&kind, content)?;
+
+    let mut after = content.columns();
+    // Detected from the old content, the webview detects it again on capture
+    if kind == "text" {
+        after.insert("subtype".to_string(), Value::Null);
+    }
+
+    let before: HistoryRow = after
+        .keys()
+        .map(|column| {
+            let value = row.get(column).cloned().unwrap_or(Value::Null);
+
+            (column.clone(), value)
+        })
+        .collect();
+
+    if before == after {
+        return Ok(row);
+    }
+
+    commit_operation(Operation::Edit {
+        id: id.to_string(),
+        before,
+        after: after.clone(),
+    })?;
+
+    with_connection(|connection| {
+        let transaction = connection.unchecked_transaction()?;
+
+        delete_formats(&transaction, id)?;
+        for format in &content.formats {
+            write_format(&transaction, id, format)?;
+        }
+
+        transaction.commit()
+    })?;
+
+    let mut row = row;
+    row.extend(after);
+
+    Ok(row)
+}
+
+// 手动新建条目（文本、html 或 rtf），可以同时添加标签
+#[command]
+pub async fn create_item<R: Runtime>(
+    app_handle: AppHandle<R>,
+    item: NewItem,
+) -> Result<HistoryRow, String> {
+    let row = create_entry(&item)?;
+
+    notify_changed(&app_handle);
+
+    Ok(row)
+}
+
+// 编辑文本类条目的内容
+#[command]
+pub async fn edit_item_content<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    content: String,
+) -> Result<HistoryRow, String> {
+    let row = edit_entry_content(&id, &content)?;
+
+    notify_changed(&app_handle);
+
+    Ok(row)
+}
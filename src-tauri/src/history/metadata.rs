@@ -0,0 +1,124 @@
+use super::store;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use tauri::command;
+
+/// Application the item was copied from.
+pub const SOURCE_APP: &str = "source_app";
+/// Executable of that application.
+pub const SOURCE_PATH: &str = "source_path";
+/// Cached PNG of its icon.
+pub const SOURCE_ICON: &str = "source_icon";
+/// Foreground window title at capture time.
+pub const WINDOW_TITLE: &str = "window_title";
+/// Page the item was copied from.
+pub const SOURCE_URL: &str = "source_url";
+/// Title of the page the item was copied on, which is the page a URL
+/// copied from the address bar points to.
+pub const PAGE_TITLE: &str = "page_title";
+/// How the item was captured, absent for clipboard copies.
+pub const CAPTURE_SOURCE: &str = "capture_source";
+/// Comma separated tags.
+pub const TAGS: &str = "tags";
+/// Screenshot tool an image item was captured with.
+pub const SCREENSHOT_SOURCE: &str = "screenshot_source";
+/// Capture time of a screenshot in milliseconds since the Unix epoch.
+pub const CAPTURED_AT: &str = "captured_at";
+/// Monitor a full-screen screenshot was taken of.
+pub const SCREENSHOT_MONITOR: &str = "screenshot_monitor";
+/// `1` for items whose content is hidden while the screen is shared.
+pub const SENSITIVE: &str = "sensitive";
+/// What a sensitive item looked like, see `sensitive::SensitiveKind`.
+pub const SENSITIVE_KIND: &str = "sensitive_kind";
+/// Blob file holding the full text of an item over the capture size limit,
+/// the row only keeps a preview.
+pub const BLOB_PATH: &str = "blob_path";
+/// Text recognized in an image item.
+pub const OCR_TEXT: &str = "ocr_text";
+/// Virtual machine a copy was bridged from.
+pub const VM_NAME: &str = "vm_name";
+/// Number of times an image item was pasted or opened.
+pub const ACCESS_COUNT: &str = "access_count";
+/// Time of the last access, in the `createTime` format.
+pub const LAST_ACCESS: &str = "last_access";
+/// Where an image went when it was moved to cold storage.
+pub const COLD_STORAGE: &str = "cold_storage";
+/// Type of the domain object a processor recognized in the item,
+/// `<processor id>/<type>`.
+pub const OBJECT_TYPE: &str = "object_type";
+/// JSON data of that object.
+pub const OBJECT_DATA: &str = "object_data";
+/// Result of a copied arithmetic expression, as it is pasted.
+pub const CALC_RESULT: &str = "calc_result";
+/// Conversions of a copied value with a unit, a JSON object of the
+/// converted values by unit code.
+pub const CONVERSIONS: &str = "conversions";
+/// Other formats of a copied timestamp or ISO date, a JSON object of the
+/// rendered times by format.
+pub const TIME_CONVERSIONS: &str = "time_conversions";
+/// What is known of a copied IP or domain, a JSON object with its kind,
+/// scope, ASN and, when lookups are enabled, reverse DNS or addresses.
+pub const NETWORK_INFO: &str = "network_info";
+
+/// Keys whose values describe the content or where it was copied, they
+/// are encrypted in vault mode. The others stay plain, list filters and
+/// maintenance compare them in SQL.
+pub const SEALED_METADATA: &[&str] = &[
+    SOURCE_PATH,
+    WINDOW_TITLE,
+    SOURCE_URL,
+    PAGE_TITLE,
+    OCR_TEXT,
+    OBJECT_DATA,
+    CALC_RESULT,
+    CONVERSIONS,
+    TIME_CONVERSIONS,
+    NETWORK_INFO,
+];
+
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS history_metadata (
+            item_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (item_id, key)
+        );
+        CREATE INDEX IF NOT EXISTS history_metadata_value ON history_metadata (value);",
+    )
+}
+
+pub fn set_metadata(item_id: &str, key: &str, value: &str) -> Result<(), String> {
+    store().set_metadata(item_id, key, value)
+}
+
+pub fn get_metadata(item_id: &str) -> Result<BTreeMap<String, String>, String> {
+    store().get_metadata(item_id)
+}
+
+// 保存条目的元数据，空值会被忽略
+#[command]
+pub async fn save_item_metadata(
+    id: String,
+    metadata: BTreeMap<String, Option<String>>,
+) -> Result<(), String> {
+    for (key, value) in metadata {
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            set_metadata(&id, &key, &value)?;
+        }
+    }
+
+    Ok(())
+}
+
+// 获取条目的元数据
+#[command]
+pub async fn get_item_metadata(id: String) -> Result<BTreeMap<String, String>, String> {
+    get_metadata(&id)
+}
+
+// 搜索元数据，返回匹配的条目 id
+#[command]
+pub async fn search_item_metadata(query: String) -> Result<Vec<String>, String> {
+    store().search_metadata(&query)
+}
@@ -0,0 +1,165 @@
+use rusqlite::Connection;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+
+mod archive;
+mod backfill;
+mod collation;
+mod duplicates;
+mod emoji;
+mod encryption;
+mod entries;
+mod items;
+mod legacy;
+mod manual;
+mod metadata;
+mod oplog;
+mod phash;
+mod redaction;
+mod relations;
+mod replace;
+mod search;
+mod sources;
+mod stats;
+mod store;
+mod tags;
+mod timestamps;
+
+pub use archive::*;
+pub use backfill::*;
+pub use collation::*;
+pub use duplicates::*;
+pub use emoji::*;
+pub use encryption::*;
+pub use entries::*;
+pub use items::*;
+pub use legacy::*;
+pub use manual::*;
+pub use metadata::*;
+pub use oplog::*;
+pub use phash::*;
+pub use redaction::*;
+pub use relations::*;
+pub use replace::*;
+pub use search::*;
+pub use sources::*;
+pub use stats::*;
+pub use store::*;
+pub use tags::*;
+pub use timestamps::*;
+
+// 通知前端刷新列表，与前端 LISTEN_KEY.REFRESH_CLIPBOARD_LIST 保持一致
+const REFRESH_CLIPBOARD_LIST_EVENT: &str = "refresh-clipboard-list";
+
+static DATABASE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+static CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// Distinguishes ids generated within the same nanosecond.
+static ID_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+// 与前端 getSaveDatabasePath 保持一致的数据库文件名
+fn database_file_name<R: Runtime>(app_handle: &AppHandle<R>) -> String {
+    let extname = if cfg!(debug_assertions) { "dev.db" } else { "db" };
+
+    format!("{}.{}", app_handle.package_info().name, extname)
+}
+
+fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    entries::migrate(connection)?;
+    timestamps::migrate(connection)?;
+    emoji::migrate(connection)?;
+    metadata::migrate(connection)?;
+    backfill::migrate(connection)?;
+    search::migrate(connection)?;
+    oplog::migrate(connection)?;
+    relations::migrate(connection)?;
+    tags::migrate(connection)
+}
+
+/// Point the backend at the history database. The data dir
+/// defaults to the app data dir until the frontend reports a custom one.
+pub fn init<R: Runtime>(app_handle: &AppHandle<R>) {
+    if let Ok(dir) = app_handle.path().app_data_dir() {
+        let path = dir.join(database_file_name(app_handle));
+
+        let _ = DATABASE_PATH.lock().unwrap().insert(path);
+    }
+}
+
+pub fn database_path() -> Result<PathBuf, String> {
+    DATABASE_PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "History database is not initialized".to_string())
+}
+
+/// Open and migrate the database ahead of the first query.
+pub fn warm_up() {
+    if let Err(error) = with_connection(|_| Ok(())) {
+        log::warn!("Failed to open history database: {}", error);
+    }
+}
+
+/// Run a closure against the lazily opened history database.
+pub fn with_connection<T>(
+    f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    let mut connection = CONNECTION.lock().unwrap();
+
+    if connection.is_none() {
+        let path = database_path()?;
+
+        let opened = Connection::open(&path).map_err(|e| e.to_string())?;
+        opened
+            .busy_timeout(Duration::from_secs(5))
+            .map_err(|e| e.to_string())?;
+        collation::register(&opened).map_err(|e| e.to_string())?;
+        // 旧版本的表结构先升级，之后的迁移都基于当前的表结构
+        legacy::upgrade_schema(&opened, &path)?;
+        migrate(&opened).map_err(|e| e.to_string())?;
+
+        let _ = connection.insert(opened);
+    }
+
+    f(connection.as_ref().unwrap()).map_err(|e| e.to_string())
+}
+
+/// Now in the `createTime` format the webview writes, `YYYY-MM-DD HH:mm:ss`.
+pub fn local_time() -> String {
+    with_connection(|connection| {
+        connection.query_row("SELECT datetime('now', 'localtime')", [], |row| row.get(0))
+    })
+    .unwrap_or_default()
+}
+
+/// Id for an item the backend creates, the webview uses nanoid for its own.
+pub fn new_item_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    format!(
+        "{:x}-{:x}",
+        nanos,
+        ID_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Tell the webview that the backend changed history rows.
+pub fn notify_changed<R: Runtime>(app_handle: &AppHandle<R>) {
+    let _ = app_handle.emit(REFRESH_CLIPBOARD_LIST_EVENT, ());
+}
+
+// 存储目录变更后重新打开数据库
+#[command]
+pub async fn set_save_data_dir<R: Runtime>(app_handle: AppHandle<R>, dir: String) {
+    let path = PathBuf::from(dir).join(database_file_name(&app_handle));
+
+    let _ = DATABASE_PATH.lock().unwrap().insert(path);
+    let _ = CONNECTION.lock().unwrap().take();
+}
@@ -0,0 +1,434 @@
+use super::{notify_changed, store, with_connection, HistoryRow};
+use crate::vault::{is_enabled, open_text, seal_text};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Runtime};
+
+static DEVICE_ID: Mutex<Option<String>> = Mutex::new(None);
+static LAST_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A single mutation of history. Every operation carries what is needed to
+/// invert it, so undo never has to diff state.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Operation {
+    Insert {
+        row: HistoryRow,
+        #[serde(default)]
+        metadata: BTreeMap<String, String>,
+    },
+    Edit {
+        id: String,
+        before: HistoryRow,
+        after: HistoryRow,
+    },
+    Tag {
+        id: String,
+        key: String,
+        before: Option<String>,
+        after: Option<String>,
+    },
+    Delete {
+        row: HistoryRow,
+        #[serde(default)]
+        metadata: BTreeMap<String, String>,
+    },
+}
+
+impl Operation {
+    pub fn item_id(&self) -> String {
+        match self {
+            Operation::Insert { row, .. } | Operation::Delete { row, .. } => row
+                .get("id")
+                .and_then(|id| id.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            Operation::Edit { id, .. } | Operation::Tag { id, .. } => id.clone(),
+        }
+    }
+
+    pub fn inverse(&self) -> Operation {
+        match self.clone() {
+            Operation::Insert { row, metadata } => Operation::Delete { row, metadata },
+            Operation::Edit { id, before, after } => Operation::Edit {
+                id,
+                before: after,
+                after: before,
+            },
+            Operation::Tag {
+                id,
+                key,
+                before,
+                after,
+            } => Operation::Tag {
+                id,
+                key,
+                before: after,
+                after: before,
+            },
+            Operation::Delete { row, metadata } => Operation::Insert { row, metadata },
+        }
+    }
+
+    /// Apply the operation to the active history store.
+    pub fn apply(&self) -> Result<(), String> {
+        let store = store();
+
+        match self {
+            Operation::Insert { row, metadata } => {
+                store.put_row(row)?;
+
+                metadata
+                    .iter()
+                    .try_for_each(|(key, value)| store.set_metadata(&self.item_id(), key, value))
+            }
+            Operation::Edit { id, after, .. } => store.update_row(id, after),
+            Operation::Tag {
+                id, key, after, ..
+            } => match after {
+                Some(value) => store.set_metadata(id, key, value),
+                None => store.delete_metadata(id, key),
+            },
+            Operation::Delete { .. } => store.delete_item(&self.item_id()).map(|_| ()),
+        }
+    }
+}
+
+/// An operation as stored in the append-only log.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationEntry {
+    /// Local position in the log, only meaningful on this device.
+    #[serde(default)]
+    pub seq: i64,
+    pub op_id: String,
+    pub device_id: String,
+    /// Milliseconds since the Unix epoch, strictly increasing per device.
+    pub timestamp: u64,
+    /// The operation this one reverts.
+    pub undo_of: Option<String>,
+    pub operation: Operation,
+}
+
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS history_oplog (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            op_id TEXT NOT NULL UNIQUE,
+            device_id TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            item_id TEXT NOT NULL,
+            undo_of TEXT,
+            operation TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS history_oplog_item ON history_oplog (item_id);
+        CREATE TABLE IF NOT EXISTS history_oplog_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Id of this device in the log, generated once and kept in the database.
+pub fn device_id() -> Result<String, String> {
+    let mut device_id = DEVICE_ID.lock().unwrap();
+    if let Some(device_id) = device_id.as_ref() {
+        return Ok(device_id.clone());
+    }
+
+    let id = with_connection(|connection| {
+        let stored: Option<String> = connection
+            .query_row(
+                "SELECT value FROM history_oplog_state WHERE key = 'device_id'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if let Some(stored) = stored {
+            return Ok(stored);
+        }
+
+        let mut hasher = DefaultHasher::new();
+        now_millis().hash(&mut hasher);
+        std::process::id().hash(&mut hasher);
+        std::env::temp_dir().hash(&mut hasher);
+        let generated = format!("{:016x}", hasher.finish());
+
+        connection.execute(
+            "INSERT INTO history_oplog_state (key, value) VALUES ('device_id', ?1)",
+            params![generated],
+        )?;
+
+        Ok(generated)
+    })?;
+
+    Ok(device_id.insert(id).clone())
+}
+
+/// Wall clock time, bumped past the last logged timestamp so local
+/// operations stay ordered even when the clock goes backwards.
+fn next_timestamp() -> Result<u64, String> {
+    if LAST_TIMESTAMP.load(Ordering::SeqCst) == 0 {
+        let last: Option<i64> = with_connection(|connection| {
+            connection.query_row("SELECT MAX(timestamp) FROM history_oplog", [], |row| {
+                row.get(0)
+            })
+        })?;
+
+        LAST_TIMESTAMP.fetch_max(last.unwrap_or_default() as u64, Ordering::SeqCst);
+    }
+
+    let now = now_millis();
+    let previous = LAST_TIMESTAMP
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap_or_default();
+
+    Ok(now.max(previous + 1))
+}
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<OperationEntry> {
+    let operation = open_text(&row.get::<_, String>(5)?).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, e.into())
+    })?;
+
+    Ok(OperationEntry {
+        seq: row.get(0)?,
+        op_id: row.get(1)?,
+        device_id: row.get(2)?,
+        timestamp: row.get::<_, i64>(3)? as u64,
+        undo_of: row.get(4)?,
+        operation: serde_json::from_str(&operation).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+    })
+}
+
+const ENTRY_COLUMNS: &str = "seq, op_id, device_id, timestamp, undo_of, operation";
+
+fn insert_entry(entry: &OperationEntry) -> Result<i64, String> {
+    let mut operation = serde_json::to_string(&entry.operation).map_err(|e| e.to_string())?;
+
+    // Operations carry whole rows, in vault mode they are encrypted as one
+    if is_enabled() {
+        operation = seal_text(&operation)?;
+    }
+
+    with_connection(|connection| {
+        connection.execute(
+            "INSERT INTO history_oplog (op_id, device_id, timestamp, item_id, undo_of, operation)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.op_id,
+                entry.device_id,
+                entry.timestamp as i64,
+                entry.operation.item_id(),
+                entry.undo_of,
+                operation,
+            ],
+        )?;
+
+        Ok(connection.last_insert_rowid())
+    })
+}
+
+fn append(operation: Operation, undo_of: Option<String>) -> Result<OperationEntry, String> {
+    let device_id = device_id()?;
+    let timestamp = next_timestamp()?;
+    let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
+
+    let mut entry = OperationEntry {
+        seq: 0,
+        op_id: format!("{}-{:x}-{:x}", device_id, timestamp, counter),
+        device_id,
+        timestamp,
+        undo_of,
+        operation,
+    };
+    entry.seq = insert_entry(&entry)?;
+
+    Ok(entry)
+}
+
+/// Log an operation the webview already applied to the database.
+pub fn record_operation(mut operation: Operation) -> Result<OperationEntry, String> {
+    // The webview deletes the row only, keep its metadata in the log so an
+    // undo can bring it back, then drop the orphaned metadata
+    if matches!(operation, Operation::Delete { .. }) {
+        let id = operation.item_id();
+        let stored = store().get_metadata(&id)?;
+
+        for key in stored.keys() {
+            store().delete_metadata(&id, key)?;
+        }
+
+        if let Operation::Delete { metadata, .. } = &mut operation {
+            metadata.extend(stored);
+        }
+    }
+
+    append(operation, None)
+}
+
+/// Apply an operation and log it.
+pub fn commit_operation(operation: Operation) -> Result<OperationEntry, String> {
+    operation.apply()?;
+
+    append(operation, None)
+}
+
+/// Revert the latest local operation that was not reverted yet.
+pub fn undo_operation() -> Result<Option<OperationEntry>, String> {
+    let device_id = device_id()?;
+
+    let last = with_connection(|connection| {
+        connection
+            .query_row(
+                &format!(
+                    "SELECT {} FROM history_oplog
+                     WHERE device_id = ?1 AND undo_of IS NULL AND op_id NOT IN (
+                        SELECT undo_of FROM history_oplog WHERE undo_of IS NOT NULL
+                     )
+                     ORDER BY seq DESC LIMIT 1",
+                    ENTRY_COLUMNS
+                ),
+                params![device_id],
+                entry_from_row,
+            )
+            .optional()
+    })?;
+
+    let Some(last) = last else {
+        return Ok(None);
+    };
+
+    let inverse = last.operation.inverse();
+    inverse.apply()?;
+
+    append(inverse, Some(last.op_id)).map(Some)
+}
+
+/// Operations logged after `seq`, for the sync engine to ship.
+pub fn operations_since(seq: i64) -> Result<Vec<OperationEntry>, String> {
+    with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM history_oplog WHERE seq > ?1 ORDER BY seq",
+            ENTRY_COLUMNS
+        ))?;
+
+        let rows = statement.query_map(params![seq], entry_from_row)?;
+
+        rows.collect()
+    })
+}
+
+/// Merge operations from another device. Operations are ordered by
+/// (timestamp, device, op id) and the newest operation of an item wins, so
+/// every device ends up with the same state whatever order they sync in.
+/// Returns the number of operations applied.
+pub fn merge_operations(mut entries: Vec<OperationEntry>) -> Result<usize, String> {
+    entries.sort_by(|a, b| {
+        (a.timestamp, &a.device_id, &a.op_id).cmp(&(b.timestamp, &b.device_id, &b.op_id))
+    });
+
+    let mut applied = 0;
+
+    for entry in entries {
+        let (known, newer) = with_connection(|connection| {
+            let known: bool = connection.query_row(
+                "SELECT EXISTS (SELECT 1 FROM history_oplog WHERE op_id = ?1)",
+                params![entry.op_id],
+                |row| row.get(0),
+            )?;
+
+            let newer: bool = connection.query_row(
+                "SELECT EXISTS (
+                    SELECT 1 FROM history_oplog WHERE item_id = ?1
+                    AND (timestamp > ?2 OR (timestamp = ?2 AND device_id > ?3))
+                )",
+                params![
+                    entry.operation.item_id(),
+                    entry.timestamp as i64,
+                    entry.device_id
+                ],
+                |row| row.get(0),
+            )?;
+
+            Ok((known, newer))
+        })?;
+
+        if known {
+            continue;
+        }
+
+        // Still logged, so later merges see the same history on every device
+        if !newer {
+            if let Err(error) = entry.operation.apply() {
+                log::warn!("Failed to apply operation {}: {}", entry.op_id, error);
+            }
+
+            applied += 1;
+        }
+
+        insert_entry(&entry)?;
+    }
+
+    Ok(applied)
+}
+
+// 记录前端对历史记录的修改
+#[command]
+pub async fn record_history_operation(operation: Operation) -> Result<OperationEntry, String> {
+    record_operation(operation)
+}
+
+// 撤销最近一次修改
+#[command]
+pub async fn undo_history_operation<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<Option<OperationEntry>, String> {
+    let entry = undo_operation()?;
+
+    if entry.is_some() {
+        notify_changed(&app_handle);
+    }
+
+    Ok(entry)
+}
+
+// 获取指定位置之后的修改记录
+#[command]
+pub async fn get_history_operations(since: i64) -> Result<Vec<OperationEntry>, String> {
+    operations_since(since)
+}
+
+// 合并其它设备的修改记录
+#[command]
+pub async fn merge_history_operations<R: Runtime>(
+    app_handle: AppHandle<R>,
+    entries: Vec<OperationEntry>,
+) -> Result<usize, String> {
+    let applied = merge_operations(entries)?;
+
+    if applied > 0 {
+        notify_changed(&app_handle);
+    }
+
+    Ok(applied)
+}
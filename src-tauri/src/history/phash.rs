@@ -0,0 +1,107 @@
+use super::{with_connection, NewHistoryEntry};
+use crate::clipboard_image::resolve_image_path;
+use crate::image_decode::decode_file;
+use image::imageops::{self, FilterType};
+use rusqlite::params;
+use serde_json::Value;
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{command, AppHandle, Runtime};
+
+/// Side of the downscaled image the DCT runs on.
+const SAMPLE_SIZE: usize = 32;
+
+/// Side of the low frequency block kept in the hash.
+const HASH_SIZE: usize = 8;
+
+/// Whether new images are hashed as they are saved, otherwise the backfill
+/// and similarity lookups hash them when they first need to.
+static HASH_ON_SAVE: AtomicBool = AtomicBool::new(false);
+
+/// 64-bit DCT perceptual hash of an image file, similar images differ in
+/// only a few bits.
+pub fn phash(path: &Path) -> Result<u64, String> {
+    let image = decode_file(path)?.to_luma8();
+    let sample = imageops::resize(
+        &image,
+        SAMPLE_SIZE as u32,
+        SAMPLE_SIZE as u32,
+        FilterType::Triangle,
+    );
+
+    let pixels: Vec<f64> = sample.pixels().map(|pixel| pixel[0] as f64).collect();
+
+    let mut coefficients = Vec::with_capacity(HASH_SIZE * HASH_SIZE);
+    for u in 0..HASH_SIZE {
+        for v in 0..HASH_SIZE {
+            let mut sum = 0.0;
+
+            for y in 0..SAMPLE_SIZE {
+                for x in 0..SAMPLE_SIZE {
+                    sum += pixels[y * SAMPLE_SIZE + x] * cosine(x, u) * cosine(y, v);
+                }
+            }
+
+            coefficients.push(sum);
+        }
+    }
+
+    // The DC term only reflects the average brightness
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    let hash = coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, &coefficient)| coefficient > median)
+        .fold(0u64, |hash, (index, _)| hash | (1 << index));
+
+    Ok(hash)
+}
+
+fn cosine(position: usize, frequency: usize) -> f64 {
+    ((2 * position + 1) as f64 * frequency as f64 * PI / (2 * SAMPLE_SIZE) as f64).cos()
+}
+
+/// Save the perceptual hash of an image item.
+pub(super) fn save_phash(item_id: &str, hash: u64) -> Result<(), String> {
+    with_connection(|connection| {
+        connection.execute(
+            "INSERT OR REPLACE INTO history_phash (item_id, hash) VALUES (?1, ?2)",
+            params![item_id, hash as i64],
+        )
+    })
+    .map(|_| ())
+}
+
+/// Hash a newly saved image item in the background when hashing on save is
+/// enabled.
+pub(super) fn hash_saved_image<R: Runtime>(app_handle: &AppHandle<R>, entry: &NewHistoryEntry) {
+    if !HASH_ON_SAVE.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let field = |name: &str| entry.row.get(name).and_then(Value::as_str);
+
+    let (Some("image"), Some(id), Some(value)) = (field("type"), field("id"), field("value"))
+    else {
+        return;
+    };
+
+    let id = id.to_string();
+    let path = resolve_image_path(app_handle, value);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(error) = phash(&path).and_then(|hash| save_phash(&id, hash)) {
+            log::warn!("Failed to hash image {}: {}", id, error);
+        }
+    });
+}
+
+// 设置是否在保存图片时计算感知哈希，开启后查找相似图片时不必等待计算
+#[command]
+pub async fn set_image_hashing(enabled: bool) {
+    HASH_ON_SAVE.store(enabled, Ordering::Relaxed);
+}
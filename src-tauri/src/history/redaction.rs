@@ -0,0 +1,128 @@
+use super::{older_than, with_connection, HistoryRow, SENSITIVE};
+use crate::item_access::within_grace_period;
+use rusqlite::params_from_iter;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Shown instead of the content of a redacted item.
+pub const REDACTED_TEXT: &str = "••••••••";
+
+/// Columns that carry or describe an item's content.
+pub(super) const CONTENT_COLUMNS: &[&str] = &["value", "search", "note"];
+
+/// Below SQLite's limit of bound parameters.
+const MAX_IDS_PER_QUERY: usize = 500;
+
+static REDACTING: AtomicBool = AtomicBool::new(false);
+
+/// Hide sensitive items in query results even after an authentication,
+/// e.g. while the screen is shared.
+pub fn set_redacting(redacting: bool) {
+    REDACTING.store(redacting, Ordering::Relaxed);
+}
+
+pub fn is_redacting() -> bool {
+    REDACTING.load(Ordering::Relaxed)
+}
+
+/// Ids of the given rows that have to be redacted right now. Sensitive
+/// items stay hidden unless the user authenticated within the grace period,
+/// and always while the screen is shared.
+pub(super) fn redacted_ids<'a>(
+    rows: impl IntoIterator<Item = &'a HistoryRow>,
+) -> Result<HashSet<String>, String> {
+    redacted_among(
+        rows.into_iter()
+            .filter_map(|row| row.get("id").and_then(Value::as_str)),
+    )
+}
+
+/// Like [`redacted_ids`], for bare ids.
+pub fn redacted_among<'a>(
+    ids: impl IntoIterator<Item = &'a str>,
+) -> Result<HashSet<String>, String> {
+    if !is_redacting() && within_grace_period() {
+        return Ok(HashSet::new());
+    }
+
+    sensitive_among(ids)
+}
+
+/// Ids among the given ones flagged sensitive, whether or not they are
+/// shown right now.
+pub fn sensitive_among<'a>(
+    ids: impl IntoIterator<Item = &'a str>,
+) -> Result<HashSet<String>, String> {
+    let ids: Vec<&str> = ids.into_iter().collect();
+    let mut sensitive = HashSet::new();
+
+    // Whole-history callers pass more ids than a statement takes
+    for chunk in ids.chunks(MAX_IDS_PER_QUERY) {
+        let placeholders = vec!["?"; chunk.len()].join(", ");
+
+        sensitive.extend(with_connection(|connection| {
+            let mut statement = connection.prepare(&format!(
+                "SELECT item_id FROM history_metadata
+                WHERE key = '{}' AND value = '1' AND item_id IN ({})",
+                SENSITIVE, placeholders
+            ))?;
+            let rows = statement.query_map(params_from_iter(chunk.iter()), |row| row.get(0))?;
+
+            rows.collect::<rusqlite::Result<Vec<String>>>()
+        })?);
+    }
+
+    Ok(sensitive)
+}
+
+/// Mask the content of a row, `redacted` tells the webview to show a
+/// placeholder instead of a preview.
+pub(super) fn redact(row: &mut HistoryRow) {
+    for &column in CONTENT_COLUMNS {
+        if row.get(column).is_some_and(|value| !value.is_null()) {
+            row.insert(column.to_string(), Value::from(REDACTED_TEXT));
+        }
+    }
+
+    row.insert("redacted".to_string(), Value::Bool(true));
+}
+
+pub(super) fn redact_rows(rows: &mut [HistoryRow]) -> Result<(), String> {
+    let ids = redacted_ids(rows.iter())?;
+
+    for row in rows.iter_mut() {
+        if row
+            .get("id")
+            .and_then(Value::as_str)
+            .is_some_and(|id| ids.contains(id))
+        {
+            redact(row);
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the content of a single item has to be hidden right now.
+pub fn is_redacted(id: &str) -> Result<bool, String> {
+    Ok(!redacted_among([id])?.is_empty())
+}
+
+/// Sensitive items copied more than `minutes` ago.
+pub fn expired_sensitive_ids(minutes: u64) -> Result<Vec<String>, String> {
+    with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT history.id FROM history
+            JOIN history_metadata ON history_metadata.item_id = history.id
+            WHERE history_metadata.key = '{}' AND history_metadata.value = '1'
+            AND history.favorite = 0
+            AND history.{}",
+            SENSITIVE,
+            older_than(minutes * 60)
+        ))?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+
+        rows.collect()
+    })
+}
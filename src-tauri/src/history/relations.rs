@@ -0,0 +1,178 @@
+use super::redaction::redacted_among;
+use super::with_connection;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::command;
+
+/// How a derived item relates to the item it was generated from.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RelationKind {
+    /// Translation of the source text.
+    Translation,
+    /// Text recognized in the source image.
+    Ocr,
+    /// Output of a text transform.
+    Transform,
+    /// Output of an AI action.
+    Ai,
+    /// Any other user made link.
+    Link,
+}
+
+impl RelationKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RelationKind::Translation => "translation",
+            RelationKind::Ocr => "ocr",
+            RelationKind::Transform => "transform",
+            RelationKind::Ai => "ai",
+            RelationKind::Link => "link",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "translation" => RelationKind::Translation,
+            "ocr" => RelationKind::Ocr,
+            "transform" => RelationKind::Transform,
+            "ai" => RelationKind::Ai,
+            _ => RelationKind::Link,
+        }
+    }
+}
+
+/// Which end of a relation the related item is.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RelationDirection {
+    /// The related item is the source the queried item derives from.
+    Source,
+    /// The related item derives from the queried item.
+    Derived,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedItem {
+    pub id: String,
+    pub kind: RelationKind,
+    pub direction: RelationDirection,
+    /// Milliseconds since the epoch when the link was made.
+    pub created_at: i64,
+}
+
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS history_relations (
+            source_id TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (source_id, target_id, kind)
+        );
+        CREATE INDEX IF NOT EXISTS history_relations_target ON history_relations (target_id);",
+    )
+}
+
+/// Record that `target_id` derives from `source_id`.
+pub fn link_items(source_id: &str, target_id: &str, kind: RelationKind) -> Result<(), String> {
+    if source_id == target_id {
+        return Err("An item can not be linked to itself".to_string());
+    }
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    with_connection(|connection| {
+        connection.execute(
+            "INSERT OR IGNORE INTO history_relations (source_id, target_id, kind, created_at)
+            VALUES (?1, ?2, ?3, ?4)",
+            params![source_id, target_id, kind.as_str(), created_at],
+        )
+    })
+    .map(|_| ())
+}
+
+pub fn unlink_items(source_id: &str, target_id: &str) -> Result<(), String> {
+    with_connection(|connection| {
+        connection.execute(
+            "DELETE FROM history_relations WHERE source_id = ?1 AND target_id = ?2",
+            params![source_id, target_id],
+        )
+    })
+    .map(|_| ())
+}
+
+/// Items linked to `id` in either direction. Links to deleted items are kept
+/// so an undo restores them, but are not returned. Neither are links of
+/// sensitive items hidden right now, a translation or OCR result tells
+/// their content.
+pub fn related_items(id: &str) -> Result<Vec<RelatedItem>, String> {
+    if !redacted_among([id])?.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut related: Vec<RelatedItem> = with_connection(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT relation.source_id, relation.kind, relation.created_at, 0
+            FROM history_relations relation
+            JOIN history ON history.id = relation.source_id
+            WHERE relation.target_id = ?1
+            UNION ALL
+            SELECT relation.target_id, relation.kind, relation.created_at, 1
+            FROM history_relations relation
+            JOIN history ON history.id = relation.target_id
+            WHERE relation.source_id = ?1
+            ORDER BY 3 DESC",
+        )?;
+
+        let rows = statement.query_map([id], |row| {
+            let kind: String = row.get(1)?;
+            let derived: bool = row.get(3)?;
+
+            Ok(RelatedItem {
+                id: row.get(0)?,
+                kind: RelationKind::parse(&kind),
+                direction: if derived {
+                    RelationDirection::Derived
+                } else {
+                    RelationDirection::Source
+                },
+                created_at: row.get(2)?,
+            })
+        })?;
+
+        rows.collect()
+    })?;
+
+    let redacted = redacted_among(related.iter().map(|item| item.id.as_str()))?;
+    related.retain(|item| !redacted.contains(&item.id));
+
+    Ok(related)
+}
+
+// 关联两个条目，target 由 source 生成（翻译、OCR、AI 等）
+#[command]
+pub async fn link_history_items(
+    source_id: String,
+    target_id: String,
+    kind: RelationKind,
+) -> Result<(), String> {
+    link_items(&source_id, &target_id, kind)
+}
+
+// 取消两个条目的关联
+#[command]
+pub async fn unlink_history_items(source_id: String, target_id: String) -> Result<(), String> {
+    unlink_items(&source_id, &target_id)
+}
+
+// 获取与条目关联的条目
+#[command]
+pub async fn get_related(id: String) -> Result<Vec<RelatedItem>, String> {
+    related_items(&id)
+}
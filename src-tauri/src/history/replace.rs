@@ -0,0 +1,188 @@
+use super::manual::edit_entry_content;
+use super::tags::TAG_CONDITION;
+use super::{notify_changed, store, with_connection, SENSITIVE};
+use regex::Regex;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value as SqlValue;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{command, AppHandle, Runtime};
+
+/// Which text items a replacement looks at, every text item by default.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReplaceFilter {
+    /// Only these items.
+    pub ids: Vec<String>,
+    pub favorite_only: bool,
+    /// Only items carrying this tag.
+    pub tag: Option<String>,
+    /// Inclusive `createTime` bounds, in the format the webview stores.
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceChange {
+    pub id: String,
+    pub before: String,
+    pub after: String,
+    /// Number of matches replaced in the item.
+    pub replacements: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceReport {
+    pub changes: Vec<ReplaceChange>,
+    /// `false` for a dry run.
+    pub applied: bool,
+}
+
+/// Text items matching the filter. Sensitive items are left out, a dry run
+/// would otherwise show their content.
+fn candidates(filter: &ReplaceFilter) -> Result<Vec<(String, String)>, String> {
+    let mut conditions = vec![
+        "type = 'text'".to_string(),
+        format!(
+            "NOT EXISTS (
+                SELECT 1 FROM history_metadata
+                WHERE item_id = history.id AND key = '{}' AND value = '1'
+            )",
+            SENSITIVE
+        ),
+    ];
+    let mut values = Vec::new();
+
+    if !filter.ids.is_empty() {
+        conditions.push(format!(
+            "id IN ({})",
+            vec!["?"; filter.ids.len()].join(", ")
+        ));
+        values.extend(filter.ids.iter().cloned().map(SqlValue::Text));
+    }
+
+    if filter.favorite_only {
+        conditions.push("favorite = 1".to_string());
+    }
+
+    if let Some(tag) = filter.tag.as_ref().filter(|tag| !tag.is_empty()) {
+        conditions.push(TAG_CONDITION.to_string());
+        values.push(SqlValue::Text(tag.clone()));
+    }
+
+    if let Some(since) = &filter.since {
+        conditions.push("createTime >= ?".to_string());
+        values.push(SqlValue::Text(since.clone()));
+    }
+
+    if let Some(until) = &filter.until {
+        conditions.push("createTime <= ?".to_string());
+        values.push(SqlValue::Text(until.clone()));
+    }
+
+    let ids: Vec<String> = with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT id FROM history WHERE {} ORDER BY seq DESC",
+            conditions.join(" AND ")
+        ))?;
+
+        let rows = statement.query_map(params_from_iter(values.iter()), |row| row.get(0))?;
+
+        rows.collect()
+    })?;
+
+    // Read through the store, the regex has to see the decrypted text and
+    // the edit is encrypted again when the vault is enabled
+    let store = store();
+
+    ids.into_iter()
+        .filter_map(|id| store.get_row(&id).transpose().map(|row| (id, row)))
+        .map(|(id, row)| {
+            let value = row?
+                .get("value")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+
+            Ok((id, value))
+        })
+        .collect()
+}
+
+/// What the replacement would change, items it leaves as they are are not
+/// listed.
+pub fn plan_replace(
+    pattern: &str,
+    replacement: &str,
+    filter: &ReplaceFilter,
+) -> Result<Vec<ReplaceChange>, String> {
+    let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+
+    Ok(candidates(filter)?
+        .into_iter()
+        .filter_map(|(id, before)| {
+            let replacements = regex.find_iter(&before).count();
+            let after = regex.replace_all(&before, replacement).into_owned();
+
+            (after != before).then_some(ReplaceChange {
+                id,
+                before,
+                after,
+                replacements,
+            })
+        })
+        .collect())
+}
+
+/// Edit every planned item through the operation log, so each change can
+/// be undone on its own. When one edit fails the ones already made are
+/// reverted and nothing is changed.
+pub fn apply_replace(changes: &[ReplaceChange]) -> Result<(), String> {
+    for (index, change) in changes.iter().enumerate() {
+        if let Err(error) = edit_entry_content(&change.id, &change.after) {
+            for applied in changes[..index].iter().rev() {
+                if let Err(error) = edit_entry_content(&applied.id, &applied.before) {
+                    log::warn!(
+                        "Failed to revert the replacement in {}: {}",
+                        applied.id,
+                        error
+                    );
+                }
+            }
+
+            return Err(format!("Failed to replace in {}: {}", change.id, error));
+        }
+    }
+
+    Ok(())
+}
+
+// 在历史记录的文本条目中批量正则替换，dry_run 时只返回将要修改的内容
+#[command]
+pub async fn history_replace<R: Runtime>(
+    app_handle: AppHandle<R>,
+    pattern: String,
+    replacement: String,
+    filter: Option<ReplaceFilter>,
+    dry_run: bool,
+) -> Result<ReplaceReport, String> {
+    let changes = plan_replace(&pattern, &replacement, &filter.unwrap_or_default())?;
+
+    if dry_run || changes.is_empty() {
+        return Ok(ReplaceReport {
+            changes,
+            applied: false,
+        });
+    }
+
+    apply_replace(&changes)?;
+
+    notify_changed(&app_handle);
+
+    Ok(ReplaceReport {
+        changes,
+        applied: true,
+    })
+}
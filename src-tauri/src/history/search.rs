@@ -0,0 +1,527 @@
+use super::collation::{fold_char, fold_text};
+use super::encryption::open_row;
+use super::redaction::{redact, redacted_ids, REDACTED_TEXT};
+use super::sources::SOURCE_APP_CONDITION;
+use super::store::{history_column_list, history_row};
+use super::tags::TAG_CONDITION;
+use super::{with_connection, HistoryRow};
+use crate::core::cache;
+use crate::vault;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use tauri::command;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page returned by a single search.
+const MAX_PAGE_SIZE: usize = 200;
+
+/// The trigram tokenizer cannot match shorter terms through the index.
+const MIN_INDEXED_CHARS: usize = 3;
+
+/// Length of the excerpt returned with each match.
+const SNIPPET_CHARS: usize = 160;
+
+/// Characters kept before the first match in the excerpt.
+const SNIPPET_LEAD_CHARS: usize = 40;
+
+const ELLIPSIS: char = '…';
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SearchFilters {
+    /// A group id, `all`, `favorite` or `screenshot`.
+    pub group: Option<String>,
+    /// Only items carrying this tag.
+    pub tag: Option<String>,
+    /// Only items copied from this app.
+    pub source_app: Option<String>,
+    /// Inclusive `createTime` bounds, in the format the webview stores.
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// 1-based.
+    pub page: usize,
+    /// Defaults to 50.
+    pub size: usize,
+}
+
+/// A matched range of a snippet, in UTF-16 code units like JS string indices.
+#[derive(Serialize, Clone, Copy)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub item: HistoryRow,
+    /// bm25 of the match, lower ranks first. Zero when only short terms
+    /// were searched and results are ordered by time.
+    pub rank: f64,
+    /// Excerpt of the search text or note around the first match.
+    pub snippet: String,
+    pub highlights: Vec<Highlight>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    pub matches: Vec<SearchMatch>,
+    /// Matches across all pages.
+    pub total: usize,
+    pub has_more: bool,
+}
+
+/// The text `history_fts` indexes for a row, kept in sync with the triggers.
+pub(super) fn fts_content(search: &str, note: &str) -> String {
+    format!("{}\n{}", search, note)
+}
+
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    let indexed: Option<String> = connection
+        .query_row(
+            "SELECT name FROM sqlite_master WHERE type = 'trigger' AND name = 'history_fts_insert'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    // Whoever writes `history`, the webview or the backend, keeps the index
+    // current through these triggers
+    connection.execute_batch(
+        "CREATE TRIGGER IF NOT EXISTS history_fts_insert AFTER INSERT ON history
+        WHEN COALESCE(new.search, '') != '' OR COALESCE(new.note, '') != ''
+        BEGIN
+            INSERT INTO history_fts (item_id, content)
+            VALUES (new.id, COALESCE(new.search, '') || char(10) || COALESCE(new.note, ''));
+        END;
+        CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history
+        BEGIN
+            DELETE FROM history_fts WHERE item_id = old.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE OF id, search, note ON history
+        BEGIN
+            DELETE FROM history_fts WHERE item_id = old.id;
+            INSERT INTO history_fts (item_id, content)
+            SELECT new.id, COALESCE(new.search, '') || char(10) || COALESCE(new.note, '')
+            WHERE COALESCE(new.search, '') != '' OR COALESCE(new.note, '') != '';
+        END;",
+    )?;
+
+    // Rows written before the triggers existed are picked up by the backfill,
+    // which resumes on the next start
+    if indexed.is_none() {
+        connection.execute(
+            "INSERT INTO backfill_checkpoint (id, cursor, processed, active) VALUES (0, 0, 0, 1)
+             ON CONFLICT (id) DO UPDATE SET active = 1",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Whitespace separated terms, folded and without duplicates.
+pub(super) fn split_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in query.split_whitespace().map(fold_text) {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+
+    terms
+}
+
+/// Each term as an FTS5 string, so user input is never parsed as syntax.
+fn match_expression(terms: &[&String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Folded per character, so indices line up with the original text.
+fn fold(text: &str) -> Vec<char> {
+    text.chars().map(fold_char).collect()
+}
+
+/// Character ranges of every term in `text`, sorted and merged.
+fn find_ranges(text: &[char], terms: &[String]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+
+    for term in terms {
+        let term = fold(term);
+        if term.is_empty() || term.len() > text.len() {
+            continue;
+        }
+
+        for start in 0..=text.len() - term.len() {
+            if text[start..start + term.len()] == term[..] {
+                ranges.push((start, start + term.len()));
+            }
+        }
+    }
+
+    ranges.sort();
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+/// Cut an excerpt around the first match and translate the matches into it.
+fn excerpt(text: &str, terms: &[String]) -> (String, Vec<Highlight>) {
+    let chars: Vec<char> = text.chars().collect();
+    let ranges = find_ranges(&fold(text), terms);
+
+    let start = ranges
+        .first()
+        .map_or(0, |(start, _)| start.saturating_sub(SNIPPET_LEAD_CHARS));
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+
+    let mut snippet = String::new();
+    let mut offsets = Vec::with_capacity(end - start + 1);
+    let mut offset = 0;
+
+    if start > 0 {
+        snippet.push(ELLIPSIS);
+        offset += ELLIPSIS.len_utf16();
+    }
+
+    for &ch in &chars[start..end] {
+        offsets.push(offset);
+
+        let ch = if ch.is_whitespace() { ' ' } else { ch };
+        snippet.push(ch);
+        offset += ch.len_utf16();
+    }
+    offsets.push(offset);
+
+    if end < chars.len() {
+        snippet.push(ELLIPSIS);
+    }
+
+    let highlights = ranges
+        .into_iter()
+        .filter(|&(range_start, range_end)| range_start < end && range_end > start)
+        .map(|(range_start, range_end)| Highlight {
+            start: offsets[range_start.max(start) - start],
+            end: offsets[range_end.min(end) - start],
+        })
+        .collect();
+
+    (snippet, highlights)
+}
+
+/// Excerpt from the search text, or from the note when only it matched.
+pub(super) fn snippet_of(row: &HistoryRow, terms: &[String]) -> (String, Vec<Highlight>) {
+    let field = |name: &str| row.get(name).and_then(Value::as_str).unwrap_or_default();
+
+    let search = field("search");
+    let note = field("note");
+
+    let (snippet, highlights) = excerpt(search, terms);
+    if highlights.is_empty() && !note.is_empty() {
+        let (note_snippet, note_highlights) = excerpt(note, terms);
+
+        if !note_highlights.is_empty() {
+            return (note_snippet, note_highlights);
+        }
+    }
+
+    (snippet, highlights)
+}
+
+/// Rank matches through `history_fts`. Terms of three or more characters go
+/// through the trigram index, shorter ones are filtered by a scan.
+pub fn search_entries(query: &str, filters: &SearchFilters) -> Result<SearchResults, String> {
+    let terms = split_terms(query);
+    let size = match filters.size {
+        0 => DEFAULT_PAGE_SIZE,
+        size => size.min(MAX_PAGE_SIZE),
+    };
+    let offset = filters.page.max(1).saturating_sub(1) * size;
+
+    if terms.is_empty() {
+        return Ok(SearchResults {
+            matches: Vec::new(),
+            total: 0,
+            has_more: false,
+        });
+    }
+
+    // The index only holds encrypted text in vault mode
+    if vault::is_enabled() {
+        let mut results = search_sealed(&terms, filters, size, offset)?;
+        redact_matches(&mut results)?;
+
+        return Ok(results);
+    }
+
+    let (indexed, scanned): (Vec<&String>, Vec<&String>) = terms
+        .iter()
+        .partition(|term| term.chars().count() >= MIN_INDEXED_CHARS);
+
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    if !indexed.is_empty() {
+        conditions.push("history_fts MATCH ?");
+        values.push(SqlValue::Text(match_expression(&indexed)));
+    }
+
+    for term in scanned {
+        conditions.push("instr(fold(content), ?) > 0");
+        values.push(SqlValue::Text(term.clone()));
+    }
+
+    match filters.group.as_deref() {
+        None | Some("all") => {}
+        Some("favorite") => conditions.push("favorite = 1"),
+        Some("screenshot") => conditions.push("subtype = 'screenshot'"),
+        Some(group) => {
+            conditions.push("\"group\" = ?");
+            values.push(SqlValue::Text(group.to_string()));
+        }
+    }
+
+    if let Some(tag) = filters.tag.as_ref().filter(|tag| !tag.is_empty()) {
+        conditions.push(TAG_CONDITION);
+        values.push(SqlValue::Text(tag.clone()));
+    }
+
+    if let Some(app) = filters.source_app.as_ref().filter(|app| !app.is_empty()) {
+        conditions.push(SOURCE_APP_CONDITION);
+        values.push(SqlValue::Text(app.clone()));
+    }
+
+    if let Some(since) = &filters.since {
+        conditions.push("createTime >= ?");
+        values.push(SqlValue::Text(since.clone()));
+    }
+
+    if let Some(until) = &filters.until {
+        conditions.push("createTime <= ?");
+        values.push(SqlValue::Text(until.clone()));
+    }
+
+    let filter = format!(
+        "FROM history_fts JOIN history ON history.id = history_fts.item_id WHERE {}",
+        conditions.join(" AND ")
+    );
+    let (rank, order) = if indexed.is_empty() {
+        ("0.0", "history.seq DESC")
+    } else {
+        ("rank", "rank, history.seq DESC")
+    };
+
+    let mut results = with_connection(|connection| {
+        let total: i64 = connection.query_row(
+            &format!("SELECT COUNT(*) {}", filter),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut statement = connection.prepare(&format!(
+            "SELECT {}, {} {} ORDER BY {} LIMIT {} OFFSET {}",
+            history_column_list(),
+            rank,
+            filter,
+            order,
+            size,
+            offset
+        ))?;
+        let rank_index = statement.column_count() - 1;
+
+        let rows = statement
+            .query_map(params_from_iter(values.iter()), |row| {
+                Ok((history_row(row)?, row.get::<_, f64>(rank_index)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let matches: Vec<SearchMatch> = rows
+            .into_iter()
+            .map(|(item, rank)| {
+                let (snippet, highlights) = snippet_of(&item, &terms);
+
+                SearchMatch {
+                    item,
+                    rank,
+                    snippet,
+                    highlights,
+                }
+            })
+            .collect();
+
+        Ok(SearchResults {
+            has_more: offset + matches.len() < total as usize,
+            total: total as usize,
+            matches,
+        })
+    })?;
+
+    redact_matches(&mut results)?;
+
+    Ok(results)
+}
+
+/// Neither the excerpt nor its highlights may give the content away.
+fn redact_matches(results: &mut SearchResults) -> Result<(), String> {
+    let redacted = redacted_ids(results.matches.iter().map(|found| &found.item))?;
+    for found in &mut results.matches {
+        if found
+            .item
+            .get("id")
+            .and_then(Value::as_str)
+            .is_some_and(|id| redacted.contains(id))
+        {
+            redact(&mut found.item);
+            found.snippet = REDACTED_TEXT.to_string();
+            found.highlights.clear();
+        }
+    }
+
+    Ok(())
+}
+
+/// Cache key of a row's searchable content, sealing uses a fresh nonce so
+/// the key changes whenever the content does.
+fn sealed_content_key(row: &HistoryRow) -> String {
+    let field = |name: &str| row.get(name).and_then(Value::as_str).unwrap_or_default();
+
+    let mut hasher = DefaultHasher::new();
+    field("search").hash(&mut hasher);
+    field("note").hash(&mut hasher);
+
+    format!("{}:{:x}", field("id"), hasher.finish())
+}
+
+/// Match every term against the decrypted rows the filters select, newest
+/// first. Nothing matches while the vault is locked.
+fn search_sealed(
+    terms: &[String],
+    filters: &SearchFilters,
+    size: usize,
+    offset: usize,
+) -> Result<SearchResults, String> {
+    if !vault::is_unlocked() {
+        return Ok(SearchResults {
+            matches: Vec::new(),
+            total: 0,
+            has_more: false,
+        });
+    }
+
+    let mut conditions = vec!["1 = 1"];
+    let mut values = Vec::new();
+
+    match filters.group.as_deref() {
+        None | Some("all") => {}
+        Some("favorite") => conditions.push("favorite = 1"),
+        Some("screenshot") => conditions.push("subtype = 'screenshot'"),
+        Some(group) => {
+            conditions.push("\"group\" = ?");
+            values.push(SqlValue::Text(group.to_string()));
+        }
+    }
+
+    if let Some(tag) = filters.tag.as_ref().filter(|tag| !tag.is_empty()) {
+        conditions.push(TAG_CONDITION);
+        values.push(SqlValue::Text(tag.clone()));
+    }
+
+    if let Some(app) = filters.source_app.as_ref().filter(|app| !app.is_empty()) {
+        conditions.push(SOURCE_APP_CONDITION);
+        values.push(SqlValue::Text(app.clone()));
+    }
+
+    if let Some(since) = &filters.since {
+        conditions.push("createTime >= ?");
+        values.push(SqlValue::Text(since.clone()));
+    }
+
+    if let Some(until) = &filters.until {
+        conditions.push("createTime <= ?");
+        values.push(SqlValue::Text(until.clone()));
+    }
+
+    let rows = with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM history WHERE {} ORDER BY seq DESC",
+            history_column_list(),
+            conditions.join(" AND ")
+        ))?;
+        let rows = statement.query_map(params_from_iter(values.iter()), history_row)?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+
+    // Only matches are decrypted once the folded content of a row is cached
+    let mut matching = Vec::new();
+    for mut row in rows {
+        let key = sealed_content_key(&row);
+
+        let content = match cache::get_string(cache::SEARCH, &key) {
+            Some(content) => content,
+            None => {
+                open_row(&mut row)?;
+
+                let field = |name: &str| row.get(name).and_then(Value::as_str).unwrap_or_default();
+                let content = fold_text(&fts_content(field("search"), field("note")));
+                cache::put_string(cache::SEARCH, key, &content);
+
+                content
+            }
+        };
+
+        if terms.iter().all(|term| content.contains(term.as_str())) {
+            open_row(&mut row)?;
+            matching.push(row);
+        }
+    }
+
+    let total = matching.len();
+    let matches: Vec<SearchMatch> = matching
+        .into_iter()
+        .skip(offset)
+        .take(size)
+        .map(|item| {
+            let (snippet, highlights) = snippet_of(&item, terms);
+
+            SearchMatch {
+                item,
+                rank: 0.0,
+                snippet,
+                highlights,
+            }
+        })
+        .collect();
+
+    Ok(SearchResults {
+        has_more: offset + matches.len() < total,
+        total,
+        matches,
+    })
+}
+
+// 全文搜索历史记录，按相关度排序并返回高亮位置
+#[command]
+pub async fn search_history(
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<SearchResults, String> {
+    search_entries(&query, &filters.unwrap_or_default())
+}
@@ -0,0 +1,52 @@
+use super::{with_connection, SOURCE_APP, SOURCE_ICON};
+use rusqlite::params;
+use serde::Serialize;
+use tauri::command;
+
+/// Matches items copied from the app bound to the placeholder.
+pub(super) const SOURCE_APP_CONDITION: &str = "EXISTS (
+    SELECT 1 FROM history_metadata
+    WHERE item_id = history.id AND key = 'source_app'
+    AND value = ? COLLATE NOCASE
+)";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceAppSummary {
+    pub name: String,
+    /// Cached PNG of the app icon, when one could be extracted.
+    pub icon: Option<String>,
+    pub count: usize,
+}
+
+/// Every app items were copied from with the number of items, most used
+/// first.
+pub fn source_app_summaries() -> Result<Vec<SourceAppSummary>, String> {
+    with_connection(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT app.value, COUNT(*), MAX(icon.value)
+            FROM history_metadata app
+            LEFT JOIN history_metadata icon
+                ON icon.item_id = app.item_id AND icon.key = ?2
+            WHERE app.key = ?1
+            GROUP BY app.value COLLATE NOCASE
+            ORDER BY COUNT(*) DESC, app.value COLLATE locale",
+        )?;
+
+        let summaries = statement.query_map(params![SOURCE_APP, SOURCE_ICON], |row| {
+            Ok(SourceAppSummary {
+                name: row.get(0)?,
+                count: row.get::<_, i64>(1)? as usize,
+                icon: row.get(2)?,
+            })
+        })?;
+
+        summaries.collect()
+    })
+}
+
+// 获取复制来源的应用及各自的条目数，用于按应用筛选
+#[command]
+pub async fn list_source_apps() -> Result<Vec<SourceAppSummary>, String> {
+    source_app_summaries()
+}
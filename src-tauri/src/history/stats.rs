@@ -0,0 +1,123 @@
+use super::{with_connection, ACCESS_COUNT, LAST_ACCESS, SENSITIVE, SOURCE_APP};
+use rusqlite::params;
+use serde::Serialize;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeCount {
+    pub kind: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppCount {
+    pub app: String,
+    pub count: usize,
+}
+
+/// What was copied between two `createTime`s.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodStats {
+    pub total: usize,
+    pub favorites: usize,
+    /// Most copied type first.
+    pub by_type: Vec<TypeCount>,
+    /// Apps items were copied from, most first.
+    pub top_apps: Vec<AppCount>,
+}
+
+/// Count the items created in `[since, until)`.
+pub fn period_stats(since: &str, until: &str, top_apps: usize) -> Result<PeriodStats, String> {
+    with_connection(|connection| {
+        let (total, favorites) = connection.query_row(
+            "SELECT count(*), coalesce(sum(favorite), 0) FROM history
+            WHERE createTime >= ?1 AND createTime < ?2",
+            params![since, until],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+
+        let by_type = {
+            let mut statement = connection.prepare(
+                "SELECT coalesce(type, ''), count(*) AS total FROM history
+                WHERE createTime >= ?1 AND createTime < ?2
+                GROUP BY 1 ORDER BY total DESC",
+            )?;
+            let rows = statement.query_map(params![since, until], |row| {
+                Ok(TypeCount {
+                    kind: row.get(0)?,
+                    count: row.get::<_, i64>(1)? as usize,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        let top_apps = {
+            let mut statement = connection.prepare(
+                "SELECT metadata.value, count(*) AS total FROM history
+                JOIN history_metadata metadata ON metadata.item_id = history.id
+                    AND metadata.key = ?3
+                WHERE history.createTime >= ?1 AND history.createTime < ?2
+                GROUP BY metadata.value ORDER BY total DESC LIMIT ?4",
+            )?;
+            let rows =
+                statement.query_map(params![since, until, SOURCE_APP, top_apps as i64], |row| {
+                    Ok(AppCount {
+                        app: row.get(0)?,
+                        count: row.get::<_, i64>(1)? as usize,
+                    })
+                })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        Ok(PeriodStats {
+            total: total as usize,
+            favorites: favorites as usize,
+            by_type,
+            top_apps,
+        })
+    })
+}
+
+/// Ids of the items created or used in `[since, until)` ranked by
+/// frecency: every access and a favorite count for more, and the score
+/// decays by the days since the item was last used. Sensitive items are
+/// left out, their content should not end up in a summary.
+pub fn frecent_items(since: &str, until: &str, limit: usize) -> Result<Vec<String>, String> {
+    with_connection(|connection| {
+        let mut statement = connection.prepare(
+            "SELECT history.id FROM history
+            LEFT JOIN history_metadata accesses ON accesses.item_id = history.id
+                AND accesses.key = ?3
+            LEFT JOIN history_metadata accessed ON accessed.item_id = history.id
+                AND accessed.key = ?4
+            WHERE (history.createTime >= ?1 AND history.createTime < ?2
+                OR accessed.value >= ?1 AND accessed.value < ?2)
+            AND NOT EXISTS (
+                SELECT 1 FROM history_metadata
+                WHERE item_id = history.id AND key = ?5 AND value = '1'
+            )
+            ORDER BY (1 + coalesce(CAST(accesses.value AS INTEGER), 0)
+                + 2 * coalesce(history.favorite, 0))
+                / (1 + max(julianday(?2)
+                    - julianday(coalesce(accessed.value, history.createTime)), 0)) DESC,
+                history.createTime DESC
+            LIMIT ?6",
+        )?;
+
+        let ids = statement.query_map(
+            params![
+                since,
+                until,
+                ACCESS_COUNT,
+                LAST_ACCESS,
+                SENSITIVE,
+                limit as i64
+            ],
+            |row| row.get(0),
+        )?;
+
+        ids.collect()
+    })
+}
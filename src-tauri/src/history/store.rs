@@ -0,0 +1,574 @@
+use super::collation::{compare, fold_text};
+use super::entries::delete_formats;
+use super::{with_connection, HistoryItem};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, params_from_iter, OptionalExtension, Row};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+/// Columns of the `history` table, created by `entries::migrate`.
+pub const HISTORY_COLUMNS: &[&str] = &[
+    "id",
+    "type",
+    "group",
+    "value",
+    "search",
+    "count",
+    "width",
+    "height",
+    "favorite",
+    "createTime",
+    "note",
+    "subtype",
+];
+
+/// A complete `history` row keyed by column name, as the webview stores it.
+pub type HistoryRow = BTreeMap<String, Value>;
+
+/// Storage of history items and their metadata. The SQLite database shared
+/// with the webview is the default; other backends (an encrypted database,
+/// an in-memory store for tests) can be swapped in with [`use_store`].
+///
+/// Maintenance that is specific to SQLite (storage optimization, the full
+/// text backfill) keeps using [`with_connection`] directly.
+pub trait HistoryStore: Send + Sync {
+    fn get_item(&self, id: &str) -> Result<Option<HistoryItem>, String>;
+
+    /// Newest items first.
+    fn list_items(&self, limit: usize) -> Result<Vec<HistoryItem>, String>;
+
+    /// Items created on a `YYYY-MM-DD` day, oldest first.
+    fn list_items_on(&self, date: &str) -> Result<Vec<HistoryItem>, String>;
+
+    /// Favorited items, newest first.
+    fn list_favorites(&self) -> Result<Vec<HistoryItem>, String>;
+
+    fn insert_item(&self, item: &HistoryItem) -> Result<(), String>;
+
+    /// Returns whether an item was deleted.
+    fn delete_item(&self, id: &str) -> Result<bool, String>;
+
+    fn get_row(&self, id: &str) -> Result<Option<HistoryRow>, String>;
+
+    /// Insert a full row, replacing an existing row with the same id.
+    fn put_row(&self, row: &HistoryRow) -> Result<(), String>;
+
+    /// Overwrite some columns of a row.
+    fn update_row(&self, id: &str, fields: &HistoryRow) -> Result<(), String>;
+
+    fn set_metadata(&self, item_id: &str, key: &str, value: &str) -> Result<(), String>;
+
+    fn delete_metadata(&self, item_id: &str, key: &str) -> Result<(), String>;
+
+    fn get_metadata(&self, item_id: &str) -> Result<BTreeMap<String, String>, String>;
+
+    /// Ids of items with a metadata value containing `query`.
+    fn search_metadata(&self, query: &str) -> Result<Vec<String>, String>;
+
+    /// Distinct values stored under a metadata key, sorted by the locale.
+    fn metadata_values(&self, key: &str) -> Result<Vec<String>, String>;
+}
+
+static STORE: Mutex<Option<Arc<dyn HistoryStore>>> = Mutex::new(None);
+
+/// Replace the history backend.
+pub fn use_store(store: Arc<dyn HistoryStore>) {
+    let _ = STORE.lock().unwrap().insert(store);
+}
+
+/// The active history backend, SQLite unless another one was set.
+pub fn store() -> Arc<dyn HistoryStore> {
+    STORE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| Arc::new(SqliteStore))
+        .clone()
+}
+
+/// Store values the way the webview serializes them: booleans as integers
+/// and file lists as JSON text.
+fn normalize(value: &Value) -> Value {
+    match value {
+        Value::Bool(value) => Value::from(*value as i64),
+        Value::Array(_) | Value::Object(_) => Value::String(value.to_string()),
+        value => value.clone(),
+    }
+}
+
+/// Keep only known columns, normalized.
+pub(super) fn known_columns(row: &HistoryRow) -> Vec<(&'static str, Value)> {
+    HISTORY_COLUMNS
+        .iter()
+        .filter_map(|&column| row.get(column).map(|value| (column, normalize(value))))
+        .collect()
+}
+
+pub(super) fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(value) => SqlValue::Integer(*value as i64),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => SqlValue::Integer(integer),
+            None => SqlValue::Real(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(text) => SqlValue::Text(text.clone()),
+        value => SqlValue::Text(value.to_string()),
+    }
+}
+
+fn from_sql(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+        ValueRef::Integer(integer) => Value::from(integer),
+        ValueRef::Real(real) => Value::from(real),
+        ValueRef::Text(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+    }
+}
+
+/// All columns of the `history` table, quoted for a SELECT.
+pub(super) fn history_column_list() -> String {
+    HISTORY_COLUMNS
+        .iter()
+        .map(|column| format!("\"{}\"", column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A row selected with [`history_column_list`].
+pub(super) fn history_row(row: &Row) -> rusqlite::Result<HistoryRow> {
+    let mut values = HistoryRow::new();
+    for (index, column) in HISTORY_COLUMNS.iter().enumerate() {
+        values.insert(column.to_string(), from_sql(row.get_ref(index)?));
+    }
+
+    Ok(values)
+}
+
+/// Group the webview files an item under, derived from its type.
+fn group_of(kind: &str) -> &'static str {
+    match kind {
+        "image" => "image",
+        "files" => "files",
+        _ => "text",
+    }
+}
+
+/// The history database, only the backend opens it.
+pub struct SqliteStore;
+
+const ITEM_COLUMNS: &str = "id, type, value, search, subtype, createTime";
+
+fn item_from_row(row: &Row) -> rusqlite::Result<HistoryItem> {
+    Ok(HistoryItem {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        value: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+        search: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+        subtype: row.get(4)?,
+        create_time: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+    })
+}
+
+impl HistoryStore for SqliteStore {
+    fn get_item(&self, id: &str) -> Result<Option<HistoryItem>, String> {
+        with_connection(|connection| {
+            connection
+                .query_row(
+                    &format!("SELECT {} FROM history WHERE id = ?1", ITEM_COLUMNS),
+                    params![id],
+                    item_from_row,
+                )
+                .optional()
+        })
+    }
+
+    fn list_items(&self, limit: usize) -> Result<Vec<HistoryItem>, String> {
+        with_connection(|connection| {
+            let mut statement = connection.prepare(&format!(
+                "SELECT {} FROM history ORDER BY seq DESC LIMIT ?1",
+                ITEM_COLUMNS
+            ))?;
+
+            let rows = statement.query_map(params![limit as i64], item_from_row)?;
+
+            rows.collect()
+        })
+    }
+
+    fn list_items_on(&self, date: &str) -> Result<Vec<HistoryItem>, String> {
+        with_connection(|connection| {
+            let mut statement = connection.prepare(&format!(
+                "SELECT {} FROM history
+                WHERE substr(createTime, 1, 10) = ?1
+                ORDER BY seq ASC",
+                ITEM_COLUMNS
+            ))?;
+
+            let rows = statement.query_map(params![date], item_from_row)?;
+
+            rows.collect()
+        })
+    }
+
+    fn list_favorites(&self) -> Result<Vec<HistoryItem>, String> {
+        with_connection(|connection| {
+            let mut statement = connection.prepare(&format!(
+                "SELECT {} FROM history WHERE favorite = 1 ORDER BY seq DESC",
+                ITEM_COLUMNS
+            ))?;
+
+            let rows = statement.query_map([], item_from_row)?;
+
+            rows.collect()
+        })
+    }
+
+    fn insert_item(&self, item: &HistoryItem) -> Result<(), String> {
+        with_connection(|connection| {
+            connection.execute(
+                "INSERT INTO history (id, type, \"group\", value, search, count, favorite, createTime, subtype)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7, ?8)",
+                params![
+                    item.id,
+                    item.kind,
+                    group_of(&item.kind),
+                    item.value,
+                    item.search,
+                    item.plain_text().chars().count() as i64,
+                    item.create_time,
+                    item.subtype,
+                ],
+            )?;
+
+            Ok(())
+        })
+    }
+
+    fn delete_item(&self, id: &str) -> Result<bool, String> {
+        with_connection(|connection| {
+            let deleted = connection.execute("DELETE FROM history WHERE id = ?1", params![id])?;
+            connection.execute(
+                "DELETE FROM history_metadata WHERE item_id = ?1",
+                params![id],
+            )?;
+            delete_formats(connection, id)?;
+
+            Ok(deleted > 0)
+        })
+    }
+
+    fn get_row(&self, id: &str) -> Result<Option<HistoryRow>, String> {
+        with_connection(|connection| {
+            connection
+                .query_row(
+                    &format!(
+                        "SELECT {} FROM history WHERE id = ?1",
+                        history_column_list()
+                    ),
+                    params![id],
+                    history_row,
+                )
+                .optional()
+        })
+    }
+
+    fn put_row(&self, row: &HistoryRow) -> Result<(), String> {
+        let fields = known_columns(row);
+        let columns = fields
+            .iter()
+            .map(|(column, _)| format!("\"{}\"", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let placeholders = vec!["?"; fields.len()].join(", ");
+
+        with_connection(|connection| {
+            connection.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO history ({}) VALUES ({})",
+                    columns, placeholders
+                ),
+                params_from_iter(fields.iter().map(|(_, value)| to_sql(value))),
+            )?;
+
+            Ok(())
+        })
+    }
+
+    fn update_row(&self, id: &str, fields: &HistoryRow) -> Result<(), String> {
+        let fields: Vec<_> = known_columns(fields)
+            .into_iter()
+            .filter(|(column, _)| *column != "id")
+            .collect();
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        let assignments = fields
+            .iter()
+            .map(|(column, _)| format!("\"{}\" = ?", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut values: Vec<SqlValue> = fields.iter().map(|(_, value)| to_sql(value)).collect();
+        values.push(SqlValue::Text(id.to_string()));
+
+        with_connection(|connection| {
+            connection.execute(
+                &format!("UPDATE history SET {} WHERE id = ?", assignments),
+                params_from_iter(values),
+            )?;
+
+            Ok(())
+        })
+    }
+
+    fn set_metadata(&self, item_id: &str, key: &str, value: &str) -> Result<(), String> {
+        with_connection(|connection| {
+            connection.execute(
+                "INSERT INTO history_metadata (item_id, key, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (item_id, key) DO UPDATE SET value = excluded.value",
+                params![item_id, key, value],
+            )?;
+
+            Ok(())
+        })
+    }
+
+    fn delete_metadata(&self, item_id: &str, key: &str) -> Result<(), String> {
+        with_connection(|connection| {
+            connection.execute(
+                "DELETE FROM history_metadata WHERE item_id = ?1 AND key = ?2",
+                params![item_id, key],
+            )?;
+
+            Ok(())
+        })
+    }
+
+    fn get_metadata(&self, item_id: &str) -> Result<BTreeMap<String, String>, String> {
+        with_connection(|connection| {
+            let mut statement =
+                connection.prepare("SELECT key, value FROM history_metadata WHERE item_id = ?1")?;
+
+            let rows =
+                statement.query_map(params![item_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+            rows.collect()
+        })
+    }
+
+    fn search_metadata(&self, query: &str) -> Result<Vec<String>, String> {
+        let pattern = format!("%{}%", fold_text(query.trim()));
+
+        with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT DISTINCT item_id FROM history_metadata WHERE fold(value) LIKE ?1",
+            )?;
+
+            let rows = statement.query_map(params![pattern], |row| row.get(0))?;
+
+            rows.collect()
+        })
+    }
+
+    fn metadata_values(&self, key: &str) -> Result<Vec<String>, String> {
+        with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT DISTINCT value FROM history_metadata WHERE key = ?1 ORDER BY value COLLATE locale",
+            )?;
+
+            let rows = statement.query_map(params![key], |row| row.get(0))?;
+
+            rows.collect()
+        })
+    }
+}
+
+/// Keeps everything in memory, for tests and tools that run without a
+/// database file.
+#[derive(Default)]
+pub struct MemoryStore {
+    rows: Mutex<BTreeMap<String, HistoryRow>>,
+    metadata: Mutex<BTreeMap<String, BTreeMap<String, String>>>,
+}
+
+fn item_from_history_row(row: &HistoryRow) -> Option<HistoryItem> {
+    let row: serde_json::Map<String, Value> = row
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(column, value)| (column.clone(), value.clone()))
+        .collect();
+
+    serde_json::from_value(Value::Object(row)).ok()
+}
+
+impl HistoryStore for MemoryStore {
+    fn get_item(&self, id: &str) -> Result<Option<HistoryItem>, String> {
+        Ok(self
+            .rows
+            .lock()
+            .unwrap()
+            .get(id)
+            .and_then(item_from_history_row))
+    }
+
+    fn list_items(&self, limit: usize) -> Result<Vec<HistoryItem>, String> {
+        let mut items: Vec<HistoryItem> = self
+            .rows
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(item_from_history_row)
+            .collect();
+        items.sort_by(|a, b| b.create_time.cmp(&a.create_time));
+        items.truncate(limit);
+
+        Ok(items)
+    }
+
+    fn list_items_on(&self, date: &str) -> Result<Vec<HistoryItem>, String> {
+        let mut items: Vec<HistoryItem> = self
+            .rows
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(item_from_history_row)
+            .filter(|item| item.create_date() == date)
+            .collect();
+        items.sort_by(|a, b| a.create_time.cmp(&b.create_time));
+
+        Ok(items)
+    }
+
+    fn list_favorites(&self) -> Result<Vec<HistoryItem>, String> {
+        let mut items: Vec<HistoryItem> = self
+            .rows
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|row| row.get("favorite") == Some(&Value::from(1)))
+            .filter_map(item_from_history_row)
+            .collect();
+        items.sort_by(|a, b| b.create_time.cmp(&a.create_time));
+
+        Ok(items)
+    }
+
+    fn insert_item(&self, item: &HistoryItem) -> Result<(), String> {
+        if self.rows.lock().unwrap().contains_key(&item.id) {
+            return Err(format!("History item {} already exists", item.id));
+        }
+
+        let mut row: HistoryRow = match serde_json::to_value(item).map_err(|e| e.to_string())? {
+            Value::Object(row) => row.into_iter().collect(),
+            _ => HistoryRow::new(),
+        };
+        row.insert("group".to_string(), Value::from(group_of(&item.kind)));
+
+        self.put_row(&row)
+    }
+
+    fn delete_item(&self, id: &str) -> Result<bool, String> {
+        self.metadata.lock().unwrap().remove(id);
+
+        Ok(self.rows.lock().unwrap().remove(id).is_some())
+    }
+
+    fn get_row(&self, id: &str) -> Result<Option<HistoryRow>, String> {
+        Ok(self.rows.lock().unwrap().get(id).cloned())
+    }
+
+    fn put_row(&self, row: &HistoryRow) -> Result<(), String> {
+        let row: HistoryRow = known_columns(row)
+            .into_iter()
+            .map(|(column, value)| (column.to_string(), value))
+            .collect();
+
+        let id = row
+            .get("id")
+            .and_then(Value::as_str)
+            .ok_or("History row without an id")?
+            .to_string();
+
+        self.rows.lock().unwrap().insert(id, row);
+
+        Ok(())
+    }
+
+    fn update_row(&self, id: &str, fields: &HistoryRow) -> Result<(), String> {
+        let mut rows = self.rows.lock().unwrap();
+        let Some(row) = rows.get_mut(id) else {
+            return Ok(());
+        };
+
+        for (column, value) in known_columns(fields) {
+            if column != "id" {
+                row.insert(column.to_string(), value);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_metadata(&self, item_id: &str, key: &str, value: &str) -> Result<(), String> {
+        self.metadata
+            .lock()
+            .unwrap()
+            .entry(item_id.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+
+        Ok(())
+    }
+
+    fn delete_metadata(&self, item_id: &str, key: &str) -> Result<(), String> {
+        if let Some(values) = self.metadata.lock().unwrap().get_mut(item_id) {
+            values.remove(key);
+        }
+
+        Ok(())
+    }
+
+    fn get_metadata(&self, item_id: &str) -> Result<BTreeMap<String, String>, String> {
+        Ok(self
+            .metadata
+            .lock()
+            .unwrap()
+            .get(item_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn search_metadata(&self, query: &str) -> Result<Vec<String>, String> {
+        let query = fold_text(query.trim());
+
+        Ok(self
+            .metadata
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, values)| {
+                values
+                    .values()
+                    .any(|value| fold_text(value).contains(&query))
+            })
+            .map(|(item_id, _)| item_id.clone())
+            .collect())
+    }
+
+    fn metadata_values(&self, key: &str) -> Result<Vec<String>, String> {
+        let values: BTreeSet<String> = self
+            .metadata
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|values| values.get(key).cloned())
+            .collect();
+
+        let mut values: Vec<String> = values.into_iter().collect();
+        values.sort_by(|a, b| compare(a, b));
+
+        Ok(values)
+    }
+}
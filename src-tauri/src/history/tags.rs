@@ -0,0 +1,227 @@
+use super::collation::compare;
+use super::{commit_operation, notify_changed, store, with_connection, Operation, TAGS};
+use crate::clipboard_request::clean_tags;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::{command, AppHandle, Runtime};
+
+/// Selects the rows of `history` carrying the tag bound to its `?`. Tags
+/// are kept comma separated under the [`TAGS`] metadata key.
+pub(super) const TAG_CONDITION: &str = "EXISTS (
+    SELECT 1 FROM history_metadata
+    WHERE item_id = history.id AND key = 'tags'
+    AND instr(',' || value || ',', ',' || ? || ',') > 0
+)";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagSummary {
+    pub name: String,
+    /// Items carrying the tag, `0` for a tag created but not used yet.
+    pub count: usize,
+}
+
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    // Tags created ahead of use, the ones on items live in the metadata
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS history_tags (
+            name TEXT PRIMARY KEY,
+            createTime TEXT NOT NULL
+        );",
+    )
+}
+
+/// [`clean_tags`] without commas, they separate the stored tags.
+fn clean(tags: Vec<String>) -> Vec<String> {
+    clean_tags(tags.into_iter().map(|tag| tag.replace(',', " ")).collect())
+}
+
+fn clean_tag(tag: String) -> Result<String, String> {
+    clean(vec![tag])
+        .pop()
+        .ok_or_else(|| "The tag name cannot be empty".to_string())
+}
+
+fn register(tags: &[String]) -> Result<(), String> {
+    with_connection(|connection| {
+        for tag in tags {
+            connection.execute(
+                "INSERT OR IGNORE INTO history_tags (name, createTime)
+                VALUES (?1, datetime('now', 'localtime'))",
+                params![tag],
+            )?;
+        }
+
+        Ok(())
+    })
+}
+
+pub fn item_tags(id: &str) -> Result<Vec<String>, String> {
+    let metadata = store().get_metadata(id)?;
+
+    Ok(metadata
+        .get(TAGS)
+        .map(|tags| {
+            tags.split(',')
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Replace the tags of an item through the operation log, so the change
+/// can be undone and syncs. Returns the tags as stored.
+pub fn set_tags(id: &str, tags: Vec<String>) -> Result<Vec<String>, String> {
+    let tags = clean(tags);
+
+    let before = store().get_metadata(id)?.remove(TAGS);
+    let after = (!tags.is_empty()).then(|| tags.join(","));
+
+    if before != after {
+        commit_operation(Operation::Tag {
+            id: id.to_string(),
+            key: TAGS.to_string(),
+            before,
+            after,
+        })?;
+    }
+
+    register(&tags)?;
+
+    Ok(tags)
+}
+
+/// Items carrying a tag.
+fn tagged_ids(tag: &str) -> Result<Vec<String>, String> {
+    with_connection(|connection| {
+        let mut statement =
+            connection.prepare(&format!("SELECT id FROM history WHERE {}", TAG_CONDITION))?;
+        let ids = statement.query_map(params![tag], |row| row.get(0))?;
+
+        ids.collect()
+    })
+}
+
+/// Every created or used tag with the number of items carrying it, by
+/// name in the order of the configured locale.
+pub fn tag_summaries() -> Result<Vec<TagSummary>, String> {
+    let (created, used) = with_connection(|connection| {
+        let created = {
+            let mut statement = connection.prepare("SELECT name FROM history_tags")?;
+            let names = statement.query_map([], |row| row.get::<_, String>(0))?;
+            names.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        let used = {
+            let mut statement =
+                connection.prepare("SELECT value FROM history_metadata WHERE key = ?1")?;
+            let values = statement.query_map(params![TAGS], |row| row.get::<_, String>(0))?;
+            values.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        Ok((created, used))
+    })?;
+
+    let mut counts: BTreeMap<String, usize> = created.into_iter().map(|name| (name, 0)).collect();
+
+    for tag in used.iter().flat_map(|tags| tags.split(',')) {
+        if !tag.is_empty() {
+            *counts.entry(tag.to_string()).or_default() += 1;
+        }
+    }
+
+    let mut summaries: Vec<TagSummary> = counts
+        .into_iter()
+        .map(|(name, count)| TagSummary { name, count })
+        .collect();
+    summaries.sort_by(|a, b| compare(&a.name, &b.name));
+
+    Ok(summaries)
+}
+
+// 新建标签，返回整理后的标签名
+#[command]
+pub async fn create_tag(name: String) -> Result<String, String> {
+    let tag = clean_tag(name)?;
+
+    register(std::slice::from_ref(&tag))?;
+
+    Ok(tag)
+}
+
+// 删除标签，同时从所有条目上移除，返回受影响的条目数
+#[command]
+pub async fn delete_tag<R: Runtime>(
+    app_handle: AppHandle<R>,
+    name: String,
+) -> Result<usize, String> {
+    let ids = tagged_ids(&name)?;
+
+    for id in &ids {
+        let tags = item_tags(id)?
+            .into_iter()
+            .filter(|tag| *tag != name)
+            .collect();
+
+        set_tags(id, tags)?;
+    }
+
+    with_connection(|connection| {
+        connection.execute("DELETE FROM history_tags WHERE name = ?1", params![name])
+    })?;
+
+    if !ids.is_empty() {
+        notify_changed(&app_handle);
+    }
+
+    Ok(ids.len())
+}
+
+// 获取所有标签及使用它们的条目数
+#[command]
+pub async fn list_tags() -> Result<Vec<TagSummary>, String> {
+    tag_summaries()
+}
+
+// 设置条目的标签，返回整理后的标签
+#[command]
+pub async fn set_item_tags(id: String, tags: Vec<String>) -> Result<Vec<String>, String> {
+    set_tags(&id, tags)
+}
+
+// 批量给条目添加或移除一个标签，返回有变化的条目数
+#[command]
+pub async fn tag_history_items<R: Runtime>(
+    app_handle: AppHandle<R>,
+    ids: Vec<String>,
+    tag: String,
+    assigned: bool,
+) -> Result<usize, String> {
+    let tag = clean_tag(tag)?;
+    let mut changed = 0;
+
+    for id in &ids {
+        let mut tags = item_tags(id)?;
+
+        if assigned == tags.contains(&tag) {
+            continue;
+        }
+
+        if assigned {
+            tags.push(tag.clone());
+        } else {
+            tags.retain(|current| *current != tag);
+        }
+
+        set_tags(id, tags)?;
+        changed += 1;
+    }
+
+    if changed > 0 {
+        notify_changed(&app_handle);
+    }
+
+    Ok(changed)
+}
@@ -0,0 +1,151 @@
+use super::{with_connection, HistoryRow};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Current time as UTC milliseconds since the Unix epoch, as SQL.
+pub const NOW_MILLIS: &str = "CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)";
+
+/// A `createTime` within a minute of the local clock was just written by a
+/// capture, its UTC time is taken from the clock. Older ones come from an
+/// import, a sync or an undo and are converted, which is off by an hour for
+/// times inside the hour a DST change repeats.
+const CREATED_AT: &str = "CASE
+    WHEN abs(julianday(NEW.createTime) - julianday('now', 'localtime')) < 1.0 / 1440
+    THEN CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)
+    ELSE CAST(strftime('%s', NEW.createTime, 'utc') AS INTEGER) * 1000
+END";
+
+/// How `createTime` is returned by queries.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeFormat {
+    /// As written, in the local time of the device at capture.
+    #[default]
+    Stored,
+    /// Rendered from the UTC time in the current time zone, right after the
+    /// time zone or DST changed.
+    Local,
+    /// ISO 8601 in UTC.
+    Utc,
+}
+
+impl TimeFormat {
+    /// SQL rendering `createdAt` in this format, `None` to keep the column.
+    fn expression(&self) -> Option<&'static str> {
+        match self {
+            TimeFormat::Stored => None,
+            TimeFormat::Local => Some("datetime(createdAt / 1000, 'unixepoch', 'localtime')"),
+            TimeFormat::Utc => {
+                Some("strftime('%Y-%m-%dT%H:%M:%fZ', createdAt / 1000.0, 'unixepoch')")
+            }
+        }
+    }
+}
+
+/// Render the `createTime` of queried rows in `format`, adding their
+/// `createdAt` next to it. Rows keep what was stored when no UTC time is
+/// known.
+pub(super) fn format_times(rows: &mut [HistoryRow], format: TimeFormat) -> Result<(), String> {
+    let Some(expression) = format.expression() else {
+        return Ok(());
+    };
+
+    with_connection(|connection| {
+        let mut statement = connection.prepare(&format!(
+            "SELECT createdAt, {} FROM history WHERE id = ?1 AND createdAt IS NOT NULL",
+            expression
+        ))?;
+
+        for row in rows.iter_mut() {
+            let Some(id) = row.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+
+            let times = statement
+                .query_row(params![id], |times| {
+                    Ok((times.get::<_, i64>(0)?, times.get::<_, String>(1)?))
+                })
+                .optional()?;
+
+            if let Some((created_at, create_time)) = times {
+                row.insert("createdAt".to_string(), Value::from(created_at));
+                row.insert("createTime".to_string(), Value::from(create_time));
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// SQL condition for rows created more than `seconds` ago. Ages are measured
+/// in UTC, a DST change doesn't shift them by an hour.
+pub fn older_than(seconds: u64) -> String {
+    format!("createdAt < {} - {}", NOW_MILLIS, seconds * 1000)
+}
+
+fn has_column(connection: &Connection, column: &str) -> rusqlite::Result<bool> {
+    let mut statement = connection.prepare("SELECT name FROM pragma_table_info('history')")?;
+    let names = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// `createdAt` holds the UTC time in milliseconds and `seq` the order items
+/// were added or copied again in, which no clock change can reorder. The
+/// webview keeps writing `createTime` only, triggers fill in the rest.
+pub(super) fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    if !has_column(connection, "createdAt")? {
+        connection.execute_batch(
+            "ALTER TABLE history ADD COLUMN createdAt INTEGER;
+            UPDATE history
+            SET createdAt = CAST(strftime('%s', createTime, 'utc') AS INTEGER) * 1000;",
+        )?;
+    }
+
+    if !has_column(connection, "seq")? {
+        connection.execute_batch(
+            "ALTER TABLE history ADD COLUMN seq INTEGER;
+            UPDATE history SET seq = ordered.seq
+            FROM (
+                SELECT id, ROW_NUMBER() OVER (ORDER BY createTime, rowid) AS seq FROM history
+            ) AS ordered
+            WHERE history.id = ordered.id;",
+        )?;
+    }
+
+    connection.execute_batch(&format!(
+        "CREATE INDEX IF NOT EXISTS history_seq ON history (seq);
+        CREATE INDEX IF NOT EXISTS history_created_at ON history (createdAt);
+        CREATE TRIGGER IF NOT EXISTS history_stamp_insert
+        AFTER INSERT ON history WHEN NEW.seq IS NULL
+        BEGIN
+            UPDATE history SET
+                seq = (SELECT coalesce(MAX(seq), 0) + 1 FROM history),
+                createdAt = {created_at}
+            WHERE rowid = NEW.rowid;
+        END;
+        CREATE TRIGGER IF NOT EXISTS history_stamp_update
+        AFTER UPDATE OF createTime ON history
+        WHEN NEW.createTime IS NOT OLD.createTime
+        BEGIN
+            -- Copied again moves the item to the top, an undo restoring an
+            -- older time keeps its place
+            UPDATE history SET
+                seq = CASE
+                    WHEN abs(julianday(NEW.createTime) - julianday('now', 'localtime')) < 1.0 / 1440
+                    THEN (SELECT MAX(seq) + 1 FROM history)
+                    ELSE seq
+                END,
+                createdAt = {created_at}
+            WHERE rowid = NEW.rowid;
+        END;",
+        created_at = CREATED_AT
+    ))
+}
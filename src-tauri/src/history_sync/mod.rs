@@ -0,0 +1,455 @@
+use crate::clipboard_image::{get_images_dir, resolve_image_path};
+use crate::history::{
+    device_id, merge_operations, notify_changed, operations_since, HistoryRow, Operation,
+    OperationEntry,
+};
+use crate::vault;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Manager, Runtime};
+use webdav::WebDav;
+
+mod webdav;
+
+/// Each device appends batches of its operations under `ops/<device id>/`,
+/// named after the last local sequence number they hold. Images of inserted
+/// items are shared under `images/`, named like the local file.
+const OPS_DIR: &str = "ops";
+const IMAGES_DIR: &str = "images";
+
+/// Salt of the vault's sync key, published by the first device that syncs
+/// with the vault enabled.
+const VAULT_SALT: &str = "vault-salt";
+
+/// Batch names are zero-padded so they sort by sequence number.
+const BATCH_DIGITS: usize = 12;
+
+const DEFAULT_INTERVAL_MINUTES: u64 = 15;
+
+/// How often the scheduler checks whether a sync is due.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavConfig {
+    /// Folder on the server, e.g.
+    /// `https://cloud.example.com/remote.php/dav/files/me/EcoPaste`.
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Minutes between scheduled syncs, defaults to 15.
+    pub interval_minutes: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Local operations uploaded.
+    pub pushed: usize,
+    /// Operations of other devices downloaded.
+    pub pulled: usize,
+    /// Downloaded operations that changed the history, the others lost to a
+    /// newer change of the same item.
+    pub applied: usize,
+    pub uploaded_images: usize,
+    pub downloaded_images: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub enabled: bool,
+    /// Milliseconds since the epoch of the last sync attempt.
+    pub synced_at: Option<u64>,
+    pub error: Option<String>,
+    pub last_report: Option<SyncReport>,
+}
+
+/// What was exchanged with the server so far, kept across restarts.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Cursor {
+    /// Server the cursor belongs to, a new one starts over.
+    url: String,
+    /// Last local sequence number uploaded.
+    pushed_seq: i64,
+    /// Last sequence number downloaded per device.
+    pulled: BTreeMap<String, i64>,
+}
+
+struct SyncState {
+    config: Option<WebDavConfig>,
+    synced_at: Option<u64>,
+    error: Option<String>,
+    last_report: Option<SyncReport>,
+}
+
+static STATE: Mutex<SyncState> = Mutex::new(SyncState {
+    config: None,
+    synced_at: None,
+    error: None,
+    last_report: None,
+});
+
+/// The scheduler and the command share the cursor, syncs never overlap.
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn cursor_path<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("webdav-sync.json")
+}
+
+fn load_cursor<R: Runtime>(app_handle: &AppHandle<R>, url: &str) -> Cursor {
+    let cursor = std::fs::read(cursor_path(app_handle))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Cursor>(&bytes).ok())
+        .filter(|cursor| cursor.url == url);
+
+    cursor.unwrap_or_else(|| Cursor {
+        url: url.to_string(),
+        ..Default::default()
+    })
+}
+
+fn save_cursor<R: Runtime>(app_handle: &AppHandle<R>, cursor: &Cursor) -> Result<(), String> {
+    let bytes = serde_json::to_vec(cursor).map_err(|e| e.to_string())?;
+
+    std::fs::write(cursor_path(app_handle), bytes).map_err(|e| e.to_string())
+}
+
+/// Device ids and batch names from the server become paths, anything
+/// unexpected is skipped.
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.')
+        && !name.starts_with('.')
+}
+
+fn is_image(row: &HistoryRow) -> bool {
+    row.get("type").and_then(Value::as_str) == Some("image")
+}
+
+pub(crate) fn image_name(row: &HistoryRow) -> Option<String> {
+    let value = row.get("value").and_then(Value::as_str)?;
+    let name = Path::new(value).file_name()?.to_string_lossy().to_string();
+
+    is_safe_name(&name).then_some(name)
+}
+
+/// With the vault enabled nothing leaves the device in plain text, the
+/// server only stores what the vault password decrypts.
+fn seal_upload(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    if vault::is_enabled() {
+        vault::seal_sync(&bytes)
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Publish this vault's sync salt, or take over the one another device
+/// published. Each vault has its own salt, a dictionary attack on synced
+/// data can't cover every user at once.
+fn share_sync_salt(webdav: &WebDav) -> Result<(), String> {
+    let Some(local) = vault::sync_salt()? else {
+        return Ok(());
+    };
+
+    match webdav.get(VAULT_SALT)? {
+        Some(remote) if vault::adopt_sync_salt(&remote)? => Err(
+            "Synced data uses the vault of another device, unlock the vault again to read it"
+                .to_string(),
+        ),
+        Some(_) => Ok(()),
+        None => webdav.put(VAULT_SALT, local),
+    }
+}
+
+/// Upload the local operations logged since the last push with the images
+/// they insert.
+fn push<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    webdav: &WebDav,
+    device: &str,
+    cursor: &mut Cursor,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let entries = operations_since(cursor.pushed_seq)?;
+
+    let Some(last_seq) = entries.last().map(|entry| entry.seq) else {
+        return Ok(());
+    };
+
+    // Operations merged from other devices are logged locally too
+    let own: Vec<OperationEntry> = entries
+        .into_iter()
+        .filter(|entry| entry.device_id == device)
+        .collect();
+
+    for entry in &own {
+        let Operation::Insert { row, .. } = &entry.operation else {
+            continue;
+        };
+        let Some(name) = image_name(row).filter(|_| is_image(row)) else {
+            continue;
+        };
+        let value = row.get("value").and_then(Value::as_str).unwrap_or_default();
+
+        if let Ok(bytes) = vault::read_file(&resolve_image_path(app_handle, value)) {
+            webdav.put(&format!("{}/{}", IMAGES_DIR, name), seal_upload(bytes)?)?;
+            report.uploaded_images += 1;
+        }
+    }
+
+    if !own.is_empty() {
+        let bytes = seal_upload(serde_json::to_vec(&own).map_err(|e| e.to_string())?)?;
+        let batch = format!(
+            "{}/{}/{:0width$}.json",
+            OPS_DIR,
+            device,
+            last_seq,
+            width = BATCH_DIGITS
+        );
+
+        webdav.put(&batch, bytes)?;
+        report.pushed += own.len();
+    }
+
+    cursor.pushed_seq = last_seq;
+
+    Ok(())
+}
+
+/// Point an image row at the local images folder, downloading the file
+/// when it is not there yet.
+fn localize_image<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    webdav: &WebDav,
+    row: &mut HistoryRow,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let Some(name) = image_name(row) else {
+        return Ok(());
+    };
+
+    let path = get_images_dir(app_handle).join(&name);
+
+    if !path.exists() {
+        let Some(bytes) = webdav.get(&format!("{}/{}", IMAGES_DIR, name))? else {
+            return Ok(());
+        };
+
+        std::fs::create_dir_all(get_images_dir(app_handle)).map_err(|e| e.to_string())?;
+        vault::write_file(&path, &vault::open_sync(bytes)?)?;
+        report.downloaded_images += 1;
+    }
+
+    row.insert(
+        "value".to_string(),
+        Value::from(path.to_string_lossy().to_string()),
+    );
+
+    Ok(())
+}
+
+/// Download the batches other devices uploaded since the last pull.
+fn pull<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    webdav: &WebDav,
+    device: &str,
+    cursor: &mut Cursor,
+    report: &mut SyncReport,
+) -> Result<Vec<OperationEntry>, String> {
+    let mut entries = Vec::new();
+
+    for other in webdav.list(OPS_DIR)? {
+        if other == device || !is_safe_name(&other) {
+            continue;
+        }
+
+        let pulled = cursor.pulled.get(&other).copied().unwrap_or_default();
+        let mut batches: Vec<(i64, String)> = webdav
+            .list(&format!("{}/{}", OPS_DIR, other))?
+            .into_iter()
+            .filter_map(|name| {
+                let seq = name.strip_suffix(".json")?.parse().ok()?;
+
+                (seq > pulled).then_some((seq, name))
+            })
+            .collect();
+
+        batches.sort();
+
+        for (seq, name) in batches {
+            let Some(bytes) = webdav.get(&format!("{}/{}/{}", OPS_DIR, other, name))? else {
+                continue;
+            };
+
+            let mut batch: Vec<OperationEntry> =
+                serde_json::from_slice(&vault::open_sync(bytes)?).map_err(|e| e.to_string())?;
+
+            for entry in &mut batch {
+                if let Operation::Insert { row, .. } = &mut entry.operation {
+                    if is_image(row) {
+                        localize_image(app_handle, webdav, row, report)?;
+                    }
+                }
+            }
+
+            report.pulled += batch.len();
+            entries.extend(batch);
+            cursor.pulled.insert(other.clone(), seq);
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Push local operations, then pull and merge the other devices'. Merging
+/// keeps the newest change of every item, so devices converge whatever
+/// order they sync in.
+pub fn sync_history<R: Runtime>(app_handle: &AppHandle<R>) -> Result<SyncReport, String> {
+    let _guard = SYNC_LOCK.lock().unwrap();
+
+    let config = STATE
+        .lock()
+        .unwrap()
+        .config
+        .clone()
+        .ok_or("WebDAV sync is not configured")?;
+
+    let webdav = WebDav::new(&config.url, &config.username, &config.password)?;
+    let device = device_id()?;
+    let mut cursor = load_cursor(app_handle, &config.url);
+    let mut report = SyncReport::default();
+
+    webdav.create_dir(OPS_DIR)?;
+    webdav.create_dir(&format!("{}/{}", OPS_DIR, device))?;
+    webdav.create_dir(IMAGES_DIR)?;
+
+    if vault::is_enabled() {
+        share_sync_salt(&webdav)?;
+    }
+
+    push(app_handle, &webdav, &device, &mut cursor, &mut report)?;
+    save_cursor(app_handle, &cursor)?;
+
+    let entries = pull(app_handle, &webdav, &device, &mut cursor, &mut report)?;
+    report.applied = merge_operations(entries)?;
+    save_cursor(app_handle, &cursor)?;
+
+    if report.applied > 0 {
+        notify_changed(app_handle);
+    }
+
+    Ok(report)
+}
+
+fn run_sync<R: Runtime>(app_handle: &AppHandle<R>) -> Result<SyncReport, String> {
+    let result = sync_history(app_handle);
+
+    let mut state = STATE.lock().unwrap();
+    state.synced_at = Some(now_millis());
+
+    match &result {
+        Ok(report) => {
+            state.error = None;
+            state.last_report = Some(report.clone());
+        }
+        Err(error) => {
+            log::warn!("Failed to sync history over WebDAV: {}", error);
+
+            state.error = Some(error.clone());
+        }
+    }
+
+    result
+}
+
+fn is_due(state: &SyncState, now: u64) -> bool {
+    let Some(config) = &state.config else {
+        return false;
+    };
+
+    let minutes = config
+        .interval_minutes
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+        .max(1);
+
+    match state.synced_at {
+        Some(synced_at) => now.saturating_sub(synced_at) >= minutes * 60 * 1000,
+        None => true,
+    }
+}
+
+pub fn start_sync_scheduler<R: Runtime>(app_handle: AppHandle<R>) {
+    thread::spawn(move || loop {
+        if is_due(&STATE.lock().unwrap(), now_millis()) {
+            let _ = run_sync(&app_handle);
+        }
+
+        thread::sleep(SCHEDULE_CHECK_INTERVAL);
+    });
+}
+
+/// Sync in the background if it is configured, e.g. after a resume when the
+/// interval may have passed long ago and a sync may have been cut off.
+pub fn sync_soon<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STATE.lock().unwrap().config.is_none() {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    thread::spawn(move || run_sync(&app_handle));
+}
+
+// 设置历史记录的 WebDAV 同步，传入 null 关闭同步
+#[command]
+pub async fn set_webdav_sync<R: Runtime>(app_handle: AppHandle<R>, config: Option<WebDavConfig>) {
+    let enabled = config.is_some();
+
+    {
+        let mut state = STATE.lock().unwrap();
+        state.config = config;
+        state.synced_at = None;
+        state.error = None;
+    }
+
+    if enabled {
+        thread::spawn(move || run_sync(&app_handle));
+    }
+}
+
+// 立即通过 WebDAV 同步历史记录
+#[command]
+pub async fn sync_now<R: Runtime>(app_handle: AppHandle<R>) -> Result<SyncReport, String> {
+    tauri::async_runtime::spawn_blocking(move || run_sync(&app_handle))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 获取历史记录的 WebDAV 同步状态
+#[command]
+pub async fn get_webdav_sync_status() -> SyncStatus {
+    let state = STATE.lock().unwrap();
+
+    SyncStatus {
+        enabled: state.config.is_some(),
+        synced_at: state.synced_at,
+        error: state.error.clone(),
+        last_report: state.last_report.clone(),
+    }
+}
@@ -0,0 +1,138 @@
+use regex::Regex;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::{Method, StatusCode};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Hrefs of a PROPFIND response, whatever prefix the server gives the
+/// `DAV:` namespace.
+static HREF: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<(?:[A-Za-z0-9]+:)?href>([^<]*)</(?:[A-Za-z0-9]+:)?href>").unwrap()
+});
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<propfind xmlns="DAV:"><prop><resourcetype/></prop></propfind>"#;
+
+/// The handful of WebDAV requests the sync needs, against a folder on the
+/// server. Paths are relative to that folder.
+pub struct WebDav {
+    client: Client,
+    base: String,
+    username: String,
+    password: String,
+}
+
+fn check(response: Response) -> Result<Response, String> {
+    let status = response.status();
+
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            Err("The WebDAV server rejected the credentials".to_string())
+        }
+        status => Err(format!("WebDAV request failed with {}", status)),
+    }
+}
+
+impl WebDav {
+    pub fn new(url: &str, username: &str, password: &str) -> Result<Self, String> {
+        let client = Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            client,
+            base: url.trim_end_matches('/').to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}/{}", self.base, path))
+            .basic_auth(&self.username, Some(&self.password))
+    }
+
+    /// Create a folder, one that already exists is fine.
+    pub fn create_dir(&self, path: &str) -> Result<(), String> {
+        let method = Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+        let response = self
+            .request(method, &format!("{}/", path))
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        // 405 is what servers answer for an existing folder
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(());
+        }
+
+        check(response).map(|_| ())
+    }
+
+    /// Names of the entries of a folder, empty when it does not exist.
+    pub fn list(&self, path: &str) -> Result<Vec<String>, String> {
+        let method = Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+        let response = self
+            .request(method, &format!("{}/", path))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(PROPFIND_BODY)
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+
+        let body = check(response)?.text().map_err(|e| e.to_string())?;
+        let folder = path
+            .trim_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+
+        // The folder itself is listed along with its entries
+        Ok(HREF
+            .captures_iter(&body)
+            .filter_map(|captures| {
+                let href = captures[1].trim_end_matches('/');
+                let name = href.rsplit('/').next()?.to_string();
+
+                (!name.is_empty() && name != folder).then_some(name)
+            })
+            .collect())
+    }
+
+    /// Content of a file, `None` when it does not exist.
+    pub fn get(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        let response = self
+            .request(Method::GET, path)
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let bytes = check(response)?.bytes().map_err(|e| e.to_string())?;
+
+        Ok(Some(bytes.to_vec()))
+    }
+
+    pub fn put(&self, path: &str, bytes: Vec<u8>) -> Result<(), String> {
+        let response = self
+            .request(Method::PUT, path)
+            .body(bytes)
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        check(response).map(|_| ())
+    }
+}
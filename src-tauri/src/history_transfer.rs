@@ -0,0 +1,332 @@
+use crate::clipboard_image::{get_images_dir, resolve_image_path};
+use crate::history::{get_metadata, notify_changed, set_metadata, store, HistoryRow};
+use crate::item_access::require_access_all;
+use crate::vault;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Runtime};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bumped when the layout of an export changes incompatibly.
+const EXPORT_VERSION: u32 = 1;
+
+/// Entry of the zip bundle holding the items, images sit under
+/// [`IMAGES_DIR`] named after their file.
+const HISTORY_ENTRY: &str = "history.json";
+const IMAGES_DIR: &str = "images";
+
+/// Every zip file starts with a local file header.
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Json,
+    Zip,
+}
+
+impl ExportFormat {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "json" => Ok(ExportFormat::Json),
+            "zip" => Ok(ExportFormat::Zip),
+            format => Err(format!("Unsupported export format: {}", format)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedItem {
+    row: HistoryRow,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Export {
+    version: u32,
+    items: Vec<ExportedItem>,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Items whose id is already in the history, the local copy is kept.
+    pub skipped: usize,
+    /// Image items whose file was neither in the bundle nor on this machine.
+    pub missing_images: usize,
+}
+
+fn row_str<'a>(row: &'a HistoryRow, column: &str) -> Option<&'a str> {
+    row.get(column).and_then(Value::as_str)
+}
+
+fn is_image(row: &HistoryRow) -> bool {
+    row_str(row, "type") == Some("image")
+}
+
+/// File name an image is stored under in the bundle.
+fn image_name(value: &str) -> Option<String> {
+    Path::new(value)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Every row with its metadata, or only the favorites, newest first.
+fn collect_items(favorites_only: bool) -> Result<Vec<ExportedItem>, String> {
+    let store = store();
+    let mut items = Vec::new();
+
+    let listed = if favorites_only {
+        store.list_favorites()?
+    } else {
+        // SQLite treats the negative limit usize::MAX wraps to as no limit
+        store.list_items(usize::MAX)?
+    };
+
+    for item in listed {
+        let Some(row) = store.get_row(&item.id)? else {
+            continue;
+        };
+
+        items.push(ExportedItem {
+            metadata: get_metadata(&item.id)?,
+            row,
+        });
+    }
+
+    Ok(items)
+}
+
+fn write_json(path: &Path, export: &Export) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+
+    serde_json::to_writer_pretty(BufWriter::new(file), export).map_err(|e| e.to_string())
+}
+
+/// Zip bundle of the items and the image files they point to. Image values
+/// are reduced to the file name, the import points them at its own images
+/// directory.
+fn write_zip<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    path: &Path,
+    mut export: Export,
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // Images are already compressed, deflating them again only costs time
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    for item in export.items.iter_mut().filter(|item| is_image(&item.row)) {
+        let Some(value) = row_str(&item.row, "value").map(str::to_string) else {
+            continue;
+        };
+        let Some(name) = image_name(&value) else {
+            continue;
+        };
+
+        // Exports carry plain images, whether or not the vault is enabled
+        let Ok(bytes) = vault::read_file(&resolve_image_path(app_handle, &value)) else {
+            continue;
+        };
+
+        zip.start_file(format!("{}/{}", IMAGES_DIR, name), stored)
+            .map_err(|e| e.to_string())?;
+        zip.write_all(&bytes).map_err(|e| e.to_string())?;
+
+        item.row.insert("value".to_string(), Value::from(name));
+    }
+
+    zip.start_file(HISTORY_ENTRY, options)
+        .map_err(|e| e.to_string())?;
+    serde_json::to_writer(&mut zip, &export).map_err(|e| e.to_string())?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn is_zip(path: &Path) -> Result<bool, String> {
+    let mut magic = [0; 4];
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+
+    Ok(file.read_exact(&mut magic).is_ok() && magic == ZIP_MAGIC)
+}
+
+/// Copy the image of a bundled item into the images directory, a file
+/// already there under the same name is the same image and is kept.
+fn restore_image<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    zip: &mut ZipArchive<BufReader<File>>,
+    name: &str,
+) -> Result<Option<PathBuf>, String> {
+    let dest = get_images_dir(app_handle).join(name);
+
+    if dest.exists() {
+        return Ok(Some(dest));
+    }
+
+    let Ok(mut entry) = zip.by_name(&format!("{}/{}", IMAGES_DIR, name)) else {
+        return Ok(None);
+    };
+
+    fs::create_dir_all(get_images_dir(app_handle)).map_err(|e| e.to_string())?;
+
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    vault::write_file(&dest, &bytes)?;
+
+    Ok(Some(dest))
+}
+
+fn import_items<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    export: Export,
+    mut zip: Option<ZipArchive<BufReader<File>>>,
+) -> Result<ImportSummary, String> {
+    if export.version > EXPORT_VERSION {
+        return Err(format!(
+            "The export was made by a newer version of EcoPaste (format {})",
+            export.version
+        ));
+    }
+
+    let store = store();
+    let mut summary = ImportSummary::default();
+
+    for ExportedItem { mut row, metadata } in export.items {
+        let Some(id) = row_str(&row, "id").map(str::to_string) else {
+            continue;
+        };
+
+        if store.get_row(&id)?.is_some() {
+            summary.skipped += 1;
+            continue;
+        }
+
+        if is_image(&row) {
+            let value = row_str(&row, "value").unwrap_or_default().to_string();
+
+            // Bundled images are referenced by name, JSON exports keep the
+            // path of the machine they were made on
+            let path = match (&mut zip, image_name(&value)) {
+                (Some(zip), Some(name)) => restore_image(app_handle, zip, &name)?,
+                _ => Some(resolve_image_path(app_handle, &value)).filter(|path| path.exists()),
+            };
+
+            match path {
+                Some(path) => {
+                    row.insert(
+                        "value".to_string(),
+                        Value::from(path.to_string_lossy().to_string()),
+                    );
+                }
+                None => summary.missing_images += 1,
+            }
+        }
+
+        store.put_row(&row)?;
+
+        for (key, value) in metadata {
+            set_metadata(&id, &key, &value)?;
+        }
+
+        summary.imported += 1;
+    }
+
+    Ok(summary)
+}
+
+async fn export<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    path: String,
+    format: String,
+    favorites_only: bool,
+) -> Result<usize, String> {
+    let format = ExportFormat::parse(&format)?;
+    let path = PathBuf::from(path);
+
+    let items = collect_items(favorites_only)?;
+
+    // Exports carry plain content, sensitive items need an authentication
+    require_access_all(
+        app_handle,
+        items.iter().filter_map(|item| row_str(&item.row, "id")),
+    )
+    .await?;
+
+    let export = Export {
+        version: EXPORT_VERSION,
+        items,
+    };
+    let count = export.items.len();
+
+    match format {
+        ExportFormat::Json => write_json(&path, &export)?,
+        ExportFormat::Zip => write_zip(app_handle, &path, export)?,
+    }
+
+    Ok(count)
+}
+
+// 导出全部剪贴板历史，format 为 json（仅条目和元数据）或 zip（同时打包图片文件）
+#[command]
+pub async fn export_history<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+    format: String,
+) -> Result<usize, String> {
+    export(&app_handle, path, format, false).await
+}
+
+// 单独导出收藏的条目，格式与 export_history 相同，可用 import_history 导入
+#[command]
+pub async fn export_favorites<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+    format: String,
+) -> Result<usize, String> {
+    export(&app_handle, path, format, true).await
+}
+
+// 导入 export_history 导出的文件，自动识别 json 和 zip，已存在的条目保留本地版本
+#[command]
+pub async fn import_history<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+) -> Result<ImportSummary, String> {
+    let path = PathBuf::from(path);
+
+    let (export, zip) = if is_zip(&path)? {
+        let file = File::open(&path).map_err(|e| e.to_string())?;
+        let mut zip = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+
+        let export: Export = {
+            let entry = zip.by_name(HISTORY_ENTRY).map_err(|e| e.to_string())?;
+
+            serde_json::from_reader(entry).map_err(|e| e.to_string())?
+        };
+
+        (export, Some(zip))
+    } else {
+        let file = File::open(&path).map_err(|e| e.to_string())?;
+        let export: Export =
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+
+        (export, None)
+    };
+
+    let summary = import_items(&app_handle, export, zip)?;
+
+    if summary.imported > 0 {
+        notify_changed(&app_handle);
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,293 @@
+use crate::paste::{paste_slot, MAX_SLOT};
+use crate::paste_queue::paste_next;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Emitted to every window when a global shortcut is pressed, each window
+/// handles the actions it owns. Quick paste and the paste queue are handled
+/// here instead, they must not wait for a hidden webview.
+pub const PRESSED_EVENT: &str = "hotkey://pressed";
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum HotkeyAction {
+    /// Toggle the clipboard window.
+    ShowWindow,
+    /// Toggle the preference window.
+    ShowPreference,
+    /// Paste the latest entry.
+    PasteLast,
+    /// Paste the selected entry, or the latest one, as plain text.
+    PastePlain,
+    /// Paste the entry at `index`, counting from 1, without showing the
+    /// window.
+    PasteNth,
+    /// Paste the next entry of the paste queue.
+    PasteQueue,
+}
+
+/// Shortcuts in the format of the global shortcut plugin, e.g.
+/// `Command+Shift+V`. Empty or missing ones are not registered.
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HotkeyConfig {
+    pub show_window: Option<String>,
+    pub show_preference: Option<String>,
+    pub paste_last: Option<String>,
+    pub paste_plain: Option<String>,
+    /// Modifiers only, e.g. `Command+Shift`, the digit is appended.
+    pub paste_nth: Option<String>,
+    pub paste_queue: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyPressed {
+    pub action: HotkeyAction,
+    pub index: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictReason {
+    /// The shortcut can't be parsed.
+    Invalid,
+    /// Another action already uses the shortcut.
+    Duplicate,
+    /// The system or another application refused the registration.
+    Taken,
+}
+
+/// A shortcut that was not registered.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyConflict {
+    pub action: HotkeyAction,
+    pub shortcut: String,
+    pub reason: ConflictReason,
+    pub message: String,
+}
+
+struct Binding {
+    shortcut: Shortcut,
+    pressed: HotkeyPressed,
+}
+
+/// Shortcuts registered by [`set_hotkeys`], unregistered before the next
+/// call registers the new ones.
+static REGISTERED: Mutex<Vec<Shortcut>> = Mutex::new(Vec::new());
+
+/// Config of the last [`set_hotkeys`] call, registered again by
+/// [`resume_hotkeys`].
+static CONFIG: Mutex<Option<HotkeyConfig>> = Mutex::new(None);
+
+/// Set while the session is locked or switched away, see [`suspend_hotkeys`].
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// Every shortcut of the config with the action it triggers.
+fn requested(config: &HotkeyConfig) -> Vec<(String, HotkeyPressed)> {
+    let single = [
+        (&config.show_window, HotkeyAction::ShowWindow),
+        (&config.show_preference, HotkeyAction::ShowPreference),
+        (&config.paste_last, HotkeyAction::PasteLast),
+        (&config.paste_plain, HotkeyAction::PastePlain),
+        (&config.paste_queue, HotkeyAction::PasteQueue),
+    ];
+
+    let mut requested: Vec<_> = single
+        .into_iter()
+        .filter_map(|(shortcut, action)| {
+            let shortcut = shortcut.as_deref()?.trim();
+
+            (!shortcut.is_empty()).then(|| {
+                (
+                    shortcut.to_string(),
+                    HotkeyPressed {
+                        action,
+                        index: None,
+                    },
+                )
+            })
+        })
+        .collect();
+
+    if let Some(modifiers) = config.paste_nth.as_deref().map(str::trim) {
+        if !modifiers.is_empty() {
+            requested.extend((1..=MAX_SLOT).map(|index| {
+                (
+                    format!("{}+{}", modifiers, index),
+                    HotkeyPressed {
+                        action: HotkeyAction::PasteNth,
+                        index: Some(index),
+                    },
+                )
+            }));
+        }
+    }
+
+    requested
+}
+
+/// Parse the shortcuts, the ones that can't be parsed or are already used
+/// by an earlier action become conflicts.
+fn bindings(config: &HotkeyConfig) -> (Vec<Binding>, Vec<HotkeyConflict>) {
+    let mut bindings: Vec<Binding> = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for (text, pressed) in requested(config) {
+        let shortcut = match text.parse::<Shortcut>() {
+            Ok(shortcut) => shortcut,
+            Err(error) => {
+                conflicts.push(HotkeyConflict {
+                    action: pressed.action,
+                    shortcut: text,
+                    reason: ConflictReason::Invalid,
+                    message: error.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if let Some(existing) = bindings.iter().find(|binding| binding.shortcut == shortcut) {
+            conflicts.push(HotkeyConflict {
+                action: pressed.action,
+                shortcut: text,
+                reason: ConflictReason::Duplicate,
+                message: format!("Already used by {:?}", existing.pressed.action),
+            });
+            continue;
+        }
+
+        bindings.push(Binding { shortcut, pressed });
+    }
+
+    (bindings, conflicts)
+}
+
+fn handle<R: Runtime>(app_handle: &AppHandle<R>, pressed: &HotkeyPressed) {
+    let app_handle = app_handle.clone();
+
+    match (pressed.action, pressed.index) {
+        (HotkeyAction::PasteNth, Some(slot)) => {
+            tauri::async_runtime::spawn(async move {
+                if let Err(error) = paste_slot(&app_handle, slot).await {
+                    log::warn!("Failed to quick paste slot {}: {}", slot, error);
+                }
+            });
+        }
+        (HotkeyAction::PasteQueue, _) => {
+            tauri::async_runtime::spawn(async move {
+                if let Err(error) = paste_next(&app_handle).await {
+                    log::warn!("Failed to paste the next queued item: {}", error);
+                }
+            });
+        }
+        _ => {
+            let _ = app_handle.emit(PRESSED_EVENT, pressed.clone());
+        }
+    }
+}
+
+fn unregister_all<R: Runtime>(app_handle: &AppHandle<R>) {
+    let shortcuts = std::mem::take(&mut *REGISTERED.lock().unwrap());
+
+    for shortcut in shortcuts {
+        if let Err(error) = app_handle.global_shortcut().unregister(shortcut) {
+            log::warn!("Failed to unregister {}: {}", shortcut, error);
+        }
+    }
+}
+
+/// Replace the registered shortcuts with the ones of `config`, returns the
+/// ones that could not be registered.
+pub fn register_hotkeys<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    config: &HotkeyConfig,
+) -> Vec<HotkeyConflict> {
+    unregister_all(app_handle);
+
+    let (bindings, mut conflicts) = bindings(config);
+    let mut registered = Vec::new();
+
+    for Binding { shortcut, pressed } in bindings {
+        let payload = pressed.clone();
+
+        let result =
+            app_handle
+                .global_shortcut()
+                .on_shortcut(shortcut, move |app_handle, _, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        handle(app_handle, &payload);
+                    }
+                });
+
+        match result {
+            Ok(()) => registered.push(shortcut),
+            Err(error) => conflicts.push(HotkeyConflict {
+                action: pressed.action,
+                shortcut: shortcut.to_string(),
+                reason: ConflictReason::Taken,
+                message: error.to_string(),
+            }),
+        }
+    }
+
+    for conflict in &conflicts {
+        log::warn!(
+            "Shortcut {} for {:?} not registered: {}",
+            conflict.shortcut,
+            conflict.action,
+            conflict.message
+        );
+    }
+
+    *REGISTERED.lock().unwrap() = registered;
+
+    conflicts
+}
+
+/// Unregister every shortcut while the session is locked or another user is
+/// active, the grabs would otherwise outlive the session they were made in.
+pub fn suspend_hotkeys<R: Runtime>(app_handle: &AppHandle<R>) {
+    SUSPENDED.store(true, Ordering::SeqCst);
+
+    unregister_all(app_handle);
+}
+
+/// Register the last config again once the session is back.
+pub fn resume_hotkeys<R: Runtime>(app_handle: &AppHandle<R>) {
+    SUSPENDED.store(false, Ordering::SeqCst);
+
+    let config = CONFIG.lock().unwrap().clone();
+
+    if let Some(config) = config {
+        register_hotkeys(app_handle, &config);
+    }
+}
+
+/// Register the last config again unless the session is away, grabs can be
+/// lost when the machine sleeps.
+pub fn refresh_hotkeys<R: Runtime>(app_handle: &AppHandle<R>) {
+    if !SUSPENDED.load(Ordering::SeqCst) {
+        resume_hotkeys(app_handle);
+    }
+}
+
+// 注册全局快捷键，替换之前注册的快捷键，返回无法注册的快捷键
+#[command]
+pub async fn set_hotkeys<R: Runtime>(
+    app_handle: AppHandle<R>,
+    config: HotkeyConfig,
+) -> Vec<HotkeyConflict> {
+    *CONFIG.lock().unwrap() = Some(config.clone());
+
+    // Registered by resume_hotkeys
+    if SUSPENDED.load(Ordering::SeqCst) {
+        return Vec::new();
+    }
+
+    register_hotkeys(&app_handle, &config)
+}
@@ -0,0 +1,111 @@
+use crate::core::cache;
+use crate::vault::read_file;
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Largest width or height accepted from a clipboard bitmap.
+const MAX_DIMENSION: u32 = 16384;
+
+/// Upper bound of the memory a single decode may allocate.
+const MAX_ALLOC: u64 = 512 * 1024 * 1024;
+
+/// A decode running longer than this is abandoned.
+const DECODE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn limits() -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_ALLOC);
+
+    limits
+}
+
+/// Reject dimensions read from a raw header before any buffer is sized by them.
+pub fn check_dimensions(width: u32, height: u32) -> Result<(), String> {
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(format!("Image {}x{} exceeds the decode limit", width, height));
+    }
+
+    Ok(())
+}
+
+/// Run image work on a separate thread so a panic inside a decoder turns
+/// into an error and a decoder stuck on malformed data can't hang the caller.
+/// A timed out thread is detached and left to finish on its own.
+pub fn run_isolated<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::Builder::new()
+        .name("image-decode".to_string())
+        .spawn(move || {
+            let _ = sender.send(f());
+        })
+        .map_err(|e| e.to_string())?;
+
+    match receiver.recv_timeout(DECODE_TIMEOUT) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err("Image decoding timed out".to_string()),
+        // The sender was dropped without a result, the decoder panicked
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err("Image decoder crashed".to_string()),
+    }
+}
+
+/// Decode with size limits, used inside [`run_isolated`].
+pub fn decode_limited(bytes: &[u8], format: ImageFormat) -> Result<DynamicImage, String> {
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits());
+
+    reader.decode().map_err(|e| e.to_string())
+}
+
+/// Decode an image buffer in isolation.
+pub fn decode(bytes: Vec<u8>, format: ImageFormat) -> Result<DynamicImage, String> {
+    run_isolated(move || decode_limited(&bytes, format))
+}
+
+/// Cache key of a file's current content, a rewritten file gets a new one.
+fn file_key(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?;
+
+    Some(format!("{}:{}:{:?}", path.display(), metadata.len(), modified))
+}
+
+/// Decode an image file in isolation, guessing the format from its content.
+/// Files encrypted by the vault are decrypted in memory.
+pub fn decode_file(path: &Path) -> Result<DynamicImage, String> {
+    let key = file_key(path);
+
+    let cached = key
+        .as_deref()
+        .and_then(|key| cache::get::<DynamicImage>(cache::IMAGES, key));
+
+    if let Some(image) = cached {
+        return Ok(DynamicImage::clone(&image));
+    }
+
+    let bytes = read_file(path)?;
+
+    let image = run_isolated(move || {
+        let mut reader = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| e.to_string())?;
+        reader.limits(limits());
+
+        reader.decode().map_err(|e| e.to_string())
+    })?;
+
+    if let Some(key) = key {
+        let size = image.as_bytes().len();
+        cache::put(cache::IMAGES, key, image.clone(), size);
+    }
+
+    Ok(image)
+}
@@ -0,0 +1,275 @@
+use crate::clipboard_image::resolve_image_path;
+use crate::history::{archived_image_values, notify_changed, store, with_connection, HistoryRow};
+use crate::image_decode::{decode_file, decode_limited, run_isolated};
+use crate::vault;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageEncoder, ImageFormat, Rgb, RgbImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Runtime};
+
+/// File format new clipboard images are saved in.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageFormat {
+    #[default]
+    Png,
+    /// Lossless, a good deal smaller than PNG.
+    Webp,
+    /// Lossy, transparent pixels are flattened onto white.
+    Jpeg,
+}
+
+/// How hard PNG data is compressed, better is slower.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ImageStoragePolicy {
+    pub format: StorageFormat,
+    /// JPEG quality from 1 to 100.
+    pub quality: u8,
+    pub compression: PngCompression,
+}
+
+const DEFAULT_POLICY: ImageStoragePolicy = ImageStoragePolicy {
+    format: StorageFormat::Png,
+    quality: 85,
+    compression: PngCompression::Default,
+};
+
+impl Default for ImageStoragePolicy {
+    fn default() -> Self {
+        DEFAULT_POLICY
+    }
+}
+
+static POLICY: Mutex<ImageStoragePolicy> = Mutex::new(DEFAULT_POLICY);
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RecompressReport {
+    pub converted: u64,
+    /// Images already in the format, animated, or not smaller once converted.
+    pub skipped: u64,
+    pub saved_bytes: u64,
+}
+
+impl StorageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StorageFormat::Png => "png",
+            StorageFormat::Webp => "webp",
+            StorageFormat::Jpeg => "jpg",
+        }
+    }
+
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            StorageFormat::Png => ImageFormat::Png,
+            StorageFormat::Webp => ImageFormat::WebP,
+            StorageFormat::Jpeg => ImageFormat::Jpeg,
+        }
+    }
+}
+
+/// The policy new images are saved with.
+pub fn storage_policy() -> ImageStoragePolicy {
+    *POLICY.lock().unwrap()
+}
+
+/// JPEG has no alpha channel, transparent areas would turn black.
+fn flatten(image: &RgbaImage) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let blend =
+            |channel: u8| ((channel as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+/// Encode an image in the format of the policy.
+pub fn encode_for_storage(
+    image: &RgbaImage,
+    policy: &ImageStoragePolicy,
+) -> Result<Vec<u8>, String> {
+    let mut encoded = Vec::new();
+
+    match policy.format {
+        StorageFormat::Png => {
+            let compression = match policy.compression {
+                PngCompression::Fast => CompressionType::Fast,
+                PngCompression::Default => CompressionType::Default,
+                PngCompression::Best => CompressionType::Best,
+            };
+
+            PngEncoder::new_with_quality(&mut encoded, compression, FilterType::Adaptive)
+                .write_image(
+                    image.as_raw(),
+                    image.width(),
+                    image.height(),
+                    image::ExtendedColorType::Rgba8,
+                )
+                .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+        }
+        StorageFormat::Webp => {
+            image
+                .write_to(&mut std::io::Cursor::new(&mut encoded), ImageFormat::WebP)
+                .map_err(|e| format!("Failed to encode WebP: {}", e))?;
+        }
+        StorageFormat::Jpeg => {
+            JpegEncoder::new_with_quality(&mut encoded, policy.quality.clamp(1, 100))
+                .encode_image(&flatten(image))
+                .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+        }
+    }
+
+    Ok(encoded)
+}
+
+/// Re-encode PNG data read from the clipboard for storage. PNG data is kept
+/// as is under the default policy.
+pub fn encode_png_for_storage(
+    png_bytes: &[u8],
+    policy: ImageStoragePolicy,
+) -> Result<Vec<u8>, String> {
+    if policy == DEFAULT_POLICY {
+        return Ok(png_bytes.to_vec());
+    }
+
+    let bytes = png_bytes.to_vec();
+
+    run_isolated(move || {
+        let image = decode_limited(&bytes, ImageFormat::Png)?.to_rgba8();
+
+        encode_for_storage(&image, &policy)
+    })
+}
+
+/// Convert one image file to the policy format, every item pointing at it
+/// is moved to the new file. Returns the bytes saved, `None` when skipped.
+/// Files archived items still point at are kept.
+fn recompress_file<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    value: &str,
+    ids: &[String],
+    policy: &ImageStoragePolicy,
+    archived: bool,
+) -> Result<Option<u64>, String> {
+    let path = resolve_image_path(app_handle, value);
+
+    // Animated GIFs would lose their frames
+    match ImageFormat::from_path(&path).ok() {
+        Some(ImageFormat::Gif) | None => return Ok(None),
+        Some(format) if format == policy.format.image_format() => return Ok(None),
+        _ => {}
+    }
+
+    let original = std::fs::metadata(&path).map_err(|e| e.to_string())?.len();
+    let image = decode_file(&path)?.to_rgba8();
+    let encoded = encode_for_storage(&image, policy)?;
+
+    if encoded.len() as u64 >= original {
+        return Ok(None);
+    }
+
+    let new_value = Path::new(value)
+        .with_extension(policy.format.extension())
+        .to_string_lossy()
+        .to_string();
+
+    vault::write_file(&resolve_image_path(app_handle, &new_value), &encoded)?;
+
+    for id in ids {
+        store().update_row(
+            id,
+            &HistoryRow::from([("value".to_string(), Value::from(new_value.clone()))]),
+        )?;
+    }
+
+    if archived {
+        return Ok(Some(0));
+    }
+
+    let _ = std::fs::remove_file(path);
+
+    Ok(Some(original - encoded.len() as u64))
+}
+
+/// Convert the saved images to the current storage format. Thumbnails are
+/// found by name and keep working.
+pub fn recompress_images<R: Runtime>(
+    app_handle: &AppHandle<R>,
+) -> Result<RecompressReport, String> {
+    let policy = storage_policy();
+    let archived: HashSet<String> = archived_image_values()?.into_iter().collect();
+
+    let rows: Vec<(String, String)> = with_connection(|connection| {
+        let mut statement =
+            connection.prepare("SELECT id, value FROM history WHERE type = 'image'")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            ))
+        })?;
+
+        rows.collect()
+    })?;
+
+    // Exact duplicates share a file
+    let mut files: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (id, value) in rows {
+        files.entry(value).or_default().push(id);
+    }
+
+    let mut report = RecompressReport::default();
+
+    for (value, ids) in &files {
+        match recompress_file(app_handle, value, ids, &policy, archived.contains(value)) {
+            Ok(Some(saved)) => {
+                report.converted += 1;
+                report.saved_bytes += saved;
+            }
+            Ok(None) => report.skipped += 1,
+            Err(error) => {
+                log::warn!("Failed to recompress {}: {}", value, error);
+                report.skipped += 1;
+            }
+        }
+    }
+
+    if report.converted > 0 {
+        notify_changed(app_handle);
+    }
+
+    Ok(report)
+}
+
+// 设置新图片保存的格式（PNG、无损 WebP 或 JPEG）及压缩程度，为空时恢复默认
+#[command]
+pub async fn set_image_storage(policy: Option<ImageStoragePolicy>) {
+    *POLICY.lock().unwrap() = policy.unwrap_or_default();
+}
+
+// 将已保存的图片转换为当前设置的格式，只保留变小的结果
+#[command]
+pub async fn recompress_stored_images<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<RecompressReport, String> {
+    tauri::async_runtime::spawn_blocking(move || recompress_images(&app_handle))
+        .await
+        .map_err(|e| e.to_string())?
+}
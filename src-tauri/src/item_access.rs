@@ -0,0 +1,253 @@
+use crate::history::{get_metadata, require_item, sensitive_among, HistoryItem, SENSITIVE};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{async_runtime, command, AppHandle, Runtime};
+
+/// Shown by the system prompt.
+const REASON: &str = "EcoPaste wants to reveal a sensitive item";
+
+/// Seconds a successful authentication unlocks sensitive items for.
+const DEFAULT_GRACE_PERIOD: u64 = 60;
+
+static GRACE_PERIOD: AtomicU64 = AtomicU64::new(DEFAULT_GRACE_PERIOD);
+static AUTHENTICATED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+/// Held while the system prompt is shown so concurrent requests share it.
+static PROMPT: Mutex<()> = Mutex::new(());
+
+#[cfg(target_os = "windows")]
+mod win {
+    use windows::core::{factory, HSTRING};
+    use windows::Foundation::IAsyncOperation;
+    use windows::Security::Credentials::UI::{UserConsentVerificationResult, UserConsentVerifier};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::WinRT::IUserConsentVerifierInterop;
+
+    /// Windows Hello (PIN, face or fingerprint), the interop call parents the
+    /// prompt to our window so it is not opened behind it.
+    pub fn authenticate(hwnd: Option<isize>, reason: &str) -> Result<bool, String> {
+        let hwnd = hwnd.ok_or("Main window is not available")?;
+
+        let interop = factory::<UserConsentVerifier, IUserConsentVerifierInterop>()
+            .map_err(|e| e.to_string())?;
+        let operation: IAsyncOperation<UserConsentVerificationResult> = unsafe {
+            interop.RequestVerificationForWindowAsync(HWND(hwnd as _), &HSTRING::from(reason))
+        }
+        .map_err(|e| e.to_string())?;
+
+        match operation.get().map_err(|e| e.to_string())? {
+            UserConsentVerificationResult::Verified => Ok(true),
+            UserConsentVerificationResult::Canceled
+            | UserConsentVerificationResult::RetriesExhausted => Ok(false),
+            _ => Err("Windows Hello is not set up on this device".to_string()),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    use block::ConcreteBlock;
+    use cocoa::base::{id, nil, YES};
+    use cocoa::foundation::NSString;
+    use objc::runtime::BOOL;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::mpsc;
+
+    /// LAPolicyDeviceOwnerAuthentication, Touch ID with the login password
+    /// as fallback.
+    const DEVICE_OWNER_AUTHENTICATION: i64 = 2;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    pub fn authenticate(_hwnd: Option<isize>, reason: &str) -> Result<bool, String> {
+        let (sender, receiver) = mpsc::channel();
+
+        unsafe {
+            let context: id = msg_send![class!(LAContext), new];
+            let reason = NSString::alloc(nil).init_str(reason);
+
+            let reply = ConcreteBlock::new(move |success: BOOL, _error: id| {
+                let _ = sender.send(success == YES);
+            })
+            .copy();
+
+            let _: () = msg_send![
+                context,
+                evaluatePolicy: DEVICE_OWNER_AUTHENTICATION
+                localizedReason: reason
+                reply: &*reply
+            ];
+
+            let result = receiver.recv().map_err(|e| e.to_string());
+
+            let _: () = msg_send![reason, release];
+            let _: () = msg_send![context, release];
+
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::process::{self, Command};
+
+    /// Declared by the policy the deb and rpm packages install, it asks for
+    /// the user's own password rather than an administrator's.
+    const ACTION_ID: &str = "com.ayangweb.EcoPaste.reveal-sensitive";
+
+    /// pkcheck exits with 1 when the authentication failed, 2 when no
+    /// agent could ask for it and 3 when the dialog was dismissed.
+    const NOT_AUTHORIZED: i32 = 1;
+    const NO_AGENT: i32 = 2;
+    const DISMISSED: i32 = 3;
+
+    /// Ask polkit's agent to authorize this process for the reveal action.
+    pub fn authenticate(_hwnd: Option<isize>, _reason: &str) -> Result<bool, String> {
+        let status = Command::new("pkcheck")
+            .args(["--action-id", ACTION_ID, "--process"])
+            .arg(process::id().to_string())
+            .arg("--allow-user-interaction")
+            .status()
+            .map_err(|e| format!("polkit is not available: {}", e))?;
+
+        match status.code() {
+            Some(0) => Ok(true),
+            Some(NOT_AUTHORIZED) | Some(DISMISSED) => Ok(false),
+            Some(NO_AGENT) => Err("No polkit authentication agent is running".to_string()),
+            // The action is unknown when the policy is not installed, e.g.
+            // running from an AppImage
+            _ => Err(format!(
+                "Revealing sensitive items needs the polkit action {}, install EcoPaste from the deb or rpm package",
+                ACTION_ID
+            )),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::authenticate;
+
+#[cfg(target_os = "macos")]
+use mac::authenticate;
+
+#[cfg(target_os = "linux")]
+use linux::authenticate;
+
+#[cfg(target_os = "windows")]
+fn main_window_handle<R: Runtime>(app_handle: &AppHandle<R>) -> Option<isize> {
+    use tauri::Manager;
+    use tauri_plugin_eco_window::MAIN_WINDOW_LABEL;
+
+    app_handle
+        .get_webview_window(MAIN_WINDOW_LABEL)
+        .and_then(|window| window.hwnd().ok())
+        .map(|hwnd| hwnd.0 as isize)
+}
+
+/// Only the Windows prompt needs a parent window.
+#[cfg(not(target_os = "windows"))]
+fn main_window_handle<R: Runtime>(_app_handle: &AppHandle<R>) -> Option<isize> {
+    None
+}
+
+/// Whether sensitive items may be shown without a new authentication.
+pub fn within_grace_period() -> bool {
+    let grace = Duration::from_secs(GRACE_PERIOD.load(Ordering::Relaxed));
+
+    AUTHENTICATED_AT
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() < grace)
+}
+
+fn is_sensitive(id: &str) -> Result<bool, String> {
+    Ok(get_metadata(id)?
+        .get(SENSITIVE)
+        .is_some_and(|value| value == "1"))
+}
+
+/// Prompt for the system authentication unless one succeeded within the
+/// grace period.
+fn unlock(hwnd: Option<isize>) -> Result<(), String> {
+    let _prompt = PROMPT.lock().unwrap();
+
+    // Another request may have authenticated while this one waited
+    if within_grace_period() {
+        return Ok(());
+    }
+
+    if !authenticate(hwnd, REASON)? {
+        return Err("Authentication is required to reveal this item".to_string());
+    }
+
+    *AUTHENTICATED_AT.lock().unwrap() = Some(Instant::now());
+
+    Ok(())
+}
+
+/// Fail unless the item is not sensitive or the user just authenticated.
+pub async fn require_access<R: Runtime>(app_handle: &AppHandle<R>, id: &str) -> Result<(), String> {
+    if !is_sensitive(id)? || within_grace_period() {
+        return Ok(());
+    }
+
+    let hwnd = main_window_handle(app_handle);
+
+    async_runtime::spawn_blocking(move || unlock(hwnd))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Like [`require_access`] for many items, one authentication covers all
+/// of them.
+pub async fn require_access_all<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    ids: impl IntoIterator<Item = &str>,
+) -> Result<(), String> {
+    let sensitive = sensitive_among(ids)?;
+
+    match sensitive.into_iter().next() {
+        Some(id) => require_access(app_handle, &id).await,
+        None => Ok(()),
+    }
+}
+
+/// Like [`require_item`], but sensitive items need a fresh authentication.
+pub async fn require_revealed_item<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    id: &str,
+) -> Result<HistoryItem, String> {
+    let item = require_item(id)?;
+
+    require_access(app_handle, id).await?;
+
+    Ok(item)
+}
+
+// 获取条目的完整内容，敏感条目需要先通过系统身份验证
+#[command]
+pub async fn get_item_content<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<HistoryItem, String> {
+    require_revealed_item(&app_handle, &id).await
+}
+
+// 设置身份验证后敏感条目保持解锁的秒数，0 为每次都需要验证
+#[command]
+pub async fn set_reveal_grace_period(seconds: u64) {
+    GRACE_PERIOD.store(seconds, Ordering::Relaxed);
+}
+
+/// Require a new authentication for the next sensitive item.
+pub fn forget_authentication() {
+    *AUTHENTICATED_AT.lock().unwrap() = None;
+}
+
+// 立即锁定敏感条目
+#[command]
+pub async fn lock_sensitive_items() {
+    forget_authentication();
+}
@@ -0,0 +1,331 @@
+use hmac::{Hmac, Mac};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{
+    ClientConfig, DigitallySignedStruct, DistinguishedName, ServerConfig, SignatureScheme,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use spake2::{Ed25519Group, Identity as PakeIdentity, Password, Spake2};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Frames above this are refused, an image entry is the largest message.
+const MAX_FRAME: usize = 64 * 1024 * 1024;
+
+/// The certificate is self-signed, the name is never checked.
+const SERVER_NAME: &str = "ecopaste.local";
+
+/// Certificate of this run, generated on start and advertised by its
+/// fingerprint.
+pub struct Identity {
+    cert: CertificateDer<'static>,
+    key: PrivatePkcs8KeyDer<'static>,
+    pub fingerprint: String,
+}
+
+pub fn fingerprint_of(cert: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(cert))
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+impl Identity {
+    pub fn generate() -> Result<Self, String> {
+        let certified = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+            .map_err(|e| e.to_string())?;
+
+        let cert = certified.cert.der().clone();
+        let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+
+        Ok(Self {
+            fingerprint: fingerprint_of(&cert),
+            cert,
+            key,
+        })
+    }
+
+    pub fn acceptor(&self) -> Result<TlsAcceptor, String> {
+        let config = ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_client_cert_verifier(Arc::new(AnyClientCert {
+                provider: provider(),
+            }))
+            .with_single_cert(
+                vec![self.cert.clone()],
+                PrivateKeyDer::Pkcs8(self.key.clone_key()),
+            )
+            .map_err(|e| e.to_string())?;
+
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+
+    /// Client side of a connection to a peer with the given certificate,
+    /// this run's certificate identifies the client.
+    pub fn connector(
+        &self,
+        fingerprint: &str,
+    ) -> Result<(TlsConnector, ServerName<'static>), String> {
+        let provider = provider();
+        let verifier = PinnedCert {
+            fingerprint: fingerprint.to_string(),
+            provider: provider.clone(),
+        };
+
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_client_auth_cert(
+                vec![self.cert.clone()],
+                PrivateKeyDer::Pkcs8(self.key.clone_key()),
+            )
+            .map_err(|e| e.to_string())?;
+
+        let name = ServerName::try_from(SERVER_NAME).map_err(|e| e.to_string())?;
+
+        Ok((TlsConnector::from(Arc::new(config)), name))
+    }
+}
+
+/// Requires a certificate from the client without trusting it, the server
+/// binds its fingerprint into the pairing, see [`Pairing`].
+#[derive(Debug)]
+struct AnyClientCert {
+    provider: Arc<CryptoProvider>,
+}
+
+impl ClientCertVerifier for AnyClientCert {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Accepts only the certificate a peer advertised over mDNS. Who holds it
+/// is proven afterwards with the pairing key, see [`Pairing`].
+#[derive(Debug)]
+struct PinnedCert {
+    fingerprint: String,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if fingerprint_of(end_entity) == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "Peer certificate does not match".to_string(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            hex.get(index..index + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect()
+}
+
+/// SPAKE2 over the pairing key, bound to the certificates of both ends.
+/// Unlike a keyed hash of the key, nothing sent lets a listener or a fake
+/// peer test guesses offline, each guess costs a connection.
+pub struct Pairing {
+    state: Spake2<Ed25519Group>,
+    transcript: String,
+}
+
+impl Pairing {
+    /// Returns the message for the other end, hex encoded.
+    pub fn start(pairing_key: &str, server: &str, client: &str) -> (Self, String) {
+        let transcript = format!("ecopaste-lan-v2|{}|{}", server, client);
+        let (state, message) = Spake2::<Ed25519Group>::start_symmetric(
+            &Password::new(pairing_key.as_bytes()),
+            &PakeIdentity::new(transcript.as_bytes()),
+        );
+
+        (Self { state, transcript }, to_hex(&message))
+    }
+
+    /// The session key both ends derive only if they used the same pairing
+    /// key, still to be confirmed.
+    pub fn finish(self, message: &str) -> Result<SessionKey, String> {
+        let message = from_hex(message).ok_or("Malformed pairing message")?;
+        let key = self
+            .state
+            .finish(&message)
+            .map_err(|_| "Malformed pairing message".to_string())?;
+
+        Ok(SessionKey {
+            key,
+            transcript: self.transcript,
+        })
+    }
+}
+
+pub struct SessionKey {
+    key: Vec<u8>,
+    transcript: String,
+}
+
+impl SessionKey {
+    fn mac(&self, role: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(role.as_bytes());
+        mac.update(self.transcript.as_bytes());
+
+        mac
+    }
+
+    /// Shows the other end this end derived the same key. `role` tells the
+    /// two directions apart.
+    pub fn confirmation(&self, role: &str) -> String {
+        to_hex(&self.mac(role).finalize().into_bytes())
+    }
+
+    pub fn verify(&self, role: &str, confirmation: &str) -> bool {
+        // Constant time, unlike comparing the hex strings
+        from_hex(confirmation).is_some_and(|bytes| self.mac(role).verify_slice(&bytes).is_ok())
+    }
+}
+
+/// Length-prefixed JSON.
+pub async fn write_frame<T: Serialize>(
+    stream: &mut (impl AsyncWrite + Unpin),
+    message: &T,
+) -> Result<(), String> {
+    let bytes = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+
+    stream
+        .write_u32(bytes.len() as u32)
+        .await
+        .map_err(|e| e.to_string())?;
+    stream.write_all(&bytes).await.map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())
+}
+
+/// `None` once the peer closed the connection.
+pub async fn read_frame<T: DeserializeOwned>(
+    stream: &mut (impl AsyncRead + Unpin),
+) -> Result<Option<T>, String> {
+    let len = match stream.read_u32().await {
+        Ok(len) => len as usize,
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.to_string()),
+    };
+
+    if len > MAX_FRAME {
+        return Err(format!("Message of {} bytes is too large", len));
+    }
+
+    let mut bytes = vec![0; len];
+    stream
+        .read_exact(&mut bytes)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
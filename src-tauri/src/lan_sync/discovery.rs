@@ -0,0 +1,109 @@
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+const SERVICE_TYPE: &str = "_ecopaste._tcp.local.";
+
+/// TXT record keys.
+const NAME_KEY: &str = "name";
+const FINGERPRINT_KEY: &str = "fp";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Peer {
+    pub device_id: String,
+    pub name: String,
+    #[serde(skip)]
+    pub addresses: Vec<SocketAddr>,
+    /// Of the certificate the peer serves, see [`super::channel::Identity`].
+    #[serde(skip)]
+    pub fingerprint: String,
+}
+
+/// Peers currently announced on the network, keyed by mDNS full name.
+static PEERS: Mutex<BTreeMap<String, Peer>> = Mutex::new(BTreeMap::new());
+
+pub fn peers() -> Vec<Peer> {
+    PEERS.lock().unwrap().values().cloned().collect()
+}
+
+pub fn clear_peers() {
+    PEERS.lock().unwrap().clear();
+}
+
+/// Announce this device and follow the other devices announcing themselves.
+pub fn start(
+    device_id: &str,
+    name: &str,
+    port: u16,
+    fingerprint: &str,
+) -> Result<ServiceDaemon, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+
+    let properties = [(NAME_KEY, name), (FINGERPRINT_KEY, fingerprint)];
+    let service = ServiceInfo::new(
+        SERVICE_TYPE,
+        device_id,
+        &format!("{}.local.", device_id),
+        "",
+        port,
+        &properties[..],
+    )
+    .map_err(|e| e.to_string())?
+    .enable_addr_auto();
+
+    daemon.register(service).map_err(|e| e.to_string())?;
+
+    let receiver = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+    let own = device_id.to_string();
+
+    // Ends when the daemon is shut down and the channel closes
+    tauri::async_runtime::spawn(async move {
+        while let Ok(event) = receiver.recv_async().await {
+            match event {
+                ServiceEvent::ServiceResolved(info) => {
+                    let Some(peer) = peer_of(&info).filter(|peer| peer.device_id != own) else {
+                        continue;
+                    };
+
+                    PEERS
+                        .lock()
+                        .unwrap()
+                        .insert(info.get_fullname().to_string(), peer);
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    PEERS.lock().unwrap().remove(&fullname);
+                }
+                _ => {}
+            }
+        }
+    });
+
+    Ok(daemon)
+}
+
+fn peer_of(info: &ServiceInfo) -> Option<Peer> {
+    let device_id = info
+        .get_fullname()
+        .strip_suffix(SERVICE_TYPE)?
+        .trim_end_matches('.')
+        .to_string();
+
+    let addresses = info
+        .get_addresses()
+        .iter()
+        .map(|address| SocketAddr::new(*address, info.get_port()))
+        .collect();
+
+    Some(Peer {
+        name: info
+            .get_property_val_str(NAME_KEY)
+            .unwrap_or(&device_id)
+            .to_string(),
+        fingerprint: info.get_property_val_str(FINGERPRINT_KEY)?.to_string(),
+        device_id,
+        addresses,
+    })
+}
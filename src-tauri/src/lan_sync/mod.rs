@@ -0,0 +1,392 @@
+use crate::clipboard_image::get_images_dir;
+use crate::history::{
+    device_id, insert_entry, notify_changed, store, EntryFormat, HistoryRow, NewHistoryEntry,
+};
+use crate::history_sync::image_name;
+use crate::sensitive::record_sensitive;
+use crate::vault;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use channel::{fingerprint_of, read_frame, write_frame, Identity, Pairing};
+use discovery::Peer;
+use mdns_sd::ServiceDaemon;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{command, AppHandle, Runtime};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsAcceptor;
+
+mod channel;
+mod discovery;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Each side confirms the session key under its own role.
+const CLIENT_ROLE: &str = "client";
+const SERVER_ROLE: &str = "server";
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanSyncConfig {
+    /// Entered on every device that shares its clipboard, devices with
+    /// another key are ignored. Never sent, not even hashed.
+    pub pairing_key: String,
+    /// Shown to the other devices.
+    pub device_name: String,
+}
+
+/// A connection starts with the pairing, the client's hello, the server's
+/// welcome and the client's confirmation, then carries entries until the
+/// client closes it.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum Message {
+    Hello { pake: String },
+    Welcome { pake: String, confirmation: String },
+    Confirm { confirmation: String },
+    Entry { entry: SharedEntry },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharedEntry {
+    row: HistoryRow,
+    formats: Vec<EntryFormat>,
+    /// Base64 content of the file of an image entry.
+    image: Option<String>,
+}
+
+struct Service {
+    config: LanSyncConfig,
+    identity: Arc<Identity>,
+    images_dir: PathBuf,
+    daemon: ServiceDaemon,
+    server: JoinHandle<()>,
+}
+
+static SERVICE: Mutex<Option<Service>> = Mutex::new(None);
+
+/// Store an entry a peer captured, returns whether it was new.
+fn save_entry(images_dir: &Path, entry: SharedEntry) -> Result<bool, String> {
+    let SharedEntry {
+        mut row,
+        formats,
+        image,
+    } = entry;
+
+    let Some(id) = row.get("id").and_then(Value::as_str) else {
+        return Ok(false);
+    };
+
+    if store().get_row(id)?.is_some() {
+        return Ok(false);
+    }
+
+    if row.get("type").and_then(Value::as_str) == Some("image") {
+        let name = image_name(&row).ok_or("Image entry without a valid file name")?;
+        let image = image.ok_or("Image entry without its image")?;
+        let path = images_dir.join(name);
+
+        if !path.exists() {
+            let bytes = STANDARD.decode(image).map_err(|e| e.to_string())?;
+
+            std::fs::create_dir_all(images_dir).map_err(|e| e.to_string())?;
+            vault::write_file(&path, &bytes)?;
+        }
+
+        row.insert(
+            "value".to_string(),
+            Value::from(path.to_string_lossy().to_string()),
+        );
+    }
+
+    let entry = NewHistoryEntry { row, formats };
+
+    insert_entry(&entry)?;
+
+    // Entries arrive without their metadata, secrets are flagged like
+    // they would be on capture
+    record_sensitive(&entry)?;
+
+    Ok(true)
+}
+
+async fn receive<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    acceptor: TlsAcceptor,
+    stream: TcpStream,
+    pairing_key: &str,
+    fingerprint: &str,
+) -> Result<(), String> {
+    let mut stream = acceptor.accept(stream).await.map_err(|e| e.to_string())?;
+
+    let client = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .map(|cert| fingerprint_of(cert))
+        .ok_or("The device did not present a certificate")?;
+
+    let Some(Message::Hello { pake }) = read_frame(&mut stream).await? else {
+        return Err("The device did not introduce itself".to_string());
+    };
+
+    let (pairing, welcome) = Pairing::start(pairing_key, fingerprint, &client);
+    let session = pairing.finish(&pake)?;
+
+    let welcome = Message::Welcome {
+        pake: welcome,
+        confirmation: session.confirmation(SERVER_ROLE),
+    };
+    write_frame(&mut stream, &welcome).await?;
+
+    match read_frame(&mut stream).await? {
+        Some(Message::Confirm { confirmation }) if session.verify(CLIENT_ROLE, &confirmation) => {}
+        _ => return Err("A device with another pairing key tried to connect".to_string()),
+    }
+
+    while let Some(message) = read_frame(&mut stream).await? {
+        let Message::Entry { entry } = message else {
+            continue;
+        };
+
+        let images_dir = get_images_dir(app_handle);
+        let saved = tauri::async_runtime::spawn_blocking(move || save_entry(&images_dir, entry))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        if saved {
+            notify_changed(app_handle);
+        }
+    }
+
+    Ok(())
+}
+
+async fn start<R: Runtime>(
+    app_handle: AppHandle<R>,
+    config: LanSyncConfig,
+) -> Result<Service, String> {
+    let identity = Identity::generate()?;
+    let acceptor = identity.acceptor()?;
+
+    let listener = TcpListener::bind(("0.0.0.0", 0))
+        .await
+        .map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let daemon = discovery::start(
+        &device_id()?,
+        &config.device_name,
+        port,
+        &identity.fingerprint,
+    )?;
+
+    let pairing_key = config.pairing_key.clone();
+    let fingerprint = identity.fingerprint.clone();
+    let images_dir = get_images_dir(&app_handle);
+
+    let server = tauri::async_runtime::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let app_handle = app_handle.clone();
+            let acceptor = acceptor.clone();
+            let pairing_key = pairing_key.clone();
+            let fingerprint = fingerprint.clone();
+
+            tauri::async_runtime::spawn(async move {
+                let received =
+                    receive(&app_handle, acceptor, stream, &pairing_key, &fingerprint).await;
+
+                if let Err(error) = received {
+                    log::warn!("LAN sync connection failed: {}", error);
+                }
+            });
+        }
+    });
+
+    Ok(Service {
+        config,
+        identity: Arc::new(identity),
+        images_dir,
+        daemon,
+        server,
+    })
+}
+
+fn stop() {
+    if let Some(service) = SERVICE.lock().unwrap().take() {
+        service.server.abort();
+        let _ = service.daemon.shutdown();
+    }
+
+    discovery::clear_peers();
+}
+
+/// First address of the peer that accepts a connection.
+async fn connect(addresses: &[SocketAddr]) -> Result<TcpStream, String> {
+    for address in addresses {
+        let connected = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(address)).await;
+
+        if let Ok(Ok(stream)) = connected {
+            return Ok(stream);
+        }
+    }
+
+    Err("The device is not reachable".to_string())
+}
+
+async fn send(
+    identity: &Identity,
+    peer: &Peer,
+    pairing_key: &str,
+    message: &Message,
+) -> Result<(), String> {
+    let (connector, server_name) = identity.connector(&peer.fingerprint)?;
+    let stream = connect(&peer.addresses).await?;
+    let mut stream = connector
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (pairing, pake) = Pairing::start(pairing_key, &peer.fingerprint, &identity.fingerprint);
+    write_frame(&mut stream, &Message::Hello { pake }).await?;
+
+    let Some(Message::Welcome { pake, confirmation }) = read_frame(&mut stream).await? else {
+        return Err("The device did not welcome this one".to_string());
+    };
+
+    let session = pairing.finish(&pake)?;
+
+    if !session.verify(SERVER_ROLE, &confirmation) {
+        return Err("The device uses another pairing key".to_string());
+    }
+
+    let confirm = Message::Confirm {
+        confirmation: session.confirmation(CLIENT_ROLE),
+    };
+    write_frame(&mut stream, &confirm).await?;
+
+    write_frame(&mut stream, message).await?;
+    stream.shutdown().await.map_err(|e| e.to_string())
+}
+
+fn shared_entry(
+    images_dir: &Path,
+    row: HistoryRow,
+    formats: Vec<EntryFormat>,
+) -> Result<SharedEntry, String> {
+    let image = match row.get("value").and_then(Value::as_str) {
+        Some(value) if row.get("type").and_then(Value::as_str) == Some("image") => {
+            let bytes = vault::read_file(&images_dir.join(value))?;
+
+            Some(STANDARD.encode(bytes))
+        }
+        _ => None,
+    };
+
+    Ok(SharedEntry {
+        row,
+        formats,
+        image,
+    })
+}
+
+/// Push an entry captured on this device to the paired devices in the
+/// background. Devices that are offline miss it, the history sync is
+/// there to catch up. Sensitive entries are never shared.
+pub fn share_entry(entry: &NewHistoryEntry) {
+    let service = SERVICE.lock().unwrap().as_ref().map(|service| {
+        (
+            service.config.pairing_key.clone(),
+            service.identity.clone(),
+            service.images_dir.clone(),
+        )
+    });
+    let Some((pairing_key, identity, images_dir)) = service else {
+        return;
+    };
+
+    let peers = discovery::peers();
+
+    if peers.is_empty() {
+        return;
+    }
+
+    let row = entry.row.clone();
+    let formats = entry.formats.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let entry = match shared_entry(&images_dir, row, formats) {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::warn!("Failed to share the entry: {}", error);
+                return;
+            }
+        };
+        let message = Message::Entry { entry };
+
+        for peer in peers {
+            if let Err(error) = send(&identity, &peer, &pairing_key, &message).await {
+                log::warn!("Failed to share the entry with {}: {}", peer.name, error);
+            }
+        }
+    });
+}
+
+/// Start the service again with the same config. After a resume the listener
+/// and the announcement may be bound to addresses the machine no longer has.
+pub async fn restart<R: Runtime>(app_handle: AppHandle<R>) {
+    let config = SERVICE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|service| service.config.clone());
+
+    let Some(config) = config else {
+        return;
+    };
+
+    stop();
+
+    match start(app_handle, config).await {
+        Ok(service) => {
+            let _ = SERVICE.lock().unwrap().insert(service);
+        }
+        Err(error) => log::warn!("Failed to restart LAN sync: {}", error),
+    }
+}
+
+// 设置局域网内设备间的剪贴板共享，传入 null 关闭
+#[command]
+pub async fn set_lan_sync<R: Runtime>(
+    app_handle: AppHandle<R>,
+    config: Option<LanSyncConfig>,
+) -> Result<(), String> {
+    stop();
+
+    let Some(config) = config else {
+        return Ok(());
+    };
+
+    if config.pairing_key.is_empty() {
+        return Err("A pairing key is required".to_string());
+    }
+
+    let service = start(app_handle, config).await?;
+    let _ = SERVICE.lock().unwrap().insert(service);
+
+    Ok(())
+}
+
+// 获取局域网内发现的 EcoPaste 设备
+#[command]
+pub async fn get_lan_peers() -> Vec<Peer> {
+    discovery::peers()
+}
@@ -1,15 +1,132 @@
+mod calculator;
+mod capture_denylist;
+mod capture_rules;
+mod clipboard_files;
+mod clipboard_html;
 mod clipboard_image;
+mod clipboard_request;
+mod clipboard_restore;
+mod clipboard_rtf;
+mod clipboard_simulate;
+mod clipboard_watcher;
+mod clipboard_text;
+mod cold_storage;
+mod context;
+mod conversion;
 mod core;
+mod digest;
+mod drop_capture;
+mod excluded_apps;
+mod focus_mode;
+pub mod history;
+mod history_sync;
+mod history_transfer;
+mod hotkeys;
+mod image_decode;
+mod image_storage;
+mod item_access;
+mod lan_sync;
+mod network;
+mod ocr;
+mod open_with;
+mod paste;
+mod paste_queue;
+mod power;
+mod process_tree;
+mod processors;
+mod retention;
+mod screen_share;
+mod screenshot;
+mod sensitive;
+mod services;
+mod session;
+mod shell_integration;
+mod snippets;
+mod storage;
+mod temp_files;
+mod timeline;
+mod transform;
+mod vault;
+mod vm_clipboard;
 
-use clipboard_image::{has_clipboard_image_win, read_clipboard_image_win};
-use core::{prevent_default, setup};
+use calculator::paste_result;
+use capture_denylist::{get_capture_denylist_audit, set_capture_denylist};
+use capture_rules::{apply_capture_rules, set_capture_rules};
+use clipboard_files::{read_clipboard_files, write_clipboard_files};
+use clipboard_html::{read_clipboard_html, write_clipboard_html};
+use clipboard_image::{
+    copy_image_entry, has_clipboard_image_win, read_clipboard_gif, read_clipboard_image_win,
+    write_clipboard_image,
+};
+use clipboard_restore::restore_clipboard_state;
+use clipboard_rtf::{read_clipboard_rtf, write_clipboard_rtf};
+use clipboard_simulate::{
+    replay_clipboard_fixtures, simulate_clipboard, simulate_clipboard_fixture,
+};
+use clipboard_watcher::{get_clipboard_formats, mark_clipboard_write, set_clipboard_watching};
+use clipboard_text::{load_full_text, read_clipboard_text, set_max_text_capture_size};
+use cold_storage::fetch_image;
+use context::get_capture_context;
+use conversion::{paste_converted, set_conversion_targets, set_currency_rates_fetch};
+use core::cache::{get_runtime_metrics, set_memory_budget};
+use core::{prevent_default, setup, startup};
+use digest::{get_digest, set_digest_policy};
+use drop_capture::add_dropped_text;
+use excluded_apps::set_excluded_apps;
+use focus_mode::{get_focus_mode, set_focus_pause};
+use history::{
+    create_item, create_tag, delete_history_entry, delete_history_range, delete_tag,
+    edit_item_content, find_duplicates, find_history_duplicate, find_similar_images,
+    get_backfill_progress, get_history_formats, get_history_operations, get_item_metadata,
+    get_legacy_migration, get_related, history_replace, insert_history_entry, link_history_items,
+    list_favorites, list_source_apps, list_tags, merge_duplicates, merge_history_operations,
+    query_history, recent_emoji, record_history_operation, save_item_metadata, search_archives,
+    search_fulltext, search_history, search_item_metadata, set_collation_locale, set_favorite,
+    set_image_hashing, set_item_tags, set_save_data_dir, start_backfill, tag_history_items,
+    toggle_history_favorite, undo_history_operation, unlink_history_items, update_history_entry,
+};
+use history_sync::{get_webdav_sync_status, set_webdav_sync, sync_now};
+use history_transfer::{export_favorites, export_history, import_history};
+use hotkeys::set_hotkeys;
+use image_storage::{recompress_stored_images, set_image_storage};
+use item_access::{get_item_content, lock_sensitive_items, set_reveal_grace_period};
+use lan_sync::{get_lan_peers, set_lan_sync};
+use network::{copy_ping_command, open_network_item, set_network_enrichment};
+use ocr::{ocr_history_item, ocr_image};
+use open_with::open_item_with;
+use paste::{paste_and_go, paste_emoji, paste_history_slot, paste_item, paste_plain};
+use paste_queue::{clear_paste_queue, get_paste_queue, pop_paste_queue, push_paste_queue};
+use processors::{
+    list_processors, run_capture_processors, serve_item_protocol, set_enabled_processors,
+    ITEM_PROTOCOL,
+};
+use retention::{set_archive_policy, set_cold_storage_policy, set_retention_policy};
+use screen_share::get_screen_sharing;
+use screenshot::record_screenshot;
+use sensitive::{classify_sensitive, set_sensitive_expiry};
+use shell_integration::{copy_files_with_tags, get_shell_integration, set_shell_integration};
+use snippets::{
+    get_snippet_libraries, get_snippet_sync_status, refresh_snippet_libraries, search_snippets,
+    set_snippet_libraries, set_snippet_sync, sync_snippets,
+};
+use startup::get_startup_timings;
+use storage::{analyze_storage, get_storage_stats, optimize_storage, vacuum_storage};
+use temp_files::purge_temp_files;
+use timeline::export_timeline;
 use tauri::{generate_context, Builder, Manager, WindowEvent};
 use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_eco_window::{show_main_window, MAIN_WINDOW_LABEL, PREFERENCE_WINDOW_LABEL};
 use tauri_plugin_log::{Target, TargetKind};
+use transform::{paste_as_markdown_link, paste_timestamp, paste_with_citation};
+use vault::{
+    disable_vault, enable_vault, get_vault_status, lock_vault, read_vault_image, unlock_vault,
+};
+use vm_clipboard::{get_vm_sources, set_excluded_vms};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    startup::begin();
+
     let app = Builder::default()
         .setup(|app| {
             let app_handle = app.handle();
@@ -18,13 +135,111 @@ pub fn run() {
 
             let preference_window = app.get_webview_window(PREFERENCE_WINDOW_LABEL).unwrap();
 
-            setup::default(&app_handle, main_window.clone(), preference_window.clone());
+            startup::measure("setup", || {
+                setup::default(&app_handle, main_window.clone(), preference_window.clone());
+            });
+
+            startup::measure("clipboard_watcher", || clipboard_watcher::start(app_handle));
+
+            // 跟随系统勿扰模式暂停
+            focus_mode::start(app_handle);
+
+            // 屏幕共享时隐藏敏感条目
+            screen_share::start(app_handle);
+
+            // 锁屏或切换用户时暂停监听和快捷键，回到会话后重新初始化
+            session::start(app_handle);
+
+            // 从睡眠或休眠中恢复后重新检查监听、快捷键和同步连接
+            power::start(app_handle);
+
+            // 到期删除敏感内容
+            sensitive::start(app_handle);
+
+            history::init(app_handle);
+
+            // 开启了加密保险库时，历史记录通过加密存储读写
+            vault::init();
+
+            // 耗时的子系统在后台初始化，不阻塞窗口和剪贴板监听
+            let handle = app_handle.clone();
+            startup::defer("history", move || {
+                history::warm_up();
+
+                // 旧版本遗留在数据目录中的图片
+                history::upgrade_legacy_data(&handle);
+            });
+
+            // 按保留策略定期清理历史记录
+            retention::start(app_handle);
+
+            let handle = app_handle.clone();
+            startup::defer("backfill", move || history::resume_backfill(handle));
+
+            let handle = app_handle.clone();
+            startup::defer("temp_files", move || temp_files::start_sweeper(handle));
+
+            let handle = app_handle.clone();
+            startup::defer("snippet_libraries", move || snippets::start_refresher(handle));
+
+            let handle = app_handle.clone();
+            startup::defer("snippet_sync", move || snippets::start_sync_scheduler(handle));
+
+            let handle = app_handle.clone();
+            startup::defer("history_sync", move || history_sync::start_sync_scheduler(handle));
+
+            // 按天或按周发送剪贴板摘要
+            let handle = app_handle.clone();
+            startup::defer("digest", move || digest::start_scheduler(handle));
+
+            // 由资源管理器右键菜单启动时，复制选中的文件
+            shell_integration::handle_launch_args(app_handle);
+
+            // macOS 服务菜单中的“添加到 EcoPaste”和“粘贴 EcoPaste 中最新的内容”
+            services::register(app_handle);
+
+            // CI 中执行粘贴自检，结果作为退出码，仅开发版可用
+            #[cfg(debug_assertions)]
+            if std::env::args().any(|arg| arg == "--paste-self-test") {
+                let handle = app_handle.clone();
+
+                tauri::async_runtime::spawn(async move {
+                    let code = match tauri_plugin_eco_paste::self_test(None).await {
+                        Ok(report) => {
+                            log::info!(
+                                "Paste self test: passed={} focus_restored={} actual={:?}",
+                                report.passed,
+                                report.focus_restored,
+                                report.actual
+                            );
+
+                            if report.passed {
+                                0
+                            } else {
+                                1
+                            }
+                        }
+                        Err(error) => {
+                            log::error!("Paste self test failed: {}", error);
+
+                            2
+                        }
+                    };
+
+                    handle.exit(code);
+                });
+            }
 
             Ok(())
         })
         // 确保在 windows 和 linux 上只有一个 app 实例在运行：https://github.com/tauri-apps/plugins-workspace/tree/v2/plugins/single-instance
         .plugin(tauri_plugin_single_instance::init(
-            |app_handle, _argv, _cwd| {
+            |app_handle, argv, _cwd| {
+                // 右键菜单“用 EcoPaste 复制”启动的实例只转交文件，不显示窗口
+                if shell_integration::handle_args(app_handle, &argv) {
+                    return;
+                }
+
                 show_main_window(app_handle);
             },
         ))
@@ -33,8 +248,6 @@ pub fn run() {
             MacosLauncher::LaunchAgent,
             Some(vec!["--auto-launch"]),
         ))
-        // 数据库：https://github.com/tauri-apps/tauri-plugin-sql/tree/v2
-        .plugin(tauri_plugin_sql::Builder::default().build())
         // 日志插件：https://github.com/tauri-apps/tauri-plugin-log/tree/v2
         .plugin(
             tauri_plugin_log::Builder::new()
@@ -65,6 +278,8 @@ pub fn run() {
         .plugin(tauri_plugin_locale::init())
         // 打开文件或者链接：https://github.com/tauri-apps/plugins-workspace/tree/v2/plugins/opener
         .plugin(tauri_plugin_opener::init())
+        // 系统通知：https://github.com/tauri-apps/plugins-workspace/tree/v2/plugins/notification
+        .plugin(tauri_plugin_notification::init())
         // 禁用 webview 的默认行为：https://github.com/ferreira-tb/tauri-plugin-prevent-default
         .plugin(prevent_default::init())
         // 剪贴板插件：https://github.com/ayangweb/tauri-plugin-clipboard-x
@@ -75,9 +290,163 @@ pub fn run() {
         .plugin(tauri_plugin_eco_paste::init())
         // 自定义判断是否自动启动的插件
         .plugin(tauri_plugin_eco_autostart::init())
+        // 采集处理器注册的条目类型及其预览
+        .register_asynchronous_uri_scheme_protocol(ITEM_PROTOCOL, |ctx, request, responder| {
+            let app_handle = ctx.app_handle().clone();
+
+            tauri::async_runtime::spawn_blocking(move || {
+                responder.respond(serve_item_protocol(&app_handle, &request));
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             has_clipboard_image_win,
             read_clipboard_image_win,
+            read_clipboard_gif,
+            write_clipboard_image,
+            copy_image_entry,
+            set_clipboard_watching,
+            get_clipboard_formats,
+            mark_clipboard_write,
+            set_excluded_apps,
+            set_excluded_vms,
+            get_vm_sources,
+            set_focus_pause,
+            set_hotkeys,
+            get_focus_mode,
+            get_screen_sharing,
+            classify_sensitive,
+            set_sensitive_expiry,
+            get_item_content,
+            set_reveal_grace_period,
+            lock_sensitive_items,
+            enable_vault,
+            disable_vault,
+            unlock_vault,
+            lock_vault,
+            get_vault_status,
+            read_vault_image,
+            read_clipboard_files,
+            add_dropped_text,
+            write_clipboard_files,
+            read_clipboard_rtf,
+            write_clipboard_rtf,
+            restore_clipboard_state,
+            read_clipboard_html,
+            write_clipboard_html,
+            read_clipboard_text,
+            load_full_text,
+            set_max_text_capture_size,
+            set_save_data_dir,
+            save_item_metadata,
+            get_item_metadata,
+            search_item_metadata,
+            get_capture_context,
+            record_screenshot,
+            set_capture_rules,
+            set_capture_denylist,
+            get_capture_denylist_audit,
+            apply_capture_rules,
+            list_processors,
+            set_enabled_processors,
+            run_capture_processors,
+            paste_with_citation,
+            paste_as_markdown_link,
+            paste_item,
+            paste_plain,
+            paste_emoji,
+            paste_result,
+            paste_converted,
+            paste_timestamp,
+            set_conversion_targets,
+            set_currency_rates_fetch,
+            copy_ping_command,
+            open_network_item,
+            set_network_enrichment,
+            paste_and_go,
+            paste_history_slot,
+            push_paste_queue,
+            pop_paste_queue,
+            clear_paste_queue,
+            get_paste_queue,
+            open_item_with,
+            ocr_image,
+            ocr_history_item,
+            purge_temp_files,
+            analyze_storage,
+            optimize_storage,
+            get_storage_stats,
+            vacuum_storage,
+            set_retention_policy,
+            set_archive_policy,
+            set_cold_storage_policy,
+            fetch_image,
+            search_archives,
+            export_timeline,
+            set_digest_policy,
+            get_digest,
+            export_history,
+            export_favorites,
+            import_history,
+            set_snippet_libraries,
+            refresh_snippet_libraries,
+            get_snippet_libraries,
+            search_snippets,
+            set_snippet_sync,
+            sync_snippets,
+            get_snippet_sync_status,
+            set_webdav_sync,
+            sync_now,
+            get_webdav_sync_status,
+            set_lan_sync,
+            get_lan_peers,
+            set_shell_integration,
+            get_shell_integration,
+            copy_files_with_tags,
+            get_startup_timings,
+            set_memory_budget,
+            get_runtime_metrics,
+            start_backfill,
+            get_backfill_progress,
+            get_legacy_migration,
+            search_fulltext,
+            search_history,
+            record_history_operation,
+            undo_history_operation,
+            get_history_operations,
+            merge_history_operations,
+            link_history_items,
+            unlink_history_items,
+            get_related,
+            insert_history_entry,
+            create_item,
+            edit_item_content,
+            history_replace,
+            find_duplicates,
+            merge_duplicates,
+            find_similar_images,
+            set_image_hashing,
+            set_image_storage,
+            recompress_stored_images,
+            query_history,
+            get_history_formats,
+            update_history_entry,
+            find_history_duplicate,
+            delete_history_entry,
+            delete_history_range,
+            toggle_history_favorite,
+            set_collation_locale,
+            set_favorite,
+            list_favorites,
+            create_tag,
+            delete_tag,
+            list_source_apps,
+            recent_emoji,
+            list_tags,
+            set_item_tags,
+            tag_history_items,
+            simulate_clipboard,
+            simulate_clipboard_fixture,
+            replay_clipboard_fixtures,
         ])
         .on_window_event(|window, event| match event {
             // 让 app 保持在后台运行：https://tauri.app/v1/guides/features/system-tray/#preventing-the-app-from-closing
@@ -86,6 +455,8 @@ pub fn run() {
 
                 api.prevent_close();
             }
+            // 拖放到主窗口上的文件添加到历史记录
+            WindowEvent::DragDrop(_) => drop_capture::handle_window_event(window, event),
             _ => {}
         })
         .build(generate_context!())
@@ -0,0 +1,401 @@
+use crate::clipboard_text::write_text;
+use crate::clipboard_watcher::mark_own_write;
+use crate::history::{set_metadata, NewHistoryEntry, NETWORK_INFO};
+use crate::item_access::require_revealed_item;
+use crate::vault;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, ToSocketAddrs};
+use std::sync::{LazyLock, Mutex};
+use tauri::{command, AppHandle, Runtime};
+use tauri_plugin_opener::OpenerExt;
+
+/// Longest domain name.
+const MAX_DOMAIN_LENGTH: usize = 253;
+
+/// Addresses of a domain kept in the metadata.
+const MAX_ADDRESSES: usize = 8;
+
+/// `example.com` or `api.eu.example.co.uk`, with a top-level domain of
+/// letters.
+static DOMAIN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z]{2,63}$").unwrap()
+});
+
+/// File names look like domains, these endings are taken for files.
+const FILE_EXTENSIONS: &[&str] = &[
+    "bak", "bat", "cfg", "conf", "css", "csv", "doc", "docx", "exe", "gif", "go", "gz", "h",
+    "html", "ini", "jpeg", "jpg", "js", "json", "jsx", "lock", "log", "md", "mp3", "mp4", "pdf",
+    "png", "ppt", "py", "rs", "sh", "sql", "svg", "tar", "toml", "ts", "tsx", "txt", "xls", "xlsx",
+    "xml", "yaml", "yml", "zip",
+];
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EnrichmentPolicy {
+    /// Reverse DNS of IPs and the addresses of domains. Nothing is sent
+    /// over the network unless enabled.
+    pub lookups: bool,
+    /// An ip2asn TSV file (`start end number country description`) to look
+    /// up ASNs offline.
+    pub asn_database: Option<String>,
+}
+
+static POLICY: Mutex<EnrichmentPolicy> = Mutex::new(EnrichmentPolicy {
+    lookups: false,
+    asn_database: None,
+});
+
+/// The loaded ASN database and the file it came from.
+static ASN_DATABASE: Mutex<Option<(String, Vec<AsnRange>)>> = Mutex::new(None);
+
+struct AsnRange {
+    start: IpAddr,
+    end: IpAddr,
+    asn: Asn,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Asn {
+    pub number: u32,
+    pub country: String,
+    pub name: String,
+}
+
+/// Saved with the item under [`NETWORK_INFO`].
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkInfo {
+    /// `ipv4`, `ipv6` or `domain`.
+    pub kind: &'static str,
+    /// `public`, `private`, `loopback`, `link-local`, `shared`,
+    /// `documentation`, `multicast` or `unspecified`, for IPs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<Asn>,
+    /// Host name of an IP, from a reverse lookup.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reverse_dns: Option<String>,
+    /// Addresses a domain resolves to.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<String>,
+}
+
+enum Identifier {
+    Ip(IpAddr),
+    Domain(String),
+}
+
+/// A copied IP, `[::1]` included, or a bare domain name.
+fn parse_identifier(text: &str) -> Option<Identifier> {
+    let text = text.trim();
+
+    if text.is_empty() || text.len() > MAX_DOMAIN_LENGTH {
+        return None;
+    }
+
+    let bracketed = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'));
+
+    if let Ok(ip) = bracketed.unwrap_or(text).parse::<IpAddr>() {
+        return Some(Identifier::Ip(ip));
+    }
+
+    let extension = text.rsplit('.').next().unwrap_or_default().to_lowercase();
+
+    if !DOMAIN.is_match(text) || FILE_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+
+    Some(Identifier::Domain(text.to_lowercase()))
+}
+
+fn ipv4_scope(ip: &Ipv4Addr) -> &'static str {
+    let [first, second, ..] = ip.octets();
+
+    if ip.is_unspecified() {
+        "unspecified"
+    } else if ip.is_loopback() {
+        "loopback"
+    } else if ip.is_private() {
+        "private"
+    } else if ip.is_link_local() {
+        "link-local"
+    } else if first == 100 && (64..128).contains(&second) {
+        // Carrier-grade NAT, 100.64.0.0/10
+        "shared"
+    } else if ip.is_documentation() {
+        "documentation"
+    } else if ip.is_multicast() || ip.is_broadcast() {
+        "multicast"
+    } else {
+        "public"
+    }
+}
+
+fn ipv6_scope(ip: &Ipv6Addr) -> &'static str {
+    let [first, second, ..] = ip.segments();
+
+    if let Some(ipv4) = ip.to_ipv4_mapped() {
+        return ipv4_scope(&ipv4);
+    }
+
+    if ip.is_unspecified() {
+        "unspecified"
+    } else if ip.is_loopback() {
+        "loopback"
+    } else if first & 0xFE00 == 0xFC00 {
+        // Unique local, fc00::/7
+        "private"
+    } else if first & 0xFFC0 == 0xFE80 {
+        "link-local"
+    } else if first == 0x2001 && second == 0x0DB8 {
+        "documentation"
+    } else if ip.is_multicast() {
+        "multicast"
+    } else {
+        "public"
+    }
+}
+
+/// Read an ip2asn TSV file, ranges of unrouted space are left out.
+fn load_asn_database(path: &str) -> Result<Vec<AsnRange>, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut ranges = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let mut fields = line.split('\t');
+
+        let (Some(start), Some(end), Some(number), Some(country), Some(name)) = (
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+        ) else {
+            continue;
+        };
+
+        let (Ok(start), Ok(end), Ok(number)) = (start.parse(), end.parse(), number.parse::<u32>())
+        else {
+            continue;
+        };
+
+        if number == 0 {
+            continue;
+        }
+
+        ranges.push(AsnRange {
+            start,
+            end,
+            asn: Asn {
+                number,
+                country: country.to_string(),
+                name: name.to_string(),
+            },
+        });
+    }
+
+    ranges.sort_by(|a, b| a.start.cmp(&b.start));
+
+    Ok(ranges)
+}
+
+/// The ASN announcing an IP, from the offline database.
+fn find_asn(ip: &IpAddr) -> Option<Asn> {
+    let database = ASN_DATABASE.lock().unwrap();
+    let (_, ranges) = database.as_ref()?;
+
+    let index = ranges
+        .partition_point(|range| range.start <= *ip)
+        .checked_sub(1)?;
+    let range = &ranges[index];
+
+    (*ip <= range.end).then(|| range.asn.clone())
+}
+
+/// What is known without the network.
+fn offline_info(identifier: &Identifier) -> NetworkInfo {
+    match identifier {
+        Identifier::Ip(ip) => NetworkInfo {
+            kind: if ip.is_ipv4() { "ipv4" } else { "ipv6" },
+            scope: Some(match ip {
+                IpAddr::V4(ip) => ipv4_scope(ip),
+                IpAddr::V6(ip) => ipv6_scope(ip),
+            }),
+            asn: find_asn(ip),
+            ..Default::default()
+        },
+        Identifier::Domain(_) => NetworkInfo {
+            kind: "domain",
+            ..Default::default()
+        },
+    }
+}
+
+/// Reverse DNS of a public IP, the addresses of a domain and the ASN of the
+/// first one. Private addresses are never looked up.
+fn lookup(identifier: &Identifier, info: &mut NetworkInfo) {
+    match identifier {
+        Identifier::Ip(ip) => {
+            if info.scope != Some("public") {
+                return;
+            }
+
+            info.reverse_dns = dns_lookup::lookup_addr(ip)
+                .ok()
+                .filter(|host| *host != ip.to_string());
+        }
+        Identifier::Domain(domain) => {
+            let Ok(addresses) = (domain.as_str(), 0).to_socket_addrs() else {
+                return;
+            };
+
+            let mut ips: Vec<IpAddr> = addresses.map(|address| address.ip()).collect();
+            ips.dedup();
+
+            info.asn = ips.first().and_then(find_asn);
+            info.addresses = ips
+                .iter()
+                .take(MAX_ADDRESSES)
+                .map(IpAddr::to_string)
+                .collect();
+        }
+    }
+}
+
+fn save_info(id: &str, info: &NetworkInfo) -> Result<(), String> {
+    let json = serde_json::to_string(info).map_err(|e| e.to_string())?;
+
+    set_metadata(id, NETWORK_INFO, &json)
+}
+
+/// Save what is known of a copied IP or domain with the item. Lookups over
+/// the network run in the background when enabled. Skipped in vault mode
+/// like expression results.
+pub fn record_network_info(entry: &NewHistoryEntry) {
+    if vault::is_enabled() {
+        return;
+    }
+
+    let field = |name: &str| entry.row.get(name).and_then(Value::as_str);
+
+    let (Some("text"), Some(id), Some(text)) = (field("type"), field("id"), field("value")) else {
+        return;
+    };
+
+    let Some(identifier) = parse_identifier(text) else {
+        return;
+    };
+
+    let mut info = offline_info(&identifier);
+
+    if let Err(error) = save_info(id, &info) {
+        log::warn!("Failed to save the network info: {}", error);
+        return;
+    }
+
+    if !POLICY.lock().unwrap().lookups {
+        return;
+    }
+
+    let id = id.to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        lookup(&identifier, &mut info);
+
+        if let Err(error) = save_info(&id, &info) {
+            log::warn!("Failed to save the network lookups: {}", error);
+        }
+    });
+}
+
+/// The IP or domain of an item.
+async fn item_host<R: Runtime>(app_handle: &AppHandle<R>, id: &str) -> Result<Identifier, String> {
+    let item = require_revealed_item(app_handle, id).await?;
+
+    Some(&item)
+        .filter(|item| item.kind == "text")
+        .and_then(|item| parse_identifier(&item.value))
+        .ok_or_else(|| "The item is not an IP or a domain".to_string())
+}
+
+// 设置复制 IP 和域名时附加的信息，网络查询默认关闭，ASN 从离线数据库中查找
+#[command]
+pub async fn set_network_enrichment(policy: Option<EnrichmentPolicy>) -> Result<(), String> {
+    let policy = policy.unwrap_or_default();
+    let path = policy.asn_database.clone().filter(|path| !path.is_empty());
+
+    *POLICY.lock().unwrap() = policy;
+
+    let loaded = ASN_DATABASE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|(loaded, _)| loaded.clone());
+
+    if path == loaded {
+        return Ok(());
+    }
+
+    let Some(path) = path else {
+        *ASN_DATABASE.lock().unwrap() = None;
+        return Ok(());
+    };
+
+    let ranges = tauri::async_runtime::spawn_blocking({
+        let path = path.clone();
+        move || load_asn_database(&path)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    *ASN_DATABASE.lock().unwrap() = Some((path, ranges));
+
+    Ok(())
+}
+
+// 复制 ping 该 IP 或域名的命令
+#[command]
+pub async fn copy_ping_command<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<(), String> {
+    let host = match item_host(&app_handle, &id).await? {
+        Identifier::Ip(ip) => ip.to_string(),
+        Identifier::Domain(domain) => domain,
+    };
+
+    let command = if cfg!(target_os = "windows") {
+        format!("ping {}", host)
+    } else {
+        format!("ping -c 4 {}", host)
+    };
+
+    mark_own_write();
+    write_text(&command)
+}
+
+// 在浏览器中打开该 IP 或域名
+#[command]
+pub async fn open_network_item<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<(), String> {
+    let url = match item_host(&app_handle, &id).await? {
+        Identifier::Ip(IpAddr::V6(ip)) => format!("http://[{}]", ip),
+        Identifier::Ip(ip) => format!("http://{}", ip),
+        Identifier::Domain(domain) => format!("https://{}", domain),
+    };
+
+    app_handle
+        .opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| e.to_string())
+}
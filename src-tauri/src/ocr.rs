@@ -0,0 +1,211 @@
+use crate::cold_storage::warm_image;
+use crate::history::{
+    get_metadata, notify_changed, require_item, set_metadata, store, HistoryRow, OCR_TEXT,
+    SCREENSHOT_SOURCE, SOURCE_URL, WINDOW_TITLE,
+};
+use crate::vault;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tauri::{async_runtime, command, AppHandle, Runtime};
+
+#[cfg(target_os = "windows")]
+mod win {
+    use std::path::Path;
+    use windows::core::HSTRING;
+    use windows::Graphics::Imaging::BitmapDecoder;
+    use windows::Media::Ocr::OcrEngine;
+    use windows::Storage::{FileAccessMode, StorageFile};
+
+    /// Windows.Media.Ocr with the languages of the user profile.
+    pub fn recognize(path: &Path) -> Result<String, String> {
+        let recognize = || -> windows::core::Result<String> {
+            let path = HSTRING::from(path.as_os_str());
+            let file = StorageFile::GetFileFromPathAsync(&path)?.get()?;
+            let stream = file.OpenAsync(FileAccessMode::Read)?.get()?;
+            let decoder = BitmapDecoder::CreateAsync(&stream)?.get()?;
+            let bitmap = decoder.GetSoftwareBitmapAsync()?.get()?;
+
+            let engine = OcrEngine::TryCreateFromUserProfileLanguages()?;
+            let result = engine.RecognizeAsync(&bitmap)?.get()?;
+
+            Ok(result.Text()?.to_string())
+        };
+
+        recognize().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSAutoreleasePool, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::path::Path;
+
+    #[link(name = "Vision", kind = "framework")]
+    extern "C" {}
+
+    /// VNRequestTextRecognitionLevelAccurate.
+    const RECOGNITION_LEVEL_ACCURATE: i64 = 0;
+
+    /// Vision text recognition, performed synchronously on the calling thread.
+    pub fn recognize(path: &Path) -> Result<String, String> {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+
+            let path = NSString::alloc(nil).init_str(&path.to_string_lossy());
+            let url: id = msg_send![class!(NSURL), fileURLWithPath: path];
+
+            let request: id = msg_send![class!(VNRecognizeTextRequest), new];
+            let _: () = msg_send![request, setRecognitionLevel: RECOGNITION_LEVEL_ACCURATE];
+            let _: () = msg_send![request, setUsesLanguageCorrection: true];
+
+            let handler: id = msg_send![class!(VNImageRequestHandler), alloc];
+            let handler: id = msg_send![handler, initWithURL: url options: nil];
+
+            let requests = NSArray::arrayWithObject(nil, request);
+            let mut error: id = nil;
+            let performed: bool = msg_send![handler, performRequests: requests error: &mut error];
+
+            let result = if performed {
+                let observations: id = msg_send![request, results];
+                let mut lines = Vec::new();
+
+                for index in 0..observations.count() {
+                    let observation = observations.objectAtIndex(index);
+                    let candidates: id = msg_send![observation, topCandidates: 1usize];
+
+                    if candidates.count() > 0 {
+                        let text: id = msg_send![candidates.objectAtIndex(0), string];
+                        lines.push(
+                            CStr::from_ptr(text.UTF8String())
+                                .to_string_lossy()
+                                .to_string(),
+                        );
+                    }
+                }
+
+                Ok(lines.join("\n"))
+            } else {
+                Err("Vision failed to recognize text".to_string())
+            };
+
+            let _: () = msg_send![handler, release];
+            let _: () = msg_send![request, release];
+            let _: () = msg_send![path, release];
+            pool.drain();
+
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::path::Path;
+    use std::process::Command;
+
+    /// The tesseract CLI, installed from the distribution's packages.
+    pub fn recognize(path: &Path) -> Result<String, String> {
+        let output = Command::new("tesseract")
+            .arg(path)
+            .arg("stdout")
+            .output()
+            .map_err(|e| format!("tesseract is not installed: {}", e))?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::recognize;
+
+#[cfg(target_os = "macos")]
+use mac::recognize;
+
+#[cfg(target_os = "linux")]
+use linux::recognize;
+
+/// Recognize the text of an image file, with blank lines and trailing
+/// whitespace trimmed.
+pub fn recognize_text(path: &Path) -> Result<String, String> {
+    let text = recognize(path)?;
+
+    Ok(text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// OCR blocks for up to seconds on large screenshots, keep it off the
+/// async runtime.
+async fn recognize_blocking(path: PathBuf) -> Result<String, String> {
+    async_runtime::spawn_blocking(move || recognize_text(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 识别图片中的文字
+#[command]
+pub async fn ocr_image(path: String) -> Result<String, String> {
+    recognize_blocking(PathBuf::from(path)).await
+}
+
+/// Store recognized text as the item's search content, a screenshot stays
+/// findable by the window it was taken of.
+pub fn save_recognized_text(id: &str, text: &str) -> Result<(), String> {
+    let metadata = get_metadata(id)?;
+    let context = [WINDOW_TITLE, SOURCE_URL]
+        .into_iter()
+        .filter(|_| metadata.contains_key(SCREENSHOT_SOURCE))
+        .filter_map(|key| metadata.get(key).map(String::as_str));
+    let search = context.chain([text]).collect::<Vec<_>>().join("\n");
+
+    // The search column is indexed for full-text search, the metadata keeps
+    // where it came from
+    store().update_row(
+        id,
+        &HistoryRow::from([("search".to_string(), Value::from(search))]),
+    )?;
+    set_metadata(id, OCR_TEXT, text)
+}
+
+// 识别图片条目中的文字，保存为条目的搜索内容，使图片可以被搜索到
+#[command]
+pub async fn ocr_history_item<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<String, String> {
+    let item = require_item(&id)?;
+
+    if item.kind != "image" {
+        return Err("Only image items can be recognized".to_string());
+    }
+
+    // The system recognizers read the file themselves, it is encrypted in
+    // vault mode
+    if vault::is_enabled() {
+        return Err("Text recognition is not available while the vault is enabled".to_string());
+    }
+
+    let text = recognize_blocking(warm_image(&app_handle, &item.value)?).await?;
+
+    if text.is_empty() {
+        return Ok(text);
+    }
+
+    save_recognized_text(&id, &text)?;
+
+    notify_changed(&app_handle);
+
+    Ok(text)
+}
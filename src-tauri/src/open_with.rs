@@ -0,0 +1,83 @@
+use crate::history::HistoryItem;
+use crate::item_access::require_revealed_item;
+use crate::temp_files::{materialize_item, reap, set_consumer};
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use tauri::{command, AppHandle, Runtime};
+
+/// Files of a `files` item, stored as a JSON array by the webview.
+fn item_files(item: &HistoryItem) -> Result<Vec<PathBuf>, String> {
+    let paths: Vec<String> = serde_json::from_str(&item.value).map_err(|e| e.to_string())?;
+
+    Ok(paths.into_iter().map(PathBuf::from).collect())
+}
+
+/// Launch `app_path` with the file, or show the OS "Open With" picker.
+/// Returns the launched app when it is the file's consumer, other launches
+/// are reaped in the background.
+fn launch(path: &PathBuf, app_path: Option<&str>) -> Result<Option<Child>, String> {
+    let mut command = match app_path {
+        #[cfg(target_os = "macos")]
+        Some(app_path) => {
+            let mut command = Command::new("open");
+            command.arg("-a").arg(app_path);
+            command
+        }
+        #[cfg(not(target_os = "macos"))]
+        Some(app_path) => Command::new(app_path),
+        #[cfg(target_os = "windows")]
+        None => {
+            let mut command = Command::new("rundll32.exe");
+            command.arg("shell32.dll,OpenAs_RunDLL");
+            command
+        }
+        // Finder has no scriptable picker, reveal the file so "Open With" is one click away
+        #[cfg(target_os = "macos")]
+        None => {
+            let mut command = Command::new("open");
+            command.arg("-R");
+            command
+        }
+        #[cfg(target_os = "linux")]
+        None => Command::new("xdg-open"),
+    };
+
+    let child = command.arg(path).spawn().map_err(|e| e.to_string())?;
+
+    // `open` and the picker exit right away, only a direct launch owns the file
+    if cfg!(target_os = "macos") || app_path.is_none() {
+        reap(child);
+
+        return Ok(None);
+    }
+
+    Ok(Some(child))
+}
+
+// 用指定的应用（或系统的“打开方式”）打开条目
+#[command]
+pub async fn open_item_with<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    app_path: Option<String>,
+) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+
+    if item.kind == "files" {
+        for path in item_files(&item)? {
+            if let Some(child) = launch(&path, app_path.as_deref())? {
+                reap(child);
+            }
+        }
+
+        return Ok(());
+    }
+
+    let path = materialize_item(&app_handle, &item)?;
+
+    if let Some(child) = launch(&path, app_path.as_deref())? {
+        set_consumer(&path, child);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use crate::clipboard_files::write_clipboard_files;
+use crate::clipboard_html::write_html;
+use crate::clipboard_image::write_clipboard_image;
+use crate::clipboard_rtf::write_rtf;
+use crate::clipboard_text::write_text;
+use crate::clipboard_watcher::mark_own_write;
+use crate::context::is_browser;
+use crate::history::{parse_emoji, store, HistoryItem};
+use crate::item_access::{require_access, require_revealed_item};
+use tauri::{command, AppHandle, Runtime};
+use tauri_plugin_eco_paste::{
+    detect_paste_target, find_rule, trigger_paste, trigger_paste_and_go, trigger_typing,
+};
+use tauri_plugin_opener::OpenerExt;
+
+/// Write the representation of an item best suited for the paste target.
+pub fn write_for_target(item: &HistoryItem, rich: bool) -> Result<(), String> {
+    mark_own_write();
+
+    match (item.kind.as_str(), rich) {
+        ("text", _) => write_text(&item.value),
+        ("html", true) => write_html(&item.value, &item.search),
+        ("rtf", true) => write_rtf(&item.value, &item.search),
+        ("html" | "rtf", false) => write_text(&item.search),
+        (kind, _) => Err(format!("Unsupported item type for backend paste: {}", kind)),
+    }
+}
+
+/// Write the item for the paste target and paste it, or type its plain text
+/// into targets whose rule asks for typing. The clipboard is left as it was
+/// when typing.
+async fn paste_for_target<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    item: &HistoryItem,
+) -> Result<(), String> {
+    let target = detect_paste_target();
+
+    if target.typing {
+        let text = match item.kind.as_str() {
+            "text" => &item.value,
+            _ => &item.search,
+        };
+
+        return trigger_typing(app_handle, text).await;
+    }
+
+    write_for_target(item, target.rich)?;
+
+    trigger_paste(app_handle).await;
+
+    Ok(())
+}
+
+/// Quick paste covers the digits 1 to 9.
+pub const MAX_SLOT: usize = 9;
+
+/// Paste the item in history slot `slot`, 1 being the latest, without
+/// showing any window.
+pub async fn paste_slot<R: Runtime>(app_handle: &AppHandle<R>, slot: usize) -> Result<(), String> {
+    if !(1..=MAX_SLOT).contains(&slot) {
+        return Err(format!("Slot {} is out of range", slot));
+    }
+
+    let item = store()
+        .list_items(slot)?
+        .into_iter()
+        .nth(slot - 1)
+        .ok_or_else(|| format!("History slot {} is empty", slot))?;
+
+    paste_history_item(app_handle, &item).await
+}
+
+/// Write any history item to the clipboard and paste it, sensitive items
+/// need a fresh authentication. Images and files are written as such, text
+/// in the format the paste target takes.
+pub async fn paste_history_item<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    item: &HistoryItem,
+) -> Result<(), String> {
+    require_access(app_handle, &item.id).await?;
+
+    match item.kind.as_str() {
+        "image" => {
+            mark_own_write();
+            write_clipboard_image(app_handle.clone(), Some(item.value.clone()), None).await?;
+        }
+        "files" => {
+            let paths = serde_json::from_str(&item.value).map_err(|e| e.to_string())?;
+
+            mark_own_write();
+            write_clipboard_files(paths).await?;
+        }
+        _ => return paste_for_target(app_handle, item).await,
+    }
+
+    trigger_paste(app_handle).await;
+
+    Ok(())
+}
+
+// 根据粘贴目标自动选择富文本或纯文本后粘贴，规则要求时逐字输入
+#[command]
+pub async fn paste_item<R: Runtime>(app_handle: AppHandle<R>, id: String) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+
+    paste_for_target(&app_handle, &item).await
+}
+
+// 只写入纯文本（清除富文本等其它格式）后粘贴，用于去掉粘贴到 Word、邮件中的格式
+#[command]
+pub async fn paste_plain<R: Runtime>(app_handle: AppHandle<R>, text: String) -> Result<(), String> {
+    if detect_paste_target().typing {
+        return trigger_typing(&app_handle, &text).await;
+    }
+
+    mark_own_write();
+    write_text(&text)?;
+
+    trigger_paste(&app_handle).await;
+
+    Ok(())
+}
+
+// 粘贴网址到浏览器地址栏并转到，没有浏览器获得焦点时直接打开
+#[command]
+pub async fn paste_and_go<R: Runtime>(app_handle: AppHandle<R>, id: String) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+
+    if !item.is_url() {
+        return Err("Paste and go is only available for URL items".to_string());
+    }
+
+    let url = item.value.trim();
+    let target = detect_paste_target();
+
+    let go = target.window.as_ref().is_some_and(|window| {
+        find_rule(&window.process_name)
+            .and_then(|rule| rule.go)
+            .unwrap_or_else(|| is_browser(&window.process_name))
+    });
+
+    if !go {
+        return app_handle
+            .opener()
+            .open_url(url, None::<&str>)
+            .map_err(|e| e.to_string());
+    }
+
+    mark_own_write();
+    write_text(url)?;
+
+    trigger_paste_and_go(&app_handle).await;
+
+    Ok(())
+}
+
+// 粘贴表情，codepoint 为 1F44D-1F3FD 形式的码位或表情本身，写入的剪贴板内容会计入最近使用的表情
+#[command]
+pub async fn paste_emoji<R: Runtime>(
+    app_handle: AppHandle<R>,
+    codepoint: String,
+) -> Result<(), String> {
+    let emoji = parse_emoji(&codepoint)?;
+
+    if detect_paste_target().typing {
+        return trigger_typing(&app_handle, &emoji).await;
+    }
+
+    mark_own_write();
+    write_text(&emoji)?;
+
+    trigger_paste(&app_handle).await;
+
+    Ok(())
+}
+
+// 不打开窗口，直接粘贴第 N 条历史记录（1 为最新的一条）
+#[command]
+pub async fn paste_history_slot<R: Runtime>(
+    app_handle: AppHandle<R>,
+    slot: usize,
+) -> Result<(), String> {
+    paste_slot(&app_handle, slot).await
+}
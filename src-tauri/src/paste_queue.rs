@@ -0,0 +1,95 @@
+use crate::history::{get_item, require_item};
+use crate::item_access::require_access_all;
+use crate::paste::paste_history_item;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+/// Emitted with the ids still queued whenever the queue changes.
+pub const CHANGED_EVENT: &str = "paste-queue://changed";
+
+/// Items pasted one per press of the queue shortcut, oldest first. Kept in
+/// memory only, a restart empties it.
+static QUEUE: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+fn queued() -> Vec<String> {
+    QUEUE.lock().unwrap().iter().cloned().collect()
+}
+
+fn notify<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<String> {
+    let ids = queued();
+
+    let _ = app_handle.emit(CHANGED_EVENT, &ids);
+
+    ids
+}
+
+/// Take the next queued item that still exists and paste it, returns its
+/// id or `None` once the queue is empty. An item that fails to paste, e.g.
+/// because authentication was cancelled, stays at the front.
+pub async fn paste_next<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Option<String>, String> {
+    let item = loop {
+        let next = QUEUE.lock().unwrap().pop_front();
+
+        let Some(id) = next else {
+            notify(app_handle);
+            return Ok(None);
+        };
+
+        // Deleted since it was queued
+        if let Some(item) = get_item(&id)? {
+            break item;
+        }
+    };
+
+    if let Err(error) = paste_history_item(app_handle, &item).await {
+        QUEUE.lock().unwrap().push_front(item.id);
+        notify(app_handle);
+
+        return Err(error);
+    }
+
+    notify(app_handle);
+
+    Ok(Some(item.id))
+}
+
+// 将条目依次加入粘贴队列，返回队列中的条目
+#[command]
+pub async fn push_paste_queue<R: Runtime>(
+    app_handle: AppHandle<R>,
+    ids: Vec<String>,
+) -> Result<Vec<String>, String> {
+    for id in &ids {
+        require_item(id)?;
+    }
+
+    // The queue shows what it holds, sensitive items need an authentication
+    require_access_all(&app_handle, ids.iter().map(String::as_str)).await?;
+
+    QUEUE.lock().unwrap().extend(ids);
+
+    Ok(notify(&app_handle))
+}
+
+// 粘贴队列中的下一个条目，返回它的 id，队列为空时返回 null
+#[command]
+pub async fn pop_paste_queue<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<Option<String>, String> {
+    paste_next(&app_handle).await
+}
+
+// 清空粘贴队列
+#[command]
+pub async fn clear_paste_queue<R: Runtime>(app_handle: AppHandle<R>) {
+    QUEUE.lock().unwrap().clear();
+
+    notify(&app_handle);
+}
+
+// 获取粘贴队列中的条目
+#[command]
+pub async fn get_paste_queue() -> Vec<String> {
+    queued()
+}
@@ -1,4 +1,13 @@
-const COMMANDS: &[&str] = &["paste"];
+const COMMANDS: &[&str] = &[
+    "paste",
+    "paste_as_keystrokes",
+    "set_paste_keystroke",
+    "set_paste_rules",
+    "get_paste_target",
+    "get_previous_app",
+    "set_paste_timing",
+    "run_paste_self_test",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS).build();
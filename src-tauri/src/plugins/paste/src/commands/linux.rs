@@ -1,16 +1,29 @@
-use super::wait;
+use super::{normalize_newlines, wait, wayland, AppIcon, TargetWindow};
+use crate::keystroke::{configured_chord, resolve_chord, Chord, ChordKey, PasteKeystroke};
+use crate::timing::paste_timing;
 use rdev::{simulate, EventType, Key};
 use std::sync::Mutex;
 use tauri::command;
-use tauri_plugin_eco_window::MAIN_WINDOW_TITLE;
 use x11::xlib::{
-    self, Atom, Display, XCloseDisplay, XDefaultRootWindow, XFree, XGetInputFocus,
-    XGetWindowProperty, XInternAtom, XNextEvent, XOpenDisplay, XRaiseWindow, XSelectInput,
-    XSetInputFocus,
+    self, Atom, Display, KeySym, XChangeKeyboardMapping, XCloseDisplay, XDefaultRootWindow,
+    XDisplayKeycodes, XFlush, XFree, XGetInputFocus, XGetKeyboardMapping, XGetWindowProperty,
+    XInternAtom, XKeysymToKeycode, XNextEvent, XOpenDisplay, XRaiseWindow, XSelectInput,
+    XSetInputFocus, XSync,
 };
+use x11::xtest::XTestFakeKeyEvent;
 
 static PREVIOUS_WINDOW: Mutex<Option<u64>> = Mutex::new(None);
 
+// XK_Return 和 XK_Tab
+const KEYSYM_RETURN: KeySym = 0xff0d;
+const KEYSYM_TAB: KeySym = 0xff09;
+
+// 其它 Unicode 字符的 keysym 为码点加上这个偏移
+const KEYSYM_UNICODE_OFFSET: KeySym = 0x0100_0000;
+
+// 优先选择的图标边长
+const ICON_SIZE: usize = 32;
+
 // 获取窗口标题
 fn get_net_wm_name(display: *mut Display, window: u64) -> std::result::Result<String, String> {
     let mut actual_type: Atom = 0;
@@ -49,6 +62,54 @@ fn get_net_wm_name(display: *mut Display, window: u64) -> std::result::Result<St
     }
 }
 
+// 读取 32 位的窗口属性，例如 _NET_WM_PID、_NET_WM_ICON、_NET_CLIENT_LIST
+fn get_cardinals(display: *mut Display, window: u64, name: &[u8]) -> Option<Vec<u64>> {
+    let mut actual_type: Atom = 0;
+    let mut actual_format: i32 = 0;
+    let mut nitems: u64 = 0;
+    let mut bytes_after: u64 = 0;
+    let mut prop: *mut u8 = std::ptr::null_mut();
+    let atom = unsafe { XInternAtom(display, name.as_ptr() as _, xlib::False) };
+    let result = unsafe {
+        XGetWindowProperty(
+            display,
+            window,
+            atom,
+            0,
+            // 图标可能有多个尺寸，单位为 32 位
+            1 << 20,
+            xlib::False,
+            xlib::AnyPropertyType as _,
+            &mut actual_type,
+            &mut actual_format,
+            &mut nitems,
+            &mut bytes_after,
+            &mut prop,
+        )
+    };
+    if result != xlib::Success as i32 || prop.is_null() {
+        return None;
+    }
+
+    // 格式为 32 时 Xlib 按 long 返回每一项
+    let values = (actual_format == 32).then(|| unsafe {
+        std::slice::from_raw_parts(prop as *const std::ffi::c_ulong, nitems as usize)
+            .iter()
+            .map(|&value| value as u64)
+            .collect()
+    });
+    unsafe { XFree(prop as *mut _) };
+
+    values
+}
+
+// 窗口是否属于 EcoPaste 自己的进程，按 _NET_WM_PID 判断，不受窗口标题的影响
+fn is_own_window(display: *mut Display, window: u64) -> bool {
+    get_cardinals(display, window, b"_NET_WM_PID\0")
+        .and_then(|values| values.first().copied())
+        .is_some_and(|pid| pid == std::process::id() as u64)
+}
+
 // 监听窗口切换
 pub fn observe_app() {
     std::thread::spawn(|| unsafe {
@@ -79,7 +140,7 @@ pub fn observe_app() {
 
             let wm_name = get_net_wm_name(display, window).unwrap_or_default();
 
-            if wm_name.is_empty() || wm_name.eq(MAIN_WINDOW_TITLE) {
+            if wm_name.is_empty() || is_own_window(display, window) {
                 continue;
             }
 
@@ -94,6 +155,144 @@ pub fn get_previous_window() -> Option<u64> {
     return PREVIOUS_WINDOW.lock().unwrap().clone();
 }
 
+// 获取窗口的 WM_CLASS，返回 (instance, class)
+fn get_wm_class(display: *mut Display, window: u64) -> Option<(String, String)> {
+    let mut actual_type: Atom = 0;
+    let mut actual_format: i32 = 0;
+    let mut nitems: u64 = 0;
+    let mut bytes_after: u64 = 0;
+    let mut prop: *mut u8 = std::ptr::null_mut();
+    let wm_class_atom = unsafe { XInternAtom(display, b"WM_CLASS\0".as_ptr() as _, xlib::False) };
+    let result = unsafe {
+        XGetWindowProperty(
+            display,
+            window,
+            wm_class_atom,
+            0,
+            1024,
+            xlib::False,
+            xlib::AnyPropertyType as _,
+            &mut actual_type,
+            &mut actual_format,
+            &mut nitems,
+            &mut bytes_after,
+            &mut prop,
+        )
+    };
+    if result != xlib::Success as i32 || prop.is_null() {
+        return None;
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(prop, nitems as usize) };
+    let mut parts = bytes
+        .split(|&b| b == 0)
+        .map(|part| String::from_utf8_lossy(part).into_owned());
+    let wm_class = (parts.next()?, parts.next().unwrap_or_default());
+    unsafe { XFree(prop as *mut _) };
+
+    Some(wm_class)
+}
+
+// 获取上一个窗口的进程名和类名
+pub fn get_previous_window_info() -> Option<TargetWindow> {
+    let window = get_previous_window()?;
+
+    unsafe {
+        let display = XOpenDisplay(std::ptr::null_mut());
+        if display.is_null() {
+            return None;
+        }
+
+        let wm_class = get_wm_class(display, window);
+        XCloseDisplay(display);
+
+        let (instance, class) = wm_class?;
+
+        Some(TargetWindow {
+            process_name: instance.to_lowercase(),
+            class_name: Some(class),
+        })
+    }
+}
+
+// 从 _NET_WM_ICON 中选出最接近 ICON_SIZE 的图标，像素为 ARGB
+fn pick_icon(data: &[u64]) -> Option<AppIcon> {
+    let mut best: Option<(usize, usize, usize)> = None;
+    let mut offset = 0;
+
+    while offset + 2 <= data.len() {
+        let (width, height) = (data[offset] as usize, data[offset + 1] as usize);
+        let end = offset + 2 + width * height;
+        if width == 0 || height == 0 || end > data.len() {
+            break;
+        }
+
+        let distance = |size: usize| size.abs_diff(ICON_SIZE);
+        if best.map_or(true, |(_, size, _)| distance(width) < distance(size)) {
+            best = Some((offset, width, height));
+        }
+
+        offset = end;
+    }
+
+    let (offset, width, height) = best?;
+    let pixels = data[offset + 2..offset + 2 + width * height]
+        .iter()
+        .flat_map(|&argb| {
+            let [b, g, r, a] = (argb as u32).to_le_bytes();
+
+            [r, g, b, a]
+        })
+        .collect();
+
+    Some(AppIcon::Rgba {
+        width: width as u32,
+        height: height as u32,
+        pixels,
+    })
+}
+
+// 获取上一个窗口的图标
+pub fn get_previous_window_icon() -> Option<AppIcon> {
+    let window = get_previous_window()?;
+
+    unsafe {
+        let display = XOpenDisplay(std::ptr::null_mut());
+        if display.is_null() {
+            return None;
+        }
+
+        let data = get_cardinals(display, window, b"_NET_WM_ICON\0");
+        XCloseDisplay(display);
+
+        pick_icon(&data?)
+    }
+}
+
+// 获取进程的应用图标，取自它的第一个顶层窗口
+pub fn get_process_icon(pid: u32) -> Option<AppIcon> {
+    unsafe {
+        let display = XOpenDisplay(std::ptr::null_mut());
+        if display.is_null() {
+            return None;
+        }
+
+        let root_window = XDefaultRootWindow(display);
+        let data = get_cardinals(display, root_window, b"_NET_CLIENT_LIST\0")
+            .unwrap_or_default()
+            .into_iter()
+            .find(|&window| {
+                get_cardinals(display, window, b"_NET_WM_PID\0")
+                    .and_then(|values| values.first().copied())
+                    .is_some_and(|value| value == pid as u64)
+            })
+            .and_then(|window| get_cardinals(display, window, b"_NET_WM_ICON\0"));
+        XCloseDisplay(display);
+
+        pick_icon(&data?)
+    }
+}
+
 // 聚焦上一个窗口
 fn focus_previous_window() {
     unsafe {
@@ -116,21 +315,231 @@ fn focus_previous_window() {
     }
 }
 
-// 粘贴
+// 组合键主键对应的按键
+fn key_of(key: ChordKey) -> Key {
+    match key {
+        ChordKey::Insert => Key::Insert,
+        ChordKey::Return => Key::Return,
+        ChordKey::Tab => Key::Tab,
+        ChordKey::Space => Key::Space,
+        ChordKey::Char(ch) => match ch {
+            'a' => Key::KeyA,
+            'b' => Key::KeyB,
+            'c' => Key::KeyC,
+            'd' => Key::KeyD,
+            'e' => Key::KeyE,
+            'f' => Key::KeyF,
+            'g' => Key::KeyG,
+            'h' => Key::KeyH,
+            'i' => Key::KeyI,
+            'j' => Key::KeyJ,
+            'k' => Key::KeyK,
+            'l' => Key::KeyL,
+            'm' => Key::KeyM,
+            'n' => Key::KeyN,
+            'o' => Key::KeyO,
+            'p' => Key::KeyP,
+            'q' => Key::KeyQ,
+            'r' => Key::KeyR,
+            's' => Key::KeyS,
+            't' => Key::KeyT,
+            'u' => Key::KeyU,
+            'v' => Key::KeyV,
+            'w' => Key::KeyW,
+            'x' => Key::KeyX,
+            'y' => Key::KeyY,
+            'z' => Key::KeyZ,
+            '0' => Key::Num0,
+            '1' => Key::Num1,
+            '2' => Key::Num2,
+            '3' => Key::Num3,
+            '4' => Key::Num4,
+            '5' => Key::Num5,
+            '6' => Key::Num6,
+            '7' => Key::Num7,
+            '8' => Key::Num8,
+            '9' => Key::Num9,
+            // 解析时只接受字母和数字
+            _ => Key::Unknown(0),
+        },
+    }
+}
+
+fn dispatch(event_type: &EventType) {
+    wait(20);
+
+    simulate(event_type).unwrap();
+}
+
+// 按下组合键再依次松开
+fn dispatch_chord(chord: &Chord) {
+    let modifiers: Vec<Key> = [
+        (chord.ctrl, Key::ControlLeft),
+        (chord.shift, Key::ShiftLeft),
+        (chord.alt, Key::Alt),
+        (chord.meta, Key::MetaLeft),
+    ]
+    .into_iter()
+    .filter(|(pressed, _)| *pressed)
+    .map(|(_, key)| key)
+    .collect();
+    let key = key_of(chord.key);
+
+    for modifier in &modifiers {
+        dispatch(&EventType::KeyPress(*modifier));
+    }
+
+    dispatch(&EventType::KeyPress(key));
+    dispatch(&EventType::KeyRelease(key));
+
+    for modifier in modifiers.iter().rev() {
+        dispatch(&EventType::KeyRelease(*modifier));
+    }
+}
+
+// Wayland 下 X11 模拟的按键到不了其它应用，改用 Wayland 的方式
+fn press_chord(chord: &Chord) -> Result<(), String> {
+    if wayland::is_wayland() {
+        return wayland::dispatch_chord(chord);
+    }
+
+    dispatch_chord(chord);
+
+    Ok(())
+}
+
+// 只按下一个键的组合键
+fn single_key(key: ChordKey) -> Chord {
+    Chord {
+        ctrl: false,
+        shift: false,
+        alt: false,
+        meta: false,
+        key,
+    }
+}
+
+// 粘贴，不指定按键时按目标应用的规则或全局设置
 #[command]
-pub async fn paste() {
-    fn dispatch(event_type: &EventType) {
-        wait(20);
+pub async fn paste(keystroke: Option<PasteKeystroke>) -> Result<(), String> {
+    let chord = resolve_chord(keystroke)?;
 
-        simulate(event_type).unwrap();
+    focus_previous_window();
+
+    wait(paste_timing().delay);
+
+    press_chord(&chord)
+}
+
+// 找一个没有映射任何 keysym 的键码，用来临时映射要输入的字符
+unsafe fn find_spare_keycode(display: *mut Display) -> Option<u8> {
+    let mut min_keycode = 0;
+    let mut max_keycode = 0;
+    XDisplayKeycodes(display, &mut min_keycode, &mut max_keycode);
+
+    let count = max_keycode - min_keycode + 1;
+    let mut keysyms_per_keycode = 0;
+    let mapping = XGetKeyboardMapping(
+        display,
+        min_keycode as u8,
+        count,
+        &mut keysyms_per_keycode,
+    );
+    if mapping.is_null() {
+        return None;
+    }
+
+    let per_keycode = keysyms_per_keycode as usize;
+    let keysyms = std::slice::from_raw_parts(mapping, count as usize * per_keycode);
+    let spare = (0..count as usize)
+        .rev()
+        .find(|index| {
+            keysyms[index * per_keycode..(index + 1) * per_keycode]
+                .iter()
+                .all(|&keysym| keysym == 0)
+        })
+        .map(|index| (min_keycode as usize + index) as u8);
+
+    XFree(mapping as *mut _);
+
+    spare
+}
+
+unsafe fn tap_keycode(display: *mut Display, keycode: u32) {
+    XTestFakeKeyEvent(display, keycode, xlib::True, 0);
+    XTestFakeKeyEvent(display, keycode, xlib::False, 0);
+    XFlush(display);
+}
+
+// 逐字模拟键盘输入文本，不经过剪贴板
+#[command]
+pub async fn paste_as_keystrokes(text: String, delay_ms: u64) -> Result<(), String> {
+    focus_previous_window();
+
+    wait(paste_timing().delay);
+
+    unsafe {
+        let display = XOpenDisplay(std::ptr::null_mut());
+        if display.is_null() {
+            return Err("Could not open display".to_string());
+        }
+
+        let Some(spare) = find_spare_keycode(display) else {
+            XCloseDisplay(display);
+
+            return Err("No spare keycode to type with".to_string());
+        };
+
+        for ch in normalize_newlines(&text).chars() {
+            match ch {
+                '\n' => tap_keycode(display, XKeysymToKeycode(display, KEYSYM_RETURN) as u32),
+                '\t' => tap_keycode(display, XKeysymToKeycode(display, KEYSYM_TAB) as u32),
+                _ => {
+                    // 两个层级映射同一个字符，不受 Shift 等修饰键影响
+                    let keysym = match ch as KeySym {
+                        // Latin-1 字符的 keysym 与码点相同
+                        code @ (0x20..=0x7e | 0xa0..=0xff) => code,
+                        code => KEYSYM_UNICODE_OFFSET + code,
+                    };
+                    let mut keysyms = [keysym, keysym];
+
+                    XChangeKeyboardMapping(display, spare as i32, 2, keysyms.as_mut_ptr(), 1);
+                    XSync(display, xlib::False);
+
+                    tap_keycode(display, spare as u32);
+                }
+            }
+
+            wait(delay_ms);
+        }
+
+        // 还原临时映射的键码
+        let mut keysyms: [KeySym; 2] = [0, 0];
+        XChangeKeyboardMapping(display, spare as i32, 2, keysyms.as_mut_ptr(), 1);
+        XSync(display, xlib::False);
+        XCloseDisplay(display);
     }
 
+    Ok(())
+}
+
+// 粘贴到浏览器地址栏并回车
+pub async fn paste_into_address_bar() {
+    let chord = configured_chord();
+
     focus_previous_window();
 
-    wait(100);
+    wait(paste_timing().delay);
 
-    dispatch(&EventType::KeyPress(Key::ShiftLeft));
-    dispatch(&EventType::KeyPress(Key::Insert));
-    dispatch(&EventType::KeyRelease(Key::Insert));
-    dispatch(&EventType::KeyRelease(Key::ShiftLeft));
+    let address_bar = Chord {
+        ctrl: true,
+        ..single_key(ChordKey::Char('l'))
+    };
+
+    for chord in [address_bar, chord, single_key(ChordKey::Return)] {
+        if let Err(error) = press_chord(&chord) {
+            log::error!("Failed to paste into the address bar: {}", error);
+            return;
+        }
+    }
 }
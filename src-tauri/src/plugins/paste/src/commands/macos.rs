@@ -1,18 +1,65 @@
 #![allow(deprecated)]
 use cocoa::base::{id, nil};
-use cocoa::foundation::{NSAutoreleasePool, NSString};
+use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize, NSString};
 use objc::declare::ClassDecl;
-use objc::runtime::{Class, Object, Sel};
+use objc::runtime::{Class, Object, Sel, BOOL};
 use objc::{msg_send, sel, sel_impl};
-use std::ffi::CStr;
-use std::process::Command;
+use std::ffi::{c_void, CStr};
 use std::sync::Mutex;
 use std::thread;
+use std::time::Duration;
 use tauri::{command, AppHandle, Runtime, WebviewWindow};
-use tauri_plugin_eco_window::{set_macos_panel, MacOSPanelStatus, MAIN_WINDOW_TITLE};
+use super::{normalize_newlines, AppIcon, TargetWindow};
+use crate::keystroke::{configured_chord, resolve_chord, Chord, ChordKey, PasteKeystroke};
+use crate::timing::paste_timing;
+use tauri_plugin_eco_window::{set_macos_panel, MacOSPanelStatus};
 
 static PREVIOUS_WINDOW: Mutex<Option<i32>> = Mutex::new(None);
 
+// kCGHIDEventTap，事件从硬件层注入
+const HID_EVENT_TAP: u32 = 0;
+
+// 应用图标的边长（点）
+const ICON_SIZE: f64 = 32.0;
+
+// NSCompositingOperationSourceOver
+const COMPOSITE_SOURCE_OVER: u64 = 2;
+
+// NSBitmapImageFileTypePNG
+const PNG_FILE_TYPE: u64 = 4;
+
+// 回车、制表、空格和 Help 键的虚拟键码
+const KEY_CODE_RETURN: u16 = 36;
+const KEY_CODE_TAB: u16 = 48;
+const KEY_CODE_SPACE: u16 = 49;
+const KEY_CODE_HELP: u16 = 114;
+
+// CGEventFlags 中的修饰键
+const FLAG_SHIFT: u64 = 0x0002_0000;
+const FLAG_CONTROL: u64 = 0x0004_0000;
+const FLAG_OPTION: u64 = 0x0008_0000;
+const FLAG_COMMAND: u64 = 0x0010_0000;
+
+// NSApplicationActivateIgnoringOtherApps
+const ACTIVATE_IGNORING_OTHER_APPS: u64 = 1 << 1;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventCreateKeyboardEvent(
+        source: *const c_void,
+        virtual_key: u16,
+        key_down: bool,
+    ) -> *mut c_void;
+    fn CGEventKeyboardSetUnicodeString(event: *mut c_void, length: usize, string: *const u16);
+    fn CGEventSetFlags(event: *mut c_void, flags: u64);
+    fn CGEventPost(tap: u32, event: *mut c_void);
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+}
+
 extern "C" fn application_did_activate(_self: &Object, _cmd: Sel, notification: id) {
     unsafe {
         let ns_app_key = NSString::alloc(nil).init_str("NSWorkspaceApplicationKey");
@@ -27,17 +74,13 @@ extern "C" fn application_did_activate(_self: &Object, _cmd: Sel, notification:
             return;
         }
 
-        let localized_name: id = msg_send![app, localizedName];
-        let name_str: *const i8 = msg_send![localized_name, UTF8String];
-        let name_cstr = CStr::from_ptr(name_str);
-        let name = name_cstr.to_str().unwrap_or("Unknown").to_string();
+        let process_id: i32 = msg_send![app, processIdentifier];
 
-        if name == MAIN_WINDOW_TITLE {
+        // 按进程 id 排除 EcoPaste 自己，不受应用名称本地化的影响
+        if process_id as u32 == std::process::id() {
             return;
         }
 
-        let process_id: i32 = msg_send![app, processIdentifier];
-
         let mut previous_window = PREVIOUS_WINDOW.lock().unwrap();
         let _ = previous_window.insert(process_id);
     }
@@ -79,15 +122,281 @@ pub fn get_previous_window() -> Option<i32> {
     return PREVIOUS_WINDOW.lock().unwrap().clone();
 }
 
-// 粘贴
+// 获取上一个窗口所属应用的名称
+pub fn get_previous_window_info() -> Option<TargetWindow> {
+    let process_id = get_previous_window()?;
+
+    unsafe {
+        let app: id = msg_send![
+            Class::get("NSRunningApplication").unwrap(),
+            runningApplicationWithProcessIdentifier: process_id
+        ];
+        if app == nil {
+            return None;
+        }
+
+        let localized_name: id = msg_send![app, localizedName];
+        if localized_name == nil {
+            return None;
+        }
+
+        let name_str: *const i8 = msg_send![localized_name, UTF8String];
+        let name = CStr::from_ptr(name_str).to_string_lossy().to_lowercase();
+
+        Some(TargetWindow {
+            process_name: name,
+            class_name: None,
+        })
+    }
+}
+
+// 获取上一个应用的图标
+pub fn get_previous_window_icon() -> Option<AppIcon> {
+    get_process_icon(get_previous_window()? as u32)
+}
+
+// 获取进程的应用图标，缩放到 ICON_SIZE 后编码为 PNG
+pub fn get_process_icon(pid: u32) -> Option<AppIcon> {
+    let process_id = pid as i32;
+
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+
+        let app: id = msg_send![
+            Class::get("NSRunningApplication").unwrap(),
+            runningApplicationWithProcessIdentifier: process_id
+        ];
+        let icon: id = if app == nil {
+            nil
+        } else {
+            msg_send![app, icon]
+        };
+
+        let png = if icon == nil {
+            None
+        } else {
+            let size = NSSize::new(ICON_SIZE, ICON_SIZE);
+            let rect = NSRect::new(NSPoint::new(0.0, 0.0), size);
+
+            let image: id = msg_send![Class::get("NSImage").unwrap(), alloc];
+            let image: id = msg_send![image, initWithSize: size];
+            let _: () = msg_send![image, lockFocus];
+            let _: () = msg_send![icon,
+                drawInRect: rect
+                fromRect: NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(0.0, 0.0))
+                operation: COMPOSITE_SOURCE_OVER
+                fraction: 1.0f64
+            ];
+            let _: () = msg_send![image, unlockFocus];
+
+            let tiff: id = msg_send![image, TIFFRepresentation];
+            let rep: id =
+                msg_send![Class::get("NSBitmapImageRep").unwrap(), imageRepWithData: tiff];
+            let properties: id = msg_send![Class::get("NSDictionary").unwrap(), dictionary];
+            let data: id = if rep == nil {
+                nil
+            } else {
+                msg_send![rep, representationUsingType: PNG_FILE_TYPE properties: properties]
+            };
+
+            let png = if data == nil {
+                None
+            } else {
+                let bytes: *const u8 = msg_send![data, bytes];
+                let length: usize = msg_send![data, length];
+
+                Some(std::slice::from_raw_parts(bytes, length).to_vec())
+            };
+
+            let _: () = msg_send![image, release];
+
+            png
+        };
+
+        pool.drain();
+
+        png.map(AppIcon::Png)
+    }
+}
+
+// 激活上一个应用，把焦点还给它
+fn focus_previous_window() {
+    let Some(process_id) = get_previous_window() else {
+        return;
+    };
+
+    unsafe {
+        let app: id = msg_send![
+            Class::get("NSRunningApplication").unwrap(),
+            runningApplicationWithProcessIdentifier: process_id
+        ];
+        if app == nil {
+            return;
+        }
+
+        let _: BOOL = msg_send![app, activateWithOptions: ACTIVATE_IGNORING_OTHER_APPS];
+    }
+}
+
+// 字母和数字在 ANSI 键盘上的虚拟键码
+fn char_key_code(ch: char) -> Option<u16> {
+    Some(match ch {
+        'a' => 0,
+        's' => 1,
+        'd' => 2,
+        'f' => 3,
+        'h' => 4,
+        'g' => 5,
+        'z' => 6,
+        'x' => 7,
+        'c' => 8,
+        'v' => 9,
+        'b' => 11,
+        'q' => 12,
+        'w' => 13,
+        'e' => 14,
+        'r' => 15,
+        'y' => 16,
+        't' => 17,
+        '1' => 18,
+        '2' => 19,
+        '3' => 20,
+        '4' => 21,
+        '6' => 22,
+        '5' => 23,
+        '9' => 25,
+        '7' => 26,
+        '8' => 28,
+        '0' => 29,
+        'o' => 31,
+        'u' => 32,
+        'i' => 34,
+        'p' => 35,
+        'l' => 37,
+        'j' => 38,
+        'k' => 40,
+        'n' => 45,
+        'm' => 46,
+        _ => return None,
+    })
+}
+
+// 组合键主键的虚拟键码，Insert 对应 Help 键
+fn key_code(key: ChordKey) -> Option<u16> {
+    match key {
+        ChordKey::Char(ch) => char_key_code(ch),
+        ChordKey::Insert => Some(KEY_CODE_HELP),
+        ChordKey::Return => Some(KEY_CODE_RETURN),
+        ChordKey::Tab => Some(KEY_CODE_TAB),
+        ChordKey::Space => Some(KEY_CODE_SPACE),
+    }
+}
+
+// 按下并松开一个键，指定字符时输入该字符而不是键码对应的字符
+fn post_key(virtual_key: u16, flags: u64, text: Option<&[u16]>) {
+    unsafe {
+        for key_down in [true, false] {
+            let event = CGEventCreateKeyboardEvent(std::ptr::null(), virtual_key, key_down);
+            if event.is_null() {
+                return;
+            }
+
+            // 显式设置修饰键，不受实际按住的按键影响
+            CGEventSetFlags(event, flags);
+
+            if let Some(text) = text {
+                CGEventKeyboardSetUnicodeString(event, text.len(), text.as_ptr());
+            }
+
+            CGEventPost(HID_EVENT_TAP, event);
+            CFRelease(event);
+        }
+    }
+}
+
+// 按下组合键，修饰键以事件标志的形式附带
+fn post_chord(chord: &Chord) -> Result<(), String> {
+    let key = key_code(chord.key).ok_or("The chord key has no key code on macOS")?;
+
+    let flags = [
+        (chord.meta, FLAG_COMMAND),
+        (chord.ctrl, FLAG_CONTROL),
+        (chord.shift, FLAG_SHIFT),
+        (chord.alt, FLAG_OPTION),
+    ]
+    .into_iter()
+    .filter(|(pressed, _)| *pressed)
+    .fold(0, |flags, (_, flag)| flags | flag);
+
+    post_key(key, flags, None);
+
+    Ok(())
+}
+
+// 让出焦点并激活上一个应用
+fn restore_focus<R: Runtime>(app_handle: &AppHandle<R>, window: &WebviewWindow<R>) {
+    set_macos_panel(app_handle, window, MacOSPanelStatus::Resign);
+
+    focus_previous_window();
+
+    thread::sleep(Duration::from_millis(paste_timing().delay));
+}
+
+// 粘贴，不指定按键时按目标应用的规则或全局设置
+#[command]
+pub async fn paste<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: WebviewWindow<R>,
+    keystroke: Option<PasteKeystroke>,
+) -> Result<(), String> {
+    let chord = resolve_chord(keystroke)?;
+
+    restore_focus(&app_handle, &window);
+
+    post_chord(&chord)
+}
+
+// 逐字模拟键盘输入文本，不经过剪贴板
 #[command]
-pub async fn paste<R: Runtime>(app_handle: AppHandle<R>, window: WebviewWindow<R>) {
-    set_macos_panel(&app_handle, &window, MacOSPanelStatus::Resign);
+pub async fn paste_as_keystrokes<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: WebviewWindow<R>,
+    text: String,
+    delay_ms: u64,
+) -> Result<(), String> {
+    restore_focus(&app_handle, &window);
+
+    for ch in normalize_newlines(&text).chars() {
+        match ch {
+            '\n' => post_key(KEY_CODE_RETURN, 0, None),
+            '\t' => post_key(KEY_CODE_TAB, 0, None),
+            _ => {
+                let mut buffer = [0u16; 2];
+
+                post_key(0, 0, Some(&*ch.encode_utf16(&mut buffer)));
+            }
+        }
+
+        thread::sleep(Duration::from_millis(delay_ms));
+    }
+
+    Ok(())
+}
+
+// 粘贴到浏览器地址栏并回车
+pub async fn paste_into_address_bar<R: Runtime>(app_handle: AppHandle<R>, window: WebviewWindow<R>) {
+    let chord = configured_chord();
+
+    restore_focus(&app_handle, &window);
+
+    // Command+L 在主流浏览器中都会聚焦地址栏
+    post_key(char_key_code('l').unwrap_or_default(), FLAG_COMMAND, None);
+
+    thread::sleep(Duration::from_millis(50));
+
+    let _ = post_chord(&chord);
 
-    let script = r#"tell application "System Events" to keystroke "v" using command down"#;
+    thread::sleep(Duration::from_millis(50));
 
-    Command::new("osascript")
-        .args(["-e", script])
-        .output()
-        .expect("failed to execute process");
+    post_key(KEY_CODE_RETURN, 0, None);
 }
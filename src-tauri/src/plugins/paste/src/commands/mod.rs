@@ -1,3 +1,6 @@
+use serde::Serialize;
+use tauri::{AppHandle, Runtime};
+
 #[cfg(target_os = "macos")]
 mod macos;
 
@@ -7,6 +10,9 @@ mod windows;
 #[cfg(target_os = "linux")]
 mod linux;
 
+#[cfg(target_os = "linux")]
+mod wayland;
+
 #[cfg(target_os = "macos")]
 pub use macos::*;
 
@@ -16,9 +22,101 @@ pub use windows::*;
 #[cfg(target_os = "linux")]
 pub use linux::*;
 
+// 粘贴目标窗口的信息
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetWindow {
+    // 进程名（小写，不含 .exe）
+    pub process_name: String,
+    // 获得焦点的控件（或窗口）的类名
+    pub class_name: Option<String>,
+}
+
+// 应用图标，macOS 直接得到 PNG，其它平台为像素数据
+pub enum AppIcon {
+    Png(Vec<u8>),
+    Rgba {
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    },
+}
+
+// 模拟输入时统一使用 \n 换行
+pub(crate) fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
 #[cfg(not(target_os = "macos"))]
 pub fn wait(millis: u64) {
     use std::{thread, time};
 
     thread::sleep(time::Duration::from_millis(millis));
 }
+
+// 供后端其他模块调用的粘贴，剪贴板内容需提前写入
+pub async fn trigger_paste<R: Runtime>(app_handle: &AppHandle<R>) {
+    #[cfg(target_os = "macos")]
+    {
+        use tauri::Manager;
+        use tauri_plugin_eco_window::MAIN_WINDOW_LABEL;
+
+        if let Some(window) = app_handle.get_webview_window(MAIN_WINDOW_LABEL) {
+            let _ = paste(app_handle.clone(), window, None).await;
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = app_handle;
+
+        let _ = paste(None).await;
+    }
+}
+
+// 供后端其他模块调用：逐字输入文本，用于规则中设置了逐字输入的应用
+pub async fn trigger_typing<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    text: &str,
+) -> Result<(), String> {
+    let text = text.to_string();
+
+    #[cfg(target_os = "macos")]
+    {
+        use tauri::Manager;
+        use tauri_plugin_eco_window::MAIN_WINDOW_LABEL;
+
+        let window = app_handle
+            .get_webview_window(MAIN_WINDOW_LABEL)
+            .ok_or_else(|| "Main window not found".to_string())?;
+
+        paste_as_keystrokes(app_handle.clone(), window, text, crate::TYPING_INTERVAL).await
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = app_handle;
+
+        paste_as_keystrokes(text, crate::TYPING_INTERVAL).await
+    }
+}
+
+// 供后端其他模块调用：粘贴到浏览器地址栏并回车
+pub async fn trigger_paste_and_go<R: Runtime>(app_handle: &AppHandle<R>) {
+    #[cfg(target_os = "macos")]
+    {
+        use tauri::Manager;
+        use tauri_plugin_eco_window::MAIN_WINDOW_LABEL;
+
+        if let Some(window) = app_handle.get_webview_window(MAIN_WINDOW_LABEL) {
+            paste_into_address_bar(app_handle.clone(), window).await;
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = app_handle;
+
+        paste_into_address_bar().await;
+    }
+}
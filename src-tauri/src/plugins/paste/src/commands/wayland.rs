@@ -0,0 +1,223 @@
+use crate::keystroke::{Chord, ChordKey};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::process::Command;
+use std::time::Instant;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{delegate_noop, Connection, Dispatch, QueueHandle};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
+
+// 交给合成器编译的键盘布局，按键使用 evdev 键码
+const KEYMAP: &str = "xkb_keymap {
+    xkb_keycodes { include \"evdev+aliases(qwerty)\" };
+    xkb_types { include \"complete\" };
+    xkb_compat { include \"complete\" };
+    xkb_symbols { include \"pc+us+inet(evdev)\" };
+};\0";
+
+// wl_keyboard 的 keymap_format.xkb_v1
+const KEYMAP_FORMAT_XKB_V1: u32 = 1;
+
+// evdev 中修饰键的键码
+const KEY_LEFTCTRL: u32 = 29;
+const KEY_LEFTSHIFT: u32 = 42;
+const KEY_LEFTALT: u32 = 56;
+const KEY_LEFTMETA: u32 = 125;
+
+// 上面布局中修饰键的掩码
+const MASK_SHIFT: u32 = 1 << 0;
+const MASK_CONTROL: u32 = 1 << 2;
+const MASK_MOD1: u32 = 1 << 3;
+const MASK_MOD4: u32 = 1 << 6;
+
+struct State;
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(State: ignore WlSeat);
+delegate_noop!(State: ZwpVirtualKeyboardManagerV1);
+delegate_noop!(State: ZwpVirtualKeyboardV1);
+
+// 是否运行在 Wayland 会话中，此时无法通过 X11 模拟按键
+pub fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland")
+}
+
+// 组合键主键的 evdev 键码
+fn key_code(key: ChordKey) -> Option<u32> {
+    Some(match key {
+        ChordKey::Insert => 110,
+        ChordKey::Return => 28,
+        ChordKey::Tab => 15,
+        ChordKey::Space => 57,
+        ChordKey::Char(ch) => match ch {
+            '1'..='9' => ch as u32 - '1' as u32 + 2,
+            '0' => 11,
+            'q' => 16,
+            'w' => 17,
+            'e' => 18,
+            'r' => 19,
+            't' => 20,
+            'y' => 21,
+            'u' => 22,
+            'i' => 23,
+            'o' => 24,
+            'p' => 25,
+            'a' => 30,
+            's' => 31,
+            'd' => 32,
+            'f' => 33,
+            'g' => 34,
+            'h' => 35,
+            'j' => 36,
+            'k' => 37,
+            'l' => 38,
+            'z' => 44,
+            'x' => 45,
+            'c' => 46,
+            'v' => 47,
+            'b' => 48,
+            'n' => 49,
+            'm' => 50,
+            _ => return None,
+        },
+    })
+}
+
+// 组合键中按下的修饰键及其掩码
+fn modifiers(chord: &Chord) -> Vec<(u32, u32)> {
+    [
+        (chord.ctrl, KEY_LEFTCTRL, MASK_CONTROL),
+        (chord.shift, KEY_LEFTSHIFT, MASK_SHIFT),
+        (chord.alt, KEY_LEFTALT, MASK_MOD1),
+        (chord.meta, KEY_LEFTMETA, MASK_MOD4),
+    ]
+    .into_iter()
+    .filter(|(pressed, _, _)| *pressed)
+    .map(|(_, key, mask)| (key, mask))
+    .collect()
+}
+
+// 布局通过文件描述符传给合成器，文件在发送后即可删除
+fn keymap_file() -> Result<std::fs::File, String> {
+    let path = std::env::temp_dir().join(format!("ecopaste-keymap-{}", std::process::id()));
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(&path);
+
+    file.write_all(KEYMAP.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    Ok(file)
+}
+
+// 通过 virtual-keyboard-unstable-v1 协议按下组合键，wlroots 系和 KDE 支持
+fn virtual_keyboard_chord(chord: &Chord) -> Result<(), String> {
+    let key = key_code(chord.key).ok_or("The chord key has no evdev key code")?;
+
+    let connection = Connection::connect_to_env().map_err(|e| e.to_string())?;
+    let (globals, mut queue) =
+        registry_queue_init::<State>(&connection).map_err(|e| e.to_string())?;
+    let handle = queue.handle();
+
+    let seat: WlSeat = globals
+        .bind(&handle, 1..=1, ())
+        .map_err(|e| e.to_string())?;
+    let manager: ZwpVirtualKeyboardManagerV1 = globals
+        .bind(&handle, 1..=1, ())
+        .map_err(|_| "The compositor does not support virtual keyboards".to_string())?;
+
+    let keyboard = manager.create_virtual_keyboard(&seat, &handle, ());
+
+    let keymap = keymap_file()?;
+    keyboard.keymap(KEYMAP_FORMAT_XKB_V1, keymap.as_fd(), KEYMAP.len() as u32);
+
+    let started = Instant::now();
+    let time = || started.elapsed().as_millis() as u32;
+
+    let modifiers = modifiers(chord);
+    let mask = modifiers.iter().fold(0, |mask, (_, bit)| mask | bit);
+
+    for (modifier, _) in &modifiers {
+        keyboard.key(time(), *modifier, 1);
+    }
+    keyboard.modifiers(mask, 0, 0, 0);
+
+    keyboard.key(time(), key, 1);
+    keyboard.key(time(), key, 0);
+
+    for (modifier, _) in modifiers.iter().rev() {
+        keyboard.key(time(), *modifier, 0);
+    }
+    keyboard.modifiers(0, 0, 0, 0);
+
+    queue.roundtrip(&mut State).map_err(|e| e.to_string())?;
+
+    keyboard.destroy();
+    queue.roundtrip(&mut State).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// 通过 ydotool 按下组合键，需要 ydotoold 守护进程在运行，GNOME 上只能用这种方式
+fn ydotool_chord(chord: &Chord) -> Result<(), String> {
+    let key = key_code(chord.key).ok_or("The chord key has no evdev key code")?;
+    let modifiers = modifiers(chord);
+
+    let mut args: Vec<String> = modifiers
+        .iter()
+        .map(|(modifier, _)| format!("{}:1", modifier))
+        .collect();
+    args.push(format!("{}:1", key));
+    args.push(format!("{}:0", key));
+    args.extend(
+        modifiers
+            .iter()
+            .rev()
+            .map(|(modifier, _)| format!("{}:0", modifier)),
+    );
+
+    let status = Command::new("ydotool")
+        .arg("key")
+        .args(&args)
+        .status()
+        .map_err(|e| format!("Failed to run ydotool: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("ydotool exited with {}", status));
+    }
+
+    Ok(())
+}
+
+// 在 Wayland 下按下组合键，合成器不支持虚拟键盘时退回到 ydotool
+pub fn dispatch_chord(chord: &Chord) -> Result<(), String> {
+    virtual_keyboard_chord(chord).or_else(|error| {
+        log::warn!(
+            "Virtual keyboard unavailable, falling back to ydotool: {}",
+            error
+        );
+
+        ydotool_chord(chord)
+    })
+}
@@ -1,37 +1,41 @@
-use super::wait;
-use std::ffi::OsString;
+use super::{normalize_newlines, wait, AppIcon, TargetWindow};
+use crate::keystroke::{configured_chord, resolve_chord, Chord, ChordKey, PasteKeystroke};
+use crate::timing::paste_timing;
 use std::mem;
-use std::os::windows::ffi::OsStringExt;
 use std::ptr;
 use std::sync::Mutex;
 use tauri::command;
-use tauri_plugin_eco_window::MAIN_WINDOW_TITLE;
 use winapi::shared::minwindef::DWORD;
-use winapi::shared::windef::{HWINEVENTHOOK, HWND};
+use winapi::shared::windef::{HICON, HWINEVENTHOOK, HWND};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::{GetCurrentProcessId, OpenProcess};
+use winapi::um::shellapi::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
+use winapi::um::winbase::QueryFullProcessImageNameW;
+use winapi::um::wingdi::{
+    DeleteObject, GetDIBits, GetObjectW, BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+    DIB_RGB_COLORS,
+};
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
 use winapi::um::winuser::{
-    GetWindowTextLengthW, GetWindowTextW, SendInput, SetForegroundWindow, SetWinEventHook,
-    INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP,
+    DestroyIcon, GetClassNameW, GetDC, GetForegroundWindow, GetGUIThreadInfo, GetIconInfo,
+    GetWindowThreadProcessId, GUITHREADINFO, ICONINFO, ReleaseDC,
+    SendInput, SetForegroundWindow, SetWinEventHook,
+    INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
     EVENT_SYSTEM_FOREGROUND, WINEVENT_OUTOFCONTEXT,
-    VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT, VK_INSERT,
+    VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT, VK_INSERT, VK_RETURN, VK_SPACE, VK_TAB,
 };
 
-static PREVIOUS_WINDOW: Mutex<Option<isize>> = Mutex::new(None);
-
-// 获取窗口标题
-unsafe fn get_window_title(hwnd: HWND) -> String {
-    let length = GetWindowTextLengthW(hwnd);
-
-    if length == 0 {
-        return String::new();
-    }
+// 字母键 L 的虚拟键码
+const VK_L: u16 = 0x4C;
 
-    let mut buffer: Vec<u16> = vec![0; (length + 1) as usize];
+static PREVIOUS_WINDOW: Mutex<Option<isize>> = Mutex::new(None);
 
-    GetWindowTextW(hwnd, buffer.as_mut_ptr(), length + 1);
+// 窗口是否属于 EcoPaste 自己的进程，按进程 id 判断，不受窗口标题的影响
+unsafe fn is_own_window(hwnd: HWND) -> bool {
+    let mut pid: DWORD = 0;
+    GetWindowThreadProcessId(hwnd, &mut pid);
 
-    OsString::from_wide(&buffer[..length as usize])
-        .to_string_lossy()
-        .into_owned()
+    pid == GetCurrentProcessId()
 }
 
 // 定义事件钩子回调函数
@@ -45,9 +49,7 @@ unsafe extern "system" fn event_hook_callback(
     _dwms_event_time: DWORD,
 ) {
     if event == EVENT_SYSTEM_FOREGROUND {
-        let window_title = get_window_title(hwnd);
-
-        if window_title == MAIN_WINDOW_TITLE {
+        if hwnd.is_null() || is_own_window(hwnd) {
             return;
         }
 
@@ -82,20 +84,235 @@ pub fn get_previous_window() -> Option<isize> {
     return PREVIOUS_WINDOW.lock().unwrap().clone();
 }
 
-// 聚焦上一个窗口
-fn focus_previous_window() {
+// 替换上一个窗口，返回原来的值，供自检时临时指定粘贴目标
+pub fn replace_previous_window(hwnd: Option<isize>) -> Option<isize> {
+    let mut previous_window = PREVIOUS_WINDOW.lock().unwrap();
+
+    mem::replace(&mut *previous_window, hwnd)
+}
+
+// 获取窗口类名
+unsafe fn get_class_name(hwnd: HWND) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let length = GetClassNameW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+
+    if length == 0 {
+        return None;
+    }
+
+    Some(String::from_utf16_lossy(&buffer[..length as usize]))
+}
+
+// 获取进程的可执行文件路径
+unsafe fn get_pid_path(pid: DWORD) -> Option<Vec<u16>> {
+    let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+    if process.is_null() {
+        return None;
+    }
+
+    let mut buffer = [0u16; 260];
+    let mut length = buffer.len() as DWORD;
+    let ok = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut length);
+    CloseHandle(process);
+
+    if ok == 0 {
+        return None;
+    }
+
+    Some(buffer[..length as usize].to_vec())
+}
+
+// 获取窗口所属进程的可执行文件路径
+unsafe fn get_process_path(hwnd: HWND) -> Option<Vec<u16>> {
+    let mut pid: DWORD = 0;
+    GetWindowThreadProcessId(hwnd, &mut pid);
+
+    get_pid_path(pid)
+}
+
+// 获取窗口所属的进程名
+unsafe fn get_process_name(hwnd: HWND) -> Option<String> {
+    let path = String::from_utf16_lossy(&get_process_path(hwnd)?);
+    let name = path.rsplit('\\').next()?.to_lowercase();
+
+    Some(name.trim_end_matches(".exe").to_string())
+}
+
+// 读取图标的像素，转换为 RGBA
+unsafe fn icon_pixels(icon: HICON) -> Option<AppIcon> {
+    let mut info: ICONINFO = mem::zeroed();
+    if GetIconInfo(icon, &mut info) == 0 {
+        return None;
+    }
+
+    let mut bitmap: BITMAP = mem::zeroed();
+    let read = !info.hbmColor.is_null()
+        && GetObjectW(
+            info.hbmColor as _,
+            mem::size_of::<BITMAP>() as i32,
+            &mut bitmap as *mut BITMAP as _,
+        ) != 0;
+
+    let mut pixels = Vec::new();
+    if read {
+        let mut header: BITMAPINFO = mem::zeroed();
+        header.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as DWORD;
+        header.bmiHeader.biWidth = bitmap.bmWidth;
+        // 负数表示自上而下的行顺序
+        header.bmiHeader.biHeight = -bitmap.bmHeight;
+        header.bmiHeader.biPlanes = 1;
+        header.bmiHeader.biBitCount = 32;
+        header.bmiHeader.biCompression = BI_RGB;
+
+        pixels = vec![0u8; (bitmap.bmWidth * bitmap.bmHeight * 4) as usize];
+
+        let dc = GetDC(ptr::null_mut());
+        let lines = GetDIBits(
+            dc,
+            info.hbmColor,
+            0,
+            bitmap.bmHeight as u32,
+            pixels.as_mut_ptr() as _,
+            &mut header,
+            DIB_RGB_COLORS,
+        );
+        ReleaseDC(ptr::null_mut(), dc);
+
+        if lines == 0 {
+            pixels.clear();
+        }
+    }
+
+    if !info.hbmColor.is_null() {
+        DeleteObject(info.hbmColor as _);
+    }
+    if !info.hbmMask.is_null() {
+        DeleteObject(info.hbmMask as _);
+    }
+
+    if pixels.is_empty() {
+        return None;
+    }
+
+    // 没有透明通道的旧图标 alpha 全为 0
+    let opaque = pixels.chunks_exact(4).all(|pixel| pixel[3] == 0);
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+
+        if opaque {
+            pixel[3] = 255;
+        }
+    }
+
+    Some(AppIcon::Rgba {
+        width: bitmap.bmWidth as u32,
+        height: bitmap.bmHeight as u32,
+        pixels,
+    })
+}
+
+// 获取可执行文件的图标
+unsafe fn get_file_icon(mut path: Vec<u16>) -> Option<AppIcon> {
+    path.push(0);
+
+    let mut info: SHFILEINFOW = mem::zeroed();
+    let found = SHGetFileInfoW(
+        path.as_ptr(),
+        0,
+        &mut info,
+        mem::size_of::<SHFILEINFOW>() as u32,
+        SHGFI_ICON | SHGFI_LARGEICON,
+    );
+
+    if found == 0 || info.hIcon.is_null() {
+        return None;
+    }
+
+    let icon = icon_pixels(info.hIcon);
+    DestroyIcon(info.hIcon);
+
+    icon
+}
+
+// 获取上一个窗口所属应用的图标
+pub fn get_previous_window_icon() -> Option<AppIcon> {
     unsafe {
-        let hwnd = match get_previous_window() {
-            Some(hwnd) => hwnd as HWND,
-            None => return,
-        };
+        let hwnd = get_previous_window()? as HWND;
+        if hwnd.is_null() {
+            return None;
+        }
+
+        get_file_icon(get_process_path(hwnd)?)
+    }
+}
+
+// 获取进程的应用图标
+pub fn get_process_icon(pid: u32) -> Option<AppIcon> {
+    unsafe { get_file_icon(get_pid_path(pid)?) }
+}
 
+// 获取上一个窗口的进程名和获得焦点的控件类名
+pub fn get_previous_window_info() -> Option<TargetWindow> {
+    unsafe {
+        let hwnd = get_previous_window()? as HWND;
         if hwnd.is_null() {
-            return;
+            return None;
+        }
+
+        let thread_id = GetWindowThreadProcessId(hwnd, ptr::null_mut());
+        let mut info: GUITHREADINFO = mem::zeroed();
+        info.cbSize = mem::size_of::<GUITHREADINFO>() as DWORD;
+
+        // 优先使用获得焦点的控件，拿不到时退回到顶层窗口
+        let focus = if GetGUIThreadInfo(thread_id, &mut info) != 0 && !info.hwndFocus.is_null() {
+            info.hwndFocus
+        } else {
+            hwnd
+        };
+
+        Some(TargetWindow {
+            process_name: get_process_name(hwnd)?,
+            class_name: get_class_name(focus),
+        })
+    }
+}
+
+// 聚焦上一个窗口并等待，焦点没有切换过去时（慢速应用、远程桌面等）按退避时间重试
+fn focus_previous_window() -> Result<(), String> {
+    let timing = paste_timing();
+
+    let hwnd = match get_previous_window() {
+        Some(hwnd) if hwnd != 0 => hwnd as HWND,
+        _ => {
+            wait(timing.delay);
+
+            return Ok(());
         }
+    };
 
-        SetForegroundWindow(hwnd);
+    for attempt in 0..=timing.retries {
+        unsafe {
+            SetForegroundWindow(hwnd);
+        }
+
+        wait(if attempt == 0 {
+            timing.delay
+        } else {
+            timing.backoff(attempt)
+        });
+
+        if unsafe { GetForegroundWindow() } == hwnd {
+            return Ok(());
+        }
+
+        log::debug!(
+            "Focus did not land on the target window, attempt {}",
+            attempt + 1
+        );
     }
+
+    Err("Could not focus the target window".to_string())
 }
 
 fn make_key_input(vk: u16, flags: u32) -> INPUT {
@@ -114,29 +331,124 @@ fn make_key_input(vk: u16, flags: u32) -> INPUT {
     input
 }
 
-// 粘贴
+// 组合键主键的虚拟键码
+fn key_code(key: ChordKey) -> u16 {
+    match key {
+        // 字母和数字的虚拟键码与大写的 ASCII 码相同
+        ChordKey::Char(ch) => ch.to_ascii_uppercase() as u16,
+        ChordKey::Insert => VK_INSERT as u16,
+        ChordKey::Return => VK_RETURN as u16,
+        ChordKey::Tab => VK_TAB as u16,
+        ChordKey::Space => VK_SPACE as u16,
+    }
+}
+
+// 以 UTF-16 码元输入一个字符，与键盘布局无关
+fn unicode_inputs(ch: char) -> Vec<INPUT> {
+    let mut buffer = [0u16; 2];
+
+    ch.encode_utf16(&mut buffer)
+        .iter()
+        .flat_map(|&unit| {
+            let mut down = make_key_input(0, KEYEVENTF_UNICODE);
+            let mut up = make_key_input(0, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP);
+
+            unsafe {
+                down.u.ki_mut().wScan = unit;
+                up.u.ki_mut().wScan = unit;
+            }
+
+            [down, up]
+        })
+        .collect()
+}
+
+// 按下组合键再依次松开
+fn chord_inputs(chord: &Chord) -> Vec<INPUT> {
+    let modifiers: Vec<u16> = [
+        (chord.ctrl, VK_CONTROL),
+        (chord.shift, VK_SHIFT),
+        (chord.alt, VK_MENU),
+        (chord.meta, VK_LWIN),
+    ]
+    .iter()
+    .filter(|(pressed, _)| *pressed)
+    .map(|(_, vk)| *vk as u16)
+    .collect();
+    let key = key_code(chord.key);
+
+    let mut inputs: Vec<INPUT> = modifiers.iter().map(|&vk| make_key_input(vk, 0)).collect();
+    inputs.push(make_key_input(key, 0));
+    inputs.push(make_key_input(key, KEYEVENTF_KEYUP));
+    inputs.extend(
+        modifiers
+            .iter()
+            .rev()
+            .map(|&vk| make_key_input(vk, KEYEVENTF_KEYUP)),
+    );
+
+    inputs
+}
+
+// 粘贴，不指定按键时按目标应用的规则或全局设置
 #[command]
-pub async fn paste() {
-    focus_previous_window();
+pub async fn paste(keystroke: Option<PasteKeystroke>) -> Result<(), String> {
+    let chord = resolve_chord(keystroke)?;
 
-    wait(100);
+    focus_previous_window()?;
 
     // Release any modifier keys that may still be physically held down,
-    // then send Shift+Insert for paste, all as one atomic SendInput call.
-    let inputs = vec![
-        // Release modifiers that could interfere
+    // then send the paste chord, all as one atomic SendInput call.
+    let mut inputs = vec![
         make_key_input(VK_CONTROL as u16, KEYEVENTF_KEYUP),
         make_key_input(VK_SHIFT as u16, KEYEVENTF_KEYUP),
         make_key_input(VK_MENU as u16, KEYEVENTF_KEYUP),
         make_key_input(VK_LWIN as u16, KEYEVENTF_KEYUP),
         make_key_input(VK_RWIN as u16, KEYEVENTF_KEYUP),
-        // Shift+Insert (paste)
-        make_key_input(VK_SHIFT as u16, 0),
-        make_key_input(VK_INSERT as u16, 0),
-        make_key_input(VK_INSERT as u16, KEYEVENTF_KEYUP),
-        make_key_input(VK_SHIFT as u16, KEYEVENTF_KEYUP),
     ];
+    inputs.extend(chord_inputs(&chord));
+
+    send_inputs(&inputs);
+
+    Ok(())
+}
+
+// 逐字模拟键盘输入文本，不经过剪贴板
+#[command]
+pub async fn paste_as_keystrokes(text: String, delay_ms: u64) -> Result<(), String> {
+    focus_previous_window()?;
+
+    send_inputs(&[
+        make_key_input(VK_CONTROL as u16, KEYEVENTF_KEYUP),
+        make_key_input(VK_SHIFT as u16, KEYEVENTF_KEYUP),
+        make_key_input(VK_MENU as u16, KEYEVENTF_KEYUP),
+        make_key_input(VK_LWIN as u16, KEYEVENTF_KEYUP),
+        make_key_input(VK_RWIN as u16, KEYEVENTF_KEYUP),
+    ]);
+
+    for ch in normalize_newlines(&text).chars() {
+        // 换行和制表符按实际的按键输入，部分应用不接受对应的字符
+        let inputs = match ch {
+            '\n' => vec![
+                make_key_input(VK_RETURN as u16, 0),
+                make_key_input(VK_RETURN as u16, KEYEVENTF_KEYUP),
+            ],
+            '\t' => vec![
+                make_key_input(VK_TAB as u16, 0),
+                make_key_input(VK_TAB as u16, KEYEVENTF_KEYUP),
+            ],
+            _ => unicode_inputs(ch),
+        };
+
+        send_inputs(&inputs);
+
+        wait(delay_ms);
+    }
 
+    Ok(())
+}
+
+fn send_inputs(inputs: &[INPUT]) {
     unsafe {
         SendInput(
             inputs.len() as u32,
@@ -145,3 +457,32 @@ pub async fn paste() {
         );
     }
 }
+
+// 粘贴到浏览器地址栏并回车
+pub async fn paste_into_address_bar() {
+    let chord = configured_chord();
+
+    if let Err(error) = focus_previous_window() {
+        log::error!("Failed to paste into the address bar: {}", error);
+        return;
+    }
+
+    // Ctrl+L focuses the address bar in every mainstream browser
+    send_inputs(&[
+        make_key_input(VK_CONTROL as u16, 0),
+        make_key_input(VK_L, 0),
+        make_key_input(VK_L, KEYEVENTF_KEYUP),
+        make_key_input(VK_CONTROL as u16, KEYEVENTF_KEYUP),
+    ]);
+
+    wait(50);
+
+    send_inputs(&chord_inputs(&chord));
+
+    wait(50);
+
+    send_inputs(&[
+        make_key_input(VK_RETURN as u16, 0),
+        make_key_input(VK_RETURN as u16, KEYEVENTF_KEYUP),
+    ]);
+}
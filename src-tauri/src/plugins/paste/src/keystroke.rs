@@ -0,0 +1,150 @@
+use crate::commands::get_previous_window_info;
+use crate::rules::find_rule;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::command;
+
+// 粘贴时模拟的按键
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PasteKeystroke {
+    // Ctrl+V，macOS 上为 Command+V
+    CtrlV,
+    // Shift+Insert，macOS 没有 Insert 键，按 Command+V 处理
+    ShiftInsert,
+    // 自定义组合键，例如 "ctrl+shift+v"、"cmd+v"
+    Custom { chord: String },
+}
+
+// 组合键中的主键
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChordKey {
+    // 字母或数字，统一为小写
+    Char(char),
+    Insert,
+    Return,
+    Tab,
+    Space,
+}
+
+// 解析后的组合键
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Chord {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    // Windows 键、Command 键或 Super 键
+    pub meta: bool,
+    pub key: ChordKey,
+}
+
+const SHIFT_INSERT: Chord = Chord {
+    ctrl: false,
+    shift: true,
+    alt: false,
+    meta: false,
+    key: ChordKey::Insert,
+};
+
+const CTRL_V: Chord = Chord {
+    ctrl: !cfg!(target_os = "macos"),
+    shift: false,
+    alt: false,
+    meta: cfg!(target_os = "macos"),
+    key: ChordKey::Char('v'),
+};
+
+// 全局设置的粘贴按键，为空时使用 Shift+Insert
+static DEFAULT_KEYSTROKE: Mutex<Option<PasteKeystroke>> = Mutex::new(None);
+
+impl Chord {
+    // 解析 "ctrl+shift+v" 形式的组合键，不区分大小写
+    pub fn parse(chord: &str) -> Result<Chord, String> {
+        let mut parsed = Chord {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            meta: false,
+            key: ChordKey::Space,
+        };
+        let mut key = None;
+
+        for part in chord.split('+').map(|part| part.trim().to_lowercase()) {
+            match part.as_str() {
+                "ctrl" | "control" => parsed.ctrl = true,
+                "shift" => parsed.shift = true,
+                "alt" | "option" => parsed.alt = true,
+                "meta" | "cmd" | "command" | "win" | "super" => parsed.meta = true,
+                _ if key.is_some() => {
+                    return Err(format!("Chord {} has more than one key", chord));
+                }
+                "insert" | "ins" => key = Some(ChordKey::Insert),
+                "enter" | "return" => key = Some(ChordKey::Return),
+                "tab" => key = Some(ChordKey::Tab),
+                "space" => key = Some(ChordKey::Space),
+                _ => {
+                    let mut chars = part.chars();
+
+                    match (chars.next(), chars.next()) {
+                        (Some(ch), None) if ch.is_ascii_alphanumeric() => {
+                            key = Some(ChordKey::Char(ch))
+                        }
+                        _ => return Err(format!("Unknown key {} in chord {}", part, chord)),
+                    }
+                }
+            }
+        }
+
+        parsed.key = key.ok_or_else(|| format!("Chord {} has no key", chord))?;
+
+        Ok(parsed)
+    }
+}
+
+impl PasteKeystroke {
+    pub fn chord(&self) -> Result<Chord, String> {
+        match self {
+            PasteKeystroke::CtrlV => Ok(CTRL_V),
+            PasteKeystroke::ShiftInsert if cfg!(target_os = "macos") => Ok(CTRL_V),
+            PasteKeystroke::ShiftInsert => Ok(SHIFT_INSERT),
+            PasteKeystroke::Custom { chord } => Chord::parse(chord),
+        }
+    }
+}
+
+// 目标应用的规则或全局设置中的按键，都没有时为 Shift+Insert（macOS 为 Command+V）
+pub fn configured_chord() -> Chord {
+    let keystroke = get_previous_window_info()
+        .and_then(|window| find_rule(&window.process_name))
+        .and_then(|rule| rule.keystroke)
+        .or_else(|| DEFAULT_KEYSTROKE.lock().unwrap().clone());
+
+    // 规则和全局设置在设置时已校验过
+    keystroke
+        .and_then(|keystroke| keystroke.chord().ok())
+        .unwrap_or(if cfg!(target_os = "macos") {
+            CTRL_V
+        } else {
+            SHIFT_INSERT
+        })
+}
+
+// 确定粘贴时使用的按键，调用时指定的优先于配置
+pub fn resolve_chord(keystroke: Option<PasteKeystroke>) -> Result<Chord, String> {
+    match keystroke {
+        Some(keystroke) => keystroke.chord(),
+        None => Ok(configured_chord()),
+    }
+}
+
+// 设置全局的粘贴按键，为空时恢复默认
+#[command]
+pub async fn set_paste_keystroke(keystroke: Option<PasteKeystroke>) -> Result<(), String> {
+    if let Some(keystroke) = &keystroke {
+        keystroke.chord()?;
+    }
+
+    *DEFAULT_KEYSTROKE.lock().unwrap() = keystroke;
+
+    Ok(())
+}
@@ -5,8 +5,18 @@ use tauri::{
 };
 
 mod commands;
+mod keystroke;
+mod previous_app;
+mod rules;
+mod self_test;
+mod timing;
 
 pub use commands::*;
+pub use keystroke::*;
+pub use previous_app::*;
+pub use rules::*;
+pub use self_test::*;
+pub use timing::*;
 
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("eco-paste")
@@ -15,6 +25,15 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
 
             Ok(())
         })
-        .invoke_handler(generate_handler![commands::paste])
+        .invoke_handler(generate_handler![
+            commands::paste,
+            commands::paste_as_keystrokes,
+            keystroke::set_paste_keystroke,
+            rules::set_paste_rules,
+            rules::get_paste_target,
+            previous_app::get_previous_app,
+            timing::set_paste_timing,
+            self_test::run_paste_self_test
+        ])
         .build()
 }
@@ -0,0 +1,78 @@
+use crate::commands::{get_previous_window_icon, get_previous_window_info, AppIcon};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::command;
+
+// 上一个应用的信息
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviousApp {
+    // 进程名（小写，不含 .exe）
+    pub process_name: String,
+    // PNG 格式的 data URL，取不到图标时为空
+    pub icon: Option<String>,
+}
+
+// 按进程名缓存的图标，图标很少变化，取不到的下次再试
+static ICONS: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(pixels).ok()?;
+    writer.finish().ok()?;
+
+    Some(bytes)
+}
+
+// 将应用图标统一编码为 PNG
+pub fn icon_png(icon: AppIcon) -> Option<Vec<u8>> {
+    match icon {
+        AppIcon::Png(bytes) => Some(bytes),
+        AppIcon::Rgba {
+            width,
+            height,
+            pixels,
+        } => encode_png(width, height, &pixels),
+    }
+}
+
+fn icon_data_url() -> Option<String> {
+    let png = icon_png(get_previous_window_icon()?)?;
+
+    Some(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+// 获取上一个应用的进程名和图标
+#[command]
+pub async fn get_previous_app() -> Option<PreviousApp> {
+    let process_name = get_previous_window_info()?.process_name;
+
+    let cached = ICONS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|icons| icons.get(&process_name).cloned());
+
+    let icon = cached.or_else(|| {
+        let icon = icon_data_url()?;
+
+        ICONS
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(process_name.clone(), icon.clone());
+
+        Some(icon)
+    });
+
+    Some(PreviousApp { process_name, icon })
+}
@@ -0,0 +1,135 @@
+use crate::commands::{get_previous_window_info, TargetWindow};
+use crate::keystroke::PasteKeystroke;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::command;
+
+// 只接受纯文本的控件类名（终端、纯文本编辑框等）
+const PLAIN_CLASSES: &[&str] = &[
+    "ConsoleWindowClass",
+    "CASCADIA_HOSTING_WINDOW_CLASS",
+    "VirtualConsoleClass",
+    "mintty",
+    "PuTTY",
+    "Edit",
+    "Scintilla",
+];
+
+// 只接受纯文本的应用（按进程名匹配）
+const PLAIN_APPS: &[&str] = &[
+    "terminal",
+    "iterm2",
+    "windowsterminal",
+    "alacritty",
+    "kitty",
+    "wezterm",
+    "xterm",
+    "gnome-terminal-server",
+    "konsole",
+    "putty",
+    "mintty",
+];
+
+// 按应用配置的粘贴规则
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteRule {
+    // 进程名，不区分大小写
+    pub app: String,
+    // 强制富文本（true）或纯文本（false），为空时自动检测
+    pub rich: Option<bool>,
+    // 是否允许“粘贴并转到”，为空时按是否为浏览器判断
+    pub go: Option<bool>,
+    // 粘贴时模拟的按键，为空时使用全局设置
+    pub keystroke: Option<PasteKeystroke>,
+    // 发送按键前等待的毫秒数，为空时使用全局设置，用于响应较慢的应用
+    pub delay: Option<u64>,
+    // 逐字模拟键盘输入纯文本，用于无法粘贴的应用（例如虚拟机、远程桌面）
+    #[serde(default)]
+    pub typing: bool,
+}
+
+// 粘贴目标及检测结果
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteTarget {
+    pub window: Option<TargetWindow>,
+    // 目标是否接受富文本（HTML、RTF）
+    pub rich: bool,
+    // 是否需要逐字输入文本代替粘贴
+    pub typing: bool,
+}
+
+// 逐字输入时每个字符之间等待的毫秒数
+pub const TYPING_INTERVAL: u64 = 10;
+
+static RULES: Mutex<Vec<PasteRule>> = Mutex::new(Vec::new());
+
+// 查找目标应用对应的规则
+pub fn find_rule(process_name: &str) -> Option<PasteRule> {
+    RULES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|rule| rule.app.eq_ignore_ascii_case(process_name))
+        .cloned()
+}
+
+// 根据窗口类名和进程名推测目标是否接受富文本
+fn sniff_rich(window: &TargetWindow) -> bool {
+    if let Some(class_name) = &window.class_name {
+        if PLAIN_CLASSES.contains(&class_name.as_str()) {
+            return false;
+        }
+    }
+
+    !PLAIN_APPS.contains(&window.process_name.as_str())
+}
+
+// 检测粘贴目标，规则优先于自动检测，逐字输入的目标只接受纯文本
+pub fn detect_paste_target() -> PasteTarget {
+    let window = get_previous_window_info();
+
+    let Some(target) = &window else {
+        return PasteTarget {
+            window,
+            rich: true,
+            typing: false,
+        };
+    };
+
+    let rule = find_rule(&target.process_name);
+    let typing = rule.as_ref().is_some_and(|rule| rule.typing);
+    let rich = !typing
+        && rule
+            .and_then(|rule| rule.rich)
+            .unwrap_or_else(|| sniff_rich(target));
+
+    PasteTarget {
+        window,
+        rich,
+        typing,
+    }
+}
+
+// 设置按应用配置的粘贴规则
+#[command]
+pub async fn set_paste_rules(rules: Vec<PasteRule>) -> Result<(), String> {
+    for rule in &rules {
+        if let Some(keystroke) = &rule.keystroke {
+            keystroke
+                .chord()
+                .map_err(|error| format!("{}: {}", rule.app, error))?;
+        }
+    }
+
+    *RULES.lock().unwrap() = rules;
+
+    Ok(())
+}
+
+// 获取粘贴目标
+#[command]
+pub async fn get_paste_target() -> PasteTarget {
+    detect_paste_target()
+}
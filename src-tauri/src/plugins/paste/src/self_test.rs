@@ -0,0 +1,274 @@
+use serde::Serialize;
+use tauri::command;
+
+// 文本默认的自检内容
+const DEFAULT_TEXT: &str = "EcoPaste paste self test 粘贴自检";
+
+// 粘贴自检的结果
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub passed: bool,
+    // 写入剪贴板的文本
+    pub expected: String,
+    // 测试窗口实际收到的文本
+    pub actual: String,
+    // 粘贴前是否成功聚焦到了测试窗口
+    pub focus_restored: bool,
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::SelfTestReport;
+    use crate::commands::{paste, replace_previous_window, wait};
+    use std::mem;
+    use std::ptr;
+    use std::sync::mpsc;
+    use std::thread;
+    use winapi::shared::windef::HWND;
+    use winapi::um::processthreadsapi::GetCurrentThreadId;
+    use winapi::um::winbase::{
+        GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+    };
+    use winapi::um::winuser::{
+        CloseClipboard, CreateWindowExW, DestroyWindow, DispatchMessageW, EmptyClipboard,
+        EnumClipboardFormats, GetClipboardData, GetForegroundWindow, GetMessageW,
+        GetWindowTextLengthW, GetWindowTextW, OpenClipboard, PostThreadMessageW, SetClipboardData,
+        ShowWindow, TranslateMessage, CF_UNICODETEXT, ES_AUTOHSCROLL, MSG, SW_SHOW, WM_QUIT,
+        WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+    };
+
+    /// Formats holding GDI handles instead of global memory, CF_BITMAP is
+    /// synthesized again from CF_DIB.
+    const HANDLE_FORMATS: &[u32] = &[2, 3, 9, 14, 0x80, 0x82, 0x83, 0x8E];
+    const GDI_OBJECT_FORMATS: std::ops::RangeInclusive<u32> = 0x300..=0x3FF;
+
+    /// Raw formats of the clipboard, in the order they were offered.
+    type Snapshot = Vec<(u32, Vec<u8>)>;
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Copy every memory-backed format of the clipboard.
+    unsafe fn save_clipboard() -> Snapshot {
+        let mut snapshot = Vec::new();
+
+        if OpenClipboard(ptr::null_mut()) == 0 {
+            return snapshot;
+        }
+
+        let mut format = EnumClipboardFormats(0);
+        while format != 0 {
+            if !HANDLE_FORMATS.contains(&format) && !GDI_OBJECT_FORMATS.contains(&format) {
+                let handle = GetClipboardData(format);
+                let data = if handle.is_null() {
+                    ptr::null_mut()
+                } else {
+                    GlobalLock(handle)
+                };
+
+                if !data.is_null() {
+                    let bytes = std::slice::from_raw_parts(data as *const u8, GlobalSize(handle));
+                    snapshot.push((format, bytes.to_vec()));
+                    GlobalUnlock(handle);
+                }
+            }
+
+            format = EnumClipboardFormats(format);
+        }
+
+        CloseClipboard();
+        snapshot
+    }
+
+    /// Replace the clipboard with the given formats, an empty snapshot
+    /// leaves it empty.
+    unsafe fn write_clipboard(formats: &[(u32, Vec<u8>)]) -> Result<(), String> {
+        if OpenClipboard(ptr::null_mut()) == 0 {
+            return Err("Failed to open clipboard".to_string());
+        }
+
+        EmptyClipboard();
+
+        let result = formats.iter().try_for_each(|(format, bytes)| {
+            let handle = GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1));
+            let data = GlobalLock(handle);
+            if data.is_null() {
+                GlobalFree(handle);
+                return Err("Failed to allocate clipboard memory".to_string());
+            }
+
+            ptr::copy_nonoverlapping(bytes.as_ptr(), data as *mut u8, bytes.len());
+            GlobalUnlock(handle);
+
+            if SetClipboardData(*format, handle).is_null() {
+                GlobalFree(handle);
+                return Err("Failed to set clipboard data".to_string());
+            }
+
+            Ok(())
+        });
+
+        CloseClipboard();
+        result
+    }
+
+    unsafe fn write_clipboard_text(text: &str) -> Result<(), String> {
+        let bytes = wide(text)
+            .into_iter()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+
+        write_clipboard(&[(CF_UNICODETEXT, bytes)])
+    }
+
+    unsafe fn window_text(hwnd: HWND) -> String {
+        let length = GetWindowTextLengthW(hwnd);
+        if length == 0 {
+            return String::new();
+        }
+
+        let mut buffer: Vec<u16> = vec![0; (length + 1) as usize];
+        GetWindowTextW(hwnd, buffer.as_mut_ptr(), length + 1);
+
+        String::from_utf16_lossy(&buffer[..length as usize])
+    }
+
+    /// A scratch edit control on its own thread with its own message loop,
+    /// standing in for the window the user pastes into.
+    struct ScratchWindow {
+        hwnd: HWND,
+        thread_id: u32,
+        handle: thread::JoinHandle<()>,
+    }
+
+    impl ScratchWindow {
+        fn spawn() -> Result<Self, String> {
+            let (sender, receiver) = mpsc::channel();
+
+            let handle = thread::spawn(move || unsafe {
+                let class = wide("EDIT");
+                // A top-level edit shows its window text as content
+                let title = wide("");
+
+                let hwnd = CreateWindowExW(
+                    0,
+                    class.as_ptr(),
+                    title.as_ptr(),
+                    WS_OVERLAPPEDWINDOW | WS_VISIBLE | ES_AUTOHSCROLL,
+                    100,
+                    100,
+                    480,
+                    120,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                );
+
+                if !hwnd.is_null() {
+                    ShowWindow(hwnd, SW_SHOW);
+                }
+
+                let _ = sender.send((hwnd as isize, GetCurrentThreadId()));
+
+                if hwnd.is_null() {
+                    return;
+                }
+
+                let mut message: MSG = mem::zeroed();
+                while GetMessageW(&mut message, ptr::null_mut(), 0, 0) > 0 {
+                    TranslateMessage(&message);
+                    DispatchMessageW(&message);
+                }
+
+                DestroyWindow(hwnd);
+            });
+
+            let (hwnd, thread_id) = receiver.recv().map_err(|e| e.to_string())?;
+            if hwnd == 0 {
+                return Err("Failed to create the self test window".to_string());
+            }
+
+            Ok(Self {
+                hwnd: hwnd as HWND,
+                thread_id,
+                handle,
+            })
+        }
+
+        fn close(self) {
+            unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+
+            let _ = self.handle.join();
+        }
+    }
+
+    pub async fn self_test(text: &str) -> Result<SelfTestReport, String> {
+        let window = ScratchWindow::spawn()?;
+
+        // Keep the user's clipboard and paste target to put them back afterwards
+        let saved_clipboard = unsafe { save_clipboard() };
+        let saved_window = replace_previous_window(Some(window.hwnd as isize));
+
+        let result = unsafe { write_clipboard_text(text) };
+
+        let report = match result {
+            Ok(()) => {
+                let _ = paste(None).await;
+
+                let focus_restored = unsafe { GetForegroundWindow() } == window.hwnd;
+
+                // Give the edit control time to process the injected keys
+                wait(300);
+
+                let actual = unsafe { window_text(window.hwnd) };
+
+                Ok(SelfTestReport {
+                    passed: actual == text,
+                    expected: text.to_string(),
+                    actual,
+                    focus_restored,
+                })
+            }
+            Err(error) => Err(error),
+        };
+
+        replace_previous_window(saved_window);
+        if let Err(error) = unsafe { write_clipboard(&saved_clipboard) } {
+            log::warn!("Failed to restore the clipboard after the self test: {}", error);
+        }
+
+        window.close();
+
+        report
+    }
+}
+
+// 在临时窗口中完整地走一遍粘贴流程，检查文本是否送达，结束后恢复原来的剪贴板内容，仅开发版可用
+pub async fn self_test(text: Option<String>) -> Result<SelfTestReport, String> {
+    if !cfg!(debug_assertions) {
+        return Err("The paste self test is only available in development builds".to_string());
+    }
+
+    let text = text.unwrap_or_else(|| DEFAULT_TEXT.to_string());
+
+    #[cfg(target_os = "windows")]
+    {
+        win::self_test(&text).await
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = text;
+
+        Err("The paste self test is only supported on Windows".to_string())
+    }
+}
+
+// 粘贴自检
+#[command]
+pub async fn run_paste_self_test(text: Option<String>) -> Result<SelfTestReport, String> {
+    self_test(text).await
+}
@@ -0,0 +1,81 @@
+use crate::commands::get_previous_window_info;
+use crate::rules::find_rule;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::command;
+
+// 等待时间的上限，避免误填的数值让粘贴卡住
+const MAX_DELAY: u64 = 5000;
+
+// 重试次数的上限
+const MAX_RETRIES: u32 = 8;
+
+// 重试时最短的等待时间，等待时间设为 0 时也要给窗口切换留出时间
+const MIN_BACKOFF: u64 = 50;
+
+// 粘贴前的等待和重试设置
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PasteTiming {
+    // 切换到目标窗口后，发送按键前等待的毫秒数
+    pub delay: u64,
+    // 焦点没有切换到目标窗口时的重试次数，每次的等待时间翻倍
+    pub retries: u32,
+}
+
+const DEFAULT_TIMING: PasteTiming = PasteTiming {
+    delay: 100,
+    retries: 3,
+};
+
+impl Default for PasteTiming {
+    fn default() -> Self {
+        DEFAULT_TIMING
+    }
+}
+
+static TIMING: Mutex<PasteTiming> = Mutex::new(DEFAULT_TIMING);
+
+impl PasteTiming {
+    // 第几次重试（从 1 开始）前等待的毫秒数
+    pub fn backoff(&self, attempt: u32) -> u64 {
+        let base = self.delay.clamp(MIN_BACKOFF, MAX_DELAY);
+
+        (base << attempt.min(MAX_RETRIES)).min(MAX_DELAY)
+    }
+}
+
+// 全局设置的等待时间，目标应用的规则中设置了等待时间时优先使用规则
+pub fn paste_timing() -> PasteTiming {
+    let timing = *TIMING.lock().unwrap();
+
+    let delay = get_previous_window_info()
+        .and_then(|window| find_rule(&window.process_name))
+        .and_then(|rule| rule.delay);
+
+    match delay {
+        Some(delay) => PasteTiming {
+            delay: delay.min(MAX_DELAY),
+            ..timing
+        },
+        None => timing,
+    }
+}
+
+// 设置粘贴前的等待时间和重试次数，为空时恢复默认
+#[command]
+pub async fn set_paste_timing(timing: Option<PasteTiming>) -> Result<(), String> {
+    let timing = timing.unwrap_or_default();
+
+    if timing.delay > MAX_DELAY {
+        return Err(format!("Delay must not exceed {} ms", MAX_DELAY));
+    }
+
+    if timing.retries > MAX_RETRIES {
+        return Err(format!("Retries must not exceed {}", MAX_RETRIES));
+    }
+
+    *TIMING.lock().unwrap() = timing;
+
+    Ok(())
+}
@@ -1,4 +1,11 @@
-const COMMANDS: &[&str] = &["show_window", "hide_window", "show_taskbar_icon"];
+const COMMANDS: &[&str] = &[
+    "show_window",
+    "hide_window",
+    "show_taskbar_icon",
+    "get_window_effects",
+    "set_window_effect",
+    "set_unread_badge",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS).build();
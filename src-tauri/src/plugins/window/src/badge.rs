@@ -0,0 +1,154 @@
+use crate::MAIN_WINDOW_LABEL;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use tauri::image::Image;
+use tauri::{command, AppHandle, Manager, Runtime};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Captures since the main window was last shown.
+static UNREAD: AtomicI64 = AtomicI64::new(0);
+
+/// Size of the Windows overlay icon.
+const OVERLAY_SIZE: u32 = 16;
+
+/// Counts above this show as `9+` on the overlay icon.
+const OVERLAY_MAX: i64 = 9;
+
+const OVERLAY_BACKGROUND: [u8; 4] = [0xE8, 0x3B, 0x3B, 0xFF];
+const OVERLAY_FOREGROUND: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+
+/// 3x5 glyphs of the digits and `+`, one row per `u8`, the lowest three
+/// bits from left to right.
+const GLYPHS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b000, 0b010, 0b111, 0b010, 0b000],
+];
+
+const PLUS: usize = 10;
+
+/// Glyphs are drawn at twice their size.
+const SCALE: u32 = 2;
+
+/// A red disc with the count in white, Windows has no badge of its own and
+/// shows this over the taskbar button instead.
+fn overlay_icon(count: i64) -> Image<'static> {
+    let glyphs: Vec<usize> = if count > OVERLAY_MAX {
+        vec![OVERLAY_MAX as usize, PLUS]
+    } else {
+        vec![count as usize]
+    };
+
+    let size = OVERLAY_SIZE;
+    let mut rgba = vec![0u8; (size * size * 4) as usize];
+
+    let mut put = |x: u32, y: u32, color: [u8; 4]| {
+        if x < size && y < size {
+            let offset = ((y * size + x) * 4) as usize;
+            rgba[offset..offset + 4].copy_from_slice(&color);
+        }
+    };
+
+    let center = (size as f32 - 1.0) / 2.0;
+    for y in 0..size {
+        for x in 0..size {
+            let (dx, dy) = (x as f32 - center, y as f32 - center);
+
+            if dx * dx + dy * dy <= center * center + center {
+                put(x, y, OVERLAY_BACKGROUND);
+            }
+        }
+    }
+
+    let glyph_width = 3 * SCALE;
+    let width = glyphs.len() as u32 * (glyph_width + 1) - 1;
+    let left = (size - width) / 2;
+    let top = (size - 5 * SCALE) / 2;
+
+    for (index, glyph) in glyphs.iter().enumerate() {
+        let origin = left + index as u32 * (glyph_width + 1);
+
+        for (row, bits) in GLYPHS[*glyph].iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+
+                for sy in 0..SCALE {
+                    for sx in 0..SCALE {
+                        put(
+                            origin + column * SCALE + sx,
+                            top + row as u32 * SCALE + sy,
+                            OVERLAY_FOREGROUND,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    Image::new_owned(rgba, size, size)
+}
+
+fn apply<R: Runtime>(app_handle: &AppHandle<R>) {
+    let count = UNREAD.load(Ordering::Relaxed);
+    let count = (ENABLED.load(Ordering::Relaxed) && count > 0).then_some(count);
+
+    for window in app_handle.webview_windows().values() {
+        let result = if cfg!(target_os = "windows") {
+            window.set_overlay_icon(count.map(overlay_icon))
+        } else {
+            window.set_badge_count(count)
+        };
+
+        if let Err(error) = result {
+            log::debug!("Failed to update the badge: {}", error);
+        }
+    }
+}
+
+/// Count a new capture, unless the main window is on screen.
+pub fn record_capture<R: Runtime>(app_handle: &AppHandle<R>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let visible = app_handle
+        .get_webview_window(MAIN_WINDOW_LABEL)
+        .and_then(|window| window.is_visible().ok())
+        .unwrap_or_default();
+
+    if visible {
+        return;
+    }
+
+    UNREAD.fetch_add(1, Ordering::Relaxed);
+    apply(app_handle);
+}
+
+/// Clear the count, done whenever the main window is shown.
+pub fn clear_unread<R: Runtime>(app_handle: &AppHandle<R>) {
+    if UNREAD.swap(0, Ordering::Relaxed) > 0 {
+        apply(app_handle);
+    }
+}
+
+// 设置是否在任务栏或程序坞图标上显示未查看的新内容数量
+#[command]
+pub async fn set_unread_badge<R: Runtime>(app_handle: AppHandle<R>, enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+
+    if !enabled {
+        UNREAD.store(0, Ordering::Relaxed);
+    }
+
+    apply(&app_handle);
+}
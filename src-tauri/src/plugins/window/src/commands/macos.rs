@@ -1,5 +1,5 @@
 use super::{is_main_window, shared_hide_window, shared_show_window};
-use crate::MAIN_WINDOW_LABEL;
+use crate::{clear_unread, MAIN_WINDOW_LABEL};
 use tauri::{command, AppHandle, Runtime, WebviewWindow};
 use tauri_nspanel::{CollectionBehavior, ManagerExt};
 
@@ -14,6 +14,8 @@ pub enum MacOSPanelStatus {
 pub async fn show_window<R: Runtime>(app_handle: AppHandle<R>, window: WebviewWindow<R>) {
     if is_main_window(&window) {
         set_macos_panel(&app_handle, &window, MacOSPanelStatus::Show);
+
+        clear_unread(&app_handle);
     } else {
         shared_show_window(&window);
     }
@@ -1,10 +1,15 @@
-use super::{shared_hide_window, shared_show_window};
+use super::{is_main_window, shared_hide_window, shared_show_window};
+use crate::clear_unread;
 use tauri::{command, AppHandle, Runtime, WebviewWindow};
 
 // 显示窗口
 #[command]
-pub async fn show_window<R: Runtime>(_app_handle: AppHandle<R>, window: WebviewWindow<R>) {
+pub async fn show_window<R: Runtime>(app_handle: AppHandle<R>, window: WebviewWindow<R>) {
     shared_show_window(&window);
+
+    if is_main_window(&window) {
+        clear_unread(&app_handle);
+    }
 }
 
 // 隐藏窗口
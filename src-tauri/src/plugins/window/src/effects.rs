@@ -0,0 +1,345 @@
+use crate::PREFERENCE_WINDOW_LABEL;
+use serde::{Deserialize, Serialize};
+use tauri::window::{Color, Effect, EffectState, EffectsBuilder};
+use tauri::{command, Runtime, WebviewWindow};
+
+/// Backdrop material behind the transparent webview.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowEffect {
+    /// What the window had before effects were configurable: the sidebar
+    /// material for the preference window on macOS, nothing otherwise.
+    Auto,
+    None,
+    /// Windows 11.
+    Mica,
+    /// Windows 10 1809 and later.
+    Acrylic,
+    /// Windows.
+    Blur,
+    /// macOS.
+    Vibrancy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum CornerPreference {
+    /// Left to the system.
+    #[default]
+    Default,
+    Round,
+    RoundSmall,
+    Square,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectSettings {
+    pub effect: WindowEffect,
+    /// `#RRGGBB` or `#RRGGBBAA`, used by acrylic and blur.
+    pub tint: Option<String>,
+    #[serde(default)]
+    pub corners: CornerPreference,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowEffectSupport {
+    /// `windows`, `macos` or `linux`.
+    pub platform: &'static str,
+    /// Windows build number or macOS product version, empty when unknown.
+    pub version: String,
+    /// Effects besides `auto` and `none` this system can show.
+    pub effects: Vec<WindowEffect>,
+    /// Whether the corner preference has any effect.
+    pub corners: bool,
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{CornerPreference, WindowEffect};
+    use std::ffi::c_void;
+    use tauri::{Runtime, WebviewWindow};
+
+    pub const NAME: &str = "windows";
+
+    /// First builds with acrylic and Mica backdrops.
+    const ACRYLIC_BUILD: u32 = 17763;
+    const MICA_BUILD: u32 = 22000;
+
+    /// DWMWA_WINDOW_CORNER_PREFERENCE, Windows 11 only.
+    const DWMWA_WINDOW_CORNER_PREFERENCE: u32 = 33;
+
+    #[repr(C)]
+    struct OsVersionInfo {
+        size: u32,
+        major: u32,
+        minor: u32,
+        build: u32,
+        platform: u32,
+        service_pack: [u16; 128],
+    }
+
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn RtlGetVersion(info: *mut OsVersionInfo) -> i32;
+    }
+
+    #[link(name = "dwmapi")]
+    extern "system" {
+        fn DwmSetWindowAttribute(
+            hwnd: *mut c_void,
+            attribute: u32,
+            value: *const c_void,
+            size: u32,
+        ) -> i32;
+    }
+
+    /// `GetVersionEx` reports Windows 8 to unmanifested processes,
+    /// `RtlGetVersion` does not lie.
+    fn build() -> Option<u32> {
+        let mut info = OsVersionInfo {
+            size: std::mem::size_of::<OsVersionInfo>() as u32,
+            major: 0,
+            minor: 0,
+            build: 0,
+            platform: 0,
+            service_pack: [0; 128],
+        };
+
+        let status = unsafe { RtlGetVersion(&mut info) };
+
+        (status == 0).then_some(info.build)
+    }
+
+    pub fn version() -> String {
+        build().map(|build| build.to_string()).unwrap_or_default()
+    }
+
+    pub fn effects() -> Vec<WindowEffect> {
+        let build = build().unwrap_or_default();
+        let mut effects = vec![WindowEffect::Blur];
+
+        if build >= ACRYLIC_BUILD {
+            effects.push(WindowEffect::Acrylic);
+        }
+
+        if build >= MICA_BUILD {
+            effects.push(WindowEffect::Mica);
+        }
+
+        effects
+    }
+
+    pub fn supports_corners() -> bool {
+        build().is_some_and(|build| build >= MICA_BUILD)
+    }
+
+    pub fn set_corners<R: Runtime>(
+        window: &WebviewWindow<R>,
+        corners: CornerPreference,
+    ) -> Result<(), String> {
+        if !supports_corners() {
+            return Ok(());
+        }
+
+        // DWM_WINDOW_CORNER_PREFERENCE
+        let preference: u32 = match corners {
+            CornerPreference::Default => 0,
+            CornerPreference::Square => 1,
+            CornerPreference::Round => 2,
+            CornerPreference::RoundSmall => 3,
+        };
+
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?;
+
+        let result = unsafe {
+            DwmSetWindowAttribute(
+                hwnd.0 as *mut c_void,
+                DWMWA_WINDOW_CORNER_PREFERENCE,
+                &preference as *const u32 as *const c_void,
+                std::mem::size_of::<u32>() as u32,
+            )
+        };
+
+        if result != 0 {
+            return Err(format!("DwmSetWindowAttribute failed: {:#x}", result));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{CornerPreference, WindowEffect};
+    use std::process::Command;
+    use tauri::{Runtime, WebviewWindow};
+
+    pub const NAME: &str = "macos";
+
+    pub fn version() -> String {
+        Command::new("sw_vers")
+            .arg("-productVersion")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn effects() -> Vec<WindowEffect> {
+        vec![WindowEffect::Vibrancy]
+    }
+
+    /// The corners are drawn with the vibrancy view, see `radius`.
+    pub fn supports_corners() -> bool {
+        true
+    }
+
+    pub fn set_corners<R: Runtime>(
+        _window: &WebviewWindow<R>,
+        _corners: CornerPreference,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{CornerPreference, WindowEffect};
+    use tauri::{Runtime, WebviewWindow};
+
+    pub const NAME: &str = "linux";
+
+    pub fn version() -> String {
+        String::new()
+    }
+
+    /// The compositor decides, there is no API for it.
+    pub fn effects() -> Vec<WindowEffect> {
+        Vec::new()
+    }
+
+    pub fn supports_corners() -> bool {
+        false
+    }
+
+    pub fn set_corners<R: Runtime>(
+        _window: &WebviewWindow<R>,
+        _corners: CornerPreference,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn parse_tint(tint: &str) -> Result<Color, String> {
+    let hex = tint.trim().trim_start_matches('#');
+
+    let channel = |index: usize| {
+        hex.get(index * 2..index * 2 + 2)
+            .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+    };
+
+    let color = match hex.len() {
+        6 => channel(0)
+            .zip(channel(1))
+            .zip(channel(2))
+            .map(|((r, g), b)| Color(r, g, b, 255)),
+        8 => channel(0)
+            .zip(channel(1))
+            .zip(channel(2))
+            .zip(channel(3))
+            .map(|(((r, g), b), a)| Color(r, g, b, a)),
+        _ => None,
+    };
+
+    color.ok_or_else(|| format!("Invalid tint {}", tint))
+}
+
+/// Radius of the vibrancy view on macOS.
+fn corner_radius(corners: CornerPreference) -> Option<f64> {
+    match corners {
+        CornerPreference::Default => None,
+        CornerPreference::Round => Some(12.0),
+        CornerPreference::RoundSmall => Some(6.0),
+        CornerPreference::Square => Some(0.0),
+    }
+}
+
+fn resolve<R: Runtime>(window: &WebviewWindow<R>, effect: WindowEffect) -> WindowEffect {
+    if effect != WindowEffect::Auto {
+        return effect;
+    }
+
+    if cfg!(target_os = "macos") && window.label() == PREFERENCE_WINDOW_LABEL {
+        WindowEffect::Vibrancy
+    } else {
+        WindowEffect::None
+    }
+}
+
+/// Apply the settings to a window, returns the effect shown.
+pub fn apply_window_effect<R: Runtime>(
+    window: &WebviewWindow<R>,
+    settings: &EffectSettings,
+) -> Result<WindowEffect, String> {
+    let effect = resolve(window, settings.effect);
+
+    let material = match effect {
+        WindowEffect::Auto | WindowEffect::None => None,
+        WindowEffect::Mica => Some(Effect::Mica),
+        WindowEffect::Acrylic => Some(Effect::Acrylic),
+        WindowEffect::Blur => Some(Effect::Blur),
+        WindowEffect::Vibrancy => Some(Effect::Sidebar),
+    };
+
+    match material {
+        Some(material) => {
+            if !platform::effects().contains(&effect) {
+                return Err(format!("{:?} is not supported on this system", effect));
+            }
+
+            let mut builder = EffectsBuilder::new()
+                .effect(material)
+                .state(EffectState::Active);
+
+            if let Some(tint) = settings.tint.as_deref().filter(|tint| !tint.is_empty()) {
+                builder = builder.color(parse_tint(tint)?);
+            }
+
+            if let Some(radius) = corner_radius(settings.corners) {
+                builder = builder.radius(radius);
+            }
+
+            window
+                .set_effects(builder.build())
+                .map_err(|e| e.to_string())?;
+        }
+        None => window.set_effects(None).map_err(|e| e.to_string())?,
+    }
+
+    platform::set_corners(window, settings.corners)?;
+
+    Ok(effect)
+}
+
+// 获取当前系统支持的窗口效果
+#[command]
+pub async fn get_window_effects() -> WindowEffectSupport {
+    WindowEffectSupport {
+        platform: platform::NAME,
+        version: platform::version(),
+        effects: platform::effects(),
+        corners: platform::supports_corners(),
+    }
+}
+
+// 设置窗口的背景效果、色调和圆角，返回实际使用的效果
+#[command]
+pub async fn set_window_effect<R: Runtime>(
+    window: WebviewWindow<R>,
+    settings: EffectSettings,
+) -> Result<WindowEffect, String> {
+    apply_window_effect(&window, &settings)
+}
@@ -4,16 +4,23 @@ use tauri::{
     Runtime,
 };
 
+mod badge;
 mod commands;
+mod effects;
 
+pub use badge::*;
 pub use commands::*;
+pub use effects::*;
 
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("eco-window")
         .invoke_handler(generate_handler![
             commands::show_window,
             commands::hide_window,
-            commands::show_taskbar_icon
+            commands::show_taskbar_icon,
+            effects::get_window_effects,
+            effects::set_window_effect,
+            badge::set_unread_badge
         ])
         .build()
 }
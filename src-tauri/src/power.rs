@@ -0,0 +1,161 @@
+use crate::clipboard_watcher;
+use crate::history_sync;
+use crate::hotkeys::refresh_hotkeys;
+use crate::item_access::forget_authentication;
+use crate::lan_sync;
+use crate::retention;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Runtime};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A tick this much later than planned means the machine slept, a busy
+/// system delays a thread by far less.
+const SLEEP_GAP: Duration = Duration::from_secs(30);
+
+/// Networks take a moment to come back after a resume, sync connections are
+/// revalidated after it.
+const NETWORK_SETTLE: Duration = Duration::from_secs(10);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Set by the power notification of the system, picked up by the next tick.
+static RESUMED: AtomicBool = AtomicBool::new(false);
+
+/// Windows reports suspend and resume to a callback, no window needed. Other
+/// systems rely on the gap between two ticks alone.
+#[cfg(target_os = "windows")]
+mod win {
+    use super::RESUMED;
+    use crate::item_access::forget_authentication;
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::sync::atomic::Ordering;
+
+    const DEVICE_NOTIFY_CALLBACK: u32 = 2;
+    const PBT_APMSUSPEND: u32 = 0x4;
+    const PBT_APMRESUMESUSPEND: u32 = 0x7;
+    const PBT_APMRESUMEAUTOMATIC: u32 = 0x12;
+
+    #[repr(C)]
+    struct SubscribeParameters {
+        callback: unsafe extern "system" fn(*mut c_void, u32, *mut c_void) -> u32,
+        context: *mut c_void,
+    }
+
+    #[link(name = "powrprof")]
+    extern "system" {
+        fn PowerRegisterSuspendResumeNotification(
+            flags: u32,
+            recipient: *mut c_void,
+            registration: *mut *mut c_void,
+        ) -> u32;
+    }
+
+    unsafe extern "system" fn on_power(_context: *mut c_void, kind: u32, _: *mut c_void) -> u32 {
+        match kind {
+            // Nobody should find sensitive items unlocked when the lid opens
+            PBT_APMSUSPEND => forget_authentication(),
+            PBT_APMRESUMESUSPEND | PBT_APMRESUMEAUTOMATIC => RESUMED.store(true, Ordering::SeqCst),
+            _ => {}
+        }
+
+        0
+    }
+
+    /// Windows 8 and later, the registration lives as long as the process.
+    pub fn subscribe() {
+        let parameters = Box::leak(Box::new(SubscribeParameters {
+            callback: on_power,
+            context: ptr::null_mut(),
+        }));
+        let mut registration = ptr::null_mut();
+
+        let result = unsafe {
+            PowerRegisterSuspendResumeNotification(
+                DEVICE_NOTIFY_CALLBACK,
+                parameters as *mut SubscribeParameters as *mut c_void,
+                &mut registration,
+            )
+        };
+
+        if result != 0 {
+            log::warn!("Failed to subscribe to power notifications: {}", result);
+        }
+    }
+}
+
+/// Everything that may not survive a sleep: listener registrations, global
+/// shortcut grabs, sockets bound to addresses that are gone and timers based
+/// on a clock that stood still.
+fn revalidate<R: Runtime>(app_handle: &AppHandle<R>) {
+    forget_authentication();
+    clipboard_watcher::revalidate(app_handle);
+    refresh_hotkeys(app_handle);
+    retention::run_now();
+
+    let app_handle = app_handle.clone();
+    let spawned = thread::Builder::new()
+        .name("power-resume".to_string())
+        .spawn(move || {
+            thread::sleep(NETWORK_SETTLE);
+
+            tauri::async_runtime::block_on(lan_sync::restart(app_handle.clone()));
+            history_sync::sync_soon(&app_handle);
+        });
+
+    if let Err(error) = spawned {
+        log::error!("Failed to revalidate the sync connections: {}", error);
+    }
+}
+
+fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+    let mut last = SystemTime::now();
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let now = SystemTime::now();
+
+        // Monotonic clocks stop while the machine sleeps on most systems, the
+        // wall clock does not. A clock set back counts as well.
+        let gap = match now.duration_since(last) {
+            Ok(elapsed) => elapsed
+                .checked_sub(POLL_INTERVAL)
+                .filter(|gap| *gap > SLEEP_GAP),
+            Err(error) => Some(error.duration()),
+        };
+
+        last = now;
+
+        let notified = RESUMED.swap(false, Ordering::SeqCst);
+
+        if notified || gap.is_some() {
+            log::info!("Resumed from sleep, gap {:?}", gap);
+
+            revalidate(&app_handle);
+        }
+    }
+}
+
+/// Start watching for sleep and hibernation, only once.
+pub fn start<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    #[cfg(target_os = "windows")]
+    win::subscribe();
+
+    let app_handle = app_handle.clone();
+    let spawned = thread::Builder::new()
+        .name("power".to_string())
+        .spawn(move || watch(app_handle));
+
+    if let Err(error) = spawned {
+        STARTED.store(false, Ordering::SeqCst);
+        log::error!("Failed to spawn the power watcher: {}", error);
+    }
+}
@@ -0,0 +1,278 @@
+/// Longest chain of parents walked, a reused pid could otherwise form a
+/// cycle.
+const MAX_DEPTH: usize = 32;
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::MAX_DEPTH;
+    use std::collections::HashMap;
+
+    const TH32CS_SNAPPROCESS: u32 = 0x2;
+    const INVALID_HANDLE_VALUE: isize = -1;
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+
+    #[repr(C)]
+    #[allow(non_snake_case, dead_code)]
+    struct PROCESSENTRY32W {
+        dwSize: u32,
+        cntUsage: u32,
+        th32ProcessID: u32,
+        th32DefaultHeapID: usize,
+        th32ModuleID: u32,
+        cntThreads: u32,
+        th32ParentProcessID: u32,
+        pcPriClassBase: i32,
+        dwFlags: u32,
+        szExeFile: [u16; 260],
+    }
+
+    extern "system" {
+        fn CreateToolhelp32Snapshot(dwFlags: u32, th32ProcessID: u32) -> *mut std::ffi::c_void;
+        fn Process32FirstW(hSnapshot: *mut std::ffi::c_void, lppe: *mut PROCESSENTRY32W) -> i32;
+        fn Process32NextW(hSnapshot: *mut std::ffi::c_void, lppe: *mut PROCESSENTRY32W) -> i32;
+        fn OpenProcess(
+            dwDesiredAccess: u32,
+            bInheritHandle: i32,
+            dwProcessId: u32,
+        ) -> *mut std::ffi::c_void;
+        fn GetProcessTimes(
+            hProcess: *mut std::ffi::c_void,
+            lpCreationTime: *mut u64,
+            lpExitTime: *mut u64,
+            lpKernelTime: *mut u64,
+            lpUserTime: *mut u64,
+        ) -> i32;
+        fn QueryFullProcessImageNameW(
+            hProcess: *mut std::ffi::c_void,
+            dwFlags: u32,
+            lpExeName: *mut u16,
+            lpdwSize: *mut u32,
+        ) -> i32;
+        fn CloseHandle(hObject: *mut std::ffi::c_void) -> i32;
+    }
+
+    struct Process {
+        parent: u32,
+        name: String,
+    }
+
+    /// Every running process by id, from a single toolhelp snapshot.
+    fn processes() -> HashMap<u32, Process> {
+        let mut processes = HashMap::new();
+
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+            if snapshot as isize == INVALID_HANDLE_VALUE {
+                return processes;
+            }
+
+            let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+            entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+
+            let mut more = Process32FirstW(snapshot, &mut entry) != 0;
+            while more {
+                let len = entry
+                    .szExeFile
+                    .iter()
+                    .position(|&ch| ch == 0)
+                    .unwrap_or(entry.szExeFile.len());
+
+                processes.insert(
+                    entry.th32ProcessID,
+                    Process {
+                        parent: entry.th32ParentProcessID,
+                        name: String::from_utf16_lossy(&entry.szExeFile[..len]).to_lowercase(),
+                    },
+                );
+
+                more = Process32NextW(snapshot, &mut entry) != 0;
+            }
+
+            CloseHandle(snapshot);
+        }
+
+        processes
+    }
+
+    /// Creation time as a FILETIME, `None` when the process can't be opened.
+    fn creation_time(pid: u32) -> Option<u64> {
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+
+            let (mut created, mut exited, mut kernel, mut user) = (0, 0, 0, 0);
+            let ok = GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user);
+            CloseHandle(process);
+
+            (ok != 0).then_some(created)
+        }
+    }
+
+    /// Full executable path of a process.
+    pub fn exe_path(pid: u32) -> Option<String> {
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+
+            let mut buffer = [0u16; 260];
+            let mut len = buffer.len() as u32;
+            let ok = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut len);
+            CloseHandle(process);
+
+            if ok == 0 {
+                return None;
+            }
+
+            Some(String::from_utf16_lossy(&buffer[..len as usize]))
+        }
+    }
+
+    /// Executable names of the process and its ancestors, nearest first.
+    pub fn lineage(pid: u32) -> Vec<String> {
+        let processes = processes();
+        let mut names = Vec::new();
+        let mut current = pid;
+
+        while let Some(process) = processes.get(&current) {
+            names.push(process.name.clone());
+
+            if names.len() >= MAX_DEPTH || process.parent == 0 || process.parent == current {
+                break;
+            }
+
+            // Windows keeps the id of an exited parent, which may since have
+            // been given to an unrelated process started after the child
+            let reused = match (creation_time(process.parent), creation_time(current)) {
+                (Some(parent), Some(child)) => parent > child,
+                _ => false,
+            };
+
+            if reused {
+                break;
+            }
+
+            current = process.parent;
+        }
+
+        names
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    use super::MAX_DEPTH;
+    use std::process::Command;
+
+    /// Parent id and executable name of a process, as `ps` reports them.
+    fn process(pid: u32) -> Option<(u32, String)> {
+        let output = Command::new("ps")
+            .args(["-o", "ppid=,comm=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        let (parent, command) = output.trim().split_once(char::is_whitespace)?;
+        let name = command.trim().rsplit('/').next()?.to_string();
+
+        Some((parent.parse().ok()?, name))
+    }
+
+    /// `comm` is the full executable path on macOS.
+    pub fn exe_path(pid: u32) -> Option<String> {
+        let output = Command::new("ps")
+            .args(["-o", "comm=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+
+        let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        (!path.is_empty()).then_some(path)
+    }
+
+    /// One `ps` call per ancestor, app process trees are shallow.
+    pub fn lineage(pid: u32) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut current = pid;
+
+        while let Some((parent, name)) = process(current) {
+            names.push(name);
+
+            // launchd is the parent of every app
+            if names.len() >= MAX_DEPTH || parent <= 1 || parent == current {
+                break;
+            }
+
+            current = parent;
+        }
+
+        names
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::MAX_DEPTH;
+    use std::fs;
+
+    fn parent(pid: u32) -> Option<u32> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+
+        // The name in parentheses may itself contain spaces or parentheses,
+        // the state and parent id follow the last closing one
+        let (_, fields) = stat.rsplit_once(')')?;
+
+        fields.split_whitespace().nth(1)?.parse().ok()
+    }
+
+    /// The executable's file name, `comm` is cut at 15 bytes and only used
+    /// when the link can't be read.
+    fn name(pid: u32) -> Option<String> {
+        if let Ok(exe) = fs::read_link(format!("/proc/{}/exe", pid)) {
+            if let Some(name) = exe.file_name() {
+                return Some(name.to_string_lossy().to_string());
+            }
+        }
+
+        let comm = fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+
+        Some(comm.trim().to_string())
+    }
+
+    pub fn exe_path(pid: u32) -> Option<String> {
+        let exe = fs::read_link(format!("/proc/{}/exe", pid)).ok()?;
+
+        Some(exe.to_string_lossy().to_string())
+    }
+
+    /// Walked through procfs, stopping below init.
+    pub fn lineage(pid: u32) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut current = pid;
+
+        while let Some(exe) = name(current) {
+            names.push(exe);
+
+            match parent(current) {
+                Some(parent) if names.len() < MAX_DEPTH && parent > 1 && parent != current => {
+                    current = parent;
+                }
+                _ => break,
+            }
+        }
+
+        names
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub use win::{exe_path, lineage};
+
+#[cfg(target_os = "macos")]
+pub use mac::{exe_path, lineage};
+
+#[cfg(target_os = "linux")]
+pub use linux::{exe_path, lineage};
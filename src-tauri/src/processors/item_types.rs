@@ -0,0 +1,278 @@
+use super::{enabled_processors, Processor};
+use crate::history::{get_metadata, is_redacted, OBJECT_DATA, OBJECT_TYPE};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Runtime};
+
+/// Scheme previews are served under, `ecopaste://localhost/preview/<id>`
+/// and `ecopaste://localhost/types` (`http://ecopaste.localhost/...` on
+/// Windows).
+pub const ITEM_PROTOCOL: &str = "ecopaste";
+
+/// Origins the bundled webview is served from, `tauri://localhost` on
+/// macOS and Linux and `http://tauri.localhost` on Windows.
+const APP_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+];
+
+/// Largest structured data kept for an item.
+pub const MAX_OBJECT_BYTES: usize = 64 * 1024;
+
+/// A type of domain object a processor declares in its manifest, e.g. a
+/// Jira issue or a color palette.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemType {
+    /// Unique within the processor, stored as `<processor id>/<type>`.
+    #[serde(rename = "type")]
+    pub id: String,
+    pub name: String,
+    pub preview: PreviewSchema,
+}
+
+/// How the webview shows an object. Text may reference the data with
+/// `{key}` or `{nested.key}` placeholders.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PreviewSchema {
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub fields: Vec<PreviewField>,
+    /// Key of an array of CSS colors shown as swatches.
+    pub swatches: Option<String>,
+    /// Opened when the preview is clicked, only http(s) links are kept.
+    pub link: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewField {
+    pub label: String,
+    pub value: String,
+}
+
+/// A preview schema filled with the data of one item.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Preview {
+    #[serde(rename = "type")]
+    kind: String,
+    type_name: String,
+    title: String,
+    subtitle: Option<String>,
+    fields: Vec<PreviewField>,
+    swatches: Vec<String>,
+    link: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RegisteredType {
+    #[serde(rename = "type")]
+    id: String,
+    name: String,
+    processor: String,
+}
+
+pub fn qualified_type(processor: &Processor, item_type: &ItemType) -> String {
+    format!("{}/{}", processor.id, item_type.id)
+}
+
+fn lookup<'a>(data: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.')
+        .try_fold(data, |value, part| value.get(part.trim()))
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Replace the placeholders of a template, missing keys become empty.
+fn fill(template: &str, data: &Value) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+
+        filled.push_str(&rest[..start]);
+        let key = &rest[start + 1..start + end];
+        filled.push_str(&lookup(data, key).map(to_text).unwrap_or_default());
+
+        rest = &rest[start + end + 1..];
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+fn render(kind: &str, item_type: &ItemType, data: &Value) -> Preview {
+    let schema = &item_type.preview;
+
+    let swatches = schema
+        .swatches
+        .as_deref()
+        .and_then(|key| lookup(data, key))
+        .and_then(Value::as_array)
+        .map(|colors| {
+            colors
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let link = schema
+        .link
+        .as_deref()
+        .map(|link| fill(link, data))
+        .filter(|link| link.starts_with("https://") || link.starts_with("http://"));
+
+    Preview {
+        kind: kind.to_string(),
+        type_name: item_type.name.clone(),
+        title: fill(&schema.title, data),
+        subtitle: schema
+            .subtitle
+            .as_deref()
+            .map(|subtitle| fill(subtitle, data)),
+        fields: schema
+            .fields
+            .iter()
+            .map(|field| PreviewField {
+                label: field.label.clone(),
+                value: fill(&field.value, data),
+            })
+            .collect(),
+        swatches,
+        link,
+    }
+}
+
+/// Types of the enabled processors.
+fn registered_types<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<(String, String, ItemType)> {
+    enabled_processors(app_handle)
+        .iter()
+        .flat_map(|processor| {
+            processor.manifest.item_types.iter().map(|item_type| {
+                (
+                    qualified_type(processor, item_type),
+                    processor.id.clone(),
+                    item_type.clone(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Preview of an item holding an object of a registered type, `None` for
+/// other items, objects whose processor is gone and sensitive items that
+/// are hidden right now.
+fn preview<R: Runtime>(app_handle: &AppHandle<R>, id: &str) -> Result<Option<Preview>, String> {
+    if is_redacted(id)? {
+        return Ok(None);
+    }
+
+    let metadata = get_metadata(id)?;
+
+    let (Some(kind), Some(data)) = (metadata.get(OBJECT_TYPE), metadata.get(OBJECT_DATA)) else {
+        return Ok(None);
+    };
+
+    let Some((_, _, item_type)) = registered_types(app_handle)
+        .into_iter()
+        .find(|(qualified, _, _)| qualified == kind)
+    else {
+        return Ok(None);
+    };
+
+    let data: Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+
+    Ok(Some(render(kind, &item_type, &data)))
+}
+
+/// The request's origin if it is the app's own webview, other pages the
+/// webview may load can't read the previews.
+fn app_origin<R: Runtime>(app_handle: &AppHandle<R>, request: &Request<Vec<u8>>) -> Option<String> {
+    let origin = request.headers().get(header::ORIGIN)?.to_str().ok()?;
+
+    // Debug builds load the webview from the dev server
+    let dev_origin = app_handle
+        .config()
+        .build
+        .dev_url
+        .as_ref()
+        .filter(|_| cfg!(debug_assertions))
+        .map(|url| url.origin().ascii_serialization());
+
+    (APP_ORIGINS.contains(&origin) || dev_origin.as_deref() == Some(origin))
+        .then(|| origin.to_string())
+}
+
+fn json_response(status: StatusCode, origin: Option<&str>, body: Vec<u8>) -> Response<Vec<u8>> {
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::VARY, "Origin");
+
+    // The webview is served from another origin
+    if let Some(origin) = origin {
+        response = response.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+
+    response.body(body).unwrap_or_default()
+}
+
+fn is_safe_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// Serve the registered types and item previews.
+pub fn serve_item_protocol<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    request: &Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let path = request.uri().path().trim_matches('/');
+    let origin = app_origin(app_handle, request);
+    let origin = origin.as_deref();
+
+    let body = match path.split_once('/') {
+        None if path == "types" => {
+            let types: Vec<RegisteredType> = registered_types(app_handle)
+                .into_iter()
+                .map(|(id, processor, item_type)| RegisteredType {
+                    id,
+                    name: item_type.name,
+                    processor,
+                })
+                .collect();
+
+            serde_json::to_vec(&types).map_err(|e| e.to_string())
+        }
+        Some(("preview", id)) if is_safe_id(id) => preview(app_handle, id)
+            .and_then(|preview| serde_json::to_vec(&preview).map_err(|e| e.to_string())),
+        _ => return json_response(StatusCode::NOT_FOUND, origin, b"null".to_vec()),
+    };
+
+    match body {
+        Ok(body) => json_response(StatusCode::OK, origin, body),
+        Err(error) => {
+            log::warn!("Failed to serve {}: {}", path, error);
+
+            json_response(StatusCode::INTERNAL_SERVER_ERROR, origin, b"null".to_vec())
+        }
+    }
+}
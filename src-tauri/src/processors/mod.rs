@@ -0,0 +1,448 @@
+use crate::clipboard_image::resolve_image_path;
+use crate::history::{
+    get_metadata, item_tags, notify_changed, require_item, set_metadata, set_tags, store,
+    HistoryItem, HistoryRow, NewHistoryEntry, OBJECT_DATA, OBJECT_TYPE, OCR_TEXT, SENSITIVE,
+};
+use crate::vault;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, Runtime};
+
+mod item_types;
+mod sidecar;
+
+use item_types::{qualified_type, MAX_OBJECT_BYTES};
+pub use item_types::{serve_item_protocol, ItemType, ITEM_PROTOCOL};
+
+/// Every processor is a directory under the processors directory holding
+/// this manifest, see `docs/capture-processors.md`.
+const MANIFEST_FILE: &str = "processor.json";
+
+/// The only protocol version so far.
+const PROTOCOL_VERSION: u32 = 1;
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+
+/// Metadata entries kept from one response, and the longest value.
+const MAX_METADATA: usize = 32;
+const MAX_METADATA_CHARS: usize = 4096;
+
+/// What a processor may change, results it did not declare are ignored.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    /// Metadata under keys prefixed with the processor id.
+    Metadata,
+    Tags,
+    /// Mark the item as sensitive, e.g. for DLP checks.
+    Sensitive,
+    /// Text recognized in an image, made searchable like OCR.
+    Text,
+    /// Structured data of one of the item types the processor declares.
+    Objects,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    description: String,
+    /// Executable, relative to the processor directory.
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    /// Item types the processor is run for, all when empty.
+    #[serde(default)]
+    types: Vec<String>,
+    #[serde(default)]
+    capabilities: Vec<Capability>,
+    timeout_ms: Option<u64>,
+    /// Domain objects the processor recognizes, shown with their own
+    /// preview.
+    #[serde(default)]
+    item_types: Vec<ItemType>,
+}
+
+struct Processor {
+    /// Name of the processor directory.
+    id: String,
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub types: Vec<String>,
+    pub capabilities: Vec<Capability>,
+    pub item_types: Vec<ItemType>,
+    pub enabled: bool,
+    /// Why the processor can't be loaded, it is never run then.
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request<'a> {
+    version: u32,
+    id: &'a str,
+    #[serde(rename = "type")]
+    kind: &'a str,
+    subtype: Option<&'a str>,
+    text: Option<&'a str>,
+    files: Option<Vec<String>>,
+    /// Absolute path of the image file of an image item.
+    image_path: Option<String>,
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ResponseObject {
+    /// One of the `itemTypes` of the manifest.
+    #[serde(rename = "type")]
+    kind: String,
+    data: Value,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Response {
+    metadata: BTreeMap<String, String>,
+    tags: Vec<String>,
+    sensitive: bool,
+    text: Option<String>,
+    object: Option<ResponseObject>,
+}
+
+/// Ids of the processors the user enabled, processors are off until then.
+static ENABLED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// New entries waiting for the processors before they are shared with the
+/// paired devices, a processor may still flag them as sensitive.
+static PENDING_SHARES: Mutex<Vec<NewHistoryEntry>> = Mutex::new(Vec::new());
+
+/// Entries the processors never ran for are dropped past this many.
+const MAX_PENDING_SHARES: usize = 16;
+
+fn get_processors_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("processors")
+}
+
+fn load(dir: &Path) -> Result<Manifest, String> {
+    let bytes = std::fs::read(dir.join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+    let manifest: Manifest = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+
+    // The executable has to be shipped inside the processor directory
+    let command = Path::new(&manifest.command);
+    if command.is_absolute()
+        || command
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err(format!(
+            "Command {} is outside the processor",
+            manifest.command
+        ));
+    }
+
+    if !dir.join(command).is_file() {
+        return Err(format!("Command {} not found", manifest.command));
+    }
+
+    Ok(manifest)
+}
+
+/// Every processor directory with its manifest or why it can't be loaded.
+fn discover<R: Runtime>(
+    app_handle: &AppHandle<R>,
+) -> Vec<(String, PathBuf, Result<Manifest, String>)> {
+    let Ok(entries) = std::fs::read_dir(get_processors_dir(app_handle)) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|dir| {
+            let id = dir.file_name()?.to_string_lossy().to_string();
+            let manifest = load(&dir);
+
+            Some((id, dir, manifest))
+        })
+        .collect();
+
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+fn enabled_processors<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<Processor> {
+    let enabled = ENABLED.lock().unwrap().clone();
+
+    discover(app_handle)
+        .into_iter()
+        .filter(|(id, _, _)| enabled.contains(id))
+        .filter_map(|(id, dir, manifest)| match manifest {
+            Ok(manifest) => Some(Processor { id, dir, manifest }),
+            Err(error) => {
+                log::warn!("Capture processor {} can't be loaded: {}", id, error);
+                None
+            }
+        })
+        .collect()
+}
+
+fn request<'a, R: Runtime>(
+    app_handle: &AppHandle<R>,
+    item: &'a HistoryItem,
+    metadata: &BTreeMap<String, String>,
+) -> Request<'a> {
+    let mut request = Request {
+        version: PROTOCOL_VERSION,
+        id: &item.id,
+        kind: &item.kind,
+        subtype: item.subtype.as_deref(),
+        text: None,
+        files: None,
+        image_path: None,
+        metadata: metadata.clone(),
+    };
+
+    match item.kind.as_str() {
+        "image" => {
+            let path = resolve_image_path(app_handle, &item.value);
+            request.image_path = path.exists().then(|| path.to_string_lossy().to_string());
+        }
+        "files" => request.files = serde_json::from_str(&item.value).ok(),
+        _ => request.text = Some(item.plain_text()),
+    }
+
+    request
+}
+
+/// Store what the processor found within its capabilities, returns whether
+/// anything changed.
+fn apply(processor: &Processor, item: &HistoryItem, response: Response) -> Result<bool, String> {
+    let allowed = |capability| processor.manifest.capabilities.contains(&capability);
+    let mut changed = false;
+
+    if allowed(Capability::Metadata) {
+        for (key, value) in response.metadata.into_iter().take(MAX_METADATA) {
+            if key.is_empty() || value.is_empty() {
+                continue;
+            }
+
+            let value: String = value.chars().take(MAX_METADATA_CHARS).collect();
+            set_metadata(&item.id, &format!("{}.{}", processor.id, key), &value)?;
+            changed = true;
+        }
+    }
+
+    if allowed(Capability::Tags) && !response.tags.is_empty() {
+        let mut tags = item_tags(&item.id)?;
+        tags.extend(response.tags);
+
+        set_tags(&item.id, tags)?;
+        changed = true;
+    }
+
+    if allowed(Capability::Sensitive) && response.sensitive {
+        set_metadata(&item.id, SENSITIVE, "1")?;
+        changed = true;
+    }
+
+    let text = response.text.filter(|text| !text.trim().is_empty());
+    if let Some(text) = text.filter(|_| allowed(Capability::Text) && item.kind == "image") {
+        store().update_row(
+            &item.id,
+            &HistoryRow::from([("search".to_string(), Value::from(text.as_str()))]),
+        )?;
+        set_metadata(&item.id, OCR_TEXT, &text)?;
+        changed = true;
+    }
+
+    if let Some(object) = response.object.filter(|_| allowed(Capability::Objects)) {
+        let item_type = processor
+            .manifest
+            .item_types
+            .iter()
+            .find(|item_type| item_type.id == object.kind)
+            .ok_or_else(|| format!("Undeclared item type {}", object.kind))?;
+
+        let data = serde_json::to_string(&object.data).map_err(|e| e.to_string())?;
+        if data.len() > MAX_OBJECT_BYTES {
+            return Err(format!("Object of {} bytes is too large", data.len()));
+        }
+
+        set_metadata(&item.id, OBJECT_TYPE, &qualified_type(processor, item_type))?;
+        set_metadata(&item.id, OBJECT_DATA, &data)?;
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+fn run<R: Runtime>(app_handle: &AppHandle<R>, id: &str) -> Result<usize, String> {
+    let processors = enabled_processors(app_handle);
+    if processors.is_empty() {
+        return Ok(0);
+    }
+
+    let item = require_item(id)?;
+    let metadata = get_metadata(id)?;
+    let request = request(app_handle, &item, &metadata);
+
+    let mut applied = 0;
+
+    for processor in &processors {
+        let types = &processor.manifest.types;
+        if !types.is_empty() && !types.contains(&item.kind) {
+            continue;
+        }
+
+        let timeout = processor
+            .manifest
+            .timeout_ms
+            .unwrap_or(DEFAULT_TIMEOUT_MS)
+            .min(MAX_TIMEOUT_MS);
+
+        let response = sidecar::call::<_, Response>(
+            &processor.dir,
+            &processor.dir.join(&processor.manifest.command),
+            &processor.manifest.args,
+            &request,
+            Duration::from_millis(timeout),
+        );
+
+        match response.and_then(|response| apply(processor, &item, response)) {
+            Ok(true) => applied += 1,
+            Ok(false) => {}
+            Err(error) => log::warn!("Capture processor {} failed: {}", processor.id, error),
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Share a new entry with the paired devices once the processors ran for
+/// it, right away when none will run.
+pub fn share_after_processors(entry: NewHistoryEntry) {
+    if vault::is_enabled() || ENABLED.lock().unwrap().is_empty() {
+        crate::lan_sync::share_entry(&entry);
+        return;
+    }
+
+    let mut pending = PENDING_SHARES.lock().unwrap();
+    if pending.len() >= MAX_PENDING_SHARES {
+        pending.remove(0);
+    }
+    pending.push(entry);
+}
+
+/// Share the entry waiting for the processors unless one of them flagged it
+/// as sensitive.
+fn share_pending(id: &str) -> Result<(), String> {
+    let entry = {
+        let mut pending = PENDING_SHARES.lock().unwrap();
+        let Some(index) = pending
+            .iter()
+            .position(|entry| entry.row.get("id").and_then(Value::as_str) == Some(id))
+        else {
+            return Ok(());
+        };
+
+        pending.remove(index)
+    };
+
+    if !get_metadata(id)?.contains_key(SENSITIVE) {
+        crate::lan_sync::share_entry(&entry);
+    }
+
+    Ok(())
+}
+
+// 获取插件目录中的采集处理器
+#[command]
+pub async fn list_processors<R: Runtime>(app_handle: AppHandle<R>) -> Vec<ProcessorInfo> {
+    let enabled = ENABLED.lock().unwrap().clone();
+
+    discover(&app_handle)
+        .into_iter()
+        .map(|(id, _, manifest)| {
+            let enabled = enabled.contains(&id);
+
+            match manifest {
+                Ok(manifest) => ProcessorInfo {
+                    id,
+                    name: manifest.name,
+                    version: manifest.version,
+                    description: manifest.description,
+                    types: manifest.types,
+                    capabilities: manifest.capabilities,
+                    item_types: manifest.item_types,
+                    enabled,
+                    error: None,
+                },
+                Err(error) => ProcessorInfo {
+                    name: id.clone(),
+                    id,
+                    version: String::new(),
+                    description: String::new(),
+                    types: Vec::new(),
+                    capabilities: Vec::new(),
+                    item_types: Vec::new(),
+                    enabled,
+                    error: Some(error),
+                },
+            }
+        })
+        .collect()
+}
+
+// 设置启用的采集处理器，未启用的处理器不会运行
+#[command]
+pub async fn set_enabled_processors(ids: Vec<String>) {
+    *ENABLED.lock().unwrap() = ids;
+}
+
+// 对新采集的条目运行启用的采集处理器，返回产生结果的处理器数量
+#[command]
+pub async fn run_capture_processors<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<usize, String> {
+    // Processors run outside the app, the decrypted content would leave
+    // the vault
+    if vault::is_enabled() {
+        share_pending(&id)?;
+
+        return Ok(0);
+    }
+
+    let handle = app_handle.clone();
+    let item_id = id.clone();
+    let applied = tauri::async_runtime::spawn_blocking(move || run(&handle, &item_id))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    share_pending(&id)?;
+
+    if applied > 0 {
+        notify_changed(&app_handle);
+    }
+
+    Ok(applied)
+}
@@ -0,0 +1,112 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A response is a few fields of JSON, anything longer is cut off and
+/// fails to parse.
+const MAX_OUTPUT: u64 = 1024 * 1024;
+
+/// Kept from the log of a failed run.
+const MAX_STDERR: u64 = 4 * 1024;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The only environment a processor sees, tokens and other secrets in
+/// ours stay out of reach. Windows needs `SystemRoot` to start most
+/// programs at all.
+const INHERITED_ENV: &[&str] = &["PATH", "SystemRoot", "TEMP", "TMP", "LANG"];
+
+#[cfg(target_os = "windows")]
+fn hide_window(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    command.creation_flags(CREATE_NO_WINDOW);
+}
+
+#[cfg(not(target_os = "windows"))]
+fn hide_window(_command: &mut Command) {}
+
+fn read_capped(mut reader: impl Read + Send + 'static, cap: u64) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = reader.by_ref().take(cap).read_to_end(&mut bytes);
+
+        bytes
+    })
+}
+
+/// Run a processor for one request. The request is written to its stdin
+/// as JSON and the response read from its stdout. It starts in its own
+/// directory with a bare environment and is killed once it runs past
+/// `timeout`.
+pub fn call<T: Serialize, U: DeserializeOwned>(
+    dir: &Path,
+    program: &Path,
+    args: &[String],
+    request: &T,
+    timeout: Duration,
+) -> Result<U, String> {
+    let input = serde_json::to_vec(request).map_err(|e| e.to_string())?;
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(dir)
+        .env_clear()
+        .envs(
+            INHERITED_ENV
+                .iter()
+                .filter_map(|key| Some((key, std::env::var_os(key)?))),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    hide_window(&mut command);
+
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+
+    let mut stdin = child.stdin.take().ok_or("No stdin")?;
+    let stdout = read_capped(child.stdout.take().ok_or("No stdout")?, MAX_OUTPUT);
+    let stderr = read_capped(child.stderr.take().ok_or("No stderr")?, MAX_STDERR);
+
+    // A processor that never reads its input must not block the capture,
+    // stdin closes when the thread ends
+    thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+
+            return Err(format!("Timed out after {} ms", timeout.as_millis()));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let output = stdout.join().unwrap_or_default();
+    let log = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        return Err(format!(
+            "Exited with {}: {}",
+            status,
+            String::from_utf8_lossy(&log).trim()
+        ));
+    }
+
+    serde_json::from_slice(&output).map_err(|e| e.to_string())
+}
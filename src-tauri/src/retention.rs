@@ -0,0 +1,129 @@
+use crate::cold_storage::{apply_cold_policy, ColdStoragePolicy};
+use crate::history::{archive_older_than, notify_changed};
+use crate::storage::{apply_policy, PrunePolicy};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{async_runtime, command, AppHandle, Runtime};
+use tokio::sync::Notify;
+
+/// How often the policy is applied while nothing changes.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static POLICY: Mutex<Option<PrunePolicy>> = Mutex::new(None);
+/// Months items stay in the history before they are archived, `0` never
+/// archives.
+static ARCHIVE_AFTER_MONTHS: AtomicU32 = AtomicU32::new(0);
+static COLD_STORAGE_POLICY: Mutex<Option<ColdStoragePolicy>> = Mutex::new(None);
+/// Wakes the task up to apply a new policy right away.
+static POLICY_CHANGED: Notify = Notify::const_new();
+
+fn prune<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), String> {
+    let Some(policy) = POLICY.lock().unwrap().clone() else {
+        return Ok(());
+    };
+
+    let pruned = apply_policy(app_handle, &policy)?;
+
+    if pruned > 0 {
+        log::info!("Retention pruned {} history items", pruned);
+        notify_changed(app_handle);
+    }
+
+    Ok(())
+}
+
+fn archive<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), String> {
+    let months = ARCHIVE_AFTER_MONTHS.load(Ordering::Relaxed);
+
+    if months == 0 {
+        return Ok(());
+    }
+
+    let archived = archive_older_than(months)?;
+
+    if archived > 0 {
+        log::info!("Archived {} history items", archived);
+        notify_changed(app_handle);
+    }
+
+    Ok(())
+}
+
+fn cool_down<R: Runtime>(app_handle: &AppHandle<R>) -> Result<(), String> {
+    let Some(policy) = COLD_STORAGE_POLICY.lock().unwrap().clone() else {
+        return Ok(());
+    };
+
+    let changed = apply_cold_policy(app_handle, &policy)?;
+
+    if changed > 0 {
+        log::info!("Moved {} images to cold storage", changed);
+        notify_changed(app_handle);
+    }
+
+    Ok(())
+}
+
+async fn run<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(PRUNE_INTERVAL) => {}
+            _ = POLICY_CHANGED.notified() => {}
+        }
+
+        let handle = app_handle.clone();
+        let result = async_runtime::spawn_blocking(move || {
+            // Pruned items are gone for good, there is no point archiving them
+            prune(&handle)?;
+            archive(&handle)?;
+            cool_down(&handle)
+        })
+        .await;
+
+        match result {
+            Ok(Err(error)) => log::warn!("Failed to apply the retention policy: {}", error),
+            Err(error) => log::error!("Retention task panicked: {}", error),
+            Ok(Ok(())) => {}
+        }
+    }
+}
+
+/// Apply the policies right away instead of at the end of the interval.
+pub fn run_now() {
+    POLICY_CHANGED.notify_one();
+}
+
+/// Start pruning history by the retention policy the webview sets, only once.
+pub fn start<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    async_runtime::spawn(run(app_handle.clone()));
+}
+
+// 设置历史记录的保留策略，收藏的条目不会被删除
+#[command]
+pub async fn set_retention_policy(policy: PrunePolicy) {
+    *POLICY.lock().unwrap() = Some(policy);
+
+    POLICY_CHANGED.notify_one();
+}
+
+// 设置历史记录在多少个月后移入按月归档的数据库，0 为不归档，收藏的条目不会被归档
+#[command]
+pub async fn set_archive_policy(months: u32) {
+    ARCHIVE_AFTER_MONTHS.store(months, Ordering::Relaxed);
+
+    POLICY_CHANGED.notify_one();
+}
+
+// 设置图片冷存储策略，长时间未使用的图片会被重新压缩或移到指定目录，null 为关闭
+#[command]
+pub async fn set_cold_storage_policy(policy: Option<ColdStoragePolicy>) {
+    *COLD_STORAGE_POLICY.lock().unwrap() = policy;
+
+    POLICY_CHANGED.notify_one();
+}
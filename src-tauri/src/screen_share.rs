@@ -0,0 +1,194 @@
+use crate::history::{notify_changed, set_redacting};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Runtime};
+
+/// Emitted with `true` when screen sharing starts and `false` when it ends.
+pub const CHANGED_EVENT: &str = "screen-share-changed";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static SHARING: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_os = "windows")]
+mod win {
+    /// Processes that only run while the screen is shared or recorded. Apps
+    /// capturing through Windows.Graphics.Capture cannot be enumerated, Zoom
+    /// starts CptHost for every share.
+    const CAPTURE_PROCESSES: &[&str] = &["cpthost.exe", "obs64.exe", "obs32.exe"];
+
+    /// Set by Teams, PowerPoint and others while presenting.
+    const QUNS_PRESENTATION_MODE: i32 = 4;
+
+    const TH32CS_SNAPPROCESS: u32 = 0x2;
+    const INVALID_HANDLE_VALUE: isize = -1;
+    const MAX_PATH: usize = 260;
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct ProcessEntry {
+        dw_size: u32,
+        cnt_usage: u32,
+        th32_process_id: u32,
+        th32_default_heap_id: usize,
+        th32_module_id: u32,
+        cnt_threads: u32,
+        th32_parent_process_id: u32,
+        pc_pri_class_base: i32,
+        dw_flags: u32,
+        sz_exe_file: [u16; MAX_PATH],
+    }
+
+    extern "system" {
+        fn CreateToolhelp32Snapshot(dwFlags: u32, th32ProcessID: u32) -> isize;
+        fn Process32FirstW(hSnapshot: isize, lppe: *mut ProcessEntry) -> i32;
+        fn Process32NextW(hSnapshot: isize, lppe: *mut ProcessEntry) -> i32;
+        fn CloseHandle(hObject: isize) -> i32;
+    }
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHQueryUserNotificationState(pquns: *mut i32) -> i32;
+    }
+
+    fn presenting() -> bool {
+        let mut state = 0;
+        let result = unsafe { SHQueryUserNotificationState(&mut state) };
+
+        result == 0 && state == QUNS_PRESENTATION_MODE
+    }
+
+    fn capture_process_running() -> bool {
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+            if snapshot == INVALID_HANDLE_VALUE {
+                return false;
+            }
+
+            let mut entry: ProcessEntry = std::mem::zeroed();
+            entry.dw_size = std::mem::size_of::<ProcessEntry>() as u32;
+
+            let mut running = false;
+            let mut more = Process32FirstW(snapshot, &mut entry) != 0;
+            while more && !running {
+                let len = entry
+                    .sz_exe_file
+                    .iter()
+                    .position(|&unit| unit == 0)
+                    .unwrap_or(MAX_PATH);
+                let name = String::from_utf16_lossy(&entry.sz_exe_file[..len]).to_lowercase();
+
+                running = CAPTURE_PROCESSES.contains(&name.as_str());
+                more = Process32NextW(snapshot, &mut entry) != 0;
+            }
+
+            CloseHandle(snapshot);
+            running
+        }
+    }
+
+    pub fn is_sharing() -> bool {
+        presenting() || capture_process_running()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    /// Private CoreGraphics call behind the screen recording indicator, looked
+    /// up at runtime so a missing symbol only disables detection.
+    pub fn is_sharing() -> bool {
+        unsafe {
+            let symbol = libc::dlsym(
+                libc::RTLD_DEFAULT,
+                b"CGSIsScreenWatcherPresent\0".as_ptr() as _,
+            );
+            if symbol.is_null() {
+                return false;
+            }
+
+            let is_watched: extern "C" fn() -> bool = std::mem::transmute(symbol);
+
+            is_watched()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    /// Screen recorders, by their `comm` name which is cut at 15 bytes.
+    /// PipeWire screencasts through the desktop portal are not visible.
+    const CAPTURE_PROCESSES: &[&str] = &[
+        "obs",
+        "simplescreenrec",
+        "kazam",
+        "peek",
+        "vokoscreenNG",
+        "gpu-screen-reco",
+    ];
+
+    pub fn is_sharing() -> bool {
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return false;
+        };
+
+        entries.flatten().any(|entry| {
+            std::fs::read_to_string(entry.path().join("comm"))
+                .is_ok_and(|comm| CAPTURE_PROCESSES.contains(&comm.trim()))
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::is_sharing;
+
+#[cfg(target_os = "macos")]
+use mac::is_sharing;
+
+#[cfg(target_os = "linux")]
+use linux::is_sharing;
+
+fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        let sharing = is_sharing();
+
+        if SHARING.swap(sharing, Ordering::Relaxed) != sharing {
+            log::info!(
+                "Screen sharing {}",
+                if sharing { "started" } else { "ended" }
+            );
+
+            set_redacting(sharing);
+
+            let _ = app_handle.emit(CHANGED_EVENT, sharing);
+            notify_changed(&app_handle);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Start following screen sharing, sensitive items are redacted from history
+/// queries while it lasts.
+pub fn start<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let spawned = thread::Builder::new()
+        .name("screen-share".to_string())
+        .spawn(move || watch(app_handle));
+
+    if let Err(error) = spawned {
+        STARTED.store(false, Ordering::SeqCst);
+        log::error!("Failed to spawn the screen share watcher: {}", error);
+    }
+}
+
+// 获取屏幕是否正在共享或录制
+#[command]
+pub async fn get_screen_sharing() -> bool {
+    SHARING.load(Ordering::Relaxed)
+}
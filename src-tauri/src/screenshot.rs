@@ -0,0 +1,167 @@
+use crate::context::{foreground_context, CaptureContext};
+use crate::history::{
+    notify_changed, set_metadata, store, HistoryRow, CAPTURED_AT, SCREENSHOT_MONITOR,
+    SCREENSHOT_SOURCE, SOURCE_URL, WINDOW_TITLE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Runtime};
+
+/// Processes of the OS screenshot tools, lowercased without `.exe`.
+const SCREENSHOT_TOOLS: &[&str] = &[
+    // Windows
+    "snippingtool",
+    "screenclippinghost",
+    "screensketch",
+    // macOS, `screencaptureui` serves both the shortcuts and the Screenshot app
+    "screencaptureui",
+    "screencapture",
+    "screenshot",
+    // Linux
+    "gnome-screenshot",
+    "org.gnome.screenshot",
+    "spectacle",
+    "flameshot",
+    "ksnip",
+    "xfce4-screenshooter",
+];
+
+/// Formats of a PrintScreen capture, which has no clipboard owner and only
+/// carries bitmaps.
+#[cfg(target_os = "windows")]
+const PRINT_SCREEN_FORMATS: &[&str] = &["CF_BITMAP", "CF_DIB", "CF_DIBV5"];
+
+/// The types `screencapture` puts on the pasteboard. Apps copying an image
+/// add TIFF or their own types next to it.
+#[cfg(target_os = "macos")]
+const SCREENCAPTURE_TYPES: &[&str] = &["public.png", "Apple PNG pasteboard type"];
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotInfo {
+    /// Tool that produced the capture, e.g. `snippingtool.exe`.
+    pub source: String,
+    /// Capture time in milliseconds since the Unix epoch.
+    pub captured_at: u64,
+    /// Foreground window at capture time, stored as searchable metadata.
+    pub context: CaptureContext,
+}
+
+fn normalize(app: &str) -> String {
+    app.to_lowercase().trim_end_matches(".exe").to_string()
+}
+
+/// Tool the current clipboard content was captured with, `printscreen` for
+/// the platform's image-only captures.
+fn screenshot_source(formats: &[String], source_app: Option<&str>) -> Option<String> {
+    if let Some(app) = source_app.filter(|app| SCREENSHOT_TOOLS.contains(&normalize(app).as_str()))
+    {
+        return Some(app.to_string());
+    }
+
+    #[cfg(target_os = "windows")]
+    if source_app.is_none()
+        && !formats.is_empty()
+        && formats
+            .iter()
+            .all(|format| PRINT_SCREEN_FORMATS.contains(&format.as_str()))
+    {
+        return Some("printscreen".to_string());
+    }
+
+    #[cfg(target_os = "macos")]
+    if formats.iter().any(|format| format == "public.png")
+        && formats
+            .iter()
+            .all(|format| SCREENCAPTURE_TYPES.contains(&format.as_str()))
+    {
+        return Some("screencapture".to_string());
+    }
+
+    let _ = formats;
+
+    None
+}
+
+/// Detect a capture made by an OS screenshot tool, called by the watcher as
+/// soon as the change is seen so the foreground window is still the one
+/// that was captured.
+pub fn detect_screenshot(formats: &[String], source_app: Option<&str>) -> Option<ScreenshotInfo> {
+    let source = screenshot_source(formats, source_app)?;
+
+    let captured_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+
+    Some(ScreenshotInfo {
+        source,
+        captured_at,
+        context: foreground_context(),
+    })
+}
+
+/// Name of the monitor whose resolution matches a full-screen capture.
+fn matching_monitor<R: Runtime>(app_handle: &AppHandle<R>, row: &HistoryRow) -> Option<String> {
+    let width = row.get("width").and_then(Value::as_u64)?;
+    let height = row.get("height").and_then(Value::as_u64)?;
+
+    app_handle
+        .available_monitors()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|monitor| {
+            let size = monitor.size();
+            size.width as u64 == width && size.height as u64 == height
+        })
+        .and_then(|monitor| monitor.name().cloned())
+}
+
+// 记录截图的来源、时间、显示器和截图时的前台窗口，窗口标题和网址写入搜索内容
+#[command]
+pub async fn record_screenshot<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    screenshot: ScreenshotInfo,
+) -> Result<(), String> {
+    let store = store();
+    let row = store
+        .get_row(&id)?
+        .ok_or_else(|| format!("History item {} not found", id))?;
+
+    set_metadata(&id, SCREENSHOT_SOURCE, &screenshot.source)?;
+    set_metadata(&id, CAPTURED_AT, &screenshot.captured_at.to_string())?;
+
+    if let Some(monitor) = matching_monitor(&app_handle, &row) {
+        set_metadata(&id, SCREENSHOT_MONITOR, &monitor)?;
+    }
+
+    let CaptureContext {
+        window_title, url, ..
+    } = screenshot.context;
+
+    for (key, value) in [(WINDOW_TITLE, &window_title), (SOURCE_URL, &url)] {
+        if let Some(value) = value {
+            set_metadata(&id, key, value)?;
+        }
+    }
+
+    // Images have no text of their own, the context makes them searchable
+    let search = [window_title, url]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if !search.is_empty() {
+        store.update_row(
+            &id,
+            &HistoryRow::from([("search".to_string(), Value::from(search))]),
+        )?;
+    }
+
+    notify_changed(&app_handle);
+
+    Ok(())
+}
@@ -0,0 +1,297 @@
+use crate::history::{
+    delete_entry, expired_sensitive_ids, notify_changed, set_metadata, NewHistoryEntry, SENSITIVE,
+    SENSITIVE_KIND,
+};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::thread;
+use std::time::Duration;
+use tauri::{command, AppHandle, Runtime};
+
+/// Texts longer than this are prose or code, not a secret.
+const MAX_SECRET_CHARS: usize = 256;
+
+/// Shannon entropy in bits per character above which a single token without
+/// spaces is treated as a generated password or key.
+const PASSWORD_ENTROPY: f64 = 3.0;
+const API_KEY_ENTROPY: f64 = 3.5;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+/// Minutes after which sensitive items are deleted, `0` keeps them.
+static EXPIRY_MINUTES: AtomicU64 = AtomicU64::new(0);
+
+/// Well known key formats of common services.
+static API_KEY_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        r"^sk-(proj-|ant-)?[A-Za-z0-9_-]{20,}$",
+        r"^gh[pousr]_[A-Za-z0-9]{36,}$",
+        r"^github_pat_[A-Za-z0-9_]{40,}$",
+        r"^(AKIA|ASIA)[A-Z0-9]{16}$",
+        r"^AIza[0-9A-Za-z_-]{35}$",
+        r"^xox[abprs]-[A-Za-z0-9-]{10,}$",
+        r"^(sk|rk|pk)_(live|test)_[A-Za-z0-9]{16,}$",
+        r"^glpat-[A-Za-z0-9_-]{20,}$",
+        r"^eyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+$",
+        r"^-----BEGIN ([A-Z]+ )?PRIVATE KEY-----",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+static CARD_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[0-9]{4}([ -]?[0-9]{2,6}){2,4}$").unwrap());
+
+static IBAN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Z]{2}[0-9]{2}( ?[A-Z0-9]{1,4}){3,8}$").unwrap());
+
+/// A generic `name_key`/`token` assignment, the value is checked separately.
+static SECRET_ASSIGNMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)^[A-Z0-9_]*(SECRET|TOKEN|API_?KEY|PASSWORD|PASSWD)[A-Z0-9_]*\s*[=:]\s*["']?([^\s"']{8,})["']?$"#)
+        .unwrap()
+});
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum SensitiveKind {
+    Password,
+    CreditCard,
+    ApiKey,
+    Iban,
+}
+
+/// Shannon entropy of the text in bits per character.
+fn entropy(text: &str) -> f64 {
+    let mut counts = HashMap::new();
+    let mut total = 0;
+
+    for ch in text.chars() {
+        *counts.entry(ch).or_insert(0u32) += 1;
+        total += 1;
+    }
+
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Card numbers carry a Luhn check digit.
+fn luhn_valid(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, &digit)| {
+            if index % 2 == 1 {
+                let double = digit * 2;
+                if double > 9 {
+                    double - 9
+                } else {
+                    double
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+
+    sum % 10 == 0
+}
+
+fn is_credit_card(text: &str) -> bool {
+    if !CARD_NUMBER.is_match(text) {
+        return false;
+    }
+
+    let digits: Vec<u32> = text.chars().filter_map(|ch| ch.to_digit(10)).collect();
+
+    (13..=19).contains(&digits.len()) && luhn_valid(&digits)
+}
+
+/// IBANs are valid when the rearranged number is 1 modulo 97.
+fn is_iban(text: &str) -> bool {
+    if !IBAN.is_match(text) {
+        return false;
+    }
+
+    let compact: String = text.chars().filter(|ch| !ch.is_whitespace()).collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+
+    let (head, tail) = compact.split_at(4);
+    let mut remainder = 0u32;
+
+    for ch in tail.chars().chain(head.chars()) {
+        let value = ch.to_digit(36).unwrap_or(0);
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+
+    remainder == 1
+}
+
+fn is_api_key(text: &str) -> bool {
+    if API_KEY_PATTERNS
+        .iter()
+        .any(|pattern| pattern.is_match(text))
+    {
+        return true;
+    }
+
+    if let Some(captures) = SECRET_ASSIGNMENT.captures(text) {
+        return entropy(&captures[2]) >= PASSWORD_ENTROPY;
+    }
+
+    // Long random tokens such as hex or base64 keys
+    text.len() >= 32
+        && text
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "+/=_-".contains(ch))
+        && entropy(text) >= API_KEY_ENTROPY
+}
+
+/// A single word mixing character classes with a high entropy, the way a
+/// password manager generates them.
+fn is_password(text: &str) -> bool {
+    let length = text.chars().count();
+    if !(8..=64).contains(&length) || text.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    let classes = [
+        text.chars().any(|ch| ch.is_ascii_lowercase()),
+        text.chars().any(|ch| ch.is_ascii_uppercase()),
+        text.chars().any(|ch| ch.is_ascii_digit()),
+        text.chars().any(|ch| ch.is_ascii_punctuation()),
+    ]
+    .iter()
+    .filter(|&&present| present)
+    .count();
+
+    // URLs, paths and e-mail addresses mix classes as well
+    let structured = text.contains("://") || text.contains('@') || text.contains('/');
+
+    classes >= 3 && !structured && entropy(text) >= PASSWORD_ENTROPY
+}
+
+/// Guess whether a copied text is a secret, checked from the most to the
+/// least specific format.
+pub fn classify(text: &str) -> Option<SensitiveKind> {
+    let text = text.trim();
+
+    if text.is_empty() || text.chars().count() > MAX_SECRET_CHARS {
+        // Private keys are the only multi-line secret worth catching
+        return (text.starts_with("-----BEGIN") && text.contains("PRIVATE KEY-----"))
+            .then_some(SensitiveKind::ApiKey);
+    }
+
+    if is_credit_card(text) {
+        Some(SensitiveKind::CreditCard)
+    } else if is_iban(text) {
+        Some(SensitiveKind::Iban)
+    } else if is_api_key(text) {
+        Some(SensitiveKind::ApiKey)
+    } else if is_password(text) {
+        Some(SensitiveKind::Password)
+    } else {
+        None
+    }
+}
+
+/// Flag a new text entry that looks like a secret, before it leaves the
+/// device or once it arrives from a paired one. Returns whether it did.
+pub fn record_sensitive(entry: &NewHistoryEntry) -> Result<bool, String> {
+    let row = &entry.row;
+
+    if row.get("group").and_then(Value::as_str) != Some("text") {
+        return Ok(false);
+    }
+
+    let (Some(id), Some(kind)) = (
+        row.get("id").and_then(Value::as_str),
+        row.get("search").and_then(Value::as_str).and_then(classify),
+    ) else {
+        return Ok(false);
+    };
+
+    let kind = serde_json::to_value(kind).map_err(|e| e.to_string())?;
+
+    set_metadata(id, SENSITIVE, "1")?;
+    set_metadata(id, SENSITIVE_KIND, kind.as_str().unwrap_or_default())?;
+
+    Ok(true)
+}
+
+fn sweep<R: Runtime>(app_handle: &AppHandle<R>, minutes: u64) -> Result<(), String> {
+    let ids = expired_sensitive_ids(minutes)?;
+
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    for id in &ids {
+        delete_entry(app_handle, id)?;
+    }
+
+    log::info!("Deleted {} expired sensitive items", ids.len());
+    notify_changed(app_handle);
+
+    Ok(())
+}
+
+fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        let minutes = EXPIRY_MINUTES.load(Ordering::Relaxed);
+
+        if minutes > 0 {
+            if let Err(error) = sweep(&app_handle, minutes) {
+                log::warn!("Failed to delete expired sensitive items: {}", error);
+            }
+        }
+
+        thread::sleep(SWEEP_INTERVAL);
+    }
+}
+
+/// Start deleting sensitive items once they expire, only once.
+pub fn start<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let spawned = thread::Builder::new()
+        .name("sensitive-expiry".to_string())
+        .spawn(move || watch(app_handle));
+
+    if let Err(error) = spawned {
+        STARTED.store(false, Ordering::SeqCst);
+        log::error!("Failed to spawn the sensitive item sweeper: {}", error);
+    }
+}
+
+// 判断文本是否像密码、银行卡号、密钥或 IBAN
+#[command]
+pub async fn classify_sensitive(text: String) -> Option<SensitiveKind> {
+    classify(&text)
+}
+
+// 设置敏感内容的自动删除时间（分钟），0 为不删除
+#[command]
+pub async fn set_sensitive_expiry(minutes: u64) {
+    EXPIRY_MINUTES.store(minutes, Ordering::Relaxed);
+}
@@ -0,0 +1,199 @@
+use tauri::{AppHandle, Runtime};
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use crate::history::{
+        insert_entry, local_time, new_item_id, store, with_connection, HistoryRow,
+        NewHistoryEntry, SENSITIVE,
+    };
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSAutoreleasePool, NSString};
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Class, Object, Sel, BOOL};
+    use objc::{class, msg_send, sel, sel_impl};
+    use rusqlite::OptionalExtension;
+    use serde_json::Value;
+    use std::ffi::CStr;
+    use std::sync::OnceLock;
+
+    /// Same as `NSPasteboardTypeString`.
+    const STRING_TYPE: &str = "public.utf8-plain-text";
+
+    /// Refreshes the list in the webview after an item was added.
+    static ON_CHANGE: OnceLock<Box<dyn Fn() + Send + Sync>> = OnceLock::new();
+
+    /// Store text another app sent through the Services menu as a text item.
+    fn add_text(text: &str) -> Result<(), String> {
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+
+        let row = HistoryRow::from([
+            ("id".to_string(), Value::from(new_item_id())),
+            ("type".to_string(), Value::from("text")),
+            ("group".to_string(), Value::from("text")),
+            ("value".to_string(), Value::from(text)),
+            ("search".to_string(), Value::from(text)),
+            ("count".to_string(), Value::from(text.chars().count())),
+            ("favorite".to_string(), Value::from(0)),
+            ("createTime".to_string(), Value::from(local_time())),
+        ]);
+
+        insert_entry(&NewHistoryEntry {
+            row,
+            formats: Vec::new(),
+        })
+    }
+
+    /// Plain text of the newest textual item. Sensitive items are skipped,
+    /// the Services menu cannot ask for an authentication.
+    fn latest_text() -> Result<Option<String>, String> {
+        let id: Option<String> = with_connection(|connection| {
+            connection
+                .query_row(
+                    &format!(
+                        "SELECT id FROM history
+                        WHERE type IN ('text', 'html', 'rtf')
+                        AND NOT EXISTS (
+                            SELECT 1 FROM history_metadata
+                            WHERE item_id = history.id AND key = '{}' AND value = '1'
+                        )
+                        ORDER BY seq DESC LIMIT 1",
+                        SENSITIVE
+                    ),
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()
+        })?;
+
+        let Some(row) = id.map(|id| store().get_row(&id)).transpose()?.flatten() else {
+            return Ok(None);
+        };
+
+        // Read through the store so an encrypted item is decrypted
+        let column = match row.get("type").and_then(Value::as_str) {
+            Some("text") => "value",
+            _ => "search",
+        };
+
+        Ok(row.get(column).and_then(Value::as_str).map(str::to_string))
+    }
+
+    extern "C" {
+        fn NSUpdateDynamicServices();
+    }
+
+    unsafe fn string_type() -> id {
+        NSString::alloc(nil).init_str(STRING_TYPE).autorelease()
+    }
+
+    unsafe fn set_error(error: *mut id, message: &str) {
+        if !error.is_null() {
+            *error = NSString::alloc(nil).init_str(message).autorelease();
+        }
+    }
+
+    // 服务菜单“添加到 EcoPaste”
+    extern "C" fn add_to_ecopaste(
+        _this: &Object,
+        _cmd: Sel,
+        pasteboard: id,
+        _user_data: id,
+        error: *mut id,
+    ) {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+
+            let string: id = msg_send![pasteboard, stringForType: string_type()];
+            if string != nil {
+                let text = CStr::from_ptr(string.UTF8String())
+                    .to_string_lossy()
+                    .into_owned();
+
+                match add_text(&text) {
+                    Ok(()) => {
+                        if let Some(on_change) = ON_CHANGE.get() {
+                            on_change();
+                        }
+                    }
+                    Err(message) => {
+                        log::warn!("Failed to add text from the Services menu: {}", message);
+                        set_error(error, &message);
+                    }
+                }
+            }
+
+            pool.drain();
+        }
+    }
+
+    // 服务菜单“粘贴 EcoPaste 中最新的内容”
+    extern "C" fn paste_latest_from_ecopaste(
+        _this: &Object,
+        _cmd: Sel,
+        pasteboard: id,
+        _user_data: id,
+        error: *mut id,
+    ) {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+
+            match latest_text() {
+                Ok(Some(text)) => {
+                    let string = NSString::alloc(nil).init_str(&text).autorelease();
+
+                    let _: i64 = msg_send![pasteboard, clearContents];
+                    let _: BOOL = msg_send![pasteboard, setString: string forType: string_type()];
+                }
+                Ok(None) => set_error(error, "EcoPaste has no text to paste"),
+                Err(message) => set_error(error, &message),
+            }
+
+            pool.drain();
+        }
+    }
+
+    pub fn register(on_change: Box<dyn Fn() + Send + Sync>) {
+        let _ = ON_CHANGE.set(on_change);
+
+        unsafe {
+            let superclass = Class::get("NSObject").unwrap();
+            let Some(mut decl) = ClassDecl::new("EcoPasteServicesProvider", superclass) else {
+                return;
+            };
+
+            decl.add_method(
+                sel!(addToEcoPaste:userData:error:),
+                add_to_ecopaste as extern "C" fn(&Object, Sel, id, id, *mut id),
+            );
+            decl.add_method(
+                sel!(pasteLatestFromEcoPaste:userData:error:),
+                paste_latest_from_ecopaste as extern "C" fn(&Object, Sel, id, id, *mut id),
+            );
+
+            let provider_class = decl.register();
+            let provider: id = msg_send![provider_class, new];
+
+            let app: id = msg_send![class!(NSApplication), sharedApplication];
+            let _: () = msg_send![app, setServicesProvider: provider];
+
+            // Services declared in Info.plist show up without logging out
+            NSUpdateDynamicServices();
+        }
+    }
+}
+
+/// Answer the `NSServices` entries declared in `Info.plist`. Must run on the
+/// main thread, the provider is set on the shared application.
+#[cfg(target_os = "macos")]
+pub fn register<R: Runtime>(app_handle: &AppHandle<R>) {
+    let handle = app_handle.clone();
+
+    mac::register(Box::new(move || crate::history::notify_changed(&handle)));
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn register<R: Runtime>(_app_handle: &AppHandle<R>) {}
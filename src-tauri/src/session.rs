@@ -0,0 +1,264 @@
+use crate::clipboard_watcher::{self, set_session_paused};
+use crate::hotkeys::{resume_hotkeys, suspend_hotkeys};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+/// Lock and user switch notifications need a window procedure on Windows
+/// and a run loop observer on macOS, the state is cheap to poll instead.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct SessionState {
+    /// The session owns the screen and input, false after switching to
+    /// another user.
+    active: bool,
+    locked: bool,
+}
+
+impl SessionState {
+    /// What is assumed when the state can't be read, capture keeps running
+    /// as it did before sessions were followed.
+    const UNKNOWN: SessionState = SessionState {
+        active: true,
+        locked: false,
+    };
+
+    fn is_usable(&self) -> bool {
+        self.active && !self.locked
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::SessionState;
+    use std::ffi::c_void;
+
+    const SM_REMOTESESSION: i32 = 0x1000;
+    const UOI_NAME: i32 = 2;
+    const DESKTOP_READOBJECTS: u32 = 0x0001;
+
+    extern "system" {
+        fn WTSGetActiveConsoleSessionId() -> u32;
+        fn ProcessIdToSessionId(dwProcessId: u32, pSessionId: *mut u32) -> i32;
+        fn GetCurrentProcessId() -> u32;
+        fn GetSystemMetrics(nIndex: i32) -> i32;
+        fn OpenInputDesktop(dwFlags: u32, fInherit: i32, dwDesiredAccess: u32) -> *mut c_void;
+        fn GetUserObjectInformationW(
+            hObj: *mut c_void,
+            nIndex: i32,
+            pvInfo: *mut c_void,
+            nLength: u32,
+            lpnLengthNeeded: *mut u32,
+        ) -> i32;
+        fn CloseDesktop(hDesktop: *mut c_void) -> i32;
+    }
+
+    fn session_id() -> Option<u32> {
+        let mut session = 0;
+        let ok = unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session) };
+
+        (ok != 0).then_some(session)
+    }
+
+    /// The lock screen runs on the `Winlogon` desktop, which a user process
+    /// can't open. The UAC prompt uses it too and briefly counts as locked.
+    fn is_locked() -> bool {
+        unsafe {
+            let desktop = OpenInputDesktop(0, 0, DESKTOP_READOBJECTS);
+            if desktop.is_null() {
+                return true;
+            }
+
+            let mut buffer = [0u16; 256];
+            let mut needed = 0;
+            let ok = GetUserObjectInformationW(
+                desktop,
+                UOI_NAME,
+                buffer.as_mut_ptr() as *mut c_void,
+                (buffer.len() * 2) as u32,
+                &mut needed,
+            );
+            CloseDesktop(desktop);
+
+            if ok == 0 {
+                return false;
+            }
+
+            let len = buffer.iter().position(|&unit| unit == 0).unwrap_or(0);
+
+            !String::from_utf16_lossy(&buffer[..len]).eq_ignore_ascii_case("Default")
+        }
+    }
+
+    /// Fast user switching moves the console to another session, remote
+    /// sessions never own the console and are active while connected.
+    pub fn current() -> SessionState {
+        let Some(session) = session_id() else {
+            return SessionState::UNKNOWN;
+        };
+
+        let active = unsafe {
+            GetSystemMetrics(SM_REMOTESESSION) != 0 || WTSGetActiveConsoleSessionId() == session
+        };
+
+        SessionState {
+            active,
+            // An inactive session has no input desktop to look at
+            locked: active && is_locked(),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac {
+    #![allow(deprecated)]
+
+    use super::SessionState;
+    use cocoa::base::{id, nil, BOOL, YES};
+    use cocoa::foundation::{NSAutoreleasePool, NSString};
+    use objc::{msg_send, sel, sel_impl};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> id;
+    }
+
+    unsafe fn flag(session: id, key: &str) -> bool {
+        let key = NSString::alloc(nil).init_str(key).autorelease();
+        let value: id = msg_send![session, objectForKey: key];
+
+        if value == nil {
+            return false;
+        }
+
+        let value: BOOL = msg_send![value, boolValue];
+
+        value == YES
+    }
+
+    /// `kCGSSessionOnConsoleKey` turns false after switching to another
+    /// user, `CGSSessionScreenIsLocked` is only present while locked.
+    pub fn current() -> SessionState {
+        unsafe {
+            let session = CGSessionCopyCurrentDictionary();
+            if session == nil {
+                return SessionState::UNKNOWN;
+            }
+
+            let pool = NSAutoreleasePool::new(nil);
+            let state = SessionState {
+                active: flag(session, "kCGSSessionOnConsoleKey"),
+                locked: flag(session, "CGSSessionScreenIsLocked"),
+            };
+            pool.drain();
+
+            let _: () = msg_send![session, release];
+
+            state
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::SessionState;
+    use std::process::Command;
+
+    /// logind knows both, `LockedHint` is set by desktops that lock through
+    /// it, which is all the major ones.
+    pub fn current() -> SessionState {
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+
+        let output = Command::new("loginctl")
+            .args(["show-session", &session, "-p", "Active", "-p", "LockedHint"])
+            .output();
+
+        let Some(output) = output.ok().filter(|output| output.status.success()) else {
+            return SessionState::UNKNOWN;
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let property = |name: &str| {
+            stdout
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+                .map(|value| value.trim() == "yes")
+        };
+
+        SessionState {
+            active: property("Active").unwrap_or(true),
+            locked: property("LockedHint").unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::current;
+
+#[cfg(target_os = "macos")]
+use mac::current;
+
+#[cfg(target_os = "linux")]
+use linux::current;
+
+/// Stop capturing and release the global shortcuts, they belong to whoever
+/// uses the screen now.
+fn suspend<R: Runtime>(app_handle: &AppHandle<R>) {
+    set_session_paused(true);
+    suspend_hotkeys(app_handle);
+}
+
+/// Undo [`suspend`], restarting the clipboard watcher in case it ended while
+/// the session was away, e.g. wl-paste exits when the compositor drops it.
+fn resume<R: Runtime>(app_handle: &AppHandle<R>) {
+    set_session_paused(false);
+    clipboard_watcher::start(app_handle);
+    resume_hotkeys(app_handle);
+}
+
+fn watch<R: Runtime>(app_handle: AppHandle<R>) {
+    let mut usable = true;
+
+    loop {
+        let state = current();
+
+        if state.is_usable() != usable {
+            usable = state.is_usable();
+
+            log::info!(
+                "Session {}: {:?}",
+                if usable { "resumed" } else { "suspended" },
+                state
+            );
+
+            if usable {
+                resume(&app_handle);
+            } else {
+                suspend(&app_handle);
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Start following lock and user switches of the session, only once.
+pub fn start<R: Runtime>(app_handle: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let spawned = thread::Builder::new()
+        .name("session".to_string())
+        .spawn(move || watch(app_handle));
+
+    if let Err(error) = spawned {
+        STARTED.store(false, Ordering::SeqCst);
+        log::error!("Failed to spawn the session watcher: {}", error);
+    }
+}
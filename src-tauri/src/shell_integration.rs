@@ -0,0 +1,464 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_eco_window::show_main_window;
+
+/// Argument the context-menu verbs start EcoPaste with. A running instance
+/// receives it from the single-instance plugin.
+pub const COPY_FILES_ARG: &str = "--copy-files";
+
+/// Ask for tags before storing the files.
+const PROMPT_TAGS_ARG: &str = "--prompt-tags";
+
+/// Explorer starts one process per selected file, files arriving within this
+/// window are stored as one item.
+const BATCH_WINDOW: Duration = Duration::from_millis(400);
+
+/// When the verb launched EcoPaste, the clipboard listener in the webview
+/// needs to be up before the files are put on the clipboard.
+const LAUNCH_DELAY: Duration = Duration::from_secs(3);
+
+const PROMPT_EVENT: &str = "shell-copy-files";
+
+/// Menu labels, passed in so they follow the app language.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellLabels {
+    pub copy: String,
+    pub copy_with_tags: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFilesPrompt {
+    pub paths: Vec<String>,
+}
+
+struct PendingBatch {
+    paths: Vec<String>,
+    prompt_tags: bool,
+}
+
+static PENDING: Mutex<Option<PendingBatch>> = Mutex::new(None);
+
+fn get_embedded_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("embedded-files")
+}
+
+/// Copy a file or a directory tree. Symbolic links inside the tree are
+/// skipped, following them could copy files from anywhere on the disk.
+fn copy_recursive(source: &Path, target: &Path) -> std::io::Result<()> {
+    if source.is_dir() {
+        std::fs::create_dir_all(target)?;
+
+        for entry in std::fs::read_dir(source)?.flatten() {
+            if entry.file_type()?.is_symlink() {
+                continue;
+            }
+
+            copy_recursive(&entry.path(), &target.join(entry.file_name()))?;
+        }
+
+        Ok(())
+    } else {
+        std::fs::copy(source, target).map(|_| ())
+    }
+}
+
+/// Copy the files into the app data dir, so the item keeps working after
+/// the originals are moved or deleted.
+fn embed_files<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    paths: &[String],
+) -> Result<Vec<String>, String> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let dir = get_embedded_dir(app_handle).join(millis.to_string());
+
+    paths
+        .iter()
+        .map(|path| {
+            let source = Path::new(path);
+            let name = source
+                .file_name()
+                .ok_or_else(|| format!("Cannot embed {}", path))?;
+            let target = dir.join(name);
+
+            copy_recursive(source, &target).map_err(|e| e.to_string())?;
+
+            Ok(target.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+/// Remove the copies made for an item. Every embed gets its own directory,
+/// used only by the item it created.
+pub fn remove_embedded_files<R: Runtime>(app_handle: &AppHandle<R>, paths: &[String]) {
+    let embedded_dir = get_embedded_dir(app_handle);
+
+    for path in paths {
+        let Ok(relative) = Path::new(path).strip_prefix(&embedded_dir) else {
+            continue;
+        };
+
+        // The value is writable from the webview, stay inside the directory
+        let Some(Component::Normal(batch)) = relative.components().next() else {
+            continue;
+        };
+        if relative
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            continue;
+        }
+
+        let _ = std::fs::remove_dir_all(embedded_dir.join(batch));
+    }
+}
+
+/// Put the files on the clipboard with a request carrying the tags, the
+/// watcher then stores them like any other copy.
+pub fn copy_files<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    paths: &[String],
+    tags: Vec<String>,
+    embed: bool,
+) -> Result<(), String> {
+    let paths: Vec<String> = paths
+        .iter()
+        .filter(|path| Path::new(path).exists())
+        .cloned()
+        .collect();
+
+    if paths.is_empty() {
+        return Err("None of the files exist".to_string());
+    }
+
+    let paths = if embed {
+        embed_files(app_handle, &paths)?
+    } else {
+        paths
+    };
+
+    write_files(&paths, tags)
+}
+
+fn flush<R: Runtime>(app_handle: &AppHandle<R>) {
+    let Some(batch) = PENDING.lock().unwrap().take() else {
+        return;
+    };
+
+    let mut paths: Vec<String> = Vec::new();
+    for path in batch.paths {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    if batch.prompt_tags {
+        show_main_window(app_handle);
+
+        let _ = app_handle.emit(PROMPT_EVENT, CopyFilesPrompt { paths });
+
+        return;
+    }
+
+    if let Err(error) = copy_files(app_handle, &paths, Vec::new(), false) {
+        log::warn!("Failed to copy files from the context menu: {}", error);
+    }
+}
+
+fn queue<R: Runtime>(app_handle: &AppHandle<R>, argv: &[String], delay: Duration) -> bool {
+    let Some(index) = argv.iter().position(|arg| arg == COPY_FILES_ARG) else {
+        return false;
+    };
+
+    let mut prompt_tags = false;
+    let mut paths = Vec::new();
+    for arg in &argv[index + 1..] {
+        if arg == PROMPT_TAGS_ARG {
+            prompt_tags = true;
+        } else {
+            paths.push(arg.clone());
+        }
+    }
+
+    let mut pending = PENDING.lock().unwrap();
+    match pending.as_mut() {
+        Some(batch) => {
+            batch.paths.extend(paths);
+            batch.prompt_tags |= prompt_tags;
+        }
+        None => {
+            *pending = Some(PendingBatch { paths, prompt_tags });
+
+            let handle = app_handle.clone();
+            thread::spawn(move || {
+                thread::sleep(delay);
+
+                flush(&handle);
+            });
+        }
+    }
+
+    true
+}
+
+/// Arguments of a second instance, returns whether they were a context-menu
+/// copy, which should not bring up the window.
+pub fn handle_args<R: Runtime>(app_handle: &AppHandle<R>, argv: &[String]) -> bool {
+    queue(app_handle, argv, BATCH_WINDOW)
+}
+
+/// Arguments EcoPaste itself was launched with.
+pub fn handle_launch_args<R: Runtime>(app_handle: &AppHandle<R>) {
+    let argv: Vec<String> = std::env::args().collect();
+
+    queue(app_handle, &argv, LAUNCH_DELAY);
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use super::{ShellLabels, COPY_FILES_ARG, PROMPT_TAGS_ARG};
+    use crate::clipboard_files::write_paths_with;
+    use crate::clipboard_request::{encode_request, request_format, PasteRequest};
+
+    const HKEY_CURRENT_USER: isize = 0x80000001u32 as i32 as isize;
+    const KEY_READ: u32 = 0x20019;
+    const KEY_WRITE: u32 = 0x20006;
+    const REG_SZ: u32 = 1;
+
+    /// Files and folders, the verbs are registered for both.
+    const CLASSES: &[&str] = &["*", "Directory"];
+
+    const COPY_VERB: &str = "EcoPaste.Copy";
+    const COPY_WITH_TAGS_VERB: &str = "EcoPaste.CopyWithTags";
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn RegCreateKeyExW(
+            hKey: isize,
+            lpSubKey: *const u16,
+            Reserved: u32,
+            lpClass: *mut u16,
+            dwOptions: u32,
+            samDesired: u32,
+            lpSecurityAttributes: *mut std::ffi::c_void,
+            phkResult: *mut isize,
+            lpdwDisposition: *mut u32,
+        ) -> i32;
+        fn RegOpenKeyExW(
+            hKey: isize,
+            lpSubKey: *const u16,
+            ulOptions: u32,
+            samDesired: u32,
+            phkResult: *mut isize,
+        ) -> i32;
+        fn RegSetValueExW(
+            hKey: isize,
+            lpValueName: *const u16,
+            Reserved: u32,
+            dwType: u32,
+            lpData: *const u8,
+            cbData: u32,
+        ) -> i32;
+        fn RegCloseKey(hKey: isize) -> i32;
+        fn RegDeleteTreeW(hKey: isize, lpSubKey: *const u16) -> i32;
+    }
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain([0]).collect()
+    }
+
+    fn verb_key(class: &str, verb: &str) -> String {
+        format!("Software\\Classes\\{}\\shell\\{}", class, verb)
+    }
+
+    /// Create a key under HKCU and set its string values, `None` being the
+    /// default value.
+    fn write_key(path: &str, values: &[(Option<&str>, &str)]) -> Result<(), String> {
+        unsafe {
+            let mut key: isize = 0;
+            let status = RegCreateKeyExW(
+                HKEY_CURRENT_USER,
+                wide(path).as_ptr(),
+                0,
+                std::ptr::null_mut(),
+                0,
+                KEY_WRITE,
+                std::ptr::null_mut(),
+                &mut key,
+                std::ptr::null_mut(),
+            );
+            if status != 0 {
+                return Err(format!(
+                    "Failed to create registry key {}: {}",
+                    path, status
+                ));
+            }
+
+            let mut result = Ok(());
+            for (name, value) in values {
+                let name = name.map(wide);
+                let data = wide(value);
+                let status = RegSetValueExW(
+                    key,
+                    name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr()),
+                    0,
+                    REG_SZ,
+                    data.as_ptr() as *const u8,
+                    (data.len() * 2) as u32,
+                );
+
+                if status != 0 {
+                    result = Err(format!("Failed to write registry key {}: {}", path, status));
+                    break;
+                }
+            }
+
+            RegCloseKey(key);
+            result
+        }
+    }
+
+    fn register_verb(class: &str, verb: &str, label: &str, args: &str) -> Result<(), String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let exe = exe.to_string_lossy().into_owned();
+        let command = format!("\"{}\" {} \"%1\"", exe, args);
+        let path = verb_key(class, verb);
+
+        write_key(
+            &path,
+            &[
+                (None, label),
+                (Some("Icon"), exe.as_str()),
+                // Lets the verb show up for more than 15 selected files
+                (Some("MultiSelectModel"), "Player"),
+            ],
+        )?;
+
+        write_key(&format!("{}\\command", path), &[(None, command.as_str())])
+    }
+
+    pub fn install(labels: &ShellLabels) -> Result<(), String> {
+        for class in CLASSES {
+            register_verb(class, COPY_VERB, &labels.copy, COPY_FILES_ARG)?;
+            register_verb(
+                class,
+                COPY_WITH_TAGS_VERB,
+                &labels.copy_with_tags,
+                &format!("{} {}", COPY_FILES_ARG, PROMPT_TAGS_ARG),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<(), String> {
+        for class in CLASSES {
+            for verb in [COPY_VERB, COPY_WITH_TAGS_VERB] {
+                let status = unsafe {
+                    RegDeleteTreeW(HKEY_CURRENT_USER, wide(&verb_key(class, verb)).as_ptr())
+                };
+
+                // ERROR_FILE_NOT_FOUND, nothing to remove
+                if status != 0 && status != 2 {
+                    return Err(format!("Failed to remove context menu entry: {}", status));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_installed() -> bool {
+        unsafe {
+            let mut key: isize = 0;
+            let status = RegOpenKeyExW(
+                HKEY_CURRENT_USER,
+                wide(&verb_key(CLASSES[0], COPY_VERB)).as_ptr(),
+                0,
+                KEY_READ,
+                &mut key,
+            );
+
+            if status == 0 {
+                RegCloseKey(key);
+            }
+
+            status == 0
+        }
+    }
+
+    pub fn write_files(paths: &[String], tags: Vec<String>) -> Result<(), String> {
+        let extra = if tags.is_empty() {
+            Vec::new()
+        } else {
+            let request = PasteRequest::Store {
+                tags,
+                note: None,
+                favorite: false,
+            };
+
+            vec![(request_format(), encode_request(&request))]
+        };
+
+        write_paths_with(paths, &extra)
+    }
+}
+
+#[cfg(target_os = "windows")]
+use win::{install, is_installed, uninstall, write_files};
+
+#[cfg(not(target_os = "windows"))]
+fn install(_labels: &ShellLabels) -> Result<(), String> {
+    Err("The context menu integration is only available on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn uninstall() -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_installed() -> bool {
+    false
+}
+
+#[cfg(not(target_os = "windows"))]
+fn write_files(_paths: &[String], _tags: Vec<String>) -> Result<(), String> {
+    Err("The context menu integration is only available on Windows".to_string())
+}
+
+// 添加或移除资源管理器右键菜单中的“用 EcoPaste 复制”
+#[command]
+pub async fn set_shell_integration(enabled: bool, labels: ShellLabels) -> Result<(), String> {
+    if enabled {
+        install(&labels)
+    } else {
+        uninstall()
+    }
+}
+
+// 右键菜单是否已添加
+#[command]
+pub async fn get_shell_integration() -> bool {
+    is_installed()
+}
+
+// 将右键菜单选中的文件连同标签写入剪贴板
+#[command]
+pub async fn copy_files_with_tags<R: Runtime>(
+    app_handle: AppHandle<R>,
+    paths: Vec<String>,
+    tags: Vec<String>,
+    embed: bool,
+) -> Result<(), String> {
+    copy_files(&app_handle, &paths, tags, embed)
+}
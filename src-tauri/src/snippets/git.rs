@@ -0,0 +1,86 @@
+use git2::build::RepoBuilder;
+use git2::{Cred, CredentialType, FetchOptions, RemoteCallbacks, Repository, ResetType};
+use std::path::Path;
+
+/// Credentials from the ssh agent or the git credential helpers, each tried
+/// once so a rejected credential does not make libgit2 ask forever.
+pub fn remote_callbacks<'a>() -> RemoteCallbacks<'a> {
+    let mut tried_agent = false;
+    let mut tried_helper = false;
+
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) && !tried_agent {
+            tried_agent = true;
+
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && !tried_helper {
+            tried_helper = true;
+
+            let config = git2::Config::open_default()?;
+            return Cred::credential_helper(&config, url, username);
+        }
+
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+
+        Err(git2::Error::from_str("No usable git credentials"))
+    });
+
+    callbacks
+}
+
+pub fn fetch_options<'a>() -> FetchOptions<'a> {
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks());
+
+    options
+}
+
+/// Clone `url` into `dir`, or bring an existing clone to the remote state.
+/// Local changes are discarded, the checkout is only ever read.
+pub fn clone_or_pull(url: &str, branch: Option<&str>, dir: &Path) -> Result<(), String> {
+    if !dir.join(".git").exists() {
+        if let Some(parent) = dir.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        let mut builder = RepoBuilder::new();
+        builder.fetch_options(fetch_options());
+        if let Some(branch) = branch {
+            builder.branch(branch);
+        }
+
+        return builder
+            .clone(url, dir)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+    }
+
+    let repo = Repository::open(dir).map_err(|e| e.to_string())?;
+
+    let branch = match branch {
+        Some(branch) => branch.to_string(),
+        None => repo
+            .head()
+            .ok()
+            .and_then(|head| head.shorthand().map(str::to_string))
+            .ok_or("Snippet library has no checked out branch")?,
+    };
+
+    let mut remote = repo.find_remote("origin").map_err(|e| e.to_string())?;
+    remote
+        .fetch(&[branch.as_str()], Some(&mut fetch_options()), None)
+        .map_err(|e| e.to_string())?;
+
+    let fetch_head = repo
+        .find_reference("FETCH_HEAD")
+        .and_then(|reference| reference.peel_to_commit())
+        .map_err(|e| e.to_string())?;
+
+    repo.reset(fetch_head.as_object(), ResetType::Hard, None)
+        .map_err(|e| e.to_string())
+}
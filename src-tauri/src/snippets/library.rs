@@ -0,0 +1,371 @@
+use super::git::clone_or_pull;
+use super::now_millis;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, Runtime};
+
+/// Extensions of the files read as snippets.
+const SNIPPET_EXTENSIONS: &[&str] = &["txt", "md", "markdown", "snippet"];
+
+/// Bigger files are documents rather than snippets.
+const MAX_SNIPPET_BYTES: u64 = 256 * 1024;
+
+/// Snippets read per library, a mounted share should not flood search.
+const MAX_SNIPPETS: usize = 5000;
+
+const DEFAULT_REFRESH_MINUTES: u64 = 30;
+
+/// How often the refresher checks for libraries that are due.
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const MAX_SEARCH_RESULTS: usize = 50;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LibrarySource {
+    /// A local or network folder, read in place.
+    Folder { path: String },
+    /// A git repository cloned into the app data dir and pulled on refresh.
+    Git { url: String, branch: Option<String> },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetLibrary {
+    pub id: String,
+    pub name: String,
+    pub source: LibrarySource,
+    /// Minutes between refreshes, defaults to 30.
+    pub refresh_minutes: Option<u64>,
+}
+
+/// A read-only snippet of a shared library.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedSnippet {
+    /// `<library id>/<path inside the library>`.
+    pub id: String,
+    pub library_id: String,
+    pub library_name: String,
+    pub title: String,
+    pub content: String,
+    /// Folders the snippet is nested in.
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStatus {
+    pub id: String,
+    pub name: String,
+    pub snippet_count: usize,
+    /// Milliseconds since the epoch of the last successful refresh.
+    pub refreshed_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+struct MountedLibrary {
+    library: SnippetLibrary,
+    snippets: Vec<SharedSnippet>,
+    refreshed_at: Option<u64>,
+    error: Option<String>,
+}
+
+static LIBRARIES: Mutex<Vec<MountedLibrary>> = Mutex::new(Vec::new());
+
+fn get_libraries_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("snippet-libraries")
+}
+
+/// Directory the snippets of a library are read from.
+fn checkout_dir<R: Runtime>(app_handle: &AppHandle<R>, library: &SnippetLibrary) -> PathBuf {
+    match &library.source {
+        LibrarySource::Folder { path } => PathBuf::from(path),
+        LibrarySource::Git { .. } => {
+            let name: String = library
+                .id
+                .chars()
+                .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+                .collect();
+
+            get_libraries_dir(app_handle).join(name)
+        }
+    }
+}
+
+fn is_snippet_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SNIPPET_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+fn collect_snippets(
+    library: &SnippetLibrary,
+    root: &Path,
+    dir: &Path,
+    snippets: &mut Vec<SharedSnippet>,
+) -> Result<(), String> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if snippets.len() >= MAX_SNIPPETS {
+            break;
+        }
+
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if hidden {
+            continue;
+        }
+
+        if metadata.is_dir() {
+            collect_snippets(library, root, &path, snippets)?;
+            continue;
+        }
+
+        if !is_snippet_file(&path) || metadata.len() > MAX_SNIPPET_BYTES {
+            continue;
+        }
+
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let tags = relative
+            .parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        snippets.push(SharedSnippet {
+            id: format!(
+                "{}/{}",
+                library.id,
+                relative.to_string_lossy().replace('\\', "/")
+            ),
+            library_id: library.id.clone(),
+            library_name: library.name.clone(),
+            title: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            content,
+            tags,
+        });
+    }
+
+    Ok(())
+}
+
+/// Pull a library if it is a git repository and read its snippets.
+fn load_library<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    library: &SnippetLibrary,
+) -> Result<Vec<SharedSnippet>, String> {
+    let dir = checkout_dir(app_handle, library);
+
+    if let LibrarySource::Git { url, branch } = &library.source {
+        clone_or_pull(url, branch.as_deref(), &dir)?;
+    }
+
+    let mut snippets = Vec::new();
+    collect_snippets(library, &dir, &dir, &mut snippets)?;
+
+    Ok(snippets)
+}
+
+fn is_due(mounted: &MountedLibrary, now: u64) -> bool {
+    let minutes = mounted
+        .library
+        .refresh_minutes
+        .unwrap_or(DEFAULT_REFRESH_MINUTES)
+        .max(1);
+
+    match mounted.refreshed_at {
+        Some(refreshed_at) => now.saturating_sub(refreshed_at) >= minutes * 60 * 1000,
+        None => true,
+    }
+}
+
+/// Refresh the given libraries, or the ones that are due when `None`.
+/// Loading happens outside the lock, a slow share never blocks search.
+pub fn refresh<R: Runtime>(app_handle: &AppHandle<R>, ids: Option<&[String]>) {
+    let now = now_millis();
+    let libraries: Vec<SnippetLibrary> = LIBRARIES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|mounted| match ids {
+            Some(ids) => ids.contains(&mounted.library.id),
+            None => is_due(mounted, now),
+        })
+        .map(|mounted| mounted.library.clone())
+        .collect();
+
+    for library in libraries {
+        let result = load_library(app_handle, &library);
+
+        let mut mounted = LIBRARIES.lock().unwrap();
+        let Some(mounted) = mounted
+            .iter_mut()
+            .find(|mounted| mounted.library.id == library.id)
+        else {
+            continue;
+        };
+
+        match result {
+            Ok(snippets) => {
+                mounted.snippets = snippets;
+                mounted.refreshed_at = Some(now_millis());
+                mounted.error = None;
+            }
+            Err(error) => {
+                log::warn!(
+                    "Failed to refresh snippet library {}: {}",
+                    library.name,
+                    error
+                );
+
+                // Keep serving the last good snippets, retry on the next round
+                mounted.refreshed_at = Some(now_millis());
+                mounted.error = Some(error);
+            }
+        }
+    }
+}
+
+pub fn start_refresher<R: Runtime>(app_handle: AppHandle<R>) {
+    thread::spawn(move || loop {
+        refresh(&app_handle, None);
+
+        thread::sleep(REFRESH_CHECK_INTERVAL);
+    });
+}
+
+pub fn search(query: &str) -> Vec<SharedSnippet> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let libraries = LIBRARIES.lock().unwrap();
+    let mut matches: Vec<(bool, &SharedSnippet)> = libraries
+        .iter()
+        .flat_map(|mounted| mounted.snippets.iter())
+        .filter_map(|snippet| {
+            let in_title = snippet.title.to_lowercase().contains(&query);
+            let in_rest = snippet.content.to_lowercase().contains(&query)
+                || snippet
+                    .tags
+                    .iter()
+                    .any(|tag| tag.to_lowercase().contains(&query));
+
+            (in_title || in_rest).then_some((in_title, snippet))
+        })
+        .collect();
+
+    // Title matches first, the sort is stable so library order is kept
+    matches.sort_by_key(|(in_title, _)| !in_title);
+
+    matches
+        .into_iter()
+        .take(MAX_SEARCH_RESULTS)
+        .map(|(_, snippet)| snippet.clone())
+        .collect()
+}
+
+// 设置共享的只读片段库，新增或变更的库会在后台立即刷新
+#[command]
+pub async fn set_snippet_libraries<R: Runtime>(
+    app_handle: AppHandle<R>,
+    libraries: Vec<SnippetLibrary>,
+) {
+    let mut changed = Vec::new();
+
+    {
+        let mut mounted = LIBRARIES.lock().unwrap();
+        let mut previous: Vec<MountedLibrary> = mounted.drain(..).collect();
+
+        for library in libraries {
+            let existing = previous
+                .iter()
+                .position(|old| old.library.id == library.id)
+                .map(|index| previous.swap_remove(index));
+
+            match existing {
+                Some(mut old) if old.library.source == library.source => {
+                    old.library = library;
+                    mounted.push(old);
+                }
+                _ => {
+                    changed.push(library.id.clone());
+                    mounted.push(MountedLibrary {
+                        library,
+                        snippets: Vec::new(),
+                        refreshed_at: None,
+                        error: None,
+                    });
+                }
+            }
+        }
+    }
+
+    if !changed.is_empty() {
+        thread::spawn(move || refresh(&app_handle, Some(&changed)));
+    }
+}
+
+// 立即刷新所有片段库
+#[command]
+pub async fn refresh_snippet_libraries<R: Runtime>(app_handle: AppHandle<R>) -> Vec<LibraryStatus> {
+    let ids: Vec<String> = LIBRARIES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|mounted| mounted.library.id.clone())
+        .collect();
+
+    refresh(&app_handle, Some(&ids));
+
+    get_snippet_libraries().await
+}
+
+// 获取片段库的状态
+#[command]
+pub async fn get_snippet_libraries() -> Vec<LibraryStatus> {
+    LIBRARIES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|mounted| LibraryStatus {
+            id: mounted.library.id.clone(),
+            name: mounted.library.name.clone(),
+            snippet_count: mounted.snippets.len(),
+            refreshed_at: mounted.refreshed_at,
+            error: mounted.error.clone(),
+        })
+        .collect()
+}
+
+// 在共享片段库中搜索
+#[command]
+pub async fn search_snippets(query: String) -> Vec<SharedSnippet> {
+    search(&query)
+}
@@ -0,0 +1,15 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod git;
+mod library;
+mod sync;
+
+pub use library::*;
+pub use sync::*;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
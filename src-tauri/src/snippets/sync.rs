@@ -0,0 +1,537 @@
+use super::git::{fetch_options, remote_callbacks};
+use super::now_millis;
+use crate::history::{local_time, notify_changed, store, HistoryItem, HistoryRow};
+use git2::build::CheckoutBuilder;
+use git2::{
+    Commit, Index, IndexAddOption, IndexEntry, Oid, PushOptions, Repository, RepositoryInitOptions,
+    Signature,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, Runtime};
+
+/// Pinned snippets are stored as `<item id>.txt` at the root of the repository.
+const SNIPPET_EXTENSION: &str = "txt";
+
+const DEFAULT_BRANCH: &str = "main";
+
+const DEFAULT_INTERVAL_MINUTES: u64 = 15;
+
+/// How often the scheduler checks whether a sync is due.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Stage bits of `IndexEntry::flags`, zero for resolved entries.
+const INDEX_STAGE_MASK: u16 = 0x3000;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConfig {
+    /// Remote url, ssh urls authenticate through the ssh agent and https
+    /// urls through the git credential helpers.
+    pub remote: String,
+    /// Defaults to `main`.
+    pub branch: Option<String>,
+    /// Minutes between scheduled syncs, defaults to 15.
+    pub interval_minutes: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Whether local changes were committed.
+    pub committed: bool,
+    /// Snippets added or changed by the remote.
+    pub imported: usize,
+    /// Snippets removed on the remote, unpinned here.
+    pub removed: usize,
+    /// Files holding the remote side of conflicting edits.
+    pub conflicts: Vec<String>,
+    pub pushed: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub enabled: bool,
+    /// Milliseconds since the epoch of the last sync attempt.
+    pub synced_at: Option<u64>,
+    pub error: Option<String>,
+    pub last_report: Option<SyncReport>,
+}
+
+struct SyncState {
+    config: Option<SyncConfig>,
+    synced_at: Option<u64>,
+    error: Option<String>,
+    last_report: Option<SyncReport>,
+}
+
+static STATE: Mutex<SyncState> = Mutex::new(SyncState {
+    config: None,
+    synced_at: None,
+    error: None,
+    last_report: None,
+});
+
+/// The scheduler and the command share one work tree, syncs never overlap.
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+fn get_sync_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let app_data = app_handle.path().app_data_dir().unwrap();
+    app_data.join("snippet-sync")
+}
+
+/// Ids double as file names, anything else is left out of the repository.
+fn is_snippet_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+fn snippet_id(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_suffix(SNIPPET_EXTENSION)
+        .and_then(|stem| stem.strip_suffix('.'))
+        .filter(|id| is_snippet_id(id))
+}
+
+/// Favorites are the pinned items, only text ones are synced.
+fn pinned_snippets() -> Result<BTreeMap<String, String>, String> {
+    Ok(store()
+        .list_favorites()?
+        .into_iter()
+        .filter(|item| item.kind == "text" && is_snippet_id(&item.id))
+        .map(|item| (item.id, item.value))
+        .collect())
+}
+
+fn snippet_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+
+        if let Some(id) = snippet_id(&file_name) {
+            files.push((id.to_string(), entry.path()));
+        }
+    }
+
+    Ok(files)
+}
+
+fn open_repo(dir: &Path, config: &SyncConfig, branch: &str) -> Result<Repository, git2::Error> {
+    let repo = if dir.join(".git").exists() {
+        Repository::open(dir)?
+    } else {
+        Repository::init_opts(dir, RepositoryInitOptions::new().initial_head(branch))?
+    };
+
+    match repo.find_remote("origin") {
+        Ok(remote) if remote.url() == Some(config.remote.as_str()) => {}
+        Ok(_) => repo.remote_set_url("origin", &config.remote)?,
+        Err(_) => {
+            repo.remote("origin", &config.remote)?;
+        }
+    }
+
+    Ok(repo)
+}
+
+fn head_commit(repo: &Repository) -> Option<Commit<'_>> {
+    repo.head().ok()?.peel_to_commit().ok()
+}
+
+fn head_snippet_ids(repo: &Repository) -> BTreeSet<String> {
+    let Some(tree) = head_commit(repo).and_then(|commit| commit.tree().ok()) else {
+        return BTreeSet::new();
+    };
+
+    tree.iter()
+        .filter_map(|entry| entry.name().and_then(snippet_id).map(str::to_string))
+        .collect()
+}
+
+fn signature(repo: &Repository) -> Result<Signature<'static>, git2::Error> {
+    repo.signature()
+        .or_else(|_| Signature::now("EcoPaste", "ecopaste@localhost"))
+}
+
+/// Write the pinned snippets into the work tree and commit what changed.
+fn commit_local(repo: &Repository, dir: &Path) -> Result<bool, String> {
+    let pinned = pinned_snippets()?;
+
+    for (id, value) in &pinned {
+        let path = dir.join(format!("{}.{}", id, SNIPPET_EXTENSION));
+
+        if std::fs::read_to_string(&path).ok().as_ref() != Some(value) {
+            std::fs::write(&path, value).map_err(|e| e.to_string())?;
+        }
+    }
+
+    for (id, path) in snippet_files(dir)? {
+        if !pinned.contains_key(&id) {
+            std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let commit = || -> Result<bool, git2::Error> {
+        let mut index = repo.index()?;
+        index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+        index.update_all(["*"], None)?;
+        index.write()?;
+
+        let tree_id = index.write_tree()?;
+        let head = head_commit(repo);
+        if head.as_ref().is_some_and(|head| head.tree_id() == tree_id) {
+            return Ok(false);
+        }
+
+        let tree = repo.find_tree(tree_id)?;
+        let signature = signature(repo)?;
+        let parents: Vec<&Commit> = head.iter().collect();
+
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Update pinned snippets",
+            &tree,
+            &parents,
+        )?;
+
+        Ok(true)
+    };
+
+    commit().map_err(|e| e.to_string())
+}
+
+fn fetch_remote(repo: &Repository, branch: &str) -> Result<Option<Oid>, git2::Error> {
+    let mut remote = repo.find_remote("origin")?;
+    let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch);
+
+    remote.fetch(&[refspec.as_str()], Some(&mut fetch_options()), None)?;
+
+    // An empty remote has no branch yet, the first push creates it
+    Ok(repo
+        .refname_to_id(&format!("refs/remotes/origin/{}", branch))
+        .ok())
+}
+
+fn resolved(mut entry: IndexEntry) -> IndexEntry {
+    entry.flags &= !INDEX_STAGE_MASK;
+
+    entry
+}
+
+/// Resolve conflicts without losing an edit: when both sides changed a
+/// snippet ours stays in place and theirs is kept next to it as a new
+/// snippet, and an edit always wins over a delete.
+fn resolve_conflicts(index: &mut Index) -> Result<Vec<String>, git2::Error> {
+    let conflicts = index.conflicts()?.collect::<Result<Vec<_>, _>>()?;
+    let mut copies = Vec::new();
+
+    for conflict in conflicts {
+        let Some(path) = [&conflict.our, &conflict.their, &conflict.ancestor]
+            .into_iter()
+            .flatten()
+            .next()
+            .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        else {
+            continue;
+        };
+
+        index.remove_path(Path::new(&path))?;
+
+        match (conflict.our, conflict.their) {
+            (Some(ours), Some(theirs)) => {
+                let their_id = theirs.id;
+                let differs = ours.id != their_id;
+
+                index.add(&resolved(ours))?;
+
+                if differs {
+                    let stem = path
+                        .strip_suffix(&format!(".{}", SNIPPET_EXTENSION))
+                        .unwrap_or(&path);
+                    let name = format!(
+                        "{}-conflict-{}.{}",
+                        stem,
+                        &their_id.to_string()[..8],
+                        SNIPPET_EXTENSION
+                    );
+
+                    let mut copy = resolved(theirs);
+                    copy.path = name.clone().into_bytes();
+                    index.add(&copy)?;
+
+                    copies.push(name);
+                }
+            }
+            (Some(entry), None) | (None, Some(entry)) => index.add(&resolved(entry))?,
+            (None, None) => {}
+        }
+    }
+
+    Ok(copies)
+}
+
+/// Bring the remote commit into the local branch, returns the conflict copies.
+fn merge_remote(
+    repo: &Repository,
+    branch: &str,
+    remote_id: Oid,
+) -> Result<Vec<String>, git2::Error> {
+    let annotated = repo.find_annotated_commit(remote_id)?;
+    let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+
+    if analysis.is_up_to_date() {
+        return Ok(Vec::new());
+    }
+
+    if analysis.is_unborn() || analysis.is_fast_forward() {
+        let refname = format!("refs/heads/{}", branch);
+
+        repo.reference(&refname, remote_id, true, "Fast-forward pinned snippets")?;
+        repo.set_head(&refname)?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+
+        return Ok(Vec::new());
+    }
+
+    let local = repo.head()?.peel_to_commit()?;
+    let remote = repo.find_commit(remote_id)?;
+
+    let mut index = repo.merge_commits(&local, &remote, None)?;
+    let conflicts = resolve_conflicts(&mut index)?;
+
+    let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+    let signature = signature(repo)?;
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Merge remote pinned snippets",
+        &tree,
+        &[&local, &remote],
+    )?;
+    repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+
+    Ok(conflicts)
+}
+
+/// Pin what the remote added or changed and unpin what it removed.
+fn import_remote(
+    dir: &Path,
+    before: &BTreeSet<String>,
+    after: &BTreeSet<String>,
+) -> Result<(usize, usize), String> {
+    let store = store();
+    let pinned = pinned_snippets()?;
+
+    let mut imported = 0;
+    for (id, path) in snippet_files(dir)? {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        if pinned.get(&id) == Some(&content) {
+            continue;
+        }
+
+        if store.get_item(&id)?.is_none() {
+            store.insert_item(&HistoryItem {
+                id: id.clone(),
+                kind: "text".to_string(),
+                value: content.clone(),
+                search: content.clone(),
+                subtype: None,
+                create_time: local_time(),
+            })?;
+        }
+
+        let fields = HistoryRow::from([
+            ("value".to_string(), Value::from(content.clone())),
+            ("search".to_string(), Value::from(content.clone())),
+            ("count".to_string(), Value::from(content.chars().count())),
+            ("favorite".to_string(), Value::from(1)),
+        ]);
+        store.update_row(&id, &fields)?;
+
+        imported += 1;
+    }
+
+    let mut removed = 0;
+    for id in before.difference(after) {
+        if pinned.contains_key(id) {
+            let fields = HistoryRow::from([("favorite".to_string(), Value::from(0))]);
+            store.update_row(id, &fields)?;
+
+            removed += 1;
+        }
+    }
+
+    Ok((imported, removed))
+}
+
+fn push(repo: &Repository, branch: &str) -> Result<(), String> {
+    let mut remote = repo.find_remote("origin").map_err(|e| e.to_string())?;
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+    let mut rejection = None;
+
+    {
+        let mut callbacks = remote_callbacks();
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                rejection = Some(format!("Push of {} was rejected: {}", refname, status));
+            }
+
+            Ok(())
+        });
+
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+
+        remote
+            .push(&[refspec.as_str()], Some(&mut options))
+            .map_err(|e| e.to_string())?;
+    }
+
+    match rejection {
+        Some(rejection) => Err(rejection),
+        None => Ok(()),
+    }
+}
+
+/// Commit local changes, merge the remote and push the result.
+pub fn sync_now<R: Runtime>(app_handle: &AppHandle<R>) -> Result<SyncReport, String> {
+    let _guard = SYNC_LOCK.lock().unwrap();
+
+    let config = STATE
+        .lock()
+        .unwrap()
+        .config
+        .clone()
+        .ok_or("Snippet sync is not configured")?;
+    let branch = config.branch.as_deref().unwrap_or(DEFAULT_BRANCH);
+
+    let dir = get_sync_dir(app_handle);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let repo = open_repo(&dir, &config, branch).map_err(|e| e.to_string())?;
+
+    let mut report = SyncReport {
+        committed: commit_local(&repo, &dir)?,
+        ..Default::default()
+    };
+
+    let before = head_snippet_ids(&repo);
+    let remote_id = fetch_remote(&repo, branch).map_err(|e| e.to_string())?;
+
+    if let Some(remote_id) = remote_id {
+        report.conflicts = merge_remote(&repo, branch, remote_id).map_err(|e| e.to_string())?;
+
+        let after = head_snippet_ids(&repo);
+        (report.imported, report.removed) = import_remote(&dir, &before, &after)?;
+
+        if report.imported > 0 || report.removed > 0 {
+            notify_changed(app_handle);
+        }
+    }
+
+    let head_id = head_commit(&repo).map(|commit| commit.id());
+    if head_id.is_some() && head_id != remote_id {
+        push(&repo, branch)?;
+        report.pushed = true;
+    }
+
+    Ok(report)
+}
+
+fn run_sync<R: Runtime>(app_handle: &AppHandle<R>) -> Result<SyncReport, String> {
+    let result = sync_now(app_handle);
+
+    let mut state = STATE.lock().unwrap();
+    state.synced_at = Some(now_millis());
+
+    match &result {
+        Ok(report) => {
+            state.error = None;
+            state.last_report = Some(report.clone());
+        }
+        Err(error) => {
+            log::warn!("Failed to sync pinned snippets: {}", error);
+
+            state.error = Some(error.clone());
+        }
+    }
+
+    result
+}
+
+fn is_due(state: &SyncState, now: u64) -> bool {
+    let Some(config) = &state.config else {
+        return false;
+    };
+
+    let minutes = config
+        .interval_minutes
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+        .max(1);
+
+    match state.synced_at {
+        Some(synced_at) => now.saturating_sub(synced_at) >= minutes * 60 * 1000,
+        None => true,
+    }
+}
+
+pub fn start_sync_scheduler<R: Runtime>(app_handle: AppHandle<R>) {
+    thread::spawn(move || loop {
+        if is_due(&STATE.lock().unwrap(), now_millis()) {
+            let _ = run_sync(&app_handle);
+        }
+
+        thread::sleep(SCHEDULE_CHECK_INTERVAL);
+    });
+}
+
+// 设置置顶片段的 git 同步，传入 null 关闭同步
+#[command]
+pub async fn set_snippet_sync<R: Runtime>(app_handle: AppHandle<R>, config: Option<SyncConfig>) {
+    let enabled = config.is_some();
+
+    {
+        let mut state = STATE.lock().unwrap();
+        state.config = config;
+        state.synced_at = None;
+        state.error = None;
+    }
+
+    if enabled {
+        thread::spawn(move || run_sync(&app_handle));
+    }
+}
+
+// 立即同步置顶片段
+#[command]
+pub async fn sync_snippets<R: Runtime>(app_handle: AppHandle<R>) -> Result<SyncReport, String> {
+    run_sync(&app_handle)
+}
+
+// 获取置顶片段的同步状态
+#[command]
+pub async fn get_snippet_sync_status() -> SyncStatus {
+    let state = STATE.lock().unwrap();
+
+    SyncStatus {
+        enabled: state.config.is_some(),
+        synced_at: state.synced_at,
+        error: state.error.clone(),
+        last_report: state.last_report.clone(),
+    }
+}
@@ -0,0 +1,563 @@
+use crate::clipboard_image::{get_images_dir, get_thumbnails_dir, resolve_image_path};
+use crate::clipboard_text::get_blobs_dir;
+use crate::cold_storage::RECOMPRESSED;
+use crate::history::{
+    self, archived_blob_paths, archived_image_values, delete_entry, notify_changed,
+    with_connection, BLOB_PATH, COLD_STORAGE,
+};
+use crate::image_decode::decode;
+use crate::image_storage::{recompress_images, storage_policy, StorageFormat};
+use crate::vault;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::ImageEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Runtime};
+
+/// Number of biggest items listed in a report.
+const BIGGEST_ITEMS: usize = 10;
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeUsage {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemUsage {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub database_bytes: u64,
+    pub images_bytes: u64,
+    pub by_type: BTreeMap<String, TypeUsage>,
+    pub biggest_items: Vec<ItemUsage>,
+    /// Bytes freed if identical image files were stored only once.
+    pub duplicate_image_bytes: u64,
+    /// Image files on disk that no history item references.
+    pub orphan_image_bytes: u64,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeReport {
+    pub before: StorageReport,
+    pub after: StorageReport,
+    pub recompressed_images: u64,
+    pub pruned_items: u64,
+    pub removed_orphans: u64,
+}
+
+/// Quick totals, without the hashing of a full report.
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageStats {
+    /// Everything in the images dir, thumbnails included.
+    pub images_dir_bytes: u64,
+    pub database_bytes: u64,
+    /// Item count by type.
+    pub counts: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VacuumReport {
+    pub removed_files: u64,
+    pub freed_bytes: u64,
+}
+
+/// Groups a policy can set its own max age for.
+const GROUPS: &[&str] = &["text", "image", "files"];
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Retention policy applied by `optimize_storage` and the retention task,
+/// favorites are never pruned.
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunePolicy {
+    pub max_age_days: Option<u32>,
+    pub max_count: Option<u32>,
+    /// Max age per group overriding `max_age_days`, `0` keeps the group.
+    #[serde(default)]
+    pub group_max_age_days: HashMap<String, u32>,
+}
+
+struct StoredItem {
+    id: String,
+    kind: String,
+    value: String,
+    bytes: u64,
+}
+
+fn load_items<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Vec<StoredItem>, String> {
+    let rows: Vec<(String, String, String)> = with_connection(|connection| {
+        let mut statement = connection.prepare("SELECT id, type, value FROM history")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            ))
+        })?;
+
+        rows.collect()
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, kind, value)| {
+            let bytes = if kind == "image" {
+                file_size(&resolve_image_path(app_handle, &value))
+            } else {
+                value.len() as u64
+            };
+
+            StoredItem {
+                id,
+                kind,
+                value,
+                bytes,
+            }
+        })
+        .collect())
+}
+
+fn file_size(path: &PathBuf) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn hash_file(path: &PathBuf) -> Option<u64> {
+    let bytes = vault::read_file(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+fn database_bytes() -> Result<u64, String> {
+    with_connection(|connection| {
+        connection.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+    })
+    .map(|bytes| bytes as u64)
+}
+
+/// Size of a directory and everything below it.
+fn dir_size(path: &PathBuf) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.path() {
+                    path if path.is_dir() => dir_size(&path),
+                    path => file_size(&path),
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Paths of the images items refer to, archived items included. `None`
+/// when the archives can't be read, every archived image would look
+/// orphaned.
+fn referenced_images<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    items: &[StoredItem],
+) -> Option<HashSet<PathBuf>> {
+    let archived = archived_image_values().ok()?;
+
+    Some(
+        items
+            .iter()
+            .filter(|item| item.kind == "image")
+            .map(|item| item.value.as_str())
+            .chain(archived.iter().map(String::as_str))
+            .map(|value| resolve_image_path(app_handle, value))
+            .collect(),
+    )
+}
+
+/// Files directly in a directory that are not in use.
+fn unused_files(dir: PathBuf, in_use: impl Fn(&PathBuf) -> bool) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && !in_use(path))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Image files in the images dir that no item references, archived items
+/// included.
+fn orphan_images<R: Runtime>(app_handle: &AppHandle<R>, items: &[StoredItem]) -> Vec<PathBuf> {
+    let Some(referenced) = referenced_images(app_handle, items) else {
+        return Vec::new();
+    };
+
+    unused_files(get_images_dir(app_handle), |path| referenced.contains(path))
+}
+
+/// Thumbnails of images no item references, thumbnails are named after
+/// their image.
+fn orphan_thumbnails<R: Runtime>(app_handle: &AppHandle<R>, items: &[StoredItem]) -> Vec<PathBuf> {
+    let Some(referenced) = referenced_images(app_handle, items) else {
+        return Vec::new();
+    };
+
+    let stems: HashSet<_> = referenced
+        .iter()
+        .filter_map(|path| path.file_stem())
+        .collect();
+
+    unused_files(get_thumbnails_dir(app_handle), |path| {
+        path.file_stem().is_some_and(|stem| stems.contains(stem))
+    })
+}
+
+/// Overflow blobs no item references, archived items included. Files still
+/// being written end in `.part` and are left alone.
+fn orphan_blobs<R: Runtime>(app_handle: &AppHandle<R>) -> Result<Vec<PathBuf>, String> {
+    let mut referenced: HashSet<PathBuf> = with_connection(|connection| {
+        let mut statement =
+            connection.prepare("SELECT value FROM history_metadata WHERE key = ?1")?;
+        let rows = statement.query_map([BLOB_PATH], |row| row.get::<_, String>(0))?;
+
+        rows.map(|path| path.map(PathBuf::from)).collect()
+    })?;
+    referenced.extend(archived_blob_paths()?.into_iter().map(PathBuf::from));
+
+    Ok(unused_files(get_blobs_dir(app_handle), |path| {
+        referenced.contains(path) || path.extension().is_some_and(|ext| ext == "part")
+    }))
+}
+
+/// Delete the image files, thumbnails and overflow blobs no item
+/// references.
+fn remove_orphans<R: Runtime>(app_handle: &AppHandle<R>) -> Result<VacuumReport, String> {
+    let items = load_items(app_handle)?;
+    let mut report = VacuumReport::default();
+
+    let orphans = orphan_images(app_handle, &items)
+        .into_iter()
+        .chain(orphan_thumbnails(app_handle, &items))
+        .chain(orphan_blobs(app_handle)?);
+
+    for path in orphans {
+        let bytes = file_size(&path);
+
+        if std::fs::remove_file(path).is_ok() {
+            report.removed_files += 1;
+            report.freed_bytes += bytes;
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn storage_stats<R: Runtime>(app_handle: &AppHandle<R>) -> Result<StorageStats, String> {
+    let counts = with_connection(|connection| {
+        let mut statement =
+            connection.prepare("SELECT type, COUNT(*) FROM history GROUP BY type")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+
+        rows.collect::<rusqlite::Result<BTreeMap<_, _>>>()
+    })?;
+
+    Ok(StorageStats {
+        images_dir_bytes: dir_size(&get_images_dir(app_handle)),
+        database_bytes: database_bytes()?,
+        counts,
+    })
+}
+
+pub fn analyze<R: Runtime>(app_handle: &AppHandle<R>) -> Result<StorageReport, String> {
+    let items = load_items(app_handle)?;
+    let mut report = StorageReport {
+        database_bytes: database_bytes()?,
+        ..Default::default()
+    };
+
+    let mut image_hashes: HashMap<u64, u64> = HashMap::new();
+
+    for item in &items {
+        let usage = report.by_type.entry(item.kind.clone()).or_default();
+        usage.count += 1;
+        usage.bytes += item.bytes;
+
+        if item.kind == "image" {
+            report.images_bytes += item.bytes;
+
+            if let Some(hash) = hash_file(&resolve_image_path(app_handle, &item.value)) {
+                // Every copy after the first one is reclaimable
+                if image_hashes.insert(hash, item.bytes).is_some() {
+                    report.duplicate_image_bytes += item.bytes;
+                }
+            }
+        }
+    }
+
+    let mut biggest: Vec<&StoredItem> = items.iter().collect();
+    biggest.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    report.biggest_items = biggest
+        .into_iter()
+        .take(BIGGEST_ITEMS)
+        .map(|item| ItemUsage {
+            id: item.id.clone(),
+            kind: item.kind.clone(),
+            bytes: item.bytes,
+        })
+        .collect();
+
+    report.orphan_image_bytes = orphan_images(app_handle, &items).iter().map(file_size).sum();
+
+    Ok(report)
+}
+
+/// Re-encode a PNG with the best compression, keeping it only if smaller.
+fn recompress_png(path: &PathBuf) -> Result<bool, String> {
+    let original = vault::read_file(path)?;
+    let image = decode(original.clone(), image::ImageFormat::Png)?;
+
+    let mut encoded = Vec::new();
+    PngEncoder::new_with_quality(&mut encoded, CompressionType::Best, FilterType::Adaptive)
+        .write_image(
+            image.as_bytes(),
+            image.width(),
+            image.height(),
+            image.color().into(),
+        )
+        .map_err(|e| e.to_string())?;
+
+    if encoded.len() >= original.len() {
+        return Ok(false);
+    }
+
+    // A crash mid-write must not leave the only copy truncated
+    let file_name = path
+        .file_name()
+        .ok_or("Image path has no file name")?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+    vault::write_file(&tmp_path, &encoded)?;
+
+    std::fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        e.to_string()
+    })?;
+
+    Ok(true)
+}
+
+/// Age conditions of the policy, groups with their own max age are left out
+/// of the general one.
+fn age_conditions(policy: &PrunePolicy) -> Vec<String> {
+    let older_than = |days: u32| history::older_than(days as u64 * SECONDS_PER_DAY);
+
+    let overridden: Vec<&str> = GROUPS
+        .iter()
+        .copied()
+        .filter(|group| policy.group_max_age_days.contains_key(*group))
+        .collect();
+
+    let mut conditions: Vec<String> = overridden
+        .iter()
+        .filter_map(|group| {
+            let days = policy.group_max_age_days[*group];
+            (days > 0).then(|| format!("(\"group\" = '{}' AND {})", group, older_than(days)))
+        })
+        .collect();
+
+    if let Some(days) = policy.max_age_days {
+        if overridden.is_empty() {
+            conditions.push(older_than(days));
+        } else {
+            let groups = overridden
+                .iter()
+                .map(|group| format!("'{}'", group))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            conditions.push(format!(
+                "(\"group\" NOT IN ({}) AND {})",
+                groups,
+                older_than(days)
+            ));
+        }
+    }
+
+    conditions
+}
+
+/// Ids of the non-favorite items outside the policy, with the cold storage
+/// copies of their images.
+fn prune_candidates(policy: &PrunePolicy) -> Result<(Vec<String>, Vec<String>), String> {
+    with_connection(|connection| {
+        let mut condition = age_conditions(policy);
+
+        if let Some(max_count) = policy.max_count {
+            condition.push(format!(
+                "id NOT IN (SELECT id FROM history ORDER BY seq DESC LIMIT {})",
+                max_count
+            ));
+        }
+
+        if condition.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let filter = format!("favorite = 0 AND ({})", condition.join(" OR "));
+
+        let ids: Vec<String> = {
+            let mut statement =
+                connection.prepare(&format!("SELECT id FROM history WHERE {}", filter))?;
+            let rows = statement.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        // Images moved to cold storage are deleted from there
+        let cold_images: Vec<String> = {
+            let mut statement = connection.prepare(&format!(
+                "SELECT value FROM history_metadata WHERE key = '{cold}' AND value != '{recompressed}'
+                AND item_id IN (SELECT id FROM history WHERE {filter})",
+                cold = COLD_STORAGE,
+                recompressed = RECOMPRESSED,
+            ))?;
+            let rows = statement.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        Ok((ids, cold_images))
+    })
+}
+
+/// Prune items outside the policy together with their image files, returns
+/// the number of deleted items. Items go through `delete_entry` so pruning
+/// is logged, undoable and synced like any other delete.
+pub fn apply_policy<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    policy: &PrunePolicy,
+) -> Result<u64, String> {
+    let (ids, cold_images) = prune_candidates(policy)?;
+
+    let mut pruned_items = 0;
+    for id in ids {
+        if delete_entry(app_handle, &id)? {
+            pruned_items += 1;
+        }
+    }
+
+    for value in cold_images {
+        let _ = std::fs::remove_file(resolve_image_path(app_handle, &value));
+    }
+
+    Ok(pruned_items)
+}
+
+/// Re-encode the PNG images losslessly, returns how many got smaller.
+fn recompress_pngs<R: Runtime>(app_handle: &AppHandle<R>) -> Result<u64, String> {
+    let mut recompressed_images = 0;
+
+    for item in load_items(app_handle)?
+        .iter()
+        .filter(|item| item.kind == "image")
+    {
+        let path = resolve_image_path(app_handle, &item.value);
+
+        // GIFs and images saved as WebP or JPEG are left alone
+        if image::ImageFormat::from_path(&path).ok() != Some(image::ImageFormat::Png) {
+            continue;
+        }
+
+        match recompress_png(&path) {
+            Ok(true) => recompressed_images += 1,
+            Ok(false) => {}
+            Err(error) => log::warn!("Failed to recompress {}: {}", path.display(), error),
+        }
+    }
+
+    Ok(recompressed_images)
+}
+
+fn optimize<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    policy: &PrunePolicy,
+) -> Result<OptimizeReport, String> {
+    let before = analyze(app_handle)?;
+
+    let pruned_items = apply_policy(app_handle, policy)?;
+
+    let removed_orphans = remove_orphans(app_handle)?.removed_files;
+
+    // Images follow the storage format chosen in the settings, PNGs are only
+    // compressed harder
+    let recompressed_images = if storage_policy().format == StorageFormat::Png {
+        recompress_pngs(app_handle)?
+    } else {
+        recompress_images(app_handle)?.converted
+    };
+
+    with_connection(|connection| connection.execute_batch("VACUUM; ANALYZE;"))?;
+
+    if pruned_items > 0 {
+        notify_changed(app_handle);
+    }
+
+    Ok(OptimizeReport {
+        before,
+        after: analyze(app_handle)?,
+        recompressed_images,
+        pruned_items,
+        removed_orphans,
+    })
+}
+
+// 分析存储空间占用
+#[command]
+pub async fn analyze_storage<R: Runtime>(app_handle: AppHandle<R>) -> Result<StorageReport, String> {
+    analyze(&app_handle)
+}
+
+// 一键优化存储：按设置的格式重新压缩图片、按策略清理、删除孤立文件并整理数据库
+#[command]
+pub async fn optimize_storage<R: Runtime>(
+    app_handle: AppHandle<R>,
+    policy: Option<PrunePolicy>,
+) -> Result<OptimizeReport, String> {
+    tauri::async_runtime::spawn_blocking(move || optimize(&app_handle, &policy.unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 获取存储占用的概况：图片目录和数据库的大小及各类型条目的数量
+#[command]
+pub async fn get_storage_stats<R: Runtime>(
+    app_handle: AppHandle<R>,
+) -> Result<StorageStats, String> {
+    storage_stats(&app_handle)
+}
+
+// 删除没有任何条目引用的图片文件及其缩略图
+#[command]
+pub async fn vacuum_storage<R: Runtime>(app_handle: AppHandle<R>) -> Result<VacuumReport, String> {
+    remove_orphans(&app_handle)
+}
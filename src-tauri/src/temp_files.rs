@@ -0,0 +1,219 @@
+use crate::clipboard_image::resolve_image_path;
+use crate::history::HistoryItem;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{command, AppHandle, Manager, Runtime};
+
+/// Materialized files are removed after this long even if still in use.
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Grace period after the consumer exited, some apps re-read lazily.
+const CONSUMER_GRACE: Duration = Duration::from_secs(60);
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+struct TempFile {
+    path: PathBuf,
+    created: SystemTime,
+    ttl: Duration,
+    /// The app the file was opened with, kept to tell when it exits.
+    consumer: Option<Child>,
+}
+
+impl TempFile {
+    fn expired(&mut self, now: SystemTime) -> bool {
+        let age = now.duration_since(self.created).unwrap_or_default();
+
+        // Waiting also reaps the exited app, a pid alone stays alive as a
+        // zombie until its parent waits for it
+        let exited = self
+            .consumer
+            .as_mut()
+            .is_some_and(|child| !matches!(child.try_wait(), Ok(None)));
+
+        if exited {
+            age >= CONSUMER_GRACE
+        } else {
+            age >= self.ttl
+        }
+    }
+}
+
+static TEMP_FILES: Mutex<Vec<TempFile>> = Mutex::new(Vec::new());
+
+pub fn get_temp_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    let cache = app_handle.path().app_cache_dir().unwrap();
+    cache.join("materialized")
+}
+
+/// Track a file so it is removed once its TTL passes.
+pub fn register(path: &Path, ttl: Option<Duration>) {
+    let mut temp_files = TEMP_FILES.lock().unwrap();
+
+    temp_files.retain(|file| file.path != path);
+    temp_files.push(TempFile {
+        path: path.to_path_buf(),
+        created: SystemTime::now(),
+        ttl: ttl.unwrap_or(DEFAULT_TTL),
+        consumer: None,
+    });
+}
+
+/// Wait for a launched app on another thread so it doesn't linger as a
+/// zombie once it exits.
+pub fn reap(mut child: Child) {
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+}
+
+/// Remove the file shortly after `child` exits instead of waiting for the
+/// TTL.
+pub fn set_consumer(path: &Path, child: Child) {
+    let mut temp_files = TEMP_FILES.lock().unwrap();
+
+    match temp_files.iter_mut().find(|file| file.path == path) {
+        Some(file) => {
+            if let Some(previous) = file.consumer.replace(child) {
+                reap(previous);
+            }
+        }
+        None => reap(child),
+    }
+}
+
+/// Remove expired tracked files and untracked leftovers of earlier sessions.
+pub fn sweep<R: Runtime>(app_handle: &AppHandle<R>) -> usize {
+    let now = SystemTime::now();
+    let mut removed = 0;
+
+    let expired: Vec<PathBuf> = {
+        let mut temp_files = TEMP_FILES.lock().unwrap();
+        let mut expired = Vec::new();
+
+        temp_files.retain_mut(|file| {
+            if !file.expired(now) {
+                return true;
+            }
+
+            // The TTL passed while the app still runs
+            if let Some(child) = file.consumer.take() {
+                reap(child);
+            }
+
+            expired.push(file.path.clone());
+            false
+        });
+
+        expired
+    };
+
+    for path in expired {
+        if std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+
+    let tracked: Vec<PathBuf> = TEMP_FILES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|file| file.path.clone())
+        .collect();
+
+    if let Ok(entries) = std::fs::read_dir(get_temp_dir(app_handle)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if tracked.contains(&path) {
+                continue;
+            }
+
+            let stale = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| now.duration_since(modified).unwrap_or_default() >= DEFAULT_TTL)
+                .unwrap_or(true);
+
+            if stale && std::fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+
+    removed
+}
+
+pub fn start_sweeper<R: Runtime>(app_handle: AppHandle<R>) {
+    thread::spawn(move || loop {
+        let removed = sweep(&app_handle);
+        if removed > 0 {
+            log::info!("Removed {} expired temp files", removed);
+        }
+
+        thread::sleep(SWEEP_INTERVAL);
+    });
+}
+
+/// File extension matching the stored representation of an item.
+fn extension_for(item: &HistoryItem) -> &str {
+    match item.kind.as_str() {
+        "html" => "html",
+        "rtf" => "rtf",
+        // Images keep the format they were saved in
+        "image" => Path::new(&item.value)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("png"),
+        _ => "txt",
+    }
+}
+
+/// Write an item to a tracked temp file of the right extension so that
+/// external apps can open it without touching the history copy.
+pub fn materialize_item<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    item: &HistoryItem,
+) -> Result<PathBuf, String> {
+    let dir = get_temp_dir(app_handle);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let path = dir.join(format!("{}.{}", item.id, extension_for(item)));
+
+    match item.kind.as_str() {
+        "image" => {
+            let source = resolve_image_path(app_handle, &item.value);
+            std::fs::copy(source, &path).map_err(|e| e.to_string())?;
+        }
+        "files" => return Err("File items are opened in place".to_string()),
+        _ => std::fs::write(&path, &item.value).map_err(|e| e.to_string())?,
+    }
+
+    register(&path, None);
+
+    Ok(path)
+}
+
+// 清除所有物化到磁盘的临时文件，返回删除的数量
+#[command]
+pub async fn purge_temp_files<R: Runtime>(app_handle: AppHandle<R>) -> usize {
+    for file in TEMP_FILES.lock().unwrap().drain(..) {
+        if let Some(child) = file.consumer {
+            reap(child);
+        }
+    }
+
+    let mut removed = 0;
+
+    if let Ok(entries) = std::fs::read_dir(get_temp_dir(&app_handle)) {
+        for entry in entries.flatten() {
+            if std::fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+
+    removed
+}
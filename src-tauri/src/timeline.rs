@@ -0,0 +1,218 @@
+use crate::clipboard_image::resolve_image_path;
+use crate::core::cache;
+use crate::history::{get_metadata, store, HistoryItem, SOURCE_APP, SOURCE_URL};
+use crate::image_decode::decode_file;
+use crate::item_access::require_access_all;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::ImageFormat;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Runtime};
+
+/// Longest edge of the thumbnails inlined into a report.
+const THUMBNAIL_SIZE: u32 = 320;
+
+/// Longest text kept per item, journals only need a glance.
+const MAX_TEXT_CHARS: usize = 2000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("md" | "markdown") => ReportFormat::Markdown,
+            _ => ReportFormat::Html,
+        }
+    }
+}
+
+/// One item of the report, already reduced to what gets rendered.
+struct Entry {
+    time: String,
+    kind: String,
+    text: Option<String>,
+    files: Vec<String>,
+    thumbnail: Option<String>,
+    source: Option<String>,
+}
+
+fn is_date(date: &str) -> bool {
+    let bytes = date.as_bytes();
+
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            4 | 7 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+/// Downscaled PNG of an image item as a data URI.
+fn thumbnail<R: Runtime>(app_handle: &AppHandle<R>, value: &str) -> Option<String> {
+    if let Some(thumbnail) = cache::get_string(cache::THUMBNAILS, value) {
+        return Some(thumbnail);
+    }
+
+    let image = decode_file(&resolve_image_path(app_handle, value)).ok()?;
+    let image = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .ok()?;
+
+    let thumbnail = format!("data:image/png;base64,{}", STANDARD.encode(bytes));
+    cache::put_string(cache::THUMBNAILS, value.to_string(), &thumbnail);
+
+    Some(thumbnail)
+}
+
+fn to_entry<R: Runtime>(app_handle: &AppHandle<R>, item: HistoryItem) -> Entry {
+    let metadata = get_metadata(&item.id).unwrap_or_default();
+    let source = metadata
+        .get(SOURCE_URL)
+        .or_else(|| metadata.get(SOURCE_APP))
+        .cloned();
+
+    let time = item.create_time.get(11..).unwrap_or_default().to_string();
+    let mut entry = Entry {
+        time,
+        kind: item.kind.clone(),
+        text: None,
+        files: Vec::new(),
+        thumbnail: None,
+        source,
+    };
+
+    match item.kind.as_str() {
+        "image" => entry.thumbnail = thumbnail(app_handle, &item.value),
+        "files" => entry.files = serde_json::from_str(&item.value).unwrap_or_default(),
+        _ => entry.text = Some(truncate(item.plain_text())),
+    }
+
+    entry
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(date: &str, entries: &[Entry]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{date}</title>\n\
+        <style>\
+        body{{font-family:sans-serif;max-width:800px;margin:auto;padding:16px}}\
+        section{{border-bottom:1px solid #ddd;padding:8px 0}}\
+        time{{color:#888;margin-right:8px}}\
+        pre{{white-space:pre-wrap;word-break:break-word}}\
+        </style>\n</head>\n<body>\n<h1>{date}</h1>\n"
+    );
+
+    for entry in entries {
+        html.push_str(&format!(
+            "<section>\n<p><time>{}</time><b>{}</b>",
+            escape_html(&entry.time),
+            escape_html(&entry.kind)
+        ));
+        if let Some(source) = &entry.source {
+            html.push_str(&format!(" · {}", escape_html(source)));
+        }
+        html.push_str("</p>\n");
+
+        if let Some(text) = &entry.text {
+            html.push_str(&format!("<pre>{}</pre>\n", escape_html(text)));
+        }
+        if let Some(thumbnail) = &entry.thumbnail {
+            html.push_str(&format!("<img src=\"{}\">\n", thumbnail));
+        }
+        if !entry.files.is_empty() {
+            html.push_str("<ul>\n");
+            for file in &entry.files {
+                html.push_str(&format!("<li>{}</li>\n", escape_html(file)));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_markdown(date: &str, entries: &[Entry]) -> String {
+    let mut markdown = format!("# {}\n", date);
+
+    for entry in entries {
+        markdown.push_str(&format!("\n## {} · {}\n\n", entry.time, entry.kind));
+
+        if let Some(source) = &entry.source {
+            markdown.push_str(&format!("> {}\n\n", source));
+        }
+        if let Some(text) = &entry.text {
+            // A fence longer than any backtick run inside the text
+            let longest = text
+                .split(|ch| ch != '`')
+                .map(str::len)
+                .max()
+                .unwrap_or_default();
+            let fence = "`".repeat(longest.max(2) + 1);
+
+            markdown.push_str(&format!("{fence}\n{text}\n{fence}\n"));
+        }
+        if let Some(thumbnail) = &entry.thumbnail {
+            markdown.push_str(&format!("![]({})\n", thumbnail));
+        }
+        for file in &entry.files {
+            markdown.push_str(&format!("- {}\n", file));
+        }
+    }
+
+    markdown
+}
+
+// 导出某一天的剪贴板时间线，根据文件扩展名生成 markdown 或 html
+#[command]
+pub async fn export_timeline<R: Runtime>(
+    app_handle: AppHandle<R>,
+    date: String,
+    dest: String,
+) -> Result<usize, String> {
+    if !is_date(&date) {
+        return Err(format!("Invalid date {}, expected YYYY-MM-DD", date));
+    }
+
+    let dest = PathBuf::from(dest);
+    let items = store().list_items_on(&date)?;
+
+    // The report is a plain file, sensitive items need an authentication
+    require_access_all(&app_handle, items.iter().map(|item| item.id.as_str())).await?;
+
+    let entries: Vec<Entry> = items
+        .into_iter()
+        .map(|item| to_entry(&app_handle, item))
+        .collect();
+
+    let report = match ReportFormat::from_path(&dest) {
+        ReportFormat::Html => render_html(&date, &entries),
+        ReportFormat::Markdown => render_markdown(&date, &entries),
+    };
+
+    std::fs::write(&dest, report).map_err(|e| e.to_string())?;
+
+    Ok(entries.len())
+}
@@ -0,0 +1,27 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum CitationStyle {
+    /// Appends `— source: URL, copied DATE` below the text.
+    Source,
+    /// Wraps the text in a markdown quote with a linked source line.
+    Markdown,
+}
+
+pub fn cite(text: &str, url: &str, date: &str, style: CitationStyle) -> String {
+    let text = text.trim_end();
+
+    match style {
+        CitationStyle::Source => format!("{}\n\n— source: {}, copied {}", text, url, date),
+        CitationStyle::Markdown => {
+            let quote = text
+                .lines()
+                .map(|line| format!("> {}", line).trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            format!("{}\n>\n> — [source]({}), copied {}", quote, url, date)
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use serde::Deserialize;
+
+/// Browser names appended to window titles, stripped to get the page title.
+const TITLE_SUFFIXES: &[&str] = &[
+    "Google Chrome",
+    "Microsoft Edge",
+    "Mozilla Firefox",
+    "Brave",
+    "Opera",
+    "Vivaldi",
+    "Safari",
+];
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkFormat {
+    /// `[Title](url)` as plain text.
+    #[default]
+    Markdown,
+    /// `<a href="url">Title</a>` with the markdown link as plain text fallback.
+    Html,
+}
+
+/// Derive a page title from a browser window title like `Title - Google Chrome`.
+pub fn page_title_from_window(window_title: &str) -> String {
+    let mut title = window_title.trim();
+
+    for suffix in TITLE_SUFFIXES {
+        for separator in [" - ", " — ", " – "] {
+            if let Some(stripped) = title.strip_suffix(&format!("{}{}", separator, suffix)) {
+                title = stripped.trim();
+            }
+        }
+    }
+
+    title.to_string()
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn markdown_link(title: &str, url: &str) -> String {
+    let url = url.replace(' ', "%20").replace(')', "%29");
+
+    format!("[{}]({})", escape_markdown(title), url)
+}
+
+pub fn html_anchor(title: &str, url: &str) -> String {
+    format!(r#"<a href="{}">{}</a>"#, escape_html(url), escape_html(title))
+}
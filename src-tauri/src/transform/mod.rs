@@ -0,0 +1,96 @@
+use crate::clipboard_html::write_html;
+use crate::clipboard_text::write_text;
+use crate::clipboard_watcher::mark_own_write;
+use crate::history::{get_metadata, PAGE_TITLE, SOURCE_URL, WINDOW_TITLE};
+use crate::item_access::require_revealed_item;
+use tauri::{command, AppHandle, Runtime};
+use tauri_plugin_eco_paste::trigger_paste;
+
+mod citation;
+mod link;
+mod timestamp;
+
+pub use citation::*;
+pub use link::*;
+pub use timestamp::*;
+
+/// Replace the clipboard with a transformed text and paste it.
+pub async fn paste_transformed<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    text: &str,
+) -> Result<(), String> {
+    mark_own_write();
+    write_text(text)?;
+
+    trigger_paste(app_handle).await;
+
+    Ok(())
+}
+
+// 附带来源引用粘贴
+#[command]
+pub async fn paste_with_citation<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    style: CitationStyle,
+) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+    let metadata = get_metadata(&id)?;
+
+    let text = match metadata.get(SOURCE_URL) {
+        Some(url) => cite(item.plain_text(), url, item.create_date(), style),
+        None => item.plain_text().to_string(),
+    };
+
+    paste_transformed(&app_handle, &text).await
+}
+
+// 以 markdown 链接（或 html 链接）的形式粘贴网址
+#[command]
+pub async fn paste_as_markdown_link<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    format: Option<LinkFormat>,
+) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+    let metadata = get_metadata(&id)?;
+
+    let url = item.plain_text().trim();
+    let title = metadata
+        .get(PAGE_TITLE)
+        .cloned()
+        .or_else(|| metadata.get(WINDOW_TITLE).map(|title| page_title_from_window(title)))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| url.to_string());
+
+    let markdown = markdown_link(&title, url);
+
+    match format.unwrap_or_default() {
+        LinkFormat::Markdown => paste_transformed(&app_handle, &markdown).await,
+        LinkFormat::Html => {
+            mark_own_write();
+            write_html(&html_anchor(&title, url), &markdown)?;
+
+            trigger_paste(&app_handle).await;
+
+            Ok(())
+        }
+    }
+}
+
+// 将时间戳或 ISO 日期条目转换为指定的格式后粘贴
+#[command]
+pub async fn paste_timestamp<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    format: TimestampFormat,
+) -> Result<(), String> {
+    let item = require_revealed_item(&app_handle, &id).await?;
+
+    // Converted now rather than read from the metadata, local times follow
+    // the current time zone
+    let (millis, _) =
+        parse_timestamp(item.plain_text())?.ok_or("The item is not a timestamp or an ISO date")?;
+
+    paste_transformed(&app_handle, &format_timestamp(millis, format)?).await
+}
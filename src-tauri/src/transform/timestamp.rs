@@ -0,0 +1,144 @@
+use crate::history::{set_metadata, with_connection, NewHistoryEntry, TIME_CONVERSIONS};
+use crate::vault;
+use regex::Regex;
+use rusqlite::params;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+/// Unix times from 2000 up to 2100 are taken for timestamps, other numbers
+/// are more likely ids or amounts.
+const MIN_SECONDS: i64 = 946_684_800;
+const MAX_SECONDS: i64 = 4_102_444_800;
+
+/// `2024-01-31`, `2024-01-31T09:30`, `2024-01-31 09:30:00.250+02:00`. The
+/// group is the offset, times without one are local.
+static ISO_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\d{4}-\d{2}-\d{2}(?:[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(Z|[+-]\d{2}:\d{2})?)?$")
+        .unwrap()
+});
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TimestampFormat {
+    /// Seconds since the Unix epoch.
+    Epoch,
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+    /// ISO 8601 in UTC, `2024-01-31T09:30:00Z`.
+    Iso,
+    /// Local time, `2024-01-31 10:30:00`.
+    Local,
+}
+
+const FORMATS: [TimestampFormat; 4] = [
+    TimestampFormat::Epoch,
+    TimestampFormat::EpochMillis,
+    TimestampFormat::Iso,
+    TimestampFormat::Local,
+];
+
+impl TimestampFormat {
+    /// Name of the format, as in the frontend.
+    fn key(&self) -> &'static str {
+        match self {
+            TimestampFormat::Epoch => "epoch",
+            TimestampFormat::EpochMillis => "epochMillis",
+            TimestampFormat::Iso => "iso",
+            TimestampFormat::Local => "local",
+        }
+    }
+}
+
+/// A copied Unix timestamp or ISO date as UTC milliseconds, with the format
+/// it was written in. Anything else gives `None`.
+pub fn parse_timestamp(text: &str) -> Result<Option<(i64, TimestampFormat)>, String> {
+    let text = text.trim();
+
+    if !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit()) {
+        let Ok(number) = text.parse::<i64>() else {
+            return Ok(None);
+        };
+
+        if (MIN_SECONDS..MAX_SECONDS).contains(&number) {
+            return Ok(Some((number * 1000, TimestampFormat::Epoch)));
+        }
+
+        if (MIN_SECONDS * 1000..MAX_SECONDS * 1000).contains(&number) {
+            return Ok(Some((number, TimestampFormat::EpochMillis)));
+        }
+
+        return Ok(None);
+    }
+
+    let Some(captures) = ISO_DATE.captures(text) else {
+        return Ok(None);
+    };
+
+    // SQLite reads times without an offset as UTC
+    let sql = match captures.get(1) {
+        Some(_) => "SELECT CAST(round((julianday(?1) - 2440587.5) * 86400000) AS INTEGER)",
+        None => "SELECT CAST(round((julianday(?1, 'utc') - 2440587.5) * 86400000) AS INTEGER)",
+    };
+
+    let millis: Option<i64> =
+        with_connection(|connection| connection.query_row(sql, params![text], |row| row.get(0)))?;
+
+    Ok(millis.map(|millis| (millis, TimestampFormat::Iso)))
+}
+
+/// Render UTC milliseconds in a format, local times in the current time
+/// zone.
+pub fn format_timestamp(millis: i64, format: TimestampFormat) -> Result<String, String> {
+    let pattern = match format {
+        TimestampFormat::Epoch => return Ok(millis.div_euclid(1000).to_string()),
+        TimestampFormat::EpochMillis => return Ok(millis.to_string()),
+        TimestampFormat::Iso if millis % 1000 != 0 => "%Y-%m-%dT%H:%M:%fZ",
+        TimestampFormat::Iso => "%Y-%m-%dT%H:%M:%SZ",
+        TimestampFormat::Local => "%Y-%m-%d %H:%M:%S",
+    };
+
+    let sql = match format {
+        TimestampFormat::Local => "SELECT strftime(?1, ?2 / 1000.0, 'unixepoch', 'localtime')",
+        _ => "SELECT strftime(?1, ?2 / 1000.0, 'unixepoch')",
+    };
+
+    with_connection(|connection| {
+        connection.query_row(sql, params![pattern, millis], |row| row.get(0))
+    })
+}
+
+/// Save a copied timestamp or ISO date in every format with the item, as a
+/// JSON object by format. ISO dates with an offset are normalized to UTC.
+/// Skipped in vault mode like expression results.
+pub fn record_timestamp(entry: &NewHistoryEntry) {
+    if vault::is_enabled() {
+        return;
+    }
+
+    let field = |name: &str| entry.row.get(name).and_then(Value::as_str);
+
+    let (Some("text"), Some(id), Some(text)) = (field("type"), field("id"), field("value")) else {
+        return;
+    };
+
+    let recorded = parse_timestamp(text).and_then(|timestamp| {
+        let Some((millis, _)) = timestamp else {
+            return Ok(());
+        };
+
+        let conversions = FORMATS
+            .iter()
+            .map(|format| Ok((format.key(), format_timestamp(millis, *format)?)))
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+
+        let json = serde_json::to_string(&conversions).map_err(|e| e.to_string())?;
+
+        set_metadata(id, TIME_CONVERSIONS, &json)
+    });
+
+    if let Err(error) = recorded {
+        log::warn!("Failed to save the timestamp conversions: {}", error);
+    }
+}
@@ -0,0 +1,667 @@
+use crate::clipboard_image::{get_images_dir, get_thumbnails_dir, resolve_image_path};
+use crate::clipboard_text::get_blobs_dir;
+use crate::core::cache;
+use crate::history::{
+    database_path, notify_changed, seal_database, use_store, EncryptedStore, SqliteStore,
+};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::ipc::Response;
+use tauri::{command, AppHandle, Runtime};
+use x25519_dalek::{EphemeralSecret, PublicKey, SharedSecret, StaticSecret};
+
+/// Marks encrypted text, columns written before the vault was enabled stay
+/// readable as they are.
+const TEXT_PREFIX: &str = "ecovault:v1:";
+
+/// Header of encrypted files and blobs.
+const MAGIC: &[u8] = b"ECOVAULT1";
+
+/// Marks text and headers data sealed to the capture key while the vault
+/// was locked.
+const CAPTURE_TEXT_PREFIX: &str = "ecovault:pk1:";
+const CAPTURE_MAGIC: &[u8] = b"ECOVAULTPK1";
+
+const PUBLIC_KEY_LEN: usize = 32;
+
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// Encrypted into the config to tell a wrong password from a right one.
+const CHECK_TEXT: &str = "EcoPaste vault";
+
+/// Header of data sealed for WebDAV sync.
+const SYNC_MAGIC: &[u8] = b"ECOSYNC1";
+
+/// Only held in memory, from unlocking until locking or quitting.
+static CIPHER: Mutex<Option<Aes256Gcm>> = Mutex::new(None);
+static SYNC_CIPHER: Mutex<Option<Aes256Gcm>> = Mutex::new(None);
+/// Keys the content hashes blobs are deduplicated by.
+static HASH_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+/// Captures keep working while the vault is locked, they are sealed to the
+/// public half of the capture key and only unlocking reads them.
+static CAPTURE_PUBLIC: Mutex<Option<PublicKey>> = Mutex::new(None);
+static CAPTURE_SECRET: Mutex<Option<StaticSecret>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize)]
+struct VaultConfig {
+    /// Base64 salt of the password hash.
+    salt: String,
+    check: String,
+    /// Base64 public half of the capture key.
+    #[serde(default)]
+    capture_public: Option<String>,
+    /// Its secret half, sealed with the password key.
+    #[serde(default)]
+    capture_secret: Option<String>,
+    /// Base64 salt of the sync key. Published next to the synced data, so
+    /// every device unlocked with the same password derives the same key.
+    #[serde(default)]
+    sync_salt: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+/// Next to the database, the vault belongs to the data it encrypts.
+fn config_path() -> Result<PathBuf, String> {
+    Ok(database_path()?.with_extension("vault.json"))
+}
+
+fn read_config() -> Result<Option<VaultConfig>, String> {
+    let Ok(bytes) = std::fs::read(config_path()?) else {
+        return Ok(None);
+    };
+
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+
+    Ok(key)
+}
+
+fn derive_cipher(password: &str, salt: &[u8]) -> Result<Aes256Gcm, String> {
+    let key = derive_key(password, salt)?;
+
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+/// The password key and the hash key derived from it, a hash never reveals
+/// the key.
+fn derive_keys(password: &str, salt: &[u8]) -> Result<(Aes256Gcm, [u8; 32]), String> {
+    let key = derive_key(password, salt)?;
+    let hash_key = Sha256::new()
+        .chain_update(b"EcoPaste vault content hash")
+        .chain_update(key)
+        .finalize();
+
+    Ok((
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        hash_key.into(),
+    ))
+}
+
+fn cipher() -> Result<Aes256Gcm, String> {
+    CIPHER
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "The vault is locked".to_string())
+}
+
+/// Whether history is stored encrypted.
+pub fn is_enabled() -> bool {
+    config_path().is_ok_and(|path| path.exists())
+}
+
+pub fn is_unlocked() -> bool {
+    CIPHER.lock().unwrap().is_some()
+}
+
+/// Nonce followed by the ciphertext.
+fn encrypt(cipher: &Aes256Gcm, plain: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut sealed = nonce.to_vec();
+
+    sealed.extend(
+        cipher
+            .encrypt(&nonce, plain)
+            .map_err(|_| "Failed to encrypt".to_string())?,
+    );
+
+    Ok(sealed)
+}
+
+fn decrypt(cipher: &Aes256Gcm, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt, the data was changed or the key is wrong".to_string())
+}
+
+/// AES key of one capture, agreed between an ephemeral key and the
+/// capture key.
+fn capture_cipher(shared: &SharedSecret, ephemeral: &PublicKey) -> Aes256Gcm {
+    let key = Sha256::new()
+        .chain_update(shared.as_bytes())
+        .chain_update(ephemeral.as_bytes())
+        .finalize();
+
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// Ephemeral public key followed by the nonce and the ciphertext.
+fn encrypt_capture(plain: &[u8]) -> Result<Vec<u8>, String> {
+    let public = CAPTURE_PUBLIC
+        .lock()
+        .unwrap()
+        .ok_or_else(|| "The vault is locked".to_string())?;
+
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&public);
+
+    let mut sealed = ephemeral_public.as_bytes().to_vec();
+    sealed.extend(encrypt(&capture_cipher(&shared, &ephemeral_public), plain)?);
+
+    Ok(sealed)
+}
+
+fn decrypt_capture(sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < PUBLIC_KEY_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+
+    let (ephemeral, sealed) = sealed.split_at(PUBLIC_KEY_LEN);
+    let ephemeral = PublicKey::from(<[u8; PUBLIC_KEY_LEN]>::try_from(ephemeral).unwrap());
+
+    let secret = CAPTURE_SECRET
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "The vault is locked".to_string())?;
+    let shared = secret.diffie_hellman(&ephemeral);
+
+    decrypt(&capture_cipher(&shared, &ephemeral), sealed)
+}
+
+pub fn is_sealed_text(text: &str) -> bool {
+    text.starts_with(TEXT_PREFIX) || text.starts_with(CAPTURE_TEXT_PREFIX)
+}
+
+/// Encrypt with the password key, or to the capture key while the vault is
+/// locked.
+pub fn seal_text(text: &str) -> Result<String, String> {
+    if let Ok(cipher) = cipher() {
+        let sealed = encrypt(&cipher, text.as_bytes())?;
+
+        return Ok(format!("{}{}", TEXT_PREFIX, STANDARD.encode(sealed)));
+    }
+
+    let sealed = encrypt_capture(text.as_bytes())?;
+
+    Ok(format!(
+        "{}{}",
+        CAPTURE_TEXT_PREFIX,
+        STANDARD.encode(sealed)
+    ))
+}
+
+/// Decrypt text sealed by [`seal_text`], other text is returned as is.
+pub fn open_text(text: &str) -> Result<String, String> {
+    let plain = if let Some(encoded) = text.strip_prefix(TEXT_PREFIX) {
+        let sealed = STANDARD.decode(encoded).map_err(|e| e.to_string())?;
+
+        decrypt(&cipher()?, &sealed)?
+    } else if let Some(encoded) = text.strip_prefix(CAPTURE_TEXT_PREFIX) {
+        let sealed = STANDARD.decode(encoded).map_err(|e| e.to_string())?;
+
+        decrypt_capture(&sealed)?
+    } else {
+        return Ok(text.to_string());
+    };
+
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
+
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC) || bytes.starts_with(CAPTURE_MAGIC)
+}
+
+/// Encrypt with the password key, or to the capture key while the vault is
+/// locked.
+pub fn seal_bytes(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if let Ok(cipher) = cipher() {
+        let mut sealed = MAGIC.to_vec();
+        sealed.extend(encrypt(&cipher, bytes)?);
+
+        return Ok(sealed);
+    }
+
+    let mut sealed = CAPTURE_MAGIC.to_vec();
+    sealed.extend(encrypt_capture(bytes)?);
+
+    Ok(sealed)
+}
+
+/// Decrypt bytes sealed by [`seal_bytes`], other bytes are returned as is.
+pub fn open_bytes(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    if let Some(sealed) = bytes.strip_prefix(MAGIC) {
+        return decrypt(&cipher()?, sealed);
+    }
+
+    match bytes.strip_prefix(CAPTURE_MAGIC) {
+        Some(sealed) => decrypt_capture(sealed),
+        None => Ok(bytes),
+    }
+}
+
+pub fn plain_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// HMAC under the vault key, which can't confirm guessed content. While
+/// the vault is locked it is random, such content is not deduplicated.
+pub fn keyed_hash(bytes: &[u8]) -> String {
+    let Some(key) = *HASH_KEY.lock().unwrap() else {
+        let mut random = [0u8; 32];
+        OsRng.fill_bytes(&mut random);
+
+        return plain_hash(&random);
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(bytes);
+
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Hash equal content is stored once by, keyed while the vault is enabled.
+pub fn content_hash(bytes: &[u8]) -> String {
+    if is_enabled() {
+        keyed_hash(bytes)
+    } else {
+        plain_hash(bytes)
+    }
+}
+
+/// Encrypt data before it leaves the device through sync.
+pub fn seal_sync(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = SYNC_CIPHER
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "The vault is locked".to_string())?;
+
+    let mut sealed = SYNC_MAGIC.to_vec();
+    sealed.extend(encrypt(&cipher, bytes)?);
+
+    Ok(sealed)
+}
+
+/// Decrypt data sealed by [`seal_sync`] on any device, other bytes are
+/// returned as is.
+pub fn open_sync(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    let Some(sealed) = bytes.strip_prefix(SYNC_MAGIC) else {
+        return Ok(bytes);
+    };
+
+    let cipher = SYNC_CIPHER.lock().unwrap().clone().ok_or_else(|| {
+        "Synced data is encrypted, enable and unlock the vault with the same password".to_string()
+    })?;
+
+    decrypt(&cipher, sealed)
+        .map_err(|_| "Failed to decrypt synced data, the vault password differs".to_string())
+}
+
+/// Write a file, encrypted while the vault is enabled.
+pub fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let bytes = if is_enabled() {
+        seal_bytes(bytes)?
+    } else {
+        bytes.to_vec()
+    };
+
+    std::fs::write(path, bytes).map_err(|e| e.to_string())
+}
+
+/// Read a file into memory, decrypted when it was written encrypted.
+pub fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    open_bytes(std::fs::read(path).map_err(|e| e.to_string())?)
+}
+
+/// Encrypt or decrypt the stored images, their thumbnails and the full
+/// texts of overflowed items in place.
+fn seal_images<R: Runtime>(app_handle: &AppHandle<R>, seal: bool) -> Result<(), String> {
+    for dir in [
+        get_images_dir(app_handle),
+        get_thumbnails_dir(app_handle),
+        get_blobs_dir(app_handle),
+    ] {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if !path.is_file() {
+                continue;
+            }
+
+            let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+
+            let converted = match (seal, is_sealed(&bytes)) {
+                (true, false) => seal_bytes(&bytes)?,
+                (false, true) => open_bytes(bytes)?,
+                _ => continue,
+            };
+
+            std::fs::write(&path, converted).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Serve history through the encrypting store when the vault is enabled,
+/// called once the database path is known.
+pub fn init() {
+    if is_enabled() {
+        use_store(Arc::new(EncryptedStore));
+
+        match read_config() {
+            Ok(Some(config)) => load_capture_public(&config),
+            Ok(None) => {}
+            Err(error) => log::warn!("Failed to read the vault config: {}", error),
+        }
+    }
+}
+
+fn load_capture_public(config: &VaultConfig) {
+    let public = config
+        .capture_public
+        .as_ref()
+        .and_then(|encoded| STANDARD.decode(encoded).ok())
+        .and_then(|bytes| <[u8; PUBLIC_KEY_LEN]>::try_from(bytes).ok())
+        .map(PublicKey::from);
+
+    *CAPTURE_PUBLIC.lock().unwrap() = public;
+}
+
+/// A new capture key, its secret half sealed with the password key.
+fn new_capture_key(config: &mut VaultConfig) -> Result<StaticSecret, String> {
+    let secret = StaticSecret::random_from_rng(OsRng);
+
+    config.capture_public = Some(STANDARD.encode(PublicKey::from(&secret).as_bytes()));
+    config.capture_secret = Some(seal_text(&STANDARD.encode(secret.to_bytes()))?);
+
+    Ok(secret)
+}
+
+fn random_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    salt
+}
+
+/// Salt of the sync key, `None` while the vault is disabled.
+pub fn sync_salt() -> Result<Option<Vec<u8>>, String> {
+    let Some(config) = read_config()? else {
+        return Ok(None);
+    };
+
+    config
+        .sync_salt
+        .map(|salt| STANDARD.decode(salt).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+/// Take over the sync salt another device published first, returns whether
+/// it differed. The sync key is derived from it on the next unlock, the
+/// password is not kept to derive it now.
+pub fn adopt_sync_salt(salt: &[u8]) -> Result<bool, String> {
+    let mut config = read_config()?.ok_or("The vault is not enabled")?;
+    let salt = STANDARD.encode(salt);
+
+    if config.sync_salt.as_deref() == Some(salt.as_str()) {
+        return Ok(false);
+    }
+
+    config.sync_salt = Some(salt);
+    write_config(&config)?;
+    let _ = SYNC_CIPHER.lock().unwrap().take();
+
+    Ok(true)
+}
+
+fn write_config(config: &VaultConfig) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(config).map_err(|e| e.to_string())?;
+
+    std::fs::write(config_path()?, bytes).map_err(|e| e.to_string())
+}
+
+/// Open the secret half of the capture key, vaults enabled before there
+/// was one get it on their first unlock.
+fn unlock_capture_key(config: &mut VaultConfig) -> Result<(), String> {
+    let secret = match &config.capture_secret {
+        Some(sealed) => {
+            let bytes = STANDARD
+                .decode(open_text(sealed)?)
+                .map_err(|e| e.to_string())?;
+
+            StaticSecret::from(
+                <[u8; 32]>::try_from(bytes).map_err(|_| "Invalid capture key".to_string())?,
+            )
+        }
+        None => {
+            let secret = new_capture_key(config)?;
+            write_config(config)?;
+
+            secret
+        }
+    };
+
+    load_capture_public(config);
+    let _ = CAPTURE_SECRET.lock().unwrap().insert(secret);
+
+    Ok(())
+}
+
+fn unlock(password: &str) -> Result<(), String> {
+    let mut config = read_config()?.ok_or("The vault is not enabled")?;
+    let salt = STANDARD.decode(&config.salt).map_err(|e| e.to_string())?;
+    let (cipher, hash_key) = derive_keys(password, &salt)?;
+
+    let check = config
+        .check
+        .strip_prefix(TEXT_PREFIX)
+        .and_then(|encoded| STANDARD.decode(encoded).ok())
+        .and_then(|sealed| decrypt(&cipher, &sealed).ok());
+
+    if check.as_deref() != Some(CHECK_TEXT.as_bytes()) {
+        return Err("Wrong vault password".to_string());
+    }
+
+    // Vaults enabled before the sync salt was stored get one now
+    let sync_salt = match &config.sync_salt {
+        Some(salt) => STANDARD.decode(salt).map_err(|e| e.to_string())?,
+        None => {
+            let salt = random_salt();
+            config.sync_salt = Some(STANDARD.encode(salt));
+            write_config(&config)?;
+
+            salt.to_vec()
+        }
+    };
+    let sync_cipher = derive_cipher(password, &sync_salt)?;
+
+    let _ = CIPHER.lock().unwrap().insert(cipher);
+    let _ = SYNC_CIPHER.lock().unwrap().insert(sync_cipher);
+    let _ = HASH_KEY.lock().unwrap().insert(hash_key);
+
+    unlock_capture_key(&mut config)
+}
+
+// 开启加密保险库，历史记录的内容和图片文件加密存储，解锁后才在内存中解密
+#[command]
+pub async fn enable_vault<R: Runtime>(
+    app_handle: AppHandle<R>,
+    password: String,
+) -> Result<(), String> {
+    if is_enabled() {
+        return Err("The vault is already enabled".to_string());
+    }
+
+    if password.is_empty() {
+        return Err("The vault password cannot be empty".to_string());
+    }
+
+    let salt = random_salt();
+    let sync_salt = random_salt();
+
+    let (cipher, hash_key) = derive_keys(&password, &salt)?;
+    let sync_cipher = derive_cipher(&password, &sync_salt)?;
+    let _ = CIPHER.lock().unwrap().insert(cipher);
+    let _ = SYNC_CIPHER.lock().unwrap().insert(sync_cipher);
+    let _ = HASH_KEY.lock().unwrap().insert(hash_key);
+
+    let mut config = VaultConfig {
+        salt: STANDARD.encode(salt),
+        check: seal_text(CHECK_TEXT)?,
+        capture_public: None,
+        capture_secret: None,
+        sync_salt: Some(STANDARD.encode(sync_salt)),
+    };
+    let secret = new_capture_key(&mut config)?;
+
+    // The config goes first, data sealed before a crash stays readable
+    write_config(&config)?;
+    load_capture_public(&config);
+    let _ = CAPTURE_SECRET.lock().unwrap().insert(secret);
+    use_store(Arc::new(EncryptedStore));
+
+    tauri::async_runtime::spawn_blocking(move || {
+        seal_database(true)?;
+        seal_images(&app_handle, true)?;
+
+        notify_changed(&app_handle);
+
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 关闭加密保险库，所有内容解密后恢复为明文存储
+#[command]
+pub async fn disable_vault<R: Runtime>(
+    app_handle: AppHandle<R>,
+    password: String,
+) -> Result<(), String> {
+    unlock(&password)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        seal_database(false)?;
+        seal_images(&app_handle, false)?;
+
+        std::fs::remove_file(config_path()?).map_err(|e| e.to_string())?;
+        use_store(Arc::new(SqliteStore));
+        let _ = CIPHER.lock().unwrap().take();
+        let _ = SYNC_CIPHER.lock().unwrap().take();
+        let _ = HASH_KEY.lock().unwrap().take();
+        let _ = CAPTURE_PUBLIC.lock().unwrap().take();
+        let _ = CAPTURE_SECRET.lock().unwrap().take();
+
+        notify_changed(&app_handle);
+
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 用密码解锁保险库
+#[command]
+pub async fn unlock_vault<R: Runtime>(
+    app_handle: AppHandle<R>,
+    password: String,
+) -> Result<(), String> {
+    // The password hash is deliberately slow
+    tauri::async_runtime::spawn_blocking(move || unlock(&password))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    notify_changed(&app_handle);
+
+    Ok(())
+}
+
+// 锁定保险库，清除内存中的密钥
+#[command]
+pub async fn lock_vault<R: Runtime>(app_handle: AppHandle<R>) {
+    let _ = CIPHER.lock().unwrap().take();
+    let _ = SYNC_CIPHER.lock().unwrap().take();
+    let _ = HASH_KEY.lock().unwrap().take();
+    let _ = CAPTURE_SECRET.lock().unwrap().take();
+
+    // Decrypted images and search text must not outlive the key
+    cache::clear();
+
+    notify_changed(&app_handle);
+}
+
+// 获取保险库是否开启、是否已解锁
+#[command]
+pub async fn get_vault_status() -> VaultStatus {
+    VaultStatus {
+        enabled: is_enabled(),
+        unlocked: is_unlocked(),
+    }
+}
+
+// 读取加密存储的图片并在内存中解密，供界面显示
+#[command]
+pub async fn read_vault_image<R: Runtime>(
+    app_handle: AppHandle<R>,
+    path: String,
+) -> Result<Response, String> {
+    let path = resolve_image_path(&app_handle, &path);
+
+    // Only the image store, this is not a general file reader
+    if !path.starts_with(get_images_dir(&app_handle))
+        || path
+            .components()
+            .any(|component| component == Component::ParentDir)
+    {
+        return Err("Not a history image".to_string());
+    }
+
+    Ok(Response::new(read_file(&path)?))
+}
@@ -0,0 +1,145 @@
+use crate::context::foreground_context;
+use crate::excluded_apps::{source_app, source_pid};
+use crate::history::{store, VM_NAME};
+use crate::process_tree::lineage;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::command;
+
+/// VM names whose copies are not recorded, set by the webview.
+static EXCLUDED_VMS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum Hypervisor {
+    Vmware,
+    VirtualBox,
+    HyperV,
+}
+
+impl Hypervisor {
+    fn label(self) -> &'static str {
+        match self {
+            Hypervisor::Vmware => "VMware",
+            Hypervisor::VirtualBox => "VirtualBox",
+            Hypervisor::HyperV => "Hyper-V",
+        }
+    }
+
+    /// Cut the VM name out of the title of the VM's window, e.g.
+    /// `Ubuntu [Running] - Oracle VirtualBox`.
+    fn vm_name(self, title: &str) -> Option<String> {
+        let name = match self {
+            Hypervisor::Vmware => title.split(" - VMware").next(),
+            Hypervisor::VirtualBox => title
+                .split(" [")
+                .next()
+                .and_then(|name| name.split(" - Oracle").next()),
+            Hypervisor::HyperV => title
+                .split(" - Virtual Machine Connection")
+                .next()
+                .and_then(|name| name.split(" on ").next()),
+        }?;
+
+        let name = name.trim();
+
+        // The title had none of the markers, it is not a VM window
+        (!name.is_empty() && name != title.trim()).then(|| name.to_string())
+    }
+}
+
+/// Processes that write the host clipboard on behalf of a guest, with
+/// their window titled after the VM, and the guest tools that do the same
+/// inside a VM. Names are lowercased and without `.exe`.
+const HOST_PROCESSES: &[(&str, Hypervisor)] = &[
+    ("vmware", Hypervisor::Vmware),
+    ("vmware-vmx", Hypervisor::Vmware),
+    ("vmplayer", Hypervisor::Vmware),
+    ("vmware fusion", Hypervisor::Vmware),
+    ("virtualboxvm", Hypervisor::VirtualBox),
+    ("virtualbox", Hypervisor::VirtualBox),
+    ("vmconnect", Hypervisor::HyperV),
+];
+
+const GUEST_PROCESSES: &[(&str, Hypervisor)] = &[
+    ("vmtoolsd", Hypervisor::Vmware),
+    ("vmware-user", Hypervisor::Vmware),
+    ("vmware-user-suid-wrapper", Hypervisor::Vmware),
+    ("vboxtray", Hypervisor::VirtualBox),
+    ("vboxclient", Hypervisor::VirtualBox),
+    ("vboxservice", Hypervisor::VirtualBox),
+];
+
+/// Where a copy bridged from a virtual machine came from.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VmSource {
+    pub hypervisor: Hypervisor,
+    /// Name of the VM from its window title, the hypervisor's name when the
+    /// copy came from the guest tools of the machine EcoPaste runs in.
+    pub name: String,
+}
+
+fn normalize(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .trim_end_matches(".exe")
+        .to_string()
+}
+
+fn find(processes: &[(&str, Hypervisor)], names: &[String]) -> Option<Hypervisor> {
+    names.iter().find_map(|name| {
+        processes
+            .iter()
+            .find(|(process, _)| *process == name)
+            .map(|(_, hypervisor)| *hypervisor)
+    })
+}
+
+/// Detect a copy made inside a virtual machine from the process that wrote
+/// the clipboard and its parents.
+pub fn detect() -> Option<VmSource> {
+    let mut names: Vec<String> = source_app().into_iter().collect();
+    names.extend(source_pid().map(lineage).unwrap_or_default());
+
+    let names: Vec<String> = names.iter().map(|name| normalize(name)).collect();
+
+    if let Some(hypervisor) = find(HOST_PROCESSES, &names) {
+        let name = foreground_context()
+            .window_title
+            .and_then(|title| hypervisor.vm_name(&title))
+            .unwrap_or_else(|| hypervisor.label().to_string());
+
+        return Some(VmSource { hypervisor, name });
+    }
+
+    find(GUEST_PROCESSES, &names).map(|hypervisor| VmSource {
+        hypervisor,
+        name: hypervisor.label().to_string(),
+    })
+}
+
+/// Whether copies from this VM are excluded by the user.
+pub fn is_excluded(source: &VmSource) -> bool {
+    EXCLUDED_VMS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(&source.name))
+}
+
+// 获取复制过内容的虚拟机
+#[command]
+pub async fn get_vm_sources() -> Result<Vec<String>, String> {
+    store().metadata_values(VM_NAME)
+}
+
+// 设置不记录复制内容的虚拟机
+#[command]
+pub async fn set_excluded_vms(names: Vec<String>) {
+    *EXCLUDED_VMS.lock().unwrap() = names
+        .iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+}
@@ -8,7 +8,6 @@ import { isString } from "es-toolkit";
 import { RouterProvider } from "react-router-dom";
 import { useSnapshot } from "valtio";
 import { LISTEN_KEY, PRESET_SHORTCUT } from "./constants";
-import { destroyDatabase } from "./database";
 import { useImmediateKey } from "./hooks/useImmediateKey";
 import { useTauriListen } from "./hooks/useTauriListen";
 import { useWindowState } from "./hooks/useWindowState";
@@ -59,9 +58,6 @@ const App = () => {
     showWindow();
   });
 
-  // 监听关闭数据库的事件
-  useTauriListen(LISTEN_KEY.CLOSE_DATABASE, destroyDatabase);
-
   // 链接跳转到系统浏览器
   useEventListener("click", (event) => {
     const link = (event.target as HTMLElement).closest("a");
@@ -1,14 +1,46 @@
 import { convertFileSrc } from "@tauri-apps/api/core";
-import type { FC, HTMLAttributes } from "react";
+import {
+  type FC,
+  type HTMLAttributes,
+  type SyntheticEvent,
+  useEffect,
+  useState,
+} from "react";
+import { readVaultImage } from "@/plugins/vault";
 
 interface LocalImage extends HTMLAttributes<HTMLImageElement> {
   src: string;
 }
 
 const LocalImage: FC<LocalImage> = (props) => {
-  const { src, ...rest } = props;
+  const { src, onError, ...rest } = props;
 
-  return <img {...rest} src={convertFileSrc(src)} />;
+  const [blobUrl, setBlobUrl] = useState<string>();
+
+  useEffect(() => {
+    setBlobUrl(void 0);
+  }, [src]);
+
+  useEffect(() => {
+    return () => {
+      if (blobUrl) URL.revokeObjectURL(blobUrl);
+    };
+  }, [blobUrl]);
+
+  // 开启加密保险库后图片加密存储，无法直接加载，改为解密后显示
+  const handleError = async (event: SyntheticEvent<HTMLImageElement>) => {
+    if (!blobUrl) {
+      const bytes = await readVaultImage(src).catch(() => null);
+
+      if (bytes) return setBlobUrl(URL.createObjectURL(new Blob([bytes])));
+    }
+
+    onError?.(event);
+  };
+
+  return (
+    <img {...rest} onError={handleError} src={blobUrl ?? convertFileSrc(src)} />
+  );
 };
 
 export default LocalImage;
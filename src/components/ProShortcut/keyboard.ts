@@ -1,4 +1,4 @@
-import { defaults } from "es-toolkit/compat";
+import { defaults, find } from "es-toolkit/compat";
 import { isMac } from "@/utils/is";
 
 export interface Key {
@@ -317,3 +317,15 @@ export const getKeySymbol = (key: string) => {
 
   return matched?.symbol ?? key;
 };
+
+/**
+ * 将窗口内快捷键（如 `ctrl.shift.v`）转换为全局快捷键（如 `Control+Shift+V`）
+ */
+export const toTauriShortcut = (value: string) => {
+  if (!value) return "";
+
+  return value
+    .split(".")
+    .map((key) => find(keys, { hookKey: key })?.tauriKey ?? key)
+    .join("+");
+};
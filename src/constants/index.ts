@@ -28,8 +28,9 @@ export const LISTEN_KEY = {
   CLIPBOARD_ITEM_PREVIEW: "clipboard-item-preview",
   CLIPBOARD_ITEM_SELECT_NEXT: "clipboard-item-select-next",
   CLIPBOARD_ITEM_SELECT_PREV: "clipboard-item-select-prev",
-  CLOSE_DATABASE: "close-database",
+  LEGACY_MIGRATION_PROGRESS: "legacy-migration-progress",
   REFRESH_CLIPBOARD_LIST: "refresh-clipboard-list",
+  SHELL_COPY_FILES: "shell-copy-files",
   SHOW_WINDOW: "show-window",
   STORE_CHANGED: "store-changed",
   TOGGLE_LISTEN_CLIPBOARD: "toggle-listen-clipboard",
@@ -1,62 +1,26 @@
-import { exists, remove } from "@tauri-apps/plugin-fs";
-import type { AnyObject } from "antd/es/_util/type";
-import type { SelectQueryBuilder } from "kysely";
-import { getDefaultSaveImagePath } from "tauri-plugin-clipboard-x-api";
-import type { DatabaseSchema, DatabaseSchemaHistory } from "@/types/database";
-import { join } from "@/utils/path";
-import { getDatabase } from ".";
-
-type QueryBuilder = SelectQueryBuilder<DatabaseSchema, "history", AnyObject>;
-
-export const selectHistory = async (
-  fn?: (qb: QueryBuilder) => QueryBuilder,
-) => {
-  const db = await getDatabase();
-
-  let qb = db.selectFrom("history").selectAll() as QueryBuilder;
-
-  if (fn) {
-    qb = fn(qb);
-  }
-
-  return qb.execute() as Promise<DatabaseSchemaHistory[]>;
+import {
+  deleteHistoryEntry,
+  insertHistoryEntry,
+  updateHistoryEntry,
+} from "@/plugins/history";
+import type { DatabaseSchemaHistory } from "@/types/database";
+
+// 由后端写入并记录修改
+export const insertHistory = (data: DatabaseSchemaHistory) => {
+  return insertHistoryEntry(data);
 };
 
-export const insertHistory = async (data: DatabaseSchemaHistory) => {
-  const db = await getDatabase();
-
-  return db.insertInto("history").values(data).execute();
-};
-
-export const updateHistory = async (
+// 由后端修改并记录修改，启用保险库时备注会被加密
+export const updateHistory = (
   id: string,
-  nextData: Partial<DatabaseSchemaHistory>,
+  nextData: Partial<
+    Pick<DatabaseSchemaHistory, "createTime" | "favorite" | "note">
+  >,
 ) => {
-  const db = await getDatabase();
-
-  return db.updateTable("history").set(nextData).where("id", "=", id).execute();
+  return updateHistoryEntry(id, nextData);
 };
 
-export const deleteHistory = async (data: DatabaseSchemaHistory) => {
-  const { id, type, value } = data;
-
-  const db = await getDatabase();
-
-  await db.deleteFrom("history").where("id", "=", id).execute();
-
-  if (type !== "image") return;
-
-  let path = value;
-
-  const saveImagePath = await getDefaultSaveImagePath();
-
-  if (!value.startsWith(saveImagePath)) {
-    path = join(saveImagePath, value);
-  }
-
-  const existed = await exists(path);
-
-  if (!existed) return;
-
-  return remove(path);
+// 由后端删除并记录修改，图片条目会一并删除图片文件
+export const deleteHistory = (data: DatabaseSchemaHistory) => {
+  return deleteHistoryEntry(data.id);
 };
@@ -5,24 +5,36 @@ import { message } from "antd";
 import { cloneDeep } from "es-toolkit";
 import { isEmpty, remove } from "es-toolkit/compat";
 import { nanoid } from "nanoid";
-import {
-  type ClipboardChangeOptions,
-  COMMAND,
-  type ReadClipboard,
-  startListening,
+import type {
+  ClipboardChangeOptions,
+  ReadClipboard,
 } from "tauri-plugin-clipboard-x-api";
 import { fullName } from "tauri-plugin-fs-pro-api";
-import {
-  insertHistory,
-  selectHistory,
-  updateHistory,
-} from "@/database/history";
+import { insertHistory, updateHistory } from "@/database/history";
 import { i18n } from "@/locales";
 import type { State } from "@/pages/Main";
 import {
+  addDroppedText,
+  CLIPBOARD_CHANGED_EVENT,
+  type CapturedClipboard,
+  CLIPBOARD_DROPPED_EVENT,
+  type ClipboardChange,
+  type DroppedContent,
   getClipboardTextSubtype,
+  type PasteRequest,
   readClipboardWithRetry,
+  type ScreenshotInfo,
+  type SourceApp,
+  type VmSource,
 } from "@/plugins/clipboard";
+import {
+  applyCaptureRules,
+  findHistoryDuplicate,
+  ocrHistoryItem,
+  recordScreenshot,
+  saveItemMetadata,
+} from "@/plugins/history";
+import { runCaptureProcessors } from "@/plugins/processors";
 import { clipboardStore } from "@/stores/clipboard";
 import type { DatabaseSchemaHistory } from "@/types/database";
 import { formatDate } from "@/utils/dayjs";
@@ -35,13 +47,33 @@ export const useClipboard = (
   state: State,
   options?: ClipboardChangeOptions,
 ) => {
-  useMount(async () => {
-    await startListening();
-
+  useMount(() => {
     let debounceTimer: ReturnType<typeof setTimeout> | null = null;
     let isProcessing = false;
+    let request: PasteRequest | null = null;
+    let vm: VmSource | null = null;
+    let source: SourceApp | null = null;
+    let screenshot: ScreenshotInfo | null = null;
+
+    listen<ClipboardChange>(CLIPBOARD_CHANGED_EVENT, ({ payload }) => {
+      // The app's own writes (pasting an item, copying a preview) are not
+      // captured again. A pending change is dropped too, since the clipboard
+      // now holds what the app wrote.
+      if (payload.cause.ownWrite) {
+        if (debounceTimer) {
+          clearTimeout(debounceTimer);
+          debounceTimer = null;
+        }
+
+        return;
+      }
+
+      // Only the final clipboard state is processed, so is its request.
+      request = payload.request;
+      vm = payload.vm;
+      source = payload.source;
+      screenshot = payload.screenshot;
 
-    listen(COMMAND.CLIPBOARD_CHANGED, () => {
       // Always fire beforeRead immediately so audio feedback is prompt.
       options?.beforeRead?.();
 
@@ -59,17 +91,78 @@ export const useClipboard = (
         isProcessing = true;
 
         try {
-          await processClipboard(state);
+          // 从虚拟机复制的内容记录虚拟机名称，其它记录来源应用
+          const metadata = vm
+            ? { vm_hypervisor: vm.hypervisor, vm_name: vm.name }
+            : source
+              ? sourceMetadata(source)
+              : undefined;
+
+          await processClipboard(state, request, metadata, screenshot);
         } finally {
           isProcessing = false;
         }
       }, DEBOUNCE_MS);
     });
+
+    // 拖放到主窗口上的文件和文本，与复制的内容一样保存
+    listen<DroppedContent>(CLIPBOARD_DROPPED_EVENT, ({ payload }) => {
+      const { files, text, metadata } = payload;
+      const result: ReadClipboard = {};
+
+      if (files) {
+        result.files = {
+          count: files.size,
+          type: "files",
+          value: files.files.map(({ path }) => path),
+        };
+      }
+
+      if (text) {
+        result.text = { count: text.length, type: "text", value: text };
+      }
+
+      captureContent(state, result, null, metadata, null);
+    });
+
+    // 文件由原生拖放处理，网页内的拖放只剩文本
+    document.addEventListener("dragover", (event) => {
+      if (event.dataTransfer?.types.includes("text/plain")) {
+        event.preventDefault();
+      }
+    });
+
+    document.addEventListener("drop", (event) => {
+      const text = event.dataTransfer?.getData("text/plain");
+
+      if (!text) return;
+
+      event.preventDefault();
+
+      addDroppedText(text);
+    });
   });
 };
 
-async function processClipboard(state: State) {
-  let result: ReadClipboard;
+function sourceMetadata(source: SourceApp) {
+  const metadata: Record<string, string> = { source_app: source.name };
+
+  if (source.path) metadata.source_path = source.path;
+  if (source.windowTitle) metadata.window_title = source.windowTitle;
+  if (source.icon) metadata.source_icon = source.icon;
+  if (source.url) metadata.source_url = source.url;
+  if (source.pageTitle) metadata.page_title = source.pageTitle;
+
+  return metadata;
+}
+
+async function processClipboard(
+  state: State,
+  request: PasteRequest | null,
+  metadata: Record<string, string> | undefined,
+  screenshot: ScreenshotInfo | null,
+) {
+  let result: CapturedClipboard;
   try {
     result = await readClipboardWithRetry();
   } catch (err) {
@@ -78,8 +171,18 @@ async function processClipboard(state: State) {
     return;
   }
 
+  await captureContent(state, result, request, metadata, screenshot);
+}
+
+async function captureContent(
+  state: State,
+  result: CapturedClipboard,
+  request: PasteRequest | null,
+  metadata: Record<string, string> | undefined,
+  screenshot: ScreenshotInfo | null,
+) {
   try {
-    const { files, image, html, rtf, text } = result;
+    const { files, image, html, rtf, text, blobPath } = result;
 
     if (isEmpty(result) || Object.values(result).every(isEmpty)) return;
 
@@ -111,6 +214,7 @@ async function processClipboard(state: State) {
     } else if (image) {
       Object.assign(data, image, {
         group: "image",
+        subtype: screenshot ? "screenshot" : undefined,
       });
     } else if (copyPlain && (html || rtf)) {
       const fallbackValue = data.search || html?.value || rtf?.value || "";
@@ -124,6 +228,21 @@ async function processClipboard(state: State) {
       });
     }
 
+    // 其它应用通过 ecopaste-paste 格式附带的备注和收藏
+    const requested: Partial<DatabaseSchemaHistory> = {};
+
+    if (request?.action === "store") {
+      if (request.note) {
+        requested.note = request.note;
+      }
+
+      if (request.favorite) {
+        requested.favorite = true;
+      }
+    }
+
+    Object.assign(data, requested);
+
     if (!data.type) {
       logWarn("Clipboard item has no recognized type, skipping.");
       return;
@@ -148,35 +267,24 @@ async function processClipboard(state: State) {
       sqlData.value = JSON.stringify(value);
     }
 
-    const [matched] = await selectHistory((qb) => {
-      const { type, value } = sqlData;
-
-      if (type === "image") {
-        // For images, match by path OR by identical dimensions + file size.
-        // This catches duplicates where the same image was saved to different
-        // paths (e.g. due to rapid clipboard events producing different hashes
-        // from partial reads).
-        return qb
-          .where("type", "=", "image")
-          .where((eb) =>
-            eb.or([
-              eb("value", "=", value),
-              eb.and([
-                eb("width", "=", data.width),
-                eb("height", "=", data.height),
-                eb("count", "=", data.count),
-              ]),
-            ]),
-          );
-      }
-
-      return qb.where("type", "=", type).where("value", "=", value);
+    // 由后端查找重复的条目，启用保险库时内容在解密后比较。超出大小限制的
+    // 文本只保存了预览，按完整内容所在的文件判断是否重复
+    const matchedId = await findHistoryDuplicate({
+      blobPath,
+      count: data.count,
+      height: data.height,
+      type,
+      value: sqlData.value,
+      width: data.width,
     });
 
-    const visible = state.group === "all" || state.group === group;
+    const visible =
+      state.group === "all" ||
+      state.group === group ||
+      (state.group === "screenshot" && data.subtype === "screenshot");
 
-    if (matched) {
-      const { id } = matched;
+    if (matchedId) {
+      const id = matchedId;
 
       if (visible) {
         remove(state.list, { id });
@@ -184,7 +292,7 @@ async function processClipboard(state: State) {
         state.list.unshift({ ...data, id });
       }
 
-      return updateHistory(id, { createTime });
+      return updateHistory(id, { createTime, ...requested });
     }
 
     if (visible) {
@@ -192,6 +300,39 @@ async function processClipboard(state: State) {
     }
 
     await insertHistory(sqlData);
+
+    if (request?.action === "store" && request.tags.length > 0) {
+      await saveItemMetadata(data.id, { tags: request.tags.join(",") });
+    }
+
+    if (metadata && !isEmpty(metadata)) {
+      await saveItemMetadata(data.id, metadata);
+    }
+
+    if (blobPath) {
+      await saveItemMetadata(data.id, { blob_path: blobPath });
+    }
+
+    // 截图工具截取的图片记录截图时间和所在的显示器
+    if (screenshot && data.subtype === "screenshot") {
+      await recordScreenshot(data.id, screenshot);
+    }
+
+    // 识别图片中的文字，完成后图片可以被搜索到
+    if (data.type === "image" && clipboardStore.content.autoOcr) {
+      ocrHistoryItem(data.id).catch((err) => {
+        logWarn(`OCR failed: ${String(err)}`);
+      });
+    }
+
+    applyCaptureRules(data.id).catch((err) => {
+      logWarn(`Capture rules failed: ${String(err)}`);
+    });
+
+    // 插件目录中启用的处理器补充元数据、标签或敏感标记
+    runCaptureProcessors(data.id).catch((err) => {
+      logWarn(`Capture processors failed: ${String(err)}`);
+    });
   } catch (err) {
     logError(`Clipboard processing failed: ${String(err)}`);
     message.warning(i18n.t("clipboard.hints.clipboard_process_failed"));
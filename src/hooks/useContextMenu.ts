@@ -6,10 +6,12 @@ import { find, isArray, remove } from "es-toolkit/compat";
 import { type MouseEvent, useContext } from "react";
 import { useTranslation } from "react-i18next";
 import { useSnapshot } from "valtio";
-import { deleteHistory, updateHistory } from "@/database/history";
+import { deleteHistory } from "@/database/history";
 import { MainContext } from "@/pages/Main";
 import type { ItemProps } from "@/pages/Main/components/HistoryList/components/Item";
 import { pasteToClipboard, writeToClipboard } from "@/plugins/clipboard";
+import { toggleHistoryFavorite } from "@/plugins/history";
+import { pushPasteQueue } from "@/plugins/paste";
 import { clipboardStore } from "@/stores/clipboard";
 import { globalStore } from "@/stores/global";
 import { isMac } from "@/utils/is";
@@ -25,7 +27,8 @@ interface ContextMenuItem extends MenuItemOptions {
 
 export const useContextMenu = (props: UseContextMenuProps) => {
   const { data, deleteModal, handleNote, handleNext } = props;
-  const { id, type, value, group, favorite, subtype } = data;
+  const { id, type, value, group, favorite, subtype, source } = data;
+  const readonly = source === "shared";
   const { t } = useTranslation();
   const { env } = useSnapshot(globalStore);
   const { rootState } = useContext(MainContext);
@@ -35,6 +38,8 @@ export const useContextMenu = (props: UseContextMenuProps) => {
   };
 
   const handleFavorite = async () => {
+    if (readonly) return;
+
     const nextFavorite = !favorite;
 
     const matched = find(rootState.list, { id });
@@ -43,7 +48,7 @@ export const useContextMenu = (props: UseContextMenuProps) => {
 
     matched.favorite = nextFavorite;
 
-    updateHistory(id, { favorite: nextFavorite });
+    toggleHistoryFavorite(id, nextFavorite);
   };
 
   const openToBrowser = () => {
@@ -69,7 +74,9 @@ export const useContextMenu = (props: UseContextMenuProps) => {
   const downloadImage = async () => {
     if (type !== "image") return;
 
-    const fileName = `${env.appName}_${id}.png`;
+    // 图片可能按设置保存为 WebP 或 JPEG
+    const extname = value.split(".").pop() ?? "png";
+    const fileName = `${env.appName}_${id}.${extname}`;
     const path = join(await downloadDir(), fileName);
 
     await copyFile(value, path);
@@ -88,6 +95,8 @@ export const useContextMenu = (props: UseContextMenuProps) => {
   };
 
   const handleDelete = async () => {
+    if (readonly) return;
+
     const matched = find(rootState.list, { id });
 
     if (!matched) return;
@@ -128,8 +137,14 @@ export const useContextMenu = (props: UseContextMenuProps) => {
       },
       {
         action: handleNote,
+        hide: readonly,
         text: t("clipboard.button.context_menu.note"),
       },
+      {
+        action: () => pushPasteQueue([id]),
+        hide: readonly,
+        text: t("clipboard.button.context_menu.add_to_paste_queue"),
+      },
       {
         action: pasteAsText,
         hide: type !== "html" && type !== "rtf",
@@ -142,6 +157,7 @@ export const useContextMenu = (props: UseContextMenuProps) => {
       },
       {
         action: handleFavorite,
+        hide: readonly,
         text: favorite
           ? t("clipboard.button.context_menu.unfavorite")
           : t("clipboard.button.context_menu.favorite"),
@@ -175,6 +191,7 @@ export const useContextMenu = (props: UseContextMenuProps) => {
       },
       {
         action: handleDelete,
+        hide: readonly,
         text: t("clipboard.button.context_menu.delete"),
       },
     ];
@@ -5,8 +5,11 @@ import { unionBy } from "es-toolkit/compat";
 import { useContext } from "react";
 import { getDefaultSaveImagePath } from "tauri-plugin-clipboard-x-api";
 import { LISTEN_KEY } from "@/constants";
-import { selectHistory } from "@/database/history";
 import { MainContext } from "@/pages/Main";
+import { queryHistory, searchHistory } from "@/plugins/history";
+import { type SharedSnippet, searchSnippets } from "@/plugins/snippets";
+import type { DatabaseSchemaHistory } from "@/types/database";
+import { formatDate } from "@/utils/dayjs";
 import { isBlank } from "@/utils/is";
 import { getSaveImagePath, join } from "@/utils/path";
 import { useTauriListen } from "./useTauriListen";
@@ -15,6 +18,24 @@ interface Options {
   scrollToTop: () => void;
 }
 
+const toHistoryItem = (snippet: SharedSnippet): DatabaseSchemaHistory => {
+  const { id, title, content, libraryName } = snippet;
+
+  return {
+    count: content.length,
+    createTime: formatDate(),
+    favorite: false,
+    group: "text",
+    id: `shared:${id}`,
+    libraryName,
+    note: title,
+    search: content,
+    source: "shared",
+    type: "text",
+    value: content,
+  };
+};
+
 export const useHistoryList = (options: Options) => {
   const { scrollToTop } = options;
   const { rootState } = useContext(MainContext);
@@ -31,29 +52,20 @@ export const useHistoryList = (options: Options) => {
 
       state.loading = true;
 
-      const { page } = state;
-
-      const list = await selectHistory((qb) => {
-        const { size } = state;
-        const { group, search } = rootState;
-        const isFavoriteGroup = group === "favorite";
-        const isNormalGroup = group !== "all" && !isFavoriteGroup;
-
-        return qb
-          .$if(isFavoriteGroup, (eb) => eb.where("favorite", "=", true))
-          .$if(isNormalGroup, (eb) => eb.where("group", "=", group))
-          .$if(!isBlank(search), (eb) => {
-            return eb.where((eb) => {
-              return eb.or([
-                eb("search", "like", eb.val(`%${search}%`)),
-                eb("note", "like", eb.val(`%${search}%`)),
-              ]);
-            });
-          })
-          .offset((page - 1) * size)
-          .limit(size)
-          .orderBy("createTime", "desc");
-      });
+      const { page, size } = state;
+      const { group, search } = rootState;
+
+      const { items: list, hasMore } =
+        search && !isBlank(search)
+          ? await searchHistory<DatabaseSchemaHistory>(search, {
+              group,
+              page,
+              size,
+            }).then(({ matches, hasMore }) => ({
+              hasMore,
+              items: matches.map(({ item }) => item),
+            }))
+          : await queryHistory<DatabaseSchemaHistory>({ group, page, size });
 
       for (const item of list) {
         const { type, value } = item;
@@ -78,12 +90,19 @@ export const useHistoryList = (options: Options) => {
         }
       }
 
-      state.noMore = list.length === 0;
+      state.noMore = !hasMore;
 
       if (page === 1) {
+        // 共享片段库只读，只在搜索时合并到第一页的末尾
+        if (!isBlank(search) && (group === "all" || group === "text")) {
+          const snippets = await searchSnippets(search).catch(() => []);
+
+          list.push(...snippets.map(toHistoryItem));
+        }
+
         rootState.list = list;
 
-        if (state.noMore) return;
+        if (list.length === 0) return;
 
         return scrollToTop();
       }
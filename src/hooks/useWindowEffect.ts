@@ -0,0 +1,35 @@
+import { useRef } from "react";
+import { setWindowEffect } from "@/plugins/window";
+import { globalStore } from "@/stores/global";
+import { useImmediate } from "./useImmediate";
+
+/**
+ * 将外观设置中的窗口效果应用到当前窗口
+ */
+export const useWindowEffect = () => {
+  const appliedRef = useRef("");
+
+  useImmediate(globalStore.appearance, async () => {
+    const { windowCorners, windowEffect, windowTint } = globalStore.appearance;
+
+    const settings = {
+      corners: windowCorners,
+      effect: windowEffect,
+      tint: windowTint,
+    };
+
+    // 主题等其它外观设置变化时不重复设置
+    const key = JSON.stringify(settings);
+
+    if (key === appliedRef.current) return;
+
+    appliedRef.current = key;
+
+    try {
+      await setWindowEffect(settings);
+    } catch {
+      // 当前系统不支持或色调无效时回到默认效果
+      await setWindowEffect({ effect: "auto" });
+    }
+  });
+};
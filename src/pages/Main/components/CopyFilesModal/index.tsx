@@ -0,0 +1,71 @@
+import { useBoolean } from "ahooks";
+import { Checkbox, Form, Modal, Select } from "antd";
+import { t } from "i18next";
+import { useState } from "react";
+import { LISTEN_KEY } from "@/constants";
+import { useTauriListen } from "@/hooks/useTauriListen";
+import { type CopyFilesPrompt, copyFilesWithTags } from "@/plugins/shell";
+
+interface FormFields {
+  tags: string[];
+  embed: boolean;
+}
+
+// 资源管理器右键菜单“用 EcoPaste 复制并添加标签”选中的文件
+const CopyFilesModal = () => {
+  const [open, { toggle }] = useBoolean();
+  const [paths, setPaths] = useState<string[]>([]);
+  const [form] = Form.useForm<FormFields>();
+
+  useTauriListen<CopyFilesPrompt>(LISTEN_KEY.SHELL_COPY_FILES, ({ payload }) => {
+    form.resetFields();
+
+    setPaths(payload.paths);
+
+    toggle();
+  });
+
+  const handleOk = async () => {
+    const { tags = [], embed = false } = form.getFieldsValue();
+
+    toggle();
+
+    copyFilesWithTags(paths, tags, embed);
+  };
+
+  return (
+    <Modal
+      centered
+      forceRender
+      onCancel={toggle}
+      onOk={handleOk}
+      open={open}
+      title={t("component.copy_files_modal.label.title")}
+    >
+      <Form form={form} layout="vertical" onFinish={handleOk}>
+        <Form.Item
+          label={t("component.copy_files_modal.label.tags")}
+          name="tags"
+        >
+          <Select
+            mode="tags"
+            open={false}
+            placeholder={t("component.copy_files_modal.hints.input_tags")}
+            suffixIcon={null}
+          />
+        </Form.Item>
+
+        <Form.Item
+          className="mb-0!"
+          extra={t("component.copy_files_modal.hints.embed")}
+          name="embed"
+          valuePropName="checked"
+        >
+          <Checkbox>{t("component.copy_files_modal.label.embed")}</Checkbox>
+        </Form.Item>
+      </Form>
+    </Modal>
+  );
+};
+
+export default CopyFilesModal;
@@ -33,6 +33,10 @@ const GroupList = () => {
       id: "files",
       name: t("clipboard.label.tab.files"),
     },
+    {
+      id: "screenshot",
+      name: t("clipboard.label.tab.screenshot"),
+    },
     {
       id: "favorite",
       name: t("clipboard.label.tab.favorite"),
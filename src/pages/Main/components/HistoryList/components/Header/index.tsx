@@ -14,7 +14,8 @@ interface HeaderProps {
 
 const Header: FC<HeaderProps> = (props) => {
   const { data } = props;
-  const { type, value, count, createTime, subtype } = data;
+  const { type, value, count, createTime, subtype, source, libraryName } =
+    data;
   const { t, i18n } = useTranslation();
 
   const renderType = () => {
@@ -45,6 +46,16 @@ const Header: FC<HeaderProps> = (props) => {
     }
   };
 
+  const renderSource = () => {
+    if (source !== "shared") return;
+
+    return (
+      <span className="truncate text-primary" title={libraryName}>
+        {t("clipboard.label.shared")}
+      </span>
+    );
+  };
+
   const renderCount = () => {
     if (type === "files" || type === "image") {
       return filesize(count, { standard: "jedec" });
@@ -70,6 +81,7 @@ const Header: FC<HeaderProps> = (props) => {
   return (
     <Flex className="w-25 shrink-0 text-color-2" gap={2} vertical>
       <div className="flex flex-col gap-0.5 overflow-hidden text-right text-[11px] leading-tight">
+        {renderSource()}
         <span className="truncate">{renderType()}</span>
         <span className="truncate">{renderCount()}</span>
         {renderPixel()}
@@ -1,11 +1,43 @@
-import type { FC } from "react";
+import { type FC, useState } from "react";
 import LocalImage from "@/components/LocalImage";
+import { fetchImage } from "@/plugins/history";
 import type { DatabaseSchemaHistory } from "@/types/database";
+import { getThumbnailPath } from "@/utils/path";
+
+// 与后端生成缩略图的尺寸保持一致
+const THUMBNAIL_SIZE = 256;
 
 const Image: FC<DatabaseSchemaHistory<"image">> = (props) => {
-  const { value } = props;
+  const { value, width = 0, height = 0 } = props;
+
+  // 大图先显示缩略图，缩略图不存在时回退到原图
+  const [src, setSrc] = useState(() => {
+    return Math.max(width, height) > THUMBNAIL_SIZE
+      ? getThumbnailPath(value)
+      : value;
+  });
+
+  const [fetched, setFetched] = useState(false);
+
+  // 原图也加载失败时，可能已移到冷存储目录，取回后重新加载一次
+  const handleError = async () => {
+    if (src !== value) return setSrc(value);
+
+    if (fetched) return;
+
+    await fetchImage(value);
+
+    setFetched(true);
+  };
 
-  return <LocalImage className="max-h-21.5" src={value} />;
+  return (
+    <LocalImage
+      className="max-h-21.5"
+      key={Number(fetched)}
+      onError={handleError}
+      src={src}
+    />
+  );
 };
 
 export default Image;
@@ -1,19 +1,54 @@
 import { useEventEmitter, useKeyPress, useMount, useReactive } from "ahooks";
+import { message } from "antd";
 import type { EventEmitter } from "ahooks/lib/useEventEmitter";
-import { range } from "es-toolkit";
-import { find, last } from "es-toolkit/compat";
+import { pickBy } from "es-toolkit";
+import { find } from "es-toolkit/compat";
 import { createContext, useRef } from "react";
-import { startListening, stopListening } from "tauri-plugin-clipboard-x-api";
+import { useTranslation } from "react-i18next";
 import { useSnapshot } from "valtio";
 import Audio, { type AudioRef } from "@/components/Audio";
+import { toTauriShortcut } from "@/components/ProShortcut/keyboard";
 import { LISTEN_KEY, PRESET_SHORTCUT } from "@/constants";
 import { useClipboard } from "@/hooks/useClipboard";
+import { useImmediate } from "@/hooks/useImmediate";
 import { useImmediateKey } from "@/hooks/useImmediateKey";
-import { useRegister } from "@/hooks/useRegister";
-import { useSubscribeKey } from "@/hooks/useSubscribeKey";
 import { useTauriListen } from "@/hooks/useTauriListen";
-import { pasteToClipboard } from "@/plugins/clipboard";
+import { useWindowEffect } from "@/hooks/useWindowEffect";
 import {
+  FOCUS_MODE_CHANGED_EVENT,
+  type FocusModeState,
+  getFocusMode,
+  pasteToClipboard,
+  setCaptureDenylist,
+  setClipboardWatching,
+  setExcludedApps,
+  setExcludedVms,
+  setFocusPause,
+  setMaxTextCaptureSize,
+  setSensitiveExpiry,
+} from "@/plugins/clipboard";
+import {
+  type LegacyMigrationProgress,
+  setArchivePolicy,
+  setCollationLocale,
+  setColdStoragePolicy,
+  setConversionTargets,
+  setCurrencyRatesFetch,
+  setImageHashing,
+  setImageStorage,
+  setNetworkEnrichment,
+  setRetentionPolicy,
+  setRevealGracePeriod,
+} from "@/plugins/history";
+import {
+  HOTKEY_PRESSED_EVENT,
+  type HotkeyPressed,
+  setHotkeys,
+} from "@/plugins/hotkeys";
+import { setPasteRules, setPasteTiming } from "@/plugins/paste";
+import { setEnabledProcessors } from "@/plugins/processors";
+import {
+  setUnreadBadge,
   showTaskbarIcon,
   showWindow,
   toggleWindowVisible,
@@ -26,6 +61,7 @@ import type {
 } from "@/types/database";
 import type { Store } from "@/types/store";
 import { deepAssign } from "@/utils/object";
+import CopyFilesModal from "./components/CopyFilesModal";
 import DockMode from "./components/DockMode";
 import StandardMode from "./components/StandardMode";
 
@@ -41,13 +77,11 @@ export interface State {
   activeId?: string;
   list: DatabaseSchemaHistory[];
   eventBus?: EventEmitter<EventBusPayload>;
-  quickPasteKeys: string[];
 }
 
 const INITIAL_STATE: State = {
   group: "all",
   list: [],
-  quickPasteKeys: [],
 };
 
 interface MainContextValue {
@@ -60,97 +94,248 @@ export const MainContext = createContext<MainContextValue>({
 
 const Main = () => {
   const state = useReactive<State>(INITIAL_STATE);
-  const { shortcut } = useSnapshot(globalStore);
   const { window } = useSnapshot(clipboardStore);
   const eventBus = useEventEmitter<EventBusPayload>();
   const audioRef = useRef<AudioRef>(null);
+  const focusActiveRef = useRef(false);
+  const { t } = useTranslation();
 
-  useMount(() => {
+  useMount(async () => {
     state.eventBus = eventBus;
+
+    const { active } = await getFocusMode();
+
+    focusActiveRef.current = active;
   });
 
   useClipboard(state, {
     beforeRead() {
       if (!clipboardStore.audio.copy) return;
 
+      // 勿扰模式下静音
+      if (
+        focusActiveRef.current &&
+        clipboardStore.content.focusPause !== "off"
+      ) {
+        return;
+      }
+
       audioRef.current?.play();
     },
   });
 
+  // 窗口的背景效果
+  useWindowEffect();
+
   // 任务栏图标的显示与隐藏
   useImmediateKey(globalStore.app, "showTaskbarIcon", showTaskbarIcon);
 
+  // 任务栏或程序坞图标上的新内容数量
+  useImmediateKey(globalStore.app, "unreadBadge", setUnreadBadge);
+
+  // 搜索和排序所用的语言规则
+  useImmediateKey(globalStore.appearance, "language", setCollationLocale);
+
   // 同步配置项
   useTauriListen<Store>(LISTEN_KEY.STORE_CHANGED, ({ payload }) => {
     deepAssign(globalStore, payload.globalStore);
     deepAssign(clipboardStore, payload.clipboardStore);
   });
 
-  // 窗口显示与隐藏
-  useRegister(toggleWindowVisible, [shortcut.clipboard]);
-
   // 打开偏好设置窗口
   useKeyPress(PRESET_SHORTCUT.OPEN_PREFERENCES, () => {
     showWindow("preference");
   });
 
-  // 设置快捷粘贴的快捷键
-  const setQuickPasteKeys = () => {
-    const { enable, value } = globalStore.shortcut.quickPaste;
+  // 注册全局快捷键，配置变化后重新注册
+  useImmediate(globalStore.shortcut, async () => {
+    const {
+      clipboard,
+      pasteLast,
+      pastePlain,
+      pasteQueue,
+      preference,
+      quickPaste,
+    } = globalStore.shortcut;
 
-    if (!enable) {
-      state.quickPasteKeys = [];
+    const conflicts = await setHotkeys({
+      pasteLast,
+      pasteNth: quickPaste.enable ? quickPaste.value : undefined,
+      pastePlain: toTauriShortcut(pastePlain),
+      pasteQueue,
+      showPreference: preference,
+      showWindow: clipboard,
+    });
 
-      return;
+    for (const { shortcut } of conflicts) {
+      message.warning(t("clipboard.hints.shortcut_conflict", { shortcut }));
     }
+  });
 
-    state.quickPasteKeys = range(1, 10).map((item) => [value, item].join("+"));
-  };
+  // 同步不记录复制内容的应用
+  useImmediateKey(clipboardStore.content, "excludedApps", (apps) => {
+    setExcludedApps([...apps]);
+  });
 
-  // 监听快速粘贴的启用状态变更
-  useImmediateKey(globalStore.shortcut.quickPaste, "enable", () => {
-    setQuickPasteKeys();
+  // 同步启用的采集处理器
+  useImmediateKey(clipboardStore.content, "enabledProcessors", (ids) => {
+    setEnabledProcessors([...ids]);
   });
 
-  // 监听快速粘贴的快捷键变更
-  useSubscribeKey(globalStore.shortcut.quickPaste, "value", () => {
-    setQuickPasteKeys();
+  // 同步不记录的内容规则
+  useImmediateKey(clipboardStore.content, "captureDenylist", (patterns) => {
+    setCaptureDenylist([...patterns]);
   });
 
-  // 切换剪贴板监听状态
-  useTauriListen<boolean>(LISTEN_KEY.TOGGLE_LISTEN_CLIPBOARD, ({ payload }) => {
-    if (payload) {
-      startListening();
-    } else {
-      stopListening();
-    }
+  // 同步不记录复制内容的虚拟机
+  useImmediateKey(clipboardStore.content, "excludedVms", (names) => {
+    setExcludedVms([...names]);
   });
 
-  // 监听粘贴为纯文本的快捷键
-  useKeyPress(shortcut.pastePlain, (event) => {
-    event.preventDefault();
+  // 同步完整保存的文本大小上限
+  useImmediateKey(clipboardStore.content, "maxTextSize", (size) => {
+    setMaxTextCaptureSize(size * 1024 * 1024);
+  });
 
-    const data = find(state.list, { id: state.activeId });
+  // 同步勿扰模式开启时暂停的内容
+  useImmediateKey(clipboardStore.content, "focusPause", setFocusPause);
 
-    if (!data) return;
+  // 同步敏感内容的自动删除时间
+  useImmediateKey(
+    clipboardStore.content,
+    "sensitiveExpiry",
+    setSensitiveExpiry,
+  );
 
-    pasteToClipboard(data, true);
+  // 同步敏感条目解锁后的有效时间
+  useImmediateKey(
+    clipboardStore.content,
+    "revealGracePeriod",
+    setRevealGracePeriod,
+  );
+
+  // 同步粘贴前的等待时间和重试次数
+  useImmediate(clipboardStore.content.pasteTiming, () => {
+    setPasteTiming({ ...clipboardStore.content.pasteTiming });
   });
 
-  // 监听快速粘贴的快捷键
-  useRegister(
-    async (event) => {
-      if (!globalStore.shortcut.quickPaste.enable) return;
+  // 同步按应用配置的粘贴方式
+  useImmediate(clipboardStore.content.pasteRules, () => {
+    setPasteRules(
+      clipboardStore.content.pasteRules.map((rule) => ({ ...rule })),
+    );
+  });
 
-      const index = Number(last(event.shortcut));
+  // 同步单位和货币换算的目标单位
+  useImmediateKey(clipboardStore.content, "conversionTargets", (targets) => {
+    setConversionTargets(targets.length > 0 ? [...targets] : null);
+  });
+
+  // 同步是否从网络获取实时汇率
+  useImmediateKey(clipboardStore.content, "currencyRates", (enabled) => {
+    setCurrencyRatesFetch(enabled);
+  });
 
-      const data = state.list[index - 1];
+  // 同步复制 IP 和域名时附加的信息
+  useImmediate(clipboardStore.content.networkEnrichment, () => {
+    setNetworkEnrichment({ ...clipboardStore.content.networkEnrichment });
+  });
+
+  // 同步历史记录的保留策略，由后台任务定期清理
+  useImmediate(clipboardStore.history, () => {
+    const { duration, groupDuration, maxCount } = clipboardStore.history;
 
-      pasteToClipboard(data);
+    setRetentionPolicy({
+      groupMaxAgeDays: pickBy(groupDuration, (days) => days > 0),
+      maxAgeDays: duration || undefined,
+      maxCount: maxCount || undefined,
+    });
+  });
+
+  // 同步历史记录的归档时间，由后台任务定期归档
+  useImmediateKey(
+    clipboardStore.history,
+    "archiveAfterMonths",
+    setArchivePolicy,
+  );
+
+  // 同步图片的冷存储策略
+  useImmediate(clipboardStore.history.coldStorage, () => {
+    const { afterDays, location, maxAccessCount } =
+      clipboardStore.history.coldStorage;
+
+    setColdStoragePolicy(
+      afterDays
+        ? { afterDays, location: location || undefined, maxAccessCount }
+        : null,
+    );
+  });
+
+  // 保存图片时是否计算感知哈希
+  useImmediateKey(clipboardStore.history, "hashImages", setImageHashing);
+
+  // 同步新图片保存的格式
+  useImmediate(clipboardStore.history.imageStorage, () => {
+    setImageStorage({ ...clipboardStore.history.imageStorage });
+  });
+
+  // 系统勿扰模式的开关
+  useTauriListen<FocusModeState>(FOCUS_MODE_CHANGED_EVENT, ({ payload }) => {
+    focusActiveRef.current = payload.active;
+  });
+
+  // 切换剪贴板监听状态
+  useTauriListen<boolean>(LISTEN_KEY.TOGGLE_LISTEN_CLIPBOARD, ({ payload }) => {
+    setClipboardWatching(payload);
+  });
+
+  // 旧版本数据的升级进度
+  useTauriListen<LegacyMigrationProgress>(
+    LISTEN_KEY.LEGACY_MIGRATION_PROGRESS,
+    ({ payload }) => {
+      const key = LISTEN_KEY.LEGACY_MIGRATION_PROGRESS;
+
+      switch (payload.stage) {
+        case "done":
+          return message.success({
+            content: t("clipboard.hints.legacy_migration_done", payload),
+            key,
+          });
+        case "failed":
+          return message.error({
+            content: t("clipboard.hints.legacy_migration_failed", payload),
+            key,
+          });
+        default:
+          return message.loading({
+            content: t("clipboard.hints.legacy_migration_running", payload),
+            duration: 0,
+            key,
+          });
+      }
     },
-    [state.quickPasteKeys],
   );
 
+  // 全局快捷键
+  useTauriListen<HotkeyPressed>(HOTKEY_PRESSED_EVENT, ({ payload }) => {
+    const { action } = payload;
+
+    if (action === "showWindow") {
+      return toggleWindowVisible();
+    }
+
+    if (action !== "pasteLast" && action !== "pastePlain") return;
+
+    const data =
+      action === "pastePlain"
+        ? (find(state.list, { id: state.activeId }) ?? state.list[0])
+        : state.list[0];
+
+    if (!data) return;
+
+    pasteToClipboard(data, action === "pastePlain" || undefined);
+  });
+
   return (
     <MainContext.Provider
       value={{
@@ -159,6 +344,8 @@ const Main = () => {
     >
       <Audio ref={audioRef} />
 
+      <CopyFilesModal />
+
       {window.style === "standard" ? <StandardMode /> : <DockMode />}
     </MainContext.Provider>
   );
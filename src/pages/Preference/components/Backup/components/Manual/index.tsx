@@ -19,7 +19,6 @@ import {
   getSaveStorePath,
   join,
 } from "@/utils/path";
-import { wait } from "@/utils/shared";
 import { restoreStore, saveStore } from "@/utils/store";
 import type { State } from "../..";
 
@@ -59,10 +58,6 @@ const Manual: FC<{ state: State }> = (props) => {
 
       state.spinning = true;
 
-      emit(LISTEN_KEY.CLOSE_DATABASE);
-
-      await wait();
-
       await decompress(path, getSaveDataPath());
 
       await restoreStore(true);
@@ -20,7 +20,6 @@ import {
   getSaveImagePath,
   join,
 } from "@/utils/path";
-import { wait } from "@/utils/shared";
 import type { State } from "../..";
 
 const SavePath: FC<{ state: State }> = (props) => {
@@ -44,10 +43,6 @@ const SavePath: FC<{ state: State }> = (props) => {
 
       state.spinning = true;
 
-      emit(LISTEN_KEY.CLOSE_DATABASE);
-
-      await wait();
-
       await transfer(getSaveDataPath(), dstPath, {
         includes: [
           await fullName(getSaveImagePath()),
@@ -0,0 +1,57 @@
+import { useMount } from "ahooks";
+import { message, Select } from "antd";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import {
+  getCaptureDenylistAudit,
+  setCaptureDenylist,
+} from "@/plugins/clipboard";
+import { clipboardStore } from "@/stores/clipboard";
+
+const CaptureDenylist = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+  const [blocked, setBlocked] = useState(0);
+
+  useMount(async () => {
+    const audit = await getCaptureDenylistAudit();
+
+    setBlocked(audit.blocked);
+  });
+
+  return (
+    <ProListItem
+      description={t(
+        "preference.clipboard.content_settings.hints.capture_denylist",
+        { blocked },
+      )}
+      title={t("preference.clipboard.content_settings.label.capture_denylist")}
+    >
+      <Select
+        className="w-60"
+        maxTagCount="responsive"
+        mode="tags"
+        onChange={async (value) => {
+          // 无效的正则表达式不保存
+          try {
+            await setCaptureDenylist(value);
+
+            clipboardStore.content.captureDenylist = value;
+          } catch (error) {
+            message.error(String(error));
+          }
+        }}
+        open={false}
+        placeholder={t(
+          "preference.clipboard.content_settings.hints.input_capture_denylist",
+        )}
+        suffixIcon={null}
+        value={[...content.captureDenylist]}
+      />
+    </ProListItem>
+  );
+};
+
+export default CaptureDenylist;
@@ -0,0 +1,36 @@
+import { Select } from "antd";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const ExcludedApps = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  return (
+    <ProListItem
+      description={t(
+        "preference.clipboard.content_settings.hints.excluded_apps",
+      )}
+      title={t("preference.clipboard.content_settings.label.excluded_apps")}
+    >
+      <Select
+        className="w-60"
+        maxTagCount="responsive"
+        mode="tags"
+        onChange={(value) => {
+          clipboardStore.content.excludedApps = value;
+        }}
+        open={false}
+        placeholder={t(
+          "preference.clipboard.content_settings.hints.input_excluded_apps",
+        )}
+        suffixIcon={null}
+        value={[...content.excludedApps]}
+      />
+    </ProListItem>
+  );
+};
+
+export default ExcludedApps;
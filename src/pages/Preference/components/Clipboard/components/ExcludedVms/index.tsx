@@ -0,0 +1,47 @@
+import { useMount } from "ahooks";
+import { Select } from "antd";
+import { union } from "es-toolkit";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { getVmSources } from "@/plugins/clipboard";
+import { clipboardStore } from "@/stores/clipboard";
+
+const ExcludedVms = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+  const [sources, setSources] = useState<string[]>([]);
+
+  useMount(async () => {
+    setSources(await getVmSources());
+  });
+
+  return (
+    <ProListItem
+      description={t(
+        "preference.clipboard.content_settings.hints.excluded_vms",
+      )}
+      title={t("preference.clipboard.content_settings.label.excluded_vms")}
+    >
+      <Select
+        className="w-60"
+        maxTagCount="responsive"
+        mode="tags"
+        onChange={(value) => {
+          clipboardStore.content.excludedVms = value;
+        }}
+        options={union(sources, content.excludedVms).map((name) => ({
+          label: name,
+          value: name,
+        }))}
+        placeholder={t(
+          "preference.clipboard.content_settings.hints.input_excluded_vms",
+        )}
+        value={[...content.excludedVms]}
+      />
+    </ProListItem>
+  );
+};
+
+export default ExcludedVms;
@@ -0,0 +1,50 @@
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProSelect from "@/components/ProSelect";
+import { clipboardStore } from "@/stores/clipboard";
+import type { FocusPause as Value } from "@/types/store";
+
+interface Option {
+  label: string;
+  value: Value;
+}
+
+const FocusPause = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  const options: Option[] = [
+    {
+      label: t("preference.clipboard.content_settings.label.focus_pause_off"),
+      value: "off",
+    },
+    {
+      label: t(
+        "preference.clipboard.content_settings.label.focus_pause_notifications",
+      ),
+      value: "notifications",
+    },
+    {
+      label: t(
+        "preference.clipboard.content_settings.label.focus_pause_capture",
+      ),
+      value: "capture",
+    },
+  ];
+
+  return (
+    <ProSelect
+      description={t(
+        "preference.clipboard.content_settings.hints.focus_pause",
+      )}
+      onChange={(value) => {
+        clipboardStore.content.focusPause = value;
+      }}
+      options={options}
+      title={t("preference.clipboard.content_settings.label.focus_pause")}
+      value={content.focusPause}
+    />
+  );
+};
+
+export default FocusPause;
@@ -0,0 +1,33 @@
+import { InputNumber } from "antd";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const MaxTextSize = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  return (
+    <ProListItem
+      description={t(
+        "preference.clipboard.content_settings.hints.max_text_size",
+      )}
+      title={t("preference.clipboard.content_settings.label.max_text_size")}
+    >
+      <InputNumber
+        addonAfter={t(
+          "preference.clipboard.content_settings.label.max_text_size_unit",
+        )}
+        className="w-30"
+        min={1}
+        onChange={(value) => {
+          clipboardStore.content.maxTextSize = value ?? 1;
+        }}
+        value={content.maxTextSize}
+      />
+    </ProListItem>
+  );
+};
+
+export default MaxTextSize;
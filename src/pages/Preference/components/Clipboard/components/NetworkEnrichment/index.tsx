@@ -0,0 +1,63 @@
+import { CloseOutlined, FolderOpenOutlined } from "@ant-design/icons";
+import { open } from "@tauri-apps/plugin-dialog";
+import { Button, Space } from "antd";
+import { isString } from "es-toolkit";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import ProSwitch from "@/components/ProSwitch";
+import { clipboardStore } from "@/stores/clipboard";
+
+const NetworkEnrichment = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+  const { asnDatabase, lookups } = content.networkEnrichment;
+
+  const handleSelect = async () => {
+    const path = await open({
+      filters: [{ extensions: ["tsv"], name: "ip2asn" }],
+    });
+
+    if (!isString(path)) return;
+
+    clipboardStore.content.networkEnrichment.asnDatabase = path;
+  };
+
+  return (
+    <>
+      <ProSwitch
+        description={t(
+          "preference.clipboard.content_settings.hints.network_lookups",
+        )}
+        onChange={(value) => {
+          clipboardStore.content.networkEnrichment.lookups = value;
+        }}
+        title={t("preference.clipboard.content_settings.label.network_lookups")}
+        value={lookups}
+      />
+
+      <ProListItem
+        description={
+          asnDatabase ||
+          t("preference.clipboard.content_settings.hints.asn_database")
+        }
+        title={t("preference.clipboard.content_settings.label.asn_database")}
+      >
+        <Space.Compact>
+          <Button icon={<FolderOpenOutlined />} onClick={handleSelect} />
+
+          {asnDatabase && (
+            <Button
+              icon={<CloseOutlined />}
+              onClick={() => {
+                clipboardStore.content.networkEnrichment.asnDatabase = "";
+              }}
+            />
+          )}
+        </Space.Compact>
+      </ProListItem>
+    </>
+  );
+};
+
+export default NetworkEnrichment;
@@ -0,0 +1,48 @@
+import { Flex, InputNumber } from "antd";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const PasteTiming = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  return (
+    <ProListItem
+      description={t("preference.clipboard.content_settings.hints.paste_timing")}
+      title={t("preference.clipboard.content_settings.label.paste_timing")}
+    >
+      <Flex gap="small">
+        <InputNumber
+          addonAfter={t(
+            "preference.clipboard.content_settings.label.paste_timing_unit",
+          )}
+          className="w-30"
+          max={5000}
+          min={0}
+          onChange={(value) => {
+            clipboardStore.content.pasteTiming.delay = value ?? 100;
+          }}
+          step={50}
+          value={content.pasteTiming.delay}
+        />
+
+        <InputNumber
+          addonAfter={t(
+            "preference.clipboard.content_settings.label.paste_timing_retries",
+          )}
+          className="w-30"
+          max={8}
+          min={0}
+          onChange={(value) => {
+            clipboardStore.content.pasteTiming.retries = value ?? 3;
+          }}
+          value={content.pasteTiming.retries}
+        />
+      </Flex>
+    </ProListItem>
+  );
+};
+
+export default PasteTiming;
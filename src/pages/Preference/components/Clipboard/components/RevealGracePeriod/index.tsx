@@ -0,0 +1,35 @@
+import { InputNumber } from "antd";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const RevealGracePeriod = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  return (
+    <ProListItem
+      description={t(
+        "preference.clipboard.content_settings.hints.reveal_grace_period",
+      )}
+      title={t(
+        "preference.clipboard.content_settings.label.reveal_grace_period",
+      )}
+    >
+      <InputNumber
+        addonAfter={t(
+          "preference.clipboard.content_settings.label.reveal_grace_period_unit",
+        )}
+        className="w-30"
+        min={0}
+        onChange={(value) => {
+          clipboardStore.content.revealGracePeriod = value ?? 0;
+        }}
+        value={content.revealGracePeriod}
+      />
+    </ProListItem>
+  );
+};
+
+export default RevealGracePeriod;
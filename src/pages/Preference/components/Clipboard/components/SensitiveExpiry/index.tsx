@@ -0,0 +1,33 @@
+import { InputNumber } from "antd";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const SensitiveExpiry = () => {
+  const { content } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  return (
+    <ProListItem
+      description={t(
+        "preference.clipboard.content_settings.hints.sensitive_expiry",
+      )}
+      title={t("preference.clipboard.content_settings.label.sensitive_expiry")}
+    >
+      <InputNumber
+        addonAfter={t(
+          "preference.clipboard.content_settings.label.sensitive_expiry_unit",
+        )}
+        className="w-30"
+        min={0}
+        onChange={(value) => {
+          clipboardStore.content.sensitiveExpiry = value ?? 0;
+        }}
+        value={content.sensitiveExpiry}
+      />
+    </ProListItem>
+  );
+};
+
+export default SensitiveExpiry;
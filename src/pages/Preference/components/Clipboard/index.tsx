@@ -5,8 +5,17 @@ import ProSwitch from "@/components/ProSwitch";
 import { clipboardStore } from "@/stores/clipboard";
 import AudioSettings from "./components/AudioSettings";
 import AutoPaste from "./components/AutoPaste";
+import CaptureDenylist from "./components/CaptureDenylist";
+import ExcludedApps from "./components/ExcludedApps";
+import ExcludedVms from "./components/ExcludedVms";
+import FocusPause from "./components/FocusPause";
+import MaxTextSize from "./components/MaxTextSize";
+import NetworkEnrichment from "./components/NetworkEnrichment";
 import OperationButton from "./components/OperationButton";
+import PasteTiming from "./components/PasteTiming";
+import RevealGracePeriod from "./components/RevealGracePeriod";
 import SearchPosition from "./components/SearchPosition";
+import SensitiveExpiry from "./components/SensitiveExpiry";
 import WindowPosition from "./components/WindowPosition";
 
 const ClipboardSettings = () => {
@@ -93,6 +102,8 @@ const ClipboardSettings = () => {
           value={content.pastePlain}
         />
 
+        <PasteTiming />
+
         <OperationButton />
 
         <ProSwitch
@@ -142,6 +153,44 @@ const ClipboardSettings = () => {
           )}
           value={content.showOriginalContent}
         />
+
+        <ProSwitch
+          description={t(
+            "preference.clipboard.content_settings.hints.auto_ocr",
+          )}
+          onChange={(value) => {
+            clipboardStore.content.autoOcr = value;
+          }}
+          title={t("preference.clipboard.content_settings.label.auto_ocr")}
+          value={content.autoOcr}
+        />
+
+        <MaxTextSize />
+
+        <ProSwitch
+          description={t(
+            "preference.clipboard.content_settings.hints.currency_rates",
+          )}
+          onChange={(value) => {
+            clipboardStore.content.currencyRates = value;
+          }}
+          title={t("preference.clipboard.content_settings.label.currency_rates")}
+          value={content.currencyRates}
+        />
+
+        <NetworkEnrichment />
+
+        <ExcludedApps />
+
+        <ExcludedVms />
+
+        <CaptureDenylist />
+
+        <FocusPause />
+
+        <SensitiveExpiry />
+
+        <RevealGracePeriod />
       </ProList>
     </>
   );
@@ -0,0 +1,84 @@
+import { useRequest } from "ahooks";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProSelect from "@/components/ProSelect";
+import { getWindowEffects } from "@/plugins/window";
+import { globalStore } from "@/stores/global";
+import type { WindowCorners, WindowEffect as Effect } from "@/types/store";
+
+interface Option<T> {
+  label: string;
+  value: T;
+}
+
+const WindowEffect = () => {
+  const { appearance } = useSnapshot(globalStore);
+  const { t } = useTranslation();
+  const { data: support } = useRequest(getWindowEffects);
+
+  if (!support?.effects.length) return null;
+
+  const effectOptions: Option<Effect>[] = [
+    {
+      label: t("preference.settings.appearance_settings.label.effect_auto"),
+      value: "auto",
+    },
+    {
+      label: t("preference.settings.appearance_settings.label.effect_none"),
+      value: "none",
+    },
+    ...support.effects.map((effect) => ({
+      label: t(`preference.settings.appearance_settings.label.effect_${effect}`),
+      value: effect,
+    })),
+  ];
+
+  const cornerOptions: Option<WindowCorners>[] = [
+    {
+      label: t("preference.settings.appearance_settings.label.corners_default"),
+      value: "default",
+    },
+    {
+      label: t("preference.settings.appearance_settings.label.corners_round"),
+      value: "round",
+    },
+    {
+      label: t(
+        "preference.settings.appearance_settings.label.corners_round_small",
+      ),
+      value: "roundSmall",
+    },
+    {
+      label: t("preference.settings.appearance_settings.label.corners_square"),
+      value: "square",
+    },
+  ];
+
+  return (
+    <>
+      <ProSelect
+        onChange={(value) => {
+          globalStore.appearance.windowEffect = value;
+        }}
+        options={effectOptions}
+        title={t("preference.settings.appearance_settings.label.window_effect")}
+        value={appearance.windowEffect}
+      />
+
+      {support.corners && (
+        <ProSelect
+          onChange={(value) => {
+            globalStore.appearance.windowCorners = value;
+          }}
+          options={cornerOptions}
+          title={t(
+            "preference.settings.appearance_settings.label.window_corners",
+          )}
+          value={appearance.windowCorners}
+        />
+      )}
+    </>
+  );
+};
+
+export default WindowEffect;
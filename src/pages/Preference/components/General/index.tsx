@@ -4,11 +4,13 @@ import { useSnapshot } from "valtio";
 import ProList from "@/components/ProList";
 import ProSwitch from "@/components/ProSwitch";
 import { useImmediateKey } from "@/hooks/useImmediateKey";
+import { setShellIntegration } from "@/plugins/shell";
 import { globalStore } from "@/stores/global";
-import { isMac } from "@/utils/is";
+import { isMac, isWin } from "@/utils/is";
 import Language from "./components/Language";
 import MacosPermissions from "./components/MacosPermissions";
 import ThemeMode from "./components/ThemeMode";
+import WindowEffect from "./components/WindowEffect";
 
 const General = () => {
   const { app, update } = useSnapshot(globalStore);
@@ -27,6 +29,18 @@ const General = () => {
     }
   });
 
+  // 监听资源管理器右键菜单的开启状态变更
+  useImmediateKey(globalStore.app, "shellIntegration", (value) => {
+    if (!isWin || value === undefined) return;
+
+    setShellIntegration(value, {
+      copy: t("preference.settings.app_settings.label.shell_menu_copy"),
+      copyWithTags: t(
+        "preference.settings.app_settings.label.shell_menu_copy_with_tags",
+      ),
+    });
+  });
+
   return (
     <>
       {isMac && <MacosPermissions />}
@@ -64,12 +78,36 @@ const General = () => {
           title={t("preference.settings.app_settings.label.show_taskbar_icon")}
           value={app.showTaskbarIcon}
         />
+
+        <ProSwitch
+          description={t("preference.settings.app_settings.hints.unread_badge")}
+          onChange={(value) => {
+            globalStore.app.unreadBadge = value;
+          }}
+          title={t("preference.settings.app_settings.label.unread_badge")}
+          value={app.unreadBadge}
+        />
+
+        {isWin && (
+          <ProSwitch
+            description={t(
+              "preference.settings.app_settings.hints.shell_integration",
+            )}
+            onChange={(value) => {
+              globalStore.app.shellIntegration = value;
+            }}
+            title={t("preference.settings.app_settings.label.shell_integration")}
+            value={app.shellIntegration}
+          />
+        )}
       </ProList>
 
       <ProList header={t("preference.settings.appearance_settings.title")}>
         <Language />
 
         <ThemeMode />
+
+        <WindowEffect />
       </ProList>
 
       <ProList header={t("preference.settings.update_settings.title")}>
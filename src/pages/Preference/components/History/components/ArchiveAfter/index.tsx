@@ -0,0 +1,29 @@
+import { InputNumber } from "antd";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const ArchiveAfter = () => {
+  const { history } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  return (
+    <ProListItem
+      description={t("preference.history.history.hints.archive_after")}
+      title={t("preference.history.history.label.archive_after")}
+    >
+      <InputNumber
+        addonAfter={t("preference.history.history.label.archive_after_unit")}
+        className="w-30"
+        min={0}
+        onChange={(value) => {
+          clipboardStore.history.archiveAfterMonths = value ?? 0;
+        }}
+        value={history.archiveAfterMonths}
+      />
+    </ProListItem>
+  );
+};
+
+export default ArchiveAfter;
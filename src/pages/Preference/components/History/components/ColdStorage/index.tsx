@@ -0,0 +1,80 @@
+import { CloseOutlined, FolderOpenOutlined } from "@ant-design/icons";
+import { open } from "@tauri-apps/plugin-dialog";
+import { Button, InputNumber, Space } from "antd";
+import { isString } from "es-toolkit";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const ColdStorage = () => {
+  const { history } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+  const { afterDays, location, maxAccessCount } = history.coldStorage;
+
+  const handleSelect = async () => {
+    const dir = await open({ directory: true });
+
+    if (!isString(dir)) return;
+
+    clipboardStore.history.coldStorage.location = dir;
+  };
+
+  return (
+    <>
+      <ProListItem
+        description={t("preference.history.history.hints.cold_storage")}
+        title={t("preference.history.history.label.cold_storage")}
+      >
+        <InputNumber
+          addonAfter={t("preference.history.history.label.duration_unit")}
+          className="w-30"
+          min={0}
+          onChange={(value) => {
+            clipboardStore.history.coldStorage.afterDays = value ?? 0;
+          }}
+          value={afterDays}
+        />
+      </ProListItem>
+
+      <ProListItem
+        description={t(
+          "preference.history.history.hints.cold_storage_access_count",
+        )}
+        title={t("preference.history.history.label.cold_storage_access_count")}
+      >
+        <InputNumber
+          className="w-30"
+          min={0}
+          onChange={(value) => {
+            clipboardStore.history.coldStorage.maxAccessCount = value ?? 0;
+          }}
+          value={maxAccessCount}
+        />
+      </ProListItem>
+
+      <ProListItem
+        description={
+          location ||
+          t("preference.history.history.hints.cold_storage_location")
+        }
+        title={t("preference.history.history.label.cold_storage_location")}
+      >
+        <Space.Compact>
+          <Button icon={<FolderOpenOutlined />} onClick={handleSelect} />
+
+          {location && (
+            <Button
+              icon={<CloseOutlined />}
+              onClick={() => {
+                clipboardStore.history.coldStorage.location = "";
+              }}
+            />
+          )}
+        </Space.Compact>
+      </ProListItem>
+    </>
+  );
+};
+
+export default ColdStorage;
@@ -16,7 +16,7 @@ import { useEffect } from "react";
 import { useTranslation } from "react-i18next";
 import AdaptiveSelect from "@/components/AdaptiveSelect";
 import { LISTEN_KEY } from "@/constants";
-import { deleteHistory, selectHistory } from "@/database/history";
+import { deleteHistoryRange } from "@/plugins/history";
 import { dayjs, formatDate } from "@/utils/dayjs";
 
 const { RangePicker } = DatePicker;
@@ -78,26 +78,10 @@ const Delete = () => {
         range = [dayjs().subtract(timeRange, "hour"), dayjs()];
       }
 
-      const formatRange = range.map((item) => formatDate(item));
+      const [since, until] =
+        timeRange === 0 ? [] : range.map((item) => formatDate(item));
 
-      const list = await selectHistory();
-
-      for await (const item of list) {
-        const { favorite, createTime } = item;
-
-        if (favorite && !deleteFavorite) continue;
-
-        const isBetween = dayjs(createTime).isBetween(
-          formatRange[0],
-          formatRange[1],
-          null,
-          "[]",
-        );
-
-        if (timeRange === 0 || isBetween) {
-          deleteHistory(item);
-        }
-      }
+      await deleteHistoryRange(since, until, deleteFavorite);
 
       toggle();
       message.success(t("preference.history.history.hints.delete_success"));
@@ -0,0 +1,32 @@
+import { InputNumber } from "antd";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import { clipboardStore } from "@/stores/clipboard";
+
+const GROUPS = ["text", "image", "files"] as const;
+
+const GroupDuration = () => {
+  const { history } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+
+  return GROUPS.map((group) => (
+    <ProListItem
+      description={t("preference.history.history.hints.group_duration")}
+      key={group}
+      title={t(`preference.history.history.label.group_duration.${group}`)}
+    >
+      <InputNumber
+        addonAfter={t("preference.history.history.label.duration_unit")}
+        className="w-30"
+        min={0}
+        onChange={(value) => {
+          clipboardStore.history.groupDuration[group] = value ?? 0;
+        }}
+        value={history.groupDuration[group]}
+      />
+    </ProListItem>
+  ));
+};
+
+export default GroupDuration;
@@ -0,0 +1,119 @@
+import { useBoolean } from "ahooks";
+import { Button, InputNumber, message } from "antd";
+import { filesize } from "filesize";
+import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
+import ProListItem from "@/components/ProListItem";
+import ProSelect from "@/components/ProSelect";
+import {
+  type ImageStoragePolicy,
+  recompressStoredImages,
+} from "@/plugins/history";
+import { clipboardStore } from "@/stores/clipboard";
+
+interface Option<T> {
+  label: string;
+  value: T;
+}
+
+const ImageStorage = () => {
+  const { history } = useSnapshot(clipboardStore);
+  const { t } = useTranslation();
+  const [converting, { setTrue, setFalse }] = useBoolean();
+  const { compression, format, quality } = history.imageStorage;
+
+  const formatOptions: Option<ImageStoragePolicy["format"]>[] = [
+    { label: "PNG", value: "png" },
+    {
+      label: t("preference.history.history.label.image_format_webp"),
+      value: "webp",
+    },
+    { label: "JPEG", value: "jpeg" },
+  ];
+
+  const compressionOptions: Option<ImageStoragePolicy["compression"]>[] = [
+    {
+      label: t("preference.history.history.label.image_compression_fast"),
+      value: "fast",
+    },
+    {
+      label: t("preference.history.history.label.image_compression_default"),
+      value: "default",
+    },
+    {
+      label: t("preference.history.history.label.image_compression_best"),
+      value: "best",
+    },
+  ];
+
+  const handleRecompress = async () => {
+    try {
+      setTrue();
+
+      const { converted, savedBytes } = await recompressStoredImages();
+
+      message.success(
+        t("preference.history.history.hints.recompress_success", {
+          converted,
+          saved: filesize(savedBytes, { standard: "jedec" }),
+        }),
+      );
+    } catch (error) {
+      message.error(String(error));
+    } finally {
+      setFalse();
+    }
+  };
+
+  return (
+    <>
+      <ProSelect
+        description={t("preference.history.history.hints.image_format")}
+        onChange={(value) => {
+          clipboardStore.history.imageStorage.format = value;
+        }}
+        options={formatOptions}
+        title={t("preference.history.history.label.image_format")}
+        value={format}
+      />
+
+      {format === "png" && (
+        <ProSelect
+          onChange={(value) => {
+            clipboardStore.history.imageStorage.compression = value;
+          }}
+          options={compressionOptions}
+          title={t("preference.history.history.label.image_compression")}
+          value={compression}
+        />
+      )}
+
+      {format === "jpeg" && (
+        <ProListItem
+          title={t("preference.history.history.label.image_quality")}
+        >
+          <InputNumber
+            className="w-30"
+            max={100}
+            min={1}
+            onChange={(value) => {
+              clipboardStore.history.imageStorage.quality = value ?? 85;
+            }}
+            value={quality}
+          />
+        </ProListItem>
+      )}
+
+      <ProListItem
+        description={t("preference.history.history.hints.recompress_images")}
+        title={t("preference.history.history.label.recompress_images")}
+      >
+        <Button loading={converting} onClick={handleRecompress}>
+          {t("preference.history.history.button.recompress_images")}
+        </Button>
+      </ProListItem>
+    </>
+  );
+};
+
+export default ImageStorage;
@@ -1,51 +1,42 @@
-import { useRef } from "react";
 import { useTranslation } from "react-i18next";
+import { useSnapshot } from "valtio";
 import ProList from "@/components/ProList";
-import { deleteHistory, selectHistory } from "@/database/history";
-import { useImmediate } from "@/hooks/useImmediate";
+import ProSwitch from "@/components/ProSwitch";
 import { clipboardStore } from "@/stores/clipboard";
-import type { Interval } from "@/types/shared";
-import { dayjs } from "@/utils/dayjs";
+import ArchiveAfter from "./components/ArchiveAfter";
+import ColdStorage from "./components/ColdStorage";
 import Delete from "./components/Delete";
 import Duration from "./components/Duration";
+import GroupDuration from "./components/GroupDuration";
+import ImageStorage from "./components/ImageStorage";
 import MaxCount from "./components/MaxCount";
 
 const History = () => {
+  const { history } = useSnapshot(clipboardStore);
   const { t } = useTranslation();
-  const timerRef = useRef<Interval>();
 
-  useImmediate(clipboardStore.history, async () => {
-    const { duration, maxCount } = clipboardStore.history;
-
-    clearInterval(timerRef.current);
-
-    if (duration === 0 && maxCount === 0) return;
-
-    const delay = 1000 * 60 * 30;
+  return (
+    <ProList footer={<Delete />} header={t("preference.history.history.title")}>
+      <Duration />
 
-    timerRef.current = setInterval(async () => {
-      const list = await selectHistory((qb) => {
-        return qb.where("favorite", "=", false);
-      });
+      <GroupDuration />
 
-      for (const [index, item] of list.entries()) {
-        const { createTime } = item;
-        const diffDays = dayjs().diff(createTime, "days");
-        const isExpired = duration > 0 && diffDays >= duration;
-        const isOverMaxCount = maxCount > 0 && index >= maxCount;
+      <MaxCount />
 
-        if (!isExpired && !isOverMaxCount) continue;
+      <ArchiveAfter />
 
-        deleteHistory(item);
-      }
-    }, delay);
-  });
+      <ColdStorage />
 
-  return (
-    <ProList footer={<Delete />} header={t("preference.history.history.title")}>
-      <Duration />
+      <ProSwitch
+        description={t("preference.history.history.hints.hash_images")}
+        onChange={(value) => {
+          clipboardStore.history.hashImages = value;
+        }}
+        title={t("preference.history.history.label.hash_images")}
+        value={history.hashImages}
+      />
 
-      <MaxCount />
+      <ImageStorage />
     </ProList>
   );
 };
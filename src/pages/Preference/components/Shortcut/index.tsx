@@ -31,6 +31,23 @@ const Shortcut = () => {
 
         <QuickPaste />
 
+        <ProShortcut
+          onChange={(value) => {
+            globalStore.shortcut.pasteLast = value;
+          }}
+          title={t("preference.shortcut.shortcut.label.paste_last")}
+          value={shortcut.pasteLast}
+        />
+
+        <ProShortcut
+          description={t("preference.shortcut.shortcut.hints.paste_queue")}
+          onChange={(value) => {
+            globalStore.shortcut.pasteQueue = value;
+          }}
+          title={t("preference.shortcut.shortcut.label.paste_queue")}
+          value={shortcut.pasteQueue}
+        />
+
         <ProShortcut
           description={t("preference.shortcut.shortcut.hints.paste_as_plain")}
           isSystem={false}
@@ -9,10 +9,12 @@ import { useSnapshot } from "valtio";
 import UnoIcon from "@/components/UnoIcon";
 import UpdateApp from "@/components/UpdateApp";
 import { LISTEN_KEY } from "@/constants";
-import { useRegister } from "@/hooks/useRegister";
 import { useSubscribe } from "@/hooks/useSubscribe";
+import { useTauriListen } from "@/hooks/useTauriListen";
 import { useTray } from "@/hooks/useTray";
+import { useWindowEffect } from "@/hooks/useWindowEffect";
 import { isAutostart } from "@/plugins/autostart";
+import { HOTKEY_PRESSED_EVENT, type HotkeyPressed } from "@/plugins/hotkeys";
 import { showWindow, toggleWindowVisible } from "@/plugins/window";
 import { clipboardStore } from "@/stores/clipboard";
 import { globalStore } from "@/stores/global";
@@ -27,7 +29,7 @@ import Shortcut from "./components/Shortcut";
 
 const Preference = () => {
   const { t } = useTranslation();
-  const { app, shortcut, appearance } = useSnapshot(globalStore);
+  const { app, appearance } = useSnapshot(globalStore);
   const [activeKey, setActiveKey] = useState("clipboard");
   const contentRef = useRef<HTMLElement>(null);
 
@@ -43,6 +45,9 @@ const Preference = () => {
     }
   });
 
+  // 窗口的背景效果
+  useWindowEffect();
+
   // 监听全局配置项变化
   useSubscribe(globalStore, () => handleStoreChanged());
 
@@ -50,7 +55,11 @@ const Preference = () => {
   useSubscribe(clipboardStore, () => handleStoreChanged());
 
   // 监听快捷键切换窗口显隐
-  useRegister(toggleWindowVisible, [shortcut.preference]);
+  useTauriListen<HotkeyPressed>(HOTKEY_PRESSED_EVENT, ({ payload }) => {
+    if (payload.action !== "showPreference") return;
+
+    toggleWindowVisible();
+  });
 
   // 配置项变化通知其它窗口和本地存储
   const handleStoreChanged = () => {
@@ -12,42 +12,366 @@ import {
   readHTML,
   readImage,
   readRTF,
-  readText,
-  writeFiles,
-  writeHTML,
-  writeImage,
-  writeRTF,
   writeText,
 } from "tauri-plugin-clipboard-x-api";
 import { clipboardStore } from "@/stores/clipboard";
 import type { DatabaseSchemaHistory } from "@/types/database";
+import type { FocusPause } from "@/types/store";
 import { isColor, isEmail, isURL } from "@/utils/is";
-import { paste } from "./paste";
+import {
+  type CaptureContext,
+  getItemContent,
+  getItemMetadata,
+} from "./history";
+import { getPasteTarget, paste, pastePlain } from "./paste";
+
+/**
+ * Event emitted by the native clipboard watcher on every change.
+ */
+export const CLIPBOARD_CHANGED_EVENT = "clipboard://changed";
+
+/**
+ * Request another app attached to its copy, see docs/clipboard-protocol.md.
+ */
+export type PasteRequest =
+  | { action: "store"; tags: string[]; note: string | null; favorite: boolean }
+  | { action: "ignore" };
+
+/**
+ * Best-effort explanation of a clipboard change.
+ */
+export interface ClipboardChangeCause {
+  sourceApp: string | null;
+  ownWrite: boolean;
+  cleared: boolean;
+  elapsedMs: number | null;
+}
+
+/**
+ * Virtual machine a copy was bridged from by its guest tools.
+ */
+export interface VmSource {
+  hypervisor: "vmware" | "virtualBox" | "hyperV";
+  name: string;
+}
+
+/**
+ * App a copy was made in, icon is the path of its cached PNG, url and
+ * pageTitle the page of a copy made in a browser.
+ */
+export interface SourceApp {
+  name: string;
+  path: string | null;
+  windowTitle: string | null;
+  icon: string | null;
+  url: string | null;
+  pageTitle: string | null;
+}
+
+/**
+ * Image taken by an OS screenshot tool, with the foreground window at
+ * capture time.
+ */
+export interface ScreenshotInfo {
+  source: string;
+  capturedAt: number;
+  context: CaptureContext;
+}
+
+export interface ClipboardChange {
+  sequence: number;
+  formats: string[];
+  request: PasteRequest | null;
+  cause: ClipboardChangeCause;
+  vm: VmSource | null;
+  source: SourceApp | null;
+  screenshot: ScreenshotInfo | null;
+}
+
+/**
+ * Mark the next clipboard change as written by EcoPaste itself.
+ */
+export const markClipboardWrite = () => invoke("mark_clipboard_write");
+
+/**
+ * Event emitted with content dropped onto the main window.
+ */
+export const CLIPBOARD_DROPPED_EVENT = "clipboard://dropped";
+
+export interface DroppedContent {
+  files: ReadFilesResult | null;
+  text: string | null;
+  metadata: Record<string, string>;
+}
+
+/**
+ * Add text dropped onto the window to the history without going through the
+ * system clipboard.
+ */
+export const addDroppedText = (text: string) =>
+  invoke("add_dropped_text", { text });
+
+/**
+ * Pause or resume the native clipboard watcher.
+ */
+export const setClipboardWatching = (enabled: boolean) =>
+  invoke("set_clipboard_watching", { enabled });
+
+/**
+ * Process names whose copies are never recorded, compared without case and
+ * without the `.exe` suffix.
+ */
+export const setExcludedApps = (apps: string[]) =>
+  invoke("set_excluded_apps", { apps });
+
+/**
+ * Regular expressions, matched without case against copied text and file
+ * paths; matching copies are dropped before anything is stored.
+ */
+export const setCaptureDenylist = (patterns: string[]) =>
+  invoke("set_capture_denylist", { patterns });
+
+export interface DenylistAudit {
+  blocked: number;
+  patterns: { pattern: string; blocked: number }[];
+}
+
+/**
+ * How many captures the denylist blocked since launch.
+ */
+export const getCaptureDenylistAudit = () =>
+  invoke<DenylistAudit>("get_capture_denylist_audit");
+
+/**
+ * Names of the virtual machines whose bridged copies are never recorded.
+ */
+export const setExcludedVms = (names: string[]) =>
+  invoke("set_excluded_vms", { names });
+
+/**
+ * Names of the virtual machines copies were bridged from.
+ */
+export const getVmSources = () => invoke<string[]>("get_vm_sources");
+
+/**
+ * Event emitted when the system Do Not Disturb / Focus mode turns on or off.
+ */
+export const FOCUS_MODE_CHANGED_EVENT = "focus-mode-changed";
+
+export interface FocusModeState {
+  active: boolean;
+  pause: FocusPause;
+}
+
+/**
+ * What to hold back while the system Do Not Disturb mode is on, the copy
+ * sound or capture entirely.
+ */
+export const setFocusPause = (pause: FocusPause) =>
+  invoke("set_focus_pause", { pause });
+
+/**
+ * Current Do Not Disturb state as last seen by the backend.
+ */
+export const getFocusMode = () => invoke<FocusModeState>("get_focus_mode");
+
+export type SensitiveKind = "password" | "creditCard" | "apiKey" | "iban";
+
+/**
+ * Guess whether a text is a password, card number, API key or IBAN.
+ */
+export const classifySensitive = (text: string) =>
+  invoke<SensitiveKind | null>("classify_sensitive", { text });
+
+/**
+ * Delete items flagged as sensitive this many minutes after they were
+ * copied, 0 keeps them.
+ */
+export const setSensitiveExpiry = (minutes: number) =>
+  invoke("set_sensitive_expiry", { minutes });
+
+/**
+ * Formats currently on the clipboard.
+ */
+export const getClipboardFormats = () =>
+  invoke<string[]>("get_clipboard_formats");
 
 interface WinReadImageResult {
   path: string;
   size: number;
   width: number;
   height: number;
+  thumbnailPath: string | null;
+  frames: number | null;
+  duration: number | null;
 }
 
 /**
- * Windows-specific fallback: check if the clipboard contains an image using
- * direct Windows API (handles CF_DIB, CF_DIBV5, PNG formats that the standard
- * plugin may miss).
+ * Native fallback: check if the clipboard contains an image using the OS API
+ * directly (handles CF_DIB, CF_DIBV5, PNG on Windows, public.png,
+ * public.tiff, NSImage on macOS and image/png, image/bmp on X11 and Wayland
+ * that the standard plugin may miss).
  */
 const hasClipboardImageWin = (): Promise<boolean> =>
   invoke<boolean>("has_clipboard_image_win").catch(() => false);
 
 /**
- * Windows-specific fallback: read an image from the clipboard using direct
- * Windows API, convert to PNG, and save to disk.
+ * Native fallback: read an image from the clipboard using the OS API
+ * directly, convert to PNG, and save to disk.
  */
 const readClipboardImageWin = (): Promise<WinReadImageResult | null> =>
   invoke<WinReadImageResult | null>("read_clipboard_image_win").catch(
     () => null,
   );
 
+/**
+ * Read an animated GIF from the clipboard and save the original file, which
+ * the bitmap formats read by the standard plugin flatten to a single frame.
+ */
+const readClipboardGif = (): Promise<WinReadImageResult | null> =>
+  invoke<WinReadImageResult | null>("read_clipboard_gif").catch(() => null);
+
+/**
+ * Write an image to the clipboard from a file path or raw bytes, offered as
+ * both PNG and bitmap so every app can paste it.
+ */
+export const writeClipboardImage = (image: { path?: string; bytes?: number[] }) =>
+  invoke("write_clipboard_image", image);
+
+/**
+ * 把历史记录中的图片复制回剪贴板，同时写入 PNG、CF_DIBV5 和 CF_BITMAP，
+ * 旧版应用（例如画图）也能粘贴
+ */
+export const copyImageEntry = (path: string) =>
+  invoke("copy_image_entry", { path });
+
+interface ClipboardFile {
+  path: string;
+  size: number;
+  kind: "file" | "directory" | "missing";
+  extension: string | null;
+}
+
+interface ReadFilesResult {
+  files: ClipboardFile[];
+  size: number;
+}
+
+/**
+ * Read the copied file list natively (CF_HDROP on Windows,
+ * NSFilenamesPboardType on macOS, text/uri-list on Linux).
+ */
+export const readClipboardFiles = () =>
+  invoke<ReadFilesResult | null>("read_clipboard_files");
+
+/**
+ * Write a file list to the clipboard so it can be pasted into Explorer or
+ * Finder; files that no longer exist are skipped.
+ */
+export const writeClipboardFiles = (paths: string[]) =>
+  invoke("write_clipboard_files", { paths });
+
+interface ReadHtmlResult {
+  html: string;
+  text: string;
+  sourceUrl: string | null;
+}
+
+/**
+ * Read the HTML on the clipboard natively; the fragment is cut out of the
+ * CF_HTML document on Windows and sanitized.
+ */
+export const readClipboardHtml = () =>
+  invoke<ReadHtmlResult | null>("read_clipboard_html");
+
+/**
+ * Write HTML to the clipboard with a valid CF_HTML header on Windows; the
+ * plain text alternative is extracted from the HTML when omitted.
+ */
+export const writeClipboardHtml = (html: string, text?: string) =>
+  invoke("write_clipboard_html", { html, text });
+
+interface ReadRtfResult {
+  rtf: string;
+  text: string;
+}
+
+/**
+ * Read the RTF document on the clipboard natively, together with its plain
+ * text.
+ */
+export const readClipboardRtf = () =>
+  invoke<ReadRtfResult | null>("read_clipboard_rtf");
+
+/**
+ * Write an RTF document to the clipboard; the plain text alternative is
+ * extracted from the document when omitted.
+ */
+export const writeClipboardRtf = (rtf: string, text?: string) =>
+  invoke("write_clipboard_rtf", { rtf, text });
+
+interface RestoredClipboard {
+  id: string;
+  createTime: string;
+  raw: boolean;
+}
+
+/**
+ * Put the clipboard back the way it was at a past moment (`YYYY-MM-DD
+ * HH:mm:ss`), from the raw formats of that copy where they were stored.
+ */
+export const restoreClipboardState = (timestamp: string) =>
+  invoke<RestoredClipboard>("restore_clipboard_state", { timestamp });
+
+interface ReadTextResult {
+  value: string;
+  count: number;
+  blobPath: string | null;
+  sensitive: SensitiveKind | null;
+}
+
+/**
+ * Read clipboard text natively; texts over the inline limit only return a
+ * preview and are streamed to a blob file.
+ */
+export const readClipboardText = () =>
+  invoke<ReadTextResult | null>("read_clipboard_text");
+
+/**
+ * Load the full content of a text that overflowed into a blob file.
+ */
+export const loadFullText = (blobPath: string) =>
+  invoke<string>("load_full_text", { blobPath });
+
+/**
+ * Set the max inline text capture size in bytes.
+ */
+export const setMaxTextCaptureSize = (maxBytes: number) =>
+  invoke("set_max_text_capture_size", { maxBytes });
+
+/**
+ * Development only: put raw clipboard formats on the system clipboard.
+ */
+export const simulateClipboard = (formats: Record<string, number[]>) =>
+  invoke("simulate_clipboard", { payload: { formats } });
+
+/**
+ * Development only: run the capture parsers over the clipboard fixtures.
+ */
+export const replayClipboardFixtures = () =>
+  invoke<
+    {
+      name: string;
+      formats: string[];
+      textChars: number | null;
+      sourceUrl: string | null;
+      imageSize: [number, number] | null;
+      fileCount: number | null;
+      htmlTextChars: number | null;
+      errors: string[];
+    }[]
+  >("replay_clipboard_fixtures");
+
 export const getClipboardTextSubtype = async (value: string) => {
   try {
     if (isURL(value)) {
@@ -70,40 +394,77 @@ export const getClipboardTextSubtype = async (value: string) => {
   }
 };
 
-export const writeToClipboard = (data: DatabaseSchemaHistory) => {
+/**
+ * The full text of a text item, loaded from its blob file when only a
+ * preview was stored.
+ */
+const loadItemText = async ({ id, type, value }: DatabaseSchemaHistory) => {
+  if (type !== "text") return value;
+
+  const { blob_path } = await getItemMetadata(id);
+
+  return blob_path ? loadFullText(blob_path) : value;
+};
+
+/**
+ * 隐藏的敏感条目只有占位文本，通过系统身份验证后从后端取得完整内容
+ */
+const revealItem = async (
+  data: DatabaseSchemaHistory,
+): Promise<DatabaseSchemaHistory> => {
+  if (!data.redacted) return data;
+
+  const { value, search } = await getItemContent(data.id);
+
+  return {
+    ...data,
+    redacted: false,
+    search,
+    value: data.type === "files" ? JSON.parse(value) : value,
+  } as DatabaseSchemaHistory;
+};
+
+export const writeToClipboard = async (item: DatabaseSchemaHistory) => {
+  const data = await revealItem(item);
   const { type, value, search } = data;
 
+  await markClipboardWrite();
+
   switch (type) {
     case "text":
-      return writeText(value);
+      return writeText(await loadItemText(data));
     case "rtf":
-      return writeRTF(search, value);
+      return writeClipboardRtf(value, search);
     case "html":
-      return writeHTML(search, value);
+      return writeClipboardHtml(value, search);
     case "image":
-      return writeImage(value);
+      return copyImageEntry(value);
     case "files":
-      return writeFiles(value);
+      return writeClipboardFiles(value);
   }
 };
 
 export const pasteToClipboard = async (
-  data: DatabaseSchemaHistory,
+  item: DatabaseSchemaHistory,
   asPlain?: boolean,
 ) => {
+  const data = await revealItem(item);
   const { type, value, search } = data;
-  const { pastePlain } = clipboardStore.content;
+  const { content } = clipboardStore;
+
+  // 规则要求逐字输入的应用只接受纯文本，由后端模拟输入
+  const typing = type !== "image" && (await getPasteTarget()).typing;
 
-  if (asPlain ?? pastePlain) {
+  if ((asPlain ?? content.pastePlain) || typing) {
     if (type === "files") {
-      await writeText(value.join("\n"));
-    } else {
-      await writeText(search);
+      return pastePlain(value.join("\n"));
     }
-  } else {
-    await writeToClipboard(data);
+
+    return pastePlain(type === "text" ? await loadItemText(data) : search);
   }
 
+  await writeToClipboard(data);
+
   return paste();
 };
 
@@ -112,21 +473,39 @@ const RETRY_DELAY_MS = 200;
 
 const delay = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
 
+/**
+ * Clipboard content as captured; `blobPath` is set when the text exceeded
+ * the inline limit and `text` only holds its preview.
+ */
+export type CapturedClipboard = ReadClipboard & { blobPath?: string };
+
 /**
  * Reads all available clipboard content with per-format error isolation
  * and retry logic for transient clipboard lock failures (common on Windows).
  */
-export const readClipboardWithRetry = async (): Promise<ReadClipboard> => {
+export const readClipboardWithRetry = async (): Promise<CapturedClipboard> => {
   let lastError: unknown;
 
   for (let attempt = 1; attempt <= RETRY_COUNT; attempt++) {
     try {
-      const result: ReadClipboard = {};
+      const result: CapturedClipboard = {};
 
       try {
         if (await hasText()) {
-          const text = await readText();
-          result.text = { count: text.length, type: "text", value: text };
+          // Read natively so a huge text never reaches the webview whole
+          const text = await readClipboardText();
+
+          if (text) {
+            result.text = {
+              count: text.count,
+              type: "text",
+              value: text.value,
+            };
+
+            if (text.blobPath) {
+              result.blobPath = text.blobPath;
+            }
+          }
         }
       } catch (err) {
         logWarn(
@@ -135,9 +514,30 @@ export const readClipboardWithRetry = async (): Promise<ReadClipboard> => {
         throw err;
       }
 
+      // The rich formats of an overflowed text are at least as large
       try {
-        if (await hasRTF()) {
-          const rtf = await readRTF();
+        if (!result.blobPath && (await hasRTF())) {
+          let rtf: string;
+
+          try {
+            rtf = await readRTF();
+          } catch (pluginErr) {
+            logWarn(
+              `readClipboard: standard plugin failed to read RTF (attempt ${attempt}): ${String(pluginErr)}`,
+            );
+
+            // Native fallback: read the registered RTF format directly.
+            const nativeRtf = await readClipboardRtf();
+            if (!nativeRtf) throw pluginErr;
+
+            rtf = nativeRtf.rtf;
+            result.text ??= {
+              count: nativeRtf.text.length,
+              type: "text",
+              value: nativeRtf.text,
+            };
+          }
+
           result.rtf = {
             count: result.text?.count ?? rtf.length,
             type: "rtf",
@@ -152,8 +552,28 @@ export const readClipboardWithRetry = async (): Promise<ReadClipboard> => {
       }
 
       try {
-        if (await hasHTML()) {
-          const html = await readHTML();
+        if (!result.blobPath && (await hasHTML())) {
+          let html: string;
+
+          try {
+            html = await readHTML();
+          } catch (pluginErr) {
+            logWarn(
+              `readClipboard: standard plugin failed to read HTML (attempt ${attempt}): ${String(pluginErr)}`,
+            );
+
+            // Native fallback: parse the fragment out of the HTML format.
+            const nativeHtml = await readClipboardHtml();
+            if (!nativeHtml) throw pluginErr;
+
+            html = nativeHtml.html;
+            result.text ??= {
+              count: nativeHtml.text.length,
+              type: "text",
+              value: nativeHtml.text,
+            };
+          }
+
           result.html = {
             count: result.text?.count ?? html.length,
             type: "html",
@@ -170,7 +590,16 @@ export const readClipboardWithRetry = async (): Promise<ReadClipboard> => {
       try {
         let imageReadFailed = false;
 
-        if (await hasImage()) {
+        const gif = await readClipboardGif();
+        if (gif) {
+          result.image = {
+            count: gif.size,
+            height: gif.height,
+            type: "image",
+            value: gif.path,
+            width: gif.width,
+          };
+        } else if (await hasImage()) {
           try {
             const { path, size, ...rest } = await readImage();
             result.image = {
@@ -187,7 +616,7 @@ export const readClipboardWithRetry = async (): Promise<ReadClipboard> => {
           }
         }
 
-        // Windows fallback: try direct Windows API when the standard plugin
+        // Native fallback: try the OS API directly when the standard plugin
         // either didn't detect the image or failed to read it.
         if (
           !result.image &&
@@ -212,9 +641,30 @@ export const readClipboardWithRetry = async (): Promise<ReadClipboard> => {
       }
 
       try {
+        let filesReadFailed = false;
+
         if (await hasFiles()) {
-          const { paths, size } = await readFiles();
-          result.files = { count: size, type: "files", value: paths };
+          try {
+            const { paths, size } = await readFiles();
+            result.files = { count: size, type: "files", value: paths };
+          } catch (pluginErr) {
+            logWarn(
+              `readClipboard: standard plugin failed to read files (attempt ${attempt}): ${String(pluginErr)}`,
+            );
+            filesReadFailed = true;
+          }
+        }
+
+        // Native fallback for file lists the standard plugin failed to read.
+        if (!result.files && filesReadFailed) {
+          const nativeFiles = await readClipboardFiles();
+          if (nativeFiles && nativeFiles.files.length > 0) {
+            result.files = {
+              count: nativeFiles.size,
+              type: "files",
+              value: nativeFiles.files.map(({ path }) => path),
+            };
+          }
         }
       } catch (err) {
         logWarn(
@@ -0,0 +1,46 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  GET_DIGEST: "get_digest",
+  SET_DIGEST_POLICY: "set_digest_policy",
+};
+
+export type DigestPeriod = "daily" | "weekly";
+
+export interface DigestPolicy {
+  period: DigestPeriod;
+  notify: boolean;
+  directory?: string;
+}
+
+export interface Digest {
+  period: DigestPeriod;
+  since: string;
+  until: string;
+  stats: {
+    total: number;
+    favorites: number;
+    byType: { kind: string; count: number }[];
+    topApps: { app: string; count: number }[];
+  };
+  notable: {
+    id: string;
+    kind: string;
+    preview: string;
+    sourceApp?: string;
+  }[];
+}
+
+/**
+ * 设置定期的剪贴板摘要，按天或按周以通知或文件的形式发送，传入 null 关闭
+ */
+export const setDigestPolicy = (policy: DigestPolicy | null) => {
+  return invoke(COMMAND.SET_DIGEST_POLICY, { policy });
+};
+
+/**
+ * 生成最近一天或一周的剪贴板摘要，用于预览
+ */
+export const getDigest = (period: DigestPeriod) => {
+  return invoke<Digest>(COMMAND.GET_DIGEST, { period });
+};
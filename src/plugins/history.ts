@@ -0,0 +1,922 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  ANALYZE_STORAGE: "analyze_storage",
+  APPLY_CAPTURE_RULES: "apply_capture_rules",
+  COPY_PING_COMMAND: "copy_ping_command",
+  CREATE_ITEM: "create_item",
+  CREATE_TAG: "create_tag",
+  DELETE_HISTORY_ENTRY: "delete_history_entry",
+  DELETE_HISTORY_RANGE: "delete_history_range",
+  DELETE_TAG: "delete_tag",
+  EDIT_ITEM_CONTENT: "edit_item_content",
+  EXPORT_FAVORITES: "export_favorites",
+  EXPORT_HISTORY: "export_history",
+  EXPORT_TIMELINE: "export_timeline",
+  FETCH_IMAGE: "fetch_image",
+  FIND_DUPLICATES: "find_duplicates",
+  FIND_HISTORY_DUPLICATE: "find_history_duplicate",
+  FIND_SIMILAR_IMAGES: "find_similar_images",
+  GET_BACKFILL_PROGRESS: "get_backfill_progress",
+  GET_CAPTURE_CONTEXT: "get_capture_context",
+  GET_HISTORY_FORMATS: "get_history_formats",
+  GET_HISTORY_OPERATIONS: "get_history_operations",
+  GET_ITEM_CONTENT: "get_item_content",
+  GET_ITEM_METADATA: "get_item_metadata",
+  GET_LEGACY_MIGRATION: "get_legacy_migration",
+  GET_RELATED: "get_related",
+  GET_SCREEN_SHARING: "get_screen_sharing",
+  GET_STORAGE_STATS: "get_storage_stats",
+  HISTORY_REPLACE: "history_replace",
+  IMPORT_HISTORY: "import_history",
+  INSERT_HISTORY_ENTRY: "insert_history_entry",
+  LINK_HISTORY_ITEMS: "link_history_items",
+  LIST_FAVORITES: "list_favorites",
+  LIST_SOURCE_APPS: "list_source_apps",
+  LIST_TAGS: "list_tags",
+  LOCK_SENSITIVE_ITEMS: "lock_sensitive_items",
+  MERGE_DUPLICATES: "merge_duplicates",
+  MERGE_HISTORY_OPERATIONS: "merge_history_operations",
+  OCR_HISTORY_ITEM: "ocr_history_item",
+  OCR_IMAGE: "ocr_image",
+  OPEN_ITEM_WITH: "open_item_with",
+  OPEN_NETWORK_ITEM: "open_network_item",
+  OPTIMIZE_STORAGE: "optimize_storage",
+  PURGE_TEMP_FILES: "purge_temp_files",
+  QUERY_HISTORY: "query_history",
+  RECENT_EMOJI: "recent_emoji",
+  RECOMPRESS_STORED_IMAGES: "recompress_stored_images",
+  RECORD_HISTORY_OPERATION: "record_history_operation",
+  RECORD_SCREENSHOT: "record_screenshot",
+  SAVE_ITEM_METADATA: "save_item_metadata",
+  SEARCH_ARCHIVES: "search_archives",
+  SEARCH_FULLTEXT: "search_fulltext",
+  SEARCH_HISTORY: "search_history",
+  SEARCH_ITEM_METADATA: "search_item_metadata",
+  SET_ARCHIVE_POLICY: "set_archive_policy",
+  SET_CAPTURE_RULES: "set_capture_rules",
+  SET_COLLATION_LOCALE: "set_collation_locale",
+  SET_CONVERSION_TARGETS: "set_conversion_targets",
+  SET_COLD_STORAGE_POLICY: "set_cold_storage_policy",
+  SET_CURRENCY_RATES_FETCH: "set_currency_rates_fetch",
+  SET_FAVORITE: "set_favorite",
+  SET_IMAGE_HASHING: "set_image_hashing",
+  SET_IMAGE_STORAGE: "set_image_storage",
+  SET_ITEM_TAGS: "set_item_tags",
+  SET_NETWORK_ENRICHMENT: "set_network_enrichment",
+  SET_RETENTION_POLICY: "set_retention_policy",
+  SET_REVEAL_GRACE_PERIOD: "set_reveal_grace_period",
+  SET_SAVE_DATA_DIR: "set_save_data_dir",
+  START_BACKFILL: "start_backfill",
+  TAG_HISTORY_ITEMS: "tag_history_items",
+  TOGGLE_HISTORY_FAVORITE: "toggle_history_favorite",
+  UNDO_HISTORY_OPERATION: "undo_history_operation",
+  UNLINK_HISTORY_ITEMS: "unlink_history_items",
+  UPDATE_HISTORY_ENTRY: "update_history_entry",
+  VACUUM_STORAGE: "vacuum_storage",
+};
+
+export interface StorageReport {
+  databaseBytes: number;
+  imagesBytes: number;
+  byType: Record<string, { count: number; bytes: number }>;
+  biggestItems: { id: string; type: string; bytes: number }[];
+  duplicateImageBytes: number;
+  orphanImageBytes: number;
+}
+
+export interface StorageStats {
+  imagesDirBytes: number;
+  databaseBytes: number;
+  counts: Record<string, number>;
+}
+
+export interface VacuumReport {
+  removedFiles: number;
+  freedBytes: number;
+}
+
+export interface OptimizeReport {
+  before: StorageReport;
+  after: StorageReport;
+  recompressedImages: number;
+  prunedItems: number;
+  removedOrphans: number;
+}
+
+export interface PrunePolicy {
+  maxAgeDays?: number;
+  maxCount?: number;
+  groupMaxAgeDays?: Partial<Record<"text" | "image" | "files", number>>;
+}
+
+export interface BackfillFeatures {
+  // 计算图片指纹
+  phash?: boolean;
+  // 识别图片中的文字
+  ocr?: boolean;
+}
+
+export interface ColdStoragePolicy {
+  afterDays: number;
+  maxAccessCount?: number;
+  location?: string;
+}
+
+export interface ImageStoragePolicy {
+  format: "png" | "webp" | "jpeg";
+  // JPEG 的质量，1 到 100
+  quality: number;
+  compression: "fast" | "default" | "best";
+}
+
+export interface NetworkEnrichment {
+  // 是否查询 IP 的反向解析和域名的地址，关闭时不发出任何网络请求
+  lookups: boolean;
+  // 离线的 ip2asn 数据库（TSV）路径，为空时不查询 ASN
+  asnDatabase: string;
+}
+
+export interface RecompressReport {
+  converted: number;
+  skipped: number;
+  savedBytes: number;
+}
+
+type HistoryRow = Record<string, unknown>;
+
+export type HistoryOperation =
+  | { kind: "insert"; row: HistoryRow; metadata?: Record<string, string> }
+  | { kind: "edit"; id: string; before: HistoryRow; after: HistoryRow }
+  | {
+      kind: "tag";
+      id: string;
+      key: string;
+      before: string | null;
+      after: string | null;
+    }
+  | { kind: "delete"; row: HistoryRow; metadata?: Record<string, string> };
+
+export interface HistoryOperationEntry {
+  seq: number;
+  opId: string;
+  deviceId: string;
+  timestamp: number;
+  undoOf: string | null;
+  operation: HistoryOperation;
+}
+
+export type RelationKind = "translation" | "ocr" | "transform" | "ai" | "link";
+
+export interface RelatedItem {
+  id: string;
+  kind: RelationKind;
+  direction: "source" | "derived";
+  createdAt: number;
+}
+
+export type CaptureRuleAction = { kind: "appendToFile"; path: string };
+
+export interface CaptureRule {
+  app?: string;
+  type?: "text" | "html" | "rtf" | "image" | "files";
+  contains?: string;
+  action: CaptureRuleAction;
+}
+
+export interface HistoryEntryFormat {
+  format: string;
+  text?: string | null;
+  data?: number[] | null;
+}
+
+/**
+ * 返回的 createTime 格式：stored 为写入时的本地时间，local 按当前时区重新计算，utc 为 ISO 8601
+ */
+export type TimeFormat = "stored" | "local" | "utc";
+
+export interface HistoryQuery {
+  group?: string;
+  tag?: string;
+  search?: string;
+  page: number;
+  size: number;
+  timeFormat?: TimeFormat;
+}
+
+export interface HistoryPage<T = HistoryRow> {
+  items: T[];
+  total: number;
+  hasMore: boolean;
+}
+
+export interface SearchFilters {
+  group?: string;
+  tag?: string;
+  sourceApp?: string;
+  since?: string;
+  until?: string;
+  page?: number;
+  size?: number;
+}
+
+export interface SearchMatch<T = HistoryRow> {
+  item: T;
+  rank: number;
+  snippet: string;
+  /** UTF-16 偏移，可直接用于 String.prototype.slice */
+  highlights: { start: number; end: number }[];
+}
+
+export interface SearchResults<T = HistoryRow> {
+  matches: SearchMatch<T>[];
+  total: number;
+  hasMore: boolean;
+}
+
+export interface BackfillProgress {
+  processed: number;
+  total: number;
+  running: boolean;
+}
+
+export interface LegacyMigrationProgress {
+  stage: "schema" | "images" | "done" | "failed";
+  processed: number;
+  total: number;
+  backup: string | null;
+  error: string | null;
+}
+
+export interface CaptureContext {
+  appName: string | null;
+  windowTitle: string | null;
+  url: string | null;
+}
+
+/**
+ * 同步界面语言给后端，搜索忽略大小写和变音符号，标签等列表按该语言的规则排序
+ */
+export const setCollationLocale = (locale?: string) => {
+  return invoke(COMMAND.SET_COLLATION_LOCALE, { locale });
+};
+
+/**
+ * 同步存储数据的目录给后端
+ */
+export const setSaveDataDir = (dir: string) => {
+  return invoke(COMMAND.SET_SAVE_DATA_DIR, { dir });
+};
+
+/**
+ * 获取当前前台窗口的上下文（窗口标题、浏览器地址）
+ */
+export const getCaptureContext = () => {
+  return invoke<CaptureContext>(COMMAND.GET_CAPTURE_CONTEXT);
+};
+
+/**
+ * 记录截图条目的截图工具、时间和所在的显示器
+ */
+export const recordScreenshot = (
+  id: string,
+  screenshot: { source: string; capturedAt: number; context: CaptureContext },
+) => {
+  return invoke(COMMAND.RECORD_SCREENSHOT, { id, screenshot });
+};
+
+/**
+ * 保存条目的元数据
+ */
+export const saveItemMetadata = (
+  id: string,
+  metadata: Record<string, string | null | undefined>,
+) => {
+  return invoke(COMMAND.SAVE_ITEM_METADATA, { id, metadata });
+};
+
+/**
+ * 标记或取消标记敏感条目，屏幕共享时它们在查询结果中会被隐藏
+ */
+export const setItemSensitive = (id: string, sensitive: boolean) => {
+  return saveItemMetadata(id, { sensitive: sensitive ? "1" : "0" });
+};
+
+export interface HistoryItemContent {
+  id: string;
+  type: string;
+  value: string;
+  search: string;
+  subtype?: string;
+  createTime: string;
+}
+
+/**
+ * 获取条目的完整内容，敏感条目需要先通过系统身份验证
+ */
+export const getItemContent = (id: string) => {
+  return invoke<HistoryItemContent>(COMMAND.GET_ITEM_CONTENT, { id });
+};
+
+/**
+ * 设置身份验证后敏感条目保持解锁的秒数，0 为每次都需要验证
+ */
+export const setRevealGracePeriod = (seconds: number) => {
+  return invoke(COMMAND.SET_REVEAL_GRACE_PERIOD, { seconds });
+};
+
+/**
+ * 立即锁定敏感条目
+ */
+export const lockSensitiveItems = () => {
+  return invoke(COMMAND.LOCK_SENSITIVE_ITEMS);
+};
+
+/**
+ * 识别图片中的文字
+ */
+export const ocrImage = (path: string) => {
+  return invoke<string>(COMMAND.OCR_IMAGE, { path });
+};
+
+/**
+ * 识别图片条目中的文字，保存后图片可以通过文字被搜索到
+ */
+export const ocrHistoryItem = (id: string) => {
+  return invoke<string>(COMMAND.OCR_HISTORY_ITEM, { id });
+};
+
+/**
+ * 获取屏幕是否正在共享或录制，变化通过 screen-share-changed 事件通知
+ */
+export const getScreenSharing = () => {
+  return invoke<boolean>(COMMAND.GET_SCREEN_SHARING);
+};
+
+/**
+ * 获取条目的元数据
+ */
+export const getItemMetadata = (id: string) => {
+  return invoke<Record<string, string>>(COMMAND.GET_ITEM_METADATA, { id });
+};
+
+/**
+ * 搜索元数据，返回匹配的条目 id
+ */
+export const searchItemMetadata = (query: string) => {
+  return invoke<string[]>(COMMAND.SEARCH_ITEM_METADATA, { query });
+};
+
+/**
+ * 设置采集规则，例如将匹配的条目追加到每日笔记（路径支持 {date}）
+ */
+export const setCaptureRules = (rules: CaptureRule[]) => {
+  return invoke(COMMAND.SET_CAPTURE_RULES, { rules });
+};
+
+/**
+ * 对新采集的条目执行匹配的规则，返回执行的规则数量
+ */
+export const applyCaptureRules = (id: string) => {
+  return invoke<number>(COMMAND.APPLY_CAPTURE_RULES, { id });
+};
+
+/**
+ * 用指定的应用（或系统的“打开方式”）打开条目
+ */
+export const openItemWith = (id: string, appPath?: string) => {
+  return invoke(COMMAND.OPEN_ITEM_WITH, { appPath, id });
+};
+
+/**
+ * 复制 ping 条目中 IP 或域名的命令
+ */
+export const copyPingCommand = (id: string) => {
+  return invoke(COMMAND.COPY_PING_COMMAND, { id });
+};
+
+/**
+ * 在浏览器中打开条目中的 IP 或域名
+ */
+export const openNetworkItem = (id: string) => {
+  return invoke(COMMAND.OPEN_NETWORK_ITEM, { id });
+};
+
+/**
+ * 清除所有物化到磁盘的临时文件
+ */
+export const purgeTempFiles = () => {
+  return invoke<number>(COMMAND.PURGE_TEMP_FILES);
+};
+
+/**
+ * 分析存储空间占用
+ */
+export const analyzeStorage = () => {
+  return invoke<StorageReport>(COMMAND.ANALYZE_STORAGE);
+};
+
+/**
+ * 获取存储占用的概况，比 analyzeStorage 快
+ */
+export const getStorageStats = () => {
+  return invoke<StorageStats>(COMMAND.GET_STORAGE_STATS);
+};
+
+/**
+ * 删除没有任何条目引用的图片文件及其缩略图
+ */
+export const vacuumStorage = () => {
+  return invoke<VacuumReport>(COMMAND.VACUUM_STORAGE);
+};
+
+/**
+ * 一键优化存储
+ */
+export const optimizeStorage = (policy?: PrunePolicy) => {
+  return invoke<OptimizeReport>(COMMAND.OPTIMIZE_STORAGE, { policy });
+};
+
+/**
+ * 设置历史记录的保留策略，由后台任务定期清理，收藏的条目不会被删除
+ */
+export const setRetentionPolicy = (policy: PrunePolicy) => {
+  return invoke(COMMAND.SET_RETENTION_POLICY, { policy });
+};
+
+/**
+ * 设置图片冷存储策略，传 null 关闭
+ */
+export const setColdStoragePolicy = (policy: ColdStoragePolicy | null) => {
+  return invoke(COMMAND.SET_COLD_STORAGE_POLICY, { policy });
+};
+
+/**
+ * 设置单位和货币换算的目标单位，传 null 使用默认的单位
+ */
+export const setConversionTargets = (targets: string[] | null) => {
+  return invoke(COMMAND.SET_CONVERSION_TARGETS, { targets });
+};
+
+/**
+ * 设置是否从网络获取实时汇率，默认关闭，使用内置的汇率
+ */
+export const setCurrencyRatesFetch = (enabled: boolean) => {
+  return invoke(COMMAND.SET_CURRENCY_RATES_FETCH, { enabled });
+};
+
+/**
+ * 设置复制 IP 和域名时附加的信息，传 null 关闭网络查询
+ */
+export const setNetworkEnrichment = (policy: NetworkEnrichment | null) => {
+  return invoke(COMMAND.SET_NETWORK_ENRICHMENT, { policy });
+};
+
+/**
+ * 设置新图片保存的格式及压缩程度，传 null 恢复默认的 PNG
+ */
+export const setImageStorage = (policy: ImageStoragePolicy | null) => {
+  return invoke(COMMAND.SET_IMAGE_STORAGE, { policy });
+};
+
+/**
+ * 将已保存的图片转换为当前设置的格式，只保留变小的结果
+ */
+export const recompressStoredImages = () => {
+  return invoke<RecompressReport>(COMMAND.RECOMPRESS_STORED_IMAGES);
+};
+
+/**
+ * 获取图片的本地路径，图片在冷存储目录时先移回
+ */
+export const fetchImage = (value: string) => {
+  return invoke<string>(COMMAND.FETCH_IMAGE, { value });
+};
+
+/**
+ * 设置历史记录在多少个月后移入按月归档的数据库，0 为不归档
+ */
+export const setArchivePolicy = (months: number) => {
+  return invoke(COMMAND.SET_ARCHIVE_POLICY, { months });
+};
+
+export interface ArchiveMatch extends Omit<SearchMatch, "rank"> {
+  /** 归档所属的月份，YYYY-MM */
+  month: string;
+}
+
+/**
+ * 搜索已归档的历史记录，从最近的月份开始查找
+ */
+export const searchArchives = (query: string, limit?: number) => {
+  return invoke<ArchiveMatch[]>(COMMAND.SEARCH_ARCHIVES, { limit, query });
+};
+
+/**
+ * 导出某一天的剪贴板时间线，dest 以 .md 结尾时导出 markdown，否则导出 html
+ */
+export const exportTimeline = (date: string, dest: string) => {
+  return invoke<number>(COMMAND.EXPORT_TIMELINE, { date, dest });
+};
+
+export interface ImportSummary {
+  imported: number;
+  skipped: number;
+  missingImages: number;
+}
+
+/**
+ * 导出全部剪贴板历史，json 只包含条目和元数据，zip 同时打包图片文件
+ */
+export const exportHistory = (path: string, format: "json" | "zip") => {
+  return invoke<number>(COMMAND.EXPORT_HISTORY, { format, path });
+};
+
+/**
+ * 单独导出收藏的条目，格式与 exportHistory 相同，可用 importHistory 导入
+ */
+export const exportFavorites = (path: string, format: "json" | "zip") => {
+  return invoke<number>(COMMAND.EXPORT_FAVORITES, { format, path });
+};
+
+/**
+ * 导入 exportHistory 导出的文件，已存在的条目保留本地版本
+ */
+export const importHistory = (path: string) => {
+  return invoke<ImportSummary>(COMMAND.IMPORT_HISTORY, { path });
+};
+
+/**
+ * 开始为历史记录建立全文索引，并按需计算图片指纹和识别文字，处理的内容和上次不同时从头开始，进度通过 backfill-progress 事件通知
+ */
+export const startBackfill = (features?: BackfillFeatures) => {
+  return invoke(COMMAND.START_BACKFILL, { features });
+};
+
+/**
+ * 获取索引的进度
+ */
+export const getBackfillProgress = () => {
+  return invoke<BackfillProgress>(COMMAND.GET_BACKFILL_PROGRESS);
+};
+
+/**
+ * 获取旧版本数据的升级进度，没有需要升级的数据时为空，进度通过 legacy-migration-progress 事件通知
+ */
+export const getLegacyMigration = () => {
+  return invoke<LegacyMigrationProgress | null>(COMMAND.GET_LEGACY_MIGRATION);
+};
+
+/**
+ * 全文搜索，返回匹配的条目 id
+ */
+export const searchFulltext = (query: string) => {
+  return invoke<string[]>(COMMAND.SEARCH_FULLTEXT, { query });
+};
+
+/**
+ * 记录对历史记录的修改
+ */
+export const recordHistoryOperation = (operation: HistoryOperation) => {
+  return invoke<HistoryOperationEntry>(COMMAND.RECORD_HISTORY_OPERATION, {
+    operation,
+  });
+};
+
+/**
+ * 撤销最近一次修改
+ */
+export const undoHistoryOperation = () => {
+  return invoke<HistoryOperationEntry | null>(COMMAND.UNDO_HISTORY_OPERATION);
+};
+
+/**
+ * 获取指定位置之后的修改记录
+ */
+export const getHistoryOperations = (since = 0) => {
+  return invoke<HistoryOperationEntry[]>(COMMAND.GET_HISTORY_OPERATIONS, {
+    since,
+  });
+};
+
+/**
+ * 合并其它设备的修改记录
+ */
+export const mergeHistoryOperations = (entries: HistoryOperationEntry[]) => {
+  return invoke<number>(COMMAND.MERGE_HISTORY_OPERATIONS, { entries });
+};
+
+/**
+ * 关联两个条目，target 由 source 生成（翻译、OCR、AI 等）
+ */
+export const linkHistoryItems = (
+  sourceId: string,
+  targetId: string,
+  kind: RelationKind = "link",
+) => {
+  return invoke(COMMAND.LINK_HISTORY_ITEMS, { kind, sourceId, targetId });
+};
+
+/**
+ * 取消两个条目的关联
+ */
+export const unlinkHistoryItems = (sourceId: string, targetId: string) => {
+  return invoke(COMMAND.UNLINK_HISTORY_ITEMS, { sourceId, targetId });
+};
+
+/**
+ * 获取与条目关联的条目
+ */
+export const getRelated = (id: string) => {
+  return invoke<RelatedItem[]>(COMMAND.GET_RELATED, { id });
+};
+
+export type DuplicateMode = "exact" | "normalized" | "similar_images";
+
+export interface DuplicateGroup {
+  ids: string[];
+  type: string;
+  reclaimableBytes: number;
+}
+
+export interface DuplicateReport {
+  groups: DuplicateGroup[];
+  reclaimableBytes: number;
+}
+
+/**
+ * 查找重复的条目，每组中第一个是建议保留的最新条目
+ */
+export const findDuplicates = (mode: DuplicateMode) => {
+  return invoke<DuplicateReport>(COMMAND.FIND_DUPLICATES, { mode });
+};
+
+/**
+ * 相似的图片条目，distance 为感知哈希相差的位数，0 为同一张图片
+ */
+export interface SimilarImage {
+  id: string;
+  distance: number;
+}
+
+/**
+ * 查找与指定图片相似的图片条目，最相似的在前，path 可以是历史图片的文件名
+ */
+export const findSimilarImages = (path: string, threshold?: number) => {
+  return invoke<SimilarImage[]>(COMMAND.FIND_SIMILAR_IMAGES, {
+    path,
+    threshold,
+  });
+};
+
+/**
+ * 设置是否在保存图片时计算感知哈希
+ */
+export const setImageHashing = (enabled: boolean) => {
+  return invoke(COMMAND.SET_IMAGE_HASHING, { enabled });
+};
+
+/**
+ * 合并一组重复的条目，只保留 keep，收藏、备注和标签会合并到保留的条目上
+ */
+export const mergeDuplicates = (group: string[], keep: string) => {
+  return invoke<{ removed: number; reclaimedBytes: number }>(
+    COMMAND.MERGE_DUPLICATES,
+    { group, keep },
+  );
+};
+
+export interface NewItem {
+  type: "text" | "html" | "rtf";
+  content: string;
+  tags?: string[];
+}
+
+/**
+ * 手动新建条目，返回新条目
+ */
+export const createItem = (item: NewItem) => {
+  return invoke<HistoryRow>(COMMAND.CREATE_ITEM, { item });
+};
+
+/**
+ * 编辑文本类条目的内容，返回更新后的条目
+ */
+export const editItemContent = (id: string, content: string) => {
+  return invoke<HistoryRow>(COMMAND.EDIT_ITEM_CONTENT, { content, id });
+};
+
+export interface ReplaceFilter {
+  ids?: string[];
+  favoriteOnly?: boolean;
+  tag?: string;
+  since?: string;
+  until?: string;
+}
+
+export interface ReplaceChange {
+  id: string;
+  before: string;
+  after: string;
+  replacements: number;
+}
+
+export interface ReplaceReport {
+  changes: ReplaceChange[];
+  applied: boolean;
+}
+
+/**
+ * 在文本条目中批量正则替换，dryRun 时只预览修改，不写入
+ */
+export const historyReplace = (
+  pattern: string,
+  replacement: string,
+  filter?: ReplaceFilter,
+  dryRun = false,
+) => {
+  return invoke<ReplaceReport>(COMMAND.HISTORY_REPLACE, {
+    dryRun,
+    filter,
+    pattern,
+    replacement,
+  });
+};
+
+/**
+ * 插入历史记录及其各个剪贴板格式
+ */
+export const insertHistoryEntry = (
+  row: HistoryRow,
+  formats: HistoryEntryFormat[] = [],
+) => {
+  return invoke(COMMAND.INSERT_HISTORY_ENTRY, {
+    entry: { ...row, formats },
+  });
+};
+
+/**
+ * 分页查询历史记录
+ */
+export const queryHistory = <T = HistoryRow>(query: HistoryQuery) => {
+  return invoke<HistoryPage<T>>(COMMAND.QUERY_HISTORY, { query });
+};
+
+/**
+ * 全文搜索历史记录，按相关度排序并返回高亮位置
+ */
+export const searchHistory = <T = HistoryRow>(
+  query: string,
+  filters?: SearchFilters,
+) => {
+  return invoke<SearchResults<T>>(COMMAND.SEARCH_HISTORY, { filters, query });
+};
+
+/**
+ * 获取历史记录保存的剪贴板格式
+ */
+export const getHistoryFormats = (id: string) => {
+  return invoke<HistoryEntryFormat[]>(COMMAND.GET_HISTORY_FORMATS, { id });
+};
+
+/**
+ * 删除历史记录，图片条目会一并删除图片文件
+ */
+export const deleteHistoryEntry = (id: string) => {
+  return invoke<boolean>(COMMAND.DELETE_HISTORY_ENTRY, { id });
+};
+
+/**
+ * 删除指定时间范围内复制的历史记录，不传范围时删除全部，返回删除的数量
+ */
+export const deleteHistoryRange = (
+  since?: string,
+  until?: string,
+  includeFavorites = false,
+) => {
+  return invoke<number>(COMMAND.DELETE_HISTORY_RANGE, {
+    includeFavorites,
+    since,
+    until,
+  });
+};
+
+/**
+ * 修改历史记录的复制时间、收藏或备注
+ */
+export const updateHistoryEntry = (
+  id: string,
+  fields: Partial<Record<"createTime" | "favorite" | "note", unknown>>,
+) => {
+  return invoke(COMMAND.UPDATE_HISTORY_ENTRY, { fields, id });
+};
+
+export interface CapturedContent {
+  type: string;
+  value: string;
+  width?: number;
+  height?: number;
+  count?: number;
+  blobPath?: string;
+}
+
+/**
+ * 查找新复制的内容与哪条历史记录重复，加密的内容在后端解密后比较
+ */
+export const findHistoryDuplicate = (content: CapturedContent) => {
+  return invoke<string | null>(COMMAND.FIND_HISTORY_DUPLICATE, { content });
+};
+
+/**
+ * 收藏或取消收藏，不传 favorite 时切换当前状态
+ */
+export const toggleHistoryFavorite = (id: string, favorite?: boolean) => {
+  return invoke<boolean>(COMMAND.TOGGLE_HISTORY_FAVORITE, { favorite, id });
+};
+
+/**
+ * 收藏或取消收藏，收藏的条目不会被保留策略清理
+ */
+export const setFavorite = (id: string, favorite: boolean) => {
+  return invoke(COMMAND.SET_FAVORITE, { favorite, id });
+};
+
+/**
+ * 获取所有收藏的条目，最新的在前
+ */
+export const listFavorites = () => {
+  return invoke<HistoryRow[]>(COMMAND.LIST_FAVORITES);
+};
+
+export interface TagSummary {
+  name: string;
+  count: number;
+}
+
+/**
+ * 新建标签，返回整理后的标签名
+ */
+export const createTag = (name: string) => {
+  return invoke<string>(COMMAND.CREATE_TAG, { name });
+};
+
+/**
+ * 删除标签，同时从所有条目上移除，返回受影响的条目数
+ */
+export const deleteTag = (name: string) => {
+  return invoke<number>(COMMAND.DELETE_TAG, { name });
+};
+
+export interface SourceAppSummary {
+  name: string;
+  icon: string | null;
+  count: number;
+}
+
+/**
+ * 最近使用的表情，codepoint 可直接传给 pasteEmoji
+ */
+export interface EmojiUsage {
+  emoji: string;
+  codepoint: string;
+  count: number;
+}
+
+/**
+ * 获取最近使用的表情，最近的在前，默认 24 个
+ */
+export const recentEmoji = (limit?: number) => {
+  return invoke<EmojiUsage[]>(COMMAND.RECENT_EMOJI, { limit });
+};
+
+/**
+ * 获取复制来源的应用及各自的条目数，用于按应用筛选
+ */
+export const listSourceApps = () => {
+  return invoke<SourceAppSummary[]>(COMMAND.LIST_SOURCE_APPS);
+};
+
+/**
+ * 获取所有标签及使用它们的条目数
+ */
+export const listTags = () => {
+  return invoke<TagSummary[]>(COMMAND.LIST_TAGS);
+};
+
+/**
+ * 设置条目的标签，返回整理后的标签
+ */
+export const setItemTags = (id: string, tags: string[]) => {
+  return invoke<string[]>(COMMAND.SET_ITEM_TAGS, { id, tags });
+};
+
+/**
+ * 批量给条目添加或移除一个标签，返回有变化的条目数
+ */
+export const tagHistoryItems = (
+  ids: string[],
+  tag: string,
+  assigned: boolean,
+) => {
+  return invoke<number>(COMMAND.TAG_HISTORY_ITEMS, { assigned, ids, tag });
+};
@@ -0,0 +1,48 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  SET_HOTKEYS: "set_hotkeys",
+};
+
+/**
+ * 全局快捷键被按下时发送给所有窗口的事件
+ */
+export const HOTKEY_PRESSED_EVENT = "hotkey://pressed";
+
+export type HotkeyAction =
+  | "showWindow"
+  | "showPreference"
+  | "pasteLast"
+  | "pastePlain"
+  | "pasteNth"
+  | "pasteQueue";
+
+export interface HotkeyConfig {
+  showWindow?: string;
+  showPreference?: string;
+  pasteLast?: string;
+  pastePlain?: string;
+  // 只有修饰键，会依次加上数字 1 到 9
+  pasteNth?: string;
+  pasteQueue?: string;
+}
+
+export interface HotkeyPressed {
+  // pasteNth 和 pasteQueue 由后端直接粘贴，不会发送给窗口
+  action: HotkeyAction;
+  index?: number;
+}
+
+export interface HotkeyConflict {
+  action: HotkeyAction;
+  shortcut: string;
+  reason: "invalid" | "duplicate" | "taken";
+  message: string;
+}
+
+/**
+ * 注册全局快捷键，替换之前注册的快捷键，返回无法注册的快捷键
+ */
+export const setHotkeys = (config: HotkeyConfig) => {
+  return invoke<HotkeyConflict[]>(COMMAND.SET_HOTKEYS, { config });
+};
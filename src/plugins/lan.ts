@@ -0,0 +1,30 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  GET_LAN_PEERS: "get_lan_peers",
+  SET_LAN_SYNC: "set_lan_sync",
+};
+
+export interface LanSyncConfig {
+  pairingKey: string;
+  deviceName: string;
+}
+
+export interface LanPeer {
+  deviceId: string;
+  name: string;
+}
+
+/**
+ * 设置局域网内设备间的剪贴板共享，传入 null 关闭
+ */
+export const setLanSync = (config: LanSyncConfig | null) => {
+  return invoke(COMMAND.SET_LAN_SYNC, { config });
+};
+
+/**
+ * 获取局域网内发现的 EcoPaste 设备
+ */
+export const getLanPeers = () => {
+  return invoke<LanPeer[]>(COMMAND.GET_LAN_PEERS);
+};
@@ -1,12 +1,240 @@
 import { invoke } from "@tauri-apps/api/core";
 
 export const COMMAND = {
+  CLEAR_PASTE_QUEUE: "clear_paste_queue",
+  GET_PASTE_QUEUE: "get_paste_queue",
+  GET_PASTE_TARGET: "plugin:eco-paste|get_paste_target",
+  GET_PREVIOUS_APP: "plugin:eco-paste|get_previous_app",
   PASTE: "plugin:eco-paste|paste",
+  PASTE_AS_MARKDOWN_LINK: "paste_as_markdown_link",
+  PASTE_AND_GO: "paste_and_go",
+  PASTE_EMOJI: "paste_emoji",
+  PASTE_AS_KEYSTROKES: "plugin:eco-paste|paste_as_keystrokes",
+  PASTE_CONVERTED: "paste_converted",
+  PASTE_HISTORY_SLOT: "paste_history_slot",
+  PASTE_ITEM: "paste_item",
+  PASTE_PLAIN: "paste_plain",
+  PASTE_RESULT: "paste_result",
+  PASTE_TIMESTAMP: "paste_timestamp",
+  PASTE_WITH_CITATION: "paste_with_citation",
+  POP_PASTE_QUEUE: "pop_paste_queue",
+  PUSH_PASTE_QUEUE: "push_paste_queue",
+  RUN_PASTE_SELF_TEST: "plugin:eco-paste|run_paste_self_test",
+  SET_PASTE_KEYSTROKE: "plugin:eco-paste|set_paste_keystroke",
+  SET_PASTE_RULES: "plugin:eco-paste|set_paste_rules",
+  SET_PASTE_TIMING: "plugin:eco-paste|set_paste_timing",
 };
 
 /**
- * 粘贴剪贴板内容
+ * 粘贴队列变化时的事件，携带队列中剩余的条目 id
  */
-export const paste = () => {
-  return invoke(COMMAND.PASTE);
+export const PASTE_QUEUE_CHANGED_EVENT = "paste-queue://changed";
+
+export type CitationStyle = "source" | "markdown";
+
+export type LinkFormat = "markdown" | "html";
+
+export type TimestampFormat = "epoch" | "epochMillis" | "iso" | "local";
+
+/**
+ * 粘贴时模拟的按键，自定义组合键形如 "ctrl+shift+v"
+ */
+export type PasteKeystroke =
+  | { kind: "ctrlV" }
+  | { kind: "shiftInsert" }
+  | { kind: "custom"; chord: string };
+
+/**
+ * 按应用配置的粘贴规则，app 为进程名，typing 时逐字输入纯文本代替粘贴
+ */
+export interface PasteRule {
+  app: string;
+  rich?: boolean;
+  go?: boolean;
+  keystroke?: PasteKeystroke;
+  delay?: number;
+  typing?: boolean;
+}
+
+/**
+ * 粘贴前的等待时间（毫秒）和焦点没有切换过去时的重试次数
+ */
+export interface PasteTiming {
+  delay: number;
+  retries: number;
+}
+
+export interface PasteTarget {
+  window: { processName: string; className: string | null } | null;
+  rich: boolean;
+  typing: boolean;
+}
+
+/**
+ * 上一个应用，icon 为 PNG 格式的 data URL
+ */
+export interface PreviousApp {
+  processName: string;
+  icon: string | null;
+}
+
+export interface SelfTestReport {
+  passed: boolean;
+  expected: string;
+  actual: string;
+  focusRestored: boolean;
+}
+
+/**
+ * 粘贴剪贴板内容，不指定按键时按目标应用的规则或全局设置
+ */
+export const paste = (keystroke?: PasteKeystroke) => {
+  return invoke(COMMAND.PASTE, { keystroke });
+};
+
+/**
+ * 附带来源引用粘贴
+ */
+export const pasteWithCitation = (id: string, style: CitationStyle) => {
+  return invoke(COMMAND.PASTE_WITH_CITATION, { id, style });
+};
+
+/**
+ * 以 markdown 链接（或 html 链接）的形式粘贴网址
+ */
+export const pasteAsMarkdownLink = (id: string, format?: LinkFormat) => {
+  return invoke(COMMAND.PASTE_AS_MARKDOWN_LINK, { format, id });
+};
+
+/**
+ * 将时间戳或 ISO 日期转换为指定的格式后粘贴
+ */
+export const pasteTimestamp = (id: string, format: TimestampFormat) => {
+  return invoke(COMMAND.PASTE_TIMESTAMP, { format, id });
+};
+
+/**
+ * 根据粘贴目标自动选择富文本或纯文本后粘贴
+ */
+export const pasteItem = (id: string) => {
+  return invoke(COMMAND.PASTE_ITEM, { id });
+};
+
+/**
+ * 不打开窗口，直接粘贴第 N 条历史记录（1 为最新的一条）
+ */
+export const pasteHistorySlot = (slot: number) => {
+  return invoke(COMMAND.PASTE_HISTORY_SLOT, { slot });
+};
+
+/**
+ * 将条目依次加入粘贴队列，返回队列中的条目
+ */
+export const pushPasteQueue = (ids: string[]) => {
+  return invoke<string[]>(COMMAND.PUSH_PASTE_QUEUE, { ids });
+};
+
+/**
+ * 粘贴队列中的下一个条目，返回它的 id，队列为空时返回 null
+ */
+export const popPasteQueue = () => {
+  return invoke<string | null>(COMMAND.POP_PASTE_QUEUE);
+};
+
+/**
+ * 清空粘贴队列
+ */
+export const clearPasteQueue = () => {
+  return invoke(COMMAND.CLEAR_PASTE_QUEUE);
+};
+
+/**
+ * 获取粘贴队列中的条目
+ */
+export const getPasteQueue = () => {
+  return invoke<string[]>(COMMAND.GET_PASTE_QUEUE);
+};
+
+/**
+ * 只写入纯文本（清除富文本等其它格式）后粘贴
+ */
+export const pastePlain = (text: string) => {
+  return invoke(COMMAND.PASTE_PLAIN, { text });
+};
+
+/**
+ * 逐字模拟键盘输入文本，用于禁止粘贴的应用（虚拟机、远程桌面、密码框等）
+ */
+export const pasteAsKeystrokes = (text: string, delayMs = 10) => {
+  return invoke(COMMAND.PASTE_AS_KEYSTROKES, { delayMs, text });
+};
+
+/**
+ * 获取粘贴目标
+ */
+export const getPasteTarget = () => {
+  return invoke<PasteTarget>(COMMAND.GET_PASTE_TARGET);
+};
+
+/**
+ * 获取上一个应用的进程名和图标
+ */
+export const getPreviousApp = () => {
+  return invoke<PreviousApp | null>(COMMAND.GET_PREVIOUS_APP);
+};
+
+/**
+ * 设置按应用配置的粘贴规则
+ */
+export const setPasteRules = (rules: PasteRule[]) => {
+  return invoke(COMMAND.SET_PASTE_RULES, { rules });
+};
+
+/**
+ * 设置全局的粘贴按键，不传时恢复默认的 Shift+Insert
+ */
+export const setPasteKeystroke = (keystroke?: PasteKeystroke) => {
+  return invoke(COMMAND.SET_PASTE_KEYSTROKE, { keystroke });
+};
+
+/**
+ * 设置粘贴前的等待时间和重试次数，不传时恢复默认
+ */
+export const setPasteTiming = (timing?: PasteTiming) => {
+  return invoke(COMMAND.SET_PASTE_TIMING, { timing });
+};
+
+/**
+ * 粘贴网址到浏览器地址栏并转到，没有浏览器获得焦点时直接打开
+ */
+export const pasteAndGo = (id: string) => {
+  return invoke(COMMAND.PASTE_AND_GO, { id });
+};
+
+/**
+ * 粘贴算式条目的计算结果
+ */
+export const pasteResult = (id: string) => {
+  return invoke(COMMAND.PASTE_RESULT, { id });
+};
+
+/**
+ * 粘贴带单位条目换算后的值，unit 形如 km、°F、EUR
+ */
+export const pasteConverted = (id: string, unit: string) => {
+  return invoke(COMMAND.PASTE_CONVERTED, { id, unit });
+};
+
+/**
+ * 粘贴表情，codepoint 形如 1F44D-1F3FD，也可以直接传入表情
+ */
+export const pasteEmoji = (codepoint: string) => {
+  return invoke(COMMAND.PASTE_EMOJI, { codepoint });
+};
+
+/**
+ * 粘贴自检：在临时窗口中完整地执行一次粘贴，结束后恢复剪贴板，仅开发版可用
+ */
+export const runPasteSelfTest = (text?: string) => {
+  return invoke<SelfTestReport>(COMMAND.RUN_PASTE_SELF_TEST, { text });
 };
@@ -0,0 +1,110 @@
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  LIST_PROCESSORS: "list_processors",
+  RUN_CAPTURE_PROCESSORS: "run_capture_processors",
+  SET_ENABLED_PROCESSORS: "set_enabled_processors",
+};
+
+export type ProcessorCapability =
+  | "metadata"
+  | "tags"
+  | "sensitive"
+  | "text"
+  | "objects";
+
+export interface PreviewField {
+  label: string;
+  value: string;
+}
+
+export interface ItemType {
+  type: string;
+  name: string;
+  preview: {
+    title: string;
+    subtitle?: string;
+    fields: PreviewField[];
+    swatches?: string;
+    link?: string;
+  };
+}
+
+export interface RegisteredItemType {
+  type: string;
+  name: string;
+  processor: string;
+}
+
+export interface ItemPreview {
+  type: string;
+  typeName: string;
+  title: string;
+  subtitle?: string;
+  fields: PreviewField[];
+  swatches: string[];
+  link?: string;
+}
+
+/**
+ * 条目类型及预览所在的自定义协议
+ */
+const ITEM_PROTOCOL = "ecopaste";
+
+export interface ProcessorInfo {
+  id: string;
+  name: string;
+  version: string;
+  description: string;
+  types: string[];
+  capabilities: ProcessorCapability[];
+  itemTypes: ItemType[];
+  enabled: boolean;
+  error?: string;
+}
+
+/**
+ * 获取插件目录中的采集处理器
+ */
+export const listProcessors = () => {
+  return invoke<ProcessorInfo[]>(COMMAND.LIST_PROCESSORS);
+};
+
+/**
+ * 设置启用的采集处理器，未启用的处理器不会运行
+ */
+export const setEnabledProcessors = (ids: string[]) => {
+  return invoke(COMMAND.SET_ENABLED_PROCESSORS, { ids });
+};
+
+/**
+ * 对新采集的条目运行启用的采集处理器，返回产生结果的处理器数量
+ */
+export const runCaptureProcessors = (id: string) => {
+  return invoke<number>(COMMAND.RUN_CAPTURE_PROCESSORS, { id });
+};
+
+const fetchItemProtocol = async <T>(path: string) => {
+  // convertFileSrc 会编码路径中的斜杠，只用它获取协议在当前平台的地址
+  const response = await fetch(convertFileSrc("", ITEM_PROTOCOL) + path);
+
+  if (!response.ok) {
+    throw new Error(`${response.status} ${response.statusText}`);
+  }
+
+  return response.json() as Promise<T>;
+};
+
+/**
+ * 获取启用的采集处理器注册的条目类型
+ */
+export const getItemTypes = () => {
+  return fetchItemProtocol<RegisteredItemType[]>("types");
+};
+
+/**
+ * 获取条目的预览，不是已注册类型的条目返回 null
+ */
+export const getItemPreview = (id: string) => {
+  return fetchItemProtocol<ItemPreview | null>(`preview/${id}`);
+};
@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  COPY_FILES_WITH_TAGS: "copy_files_with_tags",
+  GET_SHELL_INTEGRATION: "get_shell_integration",
+  SET_SHELL_INTEGRATION: "set_shell_integration",
+};
+
+export interface ShellLabels {
+  copy: string;
+  copyWithTags: string;
+}
+
+export interface CopyFilesPrompt {
+  paths: string[];
+}
+
+/**
+ * 添加或移除资源管理器右键菜单中的“用 EcoPaste 复制”，仅支持 Windows
+ */
+export const setShellIntegration = (enabled: boolean, labels: ShellLabels) => {
+  return invoke(COMMAND.SET_SHELL_INTEGRATION, { enabled, labels });
+};
+
+/**
+ * 右键菜单是否已添加
+ */
+export const getShellIntegration = () => {
+  return invoke<boolean>(COMMAND.GET_SHELL_INTEGRATION);
+};
+
+/**
+ * 将右键菜单选中的文件连同标签写入剪贴板，embed 为 true 时先保存文件副本
+ */
+export const copyFilesWithTags = (
+  paths: string[],
+  tags: string[],
+  embed = false,
+) => {
+  return invoke(COMMAND.COPY_FILES_WITH_TAGS, { embed, paths, tags });
+};
@@ -0,0 +1,109 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  GET_SNIPPET_LIBRARIES: "get_snippet_libraries",
+  GET_SNIPPET_SYNC_STATUS: "get_snippet_sync_status",
+  REFRESH_SNIPPET_LIBRARIES: "refresh_snippet_libraries",
+  SEARCH_SNIPPETS: "search_snippets",
+  SET_SNIPPET_LIBRARIES: "set_snippet_libraries",
+  SET_SNIPPET_SYNC: "set_snippet_sync",
+  SYNC_SNIPPETS: "sync_snippets",
+};
+
+export type LibrarySource =
+  | { kind: "folder"; path: string }
+  | { kind: "git"; url: string; branch?: string };
+
+export interface SnippetLibrary {
+  id: string;
+  name: string;
+  source: LibrarySource;
+  refreshMinutes?: number;
+}
+
+export interface SharedSnippet {
+  id: string;
+  libraryId: string;
+  libraryName: string;
+  title: string;
+  content: string;
+  tags: string[];
+}
+
+export interface LibraryStatus {
+  id: string;
+  name: string;
+  snippetCount: number;
+  refreshedAt: number | null;
+  error: string | null;
+}
+
+export interface SyncConfig {
+  remote: string;
+  branch?: string;
+  intervalMinutes?: number;
+}
+
+export interface SyncReport {
+  committed: boolean;
+  imported: number;
+  removed: number;
+  conflicts: string[];
+  pushed: boolean;
+}
+
+export interface SyncStatus {
+  enabled: boolean;
+  syncedAt: number | null;
+  error: string | null;
+  lastReport: SyncReport | null;
+}
+
+/**
+ * 设置共享的只读片段库
+ */
+export const setSnippetLibraries = (libraries: SnippetLibrary[]) => {
+  return invoke(COMMAND.SET_SNIPPET_LIBRARIES, { libraries });
+};
+
+/**
+ * 立即刷新所有片段库
+ */
+export const refreshSnippetLibraries = () => {
+  return invoke<LibraryStatus[]>(COMMAND.REFRESH_SNIPPET_LIBRARIES);
+};
+
+/**
+ * 获取片段库的状态
+ */
+export const getSnippetLibraries = () => {
+  return invoke<LibraryStatus[]>(COMMAND.GET_SNIPPET_LIBRARIES);
+};
+
+/**
+ * 在共享片段库中搜索
+ */
+export const searchSnippets = (query: string) => {
+  return invoke<SharedSnippet[]>(COMMAND.SEARCH_SNIPPETS, { query });
+};
+
+/**
+ * 设置置顶片段的 git 同步，传入 null 关闭同步
+ */
+export const setSnippetSync = (config: SyncConfig | null) => {
+  return invoke(COMMAND.SET_SNIPPET_SYNC, { config });
+};
+
+/**
+ * 立即同步置顶片段
+ */
+export const syncSnippets = () => {
+  return invoke<SyncReport>(COMMAND.SYNC_SNIPPETS);
+};
+
+/**
+ * 获取置顶片段的同步状态
+ */
+export const getSnippetSyncStatus = () => {
+  return invoke<SyncStatus>(COMMAND.GET_SNIPPET_SYNC_STATUS);
+};
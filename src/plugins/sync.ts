@@ -0,0 +1,50 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  GET_WEBDAV_SYNC_STATUS: "get_webdav_sync_status",
+  SET_WEBDAV_SYNC: "set_webdav_sync",
+  SYNC_NOW: "sync_now",
+};
+
+export interface WebDavConfig {
+  url: string;
+  username: string;
+  password: string;
+  intervalMinutes?: number;
+}
+
+export interface WebDavSyncReport {
+  pushed: number;
+  pulled: number;
+  applied: number;
+  uploadedImages: number;
+  downloadedImages: number;
+}
+
+export interface WebDavSyncStatus {
+  enabled: boolean;
+  syncedAt: number | null;
+  error: string | null;
+  lastReport: WebDavSyncReport | null;
+}
+
+/**
+ * 设置历史记录的 WebDAV 同步，传入 null 关闭同步
+ */
+export const setWebDavSync = (config: WebDavConfig | null) => {
+  return invoke(COMMAND.SET_WEBDAV_SYNC, { config });
+};
+
+/**
+ * 立即通过 WebDAV 同步历史记录
+ */
+export const syncNow = () => {
+  return invoke<WebDavSyncReport>(COMMAND.SYNC_NOW);
+};
+
+/**
+ * 获取历史记录的 WebDAV 同步状态
+ */
+export const getWebDavSyncStatus = () => {
+  return invoke<WebDavSyncStatus>(COMMAND.GET_WEBDAV_SYNC_STATUS);
+};
@@ -0,0 +1,57 @@
+import { invoke } from "@tauri-apps/api/core";
+
+const COMMAND = {
+  DISABLE_VAULT: "disable_vault",
+  ENABLE_VAULT: "enable_vault",
+  GET_VAULT_STATUS: "get_vault_status",
+  LOCK_VAULT: "lock_vault",
+  READ_VAULT_IMAGE: "read_vault_image",
+  UNLOCK_VAULT: "unlock_vault",
+};
+
+export interface VaultStatus {
+  enabled: boolean;
+  unlocked: boolean;
+}
+
+/**
+ * 开启加密保险库，已有的历史记录和图片会被加密
+ */
+export const enableVault = (password: string) => {
+  return invoke(COMMAND.ENABLE_VAULT, { password });
+};
+
+/**
+ * 关闭加密保险库，所有内容解密为明文存储
+ */
+export const disableVault = (password: string) => {
+  return invoke(COMMAND.DISABLE_VAULT, { password });
+};
+
+/**
+ * 用密码解锁保险库
+ */
+export const unlockVault = (password: string) => {
+  return invoke(COMMAND.UNLOCK_VAULT, { password });
+};
+
+/**
+ * 锁定保险库
+ */
+export const lockVault = () => {
+  return invoke(COMMAND.LOCK_VAULT);
+};
+
+/**
+ * 获取保险库的状态
+ */
+export const getVaultStatus = () => {
+  return invoke<VaultStatus>(COMMAND.GET_VAULT_STATUS);
+};
+
+/**
+ * 读取加密存储的图片，返回解密后的数据
+ */
+export const readVaultImage = (path: string) => {
+  return invoke<ArrayBuffer>(COMMAND.READ_VAULT_IMAGE, { path });
+};
@@ -5,15 +5,35 @@ import { PhysicalPosition, PhysicalSize } from "@tauri-apps/api/window";
 import { LISTEN_KEY, WINDOW_LABEL } from "@/constants";
 import { clipboardStore } from "@/stores/clipboard";
 import type { WindowLabel } from "@/types/plugin";
+import type { WindowCorners, WindowEffect } from "@/types/store";
 import { isLinux } from "@/utils/is";
 import { getCursorMonitor } from "@/utils/monitor";
 
 const COMMAND = {
+  GET_WINDOW_EFFECTS: "plugin:eco-window|get_window_effects",
   HIDE_WINDOW: "plugin:eco-window|hide_window",
+  SET_UNREAD_BADGE: "plugin:eco-window|set_unread_badge",
+  SET_WINDOW_EFFECT: "plugin:eco-window|set_window_effect",
   SHOW_TASKBAR_ICON: "plugin:eco-window|show_taskbar_icon",
   SHOW_WINDOW: "plugin:eco-window|show_window",
 };
 
+export interface WindowEffectSettings {
+  effect: WindowEffect;
+  tint?: string;
+  corners?: WindowCorners;
+}
+
+export interface WindowEffectSupport {
+  platform: "windows" | "macos" | "linux";
+  // Windows 的构建号或 macOS 的版本号
+  version: string;
+  // 除 auto 和 none 之外当前系统支持的效果
+  effects: WindowEffect[];
+  // 圆角设置是否生效
+  corners: boolean;
+}
+
 /**
  * 显示窗口
  */
@@ -100,3 +120,24 @@ export const toggleWindowVisible = async () => {
 export const showTaskbarIcon = (visible = true) => {
   invoke(COMMAND.SHOW_TASKBAR_ICON, { visible });
 };
+
+/**
+ * 设置是否在任务栏或程序坞图标上显示打开窗口后新增的内容数量
+ */
+export const setUnreadBadge = (enabled: boolean) => {
+  return invoke(COMMAND.SET_UNREAD_BADGE, { enabled });
+};
+
+/**
+ * 获取当前系统支持的窗口效果
+ */
+export const getWindowEffects = () => {
+  return invoke<WindowEffectSupport>(COMMAND.GET_WINDOW_EFFECTS);
+};
+
+/**
+ * 设置当前窗口的背景效果、色调和圆角，返回实际使用的效果
+ */
+export const setWindowEffect = (settings: WindowEffectSettings) => {
+  return invoke<WindowEffect>(COMMAND.SET_WINDOW_EFFECT, { settings });
+};
@@ -8,17 +8,65 @@ export const clipboardStore = proxy<ClipboardStore>({
 
   content: {
     autoFavorite: false,
+    autoOcr: false,
+    captureDenylist: [],
+    conversionTargets: [],
+    currencyRates: false,
+    networkEnrichment: {
+      asnDatabase: "",
+      lookups: false,
+    },
     autoPaste: "double",
     autoSort: false,
     copyPlain: false,
     deleteConfirm: true,
+    enabledProcessors: [],
+    excludedApps: [
+      "1password",
+      "bitwarden",
+      "dashlane",
+      "enpass",
+      "keepass",
+      "keepassxc",
+      "lastpass",
+    ],
+    excludedVms: [],
+    focusPause: "off",
+    maxTextSize: 1,
     operationButtons: ["copy", "star", "delete"],
     pastePlain: false,
+    pasteRules: [
+      { app: "mintty", keystroke: { kind: "shiftInsert" } },
+      { app: "virtualboxvm", typing: true },
+    ],
+    pasteTiming: {
+      delay: 100,
+      retries: 3,
+    },
+    revealGracePeriod: 60,
+    sensitiveExpiry: 0,
     showOriginalContent: false,
   },
 
   history: {
+    archiveAfterMonths: 0,
+    coldStorage: {
+      afterDays: 0,
+      location: "",
+      maxAccessCount: 0,
+    },
     duration: 0,
+    groupDuration: {
+      files: 0,
+      image: 0,
+      text: 0,
+    },
+    hashImages: false,
+    imageStorage: {
+      compression: "default",
+      format: "png",
+      quality: 85,
+    },
     maxCount: 0,
     unit: 1,
   },
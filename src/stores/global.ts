@@ -7,22 +7,27 @@ export const globalStore = proxy<GlobalStore>({
     showMenubarIcon: true,
     showTaskbarIcon: false,
     silentStart: false,
+    unreadBadge: false,
   },
 
   appearance: {
     isDark: false,
     theme: "auto",
+    windowCorners: "default",
+    windowEffect: "auto",
   },
 
   env: {},
 
   shortcut: {
     clipboard: "Alt+C",
+    pasteLast: "",
     pastePlain: "",
+    pasteQueue: "",
     preference: "Alt+X",
     quickPaste: {
       enable: false,
-      value: "Command+Shift",
+      value: "Control+Alt",
     },
   },
 
@@ -4,7 +4,13 @@ import type {
 } from "tauri-plugin-clipboard-x-api";
 import type { LiteralUnion } from "type-fest";
 
-export type DatabaseSchemaHistorySubtype = "url" | "email" | "color" | "path";
+export type DatabaseSchemaHistorySubtype =
+  | "url"
+  | "email"
+  | "color"
+  | "path"
+  | "screenshot"
+  | "emoji";
 
 export type DatabaseSchemaHistory<
   T extends ClipboardContentType = ClipboardContentType,
@@ -16,10 +22,13 @@ export type DatabaseSchemaHistory<
   createTime: string;
   note?: string;
   subtype?: DatabaseSchemaHistorySubtype;
+  source?: "shared";
+  libraryName?: string;
+  redacted?: boolean;
 };
 
 export type DatabaseSchemaGroupId = LiteralUnion<
-  "all" | "text" | "image" | "files" | "favorite",
+  "all" | "text" | "image" | "files" | "screenshot" | "favorite",
   string
 >;
 
@@ -28,8 +37,3 @@ export interface DatabaseSchemaGroup {
   name: string;
   createTime?: string;
 }
-
-export interface DatabaseSchema {
-  history: DatabaseSchemaHistory;
-  group: DatabaseSchemaGroup;
-}
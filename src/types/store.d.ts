@@ -1,7 +1,22 @@
 import type { Platform } from "@tauri-apps/plugin-os";
+import type {
+  ImageStoragePolicy,
+  NetworkEnrichment,
+} from "@/plugins/history";
+import type { PasteRule } from "@/plugins/paste";
 
 export type Theme = "auto" | "light" | "dark";
 
+export type WindowEffect =
+  | "auto"
+  | "none"
+  | "mica"
+  | "acrylic"
+  | "blur"
+  | "vibrancy";
+
+export type WindowCorners = "default" | "round" | "roundSmall" | "square";
+
 export type Language = (typeof LANGUAGE)[keyof typeof LANGUAGE];
 
 export interface Store {
@@ -16,6 +31,10 @@ export interface GlobalStore {
     silentStart: boolean;
     showMenubarIcon: boolean;
     showTaskbarIcon: boolean;
+    // 资源管理器右键菜单，仅 Windows
+    shellIntegration?: boolean;
+    // 在任务栏或程序坞图标上显示未查看的新内容数量
+    unreadBadge: boolean;
   };
 
   // 外观设置
@@ -23,6 +42,12 @@ export interface GlobalStore {
     theme: Theme;
     isDark: boolean;
     language?: Language;
+    // 窗口的背景效果
+    windowEffect: WindowEffect;
+    // 窗口背景效果的色调，#RRGGBB 或 #RRGGBBAA
+    windowTint?: string;
+    // 窗口的圆角
+    windowCorners: WindowCorners;
   };
 
   update: {
@@ -39,6 +64,10 @@ export interface GlobalStore {
       value: string;
     };
     pastePlain: string;
+    // 粘贴最新的条目，为空时不注册
+    pasteLast: string;
+    // 粘贴队列中的下一个条目，为空时不注册
+    pasteQueue: string;
   };
 
   // 只在当前系统环境使用
@@ -59,6 +88,8 @@ export type OperationButton =
   | "star"
   | "delete";
 
+export type FocusPause = "off" | "notifications" | "capture";
+
 export interface ClipboardStore {
   // 窗口设置
   window: {
@@ -90,11 +121,56 @@ export interface ClipboardStore {
     deleteConfirm: boolean;
     autoSort: boolean;
     showOriginalContent: boolean;
+    // 不记录复制内容的应用进程名，例如密码管理器
+    excludedApps: string[];
+    // 不记录复制内容的虚拟机
+    excludedVms: string[];
+    // 不记录的内容规则，正则表达式
+    captureDenylist: string[];
+    // 系统勿扰模式开启时暂停的内容
+    focusPause: FocusPause;
+    // 敏感内容在复制多少分钟后自动删除，0 为不删除
+    sensitiveExpiry: number;
+    // 身份验证后敏感条目保持解锁的秒数，0 为每次都需要验证
+    revealGracePeriod: number;
+    // 自动识别新复制图片中的文字，使图片可以被搜索到
+    autoOcr: boolean;
+    // 超过该大小（MB）的文本只保存预览，完整内容写入单独的文件
+    maxTextSize: number;
+    // 启用的采集处理器，对应插件目录中的文件夹名
+    enabledProcessors: string[];
+    // 粘贴前等待目标窗口获得焦点的毫秒数，以及焦点没有切换过去时的重试次数
+    pasteTiming: {
+      delay: number;
+      retries: number;
+    };
+    // 按应用配置的粘贴方式，例如 mintty 使用 Shift+Insert、虚拟机逐字输入
+    pasteRules: PasteRule[];
+    // 复制带单位的值时换算到的单位，例如 km、°F、EUR，为空时使用默认的单位
+    conversionTargets: string[];
+    // 是否从网络获取实时汇率，默认关闭
+    currencyRates: boolean;
+    // 复制 IP 和域名时附加的信息，网络查询默认关闭
+    networkEnrichment: NetworkEnrichment;
   };
 
   // 历史记录
   history: {
+    // 超过多少个月的条目移入归档，0 表示不归档
+    archiveAfterMonths: number;
+    // 图片冷存储，超过天数且使用次数不多的图片会被重新压缩，设置了目录时移到该目录，0 表示关闭
+    coldStorage: {
+      afterDays: number;
+      location: string;
+      maxAccessCount: number;
+    };
     duration: number;
+    // 保存图片时计算感知哈希，用于查找相似的图片
+    hashImages: boolean;
+    // 新图片保存的格式，WebP 为无损压缩，JPEG 按质量有损压缩
+    imageStorage: ImageStoragePolicy;
+    // 各分组单独的保留天数，0 表示跟随保留时长
+    groupDuration: Record<"text" | "image" | "files", number>;
     unit: number;
     maxCount: number;
   };
@@ -44,6 +44,17 @@ export const getSaveImagePath = () => {
   return join(getSaveDataPath(), "images");
 };
 
+/**
+ * 获取图片缩略图的路径，只有较大的图片才有缩略图
+ * @param path 图片路径
+ */
+export const getThumbnailPath = (path: string) => {
+  const parts = path.split(sep());
+  const name = parts.pop()?.replace(/\.[^.]+$/, "");
+
+  return join(...parts, "thumbnails", `${name}.webp`);
+};
+
 /**
  * 存储数据的目录名
  */